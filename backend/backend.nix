@@ -29,7 +29,10 @@
         eigenix-backend = rustPlatform.buildRustPackage {
           pname = "eigenix-backend";
           version = "0.1.0";
-          src = lib.cleanSource ./.;
+          # The backend depends on the shared types crate, so build from the repository root
+          src = lib.cleanSource ../.;
+          cargoRoot = "backend";
+          buildAndTestSubdir = "backend";
 
           cargoLock.lockFile = ./Cargo.lock;
 
@@ -66,7 +69,10 @@
         eigenix-backend-tests = rustPlatform.buildRustPackage {
           pname = "eigenix-backend";
           version = "0.1.0";
-          src = lib.cleanSource ./.;
+          # The backend depends on the shared types crate, so build from the repository root
+          src = lib.cleanSource ../.;
+          cargoRoot = "backend";
+          buildAndTestSubdir = "backend";
 
           cargoLock.lockFile = ./Cargo.lock;
 
@@ -0,0 +1,183 @@
+//! Host clock skew against Kraken's server time
+//!
+//! Kraken nonces and our own timestamps both come from the host clock, so a
+//! drifting clock shows up as intermittent auth failures. The skew is
+//! measured once at startup and reported in `/health`.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use utoipa::ToSchema;
+
+use super::{Alert, AlertSeverity};
+use crate::services::KrakenClient;
+
+/// A clock to compare the local one against
+pub trait ReferenceClock {
+    fn now(&self) -> impl Future<Output = Result<DateTime<Utc>>> + Send;
+}
+
+impl ReferenceClock for KrakenClient {
+    fn now(&self) -> impl Future<Output = Result<DateTime<Utc>>> + Send {
+        self.get_server_time()
+    }
+}
+
+/// Result of one skew measurement
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, ToSchema)]
+pub struct ClockSkew {
+    /// Local time minus reference time; positive when the host clock is ahead
+    pub skew_secs: f64,
+    pub measured_at: DateTime<Utc>,
+}
+
+/// Measure the local clock against a reference
+///
+/// The local reading is the midpoint of the request, which cancels out the
+/// round trip as long as it is roughly symmetric.
+pub async fn measure_skew<C: ReferenceClock>(
+    reference: &C,
+    local_now: impl Fn() -> DateTime<Utc>,
+) -> Result<ClockSkew> {
+    let before = local_now();
+    let reference_time = reference.now().await?;
+    let after = local_now();
+
+    let local_time = before + (after - before) / 2;
+    let skew = local_time - reference_time;
+
+    Ok(ClockSkew {
+        skew_secs: skew.num_milliseconds() as f64 / 1000.0,
+        measured_at: after,
+    })
+}
+
+/// Keeps the latest skew measurement and raises an alert when it is too large
+#[derive(Debug, Clone)]
+pub struct ClockSkewMonitor {
+    threshold_secs: f64,
+    last: Arc<RwLock<Option<ClockSkew>>>,
+}
+
+impl ClockSkewMonitor {
+    pub fn new(threshold_secs: u64) -> Self {
+        Self {
+            threshold_secs: threshold_secs as f64,
+            last: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Latest measurement, if one succeeded
+    pub fn last(&self) -> Option<ClockSkew> {
+        *self.last.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record a measurement, returning an alert if it exceeds the threshold
+    pub fn observe(&self, skew: ClockSkew) -> Option<Alert> {
+        *self.last.write().unwrap_or_else(|e| e.into_inner()) = Some(skew);
+
+        if skew.skew_secs.abs() <= self.threshold_secs {
+            return None;
+        }
+
+        Some(Alert {
+            kind: "clock_skew".to_string(),
+            severity: AlertSeverity::Warning,
+            message: format!(
+                "Host clock is {:.1}s {} Kraken's (threshold {}s); Kraken signing may fail",
+                skew.skew_secs.abs(),
+                if skew.skew_secs > 0.0 {
+                    "ahead of"
+                } else {
+                    "behind"
+                },
+                self.threshold_secs
+            ),
+            timestamp: skew.measured_at,
+        })
+    }
+
+    /// Measure against `reference` and record the result
+    pub async fn check<C: ReferenceClock>(&self, reference: &C) -> Result<Option<Alert>> {
+        let skew = measure_skew(reference, Utc::now).await?;
+        Ok(self.observe(skew))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    /// Reference clock that is a fixed offset from the local one
+    struct OffsetClock(Duration);
+
+    impl ReferenceClock for OffsetClock {
+        fn now(&self) -> impl Future<Output = Result<DateTime<Utc>>> + Send {
+            let offset = self.0;
+            async move { Ok(Utc::now() + offset) }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_skew_raises_alert() {
+        let monitor = ClockSkewMonitor::new(5);
+        assert_eq!(monitor.last(), None);
+
+        // Reference two minutes behind: the host is two minutes ahead
+        let alert = monitor
+            .check(&OffsetClock(Duration::seconds(-120)))
+            .await
+            .unwrap()
+            .expect("skew above the threshold should alert");
+        assert_eq!(alert.kind, "clock_skew");
+        assert_eq!(alert.severity, AlertSeverity::Warning);
+        assert!(alert.message.contains("ahead of"), "{}", alert.message);
+
+        let skew = monitor.last().unwrap();
+        assert!((skew.skew_secs - 120.0).abs() < 1.0, "{:?}", skew);
+    }
+
+    #[tokio::test]
+    async fn test_small_skew_is_recorded_without_alert() {
+        let monitor = ClockSkewMonitor::new(5);
+
+        let alert = monitor
+            .check(&OffsetClock(Duration::seconds(2)))
+            .await
+            .unwrap();
+        assert!(alert.is_none());
+
+        let skew = monitor.last().unwrap();
+        assert!((skew.skew_secs + 2.0).abs() < 1.0, "{:?}", skew);
+    }
+
+    #[tokio::test]
+    async fn test_measure_skew_uses_request_midpoint() {
+        let reference = Utc::now();
+        let ticks = std::sync::Mutex::new(vec![
+            reference + Duration::seconds(14),
+            reference + Duration::seconds(10),
+        ]);
+
+        // Local readings 10s and 14s after the reference: midpoint is 12s ahead
+        let skew = measure_skew(&FixedClock(reference), || {
+            ticks.lock().unwrap().pop().unwrap()
+        })
+        .await
+        .unwrap();
+        assert_eq!(skew.skew_secs, 12.0);
+        assert_eq!(skew.measured_at, reference + Duration::seconds(14));
+    }
+
+    struct FixedClock(DateTime<Utc>);
+
+    impl ReferenceClock for FixedClock {
+        fn now(&self) -> impl Future<Output = Result<DateTime<Utc>>> + Send {
+            let time = self.0;
+            async move { Ok(time) }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Alerting on spikes in the ASB's failed swap counter
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::VecDeque;
+
+use super::{Alert, AlertSeverity};
+
+/// Watches the cumulative `failed_swaps` counter reported by the ASB
+///
+/// Each sample is compared with the previous one; when the increases seen
+/// within `window` add up to more than `threshold`, one alert is raised and
+/// no further alert fires until a full window has passed. A counter lower
+/// than the previous sample means the ASB restarted, and its value is taken
+/// as the number of failures since then.
+#[derive(Debug)]
+pub struct FailedSwapMonitor {
+    threshold: u64,
+    window: Duration,
+    last_count: Option<u64>,
+    /// Increases inside the current window, oldest first
+    increases: VecDeque<(DateTime<Utc>, u64)>,
+    last_alert: Option<DateTime<Utc>>,
+}
+
+impl FailedSwapMonitor {
+    pub fn new(threshold: u64, window: Duration) -> Self {
+        Self {
+            threshold,
+            window,
+            last_count: None,
+            increases: VecDeque::new(),
+            last_alert: None,
+        }
+    }
+
+    /// Record a sample of the counter, returning an alert if the threshold was crossed
+    pub fn observe(&mut self, now: DateTime<Utc>, failed_swaps: u64) -> Option<Alert> {
+        let increase = match self.last_count.replace(failed_swaps) {
+            // The first sample only sets the baseline
+            None => 0,
+            Some(previous) if failed_swaps < previous => failed_swaps,
+            Some(previous) => failed_swaps - previous,
+        };
+        if increase > 0 {
+            self.increases.push_back((now, increase));
+        }
+
+        while let Some((at, _)) = self.increases.front() {
+            if now - *at > self.window {
+                self.increases.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let recent: u64 = self.increases.iter().map(|(_, n)| n).sum();
+        if recent <= self.threshold {
+            return None;
+        }
+        if let Some(last_alert) = self.last_alert {
+            if now - last_alert < self.window {
+                return None;
+            }
+        }
+
+        self.last_alert = Some(now);
+        Some(Alert {
+            kind: "asb_failed_swaps".to_string(),
+            severity: AlertSeverity::Critical,
+            message: format!(
+                "{} swaps failed in the last {} minutes (threshold {})",
+                recent,
+                self.window.num_minutes(),
+                self.threshold
+            ),
+            timestamp: now,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn test_spike_fires_once_per_window() {
+        let mut monitor = FailedSwapMonitor::new(3, Duration::minutes(60));
+
+        // A large historical count on the first sample is only a baseline
+        assert!(monitor.observe(at(0), 100).is_none());
+        assert!(monitor.observe(at(1), 102).is_none());
+
+        // Two more failures bring the window to 4, above the threshold
+        let alert = monitor.observe(at(2), 104).expect("spike should alert");
+        assert_eq!(alert.kind, "asb_failed_swaps");
+        assert!(alert.message.starts_with("4 swaps failed"));
+
+        // Still failing within the same window: no repeat alerts
+        let repeats = (3..60)
+            .filter_map(|minute| monitor.observe(at(minute), 104 + minute as u64))
+            .count();
+        assert_eq!(repeats, 0);
+
+        // Once a full window has passed, a continuing spike alerts again
+        assert!(monitor.observe(at(62), 200).is_some());
+    }
+
+    #[test]
+    fn test_old_failures_leave_the_window() {
+        let mut monitor = FailedSwapMonitor::new(3, Duration::minutes(60));
+        monitor.observe(at(0), 0);
+        monitor.observe(at(1), 2);
+
+        // Two more, but the first two are over an hour old by now
+        assert!(monitor.observe(at(90), 4).is_none());
+    }
+
+    #[test]
+    fn test_counter_reset_on_asb_restart() {
+        let mut monitor = FailedSwapMonitor::new(3, Duration::minutes(60));
+        monitor.observe(at(0), 50);
+
+        // Restart resets the counter: a drop is not a negative increase
+        assert!(monitor.observe(at(1), 0).is_none());
+        assert!(monitor.observe(at(2), 1).is_none());
+
+        // Failures counted from the new baseline still add up
+        assert!(monitor.observe(at(3), 4).is_some());
+    }
+}
@@ -0,0 +1,262 @@
+//! Operator alerts
+//!
+//! Monitors raise an [`Alert`] when something needs attention; the
+//! [`AlertNotifier`] logs it and delivers it through the configured
+//! notification channels, see [`crate::notifications`]. The original
+//! `alerts.webhook_url` is kept as a webhook channel of its own.
+//!
+//! With a signing secret configured, each webhook request carries
+//! `X-Eigenix-Signature: sha256=<hex>`, the HMAC-SHA256 of the raw request
+//! body keyed with the secret. Receivers recompute it over the body bytes
+//! exactly as received and compare in constant time.
+//!
+//! Besides the built-in monitors, operators can define threshold rules on
+//! collected metrics; see [`rules`].
+
+pub mod clock_skew;
+pub mod failed_swaps;
+pub mod rules;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::notifications::{
+    deliver_with_retry, Channel, DeliveryReport, RetryPolicy, WebhookChannel, DEFAULT_RETRY,
+};
+
+pub use clock_skew::ClockSkewMonitor;
+pub use failed_swaps::FailedSwapMonitor;
+pub use rules::{AlertRule, AlertState, RuleEngine, StoredAlert};
+
+/// How urgently an alert needs attention
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Warning,
+    Critical,
+}
+
+/// A single alert raised by a monitor
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Alert {
+    /// Stable identifier of the condition, e.g. `asb_failed_swaps`
+    pub kind: String,
+    pub severity: AlertSeverity,
+    pub message: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Header carrying the webhook body signature
+pub const SIGNATURE_HEADER: &str = "X-Eigenix-Signature";
+
+/// Signature header value for a webhook body: `sha256=` and the hex HMAC-SHA256
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("sha256={}", digest)
+}
+
+/// Delivers alerts to the log and every configured notification channel
+#[derive(Clone)]
+pub struct AlertNotifier {
+    channels: Vec<Channel>,
+    retry: RetryPolicy,
+}
+
+impl AlertNotifier {
+    /// Notifier posting to a single webhook, if any
+    pub fn new(webhook_url: Option<String>) -> Self {
+        let channels = webhook_url
+            .map(|url| Channel::Webhook(WebhookChannel::new(url, reqwest::Client::new())))
+            .into_iter()
+            .collect();
+        Self {
+            channels,
+            retry: DEFAULT_RETRY,
+        }
+    }
+
+    /// Notifier for `[alerts] webhook_url` and every `[[notifications.channels]]` entry
+    pub fn from_config(config: &Config) -> Self {
+        let client = reqwest::Client::new();
+        let channels = config
+            .notifications
+            .channels
+            .iter()
+            .map(|channel| Channel::from_config(channel, &client));
+
+        Self::new(config.alerts.webhook_url.clone())
+            .with_channels(channels)
+            .with_signing_secret(config.alerts.webhook_signing_secret.clone())
+            .with_retry(config.notifications.retry_policy())
+    }
+
+    /// Also deliver through these channels
+    pub fn with_channels(mut self, channels: impl IntoIterator<Item = Channel>) -> Self {
+        self.channels.extend(channels);
+        self
+    }
+
+    /// Sign webhook bodies with this secret
+    pub fn with_signing_secret(mut self, secret: Option<String>) -> Self {
+        for channel in &mut self.channels {
+            if let Channel::Webhook(webhook) = channel {
+                if !webhook.is_signed() {
+                    *webhook = webhook.clone().with_signing_secret(secret.clone());
+                }
+            }
+        }
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn channels(&self) -> &[Channel] {
+        &self.channels
+    }
+
+    /// Deliver an alert through every channel at once, retrying failures
+    pub async fn deliver(&self, alert: &Alert) -> Vec<DeliveryReport> {
+        let deliveries: Vec<_> = self
+            .channels
+            .iter()
+            .cloned()
+            .map(|channel| {
+                let alert = alert.clone();
+                let retry = self.retry;
+                tokio::spawn(async move { deliver_with_retry(&channel, &alert, retry).await })
+            })
+            .collect();
+
+        let mut reports = Vec::with_capacity(deliveries.len());
+        for (delivery, channel) in deliveries.into_iter().zip(&self.channels) {
+            reports.push(delivery.await.unwrap_or_else(|e| DeliveryReport {
+                channel: channel.label(),
+                delivered: false,
+                attempts: 0,
+                error: Some(e.to_string()),
+            }));
+        }
+        reports
+    }
+
+    /// Log an alert and deliver it in the background
+    ///
+    /// Returns at once so the collector and trading loop never wait out a
+    /// channel's retries; delivery failures are logged rather than returned.
+    pub fn notify(&self, alert: &Alert) {
+        match alert.severity {
+            AlertSeverity::Warning => tracing::warn!("ALERT [{}] {}", alert.kind, alert.message),
+            AlertSeverity::Critical => {
+                tracing::error!("ALERT [{}] {}", alert.kind, alert.message)
+            }
+        }
+        if self.channels.is_empty() {
+            return;
+        }
+
+        let notifier = self.clone();
+        let alert = alert.clone();
+        tokio::spawn(async move {
+            for report in notifier.deliver(&alert).await {
+                if !report.delivered {
+                    tracing::error!(
+                        "Failed to deliver alert via {} after {} attempts: {}",
+                        report.channel,
+                        report.attempts,
+                        report.error.unwrap_or_default()
+                    );
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, http::HeaderMap, routing::post, Router};
+    use tokio::sync::mpsc;
+
+    /// Local webhook receiver; yields the headers and body of each request
+    async fn webhook_receiver() -> (String, mpsc::UnboundedReceiver<(HeaderMap, Bytes)>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| async move {
+                let _ = tx.send((headers, body));
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        (url, rx)
+    }
+
+    fn alert() -> Alert {
+        Alert {
+            kind: "asb_failed_swaps".to_string(),
+            severity: AlertSeverity::Warning,
+            message: "4 ASB swaps failed in the last hour".to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_sign_payload_matches_known_vector() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign_payload("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_signature_verifies_against_body() {
+        let (url, mut requests) = webhook_receiver().await;
+        let secret = "webhook-secret";
+
+        AlertNotifier::new(Some(url))
+            .with_signing_secret(Some(secret.to_string()))
+            .notify(&alert());
+
+        let (headers, body) = requests.recv().await.unwrap();
+        let signature = headers
+            .get(SIGNATURE_HEADER)
+            .expect("signature header present")
+            .to_str()
+            .unwrap();
+        assert_eq!(signature, sign_payload(secret, &body));
+        assert_ne!(signature, sign_payload("other-secret", &body));
+
+        let received: Alert = serde_json::from_slice(&body).unwrap();
+        assert_eq!(received.kind, "asb_failed_swaps");
+    }
+
+    #[tokio::test]
+    async fn test_webhook_unsigned_without_secret() {
+        let (url, mut requests) = webhook_receiver().await;
+
+        AlertNotifier::new(Some(url)).notify(&alert());
+
+        let (headers, _) = requests.recv().await.unwrap();
+        assert!(headers.get(SIGNATURE_HEADER).is_none());
+    }
+}
@@ -0,0 +1,566 @@
+//! Threshold rules evaluated against collected metrics
+//!
+//! Rules come from `[[alerts.rules]]` in the config, e.g.
+//!
+//! ```toml
+//! [[alerts.rules]]
+//! name = "asb_down"
+//! metric = "asb_up"
+//! condition = "below"
+//! threshold = 1
+//! for_secs = 300
+//! severity = "critical"
+//! ```
+//!
+//! A rule fires once its condition has held on every sample for `for_secs`
+//! and resolves on the first sample where it no longer holds. Each firing is
+//! stored in the `alerts` table, and updated in place when it resolves.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use super::AlertSeverity;
+use crate::metrics::{
+    AsbMetrics, AsbQuoteMetrics, BitcoinMetrics, DiskMetrics, ElectrsMetrics, MoneroMetrics,
+};
+
+/// Value a rule can watch
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleMetric {
+    /// Bitcoin wallet balance in BTC
+    BitcoinWalletBalance,
+    /// Headers bitcoind knows about but hasn't validated yet
+    BitcoinSyncLag,
+    BitcoinVerificationProgress,
+    /// Peers connected to bitcoind
+    BitcoinPeers,
+    /// Monero wallet balance in XMR
+    MoneroWalletBalance,
+    /// Blocks between monerod's height and its sync target
+    MoneroHeightLag,
+    /// Incoming plus outgoing monerod connections
+    MoneroConnections,
+    /// 1 when monerod considers itself synchronized and is online, 0 otherwise
+    MoneroSynchronized,
+    /// 1 when the ASB answered its probe, 0 otherwise
+    AsbUp,
+    AsbBalance,
+    AsbPendingSwaps,
+    /// 1 when the reachable ASB quotes a non-zero maximum, 0 otherwise
+    AsbQuoting,
+    /// Largest swap the ASB currently accepts, in BTC
+    AsbMaxQuantity,
+    /// 1 when Electrs answered its probe, 0 otherwise
+    ElectrsUp,
+    /// Free share of the fullest monitored filesystem, in percent
+    DiskFreePercent,
+    /// Space left on the fullest monitored filesystem, in GiB
+    DiskFreeGib,
+}
+
+impl RuleMetric {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::BitcoinWalletBalance => "bitcoin_wallet_balance",
+            Self::BitcoinSyncLag => "bitcoin_sync_lag",
+            Self::BitcoinVerificationProgress => "bitcoin_verification_progress",
+            Self::BitcoinPeers => "bitcoin_peers",
+            Self::MoneroWalletBalance => "monero_wallet_balance",
+            Self::MoneroHeightLag => "monero_height_lag",
+            Self::MoneroConnections => "monero_connections",
+            Self::MoneroSynchronized => "monero_synchronized",
+            Self::AsbUp => "asb_up",
+            Self::AsbBalance => "asb_balance",
+            Self::AsbPendingSwaps => "asb_pending_swaps",
+            Self::AsbQuoting => "asb_quoting",
+            Self::AsbMaxQuantity => "asb_max_quantity",
+            Self::ElectrsUp => "electrs_up",
+            Self::DiskFreePercent => "disk_free_percent",
+            Self::DiskFreeGib => "disk_free_gib",
+        }
+    }
+}
+
+/// A collected sample, as the values rules can watch
+pub trait RuleInput {
+    fn readings(&self) -> Vec<(RuleMetric, f64)>;
+}
+
+fn flag(up: bool) -> f64 {
+    if up {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+impl RuleInput for BitcoinMetrics {
+    fn readings(&self) -> Vec<(RuleMetric, f64)> {
+        let mut readings = vec![
+            (
+                RuleMetric::BitcoinSyncLag,
+                self.headers.saturating_sub(self.blocks) as f64,
+            ),
+            (
+                RuleMetric::BitcoinVerificationProgress,
+                self.verification_progress,
+            ),
+        ];
+        if let Some(balance) = self.wallet_balance {
+            readings.push((RuleMetric::BitcoinWalletBalance, balance));
+        }
+        if let Some(network) = &self.network {
+            readings.push((RuleMetric::BitcoinPeers, network.peers as f64));
+        }
+        readings
+    }
+}
+
+impl RuleInput for MoneroMetrics {
+    fn readings(&self) -> Vec<(RuleMetric, f64)> {
+        // monerod reports a target height of 0 once it is synced
+        let mut readings = vec![(
+            RuleMetric::MoneroHeightLag,
+            self.target_height.saturating_sub(self.height) as f64,
+        )];
+        if let Some(balance) = self.wallet_balance {
+            readings.push((RuleMetric::MoneroWalletBalance, balance));
+        }
+        if let Some(network) = &self.network {
+            readings.push((
+                RuleMetric::MoneroConnections,
+                (network.incoming_connections + network.outgoing_connections) as f64,
+            ));
+            readings.push((
+                RuleMetric::MoneroSynchronized,
+                flag(network.synchronized && !network.offline),
+            ));
+        }
+        readings
+    }
+}
+
+impl RuleInput for AsbMetrics {
+    fn readings(&self) -> Vec<(RuleMetric, f64)> {
+        let mut readings = vec![(RuleMetric::AsbUp, flag(self.up))];
+        // A down ASB reports zeroed counters, which aren't real readings
+        if self.up {
+            readings.push((RuleMetric::AsbBalance, self.balance_btc));
+            readings.push((RuleMetric::AsbPendingSwaps, self.pending_swaps as f64));
+        }
+        readings
+    }
+}
+
+impl RuleInput for AsbQuoteMetrics {
+    fn readings(&self) -> Vec<(RuleMetric, f64)> {
+        let mut readings = vec![(RuleMetric::AsbQuoting, flag(self.quoting))];
+        if let Some(max_quantity) = self.max_quantity {
+            readings.push((RuleMetric::AsbMaxQuantity, max_quantity));
+        }
+        readings
+    }
+}
+
+impl RuleInput for ElectrsMetrics {
+    fn readings(&self) -> Vec<(RuleMetric, f64)> {
+        vec![(RuleMetric::ElectrsUp, flag(self.up))]
+    }
+}
+
+/// One collection of every monitored path; rules watch the fullest
+impl RuleInput for Vec<DiskMetrics> {
+    fn readings(&self) -> Vec<(RuleMetric, f64)> {
+        let lowest =
+            |value: fn(&DiskMetrics) -> f64| self.iter().map(value).min_by(|a, b| a.total_cmp(b));
+        let mut readings = Vec::new();
+        if let Some(percent) = lowest(DiskMetrics::free_percent) {
+            readings.push((RuleMetric::DiskFreePercent, percent));
+        }
+        if let Some(gib) = lowest(|disk| disk.available_bytes as f64 / (1u64 << 30) as f64) {
+            readings.push((RuleMetric::DiskFreeGib, gib));
+        }
+        readings
+    }
+}
+
+/// Direction of a rule's threshold
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    Above,
+    Below,
+}
+
+impl Condition {
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Self::Above => value > threshold,
+            Self::Below => value < threshold,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Above => "above",
+            Self::Below => "below",
+        }
+    }
+}
+
+/// A configured alert rule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct AlertRule {
+    /// Unique name, used as the alert kind
+    pub name: String,
+    pub metric: RuleMetric,
+    pub condition: Condition,
+    pub threshold: f64,
+    /// How long the condition must hold before the rule fires
+    #[serde(default)]
+    pub for_secs: u64,
+    #[serde(default = "default_rule_severity")]
+    pub severity: AlertSeverity,
+}
+
+fn default_rule_severity() -> AlertSeverity {
+    AlertSeverity::Warning
+}
+
+impl AlertRule {
+    /// Check the rule's own settings
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty()
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(format!(
+                "Alert rule name '{}' must be non-empty and only use letters, digits and '_'",
+                self.name
+            ));
+        }
+        if !self.threshold.is_finite() {
+            return Err(format!(
+                "Alert rule '{}' has a non-finite threshold",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Whether an alert is still active
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+/// One firing of a rule, as stored in the `alerts` table
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct StoredAlert {
+    pub rule: String,
+    pub metric: RuleMetric,
+    pub severity: AlertSeverity,
+    pub state: AlertState,
+    pub message: String,
+    /// Reading that made the rule fire
+    pub value: f64,
+    pub threshold: f64,
+    pub started_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+impl StoredAlert {
+    /// Record key in the `alerts` table, stable across the alert's lifetime
+    pub fn record_id(&self) -> String {
+        format!("{}_{}", self.rule, self.started_at.timestamp_millis())
+    }
+}
+
+#[derive(Debug, Default)]
+struct RuleState {
+    /// First sample of the current run of samples matching the condition
+    breaching_since: Option<DateTime<Utc>>,
+    firing: Option<StoredAlert>,
+}
+
+/// Evaluates every configured rule and tracks which are firing
+#[derive(Debug)]
+pub struct RuleEngine {
+    rules: Vec<AlertRule>,
+    states: HashMap<String, RuleState>,
+}
+
+impl RuleEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules,
+            states: HashMap::new(),
+        }
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Pick up alerts left firing by a previous run, so they aren't raised twice
+    pub fn restore(&mut self, firing: Vec<StoredAlert>) {
+        for alert in firing {
+            if self.rules.iter().any(|rule| rule.name == alert.rule) {
+                let state = self.states.entry(alert.rule.clone()).or_default();
+                state.breaching_since = Some(alert.started_at);
+                state.firing = Some(alert);
+            }
+        }
+    }
+
+    /// Evaluate the rules watching these readings
+    ///
+    /// Returns the alerts that started firing or resolved with this sample.
+    pub fn observe(
+        &mut self,
+        now: DateTime<Utc>,
+        readings: &[(RuleMetric, f64)],
+    ) -> Vec<StoredAlert> {
+        let mut changed = Vec::new();
+
+        for rule in &self.rules {
+            let Some(&(_, value)) = readings.iter().find(|(metric, _)| *metric == rule.metric)
+            else {
+                continue;
+            };
+            let state = self.states.entry(rule.name.clone()).or_default();
+
+            if !rule.condition.holds(value, rule.threshold) {
+                state.breaching_since = None;
+                if let Some(mut alert) = state.firing.take() {
+                    alert.state = AlertState::Resolved;
+                    alert.resolved_at = Some(now);
+                    changed.push(alert);
+                }
+                continue;
+            }
+
+            let since = *state.breaching_since.get_or_insert(now);
+            if state.firing.is_some() || now - since < Duration::seconds(rule.for_secs as i64) {
+                continue;
+            }
+
+            let alert = StoredAlert {
+                rule: rule.name.clone(),
+                metric: rule.metric,
+                severity: rule.severity,
+                state: AlertState::Firing,
+                message: format!(
+                    "{} is {} ({} {})",
+                    rule.metric.name(),
+                    value,
+                    rule.condition.describe(),
+                    rule.threshold
+                ),
+                value,
+                threshold: rule.threshold,
+                started_at: now,
+                resolved_at: None,
+            };
+            state.firing = Some(alert.clone());
+            changed.push(alert);
+        }
+
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(metric: RuleMetric, condition: Condition, threshold: f64, for_secs: u64) -> AlertRule {
+        AlertRule {
+            name: metric.name().to_string(),
+            metric,
+            condition,
+            threshold,
+            for_secs,
+            severity: AlertSeverity::Critical,
+        }
+    }
+
+    #[test]
+    fn test_rule_fires_after_duration_and_resolves() {
+        let mut engine = RuleEngine::new(vec![rule(RuleMetric::AsbUp, Condition::Below, 1.0, 300)]);
+        let start = Utc::now();
+        let at = |secs: i64| start + Duration::seconds(secs);
+        let down = AsbMetrics {
+            balance_btc: 0.0,
+            pending_swaps: 0,
+            completed_swaps: 0,
+            failed_swaps: 0,
+            up: false,
+        };
+
+        assert!(engine.observe(at(0), &down.readings()).is_empty());
+        assert!(engine.observe(at(240), &down.readings()).is_empty());
+
+        let fired = engine.observe(at(300), &down.readings());
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].state, AlertState::Firing);
+        assert_eq!(fired[0].started_at, at(300));
+        assert_eq!(fired[0].message, "asb_up is 0 (below 1)");
+
+        // Still down: no repeat
+        assert!(engine.observe(at(360), &down.readings()).is_empty());
+
+        let up = AsbMetrics { up: true, ..down };
+        let resolved = engine.observe(at(420), &up.readings());
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].state, AlertState::Resolved);
+        assert_eq!(resolved[0].resolved_at, Some(at(420)));
+        assert_eq!(resolved[0].record_id(), fired[0].record_id());
+    }
+
+    #[test]
+    fn test_recovery_resets_the_duration() {
+        let mut engine = RuleEngine::new(vec![rule(
+            RuleMetric::MoneroHeightLag,
+            Condition::Above,
+            10.0,
+            120,
+        )]);
+        let start = Utc::now();
+        let sample = |height, target_height| MoneroMetrics {
+            height,
+            target_height,
+            difficulty: 0,
+            tx_count: 0,
+            wallet_balance: None,
+            network: None,
+        };
+
+        assert!(engine
+            .observe(start, &sample(100, 150).readings())
+            .is_empty());
+        // Synced monerod reports target 0, which isn't a lag
+        let synced = start + Duration::seconds(60);
+        assert!(engine
+            .observe(synced, &sample(150, 0).readings())
+            .is_empty());
+        let lagging = start + Duration::seconds(150);
+        assert!(engine
+            .observe(lagging, &sample(150, 200).readings())
+            .is_empty());
+        assert_eq!(
+            engine
+                .observe(
+                    lagging + Duration::seconds(120),
+                    &sample(150, 200).readings()
+                )
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_missing_reading_leaves_rule_alone() {
+        let mut engine = RuleEngine::new(vec![rule(
+            RuleMetric::BitcoinWalletBalance,
+            Condition::Below,
+            0.01,
+            0,
+        )]);
+        let mut metrics = BitcoinMetrics {
+            blocks: 100,
+            headers: 100,
+            verification_progress: 1.0,
+            size_on_disk: 0,
+            wallet_balance: Some(0.005),
+            network: None,
+        };
+        let now = Utc::now();
+        assert_eq!(engine.observe(now, &metrics.readings()).len(), 1);
+
+        // No wallet balance in this sample: the alert stays firing
+        metrics.wallet_balance = None;
+        assert!(engine.observe(now, &metrics.readings()).is_empty());
+
+        // Restored alerts aren't raised again
+        let mut restarted = RuleEngine::new(engine.rules().to_vec());
+        let firing = engine
+            .states
+            .remove("bitcoin_wallet_balance")
+            .unwrap()
+            .firing;
+        restarted.restore(firing.into_iter().collect());
+        metrics.wallet_balance = Some(0.001);
+        assert!(restarted.observe(now, &metrics.readings()).is_empty());
+    }
+
+    #[test]
+    fn test_disk_rules_watch_the_fullest_path() {
+        let mut engine = RuleEngine::new(vec![rule(
+            RuleMetric::DiskFreePercent,
+            Condition::Below,
+            10.0,
+            0,
+        )]);
+        let disk = |name: &str, used_gib: u64, available_gib: u64| DiskMetrics {
+            name: name.to_string(),
+            path: format!("/mnt/vault/{}", name),
+            mount_point: "/mnt/vault".to_string(),
+            total_bytes: (used_gib + available_gib) << 30,
+            used_bytes: used_gib << 30,
+            available_bytes: available_gib << 30,
+        };
+
+        let sample = vec![disk("bitcoind", 500, 500), disk("monerod", 95, 5)];
+        assert_eq!(
+            sample.readings(),
+            vec![
+                (RuleMetric::DiskFreePercent, 5.0),
+                (RuleMetric::DiskFreeGib, 5.0)
+            ]
+        );
+        let fired = engine.observe(Utc::now(), &sample.readings());
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].message, "disk_free_percent is 5 (below 10)");
+
+        // No paths, no readings
+        assert!(Vec::<DiskMetrics>::new().readings().is_empty());
+    }
+
+    #[test]
+    fn test_rules_from_toml() {
+        #[derive(Deserialize)]
+        struct Rules {
+            rules: Vec<AlertRule>,
+        }
+
+        let parsed: Rules = toml::from_str(
+            r#"
+            [[rules]]
+            name = "low_btc"
+            metric = "bitcoin_wallet_balance"
+            condition = "below"
+            threshold = 0.01
+            "#,
+        )
+        .unwrap();
+        let rule = &parsed.rules[0];
+        assert_eq!(rule.severity, AlertSeverity::Warning);
+        assert_eq!(rule.for_secs, 0);
+        assert!(rule.validate().is_ok());
+
+        let bad = AlertRule {
+            name: "low btc".to_string(),
+            ..rule.clone()
+        };
+        assert!(bad.validate().is_err());
+    }
+}
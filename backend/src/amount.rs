@@ -0,0 +1,367 @@
+//! Exact money amounts for Bitcoin and Monero
+//!
+//! `Btc` and `Xmr` store integer base units (satoshis and piconero) so that
+//! sums and differences are exact. Conversion to and from `f64` is only meant
+//! for JSON edges and exchange rates; serde uses decimal strings.
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::openapi::schema::{ObjectBuilder, Schema, Type};
+use utoipa::openapi::RefOr;
+use utoipa::{PartialSchema, ToSchema};
+
+macro_rules! amount_type {
+    ($name:ident, $unit:literal, $decimals:expr, $base_units:ident, $example:literal) => {
+        #[doc = concat!("Amount of ", $unit, " stored as integer base units")]
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(i64);
+
+        impl $name {
+            /// Number of decimal places in one whole coin
+            pub const DECIMALS: u32 = $decimals;
+            /// Base units per whole coin
+            pub const UNITS_PER_COIN: i64 = 10i64.pow($decimals);
+            pub const ZERO: Self = Self(0);
+
+            /// Create from base units
+            pub const fn $base_units(units: i64) -> Self {
+                Self(units)
+            }
+
+            /// Amount in base units
+            pub const fn as_base_units(self) -> i64 {
+                self.0
+            }
+
+            /// Convert from a floating point coin amount, rounding to the nearest base unit
+            ///
+            /// Returns `None` for non-finite or out-of-range values.
+            pub fn from_f64(coins: f64) -> Option<Self> {
+                let units = (coins * Self::UNITS_PER_COIN as f64).round();
+                if units.is_finite() && units.abs() < i64::MAX as f64 {
+                    Some(Self(units as i64))
+                } else {
+                    None
+                }
+            }
+
+            /// Convert to a floating point coin amount (for JSON and display only)
+            pub fn to_f64(self) -> f64 {
+                self.0 as f64 / Self::UNITS_PER_COIN as f64
+            }
+
+            pub fn checked_add(self, other: Self) -> Option<Self> {
+                self.0.checked_add(other.0).map(Self)
+            }
+
+            pub fn checked_sub(self, other: Self) -> Option<Self> {
+                self.0.checked_sub(other.0).map(Self)
+            }
+
+            /// Add, stopping at the largest or smallest amount instead of overflowing
+            pub fn saturating_add(self, other: Self) -> Self {
+                Self(self.0.saturating_add(other.0))
+            }
+
+            /// Sum amounts, returning `None` on overflow
+            pub fn checked_sum<I: IntoIterator<Item = Self>>(amounts: I) -> Option<Self> {
+                amounts
+                    .into_iter()
+                    .try_fold(Self::ZERO, |acc, amount| acc.checked_add(amount))
+            }
+
+            /// Truncate toward zero to at most `decimals` decimal places
+            pub fn floor_to_decimals(self, decimals: u32) -> Self {
+                if decimals >= Self::DECIMALS {
+                    return self;
+                }
+                let step = 10i64.pow(Self::DECIMALS - decimals);
+                Self(self.0 - self.0 % step)
+            }
+
+            pub fn is_positive(self) -> bool {
+                self.0 > 0
+            }
+
+            pub fn is_negative(self) -> bool {
+                self.0 < 0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let sign = if self.0 < 0 { "-" } else { "" };
+                let abs = self.0.unsigned_abs();
+                let per_coin = Self::UNITS_PER_COIN as u64;
+                write!(
+                    f,
+                    "{}{}.{:0width$}",
+                    sign,
+                    abs / per_coin,
+                    abs % per_coin,
+                    width = Self::DECIMALS as usize
+                )
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                parse_decimal(s, Self::DECIMALS).map(Self)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                // Accept plain JSON numbers too, for compatibility with older clients
+                #[derive(Deserialize)]
+                #[serde(untagged)]
+                enum Repr {
+                    Text(String),
+                    Number(f64),
+                }
+
+                match Repr::deserialize(deserializer)? {
+                    Repr::Text(s) => s.parse().map_err(de::Error::custom),
+                    Repr::Number(n) => Self::from_f64(n)
+                        .ok_or_else(|| de::Error::custom(format!("{} is out of range", n))),
+                }
+            }
+        }
+
+        impl PartialSchema for $name {
+            fn schema() -> RefOr<Schema> {
+                ObjectBuilder::new()
+                    .schema_type(Type::String)
+                    .description(Some(concat!("Amount in ", $unit, " as a decimal string")))
+                    .examples([$example])
+                    .into()
+            }
+        }
+
+        impl ToSchema for $name {}
+    };
+}
+
+amount_type!(Btc, "BTC", 8, from_sats, "0.00120000");
+amount_type!(Xmr, "XMR", 12, from_piconero, "1.500000000000");
+
+impl Btc {
+    pub const fn as_sats(self) -> i64 {
+        self.0
+    }
+}
+
+impl Xmr {
+    pub const fn as_piconero(self) -> i64 {
+        self.0
+    }
+
+    /// BTC cost of this amount at a price quoted in BTC per XMR
+    pub fn to_btc_at(self, btc_per_xmr: f64) -> Option<Btc> {
+        Btc::from_f64(self.to_f64() * btc_per_xmr)
+    }
+}
+
+/// Parse a decimal string into base units with the given precision
+///
+/// Fails rather than rounding when the string has more decimals than the
+/// currency supports.
+fn parse_decimal(s: &str, decimals: u32) -> Result<i64, String> {
+    let s = s.trim();
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(format!("Invalid amount: '{}'", s));
+    }
+    if !whole
+        .chars()
+        .chain(fraction.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return Err(format!("Invalid amount: '{}'", s));
+    }
+    if fraction.len() > decimals as usize {
+        return Err(format!(
+            "Amount '{}' has more than {} decimal places",
+            s, decimals
+        ));
+    }
+
+    let overflow = || format!("Amount '{}' is out of range", s);
+    let whole_units = if whole.is_empty() {
+        0
+    } else {
+        whole.parse::<i64>().map_err(|_| overflow())?
+    };
+    let fraction_units = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<i64>().map_err(|_| overflow())?
+            * 10i64.pow(decimals - fraction.len() as u32)
+    };
+
+    let units = whole_units
+        .checked_mul(10i64.pow(decimals))
+        .and_then(|units| units.checked_add(fraction_units))
+        .ok_or_else(overflow)?;
+
+    Ok(if negative { -units } else { units })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small deterministic generator so the property tests are reproducible
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_display_and_parse_roundtrip() {
+        assert_eq!(Btc::from_sats(150_000_000).to_string(), "1.50000000");
+        assert_eq!(Btc::from_sats(-1).to_string(), "-0.00000001");
+        assert_eq!(Xmr::from_piconero(1).to_string(), "0.000000000001");
+
+        assert_eq!("1.5".parse::<Btc>().unwrap(), Btc::from_sats(150_000_000));
+        assert_eq!(".00000001".parse::<Btc>().unwrap(), Btc::from_sats(1));
+        assert_eq!(
+            "-2".parse::<Xmr>().unwrap().as_piconero(),
+            -2_000_000_000_000
+        );
+        assert!("0.000000001".parse::<Btc>().is_err());
+        assert!("1.2.3".parse::<Btc>().is_err());
+        assert!("abc".parse::<Xmr>().is_err());
+        assert!("".parse::<Xmr>().is_err());
+    }
+
+    #[test]
+    fn test_serde_as_decimal_string() {
+        let amount = Btc::from_sats(12_345);
+        let json = serde_json::to_string(&amount).unwrap();
+        assert_eq!(json, "\"0.00012345\"");
+        assert_eq!(serde_json::from_str::<Btc>(&json).unwrap(), amount);
+
+        // Numbers are still accepted on input
+        assert_eq!(serde_json::from_str::<Btc>("0.00012345").unwrap(), amount);
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        assert_eq!(
+            Btc::from_sats(i64::MAX).checked_add(Btc::from_sats(1)),
+            None
+        );
+        assert_eq!(
+            Btc::from_sats(i64::MIN).checked_sub(Btc::from_sats(1)),
+            None
+        );
+        assert_eq!(
+            Xmr::from_piconero(5).checked_sub(Xmr::from_piconero(7)),
+            Some(Xmr::from_piconero(-2))
+        );
+        assert_eq!(
+            Btc::from_sats(i64::MAX).saturating_add(Btc::from_sats(1)),
+            Btc::from_sats(i64::MAX)
+        );
+        assert_eq!(Btc::from_f64(f64::NAN), None);
+        assert_eq!(Btc::from_f64(1e30), None);
+    }
+
+    #[test]
+    fn test_floor_to_decimals() {
+        let amount = "0.12345678".parse::<Btc>().unwrap();
+        assert_eq!(amount.floor_to_decimals(5), "0.12345".parse().unwrap());
+        assert_eq!(amount.floor_to_decimals(0), Btc::ZERO);
+        assert_eq!(amount.floor_to_decimals(8), amount);
+        assert_eq!(amount.floor_to_decimals(10), amount);
+        assert_eq!(
+            "-1.999".parse::<Xmr>().unwrap().floor_to_decimals(1),
+            "-1.9".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_xmr_to_btc_conversion() {
+        let xmr = Xmr::from_f64(4.0).unwrap();
+        assert_eq!(xmr.to_btc_at(0.02), Some(Btc::from_sats(8_000_000)));
+    }
+
+    #[test]
+    fn test_property_sums_are_exact() {
+        let mut rng = XorShift(0x5eed_1234_abcd_ef01);
+
+        for _ in 0..200 {
+            let count = (rng.next() % 500 + 2) as usize;
+            // Random amounts up to 0.1 BTC with full satoshi precision
+            let sats: Vec<i64> = (0..count)
+                .map(|_| (rng.next() % 10_000_000) as i64)
+                .collect();
+
+            let amounts: Vec<Btc> = sats.iter().map(|&s| Btc::from_sats(s)).collect();
+            let total = Btc::checked_sum(amounts.iter().copied()).unwrap();
+
+            // Exact against integer arithmetic
+            assert_eq!(total.as_sats(), sats.iter().sum::<i64>());
+
+            // Going through the decimal string form loses nothing
+            assert_eq!(total.to_string().parse::<Btc>().unwrap(), total);
+
+            // Adding then removing every amount returns exactly to zero
+            let back = amounts
+                .iter()
+                .try_fold(total, |acc, amount| acc.checked_sub(*amount))
+                .unwrap();
+            assert_eq!(back, Btc::ZERO);
+        }
+    }
+
+    #[test]
+    fn test_property_f64_sums_drift() {
+        // 0.1 XMR added ten thousand times: f64 drifts, piconero doesn't
+        let step = Xmr::from_f64(0.1).unwrap();
+        let exact = Xmr::checked_sum(std::iter::repeat_n(step, 10_000)).unwrap();
+        assert_eq!(exact, Xmr::from_f64(1000.0).unwrap());
+
+        let float_total: f64 = std::iter::repeat_n(0.1f64, 10_000).sum();
+        assert_ne!(float_total, 1000.0);
+
+        // Random satoshi-precision amounts: the newtype always matches integers,
+        // while repeated f64 addition eventually disagrees
+        let mut rng = XorShift(0x0ddb_a11c_afe0_0042);
+        let mut drifted = false;
+        for _ in 0..50 {
+            let sats: Vec<i64> = (0..1_000)
+                .map(|_| (rng.next() % 100_000_000) as i64)
+                .collect();
+            let float_sum: f64 = sats.iter().map(|&s| s as f64 / 1e8).sum();
+            let exact = Btc::checked_sum(sats.iter().map(|&s| Btc::from_sats(s))).unwrap();
+
+            assert_eq!(exact.as_sats(), sats.iter().sum::<i64>());
+            if (float_sum * 1e8) != exact.as_sats() as f64 {
+                drifted = true;
+            }
+        }
+        assert!(drifted, "f64 sums should drift for at least one sample");
+    }
+}
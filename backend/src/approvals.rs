@@ -0,0 +1,388 @@
+//! Two-key approval of large transfers
+//!
+//! Wallet sends and engine rebalances that move more than
+//! `[approvals] btc_threshold` or `xmr_threshold` are not run straight away.
+//! They are stored as pending approvals, and only run once a second API key,
+//! other than the one that asked, approves them through `/approvals`. A
+//! wallet send runs as part of the approving call; an approved rebalance runs
+//! at the engine's next check, provided it still moves about the approved
+//! amounts and the approval hasn't expired. Every approval keeps who asked,
+//! who decided and what came of it.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    auth::{Caller, Principal},
+    routes::{bitcoin, monero, wallets},
+    trading::emergency::ConversionDirection,
+    wallets::{
+        bitcoin::CoinControlSend,
+        monero::{Destination, TransferPriority},
+        psbt::PsbtRequest,
+        transfer::TransferRequest,
+    },
+    ApiError, ApiResult, AppState, Xmr,
+};
+
+/// Requester recorded for rebalances the trading engine asks to run
+pub const ENGINE_REQUESTER: &str = "trading-engine";
+
+/// How far below the approved amounts a rebalance may come out and still be covered
+const PLAN_TOLERANCE: f64 = 0.1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalsConfig {
+    /// BTC above which a send or rebalance needs a second key. 0 disables.
+    #[serde(default)]
+    pub btc_threshold: f64,
+    /// XMR above which a send or rebalance needs a second key. 0 disables.
+    #[serde(default)]
+    pub xmr_threshold: f64,
+    /// Seconds an approval stays open, and an approved rebalance stays
+    /// usable, before it expires
+    #[serde(default = "default_expiry_secs")]
+    pub expiry_secs: u64,
+}
+
+fn default_expiry_secs() -> u64 {
+    86_400
+}
+
+impl Default for ApprovalsConfig {
+    fn default() -> Self {
+        Self {
+            btc_threshold: 0.0,
+            xmr_threshold: 0.0,
+            expiry_secs: default_expiry_secs(),
+        }
+    }
+}
+
+impl ApprovalsConfig {
+    /// Whether moving `btc` and `xmr` needs a second key
+    pub fn requires_approval(&self, btc: f64, xmr: f64) -> bool {
+        (self.btc_threshold > 0.0 && btc > self.btc_threshold)
+            || (self.xmr_threshold > 0.0 && xmr > self.xmr_threshold)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (name, threshold) in [
+            ("btc_threshold", self.btc_threshold),
+            ("xmr_threshold", self.xmr_threshold),
+        ] {
+            if !threshold.is_finite() || threshold < 0.0 {
+                anyhow::bail!("approvals.{} must be zero or positive", name);
+            }
+        }
+        if self.expiry_secs == 0 {
+            anyhow::bail!("approvals.expiry_secs must be positive");
+        }
+        Ok(())
+    }
+}
+
+/// The operation waiting for approval
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ApprovalAction {
+    /// `POST /wallets/transfer`
+    Transfer { request: TransferRequest },
+    /// `POST /wallets/bitcoin/send`
+    BitcoinSend { send: CoinControlSend },
+    /// `POST /wallets/bitcoin/psbt`
+    BitcoinPsbt { request: PsbtRequest },
+    /// `POST /wallets/monero/sweep`
+    MoneroSweep {
+        address: String,
+        priority: TransferPriority,
+        below_amount: Option<Xmr>,
+    },
+    /// `POST /wallets/monero/transfer-split`
+    MoneroTransferSplit {
+        destinations: Vec<Destination>,
+        priority: TransferPriority,
+    },
+    /// A rebalance the trading engine wants to start
+    Rebalance { direction: ConversionDirection },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    /// Approved rebalance the engine has yet to start
+    Approved,
+    Rejected,
+    Executed,
+    Failed,
+    Expired,
+}
+
+impl std::fmt::Display for ApprovalStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ApprovalStatus::Pending => "pending",
+            ApprovalStatus::Approved => "approved",
+            ApprovalStatus::Rejected => "rejected",
+            ApprovalStatus::Executed => "executed",
+            ApprovalStatus::Failed => "failed",
+            ApprovalStatus::Expired => "expired",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An operation held for a second key, and what became of it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Approval {
+    #[serde(skip_deserializing)]
+    pub id: Option<String>,
+    pub action: ApprovalAction,
+    /// BTC the operation moves, as far as known when it was requested
+    pub btc_amount: f64,
+    /// XMR the operation moves, as far as known when it was requested
+    pub xmr_amount: f64,
+    pub status: ApprovalStatus,
+    /// Name of the API key that asked, or [`ENGINE_REQUESTER`]
+    pub requested_by: String,
+    /// Who asked; another principal must decide
+    pub requester: Principal,
+    pub requested_at: DateTime<Utc>,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    /// Rejection reason or execution error
+    pub reason: Option<String>,
+    /// Response of the executed send
+    pub result: Option<serde_json::Value>,
+}
+
+impl Approval {
+    pub fn new(
+        action: ApprovalAction,
+        btc_amount: f64,
+        xmr_amount: f64,
+        requester: Principal,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: None,
+            action,
+            btc_amount,
+            xmr_amount,
+            status: ApprovalStatus::Pending,
+            requested_by: requester.name().to_string(),
+            requester,
+            requested_at: now,
+            decided_by: None,
+            decided_at: None,
+            reason: None,
+            result: None,
+        }
+    }
+
+    /// Whether the approval has outlived `expiry_secs`
+    ///
+    /// A pending approval expires that long after it was requested, an
+    /// approved rebalance that long after it was approved; other decisions
+    /// are final.
+    pub fn is_expired(&self, now: DateTime<Utc>, expiry_secs: u64) -> bool {
+        let since = match self.status {
+            ApprovalStatus::Pending => self.requested_at,
+            ApprovalStatus::Approved => self.decided_at.unwrap_or(self.requested_at),
+            _ => return false,
+        };
+        now - since > Duration::seconds(expiry_secs as i64)
+    }
+
+    /// Refuse a decision by the principal that asked
+    pub fn check_approver(&self, approver: &Principal) -> anyhow::Result<()> {
+        if *approver == self.requester {
+            anyhow::bail!(
+                "'{}' requested this operation; another key must approve it",
+                approver.name()
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether this is a rebalance in `direction` for the plan to move `btc`
+    /// and `xmr`: no more than the requested amounts, and no more than
+    /// [`PLAN_TOLERANCE`] less
+    pub fn matches_rebalance(&self, direction: ConversionDirection, btc: f64, xmr: f64) -> bool {
+        let within = |planned: f64, requested: f64| {
+            planned <= requested && planned >= requested * (1.0 - PLAN_TOLERANCE)
+        };
+        matches!(
+            self.action,
+            ApprovalAction::Rebalance { direction: asked } if asked == direction
+        ) && within(btc, self.btc_amount)
+            && within(xmr, self.xmr_amount)
+    }
+
+    /// Whether this is an approved rebalance for the plan to move `btc` and
+    /// `xmr` in `direction`, see [`Approval::matches_rebalance`]
+    pub fn covers_rebalance(&self, direction: ConversionDirection, btc: f64, xmr: f64) -> bool {
+        self.status == ApprovalStatus::Approved && self.matches_rebalance(direction, btc, xmr)
+    }
+}
+
+/// Hold `action` for approval if it moves more than the thresholds
+///
+/// # Returns
+/// The `202 Accepted` response to send in place of running the action, if held
+pub async fn hold_if_large(
+    state: &AppState,
+    caller: &Caller,
+    action: ApprovalAction,
+    btc: f64,
+    xmr: f64,
+) -> ApiResult<Option<Response>> {
+    if !state.config.get().approvals.requires_approval(btc, xmr) {
+        return Ok(None);
+    }
+
+    let mut approval = Approval::new(action, btc, xmr, caller.principal.clone(), Utc::now());
+    let id = state.db.store_approval(&approval).await?;
+    tracing::info!(
+        "Held {:?} by {} for approval ({} BTC, {} XMR): {}",
+        approval.action,
+        caller.name(),
+        btc,
+        xmr,
+        id
+    );
+    approval.id = Some(id);
+
+    Ok(Some((StatusCode::ACCEPTED, Json(approval)).into_response()))
+}
+
+/// Run the wallet send an approval was holding
+///
+/// The send is checked again against the wallet as it is now, since the
+/// balance may have moved while it waited.
+///
+/// # Returns
+/// The response body the original request would have had
+pub async fn execute(state: &AppState, action: &ApprovalAction) -> ApiResult<serde_json::Value> {
+    let result = match action {
+        ApprovalAction::Transfer { request } => {
+            serde_json::to_value(wallets::run_transfer(state, request).await?)
+        }
+        ApprovalAction::BitcoinSend { send } => {
+            serde_json::to_value(bitcoin::run_coin_control_send(state, send).await?)
+        }
+        ApprovalAction::BitcoinPsbt { request } => {
+            serde_json::to_value(bitcoin::run_create_psbt(state, request).await?)
+        }
+        ApprovalAction::MoneroSweep {
+            address,
+            priority,
+            below_amount,
+        } => {
+            serde_json::to_value(monero::run_sweep(state, address, *priority, *below_amount).await?)
+        }
+        ApprovalAction::MoneroTransferSplit {
+            destinations,
+            priority,
+        } => {
+            serde_json::to_value(monero::run_transfer_split(state, destinations, *priority).await?)
+        }
+        ApprovalAction::Rebalance { .. } => {
+            return Err(ApiError::BadRequest(
+                "Rebalances are started by the trading engine".to_string(),
+            ))
+        }
+    };
+
+    result.map_err(|e| ApiError::Internal(e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rebalance(direction: ConversionDirection, btc: f64, xmr: f64) -> Approval {
+        Approval::new(
+            ApprovalAction::Rebalance { direction },
+            btc,
+            xmr,
+            Principal::TradingEngine,
+            Utc::now(),
+        )
+    }
+
+    #[test]
+    fn test_requires_approval() {
+        let config = ApprovalsConfig {
+            btc_threshold: 0.5,
+            ..ApprovalsConfig::default()
+        };
+        assert!(config.requires_approval(0.6, 0.0));
+        assert!(!config.requires_approval(0.5, 0.0));
+        // An unset threshold never applies
+        assert!(!config.requires_approval(0.0, 1_000.0));
+        assert!(!ApprovalsConfig::default().requires_approval(100.0, 100.0));
+    }
+
+    #[test]
+    fn test_requester_cannot_approve() {
+        let approval = rebalance(ConversionDirection::BtcToXmr, 1.0, 150.0);
+        assert!(approval.check_approver(&Principal::TradingEngine).is_err());
+        assert!(approval
+            .check_approver(&Principal::ApiKey("treasurer".to_string()))
+            .is_ok());
+
+        // A key named like the admin token is not the admin token
+        let held = Approval::new(
+            ApprovalAction::Rebalance {
+                direction: ConversionDirection::BtcToXmr,
+            },
+            1.0,
+            150.0,
+            Principal::ApiKey("admin".to_string()),
+            Utc::now(),
+        );
+        assert_eq!(held.requested_by, "admin");
+        assert!(held.check_approver(&Principal::AdminToken).is_ok());
+        assert!(held
+            .check_approver(&Principal::ApiKey("admin".to_string()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_covers_rebalance() {
+        let mut approval = rebalance(ConversionDirection::BtcToXmr, 1.0, 150.0);
+        assert!(!approval.covers_rebalance(ConversionDirection::BtcToXmr, 1.0, 150.0));
+
+        approval.status = ApprovalStatus::Approved;
+        assert!(approval.covers_rebalance(ConversionDirection::BtcToXmr, 0.95, 140.0));
+        assert!(!approval.covers_rebalance(ConversionDirection::BtcToXmr, 1.1, 150.0));
+        assert!(!approval.covers_rebalance(ConversionDirection::XmrToBtc, 0.95, 140.0));
+        // A much smaller plan than the one approved needs its own approval
+        assert!(!approval.covers_rebalance(ConversionDirection::BtcToXmr, 0.2, 30.0));
+    }
+
+    #[test]
+    fn test_expiry() {
+        let mut approval = rebalance(ConversionDirection::XmrToBtc, 1.0, 150.0);
+        assert!(!approval.is_expired(approval.requested_at, 3600));
+        assert!(approval.is_expired(approval.requested_at + Duration::hours(2), 3600));
+
+        // Once approved, the clock runs from the approval
+        let approved_at = approval.requested_at + Duration::minutes(50);
+        approval.status = ApprovalStatus::Approved;
+        approval.decided_at = Some(approved_at);
+        assert!(!approval.is_expired(approved_at + Duration::minutes(30), 3600));
+        assert!(approval.is_expired(approved_at + Duration::hours(2), 3600));
+
+        approval.status = ApprovalStatus::Executed;
+        assert!(!approval.is_expired(approved_at + Duration::days(30), 3600));
+    }
+}
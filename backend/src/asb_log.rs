@@ -0,0 +1,688 @@
+//! Swap events read from the ASB's log
+//!
+//! The ASB RPC reports where each swap stands, not how it got there, and
+//! forgets nothing it can be asked about later. Its log has a line for every
+//! state a swap enters and for every error, so [`ingest`] follows the log,
+//! either a file (`[asb] log_path`, and `log_file` in it when it is the
+//! ASB's log directory) or a container's output (`[asb] log_container`),
+//! and stores those lines as [`AsbEvent`]s. Both the JSON lines of the ASB's
+//! log files and the plain text it prints are understood.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+use utoipa::ToSchema;
+
+use crate::config::{ContainerRuntime, SharedConfig};
+use crate::db::MetricsDatabase;
+use crate::metrics::ContainerHealthClient;
+use crate::shutdown::Shutdown;
+
+/// How often the log is checked for new lines
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Most of a log file read in one poll, so a long backlog is worked through
+/// over several polls instead of all at once
+const MAX_READ_BYTES: u64 = 4 * 1024 * 1024;
+
+const LEVELS: [&str; 5] = ["TRACE", "DEBUG", "INFO", "WARN", "ERROR"];
+
+/// What a logged line says happened to a swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AsbEventKind {
+    Started,
+    /// The taker's BTC lock is confirmed
+    BtcLocked,
+    /// The ASB published its XMR lock
+    XmrLockSent,
+    /// The ASB's XMR lock is confirmed
+    XmrLocked,
+    /// The taker sent the encrypted signature the ASB redeems with
+    EncSigLearned,
+    BtcRedeemed,
+    /// The cancel timelock expired or the cancel transaction was published
+    Cancelled,
+    BtcRefunded,
+    XmrRefunded,
+    BtcPunished,
+    /// Ended before anything was locked
+    Aborted,
+    /// A state not listed above
+    StateChanged,
+    /// An error logged for the swap
+    Failed,
+}
+
+impl AsbEventKind {
+    /// Kind of entering the ASB state `state`, matched loosely since the
+    /// state names vary between ASB versions ("btc is redeemed", "BtcRedeemed")
+    pub fn from_state(state: &str) -> Self {
+        let normalized: String = state
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if normalized == "started" {
+            Self::Started
+        } else if normalized.contains("punished") {
+            Self::BtcPunished
+        } else if normalized.contains("xmr") && normalized.contains("refunded") {
+            Self::XmrRefunded
+        } else if normalized.contains("refunded") {
+            Self::BtcRefunded
+        } else if normalized.contains("redeemed") {
+            Self::BtcRedeemed
+        } else if normalized.contains("cancel") {
+            Self::Cancelled
+        } else if normalized.contains("abort") {
+            Self::Aborted
+        } else if normalized.contains("xmrlocktransactionsent") {
+            Self::XmrLockSent
+        } else if matches!(normalized.as_str(), "xmrislocked" | "xmrlocked") {
+            Self::XmrLocked
+        } else if matches!(normalized.as_str(), "btcislocked" | "btclocked") {
+            Self::BtcLocked
+        } else if normalized.contains("encryptedsignature") || normalized.contains("encsig") {
+            Self::EncSigLearned
+        } else {
+            Self::StateChanged
+        }
+    }
+}
+
+/// A swap lifecycle line of the ASB's log, as stored in `asb_events`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AsbEvent {
+    /// When the ASB logged it, or when it was read if the line has no time
+    pub timestamp: DateTime<Utc>,
+    pub swap_id: String,
+    pub kind: AsbEventKind,
+    /// The state entered, as the ASB names it
+    pub state: Option<String>,
+    pub txid: Option<String>,
+    pub level: String,
+    pub message: String,
+}
+
+/// A log line taken apart, before deciding whether it is about a swap
+#[derive(Debug, Default)]
+struct LogLine {
+    timestamp: Option<DateTime<Utc>>,
+    level: String,
+    message: String,
+    /// The event's fields and those of the spans it is in
+    fields: HashMap<String, String>,
+}
+
+/// `line` without terminal color codes, which container output keeps
+fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip `ESC [ parameters final-letter`
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// The first UUID in `text`, which is how swaps are identified
+fn find_uuid(text: &str) -> Option<&str> {
+    let bytes = text.as_bytes();
+    (0..bytes.len().saturating_sub(35)).find_map(|start| {
+        let candidate = &bytes[start..start + 36];
+        let is_uuid = candidate.iter().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => *b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        });
+        is_uuid.then(|| &text[start..start + 36])
+    })
+}
+
+fn text_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A line of tracing's JSON format
+fn parse_json(line: &str) -> Option<LogLine> {
+    let value: Value = serde_json::from_str(line).ok()?;
+
+    // Outer spans first, so inner ones and the event's own fields win
+    let mut fields = HashMap::new();
+    let objects = value["spans"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .chain([&value["span"], &value["fields"]]);
+    for object in objects.filter_map(Value::as_object) {
+        for (key, field) in object {
+            fields.insert(key.clone(), text_value(field));
+        }
+    }
+
+    Some(LogLine {
+        timestamp: value["timestamp"]
+            .as_str()
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+            .map(|time| time.with_timezone(&Utc)),
+        level: value["level"].as_str().unwrap_or_default().to_string(),
+        message: fields.remove("message").unwrap_or_default(),
+        fields,
+    })
+}
+
+/// Whether `prefix` is a span list (`swap{swap_id=..}`) or a target
+/// (`swap::protocol::alice`), which tracing prints before the message.
+/// Targets are module paths, so all lowercase, unlike `Error: ..` messages.
+fn is_span_or_target(prefix: &str) -> bool {
+    let is_span = prefix.ends_with('}') && prefix.starts_with(|c: char| c.is_ascii_alphabetic());
+    let is_target = !prefix.is_empty()
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == ':');
+    is_span || is_target
+}
+
+/// Start of every `key=value` field in `body`
+fn field_starts(body: &str) -> Vec<usize> {
+    body.match_indices(' ')
+        .map(|(space, _)| space + 1)
+        .filter(|&start| {
+            let key_len = body[start..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(0);
+            key_len > 0 && body[start + key_len..].starts_with('=')
+        })
+        .collect()
+}
+
+/// A line of tracing's text format:
+/// `2024-05-01T12:00:00Z  INFO swap{swap_id=..}: target: message key=value`
+fn parse_text(line: &str) -> LogLine {
+    let mut parsed = LogLine::default();
+    let mut rest = line;
+
+    if let Some((first, after)) = rest.split_once(char::is_whitespace) {
+        if let Ok(time) = DateTime::parse_from_rfc3339(first) {
+            parsed.timestamp = Some(time.with_timezone(&Utc));
+            rest = after.trim_start();
+        }
+    }
+    if let Some((first, after)) = rest.split_once(char::is_whitespace) {
+        if LEVELS.contains(&first) {
+            parsed.level = first.to_string();
+            rest = after.trim_start();
+        }
+    }
+    while let Some((prefix, after)) = rest.split_once(": ") {
+        if !is_span_or_target(prefix) {
+            break;
+        }
+        // Span fields, such as the swap_id of `swap{swap_id=..}`
+        for pair in prefix.replace(['{', '}', ':'], " ").split_whitespace() {
+            if let Some((key, value)) = pair.split_once('=') {
+                parsed.fields.insert(key.to_string(), value.to_string());
+            }
+        }
+        rest = after;
+    }
+
+    let starts = field_starts(rest);
+    let message_end = starts.first().map_or(rest.len(), |start| start - 1);
+    parsed.message = rest[..message_end].trim().to_string();
+    for (index, &start) in starts.iter().enumerate() {
+        let end = starts.get(index + 1).map_or(rest.len(), |next| next - 1);
+        if let Some((key, value)) = rest[start..end].split_once('=') {
+            parsed
+                .fields
+                .insert(key.to_string(), value.trim().trim_matches('"').to_string());
+        }
+    }
+    parsed
+}
+
+/// The swap event a log line records, if it records one
+///
+/// Lines about a swap count when they say it entered a state, or are errors.
+/// Lines without a time of their own are dated `now`.
+pub fn parse_line(line: &str, now: DateTime<Utc>) -> Option<AsbEvent> {
+    let line = strip_ansi(line);
+    let line = line.trim();
+    let parsed = if line.starts_with('{') {
+        parse_json(line)?
+    } else {
+        parse_text(line)
+    };
+
+    let swap_id = parsed
+        .fields
+        .get("swap_id")
+        .and_then(|id| find_uuid(id))
+        .or_else(|| find_uuid(line))?
+        .to_string();
+    let state = parsed.fields.get("state").cloned();
+    let kind = match &state {
+        Some(state) => AsbEventKind::from_state(state),
+        None if parsed.level == "ERROR"
+            || parsed.message.to_ascii_lowercase().contains("swap failed") =>
+        {
+            AsbEventKind::Failed
+        }
+        None => return None,
+    };
+
+    Some(AsbEvent {
+        timestamp: parsed.timestamp.unwrap_or(now),
+        swap_id,
+        kind,
+        state,
+        txid: parsed.fields.get("txid").cloned(),
+        level: parsed.level,
+        message: parsed.message,
+    })
+}
+
+/// Record key of a log line, so reading the same line again overwrites its
+/// event instead of adding another
+pub fn event_key(line: &str) -> String {
+    Sha256::digest(line.trim().as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The file to follow: `path` itself, or `name` in it when it is a directory
+///
+/// The ASB writes several logs to its log directory at once, so following
+/// whichever was written last would keep switching between them.
+fn followed_file(path: &Path, name: &str) -> PathBuf {
+    if path.is_dir() {
+        path.join(name)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Position in the log file being followed
+#[derive(Debug, Default)]
+struct LogFile {
+    path: Option<PathBuf>,
+    offset: u64,
+    /// Start of a line still being written
+    partial: Vec<u8>,
+}
+
+impl LogFile {
+    /// Complete lines added to the log at `path` since the last read
+    ///
+    /// Starts over from the top when the log turns out to be another file,
+    /// or shorter than what was already read (truncated or replaced).
+    fn read_new_lines(&mut self, path: &Path) -> Result<Vec<String>> {
+        let mut file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let len = file.metadata()?.len();
+        if self.path.as_deref() != Some(path) || len < self.offset {
+            self.path = Some(path.to_path_buf());
+            self.offset = 0;
+            self.partial.clear();
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let read = file
+            .take(MAX_READ_BYTES)
+            .read_to_end(&mut self.partial)
+            .context("Failed to read the ASB log")?;
+        self.offset += read as u64;
+
+        let Some(end) = self.partial.iter().rposition(|&b| b == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let complete: Vec<u8> = self.partial.drain(..=end).collect();
+        Ok(String::from_utf8_lossy(&complete)
+            .lines()
+            .map(str::to_string)
+            .collect())
+    }
+}
+
+/// Output of container `name` since `since` (all of it when `None`)
+async fn container_logs(
+    runtime: ContainerRuntime,
+    name: &str,
+    since: Option<DateTime<Utc>>,
+) -> Result<Vec<String>> {
+    let mut command = match runtime {
+        ContainerRuntime::Podman | ContainerRuntime::Docker => {
+            let mut command = Command::new("sudo");
+            command.arg(if runtime == ContainerRuntime::Podman {
+                "podman"
+            } else {
+                "docker"
+            });
+            command.arg("logs");
+            if let Some(since) = since {
+                command.args(["--since", &since.to_rfc3339()]);
+            }
+            command.arg(name);
+            command
+        }
+        ContainerRuntime::Systemd | ContainerRuntime::Auto => {
+            let mut command = Command::new("journalctl");
+            command.args(["--unit", name, "--output", "cat", "--no-pager"]);
+            if let Some(since) = since {
+                command.arg(format!("--since=@{}", since.timestamp()));
+            }
+            command
+        }
+    };
+
+    let output = command
+        .output()
+        .await
+        .with_context(|| format!("Failed to read the logs of {}", name))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Reading the logs of {} failed: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    // The ASB logs to stderr, which `logs` passes through as its own
+    let mut lines: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    if runtime != ContainerRuntime::Systemd {
+        lines.extend(
+            String::from_utf8_lossy(&output.stderr)
+                .lines()
+                .map(str::to_string),
+        );
+    }
+    Ok(lines)
+}
+
+/// Where the log is followed from, kept between polls
+struct Follower {
+    /// Detects the runtime a followed container runs under
+    containers: ContainerHealthClient,
+    file: LogFile,
+    /// Container followed, the runtime it runs under and when it was last read
+    container: Option<(String, ContainerRuntime, DateTime<Utc>)>,
+}
+
+impl Follower {
+    fn new(containers: ContainerHealthClient) -> Self {
+        Self {
+            containers,
+            file: LogFile::default(),
+            container: None,
+        }
+    }
+
+    /// Lines logged since the last poll, read from wherever `config` says now
+    async fn poll(&mut self, config: &SharedConfig) -> Result<Vec<String>> {
+        let config = config.get();
+        if let Some(path) = &config.asb.log_path {
+            let path = followed_file(path, &config.asb.log_file);
+            // File reads block, so they are done off the async workers
+            let mut file = std::mem::take(&mut self.file);
+            let (file, lines) = tokio::task::spawn_blocking(move || {
+                let lines = file.read_new_lines(&path);
+                (file, lines)
+            })
+            .await
+            .context("Reading the ASB log panicked")?;
+            self.file = file;
+            return lines;
+        }
+        let Some(name) = &config.asb.log_container else {
+            return Ok(Vec::new());
+        };
+
+        let (runtime, since) = match &self.container {
+            Some((followed, runtime, since)) if followed == name => (*runtime, Some(*since)),
+            _ => {
+                let containers = self.containers.clone();
+                let runtime = tokio::task::spawn_blocking(move || {
+                    containers.select_probe().map(|probe| probe.runtime())
+                })
+                .await
+                .context("Detecting the container runtime panicked")??;
+                (runtime, None)
+            }
+        };
+        // Overlaps the previous read by a second, since `--since` is coarse;
+        // lines read twice are stored once
+        let read_at = Utc::now() - chrono::Duration::seconds(1);
+        let lines = container_logs(runtime, name, since).await?;
+        self.container = Some((name.clone(), runtime, read_at));
+        Ok(lines)
+    }
+}
+
+/// Store the swap events among `lines`, returning how many there were
+async fn store_events(db: &MetricsDatabase, lines: &[String]) -> usize {
+    let now = Utc::now();
+    let mut stored = 0;
+    for line in lines {
+        let Some(event) = parse_line(line, now) else {
+            continue;
+        };
+        match db.store_asb_event(&event_key(line), &event).await {
+            Ok(()) => stored += 1,
+            Err(e) => tracing::error!("Failed to store ASB event: {:#}", e),
+        }
+    }
+    stored
+}
+
+/// Follow the ASB log until shutdown, storing the swap events it records
+///
+/// The source is read from `config` on every poll, so a reload that sets or
+/// changes `[asb] log_path` or `log_container` takes effect right away. A
+/// container's runtime is detected through `containers`.
+pub async fn ingest(
+    config: SharedConfig,
+    db: MetricsDatabase,
+    containers: ContainerHealthClient,
+    shutdown: Shutdown,
+) {
+    let mut follower = Follower::new(containers);
+    // Repeats of the same failure are only logged once
+    let mut last_error: Option<String> = None;
+
+    loop {
+        match follower.poll(&config).await {
+            Ok(lines) => {
+                last_error = None;
+                let stored = store_events(&db, &lines).await;
+                if stored > 0 {
+                    tracing::debug!("Stored {} ASB swap events", stored);
+                }
+            }
+            Err(e) => {
+                let error = format!("{:#}", e);
+                if last_error.as_ref() != Some(&error) {
+                    tracing::warn!("Failed to read the ASB log: {}", error);
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = shutdown.triggered() => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const SWAP_ID: &str = "a1b2c3d4-0000-4000-8000-000000000001";
+
+    fn now() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_714_600_000, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_json_line() {
+        let line = format!(
+            r#"{{"timestamp":"2024-05-01T12:00:00.123Z","level":"INFO","fields":{{"message":"Advancing state","state":"btc is locked"}},"target":"swap::protocol::alice::swap","span":{{"swap_id":"{}","name":"swap"}},"spans":[{{"swap_id":"{}","name":"swap"}}]}}"#,
+            SWAP_ID, SWAP_ID
+        );
+        let event = parse_line(&line, now()).unwrap();
+        assert_eq!(event.swap_id, SWAP_ID);
+        assert_eq!(event.kind, AsbEventKind::BtcLocked);
+        assert_eq!(event.state.as_deref(), Some("btc is locked"));
+        assert_eq!(event.level, "INFO");
+        assert_eq!(event.message, "Advancing state");
+        assert_eq!(
+            event.timestamp,
+            DateTime::parse_from_rfc3339("2024-05-01T12:00:00.123Z").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_text_line() {
+        let line = format!(
+            "\x1b[2m2024-05-01T12:00:00Z\x1b[0m \x1b[32m INFO\x1b[0m swap{{swap_id={}}}: swap::protocol::alice::swap: Advancing state state=xmr lock transaction sent txid=abc123",
+            SWAP_ID
+        );
+        let event = parse_line(&line, now()).unwrap();
+        assert_eq!(event.swap_id, SWAP_ID);
+        assert_eq!(event.kind, AsbEventKind::XmrLockSent);
+        assert_eq!(event.state.as_deref(), Some("xmr lock transaction sent"));
+        assert_eq!(event.txid.as_deref(), Some("abc123"));
+        assert_eq!(event.message, "Advancing state");
+        assert_eq!(
+            event.timestamp,
+            DateTime::from_timestamp(1_714_564_800, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_errors_and_other_lines() {
+        let failed = format!(
+            "2024-05-01T12:00:00Z ERROR swap{{swap_id={}}}: swap::asb: Swap failed error=\"Failed to redeem\"",
+            SWAP_ID
+        );
+        let event = parse_line(&failed, now()).unwrap();
+        assert_eq!(event.kind, AsbEventKind::Failed);
+        assert_eq!(event.message, "Swap failed");
+        assert_eq!(event.state, None);
+
+        // About a swap, but neither a state change nor an error
+        let chatter = format!(
+            "2024-05-01T12:00:00Z  INFO swap{{swap_id={}}}: swap::asb: Waiting for cancel timelock",
+            SWAP_ID
+        );
+        assert_eq!(parse_line(&chatter, now()), None);
+        // A state change outside any swap
+        assert_eq!(
+            parse_line(
+                "2024-05-01T12:00:00Z  INFO swap::asb: Advancing state state=started",
+                now()
+            ),
+            None
+        );
+        assert_eq!(parse_line("", now()), None);
+
+        // Without a time of its own the line is dated when it was read
+        let event = parse_line(
+            &format!("Advancing state state=btc is redeemed swap_id={}", SWAP_ID),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(event.kind, AsbEventKind::BtcRedeemed);
+        assert_eq!(event.timestamp, now());
+    }
+
+    #[test]
+    fn test_kind_from_state() {
+        let cases = [
+            ("started", AsbEventKind::Started),
+            ("btc is locked", AsbEventKind::BtcLocked),
+            ("XmrLockTransactionSent", AsbEventKind::XmrLockSent),
+            ("xmr is locked", AsbEventKind::XmrLocked),
+            (
+                "encrypted signature is learned",
+                AsbEventKind::EncSigLearned,
+            ),
+            ("btc is redeemed", AsbEventKind::BtcRedeemed),
+            ("btc cancel is published", AsbEventKind::Cancelled),
+            ("btc is refunded", AsbEventKind::BtcRefunded),
+            ("xmr is refunded", AsbEventKind::XmrRefunded),
+            ("btc is punished", AsbEventKind::BtcPunished),
+            ("safely aborted", AsbEventKind::Aborted),
+            ("btc punishable", AsbEventKind::StateChanged),
+        ];
+        for (state, kind) in cases {
+            assert_eq!(AsbEventKind::from_state(state), kind, "{}", state);
+        }
+    }
+
+    #[test]
+    fn test_log_file_follows_appends_and_truncation() {
+        let dir = std::env::temp_dir().join(format!("eigenix-asb-log-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let first = dir.join("swap-all.log");
+        fs::write(&first, "one\ntwo\nthr").unwrap();
+        let followed = followed_file(&dir, "swap-all.log");
+        assert_eq!(followed, first);
+
+        let mut log = LogFile::default();
+        assert_eq!(log.read_new_lines(&followed).unwrap(), vec!["one", "two"]);
+        // The unfinished line is held back until it ends
+        let mut file = fs::OpenOptions::new().append(true).open(&first).unwrap();
+        std::io::Write::write_all(&mut file, b"ee\n").unwrap();
+        assert_eq!(log.read_new_lines(&followed).unwrap(), vec!["three"]);
+        assert!(log.read_new_lines(&followed).unwrap().is_empty());
+
+        // Other logs written next to it are left alone
+        fs::write(dir.join("tracing.log"), "verbose\n").unwrap();
+        assert_eq!(followed_file(&dir, "swap-all.log"), first);
+        std::io::Write::write_all(&mut file, b"four\n").unwrap();
+        assert_eq!(log.read_new_lines(&followed).unwrap(), vec!["four"]);
+
+        // Truncated in place: read again from the top
+        fs::write(&first, "five\n").unwrap();
+        assert_eq!(log.read_new_lines(&followed).unwrap(), vec!["five"]);
+
+        // A file given directly is followed as is
+        assert_eq!(followed_file(&first, "other.log"), first);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_event_key_ignores_surrounding_whitespace() {
+        assert_eq!(event_key("line\n"), event_key("line"));
+        assert_ne!(event_key("line"), event_key("other line"));
+        assert_eq!(event_key("line").len(), 64);
+    }
+}
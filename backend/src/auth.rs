@@ -0,0 +1,464 @@
+//! Authentication and role checks for the API
+//!
+//! Callers authenticate with an `Authorization: Bearer <key>` header. The key
+//! is either `server.admin_token` or one of the `[[auth.api_keys]]`, each of
+//! which carries a [`Role`]. The [`authenticate`] middleware resolves the key
+//! once per request and attaches the [`Caller`]; handlers then demand a role
+//! with the [`OperatorAuth`] and [`AdminAuth`] extractors. Reads stay open to
+//! anonymous callers unless `auth.require_key_for_reads` is set.
+//!
+//! Per-user data such as preferences is keyed by [`UserKey`], derived from the
+//! authenticated caller's name, never from an unverified token.
+
+use axum::{
+    extract::{FromRef, FromRequestParts, Request, State},
+    http::{request::Parts, HeaderMap, Method},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+use crate::{
+    approvals::ENGINE_REQUESTER, preferences::preferences_key, ApiError, Config, SharedConfig,
+};
+
+/// What a key may do; each role includes the ones before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Read-only access to status, metrics and history
+    ReadOnly,
+    /// Day-to-day control: enabling trading, changing its config, refreshing wallets
+    Operator,
+    /// Moving funds, exporting keys and raw database access
+    Admin,
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Role::ReadOnly => "read_only",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Who a request or a held operation comes from
+///
+/// Compared whole where identity matters, so an API key named like the
+/// admin token or the engine is still told apart from them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", content = "name", rename_all = "snake_case")]
+pub enum Principal {
+    /// The `server.admin_token`
+    AdminToken,
+    /// One of the `[[auth.api_keys]]`, by name
+    ApiKey(String),
+    /// The trading engine, acting on its own
+    TradingEngine,
+}
+
+impl Principal {
+    /// Name for logs and audit records: the key's name, `admin` or `trading-engine`
+    pub fn name(&self) -> &str {
+        match self {
+            Principal::AdminToken => "admin",
+            Principal::ApiKey(name) => name,
+            Principal::TradingEngine => ENGINE_REQUESTER,
+        }
+    }
+}
+
+/// Authenticated caller, attached to the request by [`authenticate`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caller {
+    pub principal: Principal,
+    pub role: Role,
+}
+
+impl Caller {
+    /// Name of the API key, or `admin` for the admin token
+    pub fn name(&self) -> &str {
+        self.principal.name()
+    }
+}
+
+/// Hex SHA-256 of an API key, as configured in `key_sha256`
+pub fn hash_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Find the caller a bearer token belongs to
+fn resolve(config: &Config, token: &str) -> Option<Caller> {
+    if let Some(admin_token) = config.server.admin_token.as_deref() {
+        if constant_time_eq(token.as_bytes(), admin_token.as_bytes()) {
+            return Some(Caller {
+                principal: Principal::AdminToken,
+                role: Role::Admin,
+            });
+        }
+    }
+
+    let hash = hash_key(token);
+    config
+        .auth
+        .api_keys
+        .iter()
+        .find(|key| constant_time_eq(hash.as_bytes(), key.key_sha256.as_bytes()))
+        .map(|key| Caller {
+            principal: Principal::ApiKey(key.name.clone()),
+            role: key.role,
+        })
+}
+
+/// Resolve the caller of every request, rejecting anonymous reads when
+/// `auth.require_key_for_reads` is set
+///
+/// The liveness probe stays open so orchestrators can call it without a key;
+/// it reveals nothing but the version.
+///
+/// Tokens that match no key are treated as anonymous rather than rejected,
+/// so a revoked key still reaches whatever anonymous callers may.
+pub async fn authenticate(
+    State(config): State<SharedConfig>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let config = config.get();
+    let caller = bearer_token(request.headers()).and_then(|token| resolve(&config, token));
+
+    match &caller {
+        Some(caller) => {
+            if !matches!(*request.method(), Method::GET | Method::HEAD) {
+                tracing::info!(
+                    "{} {} by {} ({})",
+                    request.method(),
+                    request.uri().path(),
+                    caller.name(),
+                    caller.role
+                );
+            }
+            request.extensions_mut().insert(caller.clone());
+        }
+        None if config.auth.require_key_for_reads
+            && *request.method() != Method::OPTIONS
+            && request.uri().path() != "/health/live" =>
+        {
+            return Err(ApiError::Unauthorized("An API key is required".to_string()));
+        }
+        None => {}
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Check that the caller of a request holds `role`
+///
+/// Uses the caller attached by [`authenticate`], or resolves the header here
+/// for routers mounted without the middleware.
+fn require_role(parts: &Parts, config: &SharedConfig, role: Role) -> Result<Caller, ApiError> {
+    let caller = match parts.extensions.get::<Caller>() {
+        Some(caller) => caller.clone(),
+        None => {
+            let token = bearer_token(&parts.headers)
+                .ok_or_else(|| ApiError::Unauthorized("Missing bearer token".to_string()))?;
+            resolve(&config.get(), token)
+                .ok_or_else(|| ApiError::Unauthorized("Invalid API key".to_string()))?
+        }
+    };
+
+    check_role(caller, role)
+}
+
+/// Fail with 403 unless `caller` holds `role`
+fn check_role(caller: Caller, role: Role) -> Result<Caller, ApiError> {
+    if caller.role < role {
+        return Err(ApiError::Forbidden(format!(
+            "Key '{}' has the {} role; this endpoint requires {}",
+            caller.name(),
+            caller.role,
+            role
+        )));
+    }
+    Ok(caller)
+}
+
+/// Extractor that only succeeds for operator and admin callers
+pub struct OperatorAuth(pub Caller);
+
+impl<S> FromRequestParts<S> for OperatorAuth
+where
+    SharedConfig: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        require_role(parts, &SharedConfig::from_ref(state), Role::Operator).map(OperatorAuth)
+    }
+}
+
+/// Extractor that only succeeds for admin callers: the admin token or an
+/// admin API key
+pub struct AdminAuth(pub Caller);
+
+impl<S> FromRequestParts<S> for AdminAuth
+where
+    SharedConfig: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        require_role(parts, &SharedConfig::from_ref(state), Role::Admin).map(AdminAuth)
+    }
+}
+
+/// Storage key identifying the caller, see [`preferences_key`]
+///
+/// Taken from the [`Caller`] attached by [`authenticate`]; anonymous callers
+/// and tokens that match no key share the default record.
+pub struct UserKey(pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for UserKey {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let name = parts.extensions.get::<Caller>().map(Caller::name);
+        Ok(UserKey(preferences_key(name)))
+    }
+}
+
+/// Storage key of an operator or admin caller, for writing per-user data
+///
+/// Rejects anonymous callers with 401 and read-only keys with 403. Relies on
+/// [`authenticate`] having run, so it works in routers that only carry part
+/// of the [`crate::AppState`].
+pub struct OperatorKey(pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for OperatorKey {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let caller = parts
+            .extensions
+            .get::<Caller>()
+            .cloned()
+            .ok_or_else(|| ApiError::Unauthorized("An API key is required".to_string()))?;
+        let caller = check_role(caller, Role::Operator)?;
+        Ok(OperatorKey(preferences_key(Some(caller.name()))))
+    }
+}
+
+/// Extract the bearer token from the Authorization header
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+/// Compare secrets without short-circuiting on the first mismatch
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ApiKeyConfig;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        middleware,
+        routing::{get, post},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_bearer_token_parsing() {
+        let (parts, _) = Request::builder()
+            .header("Authorization", "Bearer secret-token")
+            .body(())
+            .unwrap()
+            .into_parts();
+        assert_eq!(bearer_token(&parts.headers), Some("secret-token"));
+
+        let (parts, _) = Request::builder()
+            .header("Authorization", "Basic dXNlcjpwYXNz")
+            .body(())
+            .unwrap()
+            .into_parts();
+        assert_eq!(bearer_token(&parts.headers), None);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret-longer"));
+    }
+
+    #[test]
+    fn test_resolve_roles() {
+        let mut config = Config::default();
+        config.server.admin_token = Some("admin-secret".to_string());
+        config.auth.api_keys = vec![
+            ApiKeyConfig {
+                name: "grafana".to_string(),
+                key_sha256: hash_key("read-key"),
+                role: Role::ReadOnly,
+            },
+            ApiKeyConfig {
+                name: "ops".to_string(),
+                key_sha256: hash_key("ops-key"),
+                role: Role::Operator,
+            },
+        ];
+
+        let admin = resolve(&config, "admin-secret").unwrap();
+        assert_eq!(admin.role, Role::Admin);
+        let ops = resolve(&config, "ops-key").unwrap();
+        assert_eq!((ops.name(), ops.role), ("ops", Role::Operator));
+        assert_eq!(resolve(&config, "read-key").unwrap().role, Role::ReadOnly);
+        assert_eq!(resolve(&config, "unknown"), None);
+        // The hash itself is not a key
+        assert_eq!(resolve(&config, &hash_key("ops-key")), None);
+
+        assert!(Role::ReadOnly < Role::Operator && Role::Operator < Role::Admin);
+    }
+
+    /// Request parts carrying `caller`, as [`authenticate`] leaves them
+    fn parts(caller: Option<Caller>) -> Parts {
+        let (mut parts, _) = Request::builder()
+            .header("Authorization", "Bearer unverified-token")
+            .body(())
+            .unwrap()
+            .into_parts();
+        if let Some(caller) = caller {
+            parts.extensions.insert(caller);
+        }
+        parts
+    }
+
+    fn caller(name: &str, role: Role) -> Option<Caller> {
+        Some(Caller {
+            principal: Principal::ApiKey(name.to_string()),
+            role,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_user_keys_follow_the_authenticated_caller() {
+        // An unverified token is anonymous, whatever it says
+        let UserKey(key) = UserKey::from_request_parts(&mut parts(None), &())
+            .await
+            .unwrap();
+        assert_eq!(key, crate::preferences::DEFAULT_USER);
+
+        let ops = caller("ops", Role::Operator);
+        let UserKey(key) = UserKey::from_request_parts(&mut parts(ops.clone()), &())
+            .await
+            .unwrap();
+        assert_eq!(key, preferences_key(Some("ops")));
+
+        let OperatorKey(key) = OperatorKey::from_request_parts(&mut parts(ops), &())
+            .await
+            .unwrap();
+        assert_eq!(key, preferences_key(Some("ops")));
+
+        // Writes need an operator key
+        let anonymous = OperatorKey::from_request_parts(&mut parts(None), &()).await;
+        assert!(matches!(anonymous, Err(ApiError::Unauthorized(_))));
+        let reader = caller("grafana", Role::ReadOnly);
+        let reader = OperatorKey::from_request_parts(&mut parts(reader), &()).await;
+        assert!(matches!(reader, Err(ApiError::Forbidden(_))));
+    }
+
+    /// A route per role behind [`authenticate`]
+    fn guarded_routes(config: Config) -> Router {
+        let shared = SharedConfig::new(config);
+        Router::new()
+            .route("/read", get(|| async { "ok" }))
+            .route("/operate", post(|_: OperatorAuth| async { "ok" }))
+            .route("/administer", post(|_: AdminAuth| async { "ok" }))
+            .layer(middleware::from_fn_with_state(shared.clone(), authenticate))
+            .with_state(shared)
+    }
+
+    async fn status(app: &Router, method: Method, path: &str, token: Option<&str>) -> StatusCode {
+        let mut request = Request::builder().method(method).uri(path);
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        let request = request.body(Body::empty()).unwrap();
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_roles_are_enforced_behind_the_middleware() {
+        let mut config = Config::default();
+        config.server.admin_token = Some("admin-secret".to_string());
+        config.auth.api_keys = vec![
+            ApiKeyConfig {
+                name: "grafana".to_string(),
+                key_sha256: hash_key("read-key"),
+                role: Role::ReadOnly,
+            },
+            ApiKeyConfig {
+                name: "ops".to_string(),
+                key_sha256: hash_key("ops-key"),
+                role: Role::Operator,
+            },
+        ];
+        let app = guarded_routes(config);
+
+        // A read-only key reads but can't change anything
+        assert_eq!(
+            status(&app, Method::GET, "/read", Some("read-key")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, Method::POST, "/operate", Some("read-key")).await,
+            StatusCode::FORBIDDEN
+        );
+
+        // An operator key operates but can't reach admin endpoints
+        assert_eq!(
+            status(&app, Method::POST, "/operate", Some("ops-key")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, Method::POST, "/administer", Some("ops-key")).await,
+            StatusCode::FORBIDDEN
+        );
+
+        assert_eq!(
+            status(&app, Method::POST, "/administer", Some("admin-secret")).await,
+            StatusCode::OK
+        );
+        // No key, or one that matches nothing, isn't let in at all
+        assert_eq!(
+            status(&app, Method::POST, "/operate", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&app, Method::POST, "/operate", Some("revoked-key")).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
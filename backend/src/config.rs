@@ -1,8 +1,27 @@
+use anyhow::Context;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use utoipa::ToSchema;
 
-#[derive(Parser, Debug)]
+use crate::alerts::AlertRule;
+use crate::approvals::ApprovalsConfig;
+use crate::auth::Role;
+use crate::idempotency::IdempotencyConfig;
+use crate::notifications::{ChannelConfig, DEFAULT_RETRY};
+use crate::rate_limit::RateLimitConfig;
+use crate::secrets;
+use crate::services::breaker::CircuitBreakerConfig;
+use crate::services::kraken::{KrakenClient, KrakenCredentials};
+use crate::services::retry::{RetryConfig, RetryPolicy};
+use crate::services::{binance::BINANCE_API_URL, BinanceClient, ExchangeClient};
+use crate::telemetry::TracingConfig;
+use crate::tls::TlsConfig;
+use crate::trading::config::{DeploymentParameters, TradingConfig};
+use crate::wallets::limits::SpendLimitsConfig;
+
+#[derive(Parser, Debug, Clone)]
 #[command(name = "eigenix-backend")]
 #[command(about = "Eigenix metrics backend server", long_about = None)]
 pub struct Cli {
@@ -14,10 +33,18 @@ pub struct Cli {
     #[arg(long, default_value = "http://127.0.0.1:8332")]
     pub bitcoin_rpc_url: Option<String>,
 
-    /// Bitcoin RPC cookie file path
-    #[arg(long, default_value = "/mnt/vault/bitcoind-data/.cookie")]
+    /// Bitcoin RPC cookie file path (derived from --bitcoin-data-dir when unset)
+    #[arg(long)]
     pub bitcoin_cookie_path: Option<String>,
 
+    /// Bitcoin Core data directory, used to locate the RPC cookie
+    #[arg(long)]
+    pub bitcoin_data_dir: Option<String>,
+
+    /// Bitcoin network (mainnet, testnet, testnet4, signet, regtest)
+    #[arg(long)]
+    pub bitcoin_network: Option<String>,
+
     /// Monero RPC URL
     #[arg(long, default_value = "http://127.0.0.1:18081/json_rpc")]
     pub monero_rpc_url: Option<String>,
@@ -26,6 +53,14 @@ pub struct Cli {
     #[arg(long, default_value = "http://127.0.0.1:9944")]
     pub asb_rpc_url: Option<String>,
 
+    /// ASB log file, or directory of log files, to read swap events from
+    #[arg(long)]
+    pub asb_log_path: Option<PathBuf>,
+
+    /// Filesystem to record the free space of, as NAME=PATH; repeatable
+    #[arg(long = "disk-path", value_name = "NAME=PATH", value_parser = parse_disk_path)]
+    pub disk_paths: Vec<DiskPath>,
+
     /// Server listen address
     #[arg(long, default_value = "127.0.0.1")]
     pub host: Option<String>,
@@ -45,50 +80,406 @@ pub struct Cli {
     /// SurrealDB database name
     #[arg(long, default_value = "metrics")]
     pub db_database: Option<String>,
+
+    /// Deployment parameters.json used to seed the initial trading config
+    #[arg(long, value_name = "FILE")]
+    pub parameters: Option<PathBuf>,
+
+    /// Secrets file merged over the config file (overrides `secrets_file`)
+    #[arg(long, value_name = "FILE")]
+    pub secrets: Option<PathBuf>,
+
+    /// Encrypt a secrets file with EIGENIX_SECRETS_PASSPHRASE into FILE.enc and exit
+    #[arg(long, value_name = "FILE")]
+    pub encrypt_secrets: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub server: ServerConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+    #[serde(default)]
+    pub approvals: ApprovalsConfig,
+    #[serde(default)]
+    pub limits: SpendLimitsConfig,
     pub database: DatabaseConfig,
     pub bitcoin: BitcoinConfig,
     pub monero: MoneroConfig,
     pub asb: AsbConfig,
+    #[serde(default)]
+    pub mempool: MempoolConfig,
     pub wallets: WalletsConfig,
     pub kraken: KrakenConfig,
+    /// Exchange the trading engine trades on
+    #[serde(default)]
+    pub exchange: ExchangeKind,
+    #[serde(default)]
+    pub binance: BinanceConfig,
+    /// Retry policy of each RPC/HTTP client
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// When to stop calling a client that keeps failing
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Span export to an OpenTelemetry collector
+    #[serde(default)]
+    pub tracing: TracingConfig,
     pub containers: ContainerConfig,
+    #[serde(default)]
+    pub disk: DiskConfig,
+    #[serde(default)]
+    pub collector: CollectorConfig,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub trading_lock: TradingLockConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub portfolio: PortfolioConfig,
+    #[serde(default)]
+    pub backend_metrics: BackendMetricsConfig,
+    /// Deployment parameters.json; its ASB limits seed the initial trading config
+    #[serde(default)]
+    pub parameters_file: Option<PathBuf>,
+    /// TOML file with the same layout as this one, merged over it; may be
+    /// encrypted (see `--encrypt-secrets`)
+    #[serde(default)]
+    pub secrets_file: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// Bearer token for /admin endpoints (loaded from environment variable EIGENIX_ADMIN_TOKEN).
+    /// Admin endpoints are disabled when unset.
+    #[serde(default = "admin_token_from_env", skip_serializing)]
+    pub admin_token: Option<String>,
+    /// Serve HTTPS instead of plain HTTP (`[server.tls]`)
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    /// Seconds to wait on SIGTERM for in-flight requests, the trading engine
+    /// and the collectors to stop before exiting anyway
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+}
+
+impl std::fmt::Debug for ServerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field(
+                "admin_token",
+                &self.admin_token.as_ref().map(|_| "<redacted>"),
+            )
+            .field("tls", &self.tls)
+            .field("shutdown_timeout_secs", &self.shutdown_timeout_secs)
+            .finish()
+    }
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+fn admin_token_from_env() -> Option<String> {
+    std::env::var("EIGENIX_ADMIN_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Keys accepted as bearer tokens, besides `server.admin_token`
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+    /// Reject requests without a valid key on read endpoints too
+    #[serde(default)]
+    pub require_key_for_reads: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    /// Shown in logs of the requests made with the key
+    pub name: String,
+    /// Hex SHA-256 of the key, so the config never holds the key itself
+    pub key_sha256: String,
+    pub role: Role,
+}
+
+/// Names logs, audit records and per-caller state already use for callers
+/// that aren't API keys
+const RESERVED_KEY_NAMES: &[&str] = &["admin", crate::approvals::ENGINE_REQUESTER, "anonymous"];
+
+impl ApiKeyConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.name.trim().is_empty() {
+            anyhow::bail!("API key name must not be empty");
+        }
+        if RESERVED_KEY_NAMES
+            .iter()
+            .any(|reserved| self.name.trim().eq_ignore_ascii_case(reserved))
+        {
+            anyhow::bail!("API key name '{}' is reserved", self.name);
+        }
+        if self.key_sha256.len() != 64
+            || !self
+                .key_sha256
+                .bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        {
+            anyhow::bail!(
+                "API key '{}': key_sha256 must be 64 lowercase hex characters",
+                self.name
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DatabaseConfig {
+    /// Connect to a SurrealDB server or run one inside the backend
+    #[serde(default)]
+    pub mode: DatabaseMode,
+    /// Server address, for `remote` mode
     pub endpoint: String,
+    /// RocksDB data directory, required in `embedded` mode
+    #[serde(default)]
+    pub path: Option<PathBuf>,
     pub namespace: String,
     pub database: String,
+    /// Log and skip history rows that no longer match the schema instead of
+    /// failing the whole query
+    #[serde(default = "default_skip_unparseable_rows")]
+    pub skip_unparseable_rows: bool,
+    /// Which kind of SurrealDB user `username` is, in `remote` mode
+    #[serde(default)]
+    pub auth_level: DatabaseAuthLevel,
+    #[serde(default = "default_db_username")]
+    pub username: String,
+    #[serde(default = "default_db_password", skip_serializing)]
+    pub password: String,
+    /// Queue metric writes and insert them in bulk this often. 0 writes
+    /// every sample right away.
+    #[serde(default = "default_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+    /// Rows a table may queue before it is flushed early
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: usize,
+}
+
+impl std::fmt::Debug for DatabaseConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DatabaseConfig")
+            .field("mode", &self.mode)
+            .field("endpoint", &self.endpoint)
+            .field("path", &self.path)
+            .field("namespace", &self.namespace)
+            .field("database", &self.database)
+            .field("skip_unparseable_rows", &self.skip_unparseable_rows)
+            .field("auth_level", &self.auth_level)
+            .field("username", &self.username)
+            .field("password", &redact(&self.password))
+            .field("flush_interval_secs", &self.flush_interval_secs)
+            .field("max_batch_size", &self.max_batch_size)
+            .finish()
+    }
+}
+
+fn default_skip_unparseable_rows() -> bool {
+    true
+}
+
+fn default_db_username() -> String {
+    "root".to_string()
+}
+
+fn default_db_password() -> String {
+    "root".to_string()
+}
+
+fn default_flush_interval_secs() -> u64 {
+    5
+}
+
+fn default_max_batch_size() -> usize {
+    500
+}
+
+/// Scope of the SurrealDB user the backend signs in as
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseAuthLevel {
+    /// A root user, with access to every namespace
+    #[default]
+    Root,
+    /// A user defined on the configured namespace
+    Namespace,
+    /// A user defined on the configured database
+    Database,
+}
+
+impl std::fmt::Display for DatabaseAuthLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatabaseAuthLevel::Root => write!(f, "root"),
+            DatabaseAuthLevel::Namespace => write!(f, "namespace"),
+            DatabaseAuthLevel::Database => write!(f, "database"),
+        }
+    }
+}
+
+/// Where the metrics database runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseMode {
+    /// A separate SurrealDB server, over WebSocket
+    #[default]
+    Remote,
+    /// SurrealDB on RocksDB inside this process; needs the `embedded-db`
+    /// build feature
+    Embedded,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BitcoinConfig {
     pub rpc_url: String,
+    /// Explicit RPC cookie path (empty to derive it from `data_dir` and `network`)
+    #[serde(default)]
     pub cookie_path: String,
+    /// Bitcoin Core data directory
+    #[serde(default)]
+    pub data_dir: Option<String>,
+    /// Bitcoin network the node runs on
+    #[serde(default = "default_bitcoin_network")]
+    pub network: String,
+}
+
+fn default_bitcoin_network() -> String {
+    "mainnet".to_string()
+}
+
+impl BitcoinConfig {
+    /// Fill in `cookie_path` from the data directory when it isn't set explicitly
+    ///
+    /// An explicit `cookie_path` always wins. Otherwise the cookie is expected at
+    /// `<data_dir>/<network-subdir>/.cookie` and must exist.
+    pub fn resolve_cookie_path(&mut self) -> anyhow::Result<()> {
+        if !self.cookie_path.is_empty() {
+            return Ok(());
+        }
+
+        let data_dir = self
+            .data_dir
+            .as_deref()
+            .context("bitcoin.cookie_path is unset and no bitcoin.data_dir is configured")?;
+
+        let path = derive_cookie_path(Path::new(data_dir), &self.network)?;
+
+        match std::fs::metadata(&path) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                anyhow::bail!("Bitcoin cookie file not found at {}", path.display());
+            }
+            // The cookie is often only readable via sudo, so don't fail on permissions
+            Err(e) => {
+                tracing::warn!(
+                    "Could not verify Bitcoin cookie at {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+
+        tracing::info!(
+            "Resolved Bitcoin cookie path for {}: {}",
+            self.network,
+            path.display()
+        );
+        self.cookie_path = path.to_string_lossy().into_owned();
+
+        Ok(())
+    }
+}
+
+/// Cookie location for a Bitcoin Core data directory and network
+///
+/// Mainnet keeps the cookie at the root of the data directory, other networks
+/// use a per-network subdirectory.
+pub fn derive_cookie_path(data_dir: &Path, network: &str) -> anyhow::Result<PathBuf> {
+    let subdir = match network.to_lowercase().as_str() {
+        "mainnet" | "main" | "bitcoin" => None,
+        "testnet" | "testnet3" | "test" => Some("testnet3"),
+        "testnet4" => Some("testnet4"),
+        "signet" => Some("signet"),
+        "regtest" => Some("regtest"),
+        other => anyhow::bail!("Unknown Bitcoin network: {}", other),
+    };
+
+    let dir = match subdir {
+        Some(subdir) => data_dir.join(subdir),
+        None => data_dir.to_path_buf(),
+    };
+
+    Ok(dir.join(".cookie"))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoneroConfig {
     pub rpc_url: String,
+    /// Monero network the node runs on (mainnet, stagenet, testnet)
+    #[serde(default = "default_monero_network")]
+    pub network: String,
+}
+
+fn default_monero_network() -> String {
+    "mainnet".to_string()
+}
+
+fn default_asb_log_file() -> String {
+    "swap-all.log".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AsbConfig {
     pub rpc_url: String,
+    /// ASB log to read swap events from: a file, or the ASB's log directory,
+    /// in which `log_file` is followed
+    #[serde(default)]
+    pub log_path: Option<PathBuf>,
+    /// File followed when `log_path` is a directory. The ASB writes several
+    /// logs there at once, and only this one is read.
+    #[serde(default = "default_asb_log_file")]
+    pub log_file: String,
+    /// Container whose output is read for swap events instead of a file,
+    /// through `[containers] container_runtime`
+    #[serde(default)]
+    pub log_container: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Esplora-style HTTP API polled for mempool and address metrics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MempoolConfig {
+    /// API root, e.g. `http://127.0.0.1:3002` for electrs' `--http-addr` or
+    /// `https://mempool.space/api`; mempool metrics are off when unset
+    #[serde(default)]
+    pub api_url: Option<String>,
+    /// Addresses whose balance and confirmation status are recorded
+    #[serde(default)]
+    pub addresses: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct WalletsConfig {
     /// Bitcoin wallet name in Bitcoin Core
     pub bitcoin_wallet_name: String,
@@ -97,24 +488,559 @@ pub struct WalletsConfig {
     /// Monero wallet name in monero-wallet-rpc
     pub monero_wallet_name: String,
     /// Monero wallet password (empty string for no password)
+    #[serde(skip_serializing)]
     pub monero_wallet_password: String,
     /// Monero wallet RPC URL (for wallet operations, different from node RPC)
     pub monero_wallet_rpc_url: String,
+    /// Serve the Bitcoin descriptors and Monero keys to admins. Off by default
+    /// since they control every coin in the wallets.
+    #[serde(default)]
+    pub allow_key_export: bool,
+    /// Only addresses `POST /wallets/transfer` may send to; empty allows any
+    /// address on the wallet's network
+    #[serde(default)]
+    pub transfer_allowlist: Vec<String>,
+    /// Monero account internal transfers spend from
+    #[serde(default)]
+    pub monero_account: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl std::fmt::Debug for WalletsConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WalletsConfig")
+            .field("bitcoin_wallet_name", &self.bitcoin_wallet_name)
+            .field("bitcoin_rescan", &self.bitcoin_rescan)
+            .field("monero_wallet_name", &self.monero_wallet_name)
+            .field(
+                "monero_wallet_password",
+                &redact(&self.monero_wallet_password),
+            )
+            .field("monero_wallet_rpc_url", &self.monero_wallet_rpc_url)
+            .field("allow_key_export", &self.allow_key_export)
+            .field("transfer_allowlist", &self.transfer_allowlist)
+            .field("monero_account", &self.monero_account)
+            .finish()
+    }
+}
+
+/// Stand-in for a secret in Debug output, telling only whether it is set
+fn redact(secret: &str) -> &'static str {
+    if secret.is_empty() {
+        "<unset>"
+    } else {
+        "<redacted>"
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct KrakenConfig {
-    /// Kraken API key (loaded from environment variable KRAKEN_API_KEY)
+    /// Kraken trade API key (loaded from environment variable KRAKEN_API_KEY)
     #[serde(skip_serializing)]
     pub api_key: String,
-    /// Kraken API secret (loaded from environment variable KRAKEN_API_SECRET)
+    /// Kraken trade API secret (loaded from environment variable KRAKEN_API_SECRET)
     #[serde(skip_serializing)]
     pub api_secret: String,
+    /// Optional query-only key for balance and status reads (KRAKEN_READ_API_KEY)
+    #[serde(default, skip_serializing)]
+    pub read_api_key: String,
+    /// Secret for the query-only key (KRAKEN_READ_API_SECRET)
+    #[serde(default, skip_serializing)]
+    pub read_api_secret: String,
+    /// File holding the last nonce, shared by every process using these keys
+    /// (KRAKEN_NONCE_FILE). Nonces are only tracked in memory when unset.
+    #[serde(default = "kraken_nonce_file_from_env")]
+    pub nonce_file: Option<PathBuf>,
+}
+
+impl std::fmt::Debug for KrakenConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KrakenConfig")
+            .field("api_key", &redact(&self.api_key))
+            .field("api_secret", &redact(&self.api_secret))
+            .field("read_api_key", &redact(&self.read_api_key))
+            .field("read_api_secret", &redact(&self.read_api_secret))
+            .field("nonce_file", &self.nonce_file)
+            .finish()
+    }
+}
+
+fn kraken_nonce_file_from_env() -> Option<PathBuf> {
+    std::env::var_os("KRAKEN_NONCE_FILE")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+impl KrakenConfig {
+    /// The query-only key, if one is configured
+    pub fn read_key(&self) -> Option<KrakenCredentials> {
+        KrakenCredentials::new(self.read_api_key.clone(), self.read_api_secret.clone())
+    }
+
+    /// The key allowed to trade, deposit and withdraw, if one is configured
+    pub fn trade_key(&self) -> Option<KrakenCredentials> {
+        KrakenCredentials::new(self.api_key.clone(), self.api_secret.clone())
+    }
+
+    /// Client that signs reads with the read key and everything else with the trade key
+    pub fn client(&self) -> KrakenClient {
+        KrakenClient::with_keys(self.read_key(), self.trade_key())
+            .with_nonce_file(self.nonce_file.clone())
+    }
+}
+
+/// Exchange backend for the trading engine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExchangeKind {
+    #[default]
+    Kraken,
+    Binance,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BinanceConfig {
+    /// Binance API key (BINANCE_API_KEY)
+    #[serde(default = "binance_api_key_from_env", skip_serializing)]
+    pub api_key: String,
+    /// Binance API secret (BINANCE_API_SECRET)
+    #[serde(default = "binance_api_secret_from_env", skip_serializing)]
+    pub api_secret: String,
+    #[serde(default = "default_binance_api_url")]
+    pub api_url: String,
+}
+
+impl std::fmt::Debug for BinanceConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BinanceConfig")
+            .field("api_key", &self.api_key)
+            .field("api_secret", &"<redacted>")
+            .field("api_url", &self.api_url)
+            .finish()
+    }
+}
+
+fn binance_api_key_from_env() -> String {
+    std::env::var("BINANCE_API_KEY").unwrap_or_default()
+}
+
+fn binance_api_secret_from_env() -> String {
+    std::env::var("BINANCE_API_SECRET").unwrap_or_default()
+}
+
+fn default_binance_api_url() -> String {
+    BINANCE_API_URL.to_string()
+}
+
+impl Default for BinanceConfig {
+    fn default() -> Self {
+        Self {
+            api_key: binance_api_key_from_env(),
+            api_secret: binance_api_secret_from_env(),
+            api_url: default_binance_api_url(),
+        }
+    }
+}
+
+impl BinanceConfig {
+    pub fn has_key(&self) -> bool {
+        !self.api_key.is_empty() && !self.api_secret.is_empty()
+    }
+
+    pub fn client(&self) -> BinanceClient {
+        BinanceClient::new(self.api_key.clone(), self.api_secret.clone())
+            .with_base_url(self.api_url.clone())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContainerConfig {
     pub names: Vec<String>,
+    /// How container health is probed
+    #[serde(default)]
+    pub container_runtime: ContainerRuntime,
+}
+
+/// Container runtime the health probes talk to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+    /// systemd units, including systemd-nspawn containers
+    Systemd,
+    /// First available of Podman, Docker and systemd
+    #[default]
+    Auto,
+}
+
+/// Filesystems whose usage the `disk` collector records
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiskConfig {
+    /// Data directories, e.g. bitcoind's, monerod's, Electrs' and SurrealDB's;
+    /// the disk collector is off while this is empty
+    #[serde(default)]
+    pub paths: Vec<DiskPath>,
+}
+
+/// A monitored path and the name its samples are recorded under
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskPath {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Parse a `--disk-path` argument
+fn parse_disk_path(arg: &str) -> Result<DiskPath, String> {
+    match arg.split_once('=') {
+        Some((name, path)) if !name.is_empty() && !path.is_empty() => Ok(DiskPath {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+        }),
+        _ => Err(format!("expected NAME=PATH, got '{}'", arg)),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectorConfig {
+    /// Consecutive failed probes before a service is recorded as down
+    #[serde(default = "default_down_threshold")]
+    pub down_threshold: u32,
+    /// Per-source switches; disabled sources are never probed or stored
+    #[serde(default = "default_collector_enabled")]
+    pub bitcoin: bool,
+    #[serde(default = "default_collector_enabled")]
+    pub monero: bool,
+    #[serde(default = "default_collector_enabled")]
+    pub asb: bool,
+    #[serde(default = "default_collector_enabled")]
+    pub electrs: bool,
+    #[serde(default = "default_collector_enabled")]
+    pub containers: bool,
+    /// Only collected when `[mempool] api_url` is set
+    #[serde(default = "default_collector_enabled")]
+    pub mempool: bool,
+    /// BTC/USD, XMR/USD and XMR/BTC from Kraken's public ticker
+    #[serde(default = "default_collector_enabled")]
+    pub prices: bool,
+    /// Only collected when `[disk] paths` lists something
+    #[serde(default = "default_collector_enabled")]
+    pub disk: bool,
+    /// CPU, memory, swap, load and network usage of the backend's host
+    #[serde(default = "default_collector_enabled")]
+    pub system: bool,
+    /// Seconds between collections, per source
+    #[serde(default = "default_collection_interval")]
+    pub bitcoin_interval_secs: u64,
+    #[serde(default = "default_collection_interval")]
+    pub monero_interval_secs: u64,
+    #[serde(default = "default_collection_interval")]
+    pub asb_interval_secs: u64,
+    #[serde(default = "default_collection_interval")]
+    pub electrs_interval_secs: u64,
+    #[serde(default = "default_collection_interval")]
+    pub containers_interval_secs: u64,
+    #[serde(default = "default_collection_interval")]
+    pub mempool_interval_secs: u64,
+    #[serde(default = "default_collection_interval")]
+    pub prices_interval_secs: u64,
+    #[serde(default = "default_collection_interval")]
+    pub disk_interval_secs: u64,
+    #[serde(default = "default_collection_interval")]
+    pub system_interval_secs: u64,
+    /// Upper bound of the random delay added to each collection, so sources
+    /// sharing an interval don't all hit the nodes at once. Capped at half the
+    /// source's interval.
+    #[serde(default = "default_collection_jitter")]
+    pub jitter_secs: u64,
+}
+
+fn default_down_threshold() -> u32 {
+    3
+}
+
+fn default_collector_enabled() -> bool {
+    true
+}
+
+fn default_collection_interval() -> u64 {
+    60
+}
+
+fn default_collection_jitter() -> u64 {
+    5
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AlertsConfig {
+    /// URL that receives each alert as a JSON POST
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Secret for the `X-Eigenix-Signature` HMAC on webhook bodies (loaded from
+    /// environment variable EIGENIX_WEBHOOK_SIGNING_SECRET). Unsigned when unset.
+    #[serde(default = "webhook_signing_secret_from_env", skip_serializing)]
+    pub webhook_signing_secret: Option<String>,
+    /// Alert when more ASB swaps than this fail within the window
+    #[serde(default = "default_failed_swap_alert_threshold")]
+    pub failed_swap_alert_threshold: u64,
+    #[serde(default = "default_failed_swap_window_secs")]
+    pub failed_swap_window_secs: u64,
+    /// Alert when the host clock differs from Kraken's by more than this
+    #[serde(default = "default_clock_skew_threshold_secs")]
+    pub clock_skew_threshold_secs: u64,
+    /// Threshold rules on collected metrics (`[[alerts.rules]]`)
+    #[serde(default)]
+    pub rules: Vec<AlertRule>,
+}
+
+impl std::fmt::Debug for AlertsConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AlertsConfig")
+            .field("webhook_url", &self.webhook_url)
+            .field(
+                "webhook_signing_secret",
+                &self.webhook_signing_secret.as_ref().map(|_| "<redacted>"),
+            )
+            .field(
+                "failed_swap_alert_threshold",
+                &self.failed_swap_alert_threshold,
+            )
+            .field("failed_swap_window_secs", &self.failed_swap_window_secs)
+            .field("clock_skew_threshold_secs", &self.clock_skew_threshold_secs)
+            .field("rules", &self.rules)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    /// Where alerts are delivered, in addition to `alerts.webhook_url`
+    #[serde(default)]
+    pub channels: Vec<ChannelConfig>,
+    /// Attempts per alert and channel, the first one included
+    #[serde(default = "default_notification_attempts")]
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubles with each further retry
+    #[serde(default = "default_notification_backoff_ms")]
+    pub initial_backoff_ms: u64,
+}
+
+fn default_notification_attempts() -> u32 {
+    3
+}
+
+fn default_notification_backoff_ms() -> u64 {
+    1000
+}
+
+impl NotificationsConfig {
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts,
+            initial_backoff_ms: self.initial_backoff_ms,
+            ..DEFAULT_RETRY
+        }
+    }
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            channels: Vec::new(),
+            max_attempts: default_notification_attempts(),
+            initial_backoff_ms: default_notification_backoff_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingLockConfig {
+    /// Take a database lease before trading so only one instance trades
+    #[serde(default = "default_trading_lock_enabled")]
+    pub enabled: bool,
+    /// Lease length; a crashed holder blocks other instances for at most this long
+    #[serde(default = "default_trading_lock_lease_secs")]
+    pub lease_secs: u64,
+    /// Name recorded on the lease (defaults to `<hostname>:<pid>`)
+    #[serde(default)]
+    pub instance_id: Option<String>,
+}
+
+fn default_trading_lock_enabled() -> bool {
+    true
+}
+
+fn default_trading_lock_lease_secs() -> u64 {
+    60
+}
+
+impl Default for TradingLockConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_trading_lock_enabled(),
+            lease_secs: default_trading_lock_lease_secs(),
+            instance_id: None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// Directory encrypted wallet backups are written to. Scheduled backups
+    /// are off when unset.
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+    #[serde(default = "default_backup_interval_secs")]
+    pub interval_secs: u64,
+    /// Backups kept in `dir`; older ones are deleted after each new one
+    #[serde(default = "default_backup_keep")]
+    pub keep: usize,
+    /// Passphrase the backups are encrypted with (loaded from environment
+    /// variable EIGENIX_BACKUP_PASSPHRASE). Nothing is written when unset.
+    #[serde(default = "backup_passphrase_from_env", skip_serializing)]
+    pub passphrase: Option<String>,
+}
+
+impl std::fmt::Debug for BackupConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BackupConfig")
+            .field("dir", &self.dir)
+            .field("interval_secs", &self.interval_secs)
+            .field("keep", &self.keep)
+            .field(
+                "passphrase",
+                &self.passphrase.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+fn default_backup_interval_secs() -> u64 {
+    86400
+}
+
+fn default_backup_keep() -> usize {
+    7
+}
+
+fn backup_passphrase_from_env() -> Option<String> {
+    std::env::var("EIGENIX_BACKUP_PASSPHRASE")
+        .ok()
+        .filter(|passphrase| !passphrase.is_empty())
+}
+
+impl BackupConfig {
+    /// Directory and passphrase, when both are set
+    pub fn target(&self) -> Option<(&Path, &str)> {
+        Some((self.dir.as_deref()?, self.passphrase.as_deref()?))
+    }
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            interval_secs: default_backup_interval_secs(),
+            keep: default_backup_keep(),
+            passphrase: backup_passphrase_from_env(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioConfig {
+    /// Seconds between stored portfolio snapshots; 0 turns them off
+    #[serde(default = "default_portfolio_snapshot_interval_secs")]
+    pub snapshot_interval_secs: u64,
+}
+
+fn default_portfolio_snapshot_interval_secs() -> u64 {
+    300
+}
+
+impl Default for PortfolioConfig {
+    fn default() -> Self {
+        Self {
+            snapshot_interval_secs: default_portfolio_snapshot_interval_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendMetricsConfig {
+    /// Seconds between stored snapshots of the backend's own metrics; 0
+    /// turns them off
+    #[serde(default = "default_backend_metrics_snapshot_interval_secs")]
+    pub snapshot_interval_secs: u64,
+}
+
+fn default_backend_metrics_snapshot_interval_secs() -> u64 {
+    60
+}
+
+impl Default for BackendMetricsConfig {
+    fn default() -> Self {
+        Self {
+            snapshot_interval_secs: default_backend_metrics_snapshot_interval_secs(),
+        }
+    }
+}
+
+fn webhook_signing_secret_from_env() -> Option<String> {
+    std::env::var("EIGENIX_WEBHOOK_SIGNING_SECRET")
+        .ok()
+        .filter(|secret| !secret.is_empty())
+}
+
+fn default_failed_swap_alert_threshold() -> u64 {
+    3
+}
+
+fn default_failed_swap_window_secs() -> u64 {
+    3600
+}
+
+fn default_clock_skew_threshold_secs() -> u64 {
+    5
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            webhook_signing_secret: webhook_signing_secret_from_env(),
+            failed_swap_alert_threshold: default_failed_swap_alert_threshold(),
+            failed_swap_window_secs: default_failed_swap_window_secs(),
+            clock_skew_threshold_secs: default_clock_skew_threshold_secs(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl Default for CollectorConfig {
+    fn default() -> Self {
+        Self {
+            down_threshold: default_down_threshold(),
+            bitcoin: default_collector_enabled(),
+            monero: default_collector_enabled(),
+            asb: default_collector_enabled(),
+            electrs: default_collector_enabled(),
+            containers: default_collector_enabled(),
+            mempool: default_collector_enabled(),
+            prices: default_collector_enabled(),
+            disk: default_collector_enabled(),
+            system: default_collector_enabled(),
+            bitcoin_interval_secs: default_collection_interval(),
+            monero_interval_secs: default_collection_interval(),
+            asb_interval_secs: default_collection_interval(),
+            electrs_interval_secs: default_collection_interval(),
+            containers_interval_secs: default_collection_interval(),
+            mempool_interval_secs: default_collection_interval(),
+            prices_interval_secs: default_collection_interval(),
+            disk_interval_secs: default_collection_interval(),
+            system_interval_secs: default_collection_interval(),
+            jitter_secs: default_collection_jitter(),
+        }
+    }
 }
 
 impl Default for Config {
@@ -123,33 +1049,67 @@ impl Default for Config {
             server: ServerConfig {
                 host: "0.0.0.0".to_string(),
                 port: env!("API_PORT").parse().unwrap(),
+                admin_token: admin_token_from_env(),
+                tls: None,
+                shutdown_timeout_secs: default_shutdown_timeout_secs(),
             },
+            auth: AuthConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            idempotency: IdempotencyConfig::default(),
+            approvals: ApprovalsConfig::default(),
+            limits: SpendLimitsConfig::default(),
             database: DatabaseConfig {
+                mode: DatabaseMode::Remote,
                 endpoint: "127.0.0.1:8001".to_string(),
+                path: None,
                 namespace: "eigenix".to_string(),
                 database: "metrics".to_string(),
+                skip_unparseable_rows: default_skip_unparseable_rows(),
+                auth_level: DatabaseAuthLevel::Root,
+                username: default_db_username(),
+                password: default_db_password(),
+                flush_interval_secs: default_flush_interval_secs(),
+                max_batch_size: default_max_batch_size(),
             },
             bitcoin: BitcoinConfig {
                 rpc_url: "http://127.0.0.1:8332".to_string(),
                 cookie_path: "/mnt/vault/bitcoind-data/.cookie".to_string(),
+                data_dir: None,
+                network: default_bitcoin_network(),
             },
             monero: MoneroConfig {
                 rpc_url: "http://127.0.0.1:18081/json_rpc".to_string(),
+                network: default_monero_network(),
             },
             asb: AsbConfig {
                 rpc_url: "http://127.0.0.1:9944".to_string(),
+                log_path: None,
+                log_file: default_asb_log_file(),
+                log_container: None,
             },
+            mempool: MempoolConfig::default(),
             wallets: WalletsConfig {
                 bitcoin_wallet_name: "eigenix".to_string(),
                 bitcoin_rescan: false,
                 monero_wallet_name: "eigenix".to_string(),
                 monero_wallet_password: "".to_string(),
                 monero_wallet_rpc_url: "http://127.0.0.1:18082/json_rpc".to_string(),
+                allow_key_export: false,
+                transfer_allowlist: Vec::new(),
+                monero_account: 0,
             },
             kraken: KrakenConfig {
                 api_key: std::env::var("KRAKEN_API_KEY").unwrap_or_default(),
                 api_secret: std::env::var("KRAKEN_API_SECRET").unwrap_or_default(),
+                read_api_key: std::env::var("KRAKEN_READ_API_KEY").unwrap_or_default(),
+                read_api_secret: std::env::var("KRAKEN_READ_API_SECRET").unwrap_or_default(),
+                nonce_file: kraken_nonce_file_from_env(),
             },
+            exchange: ExchangeKind::default(),
+            binance: BinanceConfig::default(),
+            retry: RetryConfig::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            tracing: TracingConfig::default(),
             containers: ContainerConfig {
                 names: vec![
                     "bitcoind".to_string(),
@@ -158,12 +1118,94 @@ impl Default for Config {
                     "asb".to_string(),
                     "asb-controller".to_string(),
                 ],
+                container_runtime: ContainerRuntime::default(),
             },
+            disk: DiskConfig::default(),
+            collector: CollectorConfig::default(),
+            alerts: AlertsConfig::default(),
+            notifications: NotificationsConfig::default(),
+            trading_lock: TradingLockConfig::default(),
+            backup: BackupConfig::default(),
+            portfolio: PortfolioConfig::default(),
+            backend_metrics: BackendMetricsConfig::default(),
+            parameters_file: None,
+            secrets_file: None,
         }
     }
 }
 
+/// What a deployment runs, as reported by `/health`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct EnabledFeatures {
+    /// The ASB is monitored
+    pub asb: bool,
+    /// A Kraken trade key is configured, so the engine can place orders
+    pub trading: bool,
+    /// A mempool explorer container is among the monitored containers
+    pub mempool: bool,
+    pub networks: EnabledNetworks,
+    pub collectors: EnabledCollectors,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct EnabledNetworks {
+    pub bitcoin: String,
+    pub monero: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct EnabledCollectors {
+    pub bitcoin: bool,
+    pub monero: bool,
+    pub asb: bool,
+    pub electrs: bool,
+    pub containers: bool,
+    pub mempool: bool,
+    pub prices: bool,
+    pub disk: bool,
+    pub system: bool,
+}
+
 impl Config {
+    /// Summarise which features this configuration turns on
+    pub fn enabled_features(&self) -> EnabledFeatures {
+        EnabledFeatures {
+            asb: self.collector.asb,
+            trading: match self.exchange {
+                ExchangeKind::Kraken => self.kraken.trade_key().is_some(),
+                ExchangeKind::Binance => self.binance.has_key(),
+            },
+            mempool: self
+                .containers
+                .names
+                .iter()
+                .any(|name| name.starts_with("mempool")),
+            networks: EnabledNetworks {
+                bitcoin: self.bitcoin.network.to_lowercase(),
+                monero: self.monero.network.to_lowercase(),
+            },
+            collectors: EnabledCollectors {
+                bitcoin: self.collector.bitcoin,
+                monero: self.collector.monero,
+                asb: self.collector.asb,
+                electrs: self.collector.electrs,
+                containers: self.collector.containers,
+                mempool: self.collector.mempool && self.mempool.api_url.is_some(),
+                prices: self.collector.prices,
+                disk: self.collector.disk && !self.disk.paths.is_empty(),
+                system: self.collector.system,
+            },
+        }
+    }
+
+    /// Client for the exchange selected in `exchange`
+    pub fn exchange_client(&self) -> ExchangeClient {
+        match self.exchange {
+            ExchangeKind::Kraken => self.kraken.client().into(),
+            ExchangeKind::Binance => self.binance.client().into(),
+        }
+    }
+
     /// Convert to WalletConfig for wallet initialization
     pub fn to_wallet_config(&self) -> crate::wallets::WalletConfig {
         crate::wallets::WalletConfig {
@@ -174,16 +1216,263 @@ impl Config {
             monero_rpc_url: self.wallets.monero_wallet_rpc_url.clone(),
             monero_wallet_name: self.wallets.monero_wallet_name.clone(),
             monero_wallet_password: self.wallets.monero_wallet_password.clone(),
+            monero_account: self.wallets.monero_account,
             asb_rpc_url: self.asb.rpc_url.clone(),
         }
     }
 
+    /// Trading configuration the engine starts with
+    ///
+    /// Precedence, lowest first: built-in `TradingConfig` defaults, then the
+    /// ASB limits from the deployment parameters file when one is configured
+    /// and present, then runtime updates through `PUT /trading/config` (which
+    /// last until the next restart).
+    pub fn initial_trading_config(&self) -> anyhow::Result<TradingConfig> {
+        let mut trading = TradingConfig::default();
+
+        let Some(path) = &self.parameters_file else {
+            return Ok(trading);
+        };
+        if !path.exists() {
+            tracing::warn!(
+                "Parameters file {} not found, using default trading config",
+                path.display()
+            );
+            return Ok(trading);
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read parameters file {}", path.display()))?;
+        let parameters: DeploymentParameters = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse parameters file {}", path.display()))?;
+
+        parameters.asb.apply_to(&mut trading);
+        trading.validate().map_err(|e| {
+            anyhow::anyhow!(
+                "Trading config seeded from {} is invalid: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        tracing::info!(
+            "Seeded trading config from {}: {}-{} BTC per rebalance, {:.2}% slippage",
+            path.display(),
+            trading.min_btc_per_rebalance,
+            trading.max_btc_per_rebalance,
+            trading.slippage_tolerance_percent
+        );
+
+        Ok(trading)
+    }
+
+    /// Load configuration from a TOML file without any CLI overrides
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let config_str = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let mut config: Config = toml::from_str(&config_str)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+        config.bitcoin.resolve_cookie_path()?;
+        Ok(config)
+    }
+
+    /// Check that addresses and URLs in the configuration are well formed
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.server
+            .host
+            .parse::<std::net::IpAddr>()
+            .with_context(|| format!("server.host '{}' is not an IP address", self.server.host))?;
+
+        match self.database.mode {
+            DatabaseMode::Remote if self.database.endpoint.is_empty() => {
+                anyhow::bail!("database.endpoint must not be empty");
+            }
+            DatabaseMode::Remote if self.database.username.is_empty() => {
+                anyhow::bail!("database.username must not be empty");
+            }
+            _ if self.database.max_batch_size == 0 => {
+                anyhow::bail!("database.max_batch_size must be at least 1");
+            }
+            DatabaseMode::Embedded if !cfg!(feature = "embedded-db") => {
+                anyhow::bail!(
+                    "database.mode = \"embedded\" needs a backend built with the embedded-db feature"
+                );
+            }
+            DatabaseMode::Embedded if self.database.path.is_none() => {
+                anyhow::bail!("database.path must be set in embedded mode");
+            }
+            _ => {}
+        }
+
+        let urls = [
+            ("bitcoin.rpc_url", &self.bitcoin.rpc_url),
+            ("monero.rpc_url", &self.monero.rpc_url),
+            ("asb.rpc_url", &self.asb.rpc_url),
+            (
+                "wallets.monero_wallet_rpc_url",
+                &self.wallets.monero_wallet_rpc_url,
+            ),
+        ];
+        for (name, url) in urls {
+            reqwest::Url::parse(url)
+                .with_context(|| format!("{} '{}' is not a valid URL", name, url))?;
+        }
+        if let Some(url) = &self.mempool.api_url {
+            reqwest::Url::parse(url)
+                .with_context(|| format!("mempool.api_url '{}' is not a valid URL", url))?;
+        }
+
+        if self.bitcoin.cookie_path.is_empty() {
+            anyhow::bail!("bitcoin.cookie_path could not be resolved");
+        }
+
+        if self.asb.log_path.is_some() && self.asb.log_container.is_some() {
+            anyhow::bail!("Set only one of asb.log_path and asb.log_container");
+        }
+        if self.asb.log_file.is_empty() || self.asb.log_file.contains(['/', '\\']) {
+            anyhow::bail!(
+                "asb.log_file must be a file name, got '{}'",
+                self.asb.log_file
+            );
+        }
+
+        for (index, disk) in self.disk.paths.iter().enumerate() {
+            if disk.name.is_empty() || !disk.path.is_absolute() {
+                anyhow::bail!(
+                    "disk.paths[{}] needs a name and an absolute path, got '{}' at '{}'",
+                    index,
+                    disk.name,
+                    disk.path.display()
+                );
+            }
+            if self.disk.paths[..index]
+                .iter()
+                .any(|other| other.name == disk.name)
+            {
+                anyhow::bail!("Duplicate disk path name: {}", disk.name);
+            }
+        }
+
+        if !matches!(
+            self.monero.network.to_lowercase().as_str(),
+            "mainnet" | "stagenet" | "testnet"
+        ) {
+            anyhow::bail!("Unknown Monero network: {}", self.monero.network);
+        }
+
+        if self.collector.down_threshold == 0 {
+            anyhow::bail!("collector.down_threshold must be at least 1");
+        }
+        let intervals = [
+            ("bitcoin", self.collector.bitcoin_interval_secs),
+            ("monero", self.collector.monero_interval_secs),
+            ("asb", self.collector.asb_interval_secs),
+            ("electrs", self.collector.electrs_interval_secs),
+            ("containers", self.collector.containers_interval_secs),
+            ("mempool", self.collector.mempool_interval_secs),
+            ("prices", self.collector.prices_interval_secs),
+            ("disk", self.collector.disk_interval_secs),
+            ("system", self.collector.system_interval_secs),
+        ];
+        for (source, secs) in intervals {
+            if secs == 0 {
+                anyhow::bail!("collector.{}_interval_secs must be at least 1", source);
+            }
+        }
+
+        for (index, key) in self.auth.api_keys.iter().enumerate() {
+            key.validate()?;
+            let earlier = &self.auth.api_keys[..index];
+            if earlier
+                .iter()
+                .any(|other| other.name.trim().eq_ignore_ascii_case(key.name.trim()))
+            {
+                anyhow::bail!("Duplicate API key name: {}", key.name);
+            }
+            // Only the first of two entries for one key would ever match
+            if earlier
+                .iter()
+                .any(|other| other.key_sha256 == key.key_sha256)
+            {
+                anyhow::bail!("API key '{}' has the same key as another entry", key.name);
+            }
+        }
+
+        self.approvals.validate()?;
+        self.limits.validate()?;
+        self.retry.validate()?;
+        self.circuit_breaker.validate()?;
+        self.tracing.validate()?;
+
+        if self.trading_lock.lease_secs < 3 {
+            anyhow::bail!("trading_lock.lease_secs must be at least 3");
+        }
+
+        if self.alerts.failed_swap_window_secs == 0 {
+            anyhow::bail!("alerts.failed_swap_window_secs must be at least 1");
+        }
+        if let Some(url) = &self.alerts.webhook_url {
+            reqwest::Url::parse(url)
+                .with_context(|| format!("alerts.webhook_url '{}' is not a valid URL", url))?;
+        }
+        for (index, rule) in self.alerts.rules.iter().enumerate() {
+            rule.validate().map_err(anyhow::Error::msg)?;
+            if self.alerts.rules[..index]
+                .iter()
+                .any(|other| other.name == rule.name)
+            {
+                anyhow::bail!("Duplicate alert rule name: {}", rule.name);
+            }
+        }
+
+        if self.notifications.max_attempts == 0 {
+            anyhow::bail!("notifications.max_attempts must be at least 1");
+        }
+        for (index, channel) in self.notifications.channels.iter().enumerate() {
+            channel
+                .validate()
+                .with_context(|| format!("notifications.channels[{}] is invalid", index))?;
+        }
+
+        if self.backup.dir.is_some() {
+            if self.backup.interval_secs == 0 {
+                anyhow::bail!("backup.interval_secs must be at least 1");
+            }
+            if self.backup.keep == 0 {
+                anyhow::bail!("backup.keep must be at least 1");
+            }
+        }
+
+        Ok(())
+    }
+
     /// Load configuration from CLI arguments and optional config file
     pub fn load(cli: Cli) -> anyhow::Result<Self> {
         let mut config = if let Some(config_path) = &cli.config {
             // Load from config file
             let config_str = std::fs::read_to_string(config_path)?;
-            toml::from_str(&config_str)?
+            let mut table: toml::Table = toml::from_str(&config_str)?;
+
+            let secrets_file = cli.secrets.clone().or_else(|| {
+                table
+                    .get("secrets_file")
+                    .and_then(|path| path.as_str())
+                    .map(PathBuf::from)
+            });
+            if let Some(path) = &secrets_file {
+                let passphrase = std::env::var(secrets::PASSPHRASE_ENV).ok();
+                secrets::merge(&mut table, secrets::load_file(path, passphrase.as_deref())?);
+                table.insert(
+                    "secrets_file".to_string(),
+                    toml::Value::String(path.to_string_lossy().into_owned()),
+                );
+            }
+
+            let mut value = toml::Value::Table(table);
+            secrets::expand_env(&mut value)?;
+            value.try_into()?
+        } else if cli.secrets.is_some() {
+            anyhow::bail!("--secrets needs a config file to merge into (--config)");
         } else {
             // Use defaults
             Config::default()
@@ -208,6 +1497,16 @@ impl Config {
         if let Some(url) = cli.bitcoin_rpc_url {
             config.bitcoin.rpc_url = url;
         }
+        if let Some(data_dir) = cli.bitcoin_data_dir {
+            config.bitcoin.data_dir = Some(data_dir);
+            // A data dir on the command line takes precedence over a config file cookie path
+            if cli.bitcoin_cookie_path.is_none() {
+                config.bitcoin.cookie_path.clear();
+            }
+        }
+        if let Some(network) = cli.bitcoin_network {
+            config.bitcoin.network = network;
+        }
         if let Some(path) = cli.bitcoin_cookie_path {
             config.bitcoin.cookie_path = path;
         }
@@ -217,7 +1516,470 @@ impl Config {
         if let Some(url) = cli.asb_rpc_url {
             config.asb.rpc_url = url;
         }
+        if let Some(path) = cli.asb_log_path {
+            config.asb.log_path = Some(path);
+        }
+        // Command line paths replace config file paths of the same name
+        for disk in cli.disk_paths {
+            config.disk.paths.retain(|other| other.name != disk.name);
+            config.disk.paths.push(disk);
+        }
+        if let Some(path) = cli.parameters {
+            config.parameters_file = Some(path);
+        }
+
+        config.bitcoin.resolve_cookie_path()?;
 
         Ok(config)
     }
 }
+
+/// Running configuration, swapped whole when the config file is reloaded
+///
+/// Readers take a snapshot with [`SharedConfig::get`] and keep using it for
+/// the rest of their request or collection cycle, so they never see half of
+/// an old config and half of a new one.
+#[derive(Debug, Clone)]
+pub struct SharedConfig {
+    current: Arc<RwLock<Arc<Config>>>,
+}
+
+impl SharedConfig {
+    pub fn new(config: Config) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(config))),
+        }
+    }
+
+    /// Snapshot of the current configuration
+    pub fn get(&self) -> Arc<Config> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Replace the configuration
+    pub fn replace(&self, config: Config) {
+        *self.current.write().unwrap() = Arc::new(config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(Config::default().validate().is_ok());
+
+        let mut config = Config::default();
+        config.asb.rpc_url = "not a url".to_string();
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.server.host = "localhost:3000".to_string();
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.mempool.api_url = Some("mempool.space/api".to_string());
+        assert!(config.validate().is_err());
+        config.mempool.api_url = Some("https://mempool.space/api".to_string());
+        assert!(config.validate().is_ok());
+
+        let mut config = Config::default();
+        config.collector.containers_interval_secs = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.asb.log_file = "../swap-all.log".to_string();
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.backup.dir = Some(PathBuf::from("/var/lib/eigenix/backups"));
+        config.backup.keep = 0;
+        assert!(config.validate().is_err());
+
+        // Embedded mode needs a data directory, and the build feature
+        let mut config = Config::default();
+        config.database.mode = DatabaseMode::Embedded;
+        assert!(config.validate().is_err());
+        config.database.path = Some(PathBuf::from("/var/lib/eigenix/surrealdb"));
+        assert_eq!(config.validate().is_ok(), cfg!(feature = "embedded-db"));
+
+        let mut config = Config::default();
+        config.database.max_batch_size = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.auth.api_keys.push(ApiKeyConfig {
+            name: "grafana".to_string(),
+            key_sha256: "not a hash".to_string(),
+            role: Role::ReadOnly,
+        });
+        assert!(config.validate().is_err());
+        config.auth.api_keys[0].key_sha256 = crate::auth::hash_key("read-key");
+        assert!(config.validate().is_ok());
+        config.auth.api_keys.push(config.auth.api_keys[0].clone());
+        assert!(config.validate().is_err());
+
+        // Names are unique whatever the case, and keys are unique too
+        config.auth.api_keys[1].key_sha256 = crate::auth::hash_key("other-key");
+        config.auth.api_keys[1].name = "Grafana".to_string();
+        assert!(config.validate().is_err());
+        config.auth.api_keys[1].name = "dashboard".to_string();
+        assert!(config.validate().is_ok());
+        config.auth.api_keys[1].key_sha256 = crate::auth::hash_key("read-key");
+        assert!(config.validate().is_err());
+
+        // Names that stand for the admin token, the engine or anonymous callers
+        for reserved in ["admin", "Admin ", "trading-engine", "anonymous"] {
+            let mut config = Config::default();
+            config.auth.api_keys.push(ApiKeyConfig {
+                name: reserved.to_string(),
+                key_sha256: crate::auth::hash_key("key"),
+                role: Role::Operator,
+            });
+            assert!(config.validate().is_err(), "{} accepted", reserved);
+        }
+
+        let mut config = Config::default();
+        config.approvals.btc_threshold = -1.0;
+        assert!(config.validate().is_err());
+        config.approvals.btc_threshold = 0.5;
+        assert!(config.validate().is_ok());
+
+        let mut config = Config::default();
+        config.limits.xmr_per_day = f64::NAN;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.retry.bitcoind.max_attempts = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.circuit_breaker.cooldown_secs = 0;
+        assert!(config.validate().is_err());
+        config.circuit_breaker.failure_threshold = 0;
+        assert!(config.validate().is_ok());
+
+        let mut config = Config::default();
+        config.disk.paths.push(DiskPath {
+            name: "bitcoind".to_string(),
+            path: PathBuf::from("bitcoind-data"),
+        });
+        assert!(config.validate().is_err());
+        config.disk.paths[0].path = PathBuf::from("/mnt/vault/bitcoind-data");
+        assert!(config.validate().is_ok());
+        config.disk.paths.push(config.disk.paths[0].clone());
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.tracing.otlp_endpoint = Some("tempo:4318".to_string());
+        config.tracing.sample_ratio = -0.1;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_derive_cookie_path_per_network() {
+        let data_dir = Path::new("/var/lib/bitcoind");
+
+        let cases = [
+            ("mainnet", "/var/lib/bitcoind/.cookie"),
+            ("main", "/var/lib/bitcoind/.cookie"),
+            ("testnet", "/var/lib/bitcoind/testnet3/.cookie"),
+            ("testnet4", "/var/lib/bitcoind/testnet4/.cookie"),
+            ("signet", "/var/lib/bitcoind/signet/.cookie"),
+            ("regtest", "/var/lib/bitcoind/regtest/.cookie"),
+            ("Regtest", "/var/lib/bitcoind/regtest/.cookie"),
+        ];
+
+        for (network, expected) in cases {
+            assert_eq!(
+                derive_cookie_path(data_dir, network).unwrap(),
+                PathBuf::from(expected),
+                "network {}",
+                network
+            );
+        }
+
+        assert!(derive_cookie_path(data_dir, "litecoin").is_err());
+    }
+
+    fn bitcoin_config(cookie_path: &str, data_dir: Option<&Path>, network: &str) -> BitcoinConfig {
+        BitcoinConfig {
+            rpc_url: "http://127.0.0.1:8332".to_string(),
+            cookie_path: cookie_path.to_string(),
+            data_dir: data_dir.map(|d| d.to_string_lossy().into_owned()),
+            network: network.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_cookie_path() {
+        let data_dir = std::env::temp_dir().join(format!("eigenix-cookie-{}", std::process::id()));
+        std::fs::create_dir_all(data_dir.join("signet")).unwrap();
+        std::fs::write(data_dir.join("signet/.cookie"), "__cookie__:secret").unwrap();
+
+        // Derived from data dir when no explicit path is set
+        let mut config = bitcoin_config("", Some(&data_dir), "signet");
+        config.resolve_cookie_path().unwrap();
+        assert_eq!(
+            PathBuf::from(&config.cookie_path),
+            data_dir.join("signet/.cookie")
+        );
+
+        // Explicit path takes precedence and is left untouched
+        let mut config = bitcoin_config("/explicit/.cookie", Some(&data_dir), "signet");
+        config.resolve_cookie_path().unwrap();
+        assert_eq!(config.cookie_path, "/explicit/.cookie");
+
+        // Missing cookie file for the derived path is an error
+        let mut config = bitcoin_config("", Some(&data_dir), "regtest");
+        assert!(config.resolve_cookie_path().is_err());
+
+        // Nothing to derive from
+        let mut config = bitcoin_config("", None, "mainnet");
+        assert!(config.resolve_cookie_path().is_err());
+
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[test]
+    fn test_enabled_features_follow_config() {
+        let defaults = Config::default().enabled_features();
+        assert!(defaults.asb);
+        assert!(!defaults.mempool);
+        assert_eq!(defaults.networks.monero, "mainnet");
+
+        let mut config: Config = toml::from_str(
+            r#"
+            [server]
+            host = "127.0.0.1"
+            port = 3000
+
+            [database]
+            endpoint = "127.0.0.1:8001"
+            namespace = "eigenix"
+            database = "metrics"
+
+            [bitcoin]
+            rpc_url = "http://127.0.0.1:38332"
+            cookie_path = "/var/lib/bitcoind/signet/.cookie"
+            network = "Signet"
+
+            [monero]
+            rpc_url = "http://127.0.0.1:38081/json_rpc"
+            network = "stagenet"
+
+            [asb]
+            rpc_url = "http://127.0.0.1:9944"
+
+            [wallets]
+            bitcoin_wallet_name = "eigenix"
+            bitcoin_rescan = false
+            monero_wallet_name = "eigenix"
+            monero_wallet_password = ""
+            monero_wallet_rpc_url = "http://127.0.0.1:18082/json_rpc"
+
+            [kraken]
+            api_key = ""
+            api_secret = ""
+
+            [containers]
+            names = ["bitcoind", "monerod", "mempool-web", "mempool-api"]
+
+            [collector]
+            asb = false
+            electrs = false
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+
+        let features = config.enabled_features();
+        assert_eq!(
+            features,
+            EnabledFeatures {
+                asb: false,
+                trading: false,
+                mempool: true,
+                networks: EnabledNetworks {
+                    bitcoin: "signet".to_string(),
+                    monero: "stagenet".to_string(),
+                },
+                collectors: EnabledCollectors {
+                    bitcoin: true,
+                    monero: true,
+                    asb: false,
+                    electrs: false,
+                    containers: true,
+                    // Enabled, but there is no API to poll
+                    mempool: false,
+                    prices: true,
+                    // Enabled, but no paths are listed
+                    disk: false,
+                    system: true,
+                },
+            }
+        );
+
+        config.mempool.api_url = Some("http://127.0.0.1:3002".to_string());
+        assert!(config.enabled_features().collectors.mempool);
+
+        config.kraken.api_key = "key".to_string();
+        config.kraken.api_secret = "c2VjcmV0".to_string();
+        assert!(config.enabled_features().trading);
+
+        // Trading follows the selected exchange's key
+        config.exchange = ExchangeKind::Binance;
+        config.binance.api_key.clear();
+        config.binance.api_secret.clear();
+        assert!(!config.enabled_features().trading);
+        config.binance.api_key = "key".to_string();
+        config.binance.api_secret = "secret".to_string();
+        assert!(config.enabled_features().trading);
+        assert!(matches!(
+            config.exchange_client(),
+            ExchangeClient::Binance(_)
+        ));
+
+        config.monero.network = "mainnet-beta".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_disk_paths_from_cli() {
+        let cli = Cli::parse_from([
+            "eigenix-backend",
+            "--bitcoin-cookie-path",
+            "/tmp/.cookie",
+            "--disk-path",
+            "bitcoind=/mnt/vault/bitcoind-data",
+            "--disk-path",
+            "surrealdb=/mnt/vault/surrealdb",
+        ]);
+        let config = Config::load(cli).unwrap();
+        assert_eq!(config.disk.paths.len(), 2);
+        assert_eq!(config.disk.paths[0].name, "bitcoind");
+        assert_eq!(
+            config.disk.paths[1].path,
+            PathBuf::from("/mnt/vault/surrealdb")
+        );
+        assert!(config.enabled_features().collectors.disk);
+
+        assert!(Cli::try_parse_from(["eigenix-backend", "--disk-path", "/mnt/vault"]).is_err());
+        assert!(Cli::try_parse_from(["eigenix-backend", "--disk-path", "=/mnt/vault"]).is_err());
+    }
+
+    #[test]
+    fn test_parameters_file_seeds_trading_config() {
+        let dir = std::env::temp_dir().join(format!("eigenix-params-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("parameters.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "deployment": { "name": "eigenix", "environment": "production" },
+                "asb": {
+                    "enable": true,
+                    "minBuyBtc": 0.002,
+                    "maxBuyBtc": 0.05,
+                    "askSpread": 0.03
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let config = Config {
+            parameters_file: Some(path),
+            ..Config::default()
+        };
+        let trading = config.initial_trading_config().unwrap();
+        assert_eq!(trading.max_btc_per_rebalance, 0.05);
+        assert_eq!(trading.min_btc_per_rebalance, 0.002);
+        assert!((trading.slippage_tolerance_percent - 1.5).abs() < 1e-9);
+        // Settings not covered by the parameters file keep their defaults
+        assert_eq!(
+            trading.monero_target_balance,
+            TradingConfig::default().monero_target_balance
+        );
+
+        // Missing file falls back to defaults
+        let config = Config {
+            parameters_file: Some(dir.join("missing.json")),
+            ..Config::default()
+        };
+        let trading = config.initial_trading_config().unwrap();
+        assert_eq!(
+            trading.max_btc_per_rebalance,
+            TradingConfig::default().max_btc_per_rebalance
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_with_secrets_file() {
+        let dir =
+            std::env::temp_dir().join(format!("eigenix-config-secrets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut base: toml::Table =
+            toml::from_str(&toml::to_string(&Config::default()).unwrap()).unwrap();
+        base["monero"]["rpc_url"] =
+            toml::Value::String("http://${EIGENIX_TEST_UNSET_HOST}:18081".to_string());
+        let config_path = dir.join("config.toml");
+        let secrets_path = dir.join("secrets.toml");
+        std::fs::write(
+            &secrets_path,
+            r#"
+            [kraken]
+            api_key = "${CARGO_MANIFEST_DIR}"
+            api_secret = "s3cret"
+
+            [wallets]
+            monero_wallet_password = "hunter2"
+
+            [database]
+            password = "db-pass"
+            "#,
+        )
+        .unwrap();
+        base["database"]["auth_level"] = toml::Value::String("database".to_string());
+        base["database"]["username"] = toml::Value::String("eigenix".to_string());
+
+        let cli = |args: &[&str]| {
+            let mut argv = vec!["eigenix-backend", "--bitcoin-cookie-path", "/tmp/.cookie"];
+            argv.extend_from_slice(args);
+            Cli::parse_from(argv)
+        };
+        let config_arg = config_path.to_str().unwrap();
+        let secrets_arg = secrets_path.to_str().unwrap();
+
+        // A reference to an unset variable fails the load
+        std::fs::write(&config_path, toml::to_string(&base).unwrap()).unwrap();
+        assert!(Config::load(cli(&["--config", config_arg, "--secrets", secrets_arg])).is_err());
+
+        base["monero"]["rpc_url"] =
+            toml::Value::String("http://127.0.0.1:18081/json_rpc".to_string());
+        std::fs::write(&config_path, toml::to_string(&base).unwrap()).unwrap();
+        let config =
+            Config::load(cli(&["--config", config_arg, "--secrets", secrets_arg])).unwrap();
+        assert_eq!(config.kraken.api_key, env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(config.kraken.api_secret, "s3cret");
+        assert_eq!(config.wallets.monero_wallet_password, "hunter2");
+        assert_eq!(config.database.auth_level, DatabaseAuthLevel::Database);
+        assert_eq!(config.database.username, "eigenix");
+        assert_eq!(config.database.password, "db-pass");
+        assert_eq!(config.secrets_file, Some(secrets_path.clone()));
+
+        // Secrets stay out of logs
+        let debug = format!("{:?}", config);
+        assert!(!debug.contains("s3cret"));
+        assert!(!debug.contains("hunter2"));
+        assert!(!debug.contains("db-pass"));
+
+        // Without the secrets file, the required secrets are missing
+        assert!(Config::load(cli(&["--config", config_arg])).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
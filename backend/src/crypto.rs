@@ -0,0 +1,89 @@
+//! Passphrase encryption for files at rest
+//!
+//! Argon2id stretches the passphrase into a key for XChaCha20-Poly1305.
+//! Sealed data starts with an 8-byte tag naming the file format, followed by
+//! the salt, the nonce and the ciphertext, so one kind of file is never
+//! mistaken for another.
+
+use anyhow::{Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use std::path::Path;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Derive the encryption key for `salt` from the passphrase
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("Failed to derive encryption key: {}", e))?;
+    Ok(key)
+}
+
+/// Encrypt `plaintext`, returning the tag, salt, nonce and ciphertext
+pub fn seal(tag: &[u8; 8], passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key(passphrase, &salt)?;
+    let ciphertext = XChaCha20Poly1305::new(&key.into())
+        .encrypt(&XNonce::from(nonce), plaintext)
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt"))?;
+
+    let mut out = Vec::with_capacity(tag.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(tag);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Whether `data` was sealed with `tag`
+pub fn is_sealed(tag: &[u8; 8], data: &[u8]) -> bool {
+    data.starts_with(tag)
+}
+
+/// Decrypt data written by [`seal`] with the same tag
+pub fn open(tag: &[u8; 8], passphrase: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let body = data
+        .strip_prefix(tag.as_slice())
+        .context("Unrecognized encrypted file format")?;
+    if body.len() < SALT_LEN + NONCE_LEN {
+        anyhow::bail!("Encrypted file is truncated");
+    }
+    let (salt, rest) = body.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let nonce: [u8; NONCE_LEN] = nonce.try_into()?;
+
+    let key = derive_key(passphrase, salt)?;
+    XChaCha20Poly1305::new(&key.into())
+        .decrypt(&XNonce::from(nonce), ciphertext)
+        .map_err(|_| anyhow::anyhow!("Wrong passphrase or corrupted file"))
+}
+
+/// Write a file only the owner can read
+pub fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    Ok(())
+}
@@ -0,0 +1,268 @@
+//! Bucketed aggregation of a single metric field
+//!
+//! Charts plot one field over time, so instead of shipping whole rows the
+//! database groups samples into fixed buckets and reduces each to one value.
+//! Only the numeric fields listed in [`SERIES`] can be aggregated; they are
+//! interpolated into the query, so nothing else may reach it.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use super::MetricsDatabase;
+
+/// Numeric fields that can be aggregated, with the table they live in, per source
+pub const SERIES: &[(&str, &str, &[&str])] = &[
+    (
+        "bitcoin",
+        "bitcoin_metrics",
+        &[
+            "blocks",
+            "headers",
+            "verification_progress",
+            "size_on_disk",
+            "wallet_balance",
+        ],
+    ),
+    (
+        "monero",
+        "monero_metrics",
+        &[
+            "height",
+            "target_height",
+            "difficulty",
+            "tx_count",
+            "wallet_balance",
+        ],
+    ),
+    (
+        "asb",
+        "asb_metrics",
+        &[
+            "balance_btc",
+            "pending_swaps",
+            "completed_swaps",
+            "failed_swaps",
+        ],
+    ),
+    ("electrs", "electrs_metrics", &["indexed_blocks"]),
+    (
+        "mempool",
+        "mempool_metrics",
+        &["tx_count", "vsize", "total_fee_sat"],
+    ),
+    (
+        "asb_quotes",
+        "asb_quotes",
+        &[
+            "price",
+            "min_quantity",
+            "max_quantity",
+            "market_price",
+            "spread_percent",
+        ],
+    ),
+    (
+        "prices",
+        "price_metrics",
+        &["btc_usd", "xmr_usd", "xmr_btc"],
+    ),
+    (
+        "system",
+        "system_metrics",
+        &[
+            "cpu_percent",
+            "load_1",
+            "load_5",
+            "load_15",
+            "memory_used_bytes",
+            "memory_used_percent",
+            "swap_used_bytes",
+            "net_rx_bytes_per_sec",
+            "net_tx_bytes_per_sec",
+        ],
+    ),
+    (
+        "portfolio",
+        "portfolio_snapshots",
+        &["total_btc", "total_usd"],
+    ),
+];
+
+/// The table holding `field` for `source`, if it can be aggregated
+pub fn series_table(source: &str, field: &str) -> Option<&'static str> {
+    SERIES
+        .iter()
+        .find(|(name, _, fields)| *name == source && fields.contains(&field))
+        .map(|(_, table, _)| *table)
+}
+
+/// How the samples in a bucket are reduced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregate {
+    Avg,
+    Min,
+    Max,
+    /// The most recent sample
+    Last,
+}
+
+/// Parse a bucket width such as `30s`, `5m`, `1h` or `1d`; a bare number is
+/// seconds
+pub fn parse_bucket(s: &str) -> Option<Duration> {
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(split) => s.split_at(split),
+        None => (s, "s"),
+    };
+    let number: i64 = number.parse().ok()?;
+    let seconds = match unit {
+        "s" => number,
+        "m" => number.checked_mul(60)?,
+        "h" => number.checked_mul(3600)?,
+        "d" => number.checked_mul(86_400)?,
+        _ => return None,
+    };
+    (seconds > 0).then(|| Duration::seconds(seconds))
+}
+
+#[derive(Deserialize)]
+struct BucketRow {
+    bucket: DateTime<Utc>,
+    value: Option<f64>,
+}
+
+/// Keep the last value of each bucket from rows in timestamp order
+fn last_per_bucket(rows: Vec<BucketRow>) -> Vec<(DateTime<Utc>, f64)> {
+    let mut points: Vec<(DateTime<Utc>, f64)> = Vec::new();
+    for row in rows {
+        let Some(value) = row.value else {
+            continue;
+        };
+        match points.last_mut() {
+            Some(last) if last.0 == row.bucket => last.1 = value,
+            _ => points.push((row.bucket, value)),
+        }
+    }
+    points
+}
+
+impl MetricsDatabase {
+    /// Aggregate `field` of `table` into `bucket`-wide buckets over a window
+    ///
+    /// `table` and `field` must come from [`SERIES`]. Buckets are aligned to
+    /// the Unix epoch and labelled with their start; empty buckets are left
+    /// out.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn aggregate_series(
+        &self,
+        table: &str,
+        field: &str,
+        aggregate: Aggregate,
+        bucket: Duration,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, f64)>> {
+        let bucket = format!("{}s", bucket.num_seconds());
+        let filter = format!(
+            "WHERE timestamp >= $from AND timestamp <= $to AND {field} != NONE AND {field} != NULL"
+        );
+
+        // `last` depends on sample order, which grouping doesn't keep
+        let query = match aggregate {
+            Aggregate::Last => format!(
+                "SELECT timestamp, time::floor(timestamp, {bucket}) AS bucket, {field} AS value \
+                 FROM {table} {filter} ORDER BY timestamp ASC"
+            ),
+            Aggregate::Avg | Aggregate::Min | Aggregate::Max => {
+                let function = match aggregate {
+                    Aggregate::Avg => "math::mean",
+                    Aggregate::Min => "math::min",
+                    _ => "math::max",
+                };
+                format!(
+                    "SELECT time::floor(timestamp, {bucket}) AS bucket, {function}({field}) AS value \
+                     FROM {table} {filter} GROUP BY bucket ORDER BY bucket ASC"
+                )
+            }
+        };
+
+        let rows: Vec<BucketRow> = self
+            .db
+            .query(query)
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .with_context(|| format!("Failed to aggregate {}.{}", table, field))?
+            .take(0)
+            .with_context(|| format!("Failed to read {}.{} aggregate", table, field))?;
+
+        Ok(match aggregate {
+            Aggregate::Last => last_per_bucket(rows),
+            _ => rows
+                .into_iter()
+                .filter_map(|row| row.value.map(|value| (row.bucket, value)))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_bucket() {
+        assert_eq!(parse_bucket("30s"), Some(Duration::seconds(30)));
+        assert_eq!(parse_bucket("5m"), Some(Duration::minutes(5)));
+        assert_eq!(parse_bucket("1h"), Some(Duration::hours(1)));
+        assert_eq!(parse_bucket("1d"), Some(Duration::days(1)));
+        assert_eq!(parse_bucket("90"), Some(Duration::seconds(90)));
+
+        for invalid in ["", "0m", "5w", "m", "-5m", "1.5h"] {
+            assert_eq!(parse_bucket(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_series_fields_are_whitelisted() {
+        assert_eq!(
+            series_table("bitcoin", "wallet_balance"),
+            Some("bitcoin_metrics")
+        );
+        assert_eq!(series_table("asb", "balance_btc"), Some("asb_metrics"));
+        assert_eq!(series_table("asb", "up"), None);
+        assert_eq!(series_table("monero", "blocks"), None);
+        assert_eq!(
+            series_table("bitcoin", "blocks; DELETE bitcoin_metrics"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_last_per_bucket() {
+        let at = |m: u32| Utc.with_ymd_and_hms(2024, 6, 1, 12, m, 0).unwrap();
+        let rows = vec![
+            BucketRow {
+                bucket: at(0),
+                value: Some(1.0),
+            },
+            BucketRow {
+                bucket: at(0),
+                value: Some(2.0),
+            },
+            BucketRow {
+                bucket: at(5),
+                value: None,
+            },
+            BucketRow {
+                bucket: at(10),
+                value: Some(3.0),
+            },
+        ];
+
+        assert_eq!(last_per_bucket(rows), vec![(at(0), 2.0), (at(10), 3.0)]);
+    }
+}
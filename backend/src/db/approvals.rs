@@ -0,0 +1,121 @@
+//! Transfers held for approval
+//!
+//! One `approvals` row per held operation, kept after it is decided as the
+//! record of who asked and who approved or rejected it.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::MetricsDatabase;
+use crate::approvals::{Approval, ApprovalStatus};
+
+/// Most approvals returned by a listing
+const APPROVALS_LIMIT: usize = 200;
+
+/// A listed approval along with its record id
+#[derive(Deserialize)]
+struct ApprovalRow {
+    key: String,
+    #[serde(flatten)]
+    approval: Approval,
+}
+
+impl MetricsDatabase {
+    /// Store a new approval, returning its id
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_approval(&self, approval: &Approval) -> Result<String> {
+        let ids: Vec<String> = self
+            .db
+            .query("CREATE approvals CONTENT $approval RETURN VALUE meta::id(id)")
+            .bind((
+                "approval",
+                Approval {
+                    id: None,
+                    ..approval.clone()
+                },
+            ))
+            .await
+            .context("Failed to store approval")?
+            .take(0)
+            .context("Failed to get approval id")?;
+
+        ids.into_iter().next().context("No approval id returned")
+    }
+
+    /// Get an approval by id
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_approval(&self, id: &str) -> Result<Option<Approval>> {
+        let result: Option<Approval> = self
+            .db
+            .select(("approvals", id))
+            .await
+            .context("Failed to get approval")?;
+
+        Ok(result.map(|approval| Approval {
+            id: Some(id.to_string()),
+            ..approval
+        }))
+    }
+
+    /// Most recent approvals, optionally only those in `status`
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_approvals(&self, status: Option<ApprovalStatus>) -> Result<Vec<Approval>> {
+        let mut query = String::from("SELECT *, meta::id(id) AS key FROM approvals");
+        if status.is_some() {
+            query.push_str(" WHERE status = $status");
+        }
+        query.push_str(" ORDER BY requested_at DESC LIMIT $limit");
+
+        let rows: Vec<ApprovalRow> = self
+            .db
+            .query(query)
+            .bind(("status", status))
+            .bind(("limit", APPROVALS_LIMIT))
+            .await
+            .context("Failed to query approvals")?
+            .take(0)
+            .context("Failed to parse approvals")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Approval {
+                id: Some(row.key),
+                ..row.approval
+            })
+            .collect())
+    }
+
+    /// Replace an approval if it is still in status `from`
+    ///
+    /// A single conditional update, so of two callers deciding the same
+    /// approval at once only one succeeds.
+    ///
+    /// # Returns
+    /// `false` if the approval had already left `from`
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn transition_approval(
+        &self,
+        id: &str,
+        from: ApprovalStatus,
+        approval: &Approval,
+    ) -> Result<bool> {
+        let updated: Vec<Approval> = self
+            .db
+            .query("UPDATE type::thing('approvals', $id) CONTENT $approval WHERE status = $from")
+            .bind(("id", id.to_string()))
+            .bind((
+                "approval",
+                Approval {
+                    id: None,
+                    ..approval.clone()
+                },
+            ))
+            .bind(("from", from))
+            .await
+            .context("Failed to update approval")?
+            .take(0)
+            .context("Failed to update approval")?;
+
+        Ok(!updated.is_empty())
+    }
+}
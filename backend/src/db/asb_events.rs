@@ -0,0 +1,53 @@
+//! Swap events read from the ASB's log
+//!
+//! One `asb_events` row per lifecycle line, keyed by a hash of the line, so
+//! reading part of the log again rewrites the same rows instead of adding
+//! copies.
+
+use anyhow::{Context, Result};
+
+use super::MetricsDatabase;
+use crate::asb_log::AsbEvent;
+
+impl MetricsDatabase {
+    /// Store an ASB event under `key`, replacing the one already there
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_asb_event(&self, key: &str, event: &AsbEvent) -> Result<()> {
+        let _stored: Option<AsbEvent> = self
+            .db
+            .upsert(("asb_events", key))
+            .content(event.clone())
+            .await
+            .context("Failed to store ASB event")?;
+
+        Ok(())
+    }
+
+    /// The most recent ASB events, newest first, of one swap or all of them
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_asb_events(
+        &self,
+        swap_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<AsbEvent>> {
+        let filter = if swap_id.is_some() {
+            "WHERE swap_id = $swap_id "
+        } else {
+            ""
+        };
+        let events: Vec<AsbEvent> = self
+            .db
+            .query(format!(
+                "SELECT * FROM asb_events {}ORDER BY timestamp DESC LIMIT $limit",
+                filter
+            ))
+            .bind(("swap_id", swap_id.map(str::to_string)))
+            .bind(("limit", limit))
+            .await
+            .context("Failed to query ASB events")?
+            .take(0)
+            .context("Failed to parse ASB events")?;
+
+        Ok(events)
+    }
+}
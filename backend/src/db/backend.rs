@@ -0,0 +1,45 @@
+//! Backend self-metrics
+//!
+//! One `backend_metrics` row per snapshot of the backend's own request,
+//! collector, database and process metrics. Counters in each row run from
+//! the start of the process that wrote it, so they reset on restart.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+use super::MetricsDatabase;
+use crate::metrics::backend::BackendMetricsSnapshot;
+
+impl MetricsDatabase {
+    /// Store a snapshot of the backend's metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_backend_metrics(&self, snapshot: &BackendMetricsSnapshot) -> Result<()> {
+        self.store_rows(
+            "backend_metrics",
+            |rows| &mut rows.backend,
+            vec![snapshot.clone()],
+        )
+        .await
+        .context("Failed to store backend metrics")
+    }
+
+    /// Get backend metrics snapshots within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_backend_metrics_history(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<BackendMetricsSnapshot>> {
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM backend_metrics WHERE timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query backend metrics history")?
+            .take(0)
+            .context("Failed to read backend metrics history")?;
+
+        self.history_rows(rows, "backend_metrics")
+    }
+}
@@ -0,0 +1,236 @@
+//! Buffered metric writes
+//!
+//! With a flush interval set, collected samples are queued per table instead
+//! of written one `CREATE` at a time. [`MetricsDatabase::flush`] writes each
+//! queue as a single bulk insert; it runs on the interval, as soon as a table
+//! has a full batch, and once more on shutdown. Queued samples aren't visible
+//! to queries until then.
+
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::disk::StoredDiskMetrics;
+use super::mempool::{StoredAddressMetrics, StoredMempoolMetrics};
+use super::prices::StoredPriceMetrics;
+use super::quotes::StoredAsbQuote;
+use super::system::StoredSystemMetrics;
+use super::{
+    MetricsDatabase, StoredAsbMetrics, StoredBitcoinMetrics, StoredContainerMetrics,
+    StoredElectrsMetrics, StoredMoneroMetrics,
+};
+use crate::metrics::backend::BackendMetricsSnapshot;
+use crate::portfolio::Portfolio;
+use crate::shutdown::Shutdown;
+
+/// Samples waiting to be written, one queue per metrics table
+#[derive(Debug, Default)]
+pub struct PendingRows {
+    pub bitcoin: Vec<StoredBitcoinMetrics>,
+    pub monero: Vec<StoredMoneroMetrics>,
+    pub asb: Vec<StoredAsbMetrics>,
+    pub electrs: Vec<StoredElectrsMetrics>,
+    pub containers: Vec<StoredContainerMetrics>,
+    pub disk: Vec<StoredDiskMetrics>,
+    pub system: Vec<StoredSystemMetrics>,
+    pub mempool: Vec<StoredMempoolMetrics>,
+    pub addresses: Vec<StoredAddressMetrics>,
+    pub quotes: Vec<StoredAsbQuote>,
+    pub prices: Vec<StoredPriceMetrics>,
+    pub portfolio: Vec<Portfolio>,
+    pub backend: Vec<BackendMetricsSnapshot>,
+}
+
+impl PendingRows {
+    pub fn is_empty(&self) -> bool {
+        self.bitcoin.is_empty()
+            && self.monero.is_empty()
+            && self.asb.is_empty()
+            && self.electrs.is_empty()
+            && self.containers.is_empty()
+            && self.disk.is_empty()
+            && self.system.is_empty()
+            && self.mempool.is_empty()
+            && self.addresses.is_empty()
+            && self.quotes.is_empty()
+            && self.prices.is_empty()
+            && self.portfolio.is_empty()
+            && self.backend.is_empty()
+    }
+}
+
+/// Queued writes shared by every clone of a [`MetricsDatabase`]
+#[derive(Debug)]
+pub struct WriteBuffer {
+    flush_interval: Duration,
+    max_batch_size: usize,
+    pending: Mutex<PendingRows>,
+}
+
+impl WriteBuffer {
+    pub fn new(flush_interval: Duration, max_batch_size: usize) -> Self {
+        Self {
+            flush_interval,
+            max_batch_size: max_batch_size.max(1),
+            pending: Mutex::new(PendingRows::default()),
+        }
+    }
+
+    /// Queue `rows` on the table `queue` selects
+    ///
+    /// # Returns
+    /// Whether that table now holds a full batch
+    pub fn push<T>(&self, queue: fn(&mut PendingRows) -> &mut Vec<T>, rows: Vec<T>) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        let queued = queue(&mut pending);
+        queued.extend(rows);
+        queued.len() >= self.max_batch_size
+    }
+
+    /// Take everything queued so far
+    pub fn take(&self) -> PendingRows {
+        std::mem::take(&mut *self.pending.lock().unwrap())
+    }
+}
+
+impl MetricsDatabase {
+    /// Queue metric writes and flush them every `flush_interval`, or as soon
+    /// as a table has `max_batch_size` rows. A zero interval writes every
+    /// sample right away.
+    pub fn with_write_buffer(mut self, flush_interval: Duration, max_batch_size: usize) -> Self {
+        self.buffer = (!flush_interval.is_zero())
+            .then(|| Arc::new(WriteBuffer::new(flush_interval, max_batch_size)));
+        self
+    }
+
+    /// Write `rows` to `table`, or queue them when buffering
+    pub(super) async fn store_rows<T>(
+        &self,
+        table: &str,
+        queue: fn(&mut PendingRows) -> &mut Vec<T>,
+        rows: Vec<T>,
+    ) -> Result<()>
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        match &self.buffer {
+            Some(buffer) => {
+                if buffer.push(queue, rows) {
+                    self.flush().await?;
+                }
+                Ok(())
+            }
+            None => self.insert_rows(table, rows).await,
+        }
+    }
+
+    /// Insert `rows` into `table` in a single statement
+    async fn insert_rows<T>(&self, table: &str, rows: Vec<T>) -> Result<()>
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let count = rows.len();
+        let _: Vec<T> = self
+            .db
+            .insert(table)
+            .content(rows)
+            .await
+            .with_context(|| format!("Failed to store {} rows in {}", count, table))?;
+        Ok(())
+    }
+
+    /// Write every queued sample
+    ///
+    /// Every table is attempted even if one fails; the first error is
+    /// returned and the rows that failed are dropped, as an unbuffered write
+    /// would have dropped them.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn flush(&self) -> Result<()> {
+        let Some(buffer) = &self.buffer else {
+            return Ok(());
+        };
+        let pending = buffer.take();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let results = [
+            self.insert_rows("bitcoin_metrics", pending.bitcoin).await,
+            self.insert_rows("monero_metrics", pending.monero).await,
+            self.insert_rows("asb_metrics", pending.asb).await,
+            self.insert_rows("electrs_metrics", pending.electrs).await,
+            self.insert_rows("container_metrics", pending.containers)
+                .await,
+            self.insert_rows("disk_metrics", pending.disk).await,
+            self.insert_rows("system_metrics", pending.system).await,
+            self.insert_rows("mempool_metrics", pending.mempool).await,
+            self.insert_rows("address_metrics", pending.addresses).await,
+            self.insert_rows("asb_quotes", pending.quotes).await,
+            self.insert_rows("price_metrics", pending.prices).await,
+            self.insert_rows("portfolio_snapshots", pending.portfolio)
+                .await,
+            self.insert_rows("backend_metrics", pending.backend).await,
+        ];
+        results.into_iter().collect()
+    }
+
+    /// Flush on the buffer's interval until `shutdown` is triggered
+    ///
+    /// The final flush is left to the caller, once nothing else is writing.
+    pub async fn flush_periodically(&self, shutdown: Shutdown) {
+        let Some(buffer) = &self.buffer else {
+            return;
+        };
+        let mut ticker = tokio::time::interval(buffer.flush_interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.triggered() => return,
+            }
+            if let Err(e) = self.flush().await {
+                tracing::error!("Failed to flush buffered metrics: {:#}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn electrs(indexed_blocks: u64) -> StoredElectrsMetrics {
+        StoredElectrsMetrics {
+            timestamp: Utc::now(),
+            up: true,
+            indexed_blocks,
+        }
+    }
+
+    #[test]
+    fn test_buffer_reports_full_batches() {
+        let buffer = WriteBuffer::new(Duration::from_secs(5), 3);
+
+        assert!(!buffer.push(|rows| &mut rows.electrs, vec![electrs(1)]));
+        assert!(!buffer.push(|rows| &mut rows.electrs, vec![electrs(2)]));
+        // Batches are counted per table
+        assert!(!buffer.push(|rows| &mut rows.containers, Vec::new()));
+        assert!(buffer.push(|rows| &mut rows.electrs, vec![electrs(3)]));
+
+        let pending = buffer.take();
+        let blocks: Vec<u64> = pending
+            .electrs
+            .iter()
+            .map(|row| row.indexed_blocks)
+            .collect();
+        assert_eq!(blocks, vec![1, 2, 3]);
+        assert!(buffer.take().is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+//! Filesystem usage samples
+//!
+//! One `disk_metrics` row per monitored path and collection, the rows of a
+//! collection sharing its timestamp.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::MetricsDatabase;
+use crate::metrics::DiskMetrics;
+
+/// Database-stored disk metrics with timestamp
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StoredDiskMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub name: String,
+    pub path: String,
+    pub mount_point: String,
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    pub available_bytes: u64,
+    /// Share of the usable space still free, in percent
+    pub free_percent: f64,
+}
+
+impl MetricsDatabase {
+    /// Store the usage of every monitored path
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_disk_metrics(&self, metrics: &[DiskMetrics]) -> Result<()> {
+        let timestamp = Utc::now();
+        let stored = metrics
+            .iter()
+            .map(|metric| StoredDiskMetrics {
+                timestamp,
+                name: metric.name.clone(),
+                path: metric.path.clone(),
+                mount_point: metric.mount_point.clone(),
+                total_bytes: metric.total_bytes,
+                used_bytes: metric.used_bytes,
+                available_bytes: metric.available_bytes,
+                free_percent: metric.free_percent(),
+            })
+            .collect();
+
+        self.store_rows("disk_metrics", |rows| &mut rows.disk, stored)
+            .await
+            .context("Failed to store disk metrics")
+    }
+
+    /// Get the latest usage of every monitored path
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_latest_disk_metrics(&self) -> Result<Vec<StoredDiskMetrics>> {
+        let latest: Vec<StoredDiskMetrics> = self
+            .db
+            .query(
+                "SELECT * FROM disk_metrics
+                 WHERE timestamp = (SELECT VALUE timestamp FROM disk_metrics ORDER BY timestamp DESC LIMIT 1)[0]",
+            )
+            .await
+            .context("Failed to query disk metrics")?
+            .take(0)
+            .context("Failed to parse disk metrics")?;
+
+        Ok(latest)
+    }
+
+    /// Get the usage history of one monitored path within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_disk_history(
+        &self,
+        name: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredDiskMetrics>> {
+        let name = name.to_string();
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM disk_metrics WHERE name = $name AND timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("name", name))
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query disk history")?
+            .take(0)
+            .context("Failed to read disk history")?;
+
+        self.history_rows(rows, "disk_metrics")
+    }
+}
@@ -0,0 +1,101 @@
+//! Idempotency keys
+//!
+//! One `idempotency_keys` record per key a caller has sent, holding the
+//! response to replay. The record is created before the request runs, so a
+//! concurrent retry finds it and backs off instead of running twice.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::MetricsDatabase;
+
+/// A response kept for replaying
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Base64, since downloads aren't always UTF-8
+    pub body: String,
+}
+
+/// What a key was first used for and what it returned
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IdempotencyRecord {
+    /// Hash of the method, path and body of the first request
+    pub fingerprint: String,
+    pub created_at: DateTime<Utc>,
+    /// `None` while the first request is still running
+    pub response: Option<StoredResponse>,
+}
+
+impl MetricsDatabase {
+    /// Get the record of an idempotency key
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_idempotency_record(&self, id: &str) -> Result<Option<IdempotencyRecord>> {
+        let result: Option<IdempotencyRecord> = self
+            .db
+            .select(("idempotency_keys", id))
+            .await
+            .context("Failed to read idempotency key")?;
+
+        Ok(result)
+    }
+
+    /// Claim an idempotency key for a request about to run
+    ///
+    /// # Returns
+    /// `false` if the key already has a record
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn claim_idempotency_key(
+        &self,
+        id: &str,
+        record: &IdempotencyRecord,
+    ) -> Result<bool> {
+        let created: Result<Option<IdempotencyRecord>, _> = self
+            .db
+            .create(("idempotency_keys", id))
+            .content(record.clone())
+            .await;
+
+        match created {
+            Ok(_) => Ok(true),
+            // Creating fails when the record exists; anything else is a real error
+            Err(e) => match self.get_idempotency_record(id).await? {
+                Some(_) => Ok(false),
+                None => Err(e).context("Failed to store idempotency key"),
+            },
+        }
+    }
+
+    /// Replace the record of a claimed idempotency key, once its response is known
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn save_idempotency_record(
+        &self,
+        id: &str,
+        record: &IdempotencyRecord,
+    ) -> Result<()> {
+        let _result: Option<IdempotencyRecord> = self
+            .db
+            .upsert(("idempotency_keys", id))
+            .content(record.clone())
+            .await
+            .context("Failed to store idempotent response")?;
+
+        Ok(())
+    }
+
+    /// Forget idempotency keys first used before `cutoff`
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn delete_expired_idempotency_keys(&self, cutoff: DateTime<Utc>) -> Result<()> {
+        self.db
+            .query("DELETE idempotency_keys WHERE created_at < $cutoff")
+            .bind(("cutoff", cutoff))
+            .await
+            .context("Failed to delete expired idempotency keys")?
+            .check()
+            .context("Failed to delete expired idempotency keys")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+//! Mempool and watched-address samples
+//!
+//! One `mempool_metrics` row per collection, fee histogram included, and one
+//! `address_metrics` row per watched address, all rows of a collection
+//! sharing its timestamp.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::MetricsDatabase;
+use crate::metrics::{AddressMetrics, MempoolMetrics};
+
+/// Database-stored mempool metrics with timestamp
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StoredMempoolMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub tx_count: u64,
+    pub vsize: u64,
+    pub total_fee_sat: u64,
+    /// `(feerate in sat/vB, vsize)` pairs, highest feerate first
+    pub fee_histogram: Vec<(f64, u64)>,
+}
+
+/// Database-stored address metrics with timestamp
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StoredAddressMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub address: String,
+    pub confirmed_balance: f64,
+    pub unconfirmed_balance: f64,
+    pub tx_count: u64,
+    pub mempool_tx_count: u64,
+}
+
+impl MetricsDatabase {
+    /// Store mempool metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_mempool_metrics(&self, metrics: &MempoolMetrics) -> Result<()> {
+        let stored = StoredMempoolMetrics {
+            timestamp: Utc::now(),
+            tx_count: metrics.tx_count,
+            vsize: metrics.vsize,
+            total_fee_sat: metrics.total_fee_sat,
+            fee_histogram: metrics.fee_histogram.clone(),
+        };
+
+        self.store_rows("mempool_metrics", |rows| &mut rows.mempool, vec![stored])
+            .await
+            .context("Failed to store mempool metrics")
+    }
+
+    /// Store metrics of the watched addresses
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_address_metrics(&self, metrics: &[AddressMetrics]) -> Result<()> {
+        let timestamp = Utc::now();
+        let stored = metrics
+            .iter()
+            .map(|metric| StoredAddressMetrics {
+                timestamp,
+                address: metric.address.clone(),
+                confirmed_balance: metric.confirmed_balance,
+                unconfirmed_balance: metric.unconfirmed_balance,
+                tx_count: metric.tx_count,
+                mempool_tx_count: metric.mempool_tx_count,
+            })
+            .collect();
+
+        self.store_rows("address_metrics", |rows| &mut rows.addresses, stored)
+            .await
+            .context("Failed to store address metrics")
+    }
+
+    /// Get latest mempool metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_latest_mempool_metrics(&self) -> Result<Option<StoredMempoolMetrics>> {
+        let mut result: Vec<StoredMempoolMetrics> = self
+            .db
+            .query("SELECT * FROM mempool_metrics ORDER BY timestamp DESC LIMIT 1")
+            .await
+            .context("Failed to query mempool metrics")?
+            .take(0)
+            .context("Failed to parse mempool metrics")?;
+
+        Ok(result.pop())
+    }
+
+    /// Get the latest metrics of every watched address
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_latest_address_metrics(&self) -> Result<Vec<StoredAddressMetrics>> {
+        let latest: Vec<StoredAddressMetrics> = self
+            .db
+            .query(
+                "SELECT * FROM address_metrics
+                 WHERE timestamp = (SELECT VALUE timestamp FROM address_metrics ORDER BY timestamp DESC LIMIT 1)[0]",
+            )
+            .await
+            .context("Failed to query address metrics")?
+            .take(0)
+            .context("Failed to parse address metrics")?;
+
+        Ok(latest)
+    }
+
+    /// Get mempool metrics history within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_mempool_history(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredMempoolMetrics>> {
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM mempool_metrics WHERE timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query mempool history")?
+            .take(0)
+            .context("Failed to read mempool history")?;
+
+        self.history_rows(rows, "mempool_metrics")
+    }
+
+    /// Get the history of one watched address within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_address_history(
+        &self,
+        address: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredAddressMetrics>> {
+        let address = address.to_string();
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM address_metrics WHERE address = $address AND timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("address", address))
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query address history")?
+            .take(0)
+            .context("Failed to read address history")?;
+
+        self.history_rows(rows, "address_metrics")
+    }
+}
@@ -0,0 +1,267 @@
+//! Versioned schema migrations
+//!
+//! The schema version is kept in the `schema_meta:current` record. On
+//! startup [`MetricsDatabase::run_migrations`] applies every step above the
+//! stored version, in order, and records the version after each one. Steps
+//! must be idempotent (`IF NOT EXISTS`, guarded updates) so a step that was
+//! interrupted before its version was recorded can safely run again.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::MetricsDatabase;
+
+/// A single schema change, applied once
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    /// SurrealQL run as one query
+    pub statements: &'static str,
+}
+
+/// Every migration, in the order they are applied
+///
+/// Append new steps with the next version number; never edit or reorder
+/// steps that have shipped.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Index metrics tables by timestamp",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS bitcoin_metrics_timestamp ON TABLE bitcoin_metrics FIELDS timestamp;
+            DEFINE INDEX IF NOT EXISTS monero_metrics_timestamp ON TABLE monero_metrics FIELDS timestamp;
+            DEFINE INDEX IF NOT EXISTS asb_metrics_timestamp ON TABLE asb_metrics FIELDS timestamp;
+            DEFINE INDEX IF NOT EXISTS electrs_metrics_timestamp ON TABLE electrs_metrics FIELDS timestamp;
+            DEFINE INDEX IF NOT EXISTS container_metrics_name_timestamp ON TABLE container_metrics FIELDS name, timestamp;
+        ",
+    },
+    Migration {
+        version: 2,
+        description: "Index trading transactions by timestamp and status",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS trading_transactions_timestamp ON TABLE trading_transactions FIELDS timestamp;
+            DEFINE INDEX IF NOT EXISTS trading_transactions_status ON TABLE trading_transactions FIELDS status;
+        ",
+    },
+    Migration {
+        version: 3,
+        description: "Index alerts by state and start time",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS alerts_state ON TABLE alerts FIELDS state;
+            DEFINE INDEX IF NOT EXISTS alerts_started_at ON TABLE alerts FIELDS started_at;
+        ",
+    },
+    Migration {
+        version: 4,
+        description: "Index mempool and address metrics by timestamp",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS mempool_metrics_timestamp ON TABLE mempool_metrics FIELDS timestamp;
+            DEFINE INDEX IF NOT EXISTS address_metrics_address_timestamp ON TABLE address_metrics FIELDS address, timestamp;
+        ",
+    },
+    Migration {
+        version: 5,
+        description: "Index ASB quotes by timestamp",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS asb_quotes_timestamp ON TABLE asb_quotes FIELDS timestamp;
+        ",
+    },
+    Migration {
+        version: 6,
+        description: "Index price metrics by timestamp",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS price_metrics_timestamp ON TABLE price_metrics FIELDS timestamp;
+        ",
+    },
+    Migration {
+        version: 7,
+        description: "Index portfolio snapshots by timestamp",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS portfolio_snapshots_timestamp ON TABLE portfolio_snapshots FIELDS timestamp;
+        ",
+    },
+    Migration {
+        version: 8,
+        description: "Index idempotency keys by creation time",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS idempotency_keys_created_at ON TABLE idempotency_keys FIELDS created_at;
+        ",
+    },
+    Migration {
+        version: 9,
+        description: "Index approvals by status and request time",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS approvals_status ON TABLE approvals FIELDS status;
+            DEFINE INDEX IF NOT EXISTS approvals_requested_at ON TABLE approvals FIELDS requested_at;
+        ",
+    },
+    Migration {
+        version: 10,
+        description: "Index spends by asset and time",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS spends_asset_timestamp ON TABLE spends FIELDS asset, timestamp;
+        ",
+    },
+    Migration {
+        version: 11,
+        description: "Index backend metrics by timestamp",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS backend_metrics_timestamp ON TABLE backend_metrics FIELDS timestamp;
+        ",
+    },
+    Migration {
+        version: 12,
+        description: "Index ASB events by swap and time",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS asb_events_timestamp ON TABLE asb_events FIELDS timestamp;
+            DEFINE INDEX IF NOT EXISTS asb_events_swap_id_timestamp ON TABLE asb_events FIELDS swap_id, timestamp;
+        ",
+    },
+    Migration {
+        version: 13,
+        description: "Index disk metrics by path name and time",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS disk_metrics_name_timestamp ON TABLE disk_metrics FIELDS name, timestamp;
+            DEFINE INDEX IF NOT EXISTS disk_metrics_timestamp ON TABLE disk_metrics FIELDS timestamp;
+        ",
+    },
+    Migration {
+        version: 14,
+        description: "Index system metrics by timestamp",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS system_metrics_timestamp ON TABLE system_metrics FIELDS timestamp;
+        ",
+    },
+    Migration {
+        version: 15,
+        description: "Drop display strings stored with node metrics",
+        statements: "
+            UPDATE bitcoin_metrics UNSET size_on_disk_human WHERE size_on_disk_human != NONE;
+            UPDATE monero_metrics UNSET difficulty_human WHERE difficulty_human != NONE;
+        ",
+    },
+    Migration {
+        version: 16,
+        description: "Record the requesting principal of approvals",
+        statements: "
+            UPDATE approvals SET requester = { kind: 'trading_engine' } WHERE requester = NONE AND requested_by = 'trading-engine';
+            UPDATE approvals SET requester = { kind: 'admin_token' } WHERE requester = NONE AND requested_by = 'admin';
+            UPDATE approvals SET requester = { kind: 'api_key', name: requested_by } WHERE requester = NONE;
+        ",
+    },
+];
+
+/// Schema version the code expects
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Outcome of a [`MetricsDatabase::run_migrations`] call
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// Versions applied by this call, empty when already up to date
+    pub applied: Vec<u32>,
+}
+
+/// The `schema_meta:current` record
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaMeta {
+    version: u32,
+    updated_at: DateTime<Utc>,
+}
+
+impl MetricsDatabase {
+    /// Schema version recorded in the database (0 before any migration)
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn schema_version(&self) -> Result<u32> {
+        let meta: Option<SchemaMeta> = self
+            .db
+            .select(("schema_meta", "current"))
+            .await
+            .context("Failed to read schema version")?;
+
+        Ok(meta.map_or(0, |meta| meta.version))
+    }
+
+    /// Apply every migration above the recorded schema version
+    ///
+    /// Refuses to run against a database migrated by a newer release.
+    pub async fn run_migrations(&self) -> Result<MigrationReport> {
+        let from_version = self.schema_version().await?;
+        if from_version > latest_version() {
+            anyhow::bail!(
+                "Database schema version {} is newer than this release supports ({})",
+                from_version,
+                latest_version()
+            );
+        }
+
+        let mut applied = Vec::new();
+        for migration in MIGRATIONS.iter().filter(|m| m.version > from_version) {
+            tracing::info!(
+                "Applying schema migration {}: {}",
+                migration.version,
+                migration.description
+            );
+
+            let failed = || format!("Schema migration {} failed", migration.version);
+            self.db
+                .query(migration.statements)
+                .await
+                .with_context(failed)?
+                .check()
+                .with_context(failed)?;
+
+            let _meta: Option<SchemaMeta> = self
+                .db
+                .upsert(("schema_meta", "current"))
+                .content(SchemaMeta {
+                    version: migration.version,
+                    updated_at: Utc::now(),
+                })
+                .await
+                .with_context(|| {
+                    format!("Failed to record schema version {}", migration.version)
+                })?;
+
+            applied.push(migration.version);
+        }
+
+        Ok(MigrationReport {
+            from_version,
+            to_version: applied.last().copied().unwrap_or(from_version),
+            applied,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_are_sequential() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(
+                migration.version as usize,
+                index + 1,
+                "{}",
+                migration.description
+            );
+        }
+        assert_eq!(latest_version() as usize, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_statements_parse() {
+        for migration in MIGRATIONS {
+            surrealdb::sql::parse(migration.statements).unwrap_or_else(|e| {
+                panic!("Migration {} does not parse: {}", migration.version, e)
+            });
+        }
+    }
+}
@@ -0,0 +1,1334 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use eigenix_common::metrics::{BitcoinMetricsView, MoneroMetricsView};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use surrealdb::engine::any::{self, Any};
+use surrealdb::opt::auth::{Database, Namespace, Root};
+use surrealdb::Surreal;
+use utoipa::ToSchema;
+
+use crate::alerts::{AlertState, StoredAlert};
+use crate::config::{DatabaseAuthLevel, DatabaseConfig, DatabaseMode};
+use crate::metrics::{
+    humanize, AsbMetrics, BitcoinMetrics, BitcoinNetworkMetrics, ContainerMetrics, ElectrsMetrics,
+    MoneroMetrics, MoneroNetworkMetrics,
+};
+use crate::preferences::UserPreferences;
+use crate::trading::lock::TradingLease;
+
+pub mod aggregate;
+pub mod approvals;
+pub mod asb_events;
+pub mod backend;
+pub mod buffer;
+pub mod disk;
+pub mod idempotency;
+pub mod mempool;
+pub mod migrations;
+pub mod portfolio;
+pub mod prices;
+pub mod quotes;
+pub mod spends;
+pub mod system;
+
+pub use eigenix_common::metrics::{StoredAsbMetrics, StoredContainerMetrics};
+
+/// Trading transaction type
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema, async_graphql::Enum,
+)]
+pub enum TransactionType {
+    /// Bitcoin deposit to exchange
+    BitcoinDeposit,
+    /// BTC to XMR trade on exchange
+    Trade,
+    /// Monero withdrawal from exchange
+    MoneroWithdrawal,
+    /// Monero deposit to exchange
+    MoneroDeposit,
+    /// Bitcoin withdrawal from exchange
+    BitcoinWithdrawal,
+    /// Operator-triggered full conversion, recorded alongside its individual legs
+    EmergencyConversion,
+    /// Move out of a managed wallet via `POST /wallets/transfer` or a
+    /// cold-signed PSBT
+    InternalTransfer,
+}
+
+/// Trading transaction status
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema, async_graphql::Enum,
+)]
+pub enum TransactionStatus {
+    /// Transaction initiated
+    Pending,
+    /// Transaction confirmed/completed
+    Completed,
+    /// Transaction failed
+    Failed,
+    /// Transaction cancelled
+    Cancelled,
+}
+
+/// Database-stored trading transaction
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, async_graphql::SimpleObject)]
+#[graphql(name = "TradingTransaction")]
+pub struct StoredTradingTransaction {
+    #[serde(skip_deserializing)]
+    pub id: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub transaction_type: TransactionType,
+    pub status: TransactionStatus,
+    pub btc_amount: Option<f64>,
+    pub xmr_amount: Option<f64>,
+    pub exchange_rate: Option<f64>,
+    pub txid: Option<String>,
+    pub order_id: Option<String>,
+    pub refid: Option<String>,
+    pub from_address: Option<String>,
+    pub to_address: Option<String>,
+    pub fee: Option<f64>,
+    pub notes: Option<String>,
+    pub error_message: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Source wallet balance before the transaction, where tracked
+    #[serde(default)]
+    pub balance_before: Option<f64>,
+    /// Source wallet balance after the transaction, where tracked
+    #[serde(default)]
+    pub balance_after: Option<f64>,
+    /// Earlier txids of a Bitcoin send that was fee-bumped, oldest first
+    #[serde(default)]
+    pub replaced_txids: Vec<String>,
+    /// Unsigned PSBT of a Bitcoin send waiting for an offline signature,
+    /// cleared once the signed transaction is broadcast
+    #[serde(default)]
+    pub psbt: Option<String>,
+    /// Mid of the exchange's bid and ask when a trade was placed, the
+    /// reference its slippage is measured against
+    #[serde(default)]
+    pub mid_price: Option<f64>,
+}
+
+/// Database-stored Bitcoin metrics with timestamp
+///
+/// Responses send it as a [`BitcoinMetricsView`], which adds
+/// `size_on_disk_human` (e.g. `412.3 GiB`).
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, async_graphql::SimpleObject)]
+#[graphql(complex, name = "BitcoinMetrics")]
+pub struct StoredBitcoinMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub blocks: u64,
+    pub headers: u64,
+    pub verification_progress: f64,
+    pub size_on_disk: u64,
+    pub wallet_balance: Option<f64>,
+    /// Missing from rows collected before peer metrics were recorded
+    #[serde(default)]
+    pub network: Option<BitcoinNetworkMetrics>,
+}
+
+impl From<StoredBitcoinMetrics> for BitcoinMetricsView {
+    fn from(row: StoredBitcoinMetrics) -> Self {
+        Self {
+            timestamp: row.timestamp,
+            blocks: row.blocks,
+            headers: row.headers,
+            verification_progress: row.verification_progress,
+            size_on_disk: row.size_on_disk,
+            size_on_disk_human: humanize::humanize_bytes(row.size_on_disk),
+            wallet_balance: row.wallet_balance,
+            network: row.network,
+        }
+    }
+}
+
+/// Database-stored Monero metrics with timestamp
+///
+/// Responses send it as a [`MoneroMetricsView`], which adds
+/// `difficulty_human` (e.g. `352.89 G`).
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, async_graphql::SimpleObject)]
+#[graphql(complex, name = "MoneroMetrics")]
+pub struct StoredMoneroMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub height: u64,
+    pub target_height: u64,
+    pub difficulty: u64,
+    pub tx_count: u64,
+    pub wallet_balance: Option<f64>,
+    /// Missing from rows collected before connection metrics were recorded
+    #[serde(default)]
+    pub network: Option<MoneroNetworkMetrics>,
+}
+
+impl From<StoredMoneroMetrics> for MoneroMetricsView {
+    fn from(row: StoredMoneroMetrics) -> Self {
+        Self {
+            timestamp: row.timestamp,
+            height: row.height,
+            target_height: row.target_height,
+            difficulty: row.difficulty,
+            difficulty_human: humanize::humanize_si(row.difficulty),
+            tx_count: row.tx_count,
+            wallet_balance: row.wallet_balance,
+            network: row.network,
+        }
+    }
+}
+
+/// Database-stored Electrs metrics with timestamp
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StoredElectrsMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub up: bool,
+    pub indexed_blocks: u64,
+}
+
+/// Summary of all latest metrics
+///
+/// Only ever sent as a response, so node rows serialize as their views.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MetricsSummary {
+    #[serde(serialize_with = "serialize_bitcoin_view")]
+    #[schema(value_type = Option<BitcoinMetricsView>)]
+    pub bitcoin: Option<StoredBitcoinMetrics>,
+    #[serde(serialize_with = "serialize_monero_view")]
+    #[schema(value_type = Option<MoneroMetricsView>)]
+    pub monero: Option<StoredMoneroMetrics>,
+    pub asb: Option<StoredAsbMetrics>,
+    pub electrs: Option<StoredElectrsMetrics>,
+    pub containers: Vec<StoredContainerMetrics>,
+    #[serde(default)]
+    pub disk: Vec<disk::StoredDiskMetrics>,
+    #[serde(default)]
+    pub system: Option<system::StoredSystemMetrics>,
+}
+
+fn serialize_bitcoin_view<S: serde::Serializer>(
+    row: &Option<StoredBitcoinMetrics>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    row.clone()
+        .map(BitcoinMetricsView::from)
+        .serialize(serializer)
+}
+
+fn serialize_monero_view<S: serde::Serializer>(
+    row: &Option<StoredMoneroMetrics>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    row.clone()
+        .map(MoneroMetricsView::from)
+        .serialize(serializer)
+}
+
+/// Metrics history dump produced by `GET /metrics/export`
+///
+/// Also the input format of `eigenix import-metrics`; sections missing from a
+/// file are treated as empty.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct MetricsExport {
+    #[serde(default)]
+    pub bitcoin: Vec<StoredBitcoinMetrics>,
+    #[serde(default)]
+    pub monero: Vec<StoredMoneroMetrics>,
+    #[serde(default)]
+    pub asb: Vec<StoredAsbMetrics>,
+    #[serde(default)]
+    pub electrs: Vec<StoredElectrsMetrics>,
+    #[serde(default)]
+    pub containers: Vec<StoredContainerMetrics>,
+}
+
+/// Rows inserted per source by a metrics import
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub bitcoin: usize,
+    pub monero: usize,
+    pub asb: usize,
+    pub electrs: usize,
+    pub containers: usize,
+}
+
+/// Identity of a stored metrics row for import de-duplication
+///
+/// Container rows are also keyed by name since several share a timestamp.
+#[derive(Debug, PartialEq, Eq, Hash, Deserialize)]
+struct RowKey {
+    timestamp: DateTime<Utc>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// Deserialize query result rows one at a time
+///
+/// With `skip_unparseable` set, rows that fail to deserialize (e.g. written
+/// before a field was added) are logged and dropped instead of failing the
+/// whole result. Returns the parsed rows and how many were skipped.
+fn parse_rows<T: DeserializeOwned>(
+    rows: surrealdb::sql::Value,
+    table: &str,
+    skip_unparseable: bool,
+) -> Result<(Vec<T>, usize)> {
+    let rows = match rows {
+        surrealdb::sql::Value::Array(rows) => rows.0,
+        surrealdb::sql::Value::None | surrealdb::sql::Value::Null => Vec::new(),
+        row => vec![row],
+    };
+
+    let mut parsed = Vec::with_capacity(rows.len());
+    let mut skipped = 0;
+    for row in rows {
+        match surrealdb::sql::from_value(row) {
+            Ok(row) => parsed.push(row),
+            Err(e) if skip_unparseable => {
+                if skipped == 0 {
+                    tracing::warn!("Skipping unparseable {} row: {}", table, e);
+                }
+                skipped += 1;
+            }
+            Err(e) => {
+                return Err(anyhow::Error::new(e))
+                    .with_context(|| format!("Failed to parse {} row", table))
+            }
+        }
+    }
+
+    if skipped > 1 {
+        tracing::warn!("Skipped {} unparseable {} rows in total", skipped, table);
+    }
+
+    Ok((parsed, skipped))
+}
+
+/// Metrics database interface
+#[derive(Clone)]
+pub struct MetricsDatabase {
+    db: Surreal<Any>,
+    skip_unparseable_rows: bool,
+    /// Queued metric writes, when buffering is on
+    buffer: Option<Arc<buffer::WriteBuffer>>,
+}
+
+impl MetricsDatabase {
+    /// Open the database the config points at, remote or embedded
+    pub async fn open(config: &DatabaseConfig) -> Result<Self> {
+        let db = match config.mode {
+            DatabaseMode::Remote => {
+                Self::connect_as(
+                    &config.endpoint,
+                    &config.namespace,
+                    &config.database,
+                    config.auth_level,
+                    &config.username,
+                    &config.password,
+                )
+                .await?
+            }
+            DatabaseMode::Embedded => {
+                let path = config
+                    .path
+                    .as_deref()
+                    .context("database.path must be set in embedded mode")?;
+                Self::open_embedded(path, &config.namespace, &config.database).await?
+            }
+        };
+        Ok(db
+            .with_skip_unparseable_rows(config.skip_unparseable_rows)
+            .with_write_buffer(
+                std::time::Duration::from_secs(config.flush_interval_secs),
+                config.max_batch_size,
+            ))
+    }
+
+    /// Connect to a SurrealDB server over WebSocket as the default root user
+    pub async fn connect(endpoint: &str, namespace: &str, database: &str) -> Result<Self> {
+        Self::connect_as(
+            endpoint,
+            namespace,
+            database,
+            DatabaseAuthLevel::Root,
+            "root",
+            "root",
+        )
+        .await
+    }
+
+    /// Connect to a SurrealDB server over WebSocket and sign in as `username`
+    ///
+    /// Namespace and database users are looked up in `namespace` and
+    /// `database`. Rejected credentials are an error, not a retry.
+    pub async fn connect_as(
+        endpoint: &str,
+        namespace: &str,
+        database: &str,
+        level: DatabaseAuthLevel,
+        username: &str,
+        password: &str,
+    ) -> Result<Self> {
+        let address = if endpoint.contains("://") {
+            endpoint.to_string()
+        } else {
+            format!("ws://{}", endpoint)
+        };
+        let db = any::connect(address)
+            .await
+            .context("Failed to connect to SurrealDB")?;
+
+        let signin = match level {
+            DatabaseAuthLevel::Root => db.signin(Root { username, password }).await,
+            DatabaseAuthLevel::Namespace => {
+                db.signin(Namespace {
+                    namespace,
+                    username,
+                    password,
+                })
+                .await
+            }
+            DatabaseAuthLevel::Database => {
+                db.signin(Database {
+                    namespace,
+                    database,
+                    username,
+                    password,
+                })
+                .await
+            }
+        };
+        signin.with_context(|| {
+            format!(
+                "SurrealDB rejected the {} user '{}'; check [database] username, password and auth_level",
+                level, username
+            )
+        })?;
+
+        // Use namespace and database
+        db.use_ns(namespace)
+            .use_db(database)
+            .await
+            .context("Failed to select namespace and database")?;
+
+        Ok(Self {
+            db,
+            skip_unparseable_rows: true,
+            buffer: None,
+        })
+    }
+
+    /// Run SurrealDB in this process on a RocksDB store at `path`
+    ///
+    /// RocksDB locks the directory, so nothing else (the CLI included) can
+    /// open it while the backend runs. There are no users to sign in as.
+    pub async fn open_embedded(path: &Path, namespace: &str, database: &str) -> Result<Self> {
+        if !cfg!(feature = "embedded-db") {
+            anyhow::bail!("Embedded SurrealDB needs a backend built with the embedded-db feature");
+        }
+        std::fs::create_dir_all(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+
+        let db = any::connect(format!("rocksdb://{}", path.display()))
+            .await
+            .with_context(|| format!("Failed to open embedded SurrealDB at {}", path.display()))?;
+        db.use_ns(namespace)
+            .use_db(database)
+            .await
+            .context("Failed to select namespace and database")?;
+
+        Ok(Self {
+            db,
+            skip_unparseable_rows: true,
+            buffer: None,
+        })
+    }
+
+    /// Check the connection to SurrealDB is still alive
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn ping(&self) -> Result<()> {
+        self.db
+            .health()
+            .await
+            .context("SurrealDB health check failed")?;
+        Ok(())
+    }
+
+    /// Whether history queries skip rows that fail to deserialize (the
+    /// default) or fail outright
+    pub fn with_skip_unparseable_rows(mut self, skip: bool) -> Self {
+        self.skip_unparseable_rows = skip;
+        self
+    }
+
+    /// Deserialize a history query result row by row
+    fn history_rows<T: DeserializeOwned>(
+        &self,
+        rows: surrealdb::Value,
+        table: &str,
+    ) -> Result<Vec<T>> {
+        let (rows, _skipped) = parse_rows(rows.into_inner(), table, self.skip_unparseable_rows)?;
+        Ok(rows)
+    }
+
+    /// Store Bitcoin metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_bitcoin_metrics(&self, metrics: &BitcoinMetrics) -> Result<()> {
+        let stored = StoredBitcoinMetrics {
+            timestamp: Utc::now(),
+            blocks: metrics.blocks,
+            headers: metrics.headers,
+            verification_progress: metrics.verification_progress,
+            size_on_disk: metrics.size_on_disk,
+            wallet_balance: metrics.wallet_balance,
+            network: metrics.network.clone(),
+        };
+
+        self.store_rows("bitcoin_metrics", |rows| &mut rows.bitcoin, vec![stored])
+            .await
+            .context("Failed to store Bitcoin metrics")
+    }
+
+    /// Store Monero metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_monero_metrics(&self, metrics: &MoneroMetrics) -> Result<()> {
+        let stored = StoredMoneroMetrics {
+            timestamp: Utc::now(),
+            height: metrics.height,
+            target_height: metrics.target_height,
+            difficulty: metrics.difficulty,
+            tx_count: metrics.tx_count,
+            wallet_balance: metrics.wallet_balance,
+            network: metrics.network.clone(),
+        };
+
+        self.store_rows("monero_metrics", |rows| &mut rows.monero, vec![stored])
+            .await
+            .context("Failed to store Monero metrics")
+    }
+
+    /// Store ASB metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_asb_metrics(&self, metrics: &AsbMetrics) -> Result<()> {
+        let stored = StoredAsbMetrics {
+            timestamp: Utc::now(),
+            balance_btc: metrics.balance_btc,
+            pending_swaps: metrics.pending_swaps,
+            completed_swaps: metrics.completed_swaps,
+            failed_swaps: metrics.failed_swaps,
+            up: metrics.up,
+        };
+
+        self.store_rows("asb_metrics", |rows| &mut rows.asb, vec![stored])
+            .await
+            .context("Failed to store ASB metrics")
+    }
+
+    /// Store Electrs metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_electrs_metrics(&self, metrics: &ElectrsMetrics) -> Result<()> {
+        let stored = StoredElectrsMetrics {
+            timestamp: Utc::now(),
+            up: metrics.up,
+            indexed_blocks: metrics.indexed_blocks,
+        };
+
+        self.store_rows("electrs_metrics", |rows| &mut rows.electrs, vec![stored])
+            .await
+            .context("Failed to store Electrs metrics")
+    }
+
+    /// Store Container metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_container_metrics(&self, metrics: &[ContainerMetrics]) -> Result<()> {
+        let timestamp = Utc::now();
+        let stored = metrics
+            .iter()
+            .map(|metric| StoredContainerMetrics {
+                timestamp,
+                name: metric.name.clone(),
+                up: metric.up,
+                restarts: metric.restarts,
+                uptime_seconds: metric.uptime_seconds,
+                stats: metric.stats.clone(),
+            })
+            .collect();
+
+        self.store_rows("container_metrics", |rows| &mut rows.containers, stored)
+            .await
+            .context("Failed to store container metrics")
+    }
+
+    /// Get latest Bitcoin metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_latest_bitcoin_metrics(&self) -> Result<Option<StoredBitcoinMetrics>> {
+        let mut result: Vec<StoredBitcoinMetrics> = self
+            .db
+            .query("SELECT * FROM bitcoin_metrics ORDER BY timestamp DESC LIMIT 1")
+            .await
+            .context("Failed to query Bitcoin metrics")?
+            .take(0)
+            .context("Failed to parse Bitcoin metrics")?;
+
+        Ok(result.pop())
+    }
+
+    /// Get latest Monero metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_latest_monero_metrics(&self) -> Result<Option<StoredMoneroMetrics>> {
+        let mut result: Vec<StoredMoneroMetrics> = self
+            .db
+            .query("SELECT * FROM monero_metrics ORDER BY timestamp DESC LIMIT 1")
+            .await
+            .context("Failed to query Monero metrics")?
+            .take(0)
+            .context("Failed to parse Monero metrics")?;
+
+        Ok(result.pop())
+    }
+
+    /// Get latest ASB metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_latest_asb_metrics(&self) -> Result<Option<StoredAsbMetrics>> {
+        let mut result: Vec<StoredAsbMetrics> = self
+            .db
+            .query("SELECT * FROM asb_metrics ORDER BY timestamp DESC LIMIT 1")
+            .await
+            .context("Failed to query ASB metrics")?
+            .take(0)
+            .context("Failed to parse ASB metrics")?;
+
+        Ok(result.pop())
+    }
+
+    /// Get latest Electrs metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_latest_electrs_metrics(&self) -> Result<Option<StoredElectrsMetrics>> {
+        let mut result: Vec<StoredElectrsMetrics> = self
+            .db
+            .query("SELECT * FROM electrs_metrics ORDER BY timestamp DESC LIMIT 1")
+            .await
+            .context("Failed to query Electrs metrics")?
+            .take(0)
+            .context("Failed to parse Electrs metrics")?;
+
+        Ok(result.pop())
+    }
+
+    /// Get latest Container metrics for all containers
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_latest_container_metrics(&self) -> Result<Vec<StoredContainerMetrics>> {
+        // Get the latest timestamp
+        let latest: Vec<StoredContainerMetrics> = self
+            .db
+            .query(
+                "SELECT * FROM container_metrics
+                 WHERE timestamp = (SELECT VALUE timestamp FROM container_metrics ORDER BY timestamp DESC LIMIT 1)[0]",
+            )
+            .await
+            .context("Failed to query container metrics")?
+            .take(0)
+            .context("Failed to parse container metrics")?;
+
+        Ok(latest)
+    }
+
+    /// Get Bitcoin metrics history within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_bitcoin_history(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredBitcoinMetrics>> {
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM bitcoin_metrics WHERE timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query Bitcoin history")?
+            .take(0)
+            .context("Failed to read Bitcoin history")?;
+
+        self.history_rows(rows, "bitcoin_metrics")
+    }
+
+    /// Get Monero metrics history within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_monero_history(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredMoneroMetrics>> {
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM monero_metrics WHERE timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query Monero history")?
+            .take(0)
+            .context("Failed to read Monero history")?;
+
+        self.history_rows(rows, "monero_metrics")
+    }
+
+    /// Get ASB metrics history within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_asb_history(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredAsbMetrics>> {
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM asb_metrics WHERE timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query ASB history")?
+            .take(0)
+            .context("Failed to read ASB history")?;
+
+        self.history_rows(rows, "asb_metrics")
+    }
+
+    /// Get Electrs metrics history within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_electrs_history(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredElectrsMetrics>> {
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM electrs_metrics WHERE timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query Electrs history")?
+            .take(0)
+            .context("Failed to read Electrs history")?;
+
+        self.history_rows(rows, "electrs_metrics")
+    }
+
+    /// Get Container metrics history within time range for a specific container
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_container_history(
+        &self,
+        container_name: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredContainerMetrics>> {
+        let name = container_name.to_string();
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM container_metrics WHERE name = $name AND timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("name", name))
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query container history")?
+            .take(0)
+            .context("Failed to read container history")?;
+
+        self.history_rows(rows, "container_metrics")
+    }
+
+    /// Get metrics history for every container within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_all_container_history(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredContainerMetrics>> {
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM container_metrics WHERE timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query container history")?
+            .take(0)
+            .context("Failed to read container history")?;
+
+        self.history_rows(rows, "container_metrics")
+    }
+
+    /// Read one page of `table` within a time range, oldest first
+    ///
+    /// `table` is interpolated into the query and must be a fixed table name.
+    /// Pages are taken by offset, since rows of one collection share a
+    /// timestamp; ties are ordered by id so consecutive pages don't overlap.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn export_page<T: DeserializeOwned>(
+        &self,
+        table: &'static str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        start: usize,
+        limit: usize,
+    ) -> Result<Vec<T>> {
+        let rows: surrealdb::Value = self
+            .db
+            .query(format!(
+                "SELECT * FROM {table} WHERE timestamp >= $from AND timestamp <= $to \
+                 ORDER BY timestamp ASC, id ASC LIMIT $limit START $start"
+            ))
+            .bind(("from", from))
+            .bind(("to", to))
+            .bind(("limit", limit))
+            .bind(("start", start))
+            .await
+            .with_context(|| format!("Failed to query {} for export", table))?
+            .take(0)
+            .with_context(|| format!("Failed to read {} for export", table))?;
+
+        self.history_rows(rows, table)
+    }
+
+    /// Dump all metrics history within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn export_metrics(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<MetricsExport> {
+        Ok(MetricsExport {
+            bitcoin: self.get_bitcoin_history(from, to).await?,
+            monero: self.get_monero_history(from, to).await?,
+            asb: self.get_asb_history(from, to).await?,
+            electrs: self.get_electrs_history(from, to).await?,
+            containers: self.get_all_container_history(from, to).await?,
+        })
+    }
+
+    /// Bulk-insert Bitcoin metrics keeping their original timestamps
+    ///
+    /// Rows whose timestamp is already stored are skipped, so re-importing
+    /// the same history is a no-op. Returns the number of rows inserted.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn import_bitcoin_history(&self, rows: Vec<StoredBitcoinMetrics>) -> Result<usize> {
+        self.import_rows("bitcoin_metrics", rows, |row| row.timestamp, |_| None)
+            .await
+            .context("Failed to import Bitcoin history")
+    }
+
+    /// Bulk-insert Monero metrics keeping their original timestamps
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn import_monero_history(&self, rows: Vec<StoredMoneroMetrics>) -> Result<usize> {
+        self.import_rows("monero_metrics", rows, |row| row.timestamp, |_| None)
+            .await
+            .context("Failed to import Monero history")
+    }
+
+    /// Bulk-insert ASB metrics keeping their original timestamps
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn import_asb_history(&self, rows: Vec<StoredAsbMetrics>) -> Result<usize> {
+        self.import_rows("asb_metrics", rows, |row| row.timestamp, |_| None)
+            .await
+            .context("Failed to import ASB history")
+    }
+
+    /// Bulk-insert Electrs metrics keeping their original timestamps
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn import_electrs_history(&self, rows: Vec<StoredElectrsMetrics>) -> Result<usize> {
+        self.import_rows("electrs_metrics", rows, |row| row.timestamp, |_| None)
+            .await
+            .context("Failed to import Electrs history")
+    }
+
+    /// Bulk-insert container metrics keeping their original timestamps
+    ///
+    /// De-duplicated by container name and timestamp.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn import_container_history(
+        &self,
+        rows: Vec<StoredContainerMetrics>,
+    ) -> Result<usize> {
+        self.import_rows(
+            "container_metrics",
+            rows,
+            |row| row.timestamp,
+            |row| Some(row.name.clone()),
+        )
+        .await
+        .context("Failed to import container history")
+    }
+
+    /// Import every section of a metrics export
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn import_metrics(&self, export: MetricsExport) -> Result<ImportSummary> {
+        Ok(ImportSummary {
+            bitcoin: self.import_bitcoin_history(export.bitcoin).await?,
+            monero: self.import_monero_history(export.monero).await?,
+            asb: self.import_asb_history(export.asb).await?,
+            electrs: self.import_electrs_history(export.electrs).await?,
+            containers: self.import_container_history(export.containers).await?,
+        })
+    }
+
+    /// Insert the rows not already present in `table`, in a single statement
+    async fn import_rows<T>(
+        &self,
+        table: &str,
+        rows: Vec<T>,
+        timestamp: impl Fn(&T) -> DateTime<Utc>,
+        name: impl Fn(&T) -> Option<String>,
+    ) -> Result<usize>
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        let (Some(from), Some(to)) = (
+            rows.iter().map(&timestamp).min(),
+            rows.iter().map(&timestamp).max(),
+        ) else {
+            return Ok(0);
+        };
+
+        let existing: Vec<RowKey> = self
+            .db
+            .query(format!(
+                "SELECT timestamp, name FROM {} WHERE timestamp >= $from AND timestamp <= $to",
+                table
+            ))
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query existing rows")?
+            .take(0)
+            .context("Failed to parse existing rows")?;
+
+        let mut seen: HashSet<RowKey> = existing.into_iter().collect();
+        let new_rows: Vec<T> = rows
+            .into_iter()
+            .filter(|row| {
+                seen.insert(RowKey {
+                    timestamp: timestamp(row),
+                    name: name(row),
+                })
+            })
+            .collect();
+
+        if new_rows.is_empty() {
+            return Ok(0);
+        }
+
+        let inserted: Vec<T> = self
+            .db
+            .insert(table)
+            .content(new_rows)
+            .await
+            .context("Failed to insert rows")?;
+
+        Ok(inserted.len())
+    }
+
+    /// Get summary of all latest metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_summary(&self) -> Result<MetricsSummary> {
+        Ok(MetricsSummary {
+            bitcoin: self.get_latest_bitcoin_metrics().await?,
+            monero: self.get_latest_monero_metrics().await?,
+            asb: self.get_latest_asb_metrics().await?,
+            electrs: self.get_latest_electrs_metrics().await?,
+            containers: self.get_latest_container_metrics().await?,
+            disk: self.get_latest_disk_metrics().await?,
+            system: self.get_latest_system_metrics().await?,
+        })
+    }
+
+    /// Store a trading transaction
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_trading_transaction(
+        &self,
+        transaction: &StoredTradingTransaction,
+    ) -> Result<String> {
+        let _result: Option<StoredTradingTransaction> = self
+            .db
+            .create("trading_transactions")
+            .content(transaction.clone())
+            .await
+            .context("Failed to store trading transaction")?;
+
+        // The response doesn't include the id field due to skip_deserializing
+        // So we need to query it back or use a different approach
+        // For now, let's use a query that returns the id explicitly
+        let mut response = self
+            .db
+            .query("CREATE trading_transactions CONTENT $transaction RETURN VALUE meta::id(id)")
+            .bind(("transaction", transaction.clone()))
+            .await
+            .context("Failed to store trading transaction")?;
+
+        let ids: Vec<String> = response.take(0).context("Failed to get transaction ID")?;
+        let id_string = ids.into_iter().next().context("No ID returned")?;
+
+        Ok(id_string)
+    }
+
+    /// Update a trading transaction
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn update_trading_transaction(
+        &self,
+        id: &str,
+        transaction: &StoredTradingTransaction,
+    ) -> Result<()> {
+        let _: Option<StoredTradingTransaction> = self
+            .db
+            .update(("trading_transactions", id))
+            .content(transaction.clone())
+            .await
+            .context("Failed to update trading transaction")?;
+
+        Ok(())
+    }
+
+    /// Get a user's saved display preferences
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_preferences(&self, key: &str) -> Result<Option<UserPreferences>> {
+        let result: Option<UserPreferences> = self
+            .db
+            .select(("preferences", key))
+            .await
+            .context("Failed to get preferences")?;
+
+        Ok(result)
+    }
+
+    /// Create or replace a user's display preferences
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn save_preferences(&self, key: &str, preferences: &UserPreferences) -> Result<()> {
+        let _result: Option<UserPreferences> = self
+            .db
+            .upsert(("preferences", key))
+            .content(preferences.clone())
+            .await
+            .context("Failed to save preferences")?;
+
+        Ok(())
+    }
+
+    /// Current trading lock lease, if any
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_trading_lock(&self) -> Result<Option<TradingLease>> {
+        let result: Option<TradingLease> = self
+            .db
+            .select(("trading_lock", "current"))
+            .await
+            .context("Failed to read trading lock")?;
+
+        Ok(result)
+    }
+
+    /// Take or renew the trading lock lease in one conditional write
+    ///
+    /// Succeeds only if there is no lease, `lease.holder` already holds it or
+    /// it expired before `now`, so of two instances racing for the lock only
+    /// one gets it. A renewal keeps the original `acquired_at`.
+    ///
+    /// # Returns
+    /// The stored lease, or `None` if another instance holds a live one
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn take_trading_lock(
+        &self,
+        lease: &TradingLease,
+        now: DateTime<Utc>,
+    ) -> Result<Option<TradingLease>> {
+        // Assignments apply in order, so `acquired_at` still sees the old holder
+        let updated: Vec<TradingLease> = self
+            .db
+            .query(
+                "UPDATE trading_lock:current SET
+                    acquired_at = IF holder = $holder THEN acquired_at ELSE $acquired_at END,
+                    holder = $holder,
+                    expires_at = $expires_at
+                 WHERE holder = $holder OR expires_at <= $now",
+            )
+            .bind(("holder", lease.holder.clone()))
+            .bind(("acquired_at", lease.acquired_at))
+            .bind(("expires_at", lease.expires_at))
+            .bind(("now", now))
+            .await
+            .context("Failed to take trading lock")?
+            .take(0)
+            .context("Failed to take trading lock")?;
+        if let Some(stored) = updated.into_iter().next() {
+            return Ok(Some(stored));
+        }
+
+        // No lease to take over; creating one fails if another instance
+        // created it since
+        let created: surrealdb::Result<Vec<TradingLease>> = self
+            .db
+            .query("CREATE trading_lock:current CONTENT $lease")
+            .bind(("lease", lease.clone()))
+            .await
+            .context("Failed to create trading lock")?
+            .take(0);
+        match created {
+            Ok(rows) => Ok(rows.into_iter().next()),
+            Err(_) if self.get_trading_lock().await?.is_some() => Ok(None),
+            Err(e) => Err(e).context("Failed to create trading lock"),
+        }
+    }
+
+    /// Remove the trading lock lease if `holder` holds it
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn release_trading_lock(&self, holder: &str) -> Result<()> {
+        self.db
+            .query("DELETE trading_lock:current WHERE holder = $holder")
+            .bind(("holder", holder.to_string()))
+            .await
+            .context("Failed to release trading lock")?
+            .check()
+            .context("Failed to release trading lock")?;
+
+        Ok(())
+    }
+
+    /// Create or update an alert raised by a rule
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn save_alert(&self, alert: &StoredAlert) -> Result<()> {
+        let _result: Option<StoredAlert> = self
+            .db
+            .upsert(("alerts", alert.record_id()))
+            .content(alert.clone())
+            .await
+            .context("Failed to save alert")?;
+
+        Ok(())
+    }
+
+    /// Most recent alerts first, optionally only those in one state
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_alerts(
+        &self,
+        state: Option<AlertState>,
+        limit: usize,
+    ) -> Result<Vec<StoredAlert>> {
+        let query = match state {
+            Some(_) => {
+                "SELECT * FROM alerts WHERE state = $state ORDER BY started_at DESC LIMIT $limit"
+            }
+            None => "SELECT * FROM alerts ORDER BY started_at DESC LIMIT $limit",
+        };
+        let result: Vec<StoredAlert> = self
+            .db
+            .query(query)
+            .bind(("state", state))
+            .bind(("limit", limit))
+            .await
+            .context("Failed to query alerts")?
+            .take(0)
+            .context("Failed to parse alerts")?;
+
+        Ok(result)
+    }
+
+    /// Get a trading transaction by ID
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_trading_transaction(
+        &self,
+        id: &str,
+    ) -> Result<Option<StoredTradingTransaction>> {
+        let result: Option<StoredTradingTransaction> = self
+            .db
+            .select(("trading_transactions", id))
+            .await
+            .context("Failed to get trading transaction")?;
+
+        Ok(result)
+    }
+
+    /// Get all trading transactions within a time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_trading_transactions(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredTradingTransaction>> {
+        let result: Vec<StoredTradingTransaction> = self
+            .db
+            .query("SELECT * FROM trading_transactions WHERE timestamp >= $from AND timestamp <= $to ORDER BY timestamp DESC")
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query trading transactions")?
+            .take(0)
+            .context("Failed to parse trading transactions")?;
+
+        Ok(result)
+    }
+
+    /// Get recent trading transactions
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_recent_trading_transactions(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<StoredTradingTransaction>> {
+        let result: Vec<StoredTradingTransaction> = self
+            .db
+            .query("SELECT * FROM trading_transactions ORDER BY timestamp DESC LIMIT $limit")
+            .bind(("limit", limit))
+            .await
+            .context("Failed to query recent trading transactions")?
+            .take(0)
+            .context("Failed to parse trading transactions")?;
+
+        Ok(result)
+    }
+
+    /// Get trading transactions by status
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_trading_transactions_by_status(
+        &self,
+        status: TransactionStatus,
+    ) -> Result<Vec<StoredTradingTransaction>> {
+        let status_str = format!("{:?}", status);
+        let result: Vec<StoredTradingTransaction> = self
+            .db
+            .query(
+                "SELECT * FROM trading_transactions WHERE status = $status ORDER BY timestamp DESC",
+            )
+            .bind(("status", status_str))
+            .await
+            .context("Failed to query trading transactions by status")?
+            .take(0)
+            .context("Failed to parse trading transactions")?;
+
+        Ok(result)
+    }
+
+    /// Get trading transactions by type
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_trading_transactions_by_type(
+        &self,
+        transaction_type: TransactionType,
+    ) -> Result<Vec<StoredTradingTransaction>> {
+        let type_str = format!("{:?}", transaction_type);
+        let result: Vec<StoredTradingTransaction> = self
+            .db
+            .query("SELECT * FROM trading_transactions WHERE transaction_type = $type ORDER BY timestamp DESC")
+            .bind(("type", type_str))
+            .await
+            .context("Failed to query trading transactions by type")?
+            .take(0)
+            .context("Failed to parse trading transactions")?;
+
+        Ok(result)
+    }
+
+    /// Mark a transaction as completed
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn complete_trading_transaction(
+        &self,
+        id: &str,
+        xmr_amount: Option<f64>,
+        exchange_rate: Option<f64>,
+        fee: Option<f64>,
+    ) -> Result<()> {
+        let mut transaction = self
+            .get_trading_transaction(id)
+            .await?
+            .context("Transaction not found")?;
+
+        transaction.status = TransactionStatus::Completed;
+        transaction.completed_at = Some(Utc::now());
+        if let Some(amount) = xmr_amount {
+            transaction.xmr_amount = Some(amount);
+        }
+        if let Some(rate) = exchange_rate {
+            transaction.exchange_rate = Some(rate);
+        }
+        if let Some(fee) = fee {
+            transaction.fee = Some(fee);
+        }
+
+        self.update_trading_transaction(id, &transaction).await?;
+        Ok(())
+    }
+
+    /// Mark a transaction as failed
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn fail_trading_transaction(&self, id: &str, error_message: String) -> Result<()> {
+        let mut transaction = self
+            .get_trading_transaction(id)
+            .await?
+            .context("Transaction not found")?;
+
+        transaction.status = TransactionStatus::Failed;
+        transaction.error_message = Some(error_message);
+        transaction.completed_at = Some(Utc::now());
+
+        self.update_trading_transaction(id, &transaction).await?;
+        Ok(())
+    }
+
+    /// Mark a transaction as cancelled
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn cancel_trading_transaction(&self, id: &str, reason: String) -> Result<()> {
+        let mut transaction = self
+            .get_trading_transaction(id)
+            .await?
+            .context("Transaction not found")?;
+
+        transaction.status = TransactionStatus::Cancelled;
+        transaction.error_message = Some(reason);
+        transaction.completed_at = Some(Utc::now());
+
+        self.update_trading_transaction(id, &transaction).await?;
+        Ok(())
+    }
+
+    /// Execute a raw SurrealQL query and return each statement's result as JSON
+    ///
+    /// Callers are responsible for vetting the query; see `routes::admin`.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn query_json(&self, query: &str) -> Result<Vec<serde_json::Value>> {
+        let mut response = self
+            .db
+            .query(query)
+            .await
+            .context("Failed to execute query")?;
+
+        (0..response.num_statements())
+            .map(|index| {
+                let value: surrealdb::Value = response
+                    .take(index)
+                    .with_context(|| format!("Statement {} failed", index))?;
+                Ok(value.into_inner().into_json())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rows_skips_malformed_rows() {
+        let rows = surrealdb::sql::to_value(serde_json::json!([
+            {"timestamp": "2025-01-01T00:00:00Z", "blocks": 1, "headers": 1,
+             "verification_progress": 1.0, "size_on_disk": 10, "wallet_balance": null},
+            // Written by an older schema without `headers`
+            {"timestamp": "2025-01-01T00:01:00Z", "blocks": 2,
+             "verification_progress": 1.0, "size_on_disk": 10},
+            {"timestamp": "2025-01-01T00:02:00Z", "blocks": 3, "headers": 3,
+             "verification_progress": 1.0, "size_on_disk": 10, "wallet_balance": 0.5},
+        ]))
+        .unwrap();
+
+        let (parsed, skipped) =
+            parse_rows::<StoredBitcoinMetrics>(rows.clone(), "bitcoin_metrics", true).unwrap();
+        let blocks: Vec<u64> = parsed.iter().map(|row| row.blocks).collect();
+        assert_eq!(blocks, vec![1, 3]);
+        assert_eq!(skipped, 1);
+
+        // Strict mode keeps the old all-or-nothing behaviour
+        let err = parse_rows::<StoredBitcoinMetrics>(rows, "bitcoin_metrics", false).unwrap_err();
+        assert!(err.to_string().contains("bitcoin_metrics"));
+    }
+}
@@ -0,0 +1,44 @@
+//! Portfolio snapshots
+//!
+//! One `portfolio_snapshots` row per valuation, holdings included, so the
+//! dashboard can chart total value over time.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+use super::MetricsDatabase;
+use crate::portfolio::Portfolio;
+
+impl MetricsDatabase {
+    /// Store a portfolio snapshot
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_portfolio_snapshot(&self, portfolio: &Portfolio) -> Result<()> {
+        self.store_rows(
+            "portfolio_snapshots",
+            |rows| &mut rows.portfolio,
+            vec![portfolio.clone()],
+        )
+        .await
+        .context("Failed to store portfolio snapshot")
+    }
+
+    /// Get portfolio snapshots within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_portfolio_history(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<Portfolio>> {
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM portfolio_snapshots WHERE timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query portfolio history")?
+            .take(0)
+            .context("Failed to read portfolio history")?;
+
+        self.history_rows(rows, "portfolio_snapshots")
+    }
+}
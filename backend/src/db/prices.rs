@@ -0,0 +1,161 @@
+//! Exchange rate samples
+//!
+//! The collector writes one `price_metrics` row per cycle. [`PriceCache`]
+//! serves the latest row to the API, so the dashboard's ticker reads our own
+//! database instead of calling Kraken on every request.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use utoipa::ToSchema;
+
+use super::MetricsDatabase;
+use crate::metrics::PriceMetrics;
+use crate::services::KrakenClient;
+
+/// Seconds a price read from the database is served before reading it again
+const PRICE_CACHE_TTL_SECS: i64 = 10;
+
+/// Database-stored prices with timestamp
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, async_graphql::SimpleObject)]
+#[graphql(name = "PriceMetrics")]
+pub struct StoredPriceMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub btc_usd: f64,
+    pub btc_usd_change_24h: f64,
+    pub xmr_usd: f64,
+    pub xmr_usd_change_24h: f64,
+    pub xmr_btc: f64,
+    pub xmr_btc_change_24h: f64,
+}
+
+impl StoredPriceMetrics {
+    pub fn new(timestamp: DateTime<Utc>, prices: &PriceMetrics) -> Self {
+        Self {
+            timestamp,
+            btc_usd: prices.btc_usd,
+            btc_usd_change_24h: prices.btc_usd_change_24h,
+            xmr_usd: prices.xmr_usd,
+            xmr_usd_change_24h: prices.xmr_usd_change_24h,
+            xmr_btc: prices.xmr_btc,
+            xmr_btc_change_24h: prices.xmr_btc_change_24h,
+        }
+    }
+}
+
+impl MetricsDatabase {
+    /// Store prices
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_price_metrics(&self, prices: &PriceMetrics) -> Result<()> {
+        let stored = StoredPriceMetrics::new(Utc::now(), prices);
+
+        self.store_rows("price_metrics", |rows| &mut rows.prices, vec![stored])
+            .await
+            .context("Failed to store price metrics")
+    }
+
+    /// Get latest prices
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_latest_price_metrics(&self) -> Result<Option<StoredPriceMetrics>> {
+        let mut result: Vec<StoredPriceMetrics> = self
+            .db
+            .query("SELECT * FROM price_metrics ORDER BY timestamp DESC LIMIT 1")
+            .await
+            .context("Failed to query price metrics")?
+            .take(0)
+            .context("Failed to parse price metrics")?;
+
+        Ok(result.pop())
+    }
+
+    /// Get price history within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_price_history(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredPriceMetrics>> {
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM price_metrics WHERE timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query price history")?
+            .take(0)
+            .context("Failed to read price history")?;
+
+        self.history_rows(rows, "price_metrics")
+    }
+}
+
+/// Prices held by a [`PriceCache`] and when they were cached
+#[derive(Clone)]
+struct CachedPrices {
+    cached_at: DateTime<Utc>,
+    prices: StoredPriceMetrics,
+}
+
+/// The latest prices, read from the database at most every
+/// [`PRICE_CACHE_TTL_SECS`]
+#[derive(Clone)]
+pub struct PriceCache {
+    db: MetricsDatabase,
+    cached: Arc<RwLock<Option<CachedPrices>>>,
+}
+
+impl PriceCache {
+    pub fn new(db: MetricsDatabase) -> Self {
+        Self {
+            db,
+            cached: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Latest prices, `None` before the first collection
+    pub async fn latest(&self) -> Result<Option<StoredPriceMetrics>> {
+        let cached = self.cached.read().unwrap().clone();
+        if let Some(cached) = cached {
+            if Utc::now() - cached.cached_at < Duration::seconds(PRICE_CACHE_TTL_SECS) {
+                return Ok(Some(cached.prices));
+            }
+        }
+
+        let latest = self.db.get_latest_price_metrics().await?;
+        if let Some(prices) = &latest {
+            self.put(prices.clone());
+        }
+        Ok(latest)
+    }
+
+    /// Latest prices no older than `max_age`
+    ///
+    /// Falls back to asking Kraken when nothing that recent was collected,
+    /// e.g. with the `prices` collector turned off.
+    pub async fn current(&self, max_age: Duration) -> Result<StoredPriceMetrics> {
+        if let Some(prices) = self.latest().await? {
+            if Utc::now() - prices.timestamp <= max_age {
+                return Ok(prices);
+            }
+        }
+
+        tracing::info!("No recent stored prices, fetching Kraken tickers...");
+        // Tickers are public, no credentials needed
+        let prices = KrakenClient::public()
+            .get_prices()
+            .await
+            .context("Failed to get Kraken tickers")?;
+        let prices = StoredPriceMetrics::new(Utc::now(), &prices);
+        self.put(prices.clone());
+        Ok(prices)
+    }
+
+    /// Serve `prices` until the cache next expires
+    fn put(&self, prices: StoredPriceMetrics) {
+        *self.cached.write().unwrap() = Some(CachedPrices {
+            cached_at: Utc::now(),
+            prices,
+        });
+    }
+}
@@ -0,0 +1,65 @@
+//! ASB quote samples
+//!
+//! One `asb_quotes` row per ASB collection while the ASB is up, including
+//! cycles where it offered nothing, so gaps in quoting show up as rows with
+//! `quoting` false rather than as missing data.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::MetricsDatabase;
+use crate::metrics::AsbQuoteMetrics;
+
+/// Database-stored ASB quote with timestamp
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StoredAsbQuote {
+    pub timestamp: DateTime<Utc>,
+    pub quoting: bool,
+    pub price: Option<f64>,
+    pub min_quantity: Option<f64>,
+    pub max_quantity: Option<f64>,
+    pub market_price: Option<f64>,
+    pub spread_percent: Option<f64>,
+}
+
+impl MetricsDatabase {
+    /// Store an ASB quote
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_asb_quote(&self, metrics: &AsbQuoteMetrics) -> Result<()> {
+        let stored = StoredAsbQuote {
+            timestamp: Utc::now(),
+            quoting: metrics.quoting,
+            price: metrics.price,
+            min_quantity: metrics.min_quantity,
+            max_quantity: metrics.max_quantity,
+            market_price: metrics.market_price,
+            spread_percent: metrics.spread_percent,
+        };
+
+        self.store_rows("asb_quotes", |rows| &mut rows.quotes, vec![stored])
+            .await
+            .context("Failed to store ASB quote")
+    }
+
+    /// Get ASB quote history within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_asb_quote_history(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredAsbQuote>> {
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM asb_quotes WHERE timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query ASB quote history")?
+            .take(0)
+            .context("Failed to read ASB quote history")?;
+
+        self.history_rows(rows, "asb_quotes")
+    }
+}
@@ -0,0 +1,70 @@
+//! Sends out of the managed wallets
+//!
+//! One `spends` row per send, read back as the rolling total the daily spend
+//! limit is checked against. A row is written before its send goes out and
+//! corrected or removed afterwards.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+use super::MetricsDatabase;
+use crate::wallets::limits::SpendRecord;
+use crate::wallets::transfer::TransferAsset;
+
+impl MetricsDatabase {
+    /// Record a send, returning the row's id
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_spend(&self, record: &SpendRecord) -> Result<String> {
+        let ids: Vec<String> = self
+            .db
+            .query("CREATE spends CONTENT $record RETURN VALUE meta::id(id)")
+            .bind(("record", record.clone()))
+            .await
+            .context("Failed to store spend")?
+            .take(0)
+            .context("Failed to store spend")?;
+
+        ids.into_iter().next().context("No spend ID returned")
+    }
+
+    /// Replace the send recorded as `id`
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn update_spend(&self, id: &str, record: &SpendRecord) -> Result<()> {
+        let _result: Option<SpendRecord> = self
+            .db
+            .update(("spends", id))
+            .content(record.clone())
+            .await
+            .context("Failed to update spend")?;
+
+        Ok(())
+    }
+
+    /// Remove the send recorded as `id`, for one that never went out
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn delete_spend(&self, id: &str) -> Result<()> {
+        let _result: Option<SpendRecord> = self
+            .db
+            .delete(("spends", id))
+            .await
+            .context("Failed to delete spend")?;
+
+        Ok(())
+    }
+
+    /// Total of `asset` sent since `since`
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_spent_since(&self, asset: TransferAsset, since: DateTime<Utc>) -> Result<f64> {
+        let amounts: Vec<f64> = self
+            .db
+            .query("SELECT VALUE amount FROM spends WHERE asset = $asset AND timestamp > $since")
+            .bind(("asset", asset))
+            .bind(("since", since))
+            .await
+            .context("Failed to query spends")?
+            .take(0)
+            .context("Failed to parse spends")?;
+
+        Ok(amounts.into_iter().sum())
+    }
+}
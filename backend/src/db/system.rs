@@ -0,0 +1,106 @@
+//! Host resource usage samples
+//!
+//! One `system_metrics` row per collection, for capacity planning on the
+//! deployment box.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::MetricsDatabase;
+use crate::metrics::SystemMetrics;
+
+/// Database-stored host metrics with timestamp
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, async_graphql::SimpleObject)]
+#[graphql(name = "SystemMetrics")]
+pub struct StoredSystemMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub cpu_count: u64,
+    pub cpu_percent: Option<f64>,
+    pub load_1: f64,
+    pub load_5: f64,
+    pub load_15: f64,
+    pub memory_total_bytes: u64,
+    pub memory_used_bytes: u64,
+    pub memory_available_bytes: u64,
+    /// Share of memory in use, in percent
+    pub memory_used_percent: f64,
+    pub swap_total_bytes: u64,
+    pub swap_used_bytes: u64,
+    pub net_rx_bytes: u64,
+    pub net_tx_bytes: u64,
+    pub net_rx_bytes_per_sec: Option<f64>,
+    pub net_tx_bytes_per_sec: Option<f64>,
+    pub uptime_secs: u64,
+}
+
+impl StoredSystemMetrics {
+    pub fn new(timestamp: DateTime<Utc>, metrics: &SystemMetrics) -> Self {
+        Self {
+            timestamp,
+            cpu_count: metrics.cpu_count,
+            cpu_percent: metrics.cpu_percent,
+            load_1: metrics.load_1,
+            load_5: metrics.load_5,
+            load_15: metrics.load_15,
+            memory_total_bytes: metrics.memory_total_bytes,
+            memory_used_bytes: metrics.memory_used_bytes,
+            memory_available_bytes: metrics.memory_available_bytes,
+            memory_used_percent: metrics.memory_used_percent(),
+            swap_total_bytes: metrics.swap_total_bytes,
+            swap_used_bytes: metrics.swap_used_bytes,
+            net_rx_bytes: metrics.net_rx_bytes,
+            net_tx_bytes: metrics.net_tx_bytes,
+            net_rx_bytes_per_sec: metrics.net_rx_bytes_per_sec,
+            net_tx_bytes_per_sec: metrics.net_tx_bytes_per_sec,
+            uptime_secs: metrics.uptime_secs,
+        }
+    }
+}
+
+impl MetricsDatabase {
+    /// Store host metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn store_system_metrics(&self, metrics: &SystemMetrics) -> Result<()> {
+        let stored = StoredSystemMetrics::new(Utc::now(), metrics);
+
+        self.store_rows("system_metrics", |rows| &mut rows.system, vec![stored])
+            .await
+            .context("Failed to store system metrics")
+    }
+
+    /// Get latest host metrics
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_latest_system_metrics(&self) -> Result<Option<StoredSystemMetrics>> {
+        let mut result: Vec<StoredSystemMetrics> = self
+            .db
+            .query("SELECT * FROM system_metrics ORDER BY timestamp DESC LIMIT 1")
+            .await
+            .context("Failed to query system metrics")?
+            .take(0)
+            .context("Failed to parse system metrics")?;
+
+        Ok(result.pop())
+    }
+
+    /// Get host metrics history within time range
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn get_system_history(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StoredSystemMetrics>> {
+        let rows: surrealdb::Value = self
+            .db
+            .query("SELECT * FROM system_metrics WHERE timestamp >= $from AND timestamp <= $to ORDER BY timestamp ASC")
+            .bind(("from", from))
+            .bind(("to", to))
+            .await
+            .context("Failed to query system history")?
+            .take(0)
+            .context("Failed to read system history")?;
+
+        self.history_rows(rows, "system_metrics")
+    }
+}
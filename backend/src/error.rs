@@ -4,16 +4,18 @@
 //! for proper HTTP error responses in Axum handlers.
 
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::Serialize;
 use std::fmt;
+use std::time::Duration;
+use utoipa::ToSchema;
 
 /// API error response sent to clients
-#[derive(Serialize)]
-struct ErrorResponse {
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
     error: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     details: Option<String>,
@@ -32,6 +34,16 @@ pub enum ApiError {
     NotFound(String),
     /// Invalid input/request
     BadRequest(String),
+    /// Missing or invalid credentials
+    Unauthorized(String),
+    /// Authenticated, but the operation is disabled
+    Forbidden(String),
+    /// Clashes with a request that is still in progress
+    Conflict(String),
+    /// The client is over its request budget; retry after the given wait
+    RateLimited(Duration),
+    /// An upstream service could not be reached
+    ServiceUnavailable(anyhow::Error),
     /// Internal server error
     Internal(anyhow::Error),
 }
@@ -44,6 +56,13 @@ impl fmt::Display for ApiError {
             ApiError::Metrics(e) => write!(f, "Metrics error: {}", e),
             ApiError::NotFound(msg) => write!(f, "Not found: {}", msg),
             ApiError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
+            ApiError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            ApiError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
+            ApiError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            ApiError::RateLimited(wait) => {
+                write!(f, "Rate limited: retry in {}s", retry_after_secs(*wait))
+            }
+            ApiError::ServiceUnavailable(e) => write!(f, "Service unavailable: {}", e),
             ApiError::Internal(e) => write!(f, "Internal error: {}", e),
         }
     }
@@ -55,14 +74,25 @@ impl std::error::Error for ApiError {
             ApiError::Database(e)
             | ApiError::Wallet(e)
             | ApiError::Metrics(e)
+            | ApiError::ServiceUnavailable(e)
             | ApiError::Internal(e) => e.source(),
-            ApiError::NotFound(_) | ApiError::BadRequest(_) => None,
+            ApiError::NotFound(_)
+            | ApiError::BadRequest(_)
+            | ApiError::Unauthorized(_)
+            | ApiError::Forbidden(_)
+            | ApiError::Conflict(_)
+            | ApiError::RateLimited(_) => None,
         }
     }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        let retry_after = match &self {
+            ApiError::RateLimited(wait) => Some(retry_after_secs(*wait)),
+            _ => None,
+        };
+
         let (status, error_message, details) = match self {
             ApiError::Database(e) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -89,6 +119,23 @@ impl IntoResponse for ApiError {
                 "Bad request".to_string(),
                 Some(msg),
             ),
+            ApiError::Unauthorized(msg) => (
+                StatusCode::UNAUTHORIZED,
+                "Unauthorized".to_string(),
+                Some(msg),
+            ),
+            ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, "Forbidden".to_string(), Some(msg)),
+            ApiError::Conflict(msg) => (StatusCode::CONFLICT, "Conflict".to_string(), Some(msg)),
+            ApiError::RateLimited(wait) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many requests".to_string(),
+                Some(format!("Retry in {}s", retry_after_secs(wait))),
+            ),
+            ApiError::ServiceUnavailable(e) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Service unavailable".to_string(),
+                Some(format!("{:#}", e)),
+            ),
             ApiError::Internal(e) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".to_string(),
@@ -96,18 +143,28 @@ impl IntoResponse for ApiError {
             ),
         };
 
-        // Log the error
-        tracing::error!("{}: {:?}", error_message, details);
+        // Log the error; a client over its budget is routine
+        if retry_after.is_none() {
+            tracing::error!("{}: {:?}", error_message, details);
+        }
 
         let body = Json(ErrorResponse {
             error: error_message,
             details,
         });
 
-        (status, body).into_response()
+        match retry_after {
+            Some(secs) => (status, [(header::RETRY_AFTER, secs.to_string())], body).into_response(),
+            None => (status, body).into_response(),
+        }
     }
 }
 
+/// Whole seconds for `Retry-After`, rounded up so clients never retry early
+fn retry_after_secs(wait: Duration) -> u64 {
+    wait.as_secs() + u64::from(wait.subsec_nanos() > 0)
+}
+
 /// Convenience result type for API handlers
 pub type ApiResult<T> = Result<T, ApiError>;
 
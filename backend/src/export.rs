@@ -0,0 +1,293 @@
+//! CSV and NDJSON exports of stored history
+//!
+//! Rows are read a page at a time and encoded as they go out, so a long
+//! window never sits in memory whole. Any serializable row works: a row is
+//! turned into a JSON object first, NDJSON writes that object as one line,
+//! CSV writes its fields as cells under a header taken from the first row.
+
+use anyhow::{bail, Result};
+use axum::{
+    body::Body,
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Duration, Utc};
+use futures_util::stream;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{ApiError, ApiResult, MetricsDatabase};
+
+/// Rows read from the database per page
+const PAGE_SIZE: usize = 1_000;
+
+/// Encoding of an export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+}
+
+impl ExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+/// Query parameters for export endpoints
+///
+/// `from` and `to` are RFC3339 and default to the last 24 hours.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl ExportQuery {
+    pub fn window(&self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        let to = self.to.unwrap_or(now);
+        (self.from.unwrap_or(to - Duration::hours(24)), to)
+    }
+}
+
+/// Download name for an export of `stem` over `[from, to]`
+pub fn file_name(
+    stem: &str,
+    format: ExportFormat,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> String {
+    format!(
+        "{}-{}-{}.{}",
+        stem,
+        from.format("%Y%m%dT%H%M%SZ"),
+        to.format("%Y%m%dT%H%M%SZ"),
+        format.extension()
+    )
+}
+
+/// Quote a CSV cell if it holds a separator, quote or line break
+fn csv_cell(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Text of a field as a CSV cell; nested values are written as JSON
+fn csv_value(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => csv_cell(s),
+        Some(value @ (Value::Bool(_) | Value::Number(_))) => value.to_string(),
+        Some(value) => csv_cell(&value.to_string()),
+    }
+}
+
+/// Encodes pages of rows, remembering the CSV columns between pages
+pub struct Encoder {
+    format: ExportFormat,
+    columns: Option<Vec<String>>,
+}
+
+impl Encoder {
+    pub fn new(format: ExportFormat) -> Self {
+        Self {
+            format,
+            columns: None,
+        }
+    }
+
+    /// Encode one page of rows; for CSV the header goes before the first row
+    pub fn encode<T: Serialize>(&mut self, rows: &[T]) -> Result<Vec<u8>> {
+        let mut out = String::new();
+        for row in rows {
+            let Value::Object(fields) = serde_json::to_value(row)? else {
+                bail!("Exported rows must serialize to objects");
+            };
+
+            match self.format {
+                ExportFormat::Ndjson => {
+                    out.push_str(&Value::Object(fields).to_string());
+                    out.push('\n');
+                }
+                ExportFormat::Csv => {
+                    let columns = self.columns.get_or_insert_with(|| {
+                        let columns: Vec<String> = fields.keys().cloned().collect();
+                        let header: Vec<String> = columns.iter().map(|c| csv_cell(c)).collect();
+                        out.push_str(&header.join(","));
+                        out.push_str("\r\n");
+                        columns
+                    });
+                    let cells: Vec<String> = columns
+                        .iter()
+                        .map(|column| csv_value(fields.get(column)))
+                        .collect();
+                    out.push_str(&cells.join(","));
+                    out.push_str("\r\n");
+                }
+            }
+        }
+        Ok(out.into_bytes())
+    }
+}
+
+/// Stream every row of `table` in `[from, to]` as a file download
+///
+/// The first page is read before responding, so a database that is down
+/// gets an error status; a failure on a later page cuts the download short.
+pub async fn download<T>(
+    db: MetricsDatabase,
+    table: &'static str,
+    stem: &str,
+    format: ExportFormat,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> ApiResult<Response>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+{
+    if from > to {
+        return Err(ApiError::BadRequest(
+            "from must not be after to".to_string(),
+        ));
+    }
+
+    let first: Vec<T> = db
+        .export_page(table, from, to, 0, PAGE_SIZE)
+        .await
+        .map_err(ApiError::Database)?;
+
+    let pages = stream::try_unfold(Some((first, 0, Encoder::new(format))), move |page| {
+        let db = db.clone();
+        async move {
+            let Some((rows, start, mut encoder)) = page else {
+                return Ok(None);
+            };
+            let chunk = encoder.encode(&rows)?;
+            let next = if rows.len() < PAGE_SIZE {
+                None
+            } else {
+                let start = start + rows.len();
+                let rows: Vec<T> = db
+                    .export_page(table, from, to, start, PAGE_SIZE)
+                    .await
+                    .inspect_err(|e| tracing::error!("Export of {} failed: {:#}", table, e))?;
+                Some((rows, start, encoder))
+            };
+            Ok::<_, anyhow::Error>(Some((chunk, next)))
+        }
+    });
+
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        file_name(stem, format, from, to)
+    );
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(format.content_type()),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                HeaderValue::from_str(&disposition).map_err(|e| ApiError::Internal(e.into()))?,
+            ),
+        ],
+        Body::from_stream(pages),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[derive(Serialize)]
+    struct Row {
+        name: &'static str,
+        value: Option<f64>,
+        tags: Vec<&'static str>,
+    }
+
+    fn rows() -> Vec<Row> {
+        vec![
+            Row {
+                name: "plain",
+                value: Some(1.5),
+                tags: vec![],
+            },
+            Row {
+                name: "has, comma and \"quotes\"",
+                value: None,
+                tags: vec!["a", "b"],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_csv_header_and_escaping() {
+        let mut encoder = Encoder::new(ExportFormat::Csv);
+        let first = encoder.encode(&rows()[..1]).unwrap();
+        let rest = encoder.encode(&rows()[1..]).unwrap();
+
+        let first = String::from_utf8(first).unwrap();
+        let mut columns: Vec<&str> = first.lines().next().unwrap().split(',').collect();
+        columns.sort();
+        assert_eq!(columns, vec!["name", "tags", "value"]);
+
+        // Later pages reuse the header's column order without repeating it
+        let rest = String::from_utf8(rest).unwrap();
+        assert_eq!(rest.lines().count(), 1);
+        assert!(rest.contains("\"has, comma and \"\"quotes\"\"\""));
+        assert!(rest.contains("\"[\"\"a\"\",\"\"b\"\"]\""));
+        assert!(rest.ends_with("\r\n"));
+    }
+
+    #[test]
+    fn test_ndjson_writes_one_object_per_line() {
+        let out = Encoder::new(ExportFormat::Ndjson).encode(&rows()).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let lines: Vec<Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["value"], 1.5);
+        assert_eq!(lines[1]["value"], Value::Null);
+    }
+
+    #[test]
+    fn test_file_name() {
+        let from = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2024, 6, 2, 12, 30, 0).unwrap();
+        assert_eq!(
+            file_name("bitcoin-metrics", ExportFormat::Csv, from, to),
+            "bitcoin-metrics-20240601T000000Z-20240602T123000Z.csv"
+        );
+        assert_eq!(
+            file_name("trading-transactions", ExportFormat::Ndjson, from, to),
+            "trading-transactions-20240601T000000Z-20240602T123000Z.ndjson"
+        );
+    }
+}
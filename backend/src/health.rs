@@ -0,0 +1,319 @@
+//! Readiness and liveness probes
+//!
+//! `/health` checks every dependency the API serves from, concurrently and
+//! each under a short timeout, and classifies the result: `unhealthy` (503)
+//! when one the API can't work without is down, `degraded` when only one that
+//! some endpoints need is, `healthy` otherwise. A dependency's circuit
+//! breaker state is reported alongside but doesn't change its classification.
+//! `/health/live` touches nothing outside the process and only says it is
+//! still answering.
+//!
+//! [`probe_services`] runs similar checks from outside a running backend, for
+//! `eigenix health` when the backend itself is down.
+
+use anyhow::Result;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Instant;
+use tokio::time::{timeout, Duration};
+use utoipa::ToSchema;
+
+use crate::alerts::clock_skew::ClockSkew;
+use crate::config::{Config, DatabaseMode, EnabledFeatures};
+use crate::db::MetricsDatabase;
+use crate::services::breaker::{self, CircuitState, CircuitStatus};
+use crate::services::{AsbClient, BitcoinRpcClient, KrakenClient, MoneroRpcClient};
+use crate::AppState;
+
+/// Maximum time a single dependency check may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Overall classification of the service
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    /// A dependency some endpoints need is down
+    Degraded,
+    /// A dependency the API can't work without is down
+    Unhealthy,
+}
+
+/// Result of checking one dependency
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DependencyHealth {
+    pub name: String,
+    /// Whether the service is unhealthy while this dependency is down
+    pub critical: bool,
+    pub up: bool,
+    pub latency_ms: u64,
+    /// What the check saw, or why it failed
+    pub detail: String,
+    /// State of the client's circuit breaker, for dependencies called through
+    /// one; informational only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit: Option<CircuitState>,
+}
+
+impl DependencyHealth {
+    /// Status this dependency alone puts the service in
+    fn status(&self) -> HealthStatus {
+        match (self.up, self.critical) {
+            (true, _) => HealthStatus::Healthy,
+            (false, false) => HealthStatus::Degraded,
+            (false, true) => HealthStatus::Unhealthy,
+        }
+    }
+}
+
+/// Worst status among the dependencies
+pub fn classify(dependencies: &[DependencyHealth]) -> HealthStatus {
+    if dependencies
+        .iter()
+        .any(|dep| dep.status() == HealthStatus::Unhealthy)
+    {
+        HealthStatus::Unhealthy
+    } else if dependencies
+        .iter()
+        .any(|dep| dep.status() == HealthStatus::Degraded)
+    {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Healthy
+    }
+}
+
+/// Time a check under [`CHECK_TIMEOUT`], turning errors into a down result
+async fn check<F>(name: &str, critical: bool, fut: F) -> DependencyHealth
+where
+    F: Future<Output = Result<String>>,
+{
+    let started = Instant::now();
+    let (up, detail) = match timeout(CHECK_TIMEOUT, fut).await {
+        Ok(Ok(detail)) => (true, detail),
+        Ok(Err(e)) => (false, format!("{:#}", e)),
+        Err(_) => (
+            false,
+            format!("timed out after {}s", CHECK_TIMEOUT.as_secs()),
+        ),
+    };
+
+    DependencyHealth {
+        name: name.to_string(),
+        critical,
+        up,
+        latency_ms: started.elapsed().as_millis() as u64,
+        detail,
+        circuit: breaker::global().state(name, Utc::now()),
+    }
+}
+
+/// Check every dependency concurrently
+///
+/// The database and both wallets back most endpoints and the trading engine,
+/// so they are critical; the ASB and Kraken only back their own endpoints.
+pub async fn check_dependencies(state: &AppState) -> Vec<DependencyHealth> {
+    let config = state.config.get();
+
+    let (database, bitcoind, monero_wallet, asb, kraken) = tokio::join!(
+        check("database", true, async {
+            state.db.ping().await?;
+            Ok(match config.database.mode {
+                DatabaseMode::Remote => format!("connected to {}", config.database.endpoint),
+                DatabaseMode::Embedded => "embedded".to_string(),
+            })
+        }),
+        check("bitcoind", true, async {
+            let client =
+                BitcoinRpcClient::new(config.bitcoin.rpc_url.clone(), &config.bitcoin.cookie_path)?;
+            let metrics = client.get_metrics().await?;
+            Ok(format!("height {}/{}", metrics.blocks, metrics.headers))
+        }),
+        check("monero-wallet-rpc", true, async {
+            let height = state.wallets.monero.get_height().await?;
+            Ok(format!("wallet at height {}", height))
+        }),
+        check("asb", false, async {
+            AsbClient::new(config.asb.rpc_url.clone())
+                .check_connection()
+                .await?;
+            Ok(format!("reachable at {}", config.asb.rpc_url))
+        }),
+        check("kraken", false, async {
+            let server_time = KrakenClient::public().get_server_time().await?;
+            Ok(format!("server time {}", server_time.to_rfc3339()))
+        }),
+    );
+
+    vec![database, bitcoind, monero_wallet, asb, kraken]
+}
+
+/// Check the services `config` points at directly, without a running backend
+///
+/// The database, bitcoind and monerod are critical, as the backend can't
+/// serve without them. monerod stands in for the wallet RPC, which only the
+/// backend keeps a wallet open in.
+pub async fn probe_services(config: &Config) -> Vec<DependencyHealth> {
+    let (database, bitcoind, monerod, asb, kraken) = tokio::join!(
+        check("database", true, async {
+            MetricsDatabase::open(&config.database)
+                .await?
+                .ping()
+                .await?;
+            Ok(match config.database.mode {
+                DatabaseMode::Remote => format!("connected to {}", config.database.endpoint),
+                DatabaseMode::Embedded => "embedded".to_string(),
+            })
+        }),
+        check("bitcoind", true, async {
+            let client =
+                BitcoinRpcClient::new(config.bitcoin.rpc_url.clone(), &config.bitcoin.cookie_path)?;
+            let metrics = client.get_metrics().await?;
+            Ok(format!("height {}/{}", metrics.blocks, metrics.headers))
+        }),
+        check("monerod", true, async {
+            let metrics = MoneroRpcClient::new(config.monero.rpc_url.clone())
+                .get_metrics()
+                .await?;
+            Ok(format!(
+                "height {}/{}",
+                metrics.height, metrics.target_height
+            ))
+        }),
+        check("asb", false, async {
+            AsbClient::new(config.asb.rpc_url.clone())
+                .check_connection()
+                .await?;
+            Ok(format!("reachable at {}", config.asb.rpc_url))
+        }),
+        check("kraken", false, async {
+            let server_time = KrakenClient::public().get_server_time().await?;
+            Ok(format!("server time {}", server_time.to_rfc3339()))
+        }),
+    );
+
+    vec![database, bitcoind, monerod, asb, kraken]
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Health {
+    pub status: HealthStatus,
+    pub version: String,
+    /// Host clock minus Kraken's, if the startup check succeeded
+    pub clock_skew: Option<ClockSkew>,
+    pub enabled_features: EnabledFeatures,
+    pub dependencies: Vec<DependencyHealth>,
+    /// Circuit breakers of every client that has made a call
+    pub circuits: Vec<CircuitStatus>,
+}
+
+/// Readiness probe: 200 while healthy or degraded, 503 when unhealthy
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "All dependencies reachable", body = Health),
+        (status = 503, description = "A dependency is down", body = Health),
+    )
+)]
+pub async fn health(State(state): State<AppState>) -> impl IntoResponse {
+    let dependencies = check_dependencies(&state).await;
+    let status = classify(&dependencies);
+
+    let code = match status {
+        HealthStatus::Healthy | HealthStatus::Degraded => StatusCode::OK,
+        HealthStatus::Unhealthy => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (
+        code,
+        Json(Health {
+            status,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            clock_skew: state.clock_skew.last(),
+            enabled_features: state.config.get().enabled_features(),
+            dependencies,
+            circuits: breaker::global().status(Utc::now()),
+        }),
+    )
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Liveness {
+    pub status: &'static str,
+    pub version: &'static str,
+}
+
+/// Liveness probe: answers as long as the process serves requests
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses(
+        (status = 200, description = "The process is up", body = Liveness),
+    )
+)]
+pub async fn live() -> Json<Liveness> {
+    Json(Liveness {
+        status: "alive",
+        version: env!("CARGO_PKG_VERSION"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(name: &str, critical: bool, up: bool) -> DependencyHealth {
+        DependencyHealth {
+            name: name.to_string(),
+            critical,
+            up,
+            latency_ms: 1,
+            detail: String::new(),
+            circuit: None,
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        let mut dependencies = vec![
+            dependency("database", true, true),
+            dependency("bitcoind", true, true),
+            dependency("kraken", false, true),
+        ];
+        assert_eq!(classify(&dependencies), HealthStatus::Healthy);
+
+        dependencies[2].up = false;
+        assert_eq!(classify(&dependencies), HealthStatus::Degraded);
+
+        dependencies[1].up = false;
+        assert_eq!(classify(&dependencies), HealthStatus::Unhealthy);
+
+        // An open breaker doesn't make a critical dependency any less down
+        dependencies[1].circuit = Some(CircuitState::Open);
+        assert_eq!(classify(&dependencies), HealthStatus::Unhealthy);
+
+        assert_eq!(classify(&[]), HealthStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_check_reports_failures_and_latency() {
+        let up = check("database", true, async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok("connected".to_string())
+        })
+        .await;
+        assert!(up.up);
+        assert_eq!(up.detail, "connected");
+        assert!(up.latency_ms >= 20);
+
+        let down = check("asb", false, async { anyhow::bail!("connection refused") }).await;
+        assert!(!down.up);
+        assert!(down.detail.contains("connection refused"));
+        assert_eq!(down.status(), HealthStatus::Degraded);
+    }
+}
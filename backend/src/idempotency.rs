@@ -0,0 +1,255 @@
+//! Replaying retried requests
+//!
+//! A client that times out on a send can't tell whether the funds moved, and
+//! retrying blindly could move them twice. Mutating requests may carry an
+//! `Idempotency-Key` header: the first request with a key runs and its
+//! response is stored, and later requests with the same key get the stored
+//! response back, marked with `Idempotent-Replayed: true`, without running
+//! again. Keys are scoped to the caller and forgotten after
+//! `idempotency.window_secs`.
+//!
+//! Reusing a key for a different request is rejected, as is a retry that
+//! arrives while the first request is still running. A request that never
+//! finished leaves its key refused until the window expires, since there is
+//! no telling whether it took effect.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    auth::Caller,
+    db::idempotency::{IdempotencyRecord, StoredResponse},
+    ApiError, ApiResult, AppState,
+};
+
+/// Request header carrying the client's key
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Response header set on replayed responses
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Longest key accepted, in bytes
+const MAX_KEY_LEN: usize = 255;
+
+/// Largest request body read for fingerprinting
+const MAX_BODY_BYTES: usize = 1 << 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotencyConfig {
+    /// Seconds a key replays its stored response. 0 ignores the header.
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_window_secs() -> u64 {
+    86_400
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: default_window_secs(),
+        }
+    }
+}
+
+/// Check the header value: 1 to 255 visible ASCII characters
+fn parse_key(value: &HeaderValue) -> ApiResult<&str> {
+    value
+        .to_str()
+        .ok()
+        .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LEN)
+        .filter(|key| key.bytes().all(|byte| byte.is_ascii_graphic()))
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "{} must be 1 to {} visible ASCII characters",
+                IDEMPOTENCY_KEY, MAX_KEY_LEN
+            ))
+        })
+}
+
+/// Record id of `key` as sent by `caller`; hashed so any key is a valid id
+fn record_id(caller: &str, key: &str) -> String {
+    hex_sha256(&[caller.as_bytes(), key.as_bytes()])
+}
+
+/// What a key was used for, so reusing it for something else can be caught
+fn fingerprint(method: &Method, path: &str, body: &[u8]) -> String {
+    hex_sha256(&[method.as_str().as_bytes(), path.as_bytes(), body])
+}
+
+fn hex_sha256(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        // Length-prefixed so ("ab", "c") and ("a", "bc") differ
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Rebuild a stored response, marked as a replay
+fn replayed(stored: &StoredResponse) -> ApiResult<Response> {
+    let body = general_purpose::STANDARD
+        .decode(&stored.body)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Stored response is corrupt: {}", e)))?;
+    let status = StatusCode::from_u16(stored.status)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Stored response is corrupt: {}", e)))?;
+
+    let mut response = (status, body).into_response();
+    let headers = response.headers_mut();
+    if let Some(content_type) = stored
+        .content_type
+        .as_deref()
+        .and_then(|value| HeaderValue::from_str(value).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    Ok(response)
+}
+
+/// Middleware running each idempotency key's request at most once
+pub async fn replay(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    match run_once(state, request, next).await {
+        Ok(response) => response,
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn run_once(state: AppState, request: Request, next: Next) -> ApiResult<Response> {
+    let window_secs = state.config.get().idempotency.window_secs;
+    let mutating = !matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+    let key = match request.headers().get(IDEMPOTENCY_KEY) {
+        Some(value) if mutating && window_secs > 0 => parse_key(value)?.to_string(),
+        _ => return Ok(next.run(request).await),
+    };
+
+    let caller = request.extensions().get::<Caller>().map_or_else(
+        || "anonymous".to_string(),
+        |caller| caller.name().to_string(),
+    );
+    let id = record_id(&caller, &key);
+
+    let (parts, body) = request.into_parts();
+    let body = to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("Failed to read request body: {}", e)))?;
+
+    let now = Utc::now();
+    state
+        .db
+        .delete_expired_idempotency_keys(now - Duration::seconds(window_secs as i64))
+        .await?;
+
+    let mut record = IdempotencyRecord {
+        fingerprint: fingerprint(&parts.method, parts.uri.path(), &body),
+        created_at: now,
+        response: None,
+    };
+    if !state.db.claim_idempotency_key(&id, &record).await? {
+        return match state.db.get_idempotency_record(&id).await? {
+            Some(existing) if existing.fingerprint != record.fingerprint => {
+                Err(ApiError::BadRequest(format!(
+                    "{} was already used for a different request",
+                    IDEMPOTENCY_KEY
+                )))
+            }
+            Some(IdempotencyRecord {
+                response: Some(stored),
+                ..
+            }) => {
+                tracing::info!(
+                    "Replaying {} {} for {}",
+                    parts.method,
+                    parts.uri.path(),
+                    caller
+                );
+                replayed(&stored)
+            }
+            _ => Err(ApiError::Conflict(format!(
+                "A request with this {} is still in progress",
+                IDEMPOTENCY_KEY
+            ))),
+        };
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    // Buffer the response so it can be both stored and sent
+    let (parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to read response: {}", e)))?;
+    record.response = Some(StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        body: general_purpose::STANDARD.encode(&body),
+    });
+    if let Err(e) = state.db.save_idempotency_record(&id, &record).await {
+        // The key stays claimed, so retries are refused rather than run again
+        tracing::error!("Failed to store response for idempotency key: {:#}", e);
+    }
+
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        let key = HeaderValue::from_static("send-2024-05-01-0001");
+        assert_eq!(parse_key(&key).unwrap(), "send-2024-05-01-0001");
+
+        assert!(parse_key(&HeaderValue::from_static("")).is_err());
+        assert!(parse_key(&HeaderValue::from_static("has space")).is_err());
+        let long = HeaderValue::from_str(&"k".repeat(MAX_KEY_LEN + 1)).unwrap();
+        assert!(parse_key(&long).is_err());
+    }
+
+    #[test]
+    fn test_keys_are_scoped_to_caller() {
+        assert_eq!(record_id("ops", "abc"), record_id("ops", "abc"));
+        assert_ne!(record_id("ops", "abc"), record_id("admin", "abc"));
+        assert_ne!(record_id("op", "sabc"), record_id("ops", "abc"));
+    }
+
+    #[test]
+    fn test_fingerprint_covers_path_and_body() {
+        let send = fingerprint(&Method::POST, "/bitcoin/send", b"{\"amount\":1}");
+        assert_eq!(
+            send,
+            fingerprint(&Method::POST, "/bitcoin/send", b"{\"amount\":1}")
+        );
+        assert_ne!(
+            send,
+            fingerprint(&Method::POST, "/bitcoin/send", b"{\"amount\":2}")
+        );
+        assert_ne!(
+            send,
+            fingerprint(&Method::POST, "/monero/sweep", b"{\"amount\":1}")
+        );
+    }
+}
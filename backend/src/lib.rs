@@ -3,21 +3,47 @@
 //! This library provides cryptocurrency wallet management, exchange integration,
 //! and metrics collection for Bitcoin, Monero, and atomic swap operations.
 
+use axum::extract::FromRef;
 use std::sync::Arc;
 
+pub mod alerts;
+pub mod amount;
+pub mod approvals;
+pub mod asb_log;
+pub mod auth;
 pub mod config;
+pub mod crypto;
 pub mod db;
 pub mod error;
+pub mod export;
+pub mod health;
+pub mod idempotency;
 pub mod metrics;
+pub mod notifications;
+pub mod openapi;
+pub mod portfolio;
+pub mod preferences;
+pub mod rate_limit;
+pub mod reload;
+pub mod request_id;
 pub mod routes;
+pub mod secrets;
+pub mod selftest;
+pub mod server;
 pub mod services;
+pub mod shutdown;
+pub mod stream;
+pub mod swap_timeline;
+pub mod telemetry;
+pub mod tls;
 pub mod trading;
 pub mod wallets;
 
 // Re-export commonly used types
-pub use config::Config;
+pub use amount::{Btc, Xmr};
+pub use config::{Config, SharedConfig};
 pub use db::MetricsDatabase;
-pub use error::{ApiError, ApiResult};
+pub use error::{ApiError, ApiResult, ErrorResponse};
 pub use services::{AsbClient, BitcoinRpcClient, KrakenClient, MoneroRpcClient};
 pub use trading::{TradingConfig, TradingEngine};
 pub use wallets::{BitcoinWallet, MoneroWallet, WalletConfig, WalletManager};
@@ -25,8 +51,31 @@ pub use wallets::{BitcoinWallet, MoneroWallet, WalletConfig, WalletManager};
 /// Application state shared across all route handlers
 #[derive(Clone)]
 pub struct AppState {
-    pub config: Arc<Config>,
+    /// Swapped on config reload; take a snapshot per request
+    pub config: SharedConfig,
     pub db: MetricsDatabase,
     pub wallets: Arc<WalletManager>,
     pub trading_engine: Arc<TradingEngine>,
+    /// Cached Bitcoin feerates, shared with the trading engine
+    pub fee_estimator: services::FeeEstimator,
+    /// Host clock skew against Kraken, measured at startup
+    pub clock_skew: alerts::ClockSkewMonitor,
+    /// Latest collected exchange rates
+    pub prices: db::prices::PriceCache,
+    /// Shared reading behind the `/metrics/stream` event streams
+    pub streams: stream::StreamHub,
+    /// Triggered on SIGTERM or Ctrl-C; long-lived responses end on it
+    pub shutdown: shutdown::Shutdown,
+}
+
+impl FromRef<AppState> for SharedConfig {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
+
+impl FromRef<AppState> for MetricsDatabase {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.clone()
+    }
 }
@@ -0,0 +1,439 @@
+//! The backend's own metrics
+//!
+//! Alongside the outbound call metrics in [`super::clients`], the backend
+//! times the requests it serves (per method and route), each collector cycle
+//! (per source) and each database call (per [`crate::db::MetricsDatabase`]
+//! method), and reads its memory, thread and file descriptor usage from
+//! `/proc`. Counters run from process start. The process-wide registry is
+//! reachable through [`global`] and is served by `GET /metrics/backend` and
+//! `GET /metrics/prometheus`; [`record_snapshots`] also stores it in the
+//! `backend_metrics` table so it can be charted.
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{span, Subscriber};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+use utoipa::ToSchema;
+
+use super::clients::{self, escape_label, LATENCY_BUCKETS};
+use crate::{db::MetricsDatabase, shutdown::Shutdown};
+
+/// Count and latency of one kind of operation
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct TimingStats {
+    /// `<METHOD> <route>` for requests, the source for collector cycles and
+    /// the method for database calls
+    pub name: String,
+    pub count: u64,
+    /// Requests answered with a 5xx status; not tracked for the others
+    pub errors: u64,
+    /// Sum of all durations in seconds
+    pub total_secs: f64,
+    pub max_secs: f64,
+    /// Operations per latency bucket (not cumulative), indexed like [`LATENCY_BUCKETS`]
+    #[serde(skip)]
+    pub bucket_counts: [u64; LATENCY_BUCKETS.len()],
+}
+
+impl TimingStats {
+    pub fn avg_secs(&self) -> f64 {
+        match self.count {
+            0 => 0.0,
+            n => self.total_secs / n as f64,
+        }
+    }
+
+    /// Cumulative bucket counts paired with their upper bound, as Prometheus expects
+    fn cumulative_buckets(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        LATENCY_BUCKETS
+            .iter()
+            .zip(self.bucket_counts.iter())
+            .scan(0, |total, (&le, &count)| {
+                *total += count;
+                Some((le, *total))
+            })
+    }
+}
+
+/// Timings of one kind of operation, by name
+#[derive(Default)]
+struct Timings(Mutex<BTreeMap<String, TimingStats>>);
+
+impl Timings {
+    fn record(&self, name: &str, elapsed: Duration, failed: bool) {
+        let elapsed = elapsed.as_secs_f64();
+        let mut timings = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = timings
+            .entry(name.to_string())
+            .or_insert_with(|| TimingStats {
+                name: name.to_string(),
+                ..Default::default()
+            });
+
+        stats.count += 1;
+        if failed {
+            stats.errors += 1;
+        }
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|&le| elapsed <= le) {
+            stats.bucket_counts[bucket] += 1;
+        }
+        stats.total_secs += elapsed;
+        stats.max_secs = stats.max_secs.max(elapsed);
+    }
+
+    fn snapshot(&self) -> Vec<TimingStats> {
+        let timings = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        timings.values().cloned().collect()
+    }
+}
+
+/// Resource usage of the backend process, where the OS reports it
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ProcessStats {
+    pub uptime_secs: u64,
+    pub resident_memory_bytes: Option<u64>,
+    pub threads: Option<u64>,
+    pub open_fds: Option<u64>,
+}
+
+impl ProcessStats {
+    fn read(uptime: Duration) -> Self {
+        let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+        let field = |name: &str| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+                .and_then(|value| value.split_whitespace().next()?.parse::<u64>().ok())
+        };
+
+        Self {
+            uptime_secs: uptime.as_secs(),
+            resident_memory_bytes: field("VmRSS").map(|kib| kib * 1024),
+            threads: field("Threads"),
+            open_fds: std::fs::read_dir("/proc/self/fd")
+                .ok()
+                .map(|entries| entries.count() as u64),
+        }
+    }
+}
+
+/// The backend's metrics at one point in time, as stored in `backend_metrics`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BackendMetricsSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub process: ProcessStats,
+    pub requests: Vec<TimingStats>,
+    pub collector_cycles: Vec<TimingStats>,
+    pub db_queries: Vec<TimingStats>,
+    /// Failed outbound calls per service client, retries included
+    pub rpc_errors: BTreeMap<String, u64>,
+}
+
+/// Registry of the backend's own timings
+pub struct BackendMetrics {
+    started: Instant,
+    requests: Timings,
+    collector_cycles: Timings,
+    db_queries: Timings,
+}
+
+impl Default for BackendMetrics {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            requests: Timings::default(),
+            collector_cycles: Timings::default(),
+            db_queries: Timings::default(),
+        }
+    }
+}
+
+impl BackendMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a served request; `route` is the matched route, e.g. `/metrics/{source}/export`
+    pub fn record_request(&self, method: &str, route: &str, elapsed: Duration, status: u16) {
+        self.requests
+            .record(&format!("{} {}", method, route), elapsed, status >= 500);
+    }
+
+    /// Record one collection of `source`
+    pub fn record_collector_cycle(&self, source: &str, elapsed: Duration) {
+        self.collector_cycles.record(source, elapsed, false);
+    }
+
+    /// Record one database call
+    pub fn record_db_query(&self, method: &str, elapsed: Duration) {
+        self.db_queries.record(method, elapsed, false);
+    }
+
+    /// Current metrics, with the client failures from [`clients::global`]
+    pub fn snapshot(&self) -> BackendMetricsSnapshot {
+        let mut rpc_errors = BTreeMap::new();
+        for stats in clients::global().snapshot() {
+            *rpc_errors.entry(stats.client).or_default() += stats.failure_count;
+        }
+
+        BackendMetricsSnapshot {
+            timestamp: Utc::now(),
+            process: ProcessStats::read(self.started.elapsed()),
+            requests: self.requests.snapshot(),
+            collector_cycles: self.collector_cycles.snapshot(),
+            db_queries: self.db_queries.snapshot(),
+            rpc_errors,
+        }
+    }
+
+    /// Render the metrics in Prometheus text exposition format
+    ///
+    /// The client failures are left out, as the client metrics already
+    /// carry them.
+    pub fn render_prometheus(&self) -> String {
+        let snapshot = self.snapshot();
+        let mut out = String::new();
+
+        let process = &snapshot.process;
+        for (name, help, value) in [
+            (
+                "eigenix_process_uptime_seconds",
+                "Seconds since the backend started",
+                Some(process.uptime_secs),
+            ),
+            (
+                "eigenix_process_resident_memory_bytes",
+                "Resident memory of the backend process",
+                process.resident_memory_bytes,
+            ),
+            (
+                "eigenix_process_threads",
+                "Threads of the backend process",
+                process.threads,
+            ),
+            (
+                "eigenix_process_open_fds",
+                "Open file descriptors of the backend process",
+                process.open_fds,
+            ),
+        ] {
+            if let Some(value) = value {
+                let _ = writeln!(out, "# HELP {} {}", name, help);
+                let _ = writeln!(out, "# TYPE {} gauge", name);
+                let _ = writeln!(out, "{} {}", name, value);
+            }
+        }
+
+        let requests: Vec<(String, &TimingStats)> = snapshot
+            .requests
+            .iter()
+            .map(|stats| {
+                let (method, route) = stats.name.split_once(' ').unwrap_or(("", &stats.name));
+                (
+                    format!(
+                        "method=\"{}\",route=\"{}\"",
+                        escape_label(method),
+                        escape_label(route)
+                    ),
+                    stats,
+                )
+            })
+            .collect();
+        if !requests.is_empty() {
+            let name = "eigenix_http_server_errors_total";
+            let _ = writeln!(out, "# HELP {} Requests answered with a 5xx status", name);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            for (labels, stats) in &requests {
+                let _ = writeln!(out, "{}{{{}}} {}", name, labels, stats.errors);
+            }
+        }
+        write_histogram(
+            &mut out,
+            "eigenix_http_request_duration_seconds",
+            "Time taken to answer requests, by route",
+            &requests,
+        );
+        write_histogram(
+            &mut out,
+            "eigenix_collector_cycle_duration_seconds",
+            "Time taken by a metrics collection cycle",
+            &labelled(&snapshot.collector_cycles, "source"),
+        );
+        write_histogram(
+            &mut out,
+            "eigenix_db_query_duration_seconds",
+            "Time taken by database calls",
+            &labelled(&snapshot.db_queries, "query"),
+        );
+
+        out
+    }
+}
+
+/// Pair each entry with a single `label` holding its name
+fn labelled<'a>(timings: &'a [TimingStats], label: &str) -> Vec<(String, &'a TimingStats)> {
+    timings
+        .iter()
+        .map(|stats| {
+            (
+                format!("{}=\"{}\"", label, escape_label(&stats.name)),
+                stats,
+            )
+        })
+        .collect()
+}
+
+/// Write a histogram family, one series per labelled entry
+fn write_histogram(out: &mut String, name: &str, help: &str, series: &[(String, &TimingStats)]) {
+    if series.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    for (labels, stats) in series {
+        for (le, count) in stats.cumulative_buckets() {
+            let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, le, count);
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{},le=\"+Inf\"}} {}",
+            name, labels, stats.count
+        );
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, stats.total_secs);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, stats.count);
+    }
+}
+
+/// Process-wide registry
+pub fn global() -> &'static BackendMetrics {
+    static REGISTRY: OnceLock<BackendMetrics> = OnceLock::new();
+    REGISTRY.get_or_init(BackendMetrics::new)
+}
+
+/// Middleware timing each request under its matched route
+///
+/// Requests that match no route are counted under `unmatched`, so probes for
+/// random paths don't each get their own entry.
+pub async fn track_request(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    global().record_request(
+        method.as_str(),
+        &route,
+        started.elapsed(),
+        response.status().as_u16(),
+    );
+    response
+}
+
+/// Tracing layer timing the database calls
+///
+/// Every public [`MetricsDatabase`] method runs in a debug span named after
+/// it, so timing those spans from creation to close times the calls without
+/// wrapping each one.
+pub struct DbTimingLayer;
+
+struct SpanStarted(Instant);
+
+impl<S> Layer<S> for DbTimingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanStarted(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let started = span
+            .extensions()
+            .get::<SpanStarted>()
+            .map(|started| started.0);
+        if let Some(started) = started {
+            global().record_db_query(span.name(), started.elapsed());
+        }
+    }
+}
+
+/// Store a snapshot of [`global`] every `interval` until `shutdown`
+pub async fn record_snapshots(db: MetricsDatabase, interval: Duration, shutdown: Shutdown) {
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = shutdown.triggered() => return,
+        }
+
+        if let Err(e) = db.store_backend_metrics(&global().snapshot()).await {
+            tracing::error!("Failed to store backend metrics: {:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_are_keyed_by_route() {
+        let metrics = BackendMetrics::new();
+        metrics.record_request("GET", "/trading/status", Duration::from_millis(20), 200);
+        metrics.record_request("GET", "/trading/status", Duration::from_millis(60), 503);
+        metrics.record_request("POST", "/trading/enable", Duration::from_millis(5), 200);
+
+        let requests = metrics.snapshot().requests;
+        assert_eq!(requests.len(), 2);
+        let status = &requests[0];
+        assert_eq!(status.name, "GET /trading/status");
+        assert_eq!(status.count, 2);
+        assert_eq!(status.errors, 1);
+        assert!((status.avg_secs() - 0.04).abs() < 1e-9);
+        assert!((status.max_secs - 0.06).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_render_prometheus() {
+        let metrics = BackendMetrics::new();
+        metrics.record_request(
+            "GET",
+            "/metrics/{source}/export",
+            Duration::from_millis(30),
+            500,
+        );
+        metrics.record_collector_cycle("bitcoin", Duration::from_millis(300));
+        metrics.record_db_query("get_summary", Duration::from_millis(3));
+
+        let text = metrics.render_prometheus();
+        assert!(text.contains("# TYPE eigenix_process_uptime_seconds gauge"));
+        assert!(text.contains(
+            "eigenix_http_server_errors_total{method=\"GET\",route=\"/metrics/{source}/export\"} 1"
+        ));
+        assert!(text.contains(
+            "eigenix_http_request_duration_seconds_bucket{method=\"GET\",route=\"/metrics/{source}/export\",le=\"0.05\"} 1"
+        ));
+        assert!(
+            text.contains("eigenix_collector_cycle_duration_seconds_count{source=\"bitcoin\"} 1")
+        );
+        assert!(text.contains(
+            "eigenix_db_query_duration_seconds_bucket{query=\"get_summary\",le=\"0.005\"} 1"
+        ));
+    }
+}
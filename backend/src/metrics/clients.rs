@@ -0,0 +1,378 @@
+//! Outbound call metrics for the service clients
+//!
+//! Every RPC/HTTP call made by the Kraken, bitcoind, monerod and ASB clients
+//! is timed and counted here, keyed by client and method. The process-wide
+//! registry is reachable through [`global`] and is served by
+//! `GET /metrics/clients` and `GET /metrics/prometheus`.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+use utoipa::ToSchema;
+
+/// Upper bounds in seconds of the latency histogram buckets
+///
+/// Covers fast local RPCs (a few ms) up to slow exchange calls; anything
+/// slower only lands in the implicit `+Inf` bucket.
+pub const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Latency and outcome counters for one client method
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ClientCallStats {
+    pub client: String,
+    pub method: String,
+    pub success_count: u64,
+    pub failure_count: u64,
+    /// Sum of all call latencies in seconds
+    pub total_latency_secs: f64,
+    pub max_latency_secs: f64,
+    pub last_latency_secs: f64,
+    pub last_error: Option<String>,
+    /// Calls per latency bucket (not cumulative), indexed like [`LATENCY_BUCKETS`]
+    #[serde(skip)]
+    pub bucket_counts: [u64; LATENCY_BUCKETS.len()],
+}
+
+impl ClientCallStats {
+    pub fn call_count(&self) -> u64 {
+        self.success_count + self.failure_count
+    }
+
+    pub fn avg_latency_secs(&self) -> f64 {
+        match self.call_count() {
+            0 => 0.0,
+            n => self.total_latency_secs / n as f64,
+        }
+    }
+
+    /// Cumulative bucket counts paired with their upper bound, as Prometheus expects
+    pub fn cumulative_buckets(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        LATENCY_BUCKETS
+            .iter()
+            .zip(self.bucket_counts.iter())
+            .scan(0, |total, (&le, &count)| {
+                *total += count;
+                Some((le, *total))
+            })
+    }
+}
+
+/// Registry of per-client, per-method call statistics
+#[derive(Default)]
+pub struct ClientMetricsRegistry {
+    calls: Mutex<BTreeMap<(String, String), ClientCallStats>>,
+}
+
+impl ClientMetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the outcome of a single call
+    pub fn record(&self, client: &str, method: &str, latency: Duration, error: Option<String>) {
+        let latency = latency.as_secs_f64();
+        let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = calls
+            .entry((client.to_string(), method.to_string()))
+            .or_insert_with(|| ClientCallStats {
+                client: client.to_string(),
+                method: method.to_string(),
+                ..Default::default()
+            });
+
+        match error {
+            None => stats.success_count += 1,
+            Some(error) => {
+                stats.failure_count += 1;
+                stats.last_error = Some(error);
+            }
+        }
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|&le| latency <= le) {
+            stats.bucket_counts[bucket] += 1;
+        }
+        stats.total_latency_secs += latency;
+        stats.max_latency_secs = stats.max_latency_secs.max(latency);
+        stats.last_latency_secs = latency;
+    }
+
+    /// Time a call and record whether it succeeded
+    ///
+    /// The call runs in a debug span, so it shows up under the request that
+    /// made it when spans are exported.
+    pub async fn track<T, F>(&self, client: &str, method: &str, call: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let span = tracing::debug_span!("client_call", client, method);
+        let started = Instant::now();
+        let result = call.instrument(span).await;
+        let error = result.as_ref().err().map(|e| format!("{:#}", e));
+        self.record(client, method, started.elapsed(), error);
+        result
+    }
+
+    /// Current statistics, ordered by client then method
+    pub fn snapshot(&self) -> Vec<ClientCallStats> {
+        let calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+        calls.values().cloned().collect()
+    }
+
+    /// Render the statistics in Prometheus text exposition format
+    ///
+    /// Each client gets its own metric family, e.g.
+    /// `eigenix_kraken_requests_total{method="Ticker",outcome="success"}`.
+    pub fn render_prometheus(&self) -> String {
+        let mut by_client: BTreeMap<String, Vec<ClientCallStats>> = BTreeMap::new();
+        for stats in self.snapshot() {
+            by_client
+                .entry(metric_name_part(&stats.client))
+                .or_default()
+                .push(stats);
+        }
+
+        let mut out = String::new();
+        for (client, calls) in &by_client {
+            let requests = format!("eigenix_{}_requests_total", client);
+            let _ = writeln!(
+                out,
+                "# HELP {} Calls made by the {} client",
+                requests, client
+            );
+            let _ = writeln!(out, "# TYPE {} counter", requests);
+            for stats in calls {
+                let method = escape_label(&stats.method);
+                let _ = writeln!(
+                    out,
+                    "{}{{method=\"{}\",outcome=\"success\"}} {}",
+                    requests, method, stats.success_count
+                );
+                let _ = writeln!(
+                    out,
+                    "{}{{method=\"{}\",outcome=\"failure\"}} {}",
+                    requests, method, stats.failure_count
+                );
+            }
+
+            let duration = format!("eigenix_{}_request_duration_seconds", client);
+            let _ = writeln!(
+                out,
+                "# HELP {} Latency of {} client calls",
+                duration, client
+            );
+            let _ = writeln!(out, "# TYPE {} histogram", duration);
+            for stats in calls {
+                let method = escape_label(&stats.method);
+                for (le, count) in stats.cumulative_buckets() {
+                    let _ = writeln!(
+                        out,
+                        "{}_bucket{{method=\"{}\",le=\"{}\"}} {}",
+                        duration, method, le, count
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{method=\"{}\",le=\"+Inf\"}} {}",
+                    duration,
+                    method,
+                    stats.call_count()
+                );
+                let _ = writeln!(
+                    out,
+                    "{}_sum{{method=\"{}\"}} {}",
+                    duration, method, stats.total_latency_secs
+                );
+                let _ = writeln!(
+                    out,
+                    "{}_count{{method=\"{}\"}} {}",
+                    duration,
+                    method,
+                    stats.call_count()
+                );
+            }
+        }
+
+        out
+    }
+}
+
+/// Process-wide registry shared by all service clients
+pub fn global() -> &'static ClientMetricsRegistry {
+    static REGISTRY: OnceLock<ClientMetricsRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(ClientMetricsRegistry::new)
+}
+
+/// Time a call against the global registry
+pub async fn track<T, F>(client: &str, method: &str, call: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    global().track(client, method, call).await
+}
+
+/// Restrict a client name to characters valid in a Prometheus metric name
+fn metric_name_part(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+pub(crate) fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_track_records_success_and_failure() {
+        let registry = ClientMetricsRegistry::new();
+
+        let ok = registry
+            .track("kraken", "Ticker", async {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                Ok(42)
+            })
+            .await;
+        assert_eq!(ok.unwrap(), 42);
+
+        let failed: Result<()> = registry
+            .track("kraken", "Ticker", async {
+                anyhow::bail!("connection refused")
+            })
+            .await;
+        assert!(failed.is_err());
+
+        let stats = registry.snapshot();
+        assert_eq!(stats.len(), 1);
+        let ticker = &stats[0];
+        assert_eq!(ticker.client, "kraken");
+        assert_eq!(ticker.method, "Ticker");
+        assert_eq!(ticker.success_count, 1);
+        assert_eq!(ticker.failure_count, 1);
+        assert_eq!(ticker.call_count(), 2);
+        assert!(ticker.max_latency_secs >= 0.005);
+        assert!(ticker.total_latency_secs >= ticker.max_latency_secs);
+        assert_eq!(ticker.last_error.as_deref(), Some("connection refused"));
+    }
+
+    #[test]
+    fn test_stats_are_keyed_by_client_and_method() {
+        let registry = ClientMetricsRegistry::new();
+        registry.record(
+            "bitcoind",
+            "getblockchaininfo",
+            Duration::from_millis(10),
+            None,
+        );
+        registry.record("bitcoind", "getbalances", Duration::from_millis(20), None);
+        registry.record(
+            "monerod",
+            "get_info",
+            Duration::from_millis(30),
+            Some("timeout".to_string()),
+        );
+
+        let keys: Vec<(String, String)> = registry
+            .snapshot()
+            .into_iter()
+            .map(|s| (s.client, s.method))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("bitcoind".to_string(), "getbalances".to_string()),
+                ("bitcoind".to_string(), "getblockchaininfo".to_string()),
+                ("monerod".to_string(), "get_info".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_prometheus() {
+        let registry = ClientMetricsRegistry::new();
+        registry.record("kraken", "Balance", Duration::from_millis(250), None);
+        registry.record(
+            "kraken",
+            "Balance",
+            Duration::from_millis(750),
+            Some("nonce".to_string()),
+        );
+
+        let text = registry.render_prometheus();
+        assert!(text.contains("# TYPE eigenix_kraken_requests_total counter"));
+        assert!(text
+            .contains("eigenix_kraken_requests_total{method=\"Balance\",outcome=\"success\"} 1"));
+        assert!(text
+            .contains("eigenix_kraken_requests_total{method=\"Balance\",outcome=\"failure\"} 1"));
+        assert!(text.contains("eigenix_kraken_request_duration_seconds_sum{method=\"Balance\"} 1"));
+        assert!(
+            text.contains("eigenix_kraken_request_duration_seconds_count{method=\"Balance\"} 2")
+        );
+    }
+
+    #[test]
+    fn test_render_prometheus_histogram() {
+        let registry = ClientMetricsRegistry::new();
+        for millis in [3, 8, 40, 40, 300, 1_200, 30_000] {
+            registry.record(
+                "bitcoind",
+                "getblockchaininfo",
+                Duration::from_millis(millis),
+                None,
+            );
+        }
+
+        let text = registry.render_prometheus();
+        assert!(text.contains("# TYPE eigenix_bitcoind_request_duration_seconds histogram"));
+
+        let metric = "eigenix_bitcoind_request_duration_seconds";
+        let value = |line_prefix: &str| -> f64 {
+            text.lines()
+                .find(|line| line.starts_with(line_prefix))
+                .unwrap_or_else(|| panic!("missing {}", line_prefix))
+                .rsplit(' ')
+                .next()
+                .unwrap()
+                .parse()
+                .unwrap()
+        };
+        let bucket = |le: &str| {
+            value(&format!(
+                "{}_bucket{{method=\"getblockchaininfo\",le=\"{}\"}}",
+                metric, le
+            ))
+        };
+
+        assert_eq!(bucket("0.005"), 1.0);
+        assert_eq!(bucket("0.01"), 2.0);
+        assert_eq!(bucket("0.05"), 4.0);
+        assert_eq!(bucket("0.5"), 5.0);
+        assert_eq!(bucket("2.5"), 6.0);
+        // 30s is beyond the largest bound and only shows up in +Inf
+        assert_eq!(bucket("10"), 6.0);
+        assert_eq!(bucket("+Inf"), 7.0);
+
+        // Buckets never decrease, and +Inf matches _count
+        let counts: Vec<f64> = LATENCY_BUCKETS
+            .iter()
+            .map(|le| bucket(&le.to_string()))
+            .collect();
+        assert!(counts.windows(2).all(|pair| pair[0] <= pair[1]));
+        let count = value(&format!("{}_count{{method=\"getblockchaininfo\"}}", metric));
+        assert_eq!(count, bucket("+Inf"));
+
+        let sum = value(&format!("{}_sum{{method=\"getblockchaininfo\"}}", metric));
+        assert!((sum - 31.591).abs() < 1e-9);
+    }
+}
@@ -6,71 +6,380 @@
 //! - ASB (Atomic Swap Backend)
 //! - Electrs
 //! - Container health
+//! - Mempool and watched addresses, from an Esplora-style HTTP API
+//! - BTC and XMR prices, from Kraken's public ticker
+//! - Free space of the filesystems holding the data directories
+//! - CPU, memory, swap, load and network usage of the host
 //!
-//! The collector runs as a background task and stores metrics in the database.
+//! Each enabled source runs as its own background task on the interval set
+//! in `[collector]`, with a small random delay per cycle, and stores its
+//! metrics in the database. Node and ASB URLs are read from the shared config
+//! on every cycle, so a config reload repoints them without a restart.
 
-use std::sync::Arc;
-use tokio::time::{interval, Duration as TokioDuration};
+use chrono::{Duration, Utc};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::time::{interval, Duration as TokioDuration, MissedTickBehavior};
 
 use crate::{
-    config::Config,
+    alerts::{
+        rules::RuleInput, Alert, AlertNotifier, AlertSeverity, AlertState, FailedSwapMonitor,
+        RuleEngine,
+    },
+    config::{CollectorConfig, SharedConfig},
     db::MetricsDatabase,
     metrics::{
-        AsbRpcClient, BitcoinRpcClient, ContainerHealthClient, ElectrsClient, MoneroRpcClient,
+        backend, disk, system::SystemProbe, AsbQuoteMetrics, AsbRpcClient, BitcoinRpcClient,
+        ContainerHealthClient, ElectrsClient, ElectrsMetrics, MoneroRpcClient,
+    },
+    services::{
+        breaker::{self, CircuitState},
+        AsbClient, Exchange, KrakenClient, MempoolClient,
     },
+    shutdown::Shutdown,
 };
 
+/// Debounced status of a single service probe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeStatus {
+    Up,
+    /// The probe failed but the service isn't considered down yet
+    GracePeriod,
+    Down,
+}
+
+/// Debounces a service's up/down status across collection cycles
+///
+/// A service is only reported down after `threshold` consecutive failed
+/// probes, and is reported up again on the first success.
+#[derive(Debug)]
+pub struct DownDebounce {
+    threshold: u32,
+    consecutive_failures: u32,
+}
+
+impl DownDebounce {
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            consecutive_failures: 0,
+        }
+    }
+
+    /// Record a probe result
+    pub fn observe(&mut self, probe_up: bool) -> ProbeStatus {
+        if probe_up {
+            self.consecutive_failures = 0;
+            return ProbeStatus::Up;
+        }
+
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.consecutive_failures >= self.threshold {
+            ProbeStatus::Down
+        } else {
+            ProbeStatus::GracePeriod
+        }
+    }
+}
+
+/// A source the collector polls on its own schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsSource {
+    Bitcoin,
+    Monero,
+    Asb,
+    Electrs,
+    Containers,
+    Mempool,
+    Prices,
+    Disk,
+    System,
+}
+
+impl MetricsSource {
+    pub const ALL: [Self; 9] = [
+        Self::Bitcoin,
+        Self::Monero,
+        Self::Asb,
+        Self::Electrs,
+        Self::Containers,
+        Self::Mempool,
+        Self::Prices,
+        Self::Disk,
+        Self::System,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bitcoin => "bitcoin",
+            Self::Monero => "monero",
+            Self::Asb => "asb",
+            Self::Electrs => "electrs",
+            Self::Containers => "containers",
+            Self::Mempool => "mempool",
+            Self::Prices => "prices",
+            Self::Disk => "disk",
+            Self::System => "system",
+        }
+    }
+
+    /// Collection interval for this source, or `None` when it is disabled
+    pub fn interval(self, config: &CollectorConfig) -> Option<TokioDuration> {
+        let (enabled, secs) = match self {
+            Self::Bitcoin => (config.bitcoin, config.bitcoin_interval_secs),
+            Self::Monero => (config.monero, config.monero_interval_secs),
+            Self::Asb => (config.asb, config.asb_interval_secs),
+            Self::Electrs => (config.electrs, config.electrs_interval_secs),
+            Self::Containers => (config.containers, config.containers_interval_secs),
+            Self::Mempool => (config.mempool, config.mempool_interval_secs),
+            Self::Prices => (config.prices, config.prices_interval_secs),
+            Self::Disk => (config.disk, config.disk_interval_secs),
+            Self::System => (config.system, config.system_interval_secs),
+        };
+        enabled.then(|| TokioDuration::from_secs(secs.max(1)))
+    }
+
+    /// Client whose circuit breaker pauses this source while open
+    ///
+    /// The ASB is left out: its probe is what records it as down.
+    pub fn client(self) -> Option<&'static str> {
+        match self {
+            Self::Bitcoin => Some("bitcoind"),
+            Self::Monero => Some("monerod"),
+            Self::Mempool => Some("mempool"),
+            Self::Prices => Some("kraken"),
+            Self::Asb | Self::Electrs | Self::Containers | Self::Disk | Self::System => None,
+        }
+    }
+}
+
+/// Largest random delay added to a collection cycle
+///
+/// Kept to half the interval so a delayed cycle can't run into the next one.
+pub fn max_jitter(interval: TokioDuration, jitter: TokioDuration) -> TokioDuration {
+    jitter.min(interval / 2)
+}
+
+/// Random delay in `[0, max]`
+fn random_delay(max: TokioDuration) -> TokioDuration {
+    let millis = max.as_millis() as u64;
+    if millis == 0 {
+        return TokioDuration::ZERO;
+    }
+    let random = RandomState::new().build_hasher().finish();
+    TokioDuration::from_millis(random % (millis + 1))
+}
+
 /// Metrics collector service
 pub struct MetricsCollector {
-    config: Arc<Config>,
+    config: SharedConfig,
     db: MetricsDatabase,
+    asb_status: Mutex<DownDebounce>,
+    electrs_status: Mutex<DownDebounce>,
+    failed_swaps: Mutex<FailedSwapMonitor>,
+    rules: Mutex<RuleEngine>,
+    notifier: AlertNotifier,
+    containers: ContainerHealthClient,
+    system: Mutex<SystemProbe>,
+    shutdown: Shutdown,
 }
 
 impl MetricsCollector {
     /// Create a new metrics collector
-    pub fn new(config: Arc<Config>, db: MetricsDatabase) -> Self {
-        Self { config, db }
+    pub fn new(shared: SharedConfig, db: MetricsDatabase) -> Self {
+        let config = shared.get();
+        let down_threshold = config.collector.down_threshold;
+        let failed_swaps = FailedSwapMonitor::new(
+            config.alerts.failed_swap_alert_threshold,
+            Duration::seconds(config.alerts.failed_swap_window_secs as i64),
+        );
+        let notifier = AlertNotifier::from_config(&config);
+        let containers = ContainerHealthClient::new(config.containers.container_runtime);
+        let rules = RuleEngine::new(config.alerts.rules.clone());
+        Self {
+            config: shared,
+            db,
+            asb_status: Mutex::new(DownDebounce::new(down_threshold)),
+            electrs_status: Mutex::new(DownDebounce::new(down_threshold)),
+            failed_swaps: Mutex::new(failed_swaps),
+            rules: Mutex::new(rules),
+            notifier,
+            containers,
+            system: Mutex::new(SystemProbe::new()),
+            shutdown: Shutdown::new(),
+        }
+    }
+
+    /// Stop collecting when `shutdown` is triggered, after any collection
+    /// in progress has been stored
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Probe containers with `containers`, sharing the runtime it detected
+    pub fn with_containers(mut self, containers: ContainerHealthClient) -> Self {
+        self.containers = containers;
+        self
     }
 
-    /// Run the metrics collection loop
+    /// Run the metrics collection tasks
     ///
-    /// This function runs indefinitely, collecting metrics every 60 seconds.
+    /// Spawns one task per enabled source, each on its own schedule, and runs
+    /// until they all stop on shutdown.
     pub async fn run(self) {
-        let mut ticker = interval(TokioDuration::from_secs(60));
+        self.restore_alerts().await;
+        let collector = Arc::new(self);
+        let config = collector.config.get();
+        let jitter = TokioDuration::from_secs(config.collector.jitter_secs);
 
-        loop {
-            ticker.tick().await;
-            tracing::info!("Collecting metrics...");
+        let mut tasks = Vec::new();
+        for source in MetricsSource::ALL {
+            let Some(period) = source.interval(&config.collector) else {
+                tracing::info!("Metrics collection for {} is disabled", source.name());
+                continue;
+            };
+            if source == MetricsSource::Mempool && config.mempool.api_url.is_none() {
+                tracing::info!("Mempool metrics need [mempool] api_url, not collecting them");
+                continue;
+            }
+            if source == MetricsSource::Disk && config.disk.paths.is_empty() {
+                tracing::info!("No [disk] paths configured, not collecting disk metrics");
+                continue;
+            }
 
-            self.collect_all().await;
+            tracing::info!(
+                "Collecting {} metrics every {}s",
+                source.name(),
+                period.as_secs()
+            );
+            let collector = collector.clone();
+            tasks.push(tokio::spawn(async move {
+                collector
+                    .run_source(source, period, max_jitter(period, jitter))
+                    .await
+            }));
+        }
 
-            tracing::info!("Metrics collection complete");
+        for task in tasks {
+            if let Err(e) = task.await {
+                tracing::error!("Metrics collection task stopped: {}", e);
+            }
         }
+        tracing::info!("Metrics collection stopped");
     }
 
-    /// Collect all metrics from all sources
-    async fn collect_all(&self) {
-        // Collect metrics in parallel for better performance
-        tokio::join!(
-            self.collect_bitcoin(),
-            self.collect_monero(),
-            self.collect_asb(),
-            self.collect_electrs(),
-            self.collect_containers(),
-        );
+    /// Carry over rule alerts still firing from before a restart
+    async fn restore_alerts(&self) {
+        if self.config.get().alerts.rules.is_empty() {
+            return;
+        }
+        match self.db.get_alerts(Some(AlertState::Firing), 1000).await {
+            Ok(firing) => self.rules.lock().unwrap().restore(firing),
+            Err(e) => tracing::error!("Failed to load firing alerts: {}", e),
+        }
+    }
+
+    /// Evaluate the alert rules against a fresh sample
+    async fn evaluate_rules(&self, sample: &impl RuleInput) {
+        let changed = self
+            .rules
+            .lock()
+            .unwrap()
+            .observe(Utc::now(), &sample.readings());
+
+        for alert in changed {
+            if let Err(e) = self.db.save_alert(&alert).await {
+                tracing::error!("Failed to store alert {}: {}", alert.rule, e);
+            }
+
+            let (severity, message, timestamp) = match alert.resolved_at {
+                Some(resolved_at) => (
+                    AlertSeverity::Warning,
+                    format!("Resolved: {}", alert.message),
+                    resolved_at,
+                ),
+                None => (alert.severity, alert.message, alert.started_at),
+            };
+            self.notifier.notify(&Alert {
+                kind: alert.rule,
+                severity,
+                message,
+                timestamp,
+            });
+        }
+    }
+
+    /// Collect one source on a fixed interval
+    async fn run_source(
+        &self,
+        source: MetricsSource,
+        period: TokioDuration,
+        jitter: TokioDuration,
+    ) {
+        let mut ticker = interval(period);
+        // A slow cycle shouldn't be followed by a burst of catch-up collections
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            // Only the waits are cut short; a collection runs to completion
+            tokio::select! {
+                _ = async {
+                    ticker.tick().await;
+                    tokio::time::sleep(random_delay(jitter)).await;
+                } => {}
+                _ = self.shutdown.triggered() => return,
+            }
+
+            tracing::debug!("Collecting {} metrics", source.name());
+            self.collect(source).await;
+        }
+    }
+
+    /// Collect and store one sample from a source
+    ///
+    /// While the source's circuit is open the cycle is skipped, leaving a gap
+    /// in its series rather than a failed call every cycle. Cycles that run
+    /// are timed in the backend metrics.
+    async fn collect(&self, source: MetricsSource) {
+        if let Some(client) = source.client() {
+            if breaker::global().state(client, Utc::now()) == Some(CircuitState::Open) {
+                tracing::debug!(
+                    "Skipping {} metrics while the {} circuit is open",
+                    source.name(),
+                    client
+                );
+                return;
+            }
+        }
+
+        let started = Instant::now();
+        match source {
+            MetricsSource::Bitcoin => self.collect_bitcoin().await,
+            MetricsSource::Monero => self.collect_monero().await,
+            MetricsSource::Asb => self.collect_asb().await,
+            MetricsSource::Electrs => self.collect_electrs().await,
+            MetricsSource::Containers => self.collect_containers().await,
+            MetricsSource::Mempool => self.collect_mempool().await,
+            MetricsSource::Prices => self.collect_prices().await,
+            MetricsSource::Disk => self.collect_disk().await,
+            MetricsSource::System => self.collect_system().await,
+        }
+        backend::global().record_collector_cycle(source.name(), started.elapsed());
     }
 
     /// Collect Bitcoin metrics
     async fn collect_bitcoin(&self) {
-        match BitcoinRpcClient::new(
-            self.config.bitcoin.rpc_url.clone(),
-            &self.config.bitcoin.cookie_path,
-        ) {
+        let config = self.config.get();
+        match BitcoinRpcClient::new(config.bitcoin.rpc_url.clone(), &config.bitcoin.cookie_path) {
             Ok(client) => match client.get_metrics().await {
                 Ok(metrics) => {
                     if let Err(e) = self.db.store_bitcoin_metrics(&metrics).await {
                         tracing::error!("Failed to store Bitcoin metrics: {}", e);
                     }
+                    self.evaluate_rules(&metrics).await;
                 }
                 Err(e) => tracing::error!("Failed to collect Bitcoin metrics: {}", e),
             },
@@ -80,12 +389,13 @@ impl MetricsCollector {
 
     /// Collect Monero metrics
     async fn collect_monero(&self) {
-        let client = MoneroRpcClient::new(self.config.monero.rpc_url.clone());
+        let client = MoneroRpcClient::new(self.config.get().monero.rpc_url.clone());
         match client.get_metrics().await {
             Ok(metrics) => {
                 if let Err(e) = self.db.store_monero_metrics(&metrics).await {
                     tracing::error!("Failed to store Monero metrics: {}", e);
                 }
+                self.evaluate_rules(&metrics).await;
             }
             Err(e) => tracing::error!("Failed to collect Monero metrics: {}", e),
         }
@@ -93,42 +403,105 @@ impl MetricsCollector {
 
     /// Collect ASB metrics
     async fn collect_asb(&self) {
-        let client = AsbRpcClient::new(self.config.asb.rpc_url.clone());
+        let client = AsbRpcClient::new(self.config.get().asb.rpc_url.clone());
         match client.get_metrics().await {
             Ok(metrics) => {
+                // Rules see every probe; their own `for_secs` does the debouncing
+                self.evaluate_rules(&metrics).await;
+
+                // Failed probes inside the grace period aren't stored, so the
+                // last recorded status stands until the threshold is reached
+                let status = self.asb_status.lock().unwrap().observe(metrics.up);
+                if status == ProbeStatus::GracePeriod {
+                    tracing::warn!("ASB probe failed, not yet marking it down");
+                    return;
+                }
+
                 if let Err(e) = self.db.store_asb_metrics(&metrics).await {
                     tracing::error!("Failed to store ASB metrics: {}", e);
                 }
+
+                // Down samples carry zeroed counters, which would look like a restart
+                if metrics.up {
+                    let alert = self
+                        .failed_swaps
+                        .lock()
+                        .unwrap()
+                        .observe(Utc::now(), metrics.failed_swaps);
+                    if let Some(alert) = alert {
+                        self.notifier.notify(&alert);
+                    }
+
+                    self.collect_asb_quote().await;
+                }
             }
             Err(e) => tracing::error!("Failed to collect ASB metrics: {}", e),
         }
     }
 
+    /// Record the ASB's current quote against the exchange's last price
+    ///
+    /// A quote that can't be fetched is stored as not quoting, so an ASB
+    /// that stays up but stops offering swaps still shows in the series.
+    async fn collect_asb_quote(&self) {
+        let config = self.config.get();
+        let quote = match AsbClient::new(config.asb.rpc_url.clone()).get_quote().await {
+            Ok(quote) => Some(quote),
+            Err(e) => {
+                tracing::warn!("Failed to get ASB quote: {:#}", e);
+                None
+            }
+        };
+        let market_price = match config.exchange_client().ticker().await {
+            Ok(ticker) => Some(ticker.last),
+            Err(e) => {
+                tracing::warn!("Failed to get BTC/XMR price for the ASB quote: {:#}", e);
+                None
+            }
+        };
+
+        let metrics = AsbQuoteMetrics::new(quote.as_ref(), market_price);
+        self.evaluate_rules(&metrics).await;
+        if let Err(e) = self.db.store_asb_quote(&metrics).await {
+            tracing::error!("Failed to store ASB quote: {}", e);
+        }
+    }
+
     /// Collect Electrs metrics
     async fn collect_electrs(&self) {
         let client = ElectrsClient::new("electrs".to_string());
-        match client.get_metrics().await {
-            Ok(metrics) => {
-                if let Err(e) = self.db.store_electrs_metrics(&metrics).await {
-                    tracing::error!("Failed to store Electrs metrics: {}", e);
+        let metrics = match client.get_metrics().await {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                // A failed status check counts as a failed probe
+                tracing::error!("Failed to collect Electrs metrics: {}", e);
+                ElectrsMetrics {
+                    up: false,
+                    indexed_blocks: 0,
                 }
             }
-            Err(e) => tracing::error!("Failed to collect Electrs metrics: {}", e),
+        };
+
+        self.evaluate_rules(&metrics).await;
+
+        let status = self.electrs_status.lock().unwrap().observe(metrics.up);
+        if status == ProbeStatus::GracePeriod {
+            tracing::warn!("Electrs probe failed, not yet marking it down");
+            return;
+        }
+
+        if let Err(e) = self.db.store_electrs_metrics(&metrics).await {
+            tracing::error!("Failed to store Electrs metrics: {}", e);
         }
     }
 
     /// Collect container health metrics
     async fn collect_containers(&self) {
-        let client = ContainerHealthClient::new();
-        let container_refs: Vec<&str> = self
-            .config
-            .containers
-            .names
-            .iter()
-            .map(|s| s.as_str())
-            .collect();
-
-        match client.get_metrics(&container_refs).await {
+        let config = self.config.get();
+        let container_refs: Vec<&str> =
+            config.containers.names.iter().map(|s| s.as_str()).collect();
+
+        match self.containers.get_metrics(&container_refs).await {
             Ok(metrics) => {
                 if let Err(e) = self.db.store_container_metrics(&metrics).await {
                     tracing::error!("Failed to store container metrics: {}", e);
@@ -137,4 +510,164 @@ impl MetricsCollector {
             Err(e) => tracing::error!("Failed to collect container metrics: {}", e),
         }
     }
+
+    /// Collect mempool metrics and the status of the watched addresses
+    async fn collect_mempool(&self) {
+        let config = self.config.get();
+        let Some(api_url) = config.mempool.api_url.clone() else {
+            return;
+        };
+        let client = MempoolClient::new(api_url);
+
+        match client.get_metrics().await {
+            Ok(metrics) => {
+                if let Err(e) = self.db.store_mempool_metrics(&metrics).await {
+                    tracing::error!("Failed to store mempool metrics: {}", e);
+                }
+            }
+            Err(e) => tracing::error!("Failed to collect mempool metrics: {:#}", e),
+        }
+
+        // An address that fails to resolve is skipped for this cycle only
+        let mut addresses = Vec::new();
+        for address in &config.mempool.addresses {
+            match client.get_address(address).await {
+                Ok(metrics) => addresses.push(metrics),
+                Err(e) => tracing::error!("Failed to collect address metrics: {:#}", e),
+            }
+        }
+        if !addresses.is_empty() {
+            if let Err(e) = self.db.store_address_metrics(&addresses).await {
+                tracing::error!("Failed to store address metrics: {}", e);
+            }
+        }
+    }
+
+    /// Collect BTC and XMR prices
+    async fn collect_prices(&self) {
+        match KrakenClient::public().get_prices().await {
+            Ok(prices) => {
+                if let Err(e) = self.db.store_price_metrics(&prices).await {
+                    tracing::error!("Failed to store price metrics: {}", e);
+                }
+            }
+            Err(e) => tracing::error!("Failed to collect price metrics: {:#}", e),
+        }
+    }
+
+    /// Collect the free space of every configured path
+    async fn collect_disk(&self) {
+        let config = self.config.get();
+
+        // A path that can't be read is skipped for this cycle only
+        let mut metrics = Vec::new();
+        for path in &config.disk.paths {
+            match disk::disk_usage(path).await {
+                Ok(usage) => metrics.push(usage),
+                Err(e) => {
+                    tracing::error!("Failed to collect disk metrics for {}: {:#}", path.name, e)
+                }
+            }
+        }
+        if metrics.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.db.store_disk_metrics(&metrics).await {
+            tracing::error!("Failed to store disk metrics: {}", e);
+        }
+        self.evaluate_rules(&metrics).await;
+    }
+
+    /// Collect the host's resource usage
+    async fn collect_system(&self) {
+        let metrics = self.system.lock().unwrap().sample();
+
+        if let Err(e) = self.db.store_system_metrics(&metrics).await {
+            tracing::error!("Failed to store system metrics: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_failure_does_not_mark_down() {
+        let mut status = DownDebounce::new(3);
+
+        assert_eq!(status.observe(true), ProbeStatus::Up);
+        assert_eq!(status.observe(false), ProbeStatus::GracePeriod);
+        assert_eq!(status.observe(true), ProbeStatus::Up);
+
+        // The earlier failure doesn't count towards a new streak
+        assert_eq!(status.observe(false), ProbeStatus::GracePeriod);
+        assert_eq!(status.observe(false), ProbeStatus::GracePeriod);
+        assert_eq!(status.observe(true), ProbeStatus::Up);
+    }
+
+    #[test]
+    fn test_consecutive_failures_mark_down() {
+        let mut status = DownDebounce::new(3);
+
+        assert_eq!(status.observe(false), ProbeStatus::GracePeriod);
+        assert_eq!(status.observe(false), ProbeStatus::GracePeriod);
+        assert_eq!(status.observe(false), ProbeStatus::Down);
+        assert_eq!(status.observe(false), ProbeStatus::Down);
+
+        // One success brings it straight back up
+        assert_eq!(status.observe(true), ProbeStatus::Up);
+    }
+
+    #[test]
+    fn test_source_intervals_follow_config() {
+        let mut config = CollectorConfig::default();
+        for source in MetricsSource::ALL {
+            assert_eq!(
+                source.interval(&config),
+                Some(TokioDuration::from_secs(60)),
+                "{}",
+                source.name()
+            );
+        }
+
+        config.bitcoin_interval_secs = 30;
+        config.containers_interval_secs = 10;
+        config.asb = false;
+        assert_eq!(
+            MetricsSource::Bitcoin.interval(&config),
+            Some(TokioDuration::from_secs(30))
+        );
+        assert_eq!(
+            MetricsSource::Containers.interval(&config),
+            Some(TokioDuration::from_secs(10))
+        );
+        assert_eq!(MetricsSource::Asb.interval(&config), None);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_half_the_interval() {
+        let jitter = max_jitter(TokioDuration::from_secs(10), TokioDuration::from_secs(30));
+        assert_eq!(jitter, TokioDuration::from_secs(5));
+        assert_eq!(
+            max_jitter(TokioDuration::from_secs(120), TokioDuration::from_secs(5)),
+            TokioDuration::from_secs(5)
+        );
+
+        for _ in 0..100 {
+            assert!(random_delay(jitter) <= jitter);
+        }
+        assert_eq!(random_delay(TokioDuration::ZERO), TokioDuration::ZERO);
+    }
+
+    #[test]
+    fn test_threshold_of_one_disables_debounce() {
+        let mut status = DownDebounce::new(1);
+        assert_eq!(status.observe(false), ProbeStatus::Down);
+
+        // Zero is treated as one
+        let mut status = DownDebounce::new(0);
+        assert_eq!(status.observe(false), ProbeStatus::Down);
+    }
 }
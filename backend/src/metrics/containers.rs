@@ -0,0 +1,644 @@
+//! Container health probes
+//!
+//! Deployments run the stack under Podman, Docker or plain systemd units.
+//! Each runtime has a [`ContainerProbe`]; [`ContainerHealthClient`] picks one
+//! according to `containers.container_runtime`, or the first available one
+//! when that is `auto`, detected once and kept for the life of the client and
+//! its clones. The runtime's commands block, so a collection runs
+//! them on the blocking pool, and usage of all running containers is read
+//! with a single `stats` call.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Arc, OnceLock};
+
+use super::types::{ContainerMetrics, ContainerStats};
+use crate::config::ContainerRuntime;
+
+/// Reads up/restarts/uptime of named containers from one runtime
+pub trait ContainerProbe: Send + Sync {
+    fn runtime(&self) -> ContainerRuntime;
+
+    /// Whether the runtime's tooling is installed and usable
+    fn is_available(&self) -> bool;
+
+    fn probe(&self, name: &str) -> Result<ContainerMetrics>;
+
+    /// Current usage of the running containers `names`, by name, for the
+    /// runtimes whose [`probe`](Self::probe) leaves it out
+    fn stats(&self, _names: &[&str]) -> HashMap<String, ContainerStats> {
+        HashMap::new()
+    }
+}
+
+/// Run a command and return its stdout, failing on a non-zero exit
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} {} exited with {}: {}",
+            program,
+            args.join(" "),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn uptime_since(started_at: DateTime<Utc>) -> u64 {
+    (Utc::now() - started_at).num_seconds().max(0) as u64
+}
+
+/// Bytes in a size as `stats` prints it, e.g. `12.5MiB`, `1.2kB` or `0B`
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim();
+    let unit_start = size.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = size.split_at(unit_start);
+    let multiplier: f64 = match unit {
+        "B" => 1.0,
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    let number: f64 = number.trim().parse().ok()?;
+    Some((number * multiplier).round() as u64)
+}
+
+/// Usage per container from `stats --format json` output
+///
+/// Podman prints one array of objects with snake_case keys, Docker one object
+/// per line keyed like its template fields; both hold the same strings, e.g.
+/// `1.50%`, `12.5MiB / 7.6GiB` and `1.2kB / 648B`.
+fn parse_stats(output: &str) -> HashMap<String, ContainerStats> {
+    let entries: Vec<Value> = match serde_json::from_str(output.trim()) {
+        Ok(Value::Array(entries)) => entries,
+        _ => output
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect(),
+    };
+
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let field = |podman: &str, docker: &str| {
+                entry
+                    .get(podman)
+                    .or_else(|| entry.get(docker))
+                    .and_then(Value::as_str)
+            };
+            let name = field("name", "Name")?;
+            let cpu = field("cpu_percent", "CPUPerc");
+            let memory = field("mem_usage", "MemUsage").and_then(|m| m.split_once(" / "));
+            let net = field("net_io", "NetIO").and_then(|n| n.split_once(" / "));
+
+            let stats = ContainerStats {
+                cpu_percent: cpu.and_then(|cpu| cpu.trim().trim_end_matches('%').parse().ok()),
+                memory_bytes: memory.and_then(|(usage, _)| parse_size(usage)),
+                memory_limit_bytes: memory.and_then(|(_, limit)| parse_size(limit)),
+                net_rx_bytes: net.and_then(|(rx, _)| parse_size(rx)),
+                net_tx_bytes: net.and_then(|(_, tx)| parse_size(tx)),
+            };
+            (stats != ContainerStats::default()).then(|| (name.to_string(), stats))
+        })
+        .collect()
+}
+
+/// Current usage of running containers through one `sudo <tool> stats` call
+///
+/// A failed call only costs the stats, not the rest of the probe.
+fn runtime_stats(tool: &str, names: &[&str]) -> HashMap<String, ContainerStats> {
+    let mut args = vec![tool, "stats", "--no-stream", "--format", "json"];
+    args.extend(names);
+    match run("sudo", &args) {
+        Ok(output) => parse_stats(&output),
+        Err(e) => {
+            tracing::debug!("No stats for containers {}: {:#}", names.join(", "), e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Podman containers, queried through `sudo podman`
+pub struct PodmanProbe;
+
+impl ContainerProbe for PodmanProbe {
+    fn runtime(&self) -> ContainerRuntime {
+        ContainerRuntime::Podman
+    }
+
+    fn is_available(&self) -> bool {
+        run("sudo", &["podman", "--version"]).is_ok()
+    }
+
+    fn probe(&self, name: &str) -> Result<ContainerMetrics> {
+        let filter = format!("name=^{}$", name);
+        let status = run(
+            "sudo",
+            &[
+                "podman",
+                "ps",
+                "-a",
+                "--filter",
+                &filter,
+                "--format",
+                "{{.Status}}",
+            ],
+        )
+        .context("Failed to check container status")?;
+        let up = status.contains("Up");
+
+        let (restarts, uptime_seconds) = if up {
+            // Podman exposes StartedAt as a time value, so it can format epoch seconds
+            let inspect = run(
+                "sudo",
+                &[
+                    "podman",
+                    "inspect",
+                    name,
+                    "--format",
+                    "{{.RestartCount}} {{.State.StartedAt.Unix}}",
+                ],
+            )
+            .unwrap_or_default();
+            let mut fields = inspect.split_whitespace();
+            let restarts = fields.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+            let uptime = fields
+                .next()
+                .and_then(|s| s.parse().ok())
+                .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                .map(uptime_since)
+                .unwrap_or(0);
+            (restarts, uptime)
+        } else {
+            (0, 0)
+        };
+
+        Ok(ContainerMetrics {
+            name: name.to_string(),
+            up,
+            restarts,
+            uptime_seconds,
+            stats: None,
+        })
+    }
+
+    fn stats(&self, names: &[&str]) -> HashMap<String, ContainerStats> {
+        runtime_stats("podman", names)
+    }
+}
+
+/// Docker containers, queried through `sudo docker`
+pub struct DockerProbe;
+
+impl ContainerProbe for DockerProbe {
+    fn runtime(&self) -> ContainerRuntime {
+        ContainerRuntime::Docker
+    }
+
+    fn is_available(&self) -> bool {
+        run("sudo", &["docker", "--version"]).is_ok()
+    }
+
+    fn probe(&self, name: &str) -> Result<ContainerMetrics> {
+        let filter = format!("name=^{}$", name);
+        let status = run(
+            "sudo",
+            &[
+                "docker",
+                "ps",
+                "-a",
+                "--filter",
+                &filter,
+                "--format",
+                "{{.Status}}",
+            ],
+        )
+        .context("Failed to check container status")?;
+        let up = status.contains("Up");
+
+        let (restarts, uptime_seconds) = if up {
+            // Docker reports StartedAt as an RFC 3339 string
+            let inspect = run(
+                "sudo",
+                &[
+                    "docker",
+                    "inspect",
+                    name,
+                    "--format",
+                    "{{.RestartCount}} {{.State.StartedAt}}",
+                ],
+            )
+            .unwrap_or_default();
+            let mut fields = inspect.split_whitespace();
+            let restarts = fields.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+            let uptime = fields
+                .next()
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .map(|started| uptime_since(started.with_timezone(&Utc)))
+                .unwrap_or(0);
+            (restarts, uptime)
+        } else {
+            (0, 0)
+        };
+
+        Ok(ContainerMetrics {
+            name: name.to_string(),
+            up,
+            restarts,
+            uptime_seconds,
+            stats: None,
+        })
+    }
+
+    fn stats(&self, names: &[&str]) -> HashMap<String, ContainerStats> {
+        runtime_stats("docker", names)
+    }
+}
+
+/// systemd units (including systemd-nspawn `container@` units), via `systemctl show`
+pub struct SystemdProbe;
+
+impl ContainerProbe for SystemdProbe {
+    fn runtime(&self) -> ContainerRuntime {
+        ContainerRuntime::Systemd
+    }
+
+    fn is_available(&self) -> bool {
+        run("systemctl", &["--version"]).is_ok()
+    }
+
+    fn probe(&self, name: &str) -> Result<ContainerMetrics> {
+        let output = run(
+            "systemctl",
+            &[
+                "show",
+                name,
+                "--timestamp=unix",
+                "--property=ActiveState,NRestarts,ActiveEnterTimestamp,MemoryCurrent,MemoryMax,IPIngressBytes,IPEgressBytes",
+            ],
+        )
+        .context("Failed to check unit status")?;
+
+        Ok(parse_systemctl_show(name, &output, Utc::now()))
+    }
+}
+
+/// Metrics from `systemctl show --timestamp=unix` property output
+fn parse_systemctl_show(name: &str, output: &str, now: DateTime<Utc>) -> ContainerMetrics {
+    let properties: HashMap<&str, &str> = output
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .collect();
+
+    let up = properties.get("ActiveState") == Some(&"active");
+    let restarts = properties
+        .get("NRestarts")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+    let uptime_seconds = properties
+        .get("ActiveEnterTimestamp")
+        .filter(|_| up)
+        .and_then(|s| s.strip_prefix('@')?.parse().ok())
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map(|started| (now - started).num_seconds().max(0) as u64)
+        .unwrap_or(0);
+
+    // Unset and unlimited values are `[not set]`, `infinity` or u64::MAX
+    let bytes = |key: &str| {
+        properties
+            .get(key)
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|&bytes| bytes != u64::MAX)
+    };
+    let stats = ContainerStats {
+        cpu_percent: None,
+        memory_bytes: bytes("MemoryCurrent"),
+        memory_limit_bytes: bytes("MemoryMax"),
+        net_rx_bytes: bytes("IPIngressBytes"),
+        net_tx_bytes: bytes("IPEgressBytes"),
+    };
+
+    ContainerMetrics {
+        name: name.to_string(),
+        up,
+        restarts,
+        uptime_seconds,
+        stats: (up && stats != ContainerStats::default()).then_some(stats),
+    }
+}
+
+/// Container health checker
+#[derive(Clone)]
+pub struct ContainerHealthClient {
+    runtime: ContainerRuntime,
+    /// Candidate probes, in the order `auto` tries them
+    probes: Vec<Arc<dyn ContainerProbe>>,
+    /// Index of the probe `auto` detected, if any, once it has looked
+    detected: Arc<OnceLock<Option<usize>>>,
+}
+
+impl ContainerHealthClient {
+    pub fn new(runtime: ContainerRuntime) -> Self {
+        Self::with_probes(
+            runtime,
+            vec![
+                Arc::new(PodmanProbe),
+                Arc::new(DockerProbe),
+                Arc::new(SystemdProbe),
+            ],
+        )
+    }
+
+    pub fn with_probes(runtime: ContainerRuntime, probes: Vec<Arc<dyn ContainerProbe>>) -> Self {
+        Self {
+            runtime,
+            probes,
+            detected: Arc::default(),
+        }
+    }
+
+    /// Probe for the configured runtime, or the first available one for `auto`
+    ///
+    /// `auto` checks which runtimes are available on the first call only; a
+    /// runtime installed later is picked up on restart. This blocks while it
+    /// checks, so call it off the async workers.
+    pub fn select_probe(&self) -> Result<&dyn ContainerProbe> {
+        let probe = match self.runtime {
+            ContainerRuntime::Auto => self
+                .detected
+                .get_or_init(|| self.probes.iter().position(|probe| probe.is_available()))
+                .map(|index| &self.probes[index]),
+            runtime => self.probes.iter().find(|probe| probe.runtime() == runtime),
+        };
+
+        probe
+            .map(|probe| probe.as_ref())
+            .with_context(|| format!("No usable container runtime for '{:?}'", self.runtime))
+    }
+
+    pub async fn get_metrics(&self, container_names: &[&str]) -> Result<Vec<ContainerMetrics>> {
+        let client = self.clone();
+        let names: Vec<String> = container_names
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        tokio::task::spawn_blocking(move || client.probe_all(&names))
+            .await
+            .context("Probing containers panicked")?
+    }
+
+    /// Metrics of the containers `names`, with the usage of the running ones
+    /// the probe leaves out read in one go
+    fn probe_all(&self, names: &[String]) -> Result<Vec<ContainerMetrics>> {
+        let probe = self.select_probe()?;
+        let mut metrics = names
+            .iter()
+            .map(|name| probe.probe(name))
+            .collect::<Result<Vec<_>>>()?;
+
+        let missing = |m: &ContainerMetrics| m.up && m.stats.is_none();
+        let running: Vec<&str> = metrics
+            .iter()
+            .filter(|m| missing(m))
+            .map(|m| m.name.as_str())
+            .collect();
+        if running.is_empty() {
+            return Ok(metrics);
+        }
+        let mut stats = probe.stats(&running);
+        for m in metrics.iter_mut().filter(|m| missing(m)) {
+            m.stats = stats.remove(&m.name);
+        }
+        Ok(metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeProbe {
+        runtime: ContainerRuntime,
+        available: bool,
+        /// Times availability was checked
+        checks: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl ContainerProbe for FakeProbe {
+        fn runtime(&self) -> ContainerRuntime {
+            self.runtime
+        }
+
+        fn is_available(&self) -> bool {
+            self.checks
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.available
+        }
+
+        fn probe(&self, name: &str) -> Result<ContainerMetrics> {
+            Ok(ContainerMetrics {
+                name: format!("{:?}:{}", self.runtime, name),
+                up: true,
+                restarts: 0,
+                uptime_seconds: 0,
+                stats: None,
+            })
+        }
+
+        /// Every container's usage, noting how many were asked for at once
+        fn stats(&self, names: &[&str]) -> HashMap<String, ContainerStats> {
+            names
+                .iter()
+                .map(|name| {
+                    let stats = ContainerStats {
+                        cpu_percent: Some(names.len() as f64),
+                        ..ContainerStats::default()
+                    };
+                    (name.to_string(), stats)
+                })
+                .collect()
+        }
+    }
+
+    fn client_with(runtime: ContainerRuntime, available: [bool; 3]) -> ContainerHealthClient {
+        counted_client(runtime, available, Arc::default())
+    }
+
+    /// Client whose probes count their availability checks in `checks`
+    fn counted_client(
+        runtime: ContainerRuntime,
+        available: [bool; 3],
+        checks: Arc<std::sync::atomic::AtomicUsize>,
+    ) -> ContainerHealthClient {
+        let runtimes = [
+            ContainerRuntime::Podman,
+            ContainerRuntime::Docker,
+            ContainerRuntime::Systemd,
+        ];
+        ContainerHealthClient::with_probes(
+            runtime,
+            runtimes
+                .into_iter()
+                .zip(available)
+                .map(|(runtime, available)| {
+                    Arc::new(FakeProbe {
+                        runtime,
+                        available,
+                        checks: checks.clone(),
+                    }) as Arc<dyn ContainerProbe>
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_configured_runtime_selects_its_probe() {
+        for runtime in [
+            ContainerRuntime::Podman,
+            ContainerRuntime::Docker,
+            ContainerRuntime::Systemd,
+        ] {
+            let client = client_with(runtime, [true; 3]);
+            assert_eq!(client.select_probe().unwrap().runtime(), runtime);
+        }
+
+        // An explicit choice is honoured even if the tool looks unavailable
+        let client = client_with(ContainerRuntime::Docker, [true, false, true]);
+        assert_eq!(
+            client.select_probe().unwrap().runtime(),
+            ContainerRuntime::Docker
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auto_falls_through_to_available_runtime() {
+        let client = client_with(ContainerRuntime::Auto, [false, false, true]);
+        assert_eq!(
+            client.select_probe().unwrap().runtime(),
+            ContainerRuntime::Systemd
+        );
+        let metrics = client.get_metrics(&["bitcoind"]).await.unwrap();
+        assert_eq!(metrics[0].name, "Systemd:bitcoind");
+        assert!(metrics[0].stats.is_some());
+
+        let client = client_with(ContainerRuntime::Auto, [false, true, true]);
+        assert_eq!(
+            client.select_probe().unwrap().runtime(),
+            ContainerRuntime::Docker
+        );
+
+        let client = client_with(ContainerRuntime::Auto, [false; 3]);
+        assert!(client.select_probe().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_auto_detects_the_runtime_once() {
+        let checks = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let client = counted_client(ContainerRuntime::Auto, [false, true, true], checks.clone());
+
+        for _ in 0..3 {
+            client.get_metrics(&["bitcoind"]).await.unwrap();
+            assert_eq!(
+                client.clone().select_probe().unwrap().runtime(),
+                ContainerRuntime::Docker
+            );
+        }
+        // Podman, then Docker, on the first collection only
+        assert_eq!(checks.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stats_are_read_in_one_call() {
+        let client = client_with(ContainerRuntime::Podman, [true; 3]);
+        let metrics = client
+            .get_metrics(&["bitcoind", "monerod", "asb"])
+            .await
+            .unwrap();
+        assert_eq!(metrics.len(), 3);
+        for m in metrics {
+            assert_eq!(m.stats.unwrap().cpu_percent, Some(3.0), "{}", m.name);
+        }
+    }
+
+    #[test]
+    fn test_parse_systemctl_show() {
+        let now = DateTime::from_timestamp(1_700_000_600, 0).unwrap();
+        let output = "ActiveState=active\nNRestarts=2\nActiveEnterTimestamp=@1700000000\n";
+        let metrics = parse_systemctl_show("monerod", output, now);
+        assert!(metrics.up);
+        assert_eq!(metrics.restarts, 2);
+        assert_eq!(metrics.uptime_seconds, 600);
+
+        let output = "ActiveState=failed\nNRestarts=5\nActiveEnterTimestamp=@1700000000\n";
+        let metrics = parse_systemctl_show("monerod", output, now);
+        assert!(!metrics.up);
+        assert_eq!(metrics.restarts, 5);
+        assert_eq!(metrics.uptime_seconds, 0);
+        assert_eq!(metrics.stats, None);
+    }
+
+    #[test]
+    fn test_parse_systemctl_show_usage() {
+        let now = DateTime::from_timestamp(1_700_000_600, 0).unwrap();
+        let output = "ActiveState=active\nMemoryCurrent=104857600\nMemoryMax=infinity\n\
+                      IPIngressBytes=18446744073709551615\nIPEgressBytes=[no data]\n";
+        let stats = parse_systemctl_show("monerod", output, now).stats.unwrap();
+        assert_eq!(stats.memory_bytes, Some(104_857_600));
+        assert_eq!(stats.memory_limit_bytes, None);
+        assert_eq!(stats.net_rx_bytes, None);
+        assert_eq!(stats.cpu_percent, None);
+
+        // No figures at all is no stats
+        let output = "ActiveState=active\nMemoryCurrent=[not set]\n";
+        assert_eq!(parse_systemctl_show("monerod", output, now).stats, None);
+    }
+
+    #[test]
+    fn test_parse_stats() {
+        // Docker: one object per line
+        let output = r#"{"BlockIO":"0B / 0B","CPUPerc":"1.50%","Container":"abc","ID":"abc","MemPerc":"0.16%","MemUsage":"12.5MiB / 7.6GiB","Name":"bitcoind","NetIO":"1.2kB / 648B","PIDs":"3"}
+{"CPUPerc":"0.00%","MemUsage":"1MiB / 7.6GiB","Name":"monerod","NetIO":"0B / 0B"}
+"#;
+        let stats = parse_stats(output);
+        assert_eq!(stats.len(), 2);
+        let bitcoind = &stats["bitcoind"];
+        assert_eq!(bitcoind.cpu_percent, Some(1.5));
+        assert_eq!(bitcoind.memory_bytes, Some(13_107_200));
+        assert_eq!(bitcoind.memory_limit_bytes, Some(8_160_437_862));
+        assert_eq!(bitcoind.net_rx_bytes, Some(1_200));
+        assert_eq!(bitcoind.net_tx_bytes, Some(648));
+        assert_eq!(stats["monerod"].memory_bytes, Some(1_048_576));
+
+        // Podman: one array, sizes in SI units, and `--` before the first sample
+        let output = r#"[
+            {"id": "abc", "name": "asb", "cpu_percent": "--", "mem_usage": "1.18MB / 33.53GB", "net_io": "0B / 0B", "pids": "1"},
+            {"id": "def", "name": "electrs", "cpu_percent": "--", "mem_usage": "-- / --", "net_io": "-- / --"}
+        ]"#;
+        let stats = parse_stats(output);
+        let asb = &stats["asb"];
+        assert_eq!(asb.cpu_percent, None);
+        assert_eq!(asb.memory_bytes, Some(1_180_000));
+        assert_eq!(asb.net_tx_bytes, Some(0));
+        // No figures at all is no stats
+        assert!(!stats.contains_key("electrs"));
+
+        assert!(parse_stats("").is_empty());
+        assert!(parse_stats("Error: no such container").is_empty());
+        assert_eq!(parse_size("3 parsecs"), None);
+    }
+}
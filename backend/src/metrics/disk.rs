@@ -0,0 +1,126 @@
+//! Free space of the filesystems holding the data directories
+//!
+//! Read through POSIX `df -P`, which reports the filesystem a path lives on
+//! and works the same across the distributions the backend runs on.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::time::Duration;
+use tokio::process::Command;
+
+use super::types::DiskMetrics;
+use crate::config::DiskPath;
+
+/// How long `df` may take; a hung network mount shouldn't stall the collector
+const DF_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Usage of the filesystem holding `disk.path`
+pub async fn disk_usage(disk: &DiskPath) -> Result<DiskMetrics> {
+    let output = Command::new("df")
+        .args(["-P", "-k"])
+        .arg(&disk.path)
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(DF_TIMEOUT, output)
+        .await
+        .with_context(|| format!("df {} timed out", disk.path.display()))?
+        .context("Failed to run df")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "df {} exited with {}: {}",
+            disk.path.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    parse_df(
+        &disk.name,
+        &disk.path,
+        &String::from_utf8_lossy(&output.stdout),
+    )
+}
+
+/// Parse `df -P -k` output for a single path
+///
+/// ```text
+/// Filesystem     1024-blocks      Used Available Capacity Mounted on
+/// /dev/nvme0n1p2   959786032 612345678 298765432      68% /mnt/vault
+/// ```
+///
+/// The columns are found from the capacity percentage, since the device and
+/// the mount point may both contain spaces.
+fn parse_df(name: &str, path: &Path, output: &str) -> Result<DiskMetrics> {
+    let line = output.lines().nth(1).context("df printed no filesystem")?;
+    let columns: Vec<&str> = line.split_whitespace().collect();
+    let capacity = columns
+        .iter()
+        .position(|column| {
+            column
+                .strip_suffix('%')
+                .is_some_and(|percent| percent.parse::<u64>().is_ok())
+        })
+        .filter(|&index| index >= 4)
+        .with_context(|| format!("Unexpected df output: {}", line))?;
+
+    let kib = |index: usize| {
+        columns[index]
+            .parse::<u64>()
+            .map(|blocks| blocks * 1024)
+            .with_context(|| format!("Unexpected df output: {}", line))
+    };
+
+    Ok(DiskMetrics {
+        name: name.to_string(),
+        path: path.display().to_string(),
+        mount_point: columns[capacity + 1..].join(" "),
+        total_bytes: kib(capacity - 3)?,
+        used_bytes: kib(capacity - 2)?,
+        available_bytes: kib(capacity - 1)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_df() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/nvme0n1p2   959786032 612345678 298765432      68% /mnt/vault\n";
+        let metrics = parse_df("bitcoind", Path::new("/mnt/vault/bitcoind-data"), output).unwrap();
+        assert_eq!(metrics.path, "/mnt/vault/bitcoind-data");
+        assert_eq!(metrics.mount_point, "/mnt/vault");
+        assert_eq!(metrics.total_bytes, 959_786_032 * 1024);
+        assert_eq!(metrics.used_bytes, 612_345_678 * 1024);
+        assert_eq!(metrics.available_bytes, 298_765_432 * 1024);
+        assert!((metrics.free_percent() - 32.79).abs() < 0.01);
+
+        // Spaces in the device and the mount point
+        let output = "Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+                      //nas/block chain 1000 900 100 90% /mnt/block chain\n";
+        let metrics = parse_df("monerod", Path::new("/mnt/block chain"), output).unwrap();
+        assert_eq!(metrics.mount_point, "/mnt/block chain");
+        assert_eq!(metrics.available_bytes, 100 * 1024);
+
+        assert!(parse_df("monerod", Path::new("/"), "Filesystem\n").is_err());
+        assert!(parse_df("monerod", Path::new("/"), "header\nnone - - - - /\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_disk_usage_of_temp_dir() {
+        let disk = DiskPath {
+            name: "tmp".to_string(),
+            path: std::env::temp_dir(),
+        };
+        let metrics = disk_usage(&disk).await.unwrap();
+        assert!(metrics.total_bytes > 0);
+        assert!(!metrics.mount_point.is_empty());
+
+        let missing = DiskPath {
+            name: "missing".to_string(),
+            path: std::env::temp_dir().join(format!("eigenix-no-disk-{}", std::process::id())),
+        };
+        assert!(disk_usage(&missing).await.is_err());
+    }
+}
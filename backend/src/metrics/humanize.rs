@@ -0,0 +1,64 @@
+//! Human-readable renderings of large metric values
+//!
+//! Counters such as mining difficulty use SI (powers of 1000) suffixes;
+//! byte sizes use binary (powers of 1024) IEC suffixes. The raw numbers stay
+//! in the API responses for charting.
+
+const SI_SUFFIXES: [&str; 6] = ["k", "M", "G", "T", "P", "E"];
+const BINARY_SUFFIXES: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// Scale `value` down by `base` until it fits, returning the scaled value and suffix index
+///
+/// `None` when the value is already below `base` and needs no suffix.
+fn scale(value: u64, base: f64, suffixes: usize) -> Option<(f64, usize)> {
+    let mut scaled = value as f64;
+    let mut divisions = 0;
+    while scaled >= base && divisions < suffixes {
+        scaled /= base;
+        divisions += 1;
+    }
+
+    divisions.checked_sub(1).map(|index| (scaled, index))
+}
+
+/// Counter with an SI suffix and two decimals, e.g. `352.89 G`
+pub fn humanize_si(value: u64) -> String {
+    match scale(value, 1000.0, SI_SUFFIXES.len()) {
+        Some((scaled, i)) => format!("{:.2} {}", scaled, SI_SUFFIXES[i]),
+        None => value.to_string(),
+    }
+}
+
+/// Byte size with a binary suffix and one decimal, e.g. `412.3 GiB`
+pub fn humanize_bytes(bytes: u64) -> String {
+    match scale(bytes, 1024.0, BINARY_SUFFIXES.len()) {
+        Some((scaled, i)) => format!("{:.1} {}", scaled, BINARY_SUFFIXES[i]),
+        None => format!("{} B", bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_humanize_difficulty_si() {
+        assert_eq!(humanize_si(0), "0");
+        assert_eq!(humanize_si(999), "999");
+        assert_eq!(humanize_si(1_000), "1.00 k");
+        assert_eq!(humanize_si(1_500_000), "1.50 M");
+        assert_eq!(humanize_si(352_891_234_567), "352.89 G");
+        assert_eq!(humanize_si(u64::MAX), "18.45 E");
+    }
+
+    #[test]
+    fn test_humanize_bytes_binary() {
+        assert_eq!(humanize_bytes(0), "0 B");
+        assert_eq!(humanize_bytes(1_023), "1023 B");
+        assert_eq!(humanize_bytes(1_024), "1.0 KiB");
+        assert_eq!(humanize_bytes(1_536), "1.5 KiB");
+        // 412.3 GiB, not the 442.7 an SI scale would give
+        assert_eq!(humanize_bytes(442_707_116_032), "412.3 GiB");
+        assert_eq!(humanize_bytes(5 * 1024u64.pow(4)), "5.0 TiB");
+    }
+}
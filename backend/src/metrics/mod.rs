@@ -4,10 +4,25 @@
 //! - Metric type definitions
 //! - RPC clients for collecting metrics
 //! - Background collector service
+//! - Latency/outcome tracking for outbound service calls
+//! - The backend's own request, collector, database and process metrics
+//! - Container health probes for Podman, Docker and systemd
+//! - Free space of the filesystems holding the data directories
+//! - CPU, memory, swap, load and network usage of the host
+//! - Human-readable formatting of large counters and byte sizes
+//! - Prometheus exposition of the latest stored node metrics
 
+pub mod backend;
+pub mod clients;
 pub mod collector;
+pub mod containers;
+pub mod disk;
+pub mod humanize;
+pub mod prometheus;
+pub mod system;
 pub mod types;
 
 // Re-export types for convenience
 pub use collector::MetricsCollector;
+pub use containers::{ContainerHealthClient, ContainerProbe};
 pub use types::*;
@@ -0,0 +1,653 @@
+//! Prometheus exposition of the collected node metrics
+//!
+//! Renders the latest stored sample from each collector as gauges and
+//! counters, appended to the client call metrics on `GET /metrics/prometheus`.
+//! Sources with no stored sample are left out rather than reported as zero.
+
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    config::Config,
+    db::{disk::StoredDiskMetrics, MetricsSummary, StoredContainerMetrics},
+};
+
+use super::clients::escape_label;
+
+/// Deployment labels attached to the node metrics
+#[derive(Debug, Clone)]
+pub struct ExporterLabels {
+    pub bitcoin_network: String,
+    pub monero_network: String,
+    pub bitcoin_wallet: String,
+    pub monero_wallet: String,
+}
+
+impl ExporterLabels {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            bitcoin_network: config.bitcoin.network.to_lowercase(),
+            monero_network: config.monero.network.to_lowercase(),
+            bitcoin_wallet: config.wallets.bitcoin_wallet_name.clone(),
+            monero_wallet: config.wallets.monero_wallet_name.clone(),
+        }
+    }
+}
+
+/// Writes metric families with their HELP and TYPE lines
+struct Exposition {
+    out: String,
+}
+
+impl Exposition {
+    fn family(&mut self, name: &str, kind: &str, help: &str) -> &mut Self {
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+        self
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) -> &mut Self {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+            .collect();
+        let _ = writeln!(self.out, "{}{{{}}} {}", name, labels.join(","), value);
+        self
+    }
+
+    fn metric(
+        &mut self,
+        name: &str,
+        kind: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        value: f64,
+    ) -> &mut Self {
+        self.family(name, kind, help).sample(name, labels, value)
+    }
+}
+
+fn bool_value(up: bool) -> f64 {
+    if up {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn unix_secs(timestamp: DateTime<Utc>) -> f64 {
+    timestamp.timestamp_millis() as f64 / 1000.0
+}
+
+/// Render the latest sample of each source in Prometheus text format
+pub fn render_summary(summary: &MetricsSummary, labels: &ExporterLabels) -> String {
+    let mut exp = Exposition { out: String::new() };
+    let mut sampled_at = Vec::new();
+
+    if let Some(bitcoin) = &summary.bitcoin {
+        let network = [("network", labels.bitcoin_network.as_str())];
+        exp.metric(
+            "eigenix_bitcoin_blocks",
+            "gauge",
+            "Blocks validated by bitcoind",
+            &network,
+            bitcoin.blocks as f64,
+        )
+        .metric(
+            "eigenix_bitcoin_headers",
+            "gauge",
+            "Block headers known to bitcoind",
+            &network,
+            bitcoin.headers as f64,
+        )
+        .metric(
+            "eigenix_bitcoin_verification_progress",
+            "gauge",
+            "Chain verification progress from 0 to 1",
+            &network,
+            bitcoin.verification_progress,
+        )
+        .metric(
+            "eigenix_bitcoin_size_on_disk_bytes",
+            "gauge",
+            "Size of the block and undo files on disk",
+            &network,
+            bitcoin.size_on_disk as f64,
+        );
+        if let Some(balance) = bitcoin.wallet_balance {
+            exp.metric(
+                "eigenix_bitcoin_wallet_balance_btc",
+                "gauge",
+                "Bitcoin wallet balance in BTC",
+                &[
+                    ("network", labels.bitcoin_network.as_str()),
+                    ("wallet", labels.bitcoin_wallet.as_str()),
+                ],
+                balance,
+            );
+        }
+        if let Some(net) = &bitcoin.network {
+            exp.metric(
+                "eigenix_bitcoin_peers",
+                "gauge",
+                "Peers connected to bitcoind",
+                &network,
+                net.peers as f64,
+            )
+            .metric(
+                "eigenix_bitcoin_inbound_peers",
+                "gauge",
+                "Inbound peers connected to bitcoind",
+                &network,
+                net.inbound_peers as f64,
+            )
+            .metric(
+                "eigenix_bitcoin_banned_peers",
+                "gauge",
+                "Entries in bitcoind's ban list",
+                &network,
+                net.banned_peers as f64,
+            )
+            .metric(
+                "eigenix_bitcoin_sent_bytes_total",
+                "counter",
+                "Bytes sent by bitcoind since it started",
+                &network,
+                net.bytes_sent as f64,
+            )
+            .metric(
+                "eigenix_bitcoin_received_bytes_total",
+                "counter",
+                "Bytes received by bitcoind since it started",
+                &network,
+                net.bytes_received as f64,
+            )
+            .metric(
+                "eigenix_bitcoin_min_relay_fee_sat_per_vbyte",
+                "gauge",
+                "Lowest feerate bitcoind relays",
+                &network,
+                net.min_relay_fee,
+            );
+        }
+        sampled_at.push(("bitcoin", bitcoin.timestamp));
+    }
+
+    if let Some(monero) = &summary.monero {
+        let network = [("network", labels.monero_network.as_str())];
+        exp.metric(
+            "eigenix_monero_height",
+            "gauge",
+            "Current monerod chain height",
+            &network,
+            monero.height as f64,
+        )
+        .metric(
+            "eigenix_monero_target_height",
+            "gauge",
+            "Chain height monerod is syncing towards",
+            &network,
+            monero.target_height as f64,
+        )
+        .metric(
+            "eigenix_monero_difficulty",
+            "gauge",
+            "Current network difficulty",
+            &network,
+            monero.difficulty as f64,
+        )
+        .metric(
+            "eigenix_monero_transactions_total",
+            "counter",
+            "Transactions in the Monero chain",
+            &network,
+            monero.tx_count as f64,
+        );
+        if let Some(balance) = monero.wallet_balance {
+            exp.metric(
+                "eigenix_monero_wallet_balance_xmr",
+                "gauge",
+                "Monero wallet balance in XMR",
+                &[
+                    ("network", labels.monero_network.as_str()),
+                    ("wallet", labels.monero_wallet.as_str()),
+                ],
+                balance,
+            );
+        }
+        if let Some(net) = &monero.network {
+            exp.metric(
+                "eigenix_monero_incoming_connections",
+                "gauge",
+                "Incoming connections to monerod",
+                &network,
+                net.incoming_connections as f64,
+            )
+            .metric(
+                "eigenix_monero_outgoing_connections",
+                "gauge",
+                "Outgoing connections from monerod",
+                &network,
+                net.outgoing_connections as f64,
+            )
+            .metric(
+                "eigenix_monero_database_size_bytes",
+                "gauge",
+                "Size of monerod's blockchain database",
+                &network,
+                net.database_size as f64,
+            )
+            .metric(
+                "eigenix_monero_synchronized",
+                "gauge",
+                "1 when monerod reports itself synchronized",
+                &network,
+                bool_value(net.synchronized),
+            )
+            .metric(
+                "eigenix_monero_offline",
+                "gauge",
+                "1 when monerod has no network",
+                &network,
+                bool_value(net.offline),
+            );
+            if let Some(fee) = net.fee_per_byte {
+                exp.metric(
+                    "eigenix_monero_fee_per_byte_atomic",
+                    "gauge",
+                    "Estimated fee per byte in atomic units",
+                    &network,
+                    fee as f64,
+                );
+            }
+        }
+        sampled_at.push(("monero", monero.timestamp));
+    }
+
+    if let Some(asb) = &summary.asb {
+        exp.metric(
+            "eigenix_asb_up",
+            "gauge",
+            "Whether the ASB answered its last probe",
+            &[],
+            bool_value(asb.up),
+        )
+        .metric(
+            "eigenix_asb_balance_btc",
+            "gauge",
+            "ASB wallet balance in BTC",
+            &[],
+            asb.balance_btc,
+        )
+        .metric(
+            "eigenix_asb_swaps_pending",
+            "gauge",
+            "Swaps the ASB has in progress",
+            &[],
+            asb.pending_swaps as f64,
+        )
+        .metric(
+            "eigenix_asb_swaps_completed_total",
+            "counter",
+            "Swaps the ASB has completed",
+            &[],
+            asb.completed_swaps as f64,
+        )
+        .metric(
+            "eigenix_asb_swaps_failed_total",
+            "counter",
+            "Swaps the ASB has failed",
+            &[],
+            asb.failed_swaps as f64,
+        );
+        sampled_at.push(("asb", asb.timestamp));
+    }
+
+    if let Some(electrs) = &summary.electrs {
+        exp.metric(
+            "eigenix_electrs_up",
+            "gauge",
+            "Whether Electrs answered its last probe",
+            &[],
+            bool_value(electrs.up),
+        )
+        .metric(
+            "eigenix_electrs_indexed_blocks",
+            "gauge",
+            "Blocks indexed by Electrs",
+            &[],
+            electrs.indexed_blocks as f64,
+        );
+        sampled_at.push(("electrs", electrs.timestamp));
+    }
+
+    if !summary.containers.is_empty() {
+        type Value = fn(&StoredContainerMetrics) -> Option<f64>;
+        let families: [(&str, &str, &str, Value); 8] = [
+            (
+                "eigenix_container_up",
+                "gauge",
+                "Whether the container is running",
+                |c| Some(bool_value(c.up)),
+            ),
+            (
+                "eigenix_container_restarts_total",
+                "counter",
+                "Container restarts",
+                |c| Some(c.restarts as f64),
+            ),
+            (
+                "eigenix_container_uptime_seconds",
+                "gauge",
+                "Seconds since the container started",
+                |c| Some(c.uptime_seconds as f64),
+            ),
+            (
+                "eigenix_container_cpu_percent",
+                "gauge",
+                "CPU usage of the container, 100 per fully used core",
+                |c| c.stats.as_ref()?.cpu_percent,
+            ),
+            (
+                "eigenix_container_memory_bytes",
+                "gauge",
+                "Memory used by the container",
+                |c| Some(c.stats.as_ref()?.memory_bytes? as f64),
+            ),
+            (
+                "eigenix_container_memory_limit_bytes",
+                "gauge",
+                "Memory the container may use",
+                |c| Some(c.stats.as_ref()?.memory_limit_bytes? as f64),
+            ),
+            (
+                "eigenix_container_network_receive_bytes_total",
+                "counter",
+                "Bytes the container has received",
+                |c| Some(c.stats.as_ref()?.net_rx_bytes? as f64),
+            ),
+            (
+                "eigenix_container_network_transmit_bytes_total",
+                "counter",
+                "Bytes the container has sent",
+                |c| Some(c.stats.as_ref()?.net_tx_bytes? as f64),
+            ),
+        ];
+        for (name, kind, help, value) in families {
+            // Usage is only known for running containers, so a family may be empty
+            let samples: Vec<_> = summary
+                .containers
+                .iter()
+                .filter_map(|c| Some((c.name.as_str(), value(c)?)))
+                .collect();
+            if samples.is_empty() {
+                continue;
+            }
+            exp.family(name, kind, help);
+            for (container, value) in samples {
+                exp.sample(name, &[("name", container)], value);
+            }
+        }
+        if let Some(latest) = summary.containers.iter().map(|c| c.timestamp).max() {
+            sampled_at.push(("containers", latest));
+        }
+    }
+
+    if !summary.disk.is_empty() {
+        type Value = fn(&StoredDiskMetrics) -> f64;
+        let families: [(&str, &str, Value); 4] = [
+            (
+                "eigenix_disk_total_bytes",
+                "Size of the filesystem holding the path",
+                |d| d.total_bytes as f64,
+            ),
+            (
+                "eigenix_disk_used_bytes",
+                "Space used on the filesystem holding the path",
+                |d| d.used_bytes as f64,
+            ),
+            (
+                "eigenix_disk_available_bytes",
+                "Space left to unprivileged processes on the filesystem",
+                |d| d.available_bytes as f64,
+            ),
+            (
+                "eigenix_disk_free_percent",
+                "Share of the usable space still free",
+                |d| d.free_percent,
+            ),
+        ];
+        for (name, help, value) in families {
+            exp.family(name, "gauge", help);
+            for disk in &summary.disk {
+                let labels = [("name", disk.name.as_str()), ("path", disk.path.as_str())];
+                exp.sample(name, &labels, value(disk));
+            }
+        }
+        if let Some(latest) = summary.disk.iter().map(|d| d.timestamp).max() {
+            sampled_at.push(("disk", latest));
+        }
+    }
+
+    if let Some(system) = &summary.system {
+        if let Some(cpu) = system.cpu_percent {
+            exp.metric(
+                "eigenix_system_cpu_percent",
+                "gauge",
+                "Average use of all cores of the host since the previous sample",
+                &[],
+                cpu,
+            );
+        }
+        exp.metric(
+            "eigenix_system_cpus",
+            "gauge",
+            "Logical CPUs of the host",
+            &[],
+            system.cpu_count as f64,
+        );
+        exp.family("eigenix_system_load", "gauge", "Load average of the host");
+        for (window, load) in [
+            ("1m", system.load_1),
+            ("5m", system.load_5),
+            ("15m", system.load_15),
+        ] {
+            exp.sample("eigenix_system_load", &[("window", window)], load);
+        }
+        exp.metric(
+            "eigenix_system_memory_total_bytes",
+            "gauge",
+            "Memory of the host",
+            &[],
+            system.memory_total_bytes as f64,
+        )
+        .metric(
+            "eigenix_system_memory_used_bytes",
+            "gauge",
+            "Memory in use on the host",
+            &[],
+            system.memory_used_bytes as f64,
+        )
+        .metric(
+            "eigenix_system_memory_available_bytes",
+            "gauge",
+            "Memory the host can hand out without swapping",
+            &[],
+            system.memory_available_bytes as f64,
+        )
+        .metric(
+            "eigenix_system_swap_total_bytes",
+            "gauge",
+            "Swap space of the host",
+            &[],
+            system.swap_total_bytes as f64,
+        )
+        .metric(
+            "eigenix_system_swap_used_bytes",
+            "gauge",
+            "Swap space in use on the host",
+            &[],
+            system.swap_used_bytes as f64,
+        )
+        .metric(
+            "eigenix_system_network_receive_bytes_total",
+            "counter",
+            "Bytes received by the host over every interface but loopback",
+            &[],
+            system.net_rx_bytes as f64,
+        )
+        .metric(
+            "eigenix_system_network_transmit_bytes_total",
+            "counter",
+            "Bytes sent by the host over every interface but loopback",
+            &[],
+            system.net_tx_bytes as f64,
+        );
+        sampled_at.push(("system", system.timestamp));
+    }
+
+    if !sampled_at.is_empty() {
+        let name = "eigenix_metrics_last_sample_timestamp_seconds";
+        exp.family(
+            name,
+            "gauge",
+            "Unix time of the latest stored sample per source",
+        );
+        for (source, timestamp) in sampled_at {
+            exp.sample(name, &[("source", source)], unix_secs(timestamp));
+        }
+    }
+
+    exp.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{StoredAsbMetrics, StoredBitcoinMetrics};
+    use crate::metrics::{BitcoinNetworkMetrics, ContainerStats};
+
+    fn labels() -> ExporterLabels {
+        ExporterLabels {
+            bitcoin_network: "mainnet".to_string(),
+            monero_network: "mainnet".to_string(),
+            bitcoin_wallet: "eigenix".to_string(),
+            monero_wallet: "eigenix".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let timestamp = DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let summary = MetricsSummary {
+            bitcoin: Some(StoredBitcoinMetrics {
+                timestamp,
+                blocks: 850_000,
+                headers: 850_001,
+                verification_progress: 0.99,
+                size_on_disk: 600_000_000_000,
+                wallet_balance: Some(0.5),
+                network: Some(BitcoinNetworkMetrics {
+                    peers: 10,
+                    inbound_peers: 2,
+                    banned_peers: 0,
+                    bytes_sent: 1_000,
+                    bytes_received: 5_000,
+                    min_relay_fee: 1.0,
+                }),
+            }),
+            monero: None,
+            asb: Some(StoredAsbMetrics {
+                timestamp,
+                balance_btc: 0.25,
+                pending_swaps: 1,
+                completed_swaps: 42,
+                failed_swaps: 3,
+                up: true,
+            }),
+            electrs: None,
+            containers: vec![
+                StoredContainerMetrics {
+                    timestamp,
+                    name: "bitcoind".to_string(),
+                    up: true,
+                    restarts: 0,
+                    uptime_seconds: 3600,
+                    stats: Some(ContainerStats {
+                        cpu_percent: Some(1.5),
+                        memory_bytes: Some(1_048_576),
+                        ..Default::default()
+                    }),
+                },
+                StoredContainerMetrics {
+                    timestamp,
+                    name: "monerod".to_string(),
+                    up: false,
+                    restarts: 2,
+                    uptime_seconds: 0,
+                    stats: None,
+                },
+            ],
+            disk: vec![StoredDiskMetrics {
+                timestamp,
+                name: "bitcoind".to_string(),
+                path: "/mnt/vault/bitcoind-data".to_string(),
+                mount_point: "/mnt/vault".to_string(),
+                total_bytes: 2_000,
+                used_bytes: 1_500,
+                available_bytes: 400,
+                free_percent: 21.05,
+            }],
+            system: None,
+        };
+
+        let text = render_summary(&summary, &labels());
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines.contains(&"# TYPE eigenix_bitcoin_blocks gauge"));
+        assert!(lines.contains(&"eigenix_bitcoin_blocks{network=\"mainnet\"} 850000"));
+        assert!(lines.contains(
+            &"eigenix_bitcoin_wallet_balance_btc{network=\"mainnet\",wallet=\"eigenix\"} 0.5"
+        ));
+        assert!(lines.contains(&"eigenix_bitcoin_peers{network=\"mainnet\"} 10"));
+        assert!(lines.contains(&"# TYPE eigenix_bitcoin_received_bytes_total counter"));
+        assert!(lines.contains(&"# TYPE eigenix_asb_swaps_failed_total counter"));
+        assert!(lines.contains(&"eigenix_asb_swaps_failed_total{} 3"));
+        assert!(lines.contains(&"eigenix_container_up{name=\"monerod\"} 0"));
+        assert!(lines.contains(&"eigenix_container_restarts_total{name=\"monerod\"} 2"));
+        assert!(lines.contains(&"eigenix_container_cpu_percent{name=\"bitcoind\"} 1.5"));
+        assert!(lines.contains(&"eigenix_container_memory_bytes{name=\"bitcoind\"} 1048576"));
+        assert!(!text.contains("eigenix_container_cpu_percent{name=\"monerod\"}"));
+        // Families without a single sample are left out
+        assert!(!text.contains("eigenix_container_network_receive_bytes_total"));
+        assert!(lines.contains(
+            &"eigenix_disk_available_bytes{name=\"bitcoind\",path=\"/mnt/vault/bitcoind-data\"} 400"
+        ));
+        assert!(lines.contains(
+            &"eigenix_metrics_last_sample_timestamp_seconds{source=\"bitcoin\"} 1717243200"
+        ));
+        assert!(lines.contains(
+            &"eigenix_metrics_last_sample_timestamp_seconds{source=\"disk\"} 1717243200"
+        ));
+
+        // Sources without a sample are omitted, not reported as zero
+        assert!(!text.contains("eigenix_monero_"));
+        assert!(!text.contains("eigenix_system_"));
+        assert!(!text.contains("eigenix_electrs_"));
+
+        // Every family is declared once, before its samples
+        let types = lines.iter().filter(|l| l.starts_with("# TYPE ")).count();
+        let helps = lines.iter().filter(|l| l.starts_with("# HELP ")).count();
+        assert_eq!(types, helps);
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|l| **l == "# TYPE eigenix_container_up gauge")
+                .count(),
+            1
+        );
+    }
+}
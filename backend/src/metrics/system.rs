@@ -0,0 +1,210 @@
+//! Resource usage of the host running the backend
+//!
+//! CPU usage and network throughput are rates, so [`SystemProbe`] keeps the
+//! previous reading between samples and reports the change since then. The
+//! first sample after startup has no rates.
+
+use std::time::Instant;
+use sysinfo::{Networks, System};
+
+use super::types::SystemMetrics;
+
+/// Prefixes of virtual interfaces: container veths and bridges, and
+/// libvirt's bridge
+const VIRTUAL_PREFIXES: [&str; 6] = ["veth", "podman", "docker", "br-", "cni", "virbr"];
+
+/// Whether an interface counts towards the network totals
+///
+/// Loopback traffic never leaves the host, and virtual interfaces carry
+/// container traffic the physical ones count already. They also come and go
+/// with containers, which would make the totals drop.
+fn is_counted(interface: &str) -> bool {
+    interface != "lo"
+        && interface != "lo0"
+        && !VIRTUAL_PREFIXES
+            .iter()
+            .any(|prefix| interface.starts_with(prefix))
+}
+
+/// Bytes received and sent over the counted interfaces, from
+/// `(interface, received, sent)` totals
+fn network_totals<'a>(interfaces: impl IntoIterator<Item = (&'a str, u64, u64)>) -> (u64, u64) {
+    interfaces
+        .into_iter()
+        .filter(|(interface, _, _)| is_counted(interface))
+        .fold((0, 0), |(rx, tx), (_, received, sent)| {
+            (rx + received, tx + sent)
+        })
+}
+
+/// Bytes per second between two readings of a counter
+///
+/// `None` when the counter went backwards, e.g. an interface was removed,
+/// or no time has passed.
+fn per_second(previous: u64, current: u64, elapsed_secs: f64) -> Option<f64> {
+    if current < previous || elapsed_secs <= 0.0 {
+        return None;
+    }
+    Some((current - previous) as f64 / elapsed_secs)
+}
+
+/// Network totals at one reading
+struct NetworkReading {
+    at: Instant,
+    rx_bytes: u64,
+    tx_bytes: u64,
+}
+
+/// Samples host CPU, memory, swap, load and network usage
+pub struct SystemProbe {
+    system: System,
+    networks: Networks,
+    /// Whether the CPU counters have a previous refresh to compare against
+    cpu_primed: bool,
+    previous: Option<NetworkReading>,
+}
+
+impl SystemProbe {
+    pub fn new() -> Self {
+        let mut system = System::new();
+        // CPU usage is measured between two refreshes; this is the first
+        system.refresh_cpu_usage();
+        Self {
+            system,
+            networks: Networks::new_with_refreshed_list(),
+            cpu_primed: false,
+            previous: None,
+        }
+    }
+
+    /// Read the host's current usage
+    pub fn sample(&mut self) -> SystemMetrics {
+        self.system.refresh_cpu_usage();
+        self.system.refresh_memory();
+        // Interfaces come and go with containers
+        self.networks.refresh(true);
+
+        let cpu_percent = self
+            .cpu_primed
+            .then(|| self.system.global_cpu_usage() as f64);
+        self.cpu_primed = true;
+
+        let (rx_bytes, tx_bytes) = network_totals(self.networks.iter().map(|(interface, data)| {
+            (
+                interface.as_str(),
+                data.total_received(),
+                data.total_transmitted(),
+            )
+        }));
+        let reading = NetworkReading {
+            at: Instant::now(),
+            rx_bytes,
+            tx_bytes,
+        };
+        let rates = self.previous.as_ref().map(|previous| {
+            let elapsed = reading.at.duration_since(previous.at).as_secs_f64();
+            (
+                per_second(previous.rx_bytes, reading.rx_bytes, elapsed),
+                per_second(previous.tx_bytes, reading.tx_bytes, elapsed),
+            )
+        });
+        self.previous = Some(reading);
+
+        let load = System::load_average();
+        SystemMetrics {
+            cpu_count: self.system.cpus().len() as u64,
+            cpu_percent,
+            load_1: load.one,
+            load_5: load.five,
+            load_15: load.fifteen,
+            memory_total_bytes: self.system.total_memory(),
+            memory_used_bytes: self.system.used_memory(),
+            memory_available_bytes: self.system.available_memory(),
+            swap_total_bytes: self.system.total_swap(),
+            swap_used_bytes: self.system.used_swap(),
+            net_rx_bytes: rx_bytes,
+            net_tx_bytes: tx_bytes,
+            net_rx_bytes_per_sec: rates.and_then(|(rx, _)| rx),
+            net_tx_bytes_per_sec: rates.and_then(|(_, tx)| tx),
+            uptime_secs: System::uptime(),
+        }
+    }
+}
+
+impl Default for SystemProbe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_per_second() {
+        assert_eq!(per_second(1_000, 3_000, 2.0), Some(1_000.0));
+        assert_eq!(per_second(1_000, 1_000, 2.0), Some(0.0));
+        // Counter reset
+        assert_eq!(per_second(3_000, 1_000, 2.0), None);
+        assert_eq!(per_second(1_000, 3_000, 0.0), None);
+    }
+
+    /// `(interface, received, sent)` from `/proc/net/dev` contents
+    fn parse_net_dev(contents: &str) -> Vec<(&str, u64, u64)> {
+        contents
+            .lines()
+            .filter_map(|line| {
+                let (interface, counters) = line.split_once(':')?;
+                let counters: Vec<u64> = counters
+                    .split_whitespace()
+                    .map(|n| n.parse().ok())
+                    .collect::<Option<_>>()?;
+                Some((interface.trim(), *counters.first()?, *counters.get(8)?))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_network_totals_leave_out_virtual_interfaces() {
+        let net_dev = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo: 5000000     100    0    0    0     0          0         0  5000000     100    0    0    0     0       0          0
+  eth0: 9000000    7000    0    0    0     0          0        10  3000000    4000    0    0    0     0       0          0
+podman0:  800000     600    0    0    0     0          0         0  2500000     900    0    0    0     0       0          0
+veth1a2b3c:  800000     600    0    0    0     0          0         0  2500000     900    0    0    0     0       0          0
+docker0:     100       1    0    0    0     0          0         0      100       1    0    0    0     0       0          0
+br-4f2a9c:     100       1    0    0    0     0          0         0      100       1    0    0    0     0       0          0
+cni-podman1:     100       1    0    0    0     0          0         0      100       1    0    0    0     0       0          0
+virbr0:     100       1    0    0    0     0          0         0      100       1    0    0    0     0       0          0
+   wg0:  200000     300    0    0    0     0          0         0   100000     200    0    0    0     0       0          0
+";
+        let interfaces = parse_net_dev(net_dev);
+        assert_eq!(interfaces.len(), 9);
+        assert_eq!(network_totals(interfaces.clone()), (9_200_000, 3_100_000));
+
+        // A container stopping takes its veth with it without moving the totals
+        let without_veth = interfaces
+            .into_iter()
+            .filter(|(interface, _, _)| !interface.starts_with("veth"));
+        assert_eq!(network_totals(without_veth), (9_200_000, 3_100_000));
+    }
+
+    #[test]
+    fn test_sample_has_rates_from_the_second_reading() {
+        let mut probe = SystemProbe::new();
+        let first = probe.sample();
+        assert!(first.cpu_count > 0);
+        assert!(first.memory_total_bytes > 0);
+        assert!(first.memory_used_bytes <= first.memory_total_bytes);
+        assert_eq!(first.cpu_percent, None);
+        assert_eq!(first.net_rx_bytes_per_sec, None);
+
+        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        let second = probe.sample();
+        assert!(second.cpu_percent.is_some());
+        assert!(second.net_rx_bytes_per_sec.is_some());
+        assert!(second.memory_used_percent() <= 100.0);
+    }
+}
@@ -3,6 +3,9 @@ use serde::{Deserialize, Serialize};
 
 // Re-export RPC clients from services
 pub use crate::services::{BitcoinRpcClient, MoneroRpcClient};
+pub use eigenix_common::metrics::{BitcoinNetworkMetrics, ContainerStats, MoneroNetworkMetrics};
+
+use crate::services::AsbQuote;
 
 /// Bitcoin blockchain information from getblockchaininfo RPC
 #[derive(Debug, Serialize, Deserialize)]
@@ -12,6 +15,9 @@ pub struct BitcoinMetrics {
     pub verification_progress: f64,
     pub size_on_disk: u64,
     pub wallet_balance: Option<f64>, // in BTC
+    /// Peer and traffic counters, when the node answered those calls
+    #[serde(default)]
+    pub network: Option<BitcoinNetworkMetrics>,
 }
 
 /// Monero blockchain information
@@ -22,6 +28,9 @@ pub struct MoneroMetrics {
     pub difficulty: u64,
     pub tx_count: u64,
     pub wallet_balance: Option<f64>, // in XMR
+    /// Connection and sync state, when monerod reported it
+    #[serde(default)]
+    pub network: Option<MoneroNetworkMetrics>,
 }
 
 /// ASB (Automated Swap Backend) metrics
@@ -34,6 +43,56 @@ pub struct AsbMetrics {
     pub up: bool,
 }
 
+/// The ASB's quote at collection time, next to the exchange price
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AsbQuoteMetrics {
+    /// Whether the ASB offered anything: false when it gave no quote or a
+    /// zero maximum, as it does once it has no XMR left to sell
+    pub quoting: bool,
+    /// BTC per XMR
+    pub price: Option<f64>,
+    /// Smallest and largest swap the ASB accepts, in BTC
+    pub min_quantity: Option<f64>,
+    pub max_quantity: Option<f64>,
+    /// Last BTC/XMR trade on the configured exchange, in BTC per XMR
+    pub market_price: Option<f64>,
+    /// How far `price` is above `market_price`, in percent
+    pub spread_percent: Option<f64>,
+}
+
+impl AsbQuoteMetrics {
+    pub fn new(quote: Option<&AsbQuote>, market_price: Option<f64>) -> Self {
+        let spread_percent = match (quote, market_price) {
+            (Some(quote), Some(market)) if market > 0.0 => {
+                Some((quote.price / market - 1.0) * 100.0)
+            }
+            _ => None,
+        };
+
+        Self {
+            quoting: quote.is_some_and(|quote| quote.max_quantity > 0.0),
+            price: quote.map(|quote| quote.price),
+            min_quantity: quote.map(|quote| quote.min_quantity),
+            max_quantity: quote.map(|quote| quote.max_quantity),
+            market_price,
+            spread_percent,
+        }
+    }
+}
+
+/// Exchange rates from Kraken's public ticker
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PriceMetrics {
+    pub btc_usd: f64,
+    /// Percentage change since Kraken's daily open
+    pub btc_usd_change_24h: f64,
+    pub xmr_usd: f64,
+    pub xmr_usd_change_24h: f64,
+    /// BTC per XMR
+    pub xmr_btc: f64,
+    pub xmr_btc_change_24h: f64,
+}
+
 /// Electrs metrics
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ElectrsMetrics {
@@ -41,6 +100,92 @@ pub struct ElectrsMetrics {
     pub indexed_blocks: u64,
 }
 
+/// Mempool state from an Esplora-style HTTP API
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MempoolMetrics {
+    pub tx_count: u64,
+    /// Total virtual size of the mempool in vbytes
+    pub vsize: u64,
+    pub total_fee_sat: u64,
+    /// `(feerate in sat/vB, vsize)` pairs, highest feerate first
+    pub fee_histogram: Vec<(f64, u64)>,
+}
+
+/// Balance and confirmation status of a watched address
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddressMetrics {
+    pub address: String,
+    pub confirmed_balance: f64, // in BTC
+    /// Net effect of unconfirmed transactions, negative while spending
+    pub unconfirmed_balance: f64, // in BTC
+    /// Confirmed transactions involving the address
+    pub tx_count: u64,
+    /// Transactions involving the address still in the mempool
+    pub mempool_tx_count: u64,
+}
+
+/// Space on the filesystem holding a monitored data directory
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DiskMetrics {
+    /// Name the path is configured under in `[disk] paths`
+    pub name: String,
+    pub path: String,
+    /// Where the filesystem holding `path` is mounted
+    pub mount_point: String,
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    /// Space left to unprivileged processes, so root's reserve isn't counted
+    pub available_bytes: u64,
+}
+
+impl DiskMetrics {
+    /// Share of the usable space still free, as `df` computes its capacity
+    pub fn free_percent(&self) -> f64 {
+        let usable = self.used_bytes + self.available_bytes;
+        if usable == 0 {
+            return 0.0;
+        }
+        self.available_bytes as f64 / usable as f64 * 100.0
+    }
+}
+
+/// Resource usage of the machine the backend runs on
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SystemMetrics {
+    pub cpu_count: u64,
+    /// Average use of all cores since the previous sample, in percent;
+    /// `None` on the first sample after startup
+    pub cpu_percent: Option<f64>,
+    /// Run queue averaged over 1, 5 and 15 minutes
+    pub load_1: f64,
+    pub load_5: f64,
+    pub load_15: f64,
+    pub memory_total_bytes: u64,
+    pub memory_used_bytes: u64,
+    /// Memory the kernel can hand out without swapping, caches included
+    pub memory_available_bytes: u64,
+    pub swap_total_bytes: u64,
+    pub swap_used_bytes: u64,
+    /// Received and sent over every interface but loopback since boot
+    pub net_rx_bytes: u64,
+    pub net_tx_bytes: u64,
+    /// Throughput since the previous sample; `None` on the first sample
+    /// after startup
+    pub net_rx_bytes_per_sec: Option<f64>,
+    pub net_tx_bytes_per_sec: Option<f64>,
+    pub uptime_secs: u64,
+}
+
+impl SystemMetrics {
+    /// Share of memory in use, in percent
+    pub fn memory_used_percent(&self) -> f64 {
+        if self.memory_total_bytes == 0 {
+            return 0.0;
+        }
+        self.memory_used_bytes as f64 / self.memory_total_bytes as f64 * 100.0
+    }
+}
+
 /// Container health metrics
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ContainerMetrics {
@@ -48,6 +193,9 @@ pub struct ContainerMetrics {
     pub up: bool,
     pub restarts: u64,
     pub uptime_seconds: u64,
+    /// Resource usage, when the container is up and the runtime reported it
+    #[serde(default)]
+    pub stats: Option<ContainerStats>,
 }
 
 /// ASB RPC client
@@ -193,83 +341,39 @@ impl ElectrsClient {
     }
 }
 
-/// Container health checker
-pub struct ContainerHealthClient;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-impl ContainerHealthClient {
-    pub fn new() -> Self {
-        Self
-    }
+    #[test]
+    fn test_asb_quote_spread() {
+        let quote = AsbQuote {
+            price: 0.00707,
+            min_quantity: 0.0005,
+            max_quantity: 0.25,
+        };
 
-    pub async fn get_metrics(&self, container_names: &[&str]) -> Result<Vec<ContainerMetrics>> {
-        let mut metrics = Vec::new();
+        let metrics = AsbQuoteMetrics::new(Some(&quote), Some(0.007));
+        assert!(metrics.quoting);
+        assert!((metrics.spread_percent.unwrap() - 1.0).abs() < 1e-9);
 
-        for name in container_names {
-            let output = std::process::Command::new("sudo")
-                .arg("podman")
-                .arg("ps")
-                .arg("-a")
-                .arg("--filter")
-                .arg(format!("name=^{}$", name))
-                .arg("--format")
-                .arg("{{.Status}}")
-                .output()
-                .context("Failed to check container status")?;
-
-            let status = String::from_utf8_lossy(&output.stdout);
-            let up = status.contains("Up");
-
-            let (restarts, uptime_seconds) = if up {
-                // Get restart count
-                let restart_output = std::process::Command::new("sudo")
-                    .arg("podman")
-                    .arg("inspect")
-                    .arg(name)
-                    .arg("--format")
-                    .arg("{{.RestartCount}}")
-                    .output()
-                    .ok()
-                    .and_then(|o| String::from_utf8(o.stdout).ok())
-                    .and_then(|s| s.trim().parse::<u64>().ok())
-                    .unwrap_or(0);
-
-                // Get uptime
-                let uptime_output = std::process::Command::new("sudo")
-                    .arg("podman")
-                    .arg("inspect")
-                    .arg(name)
-                    .arg("--format")
-                    .arg("{{.State.StartedAt}}")
-                    .output()
-                    .ok()
-                    .and_then(|o| String::from_utf8(o.stdout).ok())
-                    .and_then(|_started| {
-                        // Parse timestamp and calculate uptime
-                        // This is a simplified version
-                        Some(0) // TODO: Implement proper timestamp parsing
-                    })
-                    .unwrap_or(0);
-
-                (restart_output, uptime_output)
-            } else {
-                (0, 0)
-            };
-
-            metrics.push(ContainerMetrics {
-                name: name.to_string(),
-                up,
-                restarts,
-                uptime_seconds,
-            });
-        }
+        // Without a market price there is nothing to compare against
+        assert_eq!(
+            AsbQuoteMetrics::new(Some(&quote), None).spread_percent,
+            None
+        );
 
-        Ok(metrics)
-    }
-}
+        let empty = AsbQuote {
+            max_quantity: 0.0,
+            ..quote
+        };
+        assert!(!AsbQuoteMetrics::new(Some(&empty), Some(0.007)).quoting);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let missing = AsbQuoteMetrics::new(None, Some(0.007));
+        assert!(!missing.quoting);
+        assert_eq!(missing.price, None);
+        assert_eq!(missing.market_price, Some(0.007));
+    }
 
     #[tokio::test]
     #[ignore] // Only run with actual Bitcoin node
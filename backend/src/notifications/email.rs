@@ -0,0 +1,333 @@
+//! SMTP email channel
+//!
+//! Hands one plain text message per alert to a relay or a mail provider's
+//! submission port through `lettre`. Supports implicit TLS (port 465),
+//! STARTTLS (port 587) and plain connections for local relays, with optional
+//! authentication. The password is read from `EIGENIX_SMTP_PASSWORD`.
+
+use anyhow::{Context, Result};
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    Address, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::{Alert, AlertSeverity};
+
+/// How the SMTP connection is secured
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpTls {
+    /// TLS from the first byte, usually port 465
+    #[default]
+    Implicit,
+    /// Plain connection upgraded with STARTTLS, usually port 587
+    Starttls,
+    /// No encryption; only for relays on localhost or a private network
+    None,
+}
+
+/// `type = "email"` channel settings
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    #[serde(default)]
+    pub tls: SmtpTls,
+    /// SMTP login; no authentication when unset
+    #[serde(default)]
+    pub username: Option<String>,
+    /// SMTP password (loaded from environment variable EIGENIX_SMTP_PASSWORD)
+    #[serde(default = "smtp_password_from_env", skip_serializing)]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+impl std::fmt::Debug for EmailConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmailConfig")
+            .field("smtp_host", &self.smtp_host)
+            .field("smtp_port", &self.smtp_port)
+            .field("tls", &self.tls)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .finish()
+    }
+}
+
+fn default_smtp_port() -> u16 {
+    465
+}
+
+fn smtp_password_from_env() -> Option<String> {
+    std::env::var("EIGENIX_SMTP_PASSWORD")
+        .ok()
+        .filter(|password| !password.is_empty())
+}
+
+impl EmailConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.smtp_host.is_empty() {
+            anyhow::bail!("Email smtp_host must not be empty");
+        }
+        if self.to.is_empty() {
+            anyhow::bail!("Email channel needs at least one recipient in `to`");
+        }
+        for address in std::iter::once(&self.from).chain(&self.to) {
+            parse_address(address)?;
+        }
+        if self.username.is_some() && self.password.is_none() {
+            anyhow::bail!("Email channel has a username but EIGENIX_SMTP_PASSWORD is not set");
+        }
+        Ok(())
+    }
+}
+
+/// A bare `local@domain` address
+fn parse_address(address: &str) -> Result<Mailbox> {
+    let address: Address = address
+        .parse()
+        .with_context(|| format!("'{}' is not a valid email address", address))?;
+    Ok(Mailbox::new(None, address))
+}
+
+/// Emails alerts over SMTP
+#[derive(Debug, Clone)]
+pub struct EmailChannel {
+    config: EmailConfig,
+}
+
+impl EmailChannel {
+    pub fn new(config: EmailConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn label(&self) -> String {
+        format!("email {}", self.config.to.join(", "))
+    }
+
+    pub async fn send(&self, alert: &Alert) -> Result<()> {
+        let message = format_message(&self.config, alert)?;
+        self.transport()?.send(message).await.with_context(|| {
+            format!(
+                "Failed to send email through {}:{}",
+                self.config.smtp_host, self.config.smtp_port
+            )
+        })?;
+        Ok(())
+    }
+
+    /// SMTP transport for the configured server, TLS mode and login
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+        let host = self.config.smtp_host.as_str();
+        let builder = match self.config.tls {
+            SmtpTls::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+            SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+            SmtpTls::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                host,
+            )),
+        }
+        .with_context(|| format!("Failed to set up TLS for {}", host))?
+        .port(self.config.smtp_port);
+
+        let builder = match &self.config.username {
+            Some(username) => builder.credentials(Credentials::new(
+                username.clone(),
+                self.config.password.clone().unwrap_or_default(),
+            )),
+            None => builder,
+        };
+        Ok(builder.build())
+    }
+}
+
+/// The alert email: severity and kind in the subject, message and time in the body
+fn format_message(config: &EmailConfig, alert: &Alert) -> Result<Message> {
+    let marker = match alert.severity {
+        AlertSeverity::Warning => "WARNING",
+        AlertSeverity::Critical => "CRITICAL",
+    };
+
+    // Alert kinds come from config; keep them to one header line
+    let kind: String = alert.kind.chars().filter(|c| !c.is_control()).collect();
+
+    let mut builder = Message::builder()
+        .from(parse_address(&config.from)?)
+        .subject(format!("[eigenix] {} {}", marker, kind))
+        .header(ContentType::TEXT_PLAIN);
+    for recipient in &config.to {
+        builder = builder.to(parse_address(recipient)?);
+    }
+
+    builder
+        .body(format!(
+            "{}\n\nRaised at {}\n",
+            alert.message,
+            alert.timestamp.to_rfc3339()
+        ))
+        .context("Failed to build alert email")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// Scripted SMTP server; returns the commands and DATA it received
+    async fn smtp_server() -> (u16, tokio::task::JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut socket = BufReader::new(socket);
+            let mut commands = Vec::new();
+            let mut data = String::new();
+            socket.write_all(b"220 test ESMTP\r\n").await.unwrap();
+
+            loop {
+                let mut line = String::new();
+                if socket.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                let line = line.trim_end().to_string();
+                let reply: &[u8] = if line.starts_with("EHLO") {
+                    b"250-test\r\n250 AUTH PLAIN\r\n"
+                } else if line.starts_with("AUTH") {
+                    b"235 ok\r\n"
+                } else if line == "DATA" {
+                    commands.push(line);
+                    socket.write_all(b"354 go ahead\r\n").await.unwrap();
+                    loop {
+                        let mut line = String::new();
+                        socket.read_line(&mut line).await.unwrap();
+                        if line == ".\r\n" {
+                            break;
+                        }
+                        data.push_str(&line);
+                    }
+                    socket.write_all(b"250 queued\r\n").await.unwrap();
+                    continue;
+                } else if line == "QUIT" {
+                    commands.push(line);
+                    socket.write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    b"250 ok\r\n"
+                };
+                commands.push(line);
+                socket.write_all(reply).await.unwrap();
+            }
+
+            (commands, data)
+        });
+
+        (port, server)
+    }
+
+    fn config(port: u16) -> EmailConfig {
+        EmailConfig {
+            smtp_host: "127.0.0.1".to_string(),
+            smtp_port: port,
+            tls: SmtpTls::None,
+            username: Some("eigenix".to_string()),
+            password: Some("hunter2".to_string()),
+            from: "eigenix@example.com".to_string(),
+            to: vec![
+                "ops@example.com".to_string(),
+                "oncall@example.com".to_string(),
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_over_plain_smtp() {
+        let (port, server) = smtp_server().await;
+        let alert = Alert {
+            kind: "asb_down".to_string(),
+            severity: AlertSeverity::Critical,
+            message: "asb_up is 0 (below 1)\n.hidden line".to_string(),
+            timestamp: Utc::now(),
+        };
+
+        EmailChannel::new(config(port)).send(&alert).await.unwrap();
+        let (commands, data) = server.await.unwrap();
+
+        // The greeting name depends on the host
+        let commands: Vec<&str> = commands
+            .iter()
+            .map(String::as_str)
+            .filter(|command| !command.starts_with("EHLO"))
+            .collect();
+        assert_eq!(
+            commands,
+            vec![
+                "AUTH PLAIN AGVpZ2VuaXgAaHVudGVyMg==",
+                "MAIL FROM:<eigenix@example.com>",
+                "RCPT TO:<ops@example.com>",
+                "RCPT TO:<oncall@example.com>",
+                "DATA",
+                "QUIT",
+            ]
+        );
+        assert!(data.contains("Subject: [eigenix] CRITICAL asb_down\r\n"));
+        assert!(data.contains("To: ops@example.com, oncall@example.com\r\n"));
+        assert!(data.contains("\r\n\r\nasb_up is 0 (below 1)\r\n"));
+        // A leading dot in the body is escaped, not taken as end of data
+        assert!(data.contains("\r\n..hidden line\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_login_fails() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut socket = BufReader::new(socket);
+            socket.write_all(b"220 test\r\n").await.unwrap();
+            for reply in [
+                &b"250-hi\r\n250 AUTH PLAIN\r\n"[..],
+                b"535 bad credentials\r\n",
+            ] {
+                let mut line = String::new();
+                socket.read_line(&mut line).await.unwrap();
+                socket.write_all(reply).await.unwrap();
+            }
+        });
+
+        let err = EmailChannel::new(config(port))
+            .send(&Alert {
+                kind: "test".to_string(),
+                severity: AlertSeverity::Warning,
+                message: "test".to_string(),
+                timestamp: Utc::now(),
+            })
+            .await
+            .unwrap_err();
+        let err = format!("{:#}", err);
+        assert!(err.contains("535"), "{}", err);
+        assert!(!err.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_validate_addresses() {
+        let mut config = config(25);
+        assert!(config.validate().is_ok());
+
+        config
+            .to
+            .push("ops@example.com>\r\nRCPT TO:<x@evil.example".to_string());
+        assert!(config.validate().is_err());
+
+        let mut config = self::config(25);
+        config.password = None;
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,269 @@
+//! Alert delivery channels
+//!
+//! Alerts go out through every channel configured under
+//! `[[notifications.channels]]`: a generic JSON webhook, a Telegram bot or
+//! SMTP email. A failed delivery is retried with exponential backoff, and
+//! each channel is tried independently so one broken channel doesn't hold
+//! up the others. [`crate::alerts::AlertNotifier`] owns the channels and is
+//! what the monitors call.
+
+pub mod email;
+pub mod telegram;
+pub mod webhook;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::alerts::Alert;
+pub use crate::services::retry::RetryPolicy;
+
+pub use email::{EmailChannel, EmailConfig, SmtpTls};
+pub use telegram::{TelegramChannel, TelegramConfig};
+pub use webhook::{WebhookChannel, WebhookConfig};
+
+/// Timeout for a single delivery attempt
+pub const DELIVERY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A configured channel, tagged by `type`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelConfig {
+    Webhook(WebhookConfig),
+    Telegram(TelegramConfig),
+    Email(EmailConfig),
+}
+
+impl ChannelConfig {
+    /// Check the channel's settings, including secrets taken from the environment
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::Webhook(config) => config.validate(),
+            Self::Telegram(config) => config.validate(),
+            Self::Email(config) => config.validate(),
+        }
+    }
+}
+
+/// A channel ready to deliver alerts
+#[derive(Debug, Clone)]
+pub enum Channel {
+    Webhook(WebhookChannel),
+    Telegram(TelegramChannel),
+    Email(EmailChannel),
+}
+
+impl Channel {
+    pub fn from_config(config: &ChannelConfig, client: &reqwest::Client) -> Self {
+        match config {
+            ChannelConfig::Webhook(config) => {
+                Self::Webhook(WebhookChannel::new(config.url.clone(), client.clone()))
+            }
+            ChannelConfig::Telegram(config) => {
+                Self::Telegram(TelegramChannel::new(config.clone(), client.clone()))
+            }
+            ChannelConfig::Email(config) => Self::Email(EmailChannel::new(config.clone())),
+        }
+    }
+
+    /// Short description used in logs and delivery reports
+    pub fn label(&self) -> String {
+        match self {
+            Self::Webhook(channel) => channel.label(),
+            Self::Telegram(channel) => channel.label(),
+            Self::Email(channel) => channel.label(),
+        }
+    }
+
+    /// Make a single delivery attempt
+    pub async fn send(&self, alert: &Alert) -> Result<()> {
+        let send = async {
+            match self {
+                Self::Webhook(channel) => channel.send(alert).await,
+                Self::Telegram(channel) => channel.send(alert).await,
+                Self::Email(channel) => channel.send(alert).await,
+            }
+        };
+
+        tokio::time::timeout(DELIVERY_TIMEOUT, send)
+            .await
+            .map_err(|_| anyhow::anyhow!("Timed out after {}s", DELIVERY_TIMEOUT.as_secs()))?
+    }
+}
+
+/// How failed deliveries are retried unless `[notifications]` says otherwise
+pub const DEFAULT_RETRY: RetryPolicy = RetryPolicy {
+    max_attempts: 3,
+    initial_backoff_ms: 1_000,
+    max_backoff_ms: 60_000,
+    jitter: 0.0,
+};
+
+/// Outcome of delivering one alert through one channel
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeliveryReport {
+    pub channel: String,
+    pub delivered: bool,
+    pub attempts: u32,
+    /// Error from the last attempt, when delivery failed
+    pub error: Option<String>,
+}
+
+/// Deliver an alert through a channel, retrying with backoff
+pub async fn deliver_with_retry(
+    channel: &Channel,
+    alert: &Alert,
+    retry: RetryPolicy,
+) -> DeliveryReport {
+    let max_attempts = retry.max_attempts.max(1);
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+        let error = match channel.send(alert).await {
+            Ok(()) => None,
+            Err(e) => Some(format!("{:#}", e)),
+        };
+
+        if error.is_none() || attempts >= max_attempts {
+            return DeliveryReport {
+                channel: channel.label(),
+                delivered: error.is_none(),
+                attempts,
+                error,
+            };
+        }
+
+        let backoff = retry.backoff(attempts);
+        tracing::warn!(
+            "Alert delivery via {} failed (attempt {}/{}), retrying in {:?}: {}",
+            channel.label(),
+            attempts,
+            max_attempts,
+            backoff,
+            error.unwrap_or_default()
+        );
+        tokio::time::sleep(backoff).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::AlertSeverity;
+    use axum::{http::StatusCode, routing::post, Router};
+    use chrono::Utc;
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    /// Webhook receiver that fails the first `failures` requests
+    async fn flaky_receiver(failures: u32) -> (String, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(move || async move {
+                if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                } else {
+                    StatusCode::OK
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        (url, calls)
+    }
+
+    fn alert() -> Alert {
+        Alert {
+            kind: "asb_down".to_string(),
+            severity: AlertSeverity::Critical,
+            message: "asb_up is 0 (below 1)".to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn fast_retry() -> RetryPolicy {
+        RetryPolicy {
+            initial_backoff_ms: 10,
+            ..DEFAULT_RETRY
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let retry = DEFAULT_RETRY;
+        assert_eq!(retry.backoff(1), Duration::from_secs(1));
+        assert_eq!(retry.backoff(2), Duration::from_secs(2));
+        assert_eq!(retry.backoff(4), Duration::from_secs(8));
+        assert_eq!(retry.backoff(40), Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_retried() {
+        let (url, calls) = flaky_receiver(2).await;
+        let channel = Channel::Webhook(WebhookChannel::new(url, reqwest::Client::new()));
+
+        let report = deliver_with_retry(&channel, &alert(), fast_retry()).await;
+        assert!(report.delivered, "{:?}", report.error);
+        assert_eq!(report.attempts, 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_delivery_gives_up_after_max_attempts() {
+        let (url, calls) = flaky_receiver(u32::MAX).await;
+        let channel = Channel::Webhook(WebhookChannel::new(url, reqwest::Client::new()));
+
+        let report = deliver_with_retry(&channel, &alert(), fast_retry()).await;
+        assert!(!report.delivered);
+        assert_eq!(report.attempts, 3);
+        assert!(report.error.unwrap().contains("500"));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_channels_from_toml() {
+        #[derive(Deserialize)]
+        struct Channels {
+            channels: Vec<ChannelConfig>,
+        }
+
+        let parsed: Channels = toml::from_str(
+            r#"
+            [[channels]]
+            type = "webhook"
+            url = "https://hooks.example.com/eigenix"
+
+            [[channels]]
+            type = "telegram"
+            chat_id = "-100123"
+
+            [[channels]]
+            type = "email"
+            smtp_host = "smtp.example.com"
+            from = "eigenix@example.com"
+            to = ["ops@example.com"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(parsed.channels[0], ChannelConfig::Webhook(_)));
+        let ChannelConfig::Telegram(telegram) = &parsed.channels[1] else {
+            panic!("expected a Telegram channel");
+        };
+        assert_eq!(telegram.api_url, "https://api.telegram.org");
+        let ChannelConfig::Email(email) = &parsed.channels[2] else {
+            panic!("expected an email channel");
+        };
+        assert_eq!(email.smtp_port, 465);
+        assert_eq!(email.tls, SmtpTls::Implicit);
+    }
+}
@@ -0,0 +1,174 @@
+//! Telegram bot channel
+//!
+//! Sends each alert as a message to one chat through the Bot API's
+//! `sendMessage`. The bot token is read from `EIGENIX_TELEGRAM_BOT_TOKEN`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::{Alert, AlertSeverity};
+
+/// `type = "telegram"` channel settings
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TelegramConfig {
+    /// Chat, group or channel to post to (numeric id or `@channelname`)
+    pub chat_id: String,
+    /// Bot token (loaded from environment variable EIGENIX_TELEGRAM_BOT_TOKEN)
+    #[serde(default = "telegram_bot_token_from_env", skip_serializing)]
+    pub bot_token: Option<String>,
+    #[serde(default = "default_telegram_api_url")]
+    pub api_url: String,
+}
+
+impl std::fmt::Debug for TelegramConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TelegramConfig")
+            .field("chat_id", &self.chat_id)
+            .field("bot_token", &self.bot_token.as_ref().map(|_| "<redacted>"))
+            .field("api_url", &self.api_url)
+            .finish()
+    }
+}
+
+fn telegram_bot_token_from_env() -> Option<String> {
+    std::env::var("EIGENIX_TELEGRAM_BOT_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+}
+
+fn default_telegram_api_url() -> String {
+    "https://api.telegram.org".to_string()
+}
+
+impl TelegramConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.chat_id.is_empty() {
+            anyhow::bail!("Telegram chat_id must not be empty");
+        }
+        if self.bot_token.is_none() {
+            anyhow::bail!("Telegram channel needs EIGENIX_TELEGRAM_BOT_TOKEN to be set");
+        }
+        reqwest::Url::parse(&self.api_url)
+            .with_context(|| format!("Telegram api_url '{}' is not valid", self.api_url))?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct SendMessage<'a> {
+    chat_id: &'a str,
+    text: String,
+}
+
+/// Posts alerts to a Telegram chat
+#[derive(Debug, Clone)]
+pub struct TelegramChannel {
+    config: TelegramConfig,
+    client: reqwest::Client,
+}
+
+impl TelegramChannel {
+    pub fn new(config: TelegramConfig, client: reqwest::Client) -> Self {
+        Self { config, client }
+    }
+
+    pub fn label(&self) -> String {
+        format!("telegram {}", self.config.chat_id)
+    }
+
+    pub async fn send(&self, alert: &Alert) -> Result<()> {
+        let token = self
+            .config
+            .bot_token
+            .as_deref()
+            .context("EIGENIX_TELEGRAM_BOT_TOKEN is not set")?;
+        let url = format!(
+            "{}/bot{}/sendMessage",
+            self.config.api_url.trim_end_matches('/'),
+            token
+        );
+
+        let response = self
+            .client
+            .post(url)
+            .json(&SendMessage {
+                chat_id: &self.config.chat_id,
+                text: message_text(alert),
+            })
+            .send()
+            .await
+            // The request URL contains the token, keep it out of the error
+            .map_err(|e| anyhow::anyhow!("Telegram request failed: {}", e.without_url()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Telegram returned {}: {}", status, body);
+        }
+
+        Ok(())
+    }
+}
+
+/// Plain text message body for an alert
+fn message_text(alert: &Alert) -> String {
+    let marker = match alert.severity {
+        AlertSeverity::Warning => "WARNING",
+        AlertSeverity::Critical => "CRITICAL",
+    };
+    format!(
+        "[eigenix] {} {}\n{}\n{}",
+        marker,
+        alert.kind,
+        alert.message,
+        alert.timestamp.to_rfc3339()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Path, routing::post, Json, Router};
+    use chrono::Utc;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_send_message() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/{bot}/sendMessage",
+            post(
+                move |Path(bot): Path<String>, Json(body): Json<serde_json::Value>| async move {
+                    let _ = tx.send((bot, body));
+                    Json(serde_json::json!({ "ok": true }))
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let channel = TelegramChannel::new(
+            TelegramConfig {
+                chat_id: "-100123".to_string(),
+                bot_token: Some("123:abc".to_string()),
+                api_url,
+            },
+            reqwest::Client::new(),
+        );
+        let alert = Alert {
+            kind: "low_btc".to_string(),
+            severity: AlertSeverity::Warning,
+            message: "bitcoin_wallet_balance is 0.005 (below 0.01)".to_string(),
+            timestamp: Utc::now(),
+        };
+        channel.send(&alert).await.unwrap();
+
+        let (bot, body) = rx.recv().await.unwrap();
+        assert_eq!(bot, "bot123:abc");
+        assert_eq!(body["chat_id"], "-100123");
+        let text = body["text"].as_str().unwrap();
+        assert!(text.starts_with("[eigenix] WARNING low_btc\n"));
+        assert!(text.contains("below 0.01"));
+    }
+}
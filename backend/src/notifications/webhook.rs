@@ -0,0 +1,79 @@
+//! Generic JSON webhook channel
+//!
+//! POSTs the alert as JSON. With `EIGENIX_WEBHOOK_SIGNING_SECRET` set, the
+//! body is signed as described in [`crate::alerts`].
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::{sign_payload, Alert, SIGNATURE_HEADER};
+
+/// `type = "webhook"` channel settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+}
+
+impl WebhookConfig {
+    pub fn validate(&self) -> Result<()> {
+        reqwest::Url::parse(&self.url)
+            .with_context(|| format!("Webhook URL '{}' is not valid", self.url))?;
+        Ok(())
+    }
+}
+
+/// Posts alerts to a URL
+#[derive(Debug, Clone)]
+pub struct WebhookChannel {
+    url: String,
+    signing_secret: Option<String>,
+    client: reqwest::Client,
+}
+
+impl WebhookChannel {
+    pub fn new(url: String, client: reqwest::Client) -> Self {
+        Self {
+            url,
+            signing_secret: None,
+            client,
+        }
+    }
+
+    /// Sign request bodies with this secret
+    pub fn with_signing_secret(mut self, secret: Option<String>) -> Self {
+        self.signing_secret = secret;
+        self
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.signing_secret.is_some()
+    }
+
+    pub fn label(&self) -> String {
+        match reqwest::Url::parse(&self.url) {
+            Ok(url) => format!("webhook {}", url.host_str().unwrap_or_default()),
+            Err(_) => "webhook".to_string(),
+        }
+    }
+
+    pub async fn send(&self, alert: &Alert) -> Result<()> {
+        let body = serde_json::to_vec(alert).context("Failed to serialize alert")?;
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(secret) = &self.signing_secret {
+            request = request.header(SIGNATURE_HEADER, sign_payload(secret, &body));
+        }
+
+        request
+            .body(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("Webhook request failed")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+//! OpenAPI description of the HTTP API
+//!
+//! Generated from the `#[utoipa::path]` annotations on the route handlers and
+//! served at `/openapi.json`, with Swagger UI at `/docs`. Endpoints that need
+//! an operator or admin key list the `bearer` security scheme.
+
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::{
+    alerts::AlertState, approvals::ApprovalStatus, db::aggregate::Aggregate, export::ExportFormat,
+    health, routes, Btc, Xmr,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Eigenix API",
+        description = "Wallets, trading and metrics for an XMR/BTC atomic swap provider"
+    ),
+    paths(
+        health::health,
+        health::live,
+        routes::admin::run_query,
+        routes::alerts::list_alerts,
+        routes::alerts::list_rules,
+        routes::approvals::list_approvals,
+        routes::approvals::get_approval,
+        routes::approvals::approve,
+        routes::approvals::reject,
+        routes::asb::get_peers,
+        routes::asb::get_addresses,
+        routes::asb::get_swap_timeline,
+        routes::asb::list_events,
+        routes::bitcoin::get_balance,
+        routes::bitcoin::get_health,
+        routes::bitcoin::get_init_status,
+        routes::bitcoin::get_deposit_address,
+        routes::bitcoin::get_utxos,
+        routes::bitcoin::send_coin_control,
+        routes::bitcoin::create_psbt,
+        routes::bitcoin::get_psbt,
+        routes::bitcoin::submit_signed_psbt,
+        routes::bitcoin::get_descriptors,
+        routes::bitcoin::get_fees,
+        routes::config::get_display_config,
+        routes::kraken::get_tickers,
+        routes::limits::get_status,
+        routes::metrics::bitcoin_metrics,
+        routes::metrics::monero_metrics,
+        routes::metrics::asb_metrics,
+        routes::metrics::electrs_metrics,
+        routes::metrics::container_metrics,
+        routes::metrics::mempool_metrics,
+        routes::metrics::address_metrics,
+        routes::metrics::summary_metrics,
+        routes::metrics::stream_metrics,
+        routes::metrics::client_metrics,
+        routes::metrics::backend_metrics,
+        routes::metrics::backend_metrics_history,
+        routes::metrics::prometheus_metrics,
+        routes::metrics::bitcoin_history,
+        routes::metrics::monero_history,
+        routes::metrics::asb_history,
+        routes::metrics::electrs_history,
+        routes::metrics::container_history,
+        routes::metrics::mempool_history,
+        routes::metrics::address_history,
+        routes::metrics::export_metrics,
+        routes::metrics::export_source,
+        routes::metrics::bitcoin_interval,
+        routes::metrics::monero_interval,
+        routes::metrics::asb_interval,
+        routes::metrics::electrs_interval,
+        routes::metrics::container_interval,
+        routes::metrics::disk_metrics,
+        routes::metrics::disk_interval,
+        routes::metrics::system_metrics,
+        routes::metrics::system_interval,
+        routes::metrics::mempool_interval,
+        routes::metrics::price_metrics,
+        routes::metrics::price_interval,
+        routes::metrics::asb_quotes,
+        routes::metrics::aggregate_metrics,
+        routes::monero::get_balance,
+        routes::monero::get_health,
+        routes::monero::refresh_wallet,
+        routes::monero::get_deposit_address,
+        routes::monero::create_deposit_address,
+        routes::monero::sweep,
+        routes::monero::transfer_split,
+        routes::monero::get_keys,
+        routes::notifications::test_notifications,
+        routes::portfolio::get_portfolio,
+        routes::portfolio::portfolio_history,
+        routes::preferences::get_preferences,
+        routes::preferences::update_preferences,
+        routes::trading::get_status,
+        routes::trading::get_lock,
+        routes::trading::get_config,
+        routes::trading::update_config,
+        routes::trading::patch_config,
+        routes::trading::preview_config,
+        routes::trading::estimate_cost,
+        routes::trading::set_enabled,
+        routes::trading::emergency_convert,
+        routes::trading::cancel_all,
+        routes::trading::pause,
+        routes::trading::resume,
+        routes::trading::skip_step,
+        routes::trading::complete_step,
+        routes::trading::list_transactions,
+        routes::trading::export_transactions,
+        routes::trading::get_pnl,
+        routes::wallets::get_balances,
+        routes::wallets::get_wallet_health,
+        routes::wallets::transfer,
+        routes::wallets::backup_wallets,
+    ),
+    // Only referenced from query parameters, which don't collect their schemas
+    components(schemas(AlertState, ApprovalStatus, Aggregate, ExportFormat, Btc, Xmr)),
+    tags(
+        (name = "health", description = "Readiness and liveness probes"),
+        (name = "wallets", description = "Balances, transfers and spend limits across both wallets"),
+        (name = "bitcoin", description = "Bitcoin wallet and node"),
+        (name = "monero", description = "Monero wallet"),
+        (name = "kraken", description = "Exchange prices"),
+        (name = "trading", description = "Rebalancing engine"),
+        (name = "metrics", description = "Collected metrics, history and exports"),
+        (name = "portfolio", description = "Holdings valued in fiat"),
+        (name = "asb", description = "Atomic swap provider"),
+        (name = "alerts", description = "Alert rules and history"),
+        (name = "approvals", description = "Large withdrawals held for a second admin"),
+        (name = "notifications", description = "Alert delivery channels"),
+        (name = "preferences", description = "Per-key display preferences"),
+        (name = "config", description = "Display configuration"),
+        (name = "admin", description = "Database access for admins"),
+    ),
+    modifiers(&BearerAuth)
+)]
+pub struct ApiDoc;
+
+/// Registers the `bearer` scheme referenced by endpoints that need a key
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_covers_the_api() {
+        let spec = ApiDoc::openapi();
+
+        for path in [
+            "/health",
+            "/wallets/bitcoin/psbt/{id}",
+            "/wallets/monero/sweep",
+            "/metrics/bitcoin/interval",
+            "/metrics/stream",
+            "/trading/config",
+            "/preferences",
+        ] {
+            assert!(spec.paths.paths.contains_key(path), "{path} is missing");
+        }
+
+        let components = spec.components.expect("components");
+        assert!(components.security_schemes.contains_key("bearer"));
+        for schema in ["TradingConfig", "ErrorResponse", "Approval", "ExportFormat"] {
+            assert!(
+                components.schemas.contains_key(schema),
+                "{schema} is missing"
+            );
+        }
+    }
+}
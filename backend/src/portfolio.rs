@@ -0,0 +1,227 @@
+//! Portfolio valuation across our wallets, the exchange and the ASB
+//!
+//! Each location's BTC and XMR balances are valued in BTC and USD at the
+//! latest collected prices. A location that can't be read is listed in
+//! `unavailable` rather than failing the valuation, so while anything is
+//! listed the totals are a lower bound. Holdings are valued to the satoshi
+//! and summed exactly; only the USD values are floating point.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::{
+    db::prices::StoredPriceMetrics,
+    routes::kraken::current_prices,
+    services::{exchange::Asset, AsbClient, Exchange},
+    shutdown::Shutdown,
+    ApiResult, AppState, Btc, Xmr,
+};
+
+/// Where funds are held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Location {
+    /// Our own Bitcoin and Monero wallets
+    Wallet,
+    /// The configured exchange
+    Exchange,
+    /// The ASB's wallets
+    Asb,
+}
+
+/// One asset at one location
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Holding {
+    pub location: Location,
+    pub asset: Asset,
+    /// In units of `asset`
+    pub amount: f64,
+    pub value_btc: f64,
+    pub value_usd: f64,
+}
+
+/// Everything we hold, valued at one set of prices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Portfolio {
+    pub timestamp: DateTime<Utc>,
+    pub total_btc: f64,
+    pub total_usd: f64,
+    /// Prices the holdings were valued at
+    pub btc_usd: f64,
+    /// BTC per XMR
+    pub xmr_btc: f64,
+    pub holdings: Vec<Holding>,
+    /// Locations whose balances couldn't be read and are missing from the totals
+    pub unavailable: Vec<Location>,
+}
+
+impl Portfolio {
+    /// Value `balances`, given as `(location, asset, amount)`
+    pub fn value(
+        timestamp: DateTime<Utc>,
+        prices: &StoredPriceMetrics,
+        balances: Vec<(Location, Asset, f64)>,
+        unavailable: Vec<Location>,
+    ) -> Self {
+        let mut total_btc = Btc::ZERO;
+        let holdings: Vec<Holding> = balances
+            .into_iter()
+            .map(|(location, asset, amount)| {
+                let value_btc = match asset {
+                    Asset::Btc => Btc::from_f64(amount),
+                    Asset::Xmr => {
+                        Xmr::from_f64(amount).and_then(|xmr| xmr.to_btc_at(prices.xmr_btc))
+                    }
+                }
+                .unwrap_or_default();
+                total_btc = total_btc.saturating_add(value_btc);
+                Holding {
+                    location,
+                    asset,
+                    amount,
+                    value_btc: value_btc.to_f64(),
+                    value_usd: value_btc.to_f64() * prices.btc_usd,
+                }
+            })
+            .collect();
+
+        Self {
+            timestamp,
+            total_btc: total_btc.to_f64(),
+            total_usd: total_btc.to_f64() * prices.btc_usd,
+            btc_usd: prices.btc_usd,
+            xmr_btc: prices.xmr_btc,
+            holdings,
+            unavailable,
+        }
+    }
+}
+
+/// Balances held at one location, as `(asset, amount)`
+async fn location_balances(state: &AppState, location: Location) -> Result<Vec<(Asset, f64)>> {
+    match location {
+        Location::Wallet => {
+            // Pending funds are ours too; only spending waits on them
+            let bitcoin = state.wallets.bitcoin.get_balance().await?;
+            let monero = state.wallets.monero.get_balance().await?;
+            Ok(vec![
+                (Asset::Btc, bitcoin.balance + bitcoin.unconfirmed_balance),
+                (Asset::Xmr, monero.balance),
+            ])
+        }
+        Location::Exchange => {
+            let balances = state.config.get().exchange_client().balances().await?;
+            Ok(vec![
+                (Asset::Btc, balances.btc.unwrap_or(0.0)),
+                (Asset::Xmr, balances.xmr.unwrap_or(0.0)),
+            ])
+        }
+        Location::Asb => {
+            let client = AsbClient::new(state.config.get().asb.rpc_url.clone());
+            Ok(vec![
+                (Asset::Btc, client.get_bitcoin_balance().await?),
+                (Asset::Xmr, client.get_monero_balance().await?),
+            ])
+        }
+    }
+}
+
+/// Value everything we currently hold
+pub async fn current(state: &AppState) -> ApiResult<Portfolio> {
+    let prices = current_prices(state).await?;
+
+    let mut locations = vec![Location::Wallet, Location::Exchange];
+    if state.config.get().collector.asb {
+        locations.push(Location::Asb);
+    }
+
+    let mut balances = Vec::new();
+    let mut unavailable = Vec::new();
+    for location in locations {
+        match location_balances(state, location).await {
+            Ok(held) => balances.extend(
+                held.into_iter()
+                    .map(|(asset, amount)| (location, asset, amount)),
+            ),
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to read {:?} balances for the portfolio: {:#}",
+                    location,
+                    e
+                );
+                unavailable.push(location);
+            }
+        }
+    }
+
+    Ok(Portfolio::value(Utc::now(), &prices, balances, unavailable))
+}
+
+/// Store a portfolio snapshot every `interval` until `shutdown` is triggered
+pub async fn record_snapshots(state: AppState, interval: Duration, shutdown: Shutdown) {
+    loop {
+        match current(&state).await {
+            Ok(portfolio) => {
+                if let Err(e) = state.db.store_portfolio_snapshot(&portfolio).await {
+                    tracing::error!("Failed to store portfolio snapshot: {}", e);
+                }
+            }
+            Err(e) => tracing::error!("Failed to value portfolio: {}", e),
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = shutdown.triggered() => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_converts_to_btc_and_usd() {
+        let prices = StoredPriceMetrics {
+            timestamp: Utc::now(),
+            btc_usd: 60_000.0,
+            btc_usd_change_24h: 0.0,
+            xmr_usd: 150.0,
+            xmr_usd_change_24h: 0.0,
+            xmr_btc: 0.0025,
+            xmr_btc_change_24h: 0.0,
+        };
+
+        let portfolio = Portfolio::value(
+            Utc::now(),
+            &prices,
+            vec![
+                (Location::Wallet, Asset::Btc, 0.5),
+                (Location::Wallet, Asset::Xmr, 40.0),
+                (Location::Exchange, Asset::Btc, 0.1),
+            ],
+            vec![Location::Asb],
+        );
+
+        assert_eq!(portfolio.holdings[1].value_btc, 0.1);
+        assert!((portfolio.holdings[1].value_usd - 6_000.0).abs() < 1e-6);
+        assert_eq!(portfolio.total_btc, 0.7);
+        assert!((portfolio.total_usd - 42_000.0).abs() < 1e-6);
+        assert_eq!(portfolio.unavailable, vec![Location::Asb]);
+
+        // Summed exactly, where 0.1 + 0.2 in f64 is 0.30000000000000004
+        let portfolio = Portfolio::value(
+            Utc::now(),
+            &prices,
+            vec![
+                (Location::Wallet, Asset::Btc, 0.1),
+                (Location::Exchange, Asset::Btc, 0.2),
+            ],
+            Vec::new(),
+        );
+        assert_eq!(portfolio.total_btc, 0.3);
+    }
+}
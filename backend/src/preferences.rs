@@ -0,0 +1,45 @@
+//! Per-user dashboard preferences
+//!
+//! Stored server-side in the `preferences` table so they follow the operator
+//! across browsers. Records are keyed by a SHA-256 of the authenticated
+//! caller's name (an API key's name, or `admin` for the admin token), so a
+//! caller can only reach its own record; anonymous callers read the shared
+//! [`DEFAULT_USER`] record.
+
+use sha2::{Digest, Sha256};
+
+pub use eigenix_common::preferences::{
+    BalanceUnit, FiatCurrency, Theme, UserPreferences, MAX_INTERVAL_MINUTES,
+};
+
+/// Record key used for anonymous callers
+pub const DEFAULT_USER: &str = "default";
+
+/// Record key for an authenticated caller's name
+pub fn preferences_key(caller: Option<&str>) -> String {
+    match caller {
+        Some(name) if !name.is_empty() => Sha256::digest(name.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect(),
+        _ => DEFAULT_USER.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preferences_key_per_caller() {
+        assert_eq!(preferences_key(None), DEFAULT_USER);
+        assert_eq!(preferences_key(Some("")), DEFAULT_USER);
+
+        let key = preferences_key(Some("ops"));
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, preferences_key(Some("ops")));
+        assert_ne!(key, preferences_key(Some("grafana")));
+        // A key named like the shared record doesn't land in it
+        assert_ne!(preferences_key(Some(DEFAULT_USER)), DEFAULT_USER);
+    }
+}
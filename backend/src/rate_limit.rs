@@ -0,0 +1,354 @@
+//! Per-client request rate limiting
+//!
+//! Each client gets two token buckets: one for cheap reads served from the
+//! database or memory, and a smaller one for endpoints that call out to the
+//! wallets, the nodes behind them or the exchange. A bucket holds a minute's
+//! budget and refills continuously, so a client can burst up to its budget
+//! and then gets a 429 with `Retry-After` until tokens come back.
+//!
+//! Clients are told apart by API key when they authenticated, otherwise by
+//! address. A request arriving from one of `trusted_proxies` is counted
+//! against the address its `X-Forwarded-For` names instead of the proxy's,
+//! so anonymous callers behind a reverse proxy don't share one budget.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::auth::Caller;
+use crate::ApiError;
+
+/// Buckets are dropped once full and unused for this long
+const IDLE_BUCKET_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Requests per minute and client to read endpoints. 0 disables.
+    #[serde(default = "default_read_per_minute")]
+    pub read_per_minute: u32,
+    /// Requests per minute and client to wallet, node and exchange
+    /// endpoints. 0 disables.
+    #[serde(default = "default_expensive_per_minute")]
+    pub expensive_per_minute: u32,
+    /// Reverse proxies whose `X-Forwarded-For` names the client
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+fn default_read_per_minute() -> u32 {
+    600
+}
+
+fn default_expensive_per_minute() -> u32 {
+    60
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            read_per_minute: default_read_per_minute(),
+            expensive_per_minute: default_expensive_per_minute(),
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+/// Which budget a request draws from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Budget {
+    Read,
+    /// Hits a wallet RPC, a node or the exchange on every call
+    Expensive,
+}
+
+impl Budget {
+    pub fn for_path(path: &str) -> Self {
+        const EXPENSIVE: [&str; 9] = [
+            "/wallets/",
+            "/kraken/",
+            "/trading/status",
+            "/trading/preview",
+            "/trading/estimate",
+            "/trading/emergency-convert",
+            "/trading/cancel-all",
+            "/asb/peers",
+            "/asb/addresses",
+        ];
+        // The readiness probe and a live valuation call every dependency; the
+        // liveness probe and stored snapshots are free. Approving runs the
+        // held wallet send. A GraphQL query may read the wallets, and a swap
+        // timeline looks up each of the swap's transactions.
+        if path == "/health"
+            || path == "/portfolio"
+            || path == "/graphql"
+            || (path.starts_with("/approvals/") && path.ends_with("/approve"))
+            || (path.starts_with("/asb/swaps/") && path.ends_with("/timeline"))
+            || EXPENSIVE.iter().any(|prefix| path.starts_with(prefix))
+        {
+            Budget::Expensive
+        } else {
+            Budget::Read
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Shared token buckets, keyed by client and budget
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Arc<Mutex<HashMap<(String, Budget), Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn per_minute(&self, budget: Budget) -> u32 {
+        match budget {
+            Budget::Read => self.config.read_per_minute,
+            Budget::Expensive => self.config.expensive_per_minute,
+        }
+    }
+
+    /// Take a token for `client` at `now`
+    ///
+    /// # Returns
+    /// `Err` with the wait until the next token when the budget is spent
+    pub fn check(&self, client: &str, budget: Budget, now: Instant) -> Result<(), Duration> {
+        let per_minute = self.per_minute(budget);
+        if per_minute == 0 {
+            return Ok(());
+        }
+        let capacity = per_minute as f64;
+        let per_sec = capacity / 60.0;
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > 10_000 {
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated) < IDLE_BUCKET_TTL);
+        }
+
+        let bucket = buckets
+            .entry((client.to_string(), budget))
+            .or_insert(Bucket {
+                tokens: capacity,
+                updated: now,
+            });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+        }
+    }
+}
+
+/// Identify the client: its API key, else its address
+fn client_id(request: &Request, trusted_proxies: &[IpAddr]) -> String {
+    if let Some(caller) = request.extensions().get::<Caller>() {
+        return format!("key:{}", caller.name());
+    }
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => {
+            format!("ip:{}", client_ip(request, addr.ip(), trusted_proxies))
+        }
+        None => "unknown".to_string(),
+    }
+}
+
+/// Address of the client behind the peer `peer`
+///
+/// `X-Forwarded-For` is only believed as far as it was written by trusted
+/// proxies: it is read from the right, each trusted hop naming the one before
+/// it, and the first address that isn't a trusted proxy is the client. Left
+/// of that the client could have written anything.
+fn client_ip(request: &Request, peer: IpAddr, trusted_proxies: &[IpAddr]) -> IpAddr {
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+
+    let hops: Vec<&str> = request
+        .headers()
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !trusted_proxies.contains(&ip) {
+            break;
+        }
+    }
+    client
+}
+
+/// Middleware answering 429 to clients over their budget
+pub async fn limit(State(limiter): State<RateLimiter>, request: Request, next: Next) -> Response {
+    let budget = Budget::for_path(request.uri().path());
+    let client = client_id(&request, &limiter.config.trusted_proxies);
+
+    match limiter.check(&client, budget, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            tracing::debug!(
+                "Rate limited {} on {} ({:?} budget)",
+                client,
+                request.uri().path(),
+                budget
+            );
+            ApiError::RateLimited(retry_after).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_for_path() {
+        assert_eq!(Budget::for_path("/wallets/balances"), Budget::Expensive);
+        assert_eq!(Budget::for_path("/kraken/tickers"), Budget::Expensive);
+        assert_eq!(
+            Budget::for_path("/trading/estimate?xmr_needed=1"),
+            Budget::Expensive
+        );
+        assert_eq!(Budget::for_path("/trading/status"), Budget::Expensive);
+        assert_eq!(Budget::for_path("/trading/cancel-all"), Budget::Expensive);
+        assert_eq!(Budget::for_path("/trading/transactions"), Budget::Read);
+        assert_eq!(Budget::for_path("/asb/peers"), Budget::Expensive);
+        assert_eq!(Budget::for_path("/asb/addresses"), Budget::Expensive);
+        assert_eq!(
+            Budget::for_path("/asb/swaps/abc/timeline"),
+            Budget::Expensive
+        );
+        assert_eq!(Budget::for_path("/asb/events"), Budget::Read);
+        assert_eq!(Budget::for_path("/health"), Budget::Expensive);
+        assert_eq!(Budget::for_path("/health/live"), Budget::Read);
+        assert_eq!(Budget::for_path("/portfolio"), Budget::Expensive);
+        assert_eq!(Budget::for_path("/portfolio/history"), Budget::Read);
+        assert_eq!(
+            Budget::for_path("/approvals/abc/approve"),
+            Budget::Expensive
+        );
+        assert_eq!(Budget::for_path("/approvals"), Budget::Read);
+        assert_eq!(Budget::for_path("/graphql"), Budget::Expensive);
+        assert_eq!(Budget::for_path("/metrics/bitcoin/latest"), Budget::Read);
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            read_per_minute: 600,
+            expensive_per_minute: 3,
+            ..RateLimitConfig::default()
+        });
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter
+                .check("ip:10.0.0.1", Budget::Expensive, start)
+                .is_ok());
+        }
+        // 3 per minute: the next token is 20 seconds away
+        let wait = limiter
+            .check("ip:10.0.0.1", Budget::Expensive, start)
+            .unwrap_err();
+        assert_eq!(wait.as_secs(), 20);
+
+        // Other clients and the read budget are unaffected
+        assert!(limiter
+            .check("ip:10.0.0.2", Budget::Expensive, start)
+            .is_ok());
+        assert!(limiter.check("ip:10.0.0.1", Budget::Read, start).is_ok());
+
+        let later = start + Duration::from_secs(20);
+        assert!(limiter
+            .check("ip:10.0.0.1", Budget::Expensive, later)
+            .is_ok());
+        assert!(limiter
+            .check("ip:10.0.0.1", Budget::Expensive, later)
+            .is_err());
+    }
+
+    #[test]
+    fn test_zero_disables_budget() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            read_per_minute: 0,
+            expensive_per_minute: 1,
+            ..RateLimitConfig::default()
+        });
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert!(limiter.check("ip:10.0.0.1", Budget::Read, now).is_ok());
+        }
+    }
+
+    fn request_from(peer: &str, forwarded_for: &[&str]) -> Request {
+        let mut request = Request::new(axum::body::Body::empty());
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 40000)));
+        for value in forwarded_for {
+            request
+                .headers_mut()
+                .append("x-forwarded-for", value.parse().unwrap());
+        }
+        request
+    }
+
+    #[test]
+    fn test_client_behind_trusted_proxy() {
+        let proxies: Vec<IpAddr> = vec!["127.0.0.1".parse().unwrap(), "10.0.0.5".parse().unwrap()];
+
+        // Counted by the address the proxy forwarded for, past trusted hops
+        let request = request_from("127.0.0.1", &["203.0.113.7"]);
+        assert_eq!(client_id(&request, &proxies), "ip:203.0.113.7");
+        let request = request_from("127.0.0.1", &["198.51.100.1, 203.0.113.7", "10.0.0.5"]);
+        assert_eq!(client_id(&request, &proxies), "ip:203.0.113.7");
+
+        // Without a forwarded address the proxy itself is the client
+        let request = request_from("127.0.0.1", &[]);
+        assert_eq!(client_id(&request, &proxies), "ip:127.0.0.1");
+        let request = request_from("127.0.0.1", &["not-an-ip"]);
+        assert_eq!(client_id(&request, &proxies), "ip:127.0.0.1");
+
+        // Anyone else's header is ignored, and no proxies are trusted by default
+        let request = request_from("203.0.113.9", &["198.51.100.1"]);
+        assert_eq!(client_id(&request, &proxies), "ip:203.0.113.9");
+        let request = request_from("127.0.0.1", &["203.0.113.7"]);
+        assert_eq!(client_id(&request, &[]), "ip:127.0.0.1");
+    }
+
+    #[test]
+    fn test_rate_limited_response_has_retry_after() {
+        let response = ApiError::RateLimited(Duration::from_millis(20_500)).into_response();
+        assert_eq!(response.status(), axum::http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[axum::http::header::RETRY_AFTER], "21");
+    }
+}
@@ -0,0 +1,217 @@
+//! Reloading the config file without a restart
+//!
+//! On SIGHUP the config is loaded again from the same file and command line,
+//! validated, and swapped into the running [`SharedConfig`]. Only sections
+//! read afresh on every request or collection cycle take effect right away;
+//! the rest were used to build long-lived clients, listeners and schedules at
+//! startup, so changes to them are logged and kept for the next restart.
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::config::{Cli, Config, SharedConfig};
+
+/// Outcome of a reload
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReloadReport {
+    /// Changed fields now in effect, as dotted paths
+    pub applied: Vec<String>,
+    /// Changed fields that need a restart to take effect
+    pub pending_restart: Vec<String>,
+}
+
+/// Dotted paths of the fields that differ between two configs
+///
+/// Secrets aren't serialized, so they never show up here; they come from the
+/// environment, which a reload doesn't change anyway.
+pub fn changed_fields(old: &Config, new: &Config) -> Vec<String> {
+    let (Ok(old), Ok(new)) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Vec::new();
+    };
+    let mut changed = Vec::new();
+    diff_values("", &old, &new, &mut changed);
+    changed
+}
+
+fn diff_values(path: &str, old: &Value, new: &Value, changed: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                diff_values(
+                    &child,
+                    old.get(key).unwrap_or(&Value::Null),
+                    new.get(key).unwrap_or(&Value::Null),
+                    changed,
+                );
+            }
+        }
+        // Lists (API keys, alert rules, channels) are reported as a whole
+        _ if old != new => changed.push(path.to_string()),
+        _ => {}
+    }
+}
+
+/// `current` with the parts of `loaded` that are safe to change while running
+///
+/// Applied right away:
+/// - `auth`: checked on every request
+/// - `approvals`: read for every held send and rebalance
+/// - `monero` and `asb`: RPC clients are created per collection or request
+/// - `wallets.allow_key_export`: checked on every export
+/// - `wallets.transfer_allowlist`: checked on every transfer
+/// - `idempotency`: read on every mutating request
+pub fn merge_reloadable(current: &Config, loaded: &Config) -> Config {
+    let mut merged = current.clone();
+    merged.auth = loaded.auth.clone();
+    merged.approvals = loaded.approvals.clone();
+    merged.monero = loaded.monero.clone();
+    merged.asb = loaded.asb.clone();
+    merged.wallets.allow_key_export = loaded.wallets.allow_key_export;
+    merged.wallets.transfer_allowlist = loaded.wallets.transfer_allowlist.clone();
+    merged.idempotency = loaded.idempotency.clone();
+    merged
+}
+
+/// Swap in `loaded`, keeping the fields that need a restart as they are
+pub fn apply(shared: &SharedConfig, loaded: Config) -> Result<ReloadReport> {
+    loaded.validate()?;
+
+    let current = shared.get();
+    let merged = merge_reloadable(&current, &loaded);
+    let applied = changed_fields(&current, &merged);
+    let pending_restart = changed_fields(&merged, &loaded);
+
+    if !applied.is_empty() {
+        shared.replace(merged);
+    }
+
+    Ok(ReloadReport {
+        applied,
+        pending_restart,
+    })
+}
+
+/// Load the config again the way it was loaded at startup and apply it
+pub fn reload(cli: &Cli, shared: &SharedConfig) -> Result<ReloadReport> {
+    let loaded = Config::load(cli.clone())?;
+    apply(shared, loaded)
+}
+
+/// Reload the config on every SIGHUP until the process exits
+///
+/// A config that fails to load or validate is logged and ignored; the
+/// running config stays in place.
+#[cfg(unix)]
+pub async fn watch(cli: Cli, shared: SharedConfig) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            tracing::warn!("Failed to listen for SIGHUP, config reload is off: {}", e);
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        tracing::info!("Received SIGHUP, reloading configuration");
+        match reload(&cli, &shared) {
+            Ok(report) => {
+                if report.applied.is_empty() && report.pending_restart.is_empty() {
+                    tracing::info!("Configuration unchanged");
+                }
+                if !report.applied.is_empty() {
+                    tracing::info!("Reloaded configuration: {}", report.applied.join(", "));
+                }
+                if !report.pending_restart.is_empty() {
+                    tracing::warn!(
+                        "Changes that take effect after a restart: {}",
+                        report.pending_restart.join(", ")
+                    );
+                }
+            }
+            Err(e) => tracing::error!("Config reload failed, keeping the current one: {:#}", e),
+        }
+    }
+}
+
+/// SIGHUP doesn't exist here; the config is only read at startup
+#[cfg(not(unix))]
+pub async fn watch(_cli: Cli, _shared: SharedConfig) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Role;
+    use crate::config::ApiKeyConfig;
+
+    #[test]
+    fn test_changed_fields() {
+        let old = Config::default();
+        assert!(changed_fields(&old, &old).is_empty());
+
+        let mut new = old.clone();
+        new.server.port += 1;
+        new.asb.rpc_url = "http://127.0.0.1:9945".to_string();
+        assert_eq!(
+            changed_fields(&old, &new),
+            vec!["asb.rpc_url".to_string(), "server.port".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_apply_keeps_restart_only_fields() {
+        let shared = SharedConfig::new(Config::default());
+
+        let mut loaded = Config::default();
+        loaded.server.port += 1;
+        loaded.monero.rpc_url = "http://10.0.0.5:18081/json_rpc".to_string();
+        loaded.wallets.allow_key_export = true;
+        loaded.wallets.transfer_allowlist = vec!["bc1qcold".to_string()];
+        loaded.approvals.btc_threshold = 0.5;
+        loaded.auth.api_keys = vec![ApiKeyConfig {
+            name: "dashboard".to_string(),
+            key_sha256: "ab".repeat(32),
+            role: Role::Operator,
+        }];
+
+        let report = apply(&shared, loaded).unwrap();
+        assert_eq!(
+            report.applied,
+            vec![
+                "approvals.btc_threshold".to_string(),
+                "auth.api_keys".to_string(),
+                "monero.rpc_url".to_string(),
+                "wallets.allow_key_export".to_string(),
+                "wallets.transfer_allowlist".to_string()
+            ]
+        );
+        assert_eq!(report.pending_restart, vec!["server.port".to_string()]);
+
+        let current = shared.get();
+        assert_eq!(current.server.port, Config::default().server.port);
+        assert_eq!(current.monero.rpc_url, "http://10.0.0.5:18081/json_rpc");
+        assert!(current.wallets.allow_key_export);
+        assert_eq!(current.wallets.transfer_allowlist, vec!["bc1qcold"]);
+        assert_eq!(current.approvals.btc_threshold, 0.5);
+        assert_eq!(current.auth.api_keys.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_config_is_not_applied() {
+        let shared = SharedConfig::new(Config::default());
+
+        let mut loaded = Config::default();
+        loaded.asb.rpc_url = "not a url".to_string();
+        assert!(apply(&shared, loaded).is_err());
+        assert_eq!(shared.get().asb.rpc_url, Config::default().asb.rpc_url);
+    }
+}
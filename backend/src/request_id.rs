@@ -0,0 +1,90 @@
+//! Request IDs
+//!
+//! Every request runs inside a `request` span carrying an ID, so its log
+//! lines and the spans of the calls it makes (exported when
+//! `[tracing] otlp_endpoint` is set) can be told apart from those of other
+//! requests. A caller may pick the ID with an `X-Request-Id` header, e.g. to
+//! match it with its own logs; otherwise one is generated. Either way it is
+//! sent back in the response's `X-Request-Id`.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use tracing::Instrument;
+
+/// Request and response header carrying the ID
+pub const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest ID accepted from a caller, in bytes
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The caller's ID, if it is one we can log and send back as is
+fn caller_id(request: &Request) -> Option<String> {
+    let id = request.headers().get(REQUEST_ID)?.to_str().ok()?;
+    let valid = !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.bytes().all(|b| b.is_ascii_graphic());
+    valid.then(|| id.to_string())
+}
+
+/// A new random ID, 32 hex characters
+fn generate_id() -> String {
+    let random = || RandomState::new().build_hasher().finish();
+    format!("{:016x}{:016x}", random(), random())
+}
+
+/// Middleware running each request in a span tagged with its ID
+pub async fn assign(request: Request, next: Next) -> Response {
+    let id = caller_id(&request).unwrap_or_else(generate_id);
+    let span = tracing::info_span!(
+        "request",
+        id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+        status = tracing::field::Empty,
+    );
+
+    let mut response = next.run(request).instrument(span.clone()).await;
+    span.record("status", response.status().as_u16());
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn request(id: Option<&str>) -> Request {
+        let mut builder = Request::builder().uri("/trading/status");
+        if let Some(id) = id {
+            builder = builder.header(REQUEST_ID, id);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_caller_id() {
+        assert_eq!(
+            caller_id(&request(Some("deploy-42"))).as_deref(),
+            Some("deploy-42")
+        );
+        assert_eq!(caller_id(&request(None)), None);
+        assert_eq!(caller_id(&request(Some("two words"))), None);
+        assert_eq!(caller_id(&request(Some(&"a".repeat(129)))), None);
+    }
+
+    #[test]
+    fn test_generated_ids_differ() {
+        let id = generate_id();
+        assert_eq!(id.len(), 32);
+        assert_ne!(id, generate_id());
+    }
+}
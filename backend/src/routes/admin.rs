@@ -0,0 +1,226 @@
+use axum::{extract::State, routing::post, Json, Router};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::{self, Statement};
+use utoipa::ToSchema;
+
+use crate::{auth::AdminAuth, ApiError, ApiResult, AppState, ErrorResponse};
+
+/// Request body for ad-hoc queries
+#[derive(Deserialize, ToSchema)]
+pub struct QueryRequest {
+    query: String,
+}
+
+/// Result of an ad-hoc query, one entry per statement
+#[derive(Serialize, ToSchema)]
+pub struct QueryResponse {
+    results: Vec<serde_json::Value>,
+}
+
+/// Statement variants that may appear nested inside a SELECT and modify data
+const WRITE_STATEMENTS: &[&str] = &[
+    "Alter", "Create", "Define", "Delete", "Insert", "Rebuild", "Relate", "Remove", "Update",
+    "Upsert",
+];
+
+/// Namespaces of the built-in functions a query may call
+///
+/// Anything else is refused, notably `http::*`, which would let a query make
+/// requests from the server, and custom `fn::*` functions, which may write.
+const READ_ONLY_FUNCTIONS: &[&str] = &[
+    "array", "bytes", "count", "crypto", "duration", "encoding", "geo", "math", "meta", "not",
+    "object", "parse", "rand", "record", "search", "string", "time", "type", "value", "vector",
+];
+
+/// Ensure a query only contains read-only statements
+///
+/// The query is parsed with the SurrealQL parser and every top-level statement
+/// must be a SELECT or INFO. SELECTs are additionally walked so that write
+/// subqueries such as `SELECT * FROM (DELETE foo)` and calls to functions
+/// outside [`READ_ONLY_FUNCTIONS`] are rejected too.
+pub fn ensure_read_only(query: &str) -> ApiResult<()> {
+    let parsed =
+        sql::parse(query).map_err(|e| ApiError::BadRequest(format!("Invalid query: {}", e)))?;
+
+    if parsed.is_empty() {
+        return Err(ApiError::BadRequest("Query is empty".to_string()));
+    }
+
+    for statement in parsed.iter() {
+        match statement {
+            Statement::Info(_) => {}
+            Statement::Select(select) => {
+                let tree = serde_json::to_value(select)
+                    .map_err(|e| ApiError::Internal(anyhow::anyhow!(e)))?;
+                if let Some(found) = find_disallowed(&tree) {
+                    return Err(ApiError::BadRequest(format!(
+                        "Query contains {}, which is not allowed",
+                        found
+                    )));
+                }
+            }
+            other => {
+                return Err(ApiError::BadRequest(format!(
+                    "Only SELECT and INFO statements are allowed, got: {}",
+                    other
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Walk a serialized syntax tree for write statements and disallowed functions
+///
+/// Keys are only read as syntax where the tree has syntax: the keys of an
+/// object literal or record id are the query's own data, so only their values
+/// are walked.
+fn find_disallowed(node: &serde_json::Value) -> Option<String> {
+    match node {
+        serde_json::Value::Object(map) => map.iter().find_map(|(key, value)| match key.as_str() {
+            "Object" => match value {
+                serde_json::Value::Object(fields) => fields.values().find_map(find_disallowed),
+                other => find_disallowed(other),
+            },
+            "Function" => disallowed_function(value).or_else(|| find_disallowed(value)),
+            key if WRITE_STATEMENTS.contains(&key) => {
+                Some(format!("a nested {} statement", key.to_uppercase()))
+            }
+            _ => find_disallowed(value),
+        }),
+        serde_json::Value::Array(items) => items.iter().find_map(find_disallowed),
+        _ => None,
+    }
+}
+
+/// Describe a serialized function call if it may not be run
+fn disallowed_function(function: &serde_json::Value) -> Option<String> {
+    let serde_json::Value::Object(variant) = function else {
+        return None;
+    };
+    match variant.iter().next() {
+        // Built-in, as `[name, arguments]`
+        Some((kind, serde_json::Value::Array(call))) if kind == "Normal" => {
+            let Some(name) = call.first().and_then(|name| name.as_str()) else {
+                return Some("an unrecognised function".to_string());
+            };
+            let namespace = name.split("::").next().unwrap_or_default();
+            (!READ_ONLY_FUNCTIONS.contains(&namespace)).then(|| format!("the function {}", name))
+        }
+        // Closures are walked like any other expression
+        Some((kind, _)) if kind == "Anonymous" => None,
+        // Custom `fn::*` functions and embedded scripts can run any statement
+        Some((kind, _)) => Some(format!("a {} function", kind.to_lowercase())),
+        None => None,
+    }
+}
+
+/// Run a read-only SurrealQL query against the metrics database
+#[utoipa::path(
+    post,
+    path = "/admin/query",
+    tag = "admin",
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "Query result", body = QueryResponse),
+        (status = 400, description = "Query rejected", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn run_query(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Json(request): Json<QueryRequest>,
+) -> ApiResult<Json<QueryResponse>> {
+    ensure_read_only(&request.query)?;
+
+    tracing::info!("Admin query: {}", request.query);
+
+    let results = state
+        .db
+        .query_json(&request.query)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(QueryResponse { results }))
+}
+
+/// Create the admin routes router
+pub fn admin_routes() -> Router<AppState> {
+    Router::new().route("/query", post(run_query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, response::IntoResponse};
+
+    #[test]
+    fn test_select_is_allowed() {
+        assert!(
+            ensure_read_only("SELECT * FROM bitcoin_metrics ORDER BY timestamp DESC LIMIT 5")
+                .is_ok()
+        );
+        assert!(ensure_read_only(
+            "SELECT count() FROM trading_transactions GROUP ALL; INFO FOR DB;"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_delete_is_rejected_with_400() {
+        let err = ensure_read_only("DELETE bitcoin_metrics").unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_writes_are_rejected() {
+        for query in [
+            "CREATE foo SET bar = 1",
+            "UPDATE trading_transactions SET status = 'Failed'",
+            "DEFINE TABLE foo SCHEMALESS",
+            "SELECT * FROM bitcoin_metrics; DELETE bitcoin_metrics",
+            "SELECT * FROM (DELETE bitcoin_metrics RETURN BEFORE)",
+            "-- SELECT\nREMOVE TABLE bitcoin_metrics",
+        ] {
+            assert!(ensure_read_only(query).is_err(), "should reject: {}", query);
+        }
+    }
+
+    #[test]
+    fn test_object_keys_and_identifiers_are_not_statements() {
+        for query in [
+            r#"SELECT { "Update": 1, "Delete": 2 } AS literal FROM bitcoin_metrics"#,
+            "SELECT updated_at, deleted FROM trading_transactions",
+            "SELECT * FROM trading_transactions WHERE notes CONTAINS 'delete'",
+        ] {
+            assert!(ensure_read_only(query).is_ok(), "should allow: {}", query);
+        }
+    }
+
+    #[test]
+    fn test_only_read_only_functions_are_allowed() {
+        for query in [
+            "SELECT math::sum(btc_amount) AS total, count() AS n FROM trading_transactions GROUP ALL",
+            "SELECT time::now() AS now, string::lowercase(status) AS status FROM trading_transactions",
+        ] {
+            assert!(ensure_read_only(query).is_ok(), "should allow: {}", query);
+        }
+
+        for query in [
+            "SELECT * FROM http::get('http://169.254.169.254/')",
+            "SELECT http::post('http://example.com', { a: 1 }) FROM bitcoin_metrics",
+            "SELECT fn::wipe() FROM bitcoin_metrics",
+        ] {
+            assert!(ensure_read_only(query).is_err(), "should reject: {}", query);
+        }
+    }
+
+    #[test]
+    fn test_invalid_query_is_rejected() {
+        assert!(ensure_read_only("SELEC * FORM").is_err());
+        assert!(ensure_read_only("").is_err());
+    }
+}
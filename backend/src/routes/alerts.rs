@@ -0,0 +1,70 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    alerts::{AlertRule, AlertState, StoredAlert},
+    ApiError, ApiResult, AppState,
+};
+
+/// Most alerts returned by a single request
+const MAX_ALERTS: usize = 1000;
+
+/// Query parameters for listing alerts
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AlertsQuery {
+    /// `firing` or `resolved`; both when unset
+    state: Option<AlertState>,
+    limit: Option<usize>,
+}
+
+/// List alerts raised by the configured rules, newest first
+#[utoipa::path(
+    get,
+    path = "/alerts",
+    tag = "alerts",
+    params(
+        AlertsQuery,
+    ),
+    responses(
+        (status = 200, description = "Alert history, newest first", body = Vec<StoredAlert>),
+    )
+)]
+pub async fn list_alerts(
+    State(state): State<AppState>,
+    Query(query): Query<AlertsQuery>,
+) -> ApiResult<Json<Vec<StoredAlert>>> {
+    let limit = query.limit.unwrap_or(100).min(MAX_ALERTS);
+    let alerts = state
+        .db
+        .get_alerts(query.state, limit)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(alerts))
+}
+
+/// List the configured alert rules
+#[utoipa::path(
+    get,
+    path = "/alerts/rules",
+    tag = "alerts",
+    responses(
+        (status = 200, description = "Configured alert rules", body = Vec<AlertRule>),
+    )
+)]
+pub async fn list_rules(State(state): State<AppState>) -> Json<Vec<AlertRule>> {
+    Json(state.config.get().alerts.rules.clone())
+}
+
+/// Create the alerts routes router
+pub fn alerts_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_alerts))
+        .route("/rules", get(list_rules))
+}
@@ -0,0 +1,277 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    approvals::{self, Approval, ApprovalAction, ApprovalStatus},
+    auth::{AdminAuth, Caller},
+    ApiError, ApiResult, AppState, ErrorResponse,
+};
+
+/// Query parameters for listing approvals
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ApprovalsQuery {
+    /// Only approvals in this status; all when unset
+    status: Option<ApprovalStatus>,
+}
+
+/// Body of `POST /approvals/{id}/reject`
+#[derive(Deserialize, ToSchema)]
+pub struct RejectRequest {
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Mark pending approvals and unused approved rebalances past their expiry
+/// as expired
+async fn expire(state: &AppState, approvals: &mut [Approval]) -> ApiResult<()> {
+    let now = Utc::now();
+    let expiry_secs = state.config.get().approvals.expiry_secs;
+
+    for approval in approvals.iter_mut() {
+        if !approval.is_expired(now, expiry_secs) {
+            continue;
+        }
+        let Some(id) = approval.id.clone() else {
+            continue;
+        };
+        let from = approval.status;
+        approval.status = ApprovalStatus::Expired;
+        if from == ApprovalStatus::Pending {
+            approval.decided_at = Some(now);
+        }
+        state
+            .db
+            .transition_approval(&id, from, approval)
+            .await
+            .map_err(ApiError::Database)?;
+    }
+    Ok(())
+}
+
+/// Get an approval that `caller` may still decide
+async fn open_approval(state: &AppState, caller: &Caller, id: &str) -> ApiResult<Approval> {
+    let mut approval = state
+        .db
+        .get_approval(id)
+        .await
+        .map_err(ApiError::Database)?
+        .ok_or_else(|| ApiError::NotFound(format!("Approval {} not found", id)))?;
+
+    expire(state, std::slice::from_mut(&mut approval)).await?;
+    if approval.status != ApprovalStatus::Pending {
+        return Err(ApiError::Conflict(format!(
+            "Approval {} is already {}",
+            id, approval.status
+        )));
+    }
+    approval
+        .check_approver(&caller.principal)
+        .map_err(|e| ApiError::Forbidden(e.to_string()))?;
+
+    Ok(approval)
+}
+
+/// Record a decision on a pending approval, unless another request beat us to it
+async fn decide(state: &AppState, id: &str, approval: &Approval) -> ApiResult<()> {
+    if !state
+        .db
+        .transition_approval(id, ApprovalStatus::Pending, approval)
+        .await
+        .map_err(ApiError::Database)?
+    {
+        return Err(ApiError::Conflict(format!(
+            "Approval {} was decided by another request",
+            id
+        )));
+    }
+    Ok(())
+}
+
+/// List approvals, newest first
+#[utoipa::path(
+    get,
+    path = "/approvals",
+    tag = "approvals",
+    params(
+        ApprovalsQuery,
+    ),
+    responses(
+        (status = 200, description = "Approval requests", body = Vec<Approval>),
+    )
+)]
+pub async fn list_approvals(
+    State(state): State<AppState>,
+    Query(query): Query<ApprovalsQuery>,
+) -> ApiResult<Json<Vec<Approval>>> {
+    let mut approvals = state
+        .db
+        .get_approvals(query.status)
+        .await
+        .map_err(ApiError::Database)?;
+    expire(&state, &mut approvals).await?;
+    if let Some(status) = query.status {
+        approvals.retain(|approval| approval.status == status);
+    }
+
+    Ok(Json(approvals))
+}
+
+/// Get one approval
+#[utoipa::path(
+    get,
+    path = "/approvals/{id}",
+    tag = "approvals",
+    params(
+        ("id" = String, Path, description = "Approval id"),
+    ),
+    responses(
+        (status = 200, description = "Approval request", body = Approval),
+        (status = 404, description = "No such approval", body = ErrorResponse),
+    )
+)]
+pub async fn get_approval(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Approval>> {
+    let mut approval = state
+        .db
+        .get_approval(&id)
+        .await
+        .map_err(ApiError::Database)?
+        .ok_or_else(|| ApiError::NotFound(format!("Approval {} not found", id)))?;
+    expire(&state, std::slice::from_mut(&mut approval)).await?;
+
+    Ok(Json(approval))
+}
+
+/// Approve a held operation with a key other than the one that asked
+///
+/// A wallet send runs now and the approval records its outcome; an approved
+/// rebalance is left for the trading engine to start at its next check.
+#[utoipa::path(
+    post,
+    path = "/approvals/{id}/approve",
+    tag = "approvals",
+    params(
+        ("id" = String, Path, description = "Approval id"),
+    ),
+    responses(
+        (status = 200, description = "Approved and executed", body = Approval),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+        (status = 404, description = "No such approval", body = ErrorResponse),
+        (status = 409, description = "Approval is no longer pending", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn approve(
+    AdminAuth(caller): AdminAuth,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Approval>> {
+    let mut approval = open_approval(&state, &caller, &id).await?;
+    approval.status = ApprovalStatus::Approved;
+    approval.decided_by = Some(caller.name().to_string());
+    approval.decided_at = Some(Utc::now());
+    decide(&state, &id, &approval).await?;
+    tracing::info!(
+        "Approval {} ({:?}) approved by {}, requested by {}",
+        id,
+        approval.action,
+        caller.name(),
+        approval.requested_by
+    );
+
+    if matches!(approval.action, ApprovalAction::Rebalance { .. }) {
+        return Ok(Json(approval));
+    }
+
+    let outcome = approvals::execute(&state, &approval.action).await;
+    match &outcome {
+        Ok(result) => {
+            approval.status = ApprovalStatus::Executed;
+            approval.result = Some(result.clone());
+        }
+        Err(e) => {
+            tracing::error!("Approved operation {} failed: {}", id, e);
+            approval.status = ApprovalStatus::Failed;
+            approval.reason = Some(e.to_string());
+        }
+    }
+    // The send has run (or failed) either way; a failed write only loses the outcome
+    if let Err(e) = state
+        .db
+        .transition_approval(&id, ApprovalStatus::Approved, &approval)
+        .await
+    {
+        tracing::error!("Failed to record outcome of approval {}: {:#}", id, e);
+    }
+
+    outcome.map(|_| Json(approval))
+}
+
+/// Reject a held operation; it is never run
+#[utoipa::path(
+    post,
+    path = "/approvals/{id}/reject",
+    tag = "approvals",
+    params(
+        ("id" = String, Path, description = "Approval id"),
+    ),
+    request_body = RejectRequest,
+    responses(
+        (status = 200, description = "Rejected", body = Approval),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+        (status = 404, description = "No such approval", body = ErrorResponse),
+        (status = 409, description = "Approval is no longer pending", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn reject(
+    AdminAuth(caller): AdminAuth,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<RejectRequest>,
+) -> ApiResult<Json<Approval>> {
+    let mut approval = state
+        .db
+        .get_approval(&id)
+        .await
+        .map_err(ApiError::Database)?
+        .ok_or_else(|| ApiError::NotFound(format!("Approval {} not found", id)))?;
+    if approval.status != ApprovalStatus::Pending {
+        return Err(ApiError::Conflict(format!(
+            "Approval {} is already {}",
+            id, approval.status
+        )));
+    }
+
+    approval.status = ApprovalStatus::Rejected;
+    approval.decided_by = Some(caller.name().to_string());
+    approval.decided_at = Some(Utc::now());
+    approval.reason = request.reason;
+    decide(&state, &id, &approval).await?;
+    tracing::info!(
+        "Approval {} ({:?}) rejected by {}",
+        id,
+        approval.action,
+        caller.name()
+    );
+
+    Ok(Json(approval))
+}
+
+/// Create the approvals routes router
+pub fn approvals_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_approvals))
+        .route("/{id}", get(get_approval))
+        .route("/{id}/approve", post(approve))
+        .route("/{id}/reject", post(reject))
+}
@@ -0,0 +1,146 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    asb_log::AsbEvent,
+    services::{asb::PeerInfo, AsbClient},
+    swap_timeline::{self, SwapTimeline},
+    ApiError, ApiResult, AppState, ErrorResponse,
+};
+
+/// Most events `/asb/events` returns at once
+const MAX_EVENTS: usize = 1000;
+
+/// Addresses the ASB listens on and advertises
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AsbAddresses {
+    /// Local sockets the swarm is bound to
+    pub listen: Vec<String>,
+    /// External addresses peers are told to dial, including the onion address
+    pub external: Vec<String>,
+}
+
+fn asb_client(state: &AppState) -> AsbClient {
+    AsbClient::new(state.config.get().asb.rpc_url.clone())
+}
+
+/// Get the peers the ASB is connected to
+#[utoipa::path(
+    get,
+    path = "/asb/peers",
+    tag = "asb",
+    responses(
+        (status = 200, description = "Peers connected to the ASB", body = Vec<PeerInfo>),
+        (status = 503, description = "ASB unreachable", body = ErrorResponse),
+    )
+)]
+pub async fn get_peers(State(state): State<AppState>) -> ApiResult<Json<Vec<PeerInfo>>> {
+    let peers = asb_client(&state)
+        .get_peers()
+        .await
+        .map_err(ApiError::ServiceUnavailable)?;
+
+    Ok(Json(peers))
+}
+
+/// Get the ASB's listen and external addresses
+#[utoipa::path(
+    get,
+    path = "/asb/addresses",
+    tag = "asb",
+    responses(
+        (status = 200, description = "ASB listen and external addresses", body = AsbAddresses),
+        (status = 503, description = "ASB unreachable", body = ErrorResponse),
+    )
+)]
+pub async fn get_addresses(State(state): State<AppState>) -> ApiResult<Json<AsbAddresses>> {
+    let client = asb_client(&state);
+    let listen = client
+        .get_listen_addresses()
+        .await
+        .map_err(ApiError::ServiceUnavailable)?;
+    let external = client
+        .get_multiaddresses()
+        .await
+        .map_err(ApiError::ServiceUnavailable)?;
+
+    Ok(Json(AsbAddresses { listen, external }))
+}
+
+/// Get the lifecycle events of one swap, in protocol order
+#[utoipa::path(
+    get,
+    path = "/asb/swaps/{id}/timeline",
+    tag = "asb",
+    params(
+        ("id" = String, Path, description = "Swap id"),
+    ),
+    responses(
+        (status = 200, description = "On-chain timeline of the swap", body = SwapTimeline),
+        (status = 404, description = "Unknown swap", body = ErrorResponse),
+        (status = 503, description = "ASB unreachable", body = ErrorResponse),
+    )
+)]
+pub async fn get_swap_timeline(
+    State(state): State<AppState>,
+    Path(swap_id): Path<String>,
+) -> ApiResult<Json<SwapTimeline>> {
+    let swap = asb_client(&state)
+        .get_swap(&swap_id)
+        .await
+        .map_err(ApiError::ServiceUnavailable)?
+        .ok_or_else(|| ApiError::NotFound(format!("Swap {} not found", swap_id)))?;
+
+    Ok(Json(
+        swap_timeline::build_timeline(swap, &state.wallets).await,
+    ))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventsQuery {
+    /// Only this swap's events
+    pub swap_id: Option<String>,
+    /// Number of events, default 100
+    pub limit: Option<usize>,
+}
+
+/// List the swap events read from the ASB's log, newest first
+#[utoipa::path(
+    get,
+    path = "/asb/events",
+    tag = "asb",
+    params(
+        EventsQuery,
+    ),
+    responses(
+        (status = 200, description = "Swap events parsed from the ASB log", body = Vec<AsbEvent>),
+    )
+)]
+pub async fn list_events(
+    State(state): State<AppState>,
+    Query(query): Query<EventsQuery>,
+) -> ApiResult<Json<Vec<AsbEvent>>> {
+    let limit = query.limit.unwrap_or(100).min(MAX_EVENTS);
+    let events = state
+        .db
+        .get_asb_events(query.swap_id.as_deref(), limit)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(events))
+}
+
+/// Create the ASB routes router
+pub fn asb_routes() -> Router<AppState> {
+    Router::new()
+        .route("/peers", get(get_peers))
+        .route("/addresses", get(get_addresses))
+        .route("/swaps/{id}/timeline", get(get_swap_timeline))
+        .route("/events", get(list_events))
+}
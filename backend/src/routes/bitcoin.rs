@@ -1,30 +1,114 @@
-use axum::{extract::State, routing::get, Json, Router};
-use serde::Serialize;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use eigenix_common::wallets::DepositAddress;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-use crate::{ApiError, ApiResult, AppState};
+use crate::{
+    approvals::{self, Approval, ApprovalAction},
+    auth::AdminAuth,
+    db::{StoredTradingTransaction, TransactionType},
+    services::bitcoin::FeeEstimates,
+    wallets::{
+        bitcoin::{CoinControlSend, Utxo, UtxoFilter, WalletDescriptor, WalletInitStatus},
+        psbt::{self, PsbtRequest},
+    },
+    ApiError, ApiResult, AppState, Btc, ErrorResponse,
+};
 
 /// Bitcoin wallet balance response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct BitcoinBalance {
     /// Balance in BTC
     balance: f64,
 }
 
 /// Bitcoin wallet health response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct BitcoinHealth {
     /// Whether Bitcoin wallet is ready and operational
     ready: bool,
 }
 
-/// Bitcoin deposit address response
-#[derive(Serialize)]
-pub struct BitcoinAddress {
-    /// Bitcoin deposit address
-    address: String,
+/// Query parameters for `GET /utxos`
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UtxoQuery {
+    /// Minimum confirmations (default 1; 0 includes mempool outputs)
+    #[serde(default = "default_min_conf")]
+    min_conf: u32,
+    /// Only outputs of at least this amount, in BTC
+    min_amount: Option<Btc>,
+    /// Only outputs of at most this amount, in BTC
+    max_amount: Option<Btc>,
+}
+
+fn default_min_conf() -> u32 {
+    1
+}
+
+/// Unspent output in the UTXO listing
+#[derive(Serialize, ToSchema)]
+pub struct BitcoinUtxo {
+    /// `txid:vout`
+    outpoint: String,
+    txid: String,
+    vout: u32,
+    address: Option<String>,
+    amount: Btc,
+    confirmations: u64,
+}
+
+/// Coin-control send response
+#[derive(Serialize, ToSchema)]
+pub struct BitcoinSendResponse {
+    txid: String,
+}
+
+/// PSBT exported for offline signing
+#[derive(Serialize, ToSchema)]
+pub struct PsbtExport {
+    id: String,
+    txid: String,
+    /// Base64-encoded
+    psbt: String,
+}
+
+/// Body of `POST /psbt/{id}/signed`
+#[derive(Deserialize, ToSchema)]
+pub struct SignedPsbt {
+    /// Base64-encoded
+    psbt: String,
+}
+
+impl From<Utxo> for BitcoinUtxo {
+    fn from(utxo: Utxo) -> Self {
+        Self {
+            outpoint: utxo.outpoint(),
+            txid: utxo.txid,
+            vout: utxo.vout,
+            address: utxo.address,
+            amount: utxo.amount,
+            confirmations: utxo.confirmations,
+        }
+    }
 }
 
 /// Get Bitcoin wallet balance
+#[utoipa::path(
+    get,
+    path = "/wallets/bitcoin/balance",
+    tag = "bitcoin",
+    responses(
+        (status = 200, description = "Wallet balance", body = BitcoinBalance),
+        (status = 503, description = "Wallet unavailable", body = ErrorResponse),
+    )
+)]
 pub async fn get_balance(State(state): State<AppState>) -> ApiResult<Json<BitcoinBalance>> {
     let balance = state
         .wallets
@@ -36,14 +120,51 @@ pub async fn get_balance(State(state): State<AppState>) -> ApiResult<Json<Bitcoi
 }
 
 /// Check Bitcoin wallet health
+#[utoipa::path(
+    get,
+    path = "/wallets/bitcoin/health",
+    tag = "bitcoin",
+    responses(
+        (status = 200, description = "Wallet and node health", body = BitcoinHealth),
+    )
+)]
 pub async fn get_health(State(state): State<AppState>) -> ApiResult<Json<BitcoinHealth>> {
     let ready = state.wallets.bitcoin.is_ready().await;
 
     Ok(Json(BitcoinHealth { ready }))
 }
 
+/// Get descriptor import and rescan progress
+#[utoipa::path(
+    get,
+    path = "/wallets/bitcoin/init-status",
+    tag = "bitcoin",
+    responses(
+        (status = 200, description = "Wallet initialization status", body = WalletInitStatus),
+    )
+)]
+pub async fn get_init_status(State(state): State<AppState>) -> ApiResult<Json<WalletInitStatus>> {
+    let status = state
+        .wallets
+        .bitcoin
+        .get_init_status()
+        .await
+        .map_err(ApiError::Wallet)?;
+
+    Ok(Json(status))
+}
+
 /// Get a new Bitcoin deposit address
-pub async fn get_deposit_address(State(state): State<AppState>) -> ApiResult<Json<BitcoinAddress>> {
+#[utoipa::path(
+    get,
+    path = "/wallets/bitcoin/address",
+    tag = "bitcoin",
+    responses(
+        (status = 200, description = "Fresh deposit address", body = DepositAddress),
+        (status = 503, description = "Wallet unavailable", body = ErrorResponse),
+    )
+)]
+pub async fn get_deposit_address(State(state): State<AppState>) -> ApiResult<Json<DepositAddress>> {
     let address = state
         .wallets
         .bitcoin
@@ -51,7 +172,345 @@ pub async fn get_deposit_address(State(state): State<AppState>) -> ApiResult<Jso
         .await
         .map_err(ApiError::Wallet)?;
 
-    Ok(Json(BitcoinAddress { address }))
+    Ok(Json(DepositAddress { address }))
+}
+
+/// List the wallet's unspent outputs
+#[utoipa::path(
+    get,
+    path = "/wallets/bitcoin/utxos",
+    tag = "bitcoin",
+    params(
+        UtxoQuery,
+    ),
+    responses(
+        (status = 200, description = "Unspent outputs", body = Vec<BitcoinUtxo>),
+    )
+)]
+pub async fn get_utxos(
+    State(state): State<AppState>,
+    Query(query): Query<UtxoQuery>,
+) -> ApiResult<Json<Vec<BitcoinUtxo>>> {
+    let utxos = state
+        .wallets
+        .bitcoin
+        .list_unspent(&UtxoFilter {
+            min_conf: query.min_conf,
+            min_amount: query.min_amount,
+            max_amount: query.max_amount,
+        })
+        .await
+        .map_err(ApiError::Wallet)?;
+
+    Ok(Json(utxos.into_iter().map(BitcoinUtxo::from).collect()))
+}
+
+/// Send spending only the chosen UTXOs, optionally at a set feerate
+///
+/// Sends above the approval threshold are held for a second key.
+#[utoipa::path(
+    post,
+    path = "/wallets/bitcoin/send",
+    tag = "bitcoin",
+    request_body = CoinControlSend,
+    responses(
+        (status = 200, description = "Transaction broadcast", body = BitcoinSendResponse),
+        (status = 202, description = "Held for approval", body = Approval),
+        (status = 400, description = "Invalid send", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn send_coin_control(
+    AdminAuth(caller): AdminAuth,
+    State(state): State<AppState>,
+    Json(send): Json<CoinControlSend>,
+) -> ApiResult<Response> {
+    state
+        .wallets
+        .bitcoin
+        .check_coin_control_send(&send)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("{:#}", e)))?;
+
+    let btc = send.amount.to_f64();
+    let action = ApprovalAction::BitcoinSend { send: send.clone() };
+    if let Some(held) = approvals::hold_if_large(&state, &caller, action, btc, 0.0).await? {
+        return Ok(held);
+    }
+
+    Ok(Json(execute_coin_control_send(&state, &send).await?).into_response())
+}
+
+/// Check and run an approved coin-control send
+pub(crate) async fn run_coin_control_send(
+    state: &AppState,
+    send: &CoinControlSend,
+) -> ApiResult<BitcoinSendResponse> {
+    state
+        .wallets
+        .bitcoin
+        .check_coin_control_send(send)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("{:#}", e)))?;
+
+    execute_coin_control_send(state, send).await
+}
+
+async fn execute_coin_control_send(
+    state: &AppState,
+    send: &CoinControlSend,
+) -> ApiResult<BitcoinSendResponse> {
+    let txid = state
+        .wallets
+        .send_coin_control(send)
+        .await
+        .map_err(super::wallets::wallet_error)?;
+    tracing::info!(
+        "Sent {} BTC to {} from {} selected input(s): {}",
+        send.amount,
+        send.address,
+        send.inputs.len(),
+        txid
+    );
+
+    Ok(BitcoinSendResponse { txid })
+}
+
+/// Fund a send as a PSBT for offline signing and record it as pending
+///
+/// Sends above the approval threshold are held for a second key.
+#[utoipa::path(
+    post,
+    path = "/wallets/bitcoin/psbt",
+    tag = "bitcoin",
+    request_body = PsbtRequest,
+    responses(
+        (status = 201, description = "Unsigned PSBT created", body = StoredTradingTransaction),
+        (status = 202, description = "Held for approval", body = Approval),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn create_psbt(
+    AdminAuth(caller): AdminAuth,
+    State(state): State<AppState>,
+    Json(request): Json<PsbtRequest>,
+) -> ApiResult<Response> {
+    check_psbt_request(&state, &request).await?;
+
+    let btc = request.amount.to_f64();
+    let action = ApprovalAction::BitcoinPsbt {
+        request: request.clone(),
+    };
+    if let Some(held) = approvals::hold_if_large(&state, &caller, action, btc, 0.0).await? {
+        return Ok(held);
+    }
+
+    let record = fund_psbt(&state, &request).await?;
+    Ok((StatusCode::CREATED, Json(record)).into_response())
+}
+
+/// Check and fund an approved PSBT send
+pub(crate) async fn run_create_psbt(
+    state: &AppState,
+    request: &PsbtRequest,
+) -> ApiResult<StoredTradingTransaction> {
+    check_psbt_request(state, request).await?;
+    fund_psbt(state, request).await
+}
+
+async fn check_psbt_request(state: &AppState, request: &PsbtRequest) -> ApiResult<()> {
+    if !request.amount.is_positive() {
+        return Err(ApiError::BadRequest(format!(
+            "Amount must be positive, got {}",
+            request.amount
+        )));
+    }
+    if !state
+        .wallets
+        .bitcoin
+        .validate_address(&request.address)
+        .await
+        .map_err(ApiError::Wallet)?
+    {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid Bitcoin address: {}",
+            request.address
+        )));
+    }
+    Ok(())
+}
+
+async fn fund_psbt(state: &AppState, request: &PsbtRequest) -> ApiResult<StoredTradingTransaction> {
+    let funded = state
+        .wallets
+        .bitcoin
+        .create_funded_psbt(&request.address, request.amount, request.fee_rate)
+        .await
+        .map_err(ApiError::Wallet)?;
+    let mut record = psbt::psbt_record(
+        TransactionType::InternalTransfer,
+        &request.address,
+        request.amount,
+        &funded,
+        "Cold-signed send, awaiting signature".to_string(),
+    );
+
+    let id = state
+        .db
+        .store_trading_transaction(&record)
+        .await
+        .map_err(ApiError::Database)?;
+    record.id = Some(id);
+
+    Ok(record)
+}
+
+/// Export the unsigned PSBT of a send waiting for its signature
+#[utoipa::path(
+    get,
+    path = "/wallets/bitcoin/psbt/{id}",
+    tag = "bitcoin",
+    params(
+        ("id" = String, Path, description = "Transaction id"),
+    ),
+    responses(
+        (status = 200, description = "PSBT for offline signing", body = PsbtExport),
+        (status = 404, description = "No such PSBT", body = ErrorResponse),
+    )
+)]
+pub async fn get_psbt(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<PsbtExport>> {
+    let record = state
+        .db
+        .get_trading_transaction(&id)
+        .await
+        .map_err(ApiError::Database)?;
+    let (Some(psbt), Some(txid)) = record.map_or((None, None), |r| (r.psbt, r.txid)) else {
+        return Err(ApiError::NotFound(format!(
+            "No PSBT awaiting signature for transaction {}",
+            id
+        )));
+    };
+
+    Ok(Json(PsbtExport { id, txid, psbt }))
+}
+
+/// Finalize and broadcast a signed PSBT
+#[utoipa::path(
+    post,
+    path = "/wallets/bitcoin/psbt/{id}/signed",
+    tag = "bitcoin",
+    params(
+        ("id" = String, Path, description = "Transaction id"),
+    ),
+    request_body = SignedPsbt,
+    responses(
+        (status = 200, description = "Signed PSBT broadcast", body = StoredTradingTransaction),
+        (status = 400, description = "PSBT does not match or is not fully signed", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+        (status = 404, description = "No such PSBT", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn submit_signed_psbt(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(signed): Json<SignedPsbt>,
+) -> ApiResult<Json<StoredTradingTransaction>> {
+    let mut record = state
+        .db
+        .get_trading_transaction(&id)
+        .await
+        .map_err(ApiError::Database)?
+        .ok_or_else(|| ApiError::NotFound(format!("Transaction {} not found", id)))?;
+
+    let wallet = &state.wallets.bitcoin;
+    let bad_request = |e: anyhow::Error| ApiError::BadRequest(format!("{:#}", e));
+    let signed_txid = wallet.psbt_txid(&signed.psbt).await.map_err(bad_request)?;
+    psbt::check_signed(&record, &signed_txid).map_err(bad_request)?;
+    let hex = wallet
+        .finalize_psbt(&signed.psbt)
+        .await
+        .map_err(bad_request)?;
+
+    // Engine deposits were counted against the spend limits when funded
+    let txid = if record.transaction_type == TransactionType::BitcoinDeposit {
+        wallet
+            .broadcast_transaction(&hex)
+            .await
+            .map_err(ApiError::Wallet)?
+    } else {
+        state
+            .wallets
+            .broadcast_bitcoin(&hex, record.btc_amount.unwrap_or_default())
+            .await
+            .map_err(super::wallets::wallet_error)?
+    };
+    tracing::info!("Broadcast cold-signed transaction {} ({})", txid, id);
+
+    // Already broadcast; a failed write only loses the record update
+    psbt::mark_broadcast(&mut record, txid);
+    state
+        .db
+        .update_trading_transaction(&id, &record)
+        .await
+        .map_err(ApiError::Database)?;
+    record.id = Some(id);
+
+    Ok(Json(record))
+}
+
+/// Export the wallet's descriptors with their private keys
+#[utoipa::path(
+    get,
+    path = "/wallets/bitcoin/descriptors",
+    tag = "bitcoin",
+    responses(
+        (status = 200, description = "Wallet descriptors", body = Vec<WalletDescriptor>),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn get_descriptors(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<Vec<WalletDescriptor>>> {
+    super::wallets::ensure_key_export(&state)?;
+
+    let descriptors = state
+        .wallets
+        .export_bitcoin_descriptors()
+        .await
+        .map_err(ApiError::Wallet)?;
+    tracing::warn!("Exported the Bitcoin wallet's private descriptors");
+
+    Ok(Json(descriptors))
+}
+
+/// Get fast, medium and slow feerates from bitcoind, cached for a minute
+#[utoipa::path(
+    get,
+    path = "/bitcoin/fees",
+    tag = "bitcoin",
+    responses(
+        (status = 200, description = "Fee estimates by confirmation target", body = FeeEstimates),
+        (status = 503, description = "Node unavailable", body = ErrorResponse),
+    )
+)]
+pub async fn get_fees(State(state): State<AppState>) -> ApiResult<Json<FeeEstimates>> {
+    let estimates = state
+        .fee_estimator
+        .estimates()
+        .await
+        .map_err(ApiError::ServiceUnavailable)?;
+
+    Ok(Json(estimates))
 }
 
 /// Create the Bitcoin wallet routes router
@@ -60,4 +519,16 @@ pub fn bitcoin_routes() -> Router<AppState> {
         .route("/balance", get(get_balance))
         .route("/health", get(get_health))
         .route("/address", get(get_deposit_address))
+        .route("/init-status", get(get_init_status))
+        .route("/utxos", get(get_utxos))
+        .route("/send", post(send_coin_control))
+        .route("/psbt", post(create_psbt))
+        .route("/psbt/{id}", get(get_psbt))
+        .route("/psbt/{id}/signed", post(submit_signed_psbt))
+        .route("/descriptors", get(get_descriptors))
+}
+
+/// Create the Bitcoin node routes router, mounted at `/bitcoin`
+pub fn bitcoin_node_routes() -> Router<AppState> {
+    Router::new().route("/fees", get(get_fees))
 }
@@ -0,0 +1,109 @@
+use axum::{routing::get, Json, Router};
+use std::collections::HashMap;
+
+pub use eigenix_common::config::{AssetPrecision, DisplayConfig};
+
+use crate::{
+    amount::{Btc, Xmr},
+    services::{kraken::AssetInfo, KrakenClient},
+    AppState,
+};
+
+/// Precision for the asset Kraken calls `altname`, given its native decimals
+fn asset_precision(
+    assets: Option<&HashMap<String, AssetInfo>>,
+    altname: &str,
+    native_decimals: u32,
+) -> AssetPrecision {
+    let exchange = assets.and_then(|assets| {
+        assets
+            .values()
+            .find(|asset| asset.altname == altname)
+            .map(|asset| asset.decimals)
+    });
+
+    AssetPrecision {
+        display_decimals: native_decimals,
+        // Never finer than the chain itself can represent
+        exchange_decimals: exchange.map_or(native_decimals, |d| d.min(native_decimals)),
+        from_exchange: exchange.is_some(),
+    }
+}
+
+/// Build the display config from Kraken's asset metadata, if it could be fetched
+pub fn display_config(assets: Option<&HashMap<String, AssetInfo>>) -> DisplayConfig {
+    DisplayConfig {
+        btc: asset_precision(assets, "XBT", Btc::DECIMALS),
+        xmr: asset_precision(assets, "XMR", Xmr::DECIMALS),
+    }
+}
+
+/// Get per-asset display and exchange precision
+///
+/// Falls back to the native precision when Kraken is unreachable, so the
+/// frontend always gets an answer.
+#[utoipa::path(
+    get,
+    path = "/config/display",
+    tag = "config",
+    responses(
+        (status = 200, description = "Display precision per asset", body = DisplayConfig),
+    )
+)]
+pub async fn get_display_config() -> Json<DisplayConfig> {
+    let assets = match KrakenClient::public().get_assets(&["XBT", "XMR"]).await {
+        Ok(assets) => Some(assets),
+        Err(e) => {
+            tracing::warn!("Failed to fetch Kraken asset metadata: {:#}", e);
+            None
+        }
+    };
+
+    Json(display_config(assets.as_ref()))
+}
+
+/// Create the config routes router
+pub fn config_routes() -> Router<AppState> {
+    Router::new().route("/display", get(get_display_config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kraken_assets() -> HashMap<String, AssetInfo> {
+        // Shape of GET /0/public/Assets?asset=XBT,XMR
+        serde_json::from_str(
+            r#"{
+                "XXBT": {"aclass": "currency", "altname": "XBT", "decimals": 10, "display_decimals": 5, "status": "enabled"},
+                "XXMR": {"aclass": "currency", "altname": "XMR", "decimals": 10, "display_decimals": 5, "status": "enabled"}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_display_config_from_asset_metadata() {
+        let config = display_config(Some(&kraken_assets()));
+
+        assert_eq!(config.btc.display_decimals, 8);
+        assert_eq!(config.xmr.display_decimals, 12);
+        // Kraken's 10 decimals, capped at the satoshi for BTC
+        assert_eq!(config.btc.exchange_decimals, 8);
+        assert_eq!(config.xmr.exchange_decimals, 10);
+        assert!(config.btc.from_exchange && config.xmr.from_exchange);
+    }
+
+    #[test]
+    fn test_display_config_falls_back_without_metadata() {
+        for assets in [None, Some(HashMap::new())] {
+            let config = display_config(assets.as_ref());
+
+            assert_eq!(config.btc.display_decimals, 8);
+            assert_eq!(config.btc.exchange_decimals, 8);
+            assert_eq!(config.xmr.display_decimals, 12);
+            assert_eq!(config.xmr.exchange_decimals, 12);
+            assert!(!config.btc.from_exchange && !config.xmr.from_exchange);
+        }
+    }
+}
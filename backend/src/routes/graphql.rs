@@ -0,0 +1,253 @@
+//! Read-only GraphQL view of metrics, wallets and trading
+//!
+//! Serves the same data as the REST endpoints, but lets a client pick the
+//! fields it needs and fetch several series in one round trip. History fields
+//! take a `range` shaped like the query of the `/metrics/*/interval`
+//! endpoints. `POST /graphql` runs a query; `GET /graphql` serves GraphiQL.
+
+use std::sync::OnceLock;
+
+use async_graphql::{
+    http::GraphiQLSource, ComplexObject, Context, EmptyMutation, EmptySubscription, Object, Schema,
+};
+use axum::{extract::State, response::Html, routing::get, Json, Router};
+use chrono::{DateTime, Utc};
+use eigenix_common::{trading::TradingStatus, wallets::WalletBalances};
+
+use crate::{
+    db::{
+        self, prices::StoredPriceMetrics, system::StoredSystemMetrics, StoredTradingTransaction,
+        TransactionStatus, TransactionType,
+    },
+    metrics::humanize,
+    routes::{metrics::IntervalQuery, trading::MAX_TRANSACTIONS},
+    ApiError, AppState,
+};
+
+/// Deepest nesting a query may use
+const MAX_DEPTH: usize = 8;
+
+/// Most fields a single query may select
+const MAX_COMPLEXITY: usize = 500;
+
+pub type EigenixSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+fn schema() -> &'static EigenixSchema {
+    static SCHEMA: OnceLock<EigenixSchema> = OnceLock::new();
+    SCHEMA.get_or_init(|| {
+        Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+            .limit_depth(MAX_DEPTH)
+            .limit_complexity(MAX_COMPLEXITY)
+            .finish()
+    })
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Bitcoin node samples, oldest first
+    async fn bitcoin(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] range: IntervalQuery,
+    ) -> async_graphql::Result<Vec<db::StoredBitcoinMetrics>> {
+        let state = ctx.data::<AppState>()?;
+        let (from, to) = range.window(Utc::now())?;
+        let rows = state
+            .db
+            .get_bitcoin_history(from, to)
+            .await
+            .map_err(ApiError::Database)?;
+
+        Ok(range.page(rows, |row| row.timestamp).into_rows())
+    }
+
+    /// Monero node samples, oldest first
+    async fn monero(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] range: IntervalQuery,
+    ) -> async_graphql::Result<Vec<db::StoredMoneroMetrics>> {
+        let state = ctx.data::<AppState>()?;
+        let (from, to) = range.window(Utc::now())?;
+        let rows = state
+            .db
+            .get_monero_history(from, to)
+            .await
+            .map_err(ApiError::Database)?;
+
+        Ok(range.page(rows, |row| row.timestamp).into_rows())
+    }
+
+    /// ASB samples, oldest first
+    async fn asb(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] range: IntervalQuery,
+    ) -> async_graphql::Result<Vec<db::StoredAsbMetrics>> {
+        let state = ctx.data::<AppState>()?;
+        let (from, to) = range.window(Utc::now())?;
+        let rows = state
+            .db
+            .get_asb_history(from, to)
+            .await
+            .map_err(ApiError::Database)?;
+
+        Ok(range.page(rows, |row| row.timestamp).into_rows())
+    }
+
+    /// Container samples, oldest first; every container unless `name` is given
+    async fn containers(
+        &self,
+        ctx: &Context<'_>,
+        name: Option<String>,
+        #[graphql(default)] range: IntervalQuery,
+    ) -> async_graphql::Result<Vec<db::StoredContainerMetrics>> {
+        let state = ctx.data::<AppState>()?;
+        let (from, to) = range.window(Utc::now())?;
+        let rows = match name {
+            Some(name) => state.db.get_container_history(&name, from, to).await,
+            None => state.db.get_all_container_history(from, to).await,
+        }
+        .map_err(ApiError::Database)?;
+
+        Ok(range.page(rows, |row| row.timestamp).into_rows())
+    }
+
+    /// Host samples, oldest first
+    async fn system(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] range: IntervalQuery,
+    ) -> async_graphql::Result<Vec<StoredSystemMetrics>> {
+        let state = ctx.data::<AppState>()?;
+        let (from, to) = range.window(Utc::now())?;
+        let rows = state
+            .db
+            .get_system_history(from, to)
+            .await
+            .map_err(ApiError::Database)?;
+
+        Ok(range.page(rows, |row| row.timestamp).into_rows())
+    }
+
+    /// Exchange prices, oldest first
+    async fn prices(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] range: IntervalQuery,
+    ) -> async_graphql::Result<Vec<StoredPriceMetrics>> {
+        let state = ctx.data::<AppState>()?;
+        let (from, to) = range.window(Utc::now())?;
+        let rows = state
+            .db
+            .get_price_history(from, to)
+            .await
+            .map_err(ApiError::Database)?;
+
+        Ok(range.page(rows, |row| row.timestamp).into_rows())
+    }
+
+    /// Balances of both wallets, read from the wallet RPCs
+    async fn wallet_balances(&self, ctx: &Context<'_>) -> async_graphql::Result<WalletBalances> {
+        let state = ctx.data::<AppState>()?;
+        let (bitcoin, monero) = state
+            .wallets
+            .get_balances()
+            .await
+            .map_err(ApiError::Wallet)?;
+
+        Ok(WalletBalances { bitcoin, monero })
+    }
+
+    /// Current state of the trading engine
+    async fn trading_status(&self, ctx: &Context<'_>) -> async_graphql::Result<TradingStatus> {
+        let state = ctx.data::<AppState>()?;
+        Ok(state.trading_engine.get_status().await)
+    }
+
+    /// Trading transactions, newest first
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        transaction_type: Option<TransactionType>,
+        status: Option<TransactionStatus>,
+        #[graphql(default = 20)] limit: usize,
+    ) -> async_graphql::Result<Vec<StoredTradingTransaction>> {
+        let state = ctx.data::<AppState>()?;
+        let mut transactions = state
+            .db
+            .get_trading_transactions(
+                from.unwrap_or(DateTime::UNIX_EPOCH),
+                to.unwrap_or_else(Utc::now),
+            )
+            .await
+            .map_err(ApiError::Database)?;
+
+        transactions.retain(|tx| {
+            transaction_type.is_none_or(|t| tx.transaction_type == t)
+                && status.is_none_or(|s| tx.status == s)
+        });
+        transactions.truncate(limit.min(MAX_TRANSACTIONS));
+        Ok(transactions)
+    }
+}
+
+#[ComplexObject]
+impl db::StoredBitcoinMetrics {
+    /// `size_on_disk` for display, e.g. `412.3 GiB`
+    async fn size_on_disk_human(&self) -> String {
+        humanize::humanize_bytes(self.size_on_disk)
+    }
+}
+
+#[ComplexObject]
+impl db::StoredMoneroMetrics {
+    /// `difficulty` for display, e.g. `352.89 G`
+    async fn difficulty_human(&self) -> String {
+        humanize::humanize_si(self.difficulty)
+    }
+}
+
+/// Run a GraphQL query
+async fn execute(
+    State(state): State<AppState>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema().execute(request.data(state)).await)
+}
+
+/// Serve GraphiQL for exploring the schema
+async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+pub fn graphql_routes() -> Router<AppState> {
+    Router::new().route("/", get(graphiql).post(execute))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_exposes_history_and_state() {
+        let sdl = schema().sdl();
+
+        for field in [
+            "bitcoin(range: TimeRange",
+            "containers(name: String",
+            "walletBalances: WalletBalances!",
+            "tradingStatus: TradingStatus!",
+            "transactions(",
+            "sizeOnDiskHuman: String!",
+            "stateDetails: JSON",
+        ] {
+            assert!(sdl.contains(field), "{field} is missing");
+        }
+        assert!(!sdl.contains("type Mutation"));
+    }
+}
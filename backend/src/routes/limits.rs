@@ -0,0 +1,28 @@
+use axum::{extract::State, routing::get, Json, Router};
+
+use crate::{wallets::limits::LimitsStatus, ApiError, ApiResult, AppState};
+
+/// Spend limits and what is left of the daily ones
+#[utoipa::path(
+    get,
+    path = "/limits/status",
+    tag = "wallets",
+    responses(
+        (status = 200, description = "Withdrawal limits and usage", body = LimitsStatus),
+    )
+)]
+pub async fn get_status(State(state): State<AppState>) -> ApiResult<Json<LimitsStatus>> {
+    let status = state
+        .wallets
+        .spend_limits()
+        .status()
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(status))
+}
+
+/// Create the spend limits routes router
+pub fn limits_routes() -> Router<AppState> {
+    Router::new().route("/status", get(get_status))
+}
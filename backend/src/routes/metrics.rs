@@ -1,38 +1,250 @@
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
+    http::{header, HeaderName, HeaderValue},
+    response::{
+        sse::{KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     routing::get,
     Json, Router,
 };
-use chrono::{DateTime, Duration, Utc};
-use serde::Deserialize;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use eigenix_common::metrics::{BitcoinMetricsView, MoneroMetricsView};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
 
-use crate::{db, ApiError, ApiResult, AppState};
+use crate::{
+    db::{self, aggregate},
+    export::{self, ExportQuery},
+    metrics::{backend, clients, prometheus},
+    stream::{self, StreamQuery},
+    ApiError, ApiResult, AppState, ErrorResponse,
+};
 
 /// Query parameters for historical metrics
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct HistoryQuery {
     from: Option<DateTime<Utc>>,
     to: Option<DateTime<Utc>>,
 }
 
 /// Query parameters for container history
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ContainerHistoryQuery {
     name: String,
     from: Option<DateTime<Utc>>,
     to: Option<DateTime<Utc>>,
 }
 
+/// The container or disk path an interval query is about, alongside an
+/// [`IntervalQuery`]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NameQuery {
+    name: String,
+}
+
+/// Query parameters for the history of a watched address
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AddressHistoryQuery {
+    address: String,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
 /// Query parameters for interval metrics
-#[derive(Deserialize)]
+///
+/// Either an explicit `from`/`to` window (RFC3339) or a `minutes` lookback
+/// from now, which may be fractional (e.g. `0.5`).
+///
+/// `step` keeps one sample per `step` seconds, the last in each bucket;
+/// buckets are aligned to the Unix epoch so every page agrees on them.
+/// `limit` caps the samples per response; when more remain the response
+/// carries an `X-Next-Cursor` header to pass back as `cursor`.
+///
+/// The same window is the `range` argument of the GraphQL history fields.
+#[derive(Deserialize, Default, IntoParams, async_graphql::InputObject)]
+#[into_params(parameter_in = Query)]
+#[graphql(name = "TimeRange")]
 pub struct IntervalQuery {
-    minutes: Option<i64>,
+    /// Lookback from `to` when `from` is unset (default 5)
+    minutes: Option<f64>,
+    from: Option<DateTime<Utc>>,
+    /// Defaults to now
+    to: Option<DateTime<Utc>>,
+    /// Keep one sample per this many seconds
+    step: Option<u64>,
+    /// Most samples in the response
+    limit: Option<usize>,
+    /// Only samples after this time, e.g. the `X-Next-Cursor` of the
+    /// previous page
+    cursor: Option<DateTime<Utc>>,
+}
+
+/// Response header holding the cursor of the next page
+pub const NEXT_CURSOR: HeaderName = HeaderName::from_static("x-next-cursor");
+
+/// One page of interval samples, serialized as a plain array
+pub struct Page<T> {
+    rows: Vec<T>,
+    next_cursor: Option<DateTime<Utc>>,
+}
+
+impl<T: Serialize> IntoResponse for Page<T> {
+    fn into_response(self) -> Response {
+        let mut response = Json(self.rows).into_response();
+        if let Some(cursor) = self.next_cursor {
+            let cursor = cursor.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+            if let Ok(value) = HeaderValue::from_str(&cursor) {
+                response.headers_mut().insert(NEXT_CURSOR, value);
+            }
+        }
+        response
+    }
+}
+
+/// Query parameters for aggregated series
+///
+/// The window is given as for [`IntervalQuery`].
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AggregateQuery {
+    field: String,
+    #[serde(rename = "fn")]
+    function: aggregate::Aggregate,
+    /// Bucket width, e.g. `30s`, `5m`, `1h`
+    bucket: String,
+    minutes: Option<f64>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+/// Most buckets one aggregate query may return
+const MAX_BUCKETS: i64 = 10_000;
+
+/// Default lookback for interval endpoints when nothing is specified
+const DEFAULT_INTERVAL_MINUTES: f64 = 5.0;
+
+/// Longest window an interval query may span
+const MAX_INTERVAL_SPAN: Duration = Duration::days(31);
+
+impl IntervalQuery {
+    /// Resolve the query into a concrete `[from, to]` window
+    pub(crate) fn window(&self, now: DateTime<Utc>) -> ApiResult<(DateTime<Utc>, DateTime<Utc>)> {
+        let to = self.to.unwrap_or(now);
+
+        let from = match self.from {
+            Some(from) => from,
+            None => {
+                let minutes = self.minutes.unwrap_or(DEFAULT_INTERVAL_MINUTES);
+                if !minutes.is_finite() || minutes <= 0.0 {
+                    return Err(ApiError::BadRequest(
+                        "minutes must be a positive number".to_string(),
+                    ));
+                }
+                let millis = (minutes * 60_000.0).round();
+                if millis > MAX_INTERVAL_SPAN.num_milliseconds() as f64 {
+                    return Err(ApiError::BadRequest(format!(
+                        "Interval may span at most {} days",
+                        MAX_INTERVAL_SPAN.num_days()
+                    )));
+                }
+                to - Duration::milliseconds(millis as i64)
+            }
+        };
+
+        if from > to {
+            return Err(ApiError::BadRequest(
+                "from must not be after to".to_string(),
+            ));
+        }
+        if to - from > MAX_INTERVAL_SPAN {
+            return Err(ApiError::BadRequest(format!(
+                "Interval may span at most {} days",
+                MAX_INTERVAL_SPAN.num_days()
+            )));
+        }
+
+        if self.step == Some(0) {
+            return Err(ApiError::BadRequest(
+                "step must be at least 1 second".to_string(),
+            ));
+        }
+        if self.limit == Some(0) {
+            return Err(ApiError::BadRequest("limit must be at least 1".to_string()));
+        }
+
+        // Later pages only need the rest of the window
+        let from = self.cursor.map_or(from, |cursor| cursor.max(from));
+
+        Ok((from, to))
+    }
+
+    /// Downsample and paginate rows of the window, which are in timestamp order
+    pub(crate) fn page<T>(&self, rows: Vec<T>, timestamp: impl Fn(&T) -> DateTime<Utc>) -> Page<T> {
+        let mut rows: Vec<T> = match self.cursor {
+            Some(cursor) => rows
+                .into_iter()
+                .filter(|row| timestamp(row) > cursor)
+                .collect(),
+            None => rows,
+        };
+
+        if let Some(step) = self.step {
+            let step = step as i64;
+            let bucket = |row: &T| timestamp(row).timestamp().div_euclid(step);
+            let mut kept: Vec<T> = Vec::with_capacity(rows.len());
+            for row in rows {
+                // Later samples replace earlier ones in the same bucket
+                if kept.last().is_some_and(|last| bucket(last) == bucket(&row)) {
+                    kept.pop();
+                }
+                kept.push(row);
+            }
+            rows = kept;
+        }
+
+        let mut next_cursor = None;
+        if let Some(limit) = self.limit {
+            if rows.len() > limit {
+                rows.truncate(limit);
+                next_cursor = rows.last().map(&timestamp);
+            }
+        }
+
+        Page { rows, next_cursor }
+    }
+}
+
+impl<T> Page<T> {
+    /// The samples, dropping the cursor
+    pub(crate) fn into_rows(self) -> Vec<T> {
+        self.rows
+    }
+
+    /// Convert every sample, keeping the cursor
+    fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            rows: self.rows.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
 }
 
 /// Get latest Bitcoin metrics
-pub async fn bitcoin_metrics(
-    State(state): State<AppState>,
-) -> ApiResult<Json<db::StoredBitcoinMetrics>> {
+#[utoipa::path(
+    get,
+    path = "/metrics/bitcoin",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Latest Bitcoin node metrics", body = BitcoinMetricsView),
+        (status = 404, description = "Nothing collected yet", body = ErrorResponse),
+    )
+)]
+pub async fn bitcoin_metrics(State(state): State<AppState>) -> ApiResult<Json<BitcoinMetricsView>> {
     let metrics = state
         .db
         .get_latest_bitcoin_metrics()
@@ -40,13 +252,20 @@ pub async fn bitcoin_metrics(
         .map_err(ApiError::Database)?
         .ok_or_else(|| ApiError::NotFound("No Bitcoin metrics available".to_string()))?;
 
-    Ok(Json(metrics))
+    Ok(Json(metrics.into()))
 }
 
 /// Get latest Monero metrics
-pub async fn monero_metrics(
-    State(state): State<AppState>,
-) -> ApiResult<Json<db::StoredMoneroMetrics>> {
+#[utoipa::path(
+    get,
+    path = "/metrics/monero",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Latest Monero node metrics", body = MoneroMetricsView),
+        (status = 404, description = "Nothing collected yet", body = ErrorResponse),
+    )
+)]
+pub async fn monero_metrics(State(state): State<AppState>) -> ApiResult<Json<MoneroMetricsView>> {
     let metrics = state
         .db
         .get_latest_monero_metrics()
@@ -54,10 +273,19 @@ pub async fn monero_metrics(
         .map_err(ApiError::Database)?
         .ok_or_else(|| ApiError::NotFound("No Monero metrics available".to_string()))?;
 
-    Ok(Json(metrics))
+    Ok(Json(metrics.into()))
 }
 
 /// Get latest ASB metrics
+#[utoipa::path(
+    get,
+    path = "/metrics/asb",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Latest ASB metrics", body = db::StoredAsbMetrics),
+        (status = 404, description = "Nothing collected yet", body = ErrorResponse),
+    )
+)]
 pub async fn asb_metrics(State(state): State<AppState>) -> ApiResult<Json<db::StoredAsbMetrics>> {
     let metrics = state
         .db
@@ -70,6 +298,15 @@ pub async fn asb_metrics(State(state): State<AppState>) -> ApiResult<Json<db::St
 }
 
 /// Get latest Electrs metrics
+#[utoipa::path(
+    get,
+    path = "/metrics/electrs",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Latest Electrs metrics", body = db::StoredElectrsMetrics),
+        (status = 404, description = "Nothing collected yet", body = ErrorResponse),
+    )
+)]
 pub async fn electrs_metrics(
     State(state): State<AppState>,
 ) -> ApiResult<Json<db::StoredElectrsMetrics>> {
@@ -84,6 +321,14 @@ pub async fn electrs_metrics(
 }
 
 /// Get latest container metrics
+#[utoipa::path(
+    get,
+    path = "/metrics/containers",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Latest sample of each container", body = Vec<db::StoredContainerMetrics>),
+    )
+)]
 pub async fn container_metrics(
     State(state): State<AppState>,
 ) -> ApiResult<Json<Vec<db::StoredContainerMetrics>>> {
@@ -96,18 +341,199 @@ pub async fn container_metrics(
     Ok(Json(metrics))
 }
 
+/// Get latest mempool metrics
+#[utoipa::path(
+    get,
+    path = "/metrics/mempool",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Latest mempool metrics", body = db::mempool::StoredMempoolMetrics),
+        (status = 404, description = "Nothing collected yet", body = ErrorResponse),
+    )
+)]
+pub async fn mempool_metrics(
+    State(state): State<AppState>,
+) -> ApiResult<Json<db::mempool::StoredMempoolMetrics>> {
+    let metrics = state
+        .db
+        .get_latest_mempool_metrics()
+        .await
+        .map_err(ApiError::Database)?
+        .ok_or_else(|| ApiError::NotFound("No mempool metrics available".to_string()))?;
+
+    Ok(Json(metrics))
+}
+
+/// Get latest metrics of the watched addresses
+#[utoipa::path(
+    get,
+    path = "/metrics/mempool/addresses",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Latest sample of each watched address", body = Vec<db::mempool::StoredAddressMetrics>),
+    )
+)]
+pub async fn address_metrics(
+    State(state): State<AppState>,
+) -> ApiResult<Json<Vec<db::mempool::StoredAddressMetrics>>> {
+    let metrics = state
+        .db
+        .get_latest_address_metrics()
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(metrics))
+}
+
 /// Get metrics summary
+#[utoipa::path(
+    get,
+    path = "/metrics/summary",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Latest sample of every source", body = db::MetricsSummary),
+    )
+)]
 pub async fn summary_metrics(State(state): State<AppState>) -> ApiResult<Json<db::MetricsSummary>> {
     let summary = state.db.get_summary().await.map_err(ApiError::Database)?;
 
     Ok(Json(summary))
 }
 
+/// Stream new samples and trading state changes as server-sent events
+///
+/// Each event is named after its source (`bitcoin`, `monero`, `asb`,
+/// `electrs`, `containers`, `disk`, `system`) and carries that source's
+/// latest sample, as from `/metrics/summary`; `trading` events carry the
+/// engine's `state`, `enabled` and `paused`. The first events carry the
+/// current values. At most 64 streams are open at once.
+#[utoipa::path(
+    get,
+    path = "/metrics/stream",
+    tag = "metrics",
+    params(
+        StreamQuery,
+    ),
+    responses(
+        (status = 200, description = "Event stream, open until the client or server goes away", content_type = "text/event-stream", body = String),
+        (status = 400, description = "Invalid interval", body = ErrorResponse),
+        (status = 429, description = "Too many event streams open", body = ErrorResponse),
+    )
+)]
+pub async fn stream_metrics(
+    State(state): State<AppState>,
+    Query(query): Query<StreamQuery>,
+) -> ApiResult<Response> {
+    let period = query.period()?;
+    let events = Sse::new(stream::updates(&state, period)?).keep_alive(KeepAlive::default());
+
+    // Asks nginx-style proxies to pass events on instead of buffering them
+    Ok((
+        [(HeaderName::from_static("x-accel-buffering"), "no")],
+        events,
+    )
+        .into_response())
+}
+
+/// Get latency and outcome counters for the backend's outbound service calls
+#[utoipa::path(
+    get,
+    path = "/metrics/clients",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Call counters per client method", body = Vec<clients::ClientCallStats>),
+    )
+)]
+pub async fn client_metrics() -> Json<Vec<clients::ClientCallStats>> {
+    Json(clients::global().snapshot())
+}
+
+/// The backend's own request, collector, database and process metrics
+#[utoipa::path(
+    get,
+    path = "/metrics/backend",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Current backend metrics", body = backend::BackendMetricsSnapshot),
+    )
+)]
+pub async fn backend_metrics() -> Json<backend::BackendMetricsSnapshot> {
+    Json(backend::global().snapshot())
+}
+
+/// Get stored snapshots of the backend's own metrics
+#[utoipa::path(
+    get,
+    path = "/metrics/backend/history",
+    tag = "metrics",
+    params(
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Stored snapshots, oldest first", body = Vec<backend::BackendMetricsSnapshot>),
+    )
+)]
+pub async fn backend_metrics_history(
+    State(state): State<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> ApiResult<Json<Vec<backend::BackendMetricsSnapshot>>> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or_else(|| to - Duration::hours(24));
+
+    let history = state
+        .db
+        .get_backend_metrics_history(from, to)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(history))
+}
+
+/// Prometheus text exposition of the latest node metrics and the backend's own metrics
+///
+/// A database error drops the node metrics but still serves the call metrics,
+/// so a scrape never fails outright.
+#[utoipa::path(
+    get,
+    path = "/metrics/prometheus",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Prometheus text exposition", body = String, content_type = "text/plain"),
+    )
+)]
+pub async fn prometheus_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let mut body = match state.db.get_summary().await {
+        Ok(summary) => prometheus::render_summary(
+            &summary,
+            &prometheus::ExporterLabels::from_config(&state.config.get()),
+        ),
+        Err(e) => {
+            tracing::warn!("Failed to load metrics summary for Prometheus: {:#}", e);
+            String::new()
+        }
+    };
+    body.push_str(&clients::global().render_prometheus());
+    body.push_str(&backend::global().render_prometheus());
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
 /// Get Bitcoin metrics history
+#[utoipa::path(
+    get,
+    path = "/metrics/bitcoin/history",
+    tag = "metrics",
+    params(
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<BitcoinMetricsView>),
+    )
+)]
 pub async fn bitcoin_history(
     State(state): State<AppState>,
     Query(query): Query<HistoryQuery>,
-) -> ApiResult<Json<Vec<db::StoredBitcoinMetrics>>> {
+) -> ApiResult<Json<Vec<BitcoinMetricsView>>> {
     let to = query.to.unwrap_or_else(Utc::now);
     let from = query.from.unwrap_or_else(|| to - Duration::hours(24));
 
@@ -117,14 +543,25 @@ pub async fn bitcoin_history(
         .await
         .map_err(ApiError::Database)?;
 
-    Ok(Json(history))
+    Ok(Json(history.into_iter().map(Into::into).collect()))
 }
 
 /// Get Monero metrics history
+#[utoipa::path(
+    get,
+    path = "/metrics/monero/history",
+    tag = "metrics",
+    params(
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<MoneroMetricsView>),
+    )
+)]
 pub async fn monero_history(
     State(state): State<AppState>,
     Query(query): Query<HistoryQuery>,
-) -> ApiResult<Json<Vec<db::StoredMoneroMetrics>>> {
+) -> ApiResult<Json<Vec<MoneroMetricsView>>> {
     let to = query.to.unwrap_or_else(Utc::now);
     let from = query.from.unwrap_or_else(|| to - Duration::hours(24));
 
@@ -134,10 +571,21 @@ pub async fn monero_history(
         .await
         .map_err(ApiError::Database)?;
 
-    Ok(Json(history))
+    Ok(Json(history.into_iter().map(Into::into).collect()))
 }
 
 /// Get ASB metrics history
+#[utoipa::path(
+    get,
+    path = "/metrics/asb/history",
+    tag = "metrics",
+    params(
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<db::StoredAsbMetrics>),
+    )
+)]
 pub async fn asb_history(
     State(state): State<AppState>,
     Query(query): Query<HistoryQuery>,
@@ -155,6 +603,17 @@ pub async fn asb_history(
 }
 
 /// Get Electrs metrics history
+#[utoipa::path(
+    get,
+    path = "/metrics/electrs/history",
+    tag = "metrics",
+    params(
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<db::StoredElectrsMetrics>),
+    )
+)]
 pub async fn electrs_history(
     State(state): State<AppState>,
     Query(query): Query<HistoryQuery>,
@@ -172,6 +631,17 @@ pub async fn electrs_history(
 }
 
 /// Get container metrics history
+#[utoipa::path(
+    get,
+    path = "/metrics/containers/history",
+    tag = "metrics",
+    params(
+        ContainerHistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<db::StoredContainerMetrics>),
+    )
+)]
 pub async fn container_history(
     State(state): State<AppState>,
     Query(query): Query<ContainerHistoryQuery>,
@@ -188,14 +658,261 @@ pub async fn container_history(
     Ok(Json(history))
 }
 
+/// Get mempool metrics history
+#[utoipa::path(
+    get,
+    path = "/metrics/mempool/history",
+    tag = "metrics",
+    params(
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<db::mempool::StoredMempoolMetrics>),
+    )
+)]
+pub async fn mempool_history(
+    State(state): State<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> ApiResult<Json<Vec<db::mempool::StoredMempoolMetrics>>> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or_else(|| to - Duration::hours(24));
+
+    let history = state
+        .db
+        .get_mempool_history(from, to)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(history))
+}
+
+/// Get the history of one watched address
+#[utoipa::path(
+    get,
+    path = "/metrics/mempool/addresses/history",
+    tag = "metrics",
+    params(
+        AddressHistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<db::mempool::StoredAddressMetrics>),
+    )
+)]
+pub async fn address_history(
+    State(state): State<AppState>,
+    Query(query): Query<AddressHistoryQuery>,
+) -> ApiResult<Json<Vec<db::mempool::StoredAddressMetrics>>> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or_else(|| to - Duration::hours(24));
+
+    let history = state
+        .db
+        .get_address_history(&query.address, from, to)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(history))
+}
+
+/// Export all metrics history within a time range
+///
+/// The response body can be fed back in with `eigenix import-metrics`.
+#[utoipa::path(
+    get,
+    path = "/metrics/export",
+    tag = "metrics",
+    params(
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Every source's samples in the window", body = db::MetricsExport),
+    )
+)]
+pub async fn export_metrics(
+    State(state): State<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> ApiResult<Json<db::MetricsExport>> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or_else(|| to - Duration::hours(24));
+
+    let export = state
+        .db
+        .export_metrics(from, to)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(export))
+}
+
+/// Download one source's history as CSV or NDJSON
+#[utoipa::path(
+    get,
+    path = "/metrics/{source}/export",
+    tag = "metrics",
+    params(
+        ("source" = String, Path, description = "Metrics source, e.g. `bitcoin` or `containers`"),
+        ExportQuery,
+    ),
+    responses(
+        (status = 200, description = "The source's samples in the window", content((String = "text/csv"), (String = "application/x-ndjson"))),
+        (status = 400, description = "Unknown source", body = ErrorResponse),
+    )
+)]
+pub async fn export_source(
+    State(state): State<AppState>,
+    Path(source): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> ApiResult<Response> {
+    let (from, to) = query.window(Utc::now());
+    let format = query.format;
+
+    match source.as_str() {
+        "bitcoin" => {
+            export::download::<db::StoredBitcoinMetrics>(
+                state.db,
+                "bitcoin_metrics",
+                "bitcoin-metrics",
+                format,
+                from,
+                to,
+            )
+            .await
+        }
+        "monero" => {
+            export::download::<db::StoredMoneroMetrics>(
+                state.db,
+                "monero_metrics",
+                "monero-metrics",
+                format,
+                from,
+                to,
+            )
+            .await
+        }
+        "asb" => {
+            export::download::<db::StoredAsbMetrics>(
+                state.db,
+                "asb_metrics",
+                "asb-metrics",
+                format,
+                from,
+                to,
+            )
+            .await
+        }
+        "electrs" => {
+            export::download::<db::StoredElectrsMetrics>(
+                state.db,
+                "electrs_metrics",
+                "electrs-metrics",
+                format,
+                from,
+                to,
+            )
+            .await
+        }
+        "mempool" => {
+            export::download::<db::mempool::StoredMempoolMetrics>(
+                state.db,
+                "mempool_metrics",
+                "mempool-metrics",
+                format,
+                from,
+                to,
+            )
+            .await
+        }
+        "addresses" => {
+            export::download::<db::mempool::StoredAddressMetrics>(
+                state.db,
+                "address_metrics",
+                "address-metrics",
+                format,
+                from,
+                to,
+            )
+            .await
+        }
+        "asb_quotes" => {
+            export::download::<db::quotes::StoredAsbQuote>(
+                state.db,
+                "asb_quotes",
+                "asb-quotes",
+                format,
+                from,
+                to,
+            )
+            .await
+        }
+        "prices" => {
+            export::download::<db::prices::StoredPriceMetrics>(
+                state.db,
+                "price_metrics",
+                "price-metrics",
+                format,
+                from,
+                to,
+            )
+            .await
+        }
+        "containers" => {
+            export::download::<db::StoredContainerMetrics>(
+                state.db,
+                "container_metrics",
+                "container-metrics",
+                format,
+                from,
+                to,
+            )
+            .await
+        }
+        "disk" => {
+            export::download::<db::disk::StoredDiskMetrics>(
+                state.db,
+                "disk_metrics",
+                "disk-metrics",
+                format,
+                from,
+                to,
+            )
+            .await
+        }
+        "system" => {
+            export::download::<db::system::StoredSystemMetrics>(
+                state.db,
+                "system_metrics",
+                "system-metrics",
+                format,
+                from,
+                to,
+            )
+            .await
+        }
+        _ => Err(ApiError::BadRequest(format!(
+            "Unknown metrics source '{}'",
+            source
+        ))),
+    }
+}
+
 /// Get Bitcoin metrics for time interval
+#[utoipa::path(
+    get,
+    path = "/metrics/bitcoin/interval",
+    tag = "metrics",
+    params(
+        IntervalQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<BitcoinMetricsView>, headers(("x-next-cursor" = String, description = "Pass back as `cursor` for the next page"))),
+        (status = 400, description = "Invalid window, step or limit", body = ErrorResponse),
+    )
+)]
 pub async fn bitcoin_interval(
     State(state): State<AppState>,
     Query(query): Query<IntervalQuery>,
-) -> ApiResult<Json<Vec<db::StoredBitcoinMetrics>>> {
-    let minutes = query.minutes.unwrap_or(5);
-    let to = Utc::now();
-    let from = to - Duration::minutes(minutes);
+) -> ApiResult<Page<BitcoinMetricsView>> {
+    let (from, to) = query.window(Utc::now())?;
 
     let history = state
         .db
@@ -203,17 +920,27 @@ pub async fn bitcoin_interval(
         .await
         .map_err(ApiError::Database)?;
 
-    Ok(Json(history))
+    Ok(query.page(history, |row| row.timestamp).map(Into::into))
 }
 
 /// Get Monero metrics for time interval
+#[utoipa::path(
+    get,
+    path = "/metrics/monero/interval",
+    tag = "metrics",
+    params(
+        IntervalQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<MoneroMetricsView>, headers(("x-next-cursor" = String, description = "Pass back as `cursor` for the next page"))),
+        (status = 400, description = "Invalid window, step or limit", body = ErrorResponse),
+    )
+)]
 pub async fn monero_interval(
     State(state): State<AppState>,
     Query(query): Query<IntervalQuery>,
-) -> ApiResult<Json<Vec<db::StoredMoneroMetrics>>> {
-    let minutes = query.minutes.unwrap_or(5);
-    let to = Utc::now();
-    let from = to - Duration::minutes(minutes);
+) -> ApiResult<Page<MoneroMetricsView>> {
+    let (from, to) = query.window(Utc::now())?;
 
     let history = state
         .db
@@ -221,17 +948,27 @@ pub async fn monero_interval(
         .await
         .map_err(ApiError::Database)?;
 
-    Ok(Json(history))
+    Ok(query.page(history, |row| row.timestamp).map(Into::into))
 }
 
 /// Get ASB metrics for time interval
+#[utoipa::path(
+    get,
+    path = "/metrics/asb/interval",
+    tag = "metrics",
+    params(
+        IntervalQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<db::StoredAsbMetrics>, headers(("x-next-cursor" = String, description = "Pass back as `cursor` for the next page"))),
+        (status = 400, description = "Invalid window, step or limit", body = ErrorResponse),
+    )
+)]
 pub async fn asb_interval(
     State(state): State<AppState>,
     Query(query): Query<IntervalQuery>,
-) -> ApiResult<Json<Vec<db::StoredAsbMetrics>>> {
-    let minutes = query.minutes.unwrap_or(5);
-    let to = Utc::now();
-    let from = to - Duration::minutes(minutes);
+) -> ApiResult<Page<db::StoredAsbMetrics>> {
+    let (from, to) = query.window(Utc::now())?;
 
     let history = state
         .db
@@ -239,17 +976,27 @@ pub async fn asb_interval(
         .await
         .map_err(ApiError::Database)?;
 
-    Ok(Json(history))
+    Ok(query.page(history, |row| row.timestamp))
 }
 
 /// Get Electrs metrics for time interval
+#[utoipa::path(
+    get,
+    path = "/metrics/electrs/interval",
+    tag = "metrics",
+    params(
+        IntervalQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<db::StoredElectrsMetrics>, headers(("x-next-cursor" = String, description = "Pass back as `cursor` for the next page"))),
+        (status = 400, description = "Invalid window, step or limit", body = ErrorResponse),
+    )
+)]
 pub async fn electrs_interval(
     State(state): State<AppState>,
     Query(query): Query<IntervalQuery>,
-) -> ApiResult<Json<Vec<db::StoredElectrsMetrics>>> {
-    let minutes = query.minutes.unwrap_or(5);
-    let to = Utc::now();
-    let from = to - Duration::minutes(minutes);
+) -> ApiResult<Page<db::StoredElectrsMetrics>> {
+    let (from, to) = query.window(Utc::now())?;
 
     let history = state
         .db
@@ -257,13 +1004,309 @@ pub async fn electrs_interval(
         .await
         .map_err(ApiError::Database)?;
 
-    Ok(Json(history))
+    Ok(query.page(history, |row| row.timestamp))
+}
+
+/// Get one container's metrics for time interval
+#[utoipa::path(
+    get,
+    path = "/metrics/containers/interval",
+    tag = "metrics",
+    params(
+        NameQuery,
+        IntervalQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<db::StoredContainerMetrics>, headers(("x-next-cursor" = String, description = "Pass back as `cursor` for the next page"))),
+        (status = 400, description = "Invalid window, step or limit", body = ErrorResponse),
+    )
+)]
+pub async fn container_interval(
+    State(state): State<AppState>,
+    Query(container): Query<NameQuery>,
+    Query(query): Query<IntervalQuery>,
+) -> ApiResult<Page<db::StoredContainerMetrics>> {
+    let (from, to) = query.window(Utc::now())?;
+
+    let history = state
+        .db
+        .get_container_history(&container.name, from, to)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(query.page(history, |row| row.timestamp))
+}
+
+/// Get the latest usage of every monitored disk path
+#[utoipa::path(
+    get,
+    path = "/metrics/disk",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Latest sample of each monitored path", body = Vec<db::disk::StoredDiskMetrics>),
+    )
+)]
+pub async fn disk_metrics(
+    State(state): State<AppState>,
+) -> ApiResult<Json<Vec<db::disk::StoredDiskMetrics>>> {
+    let metrics = state
+        .db
+        .get_latest_disk_metrics()
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(metrics))
+}
+
+/// Get one disk path's usage for time interval
+#[utoipa::path(
+    get,
+    path = "/metrics/disk/interval",
+    tag = "metrics",
+    params(
+        NameQuery,
+        IntervalQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<db::disk::StoredDiskMetrics>, headers(("x-next-cursor" = String, description = "Pass back as `cursor` for the next page"))),
+        (status = 400, description = "Invalid window, step or limit", body = ErrorResponse),
+    )
+)]
+pub async fn disk_interval(
+    State(state): State<AppState>,
+    Query(disk): Query<NameQuery>,
+    Query(query): Query<IntervalQuery>,
+) -> ApiResult<Page<db::disk::StoredDiskMetrics>> {
+    let (from, to) = query.window(Utc::now())?;
+
+    let history = state
+        .db
+        .get_disk_history(&disk.name, from, to)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(query.page(history, |row| row.timestamp))
+}
+
+/// Get the latest resource usage of the backend's host
+#[utoipa::path(
+    get,
+    path = "/metrics/system",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Latest host resource usage", body = db::system::StoredSystemMetrics),
+        (status = 404, description = "Nothing collected yet", body = ErrorResponse),
+    )
+)]
+pub async fn system_metrics(
+    State(state): State<AppState>,
+) -> ApiResult<Json<db::system::StoredSystemMetrics>> {
+    let metrics = state
+        .db
+        .get_latest_system_metrics()
+        .await
+        .map_err(ApiError::Database)?
+        .ok_or_else(|| ApiError::NotFound("No system metrics available".to_string()))?;
+
+    Ok(Json(metrics))
+}
+
+/// Get the host's resource usage for time interval
+#[utoipa::path(
+    get,
+    path = "/metrics/system/interval",
+    tag = "metrics",
+    params(
+        IntervalQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<db::system::StoredSystemMetrics>, headers(("x-next-cursor" = String, description = "Pass back as `cursor` for the next page"))),
+        (status = 400, description = "Invalid window, step or limit", body = ErrorResponse),
+    )
+)]
+pub async fn system_interval(
+    State(state): State<AppState>,
+    Query(query): Query<IntervalQuery>,
+) -> ApiResult<Page<db::system::StoredSystemMetrics>> {
+    let (from, to) = query.window(Utc::now())?;
+
+    let history = state
+        .db
+        .get_system_history(from, to)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(query.page(history, |row| row.timestamp))
+}
+
+/// Get mempool metrics for time interval
+#[utoipa::path(
+    get,
+    path = "/metrics/mempool/interval",
+    tag = "metrics",
+    params(
+        IntervalQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<db::mempool::StoredMempoolMetrics>, headers(("x-next-cursor" = String, description = "Pass back as `cursor` for the next page"))),
+        (status = 400, description = "Invalid window, step or limit", body = ErrorResponse),
+    )
+)]
+pub async fn mempool_interval(
+    State(state): State<AppState>,
+    Query(query): Query<IntervalQuery>,
+) -> ApiResult<Page<db::mempool::StoredMempoolMetrics>> {
+    let (from, to) = query.window(Utc::now())?;
+
+    let history = state
+        .db
+        .get_mempool_history(from, to)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(query.page(history, |row| row.timestamp))
+}
+
+/// Get latest prices
+#[utoipa::path(
+    get,
+    path = "/metrics/prices",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Latest prices", body = db::prices::StoredPriceMetrics),
+        (status = 404, description = "Nothing collected yet", body = ErrorResponse),
+    )
+)]
+pub async fn price_metrics(
+    State(state): State<AppState>,
+) -> ApiResult<Json<db::prices::StoredPriceMetrics>> {
+    let prices = state
+        .prices
+        .latest()
+        .await
+        .map_err(ApiError::Database)?
+        .ok_or_else(|| ApiError::NotFound("No price metrics available".to_string()))?;
+
+    Ok(Json(prices))
+}
+
+/// Get prices for time interval
+#[utoipa::path(
+    get,
+    path = "/metrics/prices/interval",
+    tag = "metrics",
+    params(
+        IntervalQuery,
+    ),
+    responses(
+        (status = 200, description = "Samples in the window, oldest first", body = Vec<db::prices::StoredPriceMetrics>, headers(("x-next-cursor" = String, description = "Pass back as `cursor` for the next page"))),
+        (status = 400, description = "Invalid window, step or limit", body = ErrorResponse),
+    )
+)]
+pub async fn price_interval(
+    State(state): State<AppState>,
+    Query(query): Query<IntervalQuery>,
+) -> ApiResult<Page<db::prices::StoredPriceMetrics>> {
+    let (from, to) = query.window(Utc::now())?;
+
+    let history = state
+        .db
+        .get_price_history(from, to)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(query.page(history, |row| row.timestamp))
+}
+
+/// Get the ASB's quotes for time interval
+#[utoipa::path(
+    get,
+    path = "/metrics/asb/quotes",
+    tag = "metrics",
+    params(
+        IntervalQuery,
+    ),
+    responses(
+        (status = 200, description = "Quotes in the window, oldest first", body = Vec<db::quotes::StoredAsbQuote>, headers(("x-next-cursor" = String, description = "Pass back as `cursor` for the next page"))),
+        (status = 400, description = "Invalid window, step or limit", body = ErrorResponse),
+    )
+)]
+pub async fn asb_quotes(
+    State(state): State<AppState>,
+    Query(query): Query<IntervalQuery>,
+) -> ApiResult<Page<db::quotes::StoredAsbQuote>> {
+    let (from, to) = query.window(Utc::now())?;
+
+    let history = state
+        .db
+        .get_asb_quote_history(from, to)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(query.page(history, |row| row.timestamp))
+}
+
+/// Aggregate one field of a source into `[timestamp, value]` pairs, one per
+/// bucket
+#[utoipa::path(
+    get,
+    path = "/metrics/{source}/aggregate",
+    tag = "metrics",
+    params(
+        ("source" = String, Path, description = "Metrics source, e.g. `bitcoin` or `containers`"),
+        AggregateQuery,
+    ),
+    responses(
+        (status = 200, description = "`[timestamp, value]` per bucket, oldest first", body = Vec<(DateTime<Utc>, f64)>),
+        (status = 400, description = "Unknown field, invalid bucket or window", body = ErrorResponse),
+    )
+)]
+pub async fn aggregate_metrics(
+    State(state): State<AppState>,
+    Path(source): Path<String>,
+    Query(query): Query<AggregateQuery>,
+) -> ApiResult<Json<Vec<(DateTime<Utc>, f64)>>> {
+    let table = aggregate::series_table(&source, &query.field).ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "No numeric field '{}' on {} metrics",
+            query.field, source
+        ))
+    })?;
+    let bucket = aggregate::parse_bucket(&query.bucket).ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "Invalid bucket '{}', expected e.g. 30s, 5m, 1h or 1d",
+            query.bucket
+        ))
+    })?;
+
+    let (from, to) = IntervalQuery {
+        minutes: query.minutes,
+        from: query.from,
+        to: query.to,
+        ..Default::default()
+    }
+    .window(Utc::now())?;
+    if (to - from).num_seconds() / bucket.num_seconds() > MAX_BUCKETS {
+        return Err(ApiError::BadRequest(format!(
+            "At most {} buckets per query; use a wider bucket",
+            MAX_BUCKETS
+        )));
+    }
+
+    let series = state
+        .db
+        .aggregate_series(table, &query.field, query.function, bucket, from, to)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(series))
 }
 
 /// Create the metrics routes router
 pub fn metrics_routes() -> Router<AppState> {
     Router::new()
         .route("/summary", get(summary_metrics))
+        .route("/stream", get(stream_metrics))
         .route("/bitcoin", get(bitcoin_metrics))
         .route("/bitcoin/history", get(bitcoin_history))
         .route("/bitcoin/interval", get(bitcoin_interval))
@@ -273,9 +1316,157 @@ pub fn metrics_routes() -> Router<AppState> {
         .route("/asb", get(asb_metrics))
         .route("/asb/history", get(asb_history))
         .route("/asb/interval", get(asb_interval))
+        .route("/asb/quotes", get(asb_quotes))
         .route("/electrs", get(electrs_metrics))
         .route("/electrs/history", get(electrs_history))
         .route("/electrs/interval", get(electrs_interval))
+        .route("/mempool", get(mempool_metrics))
+        .route("/mempool/history", get(mempool_history))
+        .route("/mempool/interval", get(mempool_interval))
+        .route("/mempool/addresses", get(address_metrics))
+        .route("/mempool/addresses/history", get(address_history))
+        .route("/prices", get(price_metrics))
+        .route("/prices/interval", get(price_interval))
+        .route("/{source}/aggregate", get(aggregate_metrics))
+        .route("/{source}/export", get(export_source))
         .route("/containers", get(container_metrics))
         .route("/containers/history", get(container_history))
+        .route("/containers/interval", get(container_interval))
+        .route("/disk", get(disk_metrics))
+        .route("/disk/interval", get(disk_interval))
+        .route("/system", get(system_metrics))
+        .route("/system/interval", get(system_interval))
+        .route("/export", get(export_metrics))
+        .route("/clients", get(client_metrics))
+        .route("/backend", get(backend_metrics))
+        .route("/backend/history", get(backend_metrics_history))
+        .route("/prometheus", get(prometheus_metrics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_interval_explicit_window() {
+        let query = IntervalQuery {
+            from: Some(at(14, 0)),
+            to: Some(at(15, 0)),
+            // Ignored when an explicit window is given
+            minutes: Some(5.0),
+            ..Default::default()
+        };
+        assert_eq!(query.window(at(20, 0)).unwrap(), (at(14, 0), at(15, 0)));
+    }
+
+    #[test]
+    fn test_interval_minutes_fallback() {
+        let now = at(12, 0);
+
+        let query = IntervalQuery::default();
+        assert_eq!(query.window(now).unwrap(), (at(11, 55), now));
+
+        // Fractional minutes
+        let query = IntervalQuery {
+            minutes: Some(0.5),
+            ..Default::default()
+        };
+        let (from, to) = query.window(now).unwrap();
+        assert_eq!(to - from, Duration::seconds(30));
+
+        // Lookback anchored on an explicit end
+        let query = IntervalQuery {
+            minutes: Some(60.0),
+            to: Some(at(9, 0)),
+            ..Default::default()
+        };
+        assert_eq!(query.window(now).unwrap(), (at(8, 0), at(9, 0)));
+    }
+
+    #[test]
+    fn test_interval_validation() {
+        let now = at(12, 0);
+
+        let reversed = IntervalQuery {
+            from: Some(at(15, 0)),
+            to: Some(at(14, 0)),
+            ..Default::default()
+        };
+        assert!(reversed.window(now).is_err());
+
+        let too_long = IntervalQuery {
+            from: Some(at(0, 0) - Duration::days(60)),
+            to: Some(at(0, 0)),
+            ..Default::default()
+        };
+        assert!(too_long.window(now).is_err());
+
+        for minutes in [0.0, -5.0, f64::NAN, 1e12] {
+            let query = IntervalQuery {
+                minutes: Some(minutes),
+                ..Default::default()
+            };
+            assert!(query.window(now).is_err(), "minutes={}", minutes);
+        }
+
+        for query in [
+            IntervalQuery {
+                step: Some(0),
+                ..Default::default()
+            },
+            IntervalQuery {
+                limit: Some(0),
+                ..Default::default()
+            },
+        ] {
+            assert!(query.window(now).is_err());
+        }
+    }
+
+    #[test]
+    fn test_interval_step_keeps_last_sample_per_bucket() {
+        // A sample every minute from 12:00 to 12:59
+        let rows: Vec<DateTime<Utc>> = (0..60).map(|m| at(12, m)).collect();
+        let query = IntervalQuery {
+            step: Some(15 * 60),
+            ..Default::default()
+        };
+
+        let page = query.page(rows, |row| *row);
+        assert_eq!(
+            page.rows,
+            vec![at(12, 14), at(12, 29), at(12, 44), at(12, 59)]
+        );
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[test]
+    fn test_interval_pages_follow_cursor() {
+        let rows: Vec<DateTime<Utc>> = (0..10).map(|m| at(12, m)).collect();
+        let mut query = IntervalQuery {
+            from: Some(at(12, 0)),
+            limit: Some(4),
+            ..Default::default()
+        };
+
+        let mut seen = Vec::new();
+        loop {
+            let page = query.page(rows.clone(), |row| *row);
+            seen.extend(page.rows);
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(seen, rows);
+
+        // The cursor narrows the window queried
+        let (from, _) = query.window(at(13, 0)).unwrap();
+        assert_eq!(from, at(12, 7));
+    }
 }
@@ -1,15 +1,35 @@
 /// API route modules
 ///
 /// This module organizes the API endpoints into logical groups:
+/// - `admin`: Privileged endpoints guarded by the admin token
+/// - `alerts`: Alerts raised by the configured rules
+/// - `approvals`: Large sends and rebalances held for a second key
+/// - `asb`: ASB peer connectivity, addresses, swap timelines and logged swap events
 /// - `bitcoin`: Endpoints for Bitcoin wallet operations
+/// - `config`: Frontend display settings
+/// - `graphql`: Read-only GraphQL queries over metrics, wallets and trading
 /// - `kraken`: Endpoints for Kraken exchange data
+/// - `limits`: Spend limits on the wallets and the remaining daily budget
 /// - `metrics`: Endpoints for retrieving system and service metrics
 /// - `monero`: Endpoints for Monero wallet operations
+/// - `notifications`: Test delivery through the notification channels
+/// - `portfolio`: Holdings across wallets, exchange and ASB, valued in BTC and USD
+/// - `preferences`: Per-user dashboard display preferences
 /// - `trading`: Endpoints for trading engine control and monitoring
 /// - `wallets`: Combined wallet endpoints and orchestration
+pub mod admin;
+pub mod alerts;
+pub mod approvals;
+pub mod asb;
 pub mod bitcoin;
+pub mod config;
+pub mod graphql;
 pub mod kraken;
+pub mod limits;
 pub mod metrics;
 pub mod monero;
+pub mod notifications;
+pub mod portfolio;
+pub mod preferences;
 pub mod trading;
 pub mod wallets;
@@ -1,41 +1,76 @@
 use axum::{
     extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
-use serde::Serialize;
+use eigenix_common::wallets::DepositAddress;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-use crate::{ApiError, ApiResult, AppState};
+use crate::{
+    approvals::{self, Approval, ApprovalAction},
+    auth::{AdminAuth, OperatorAuth},
+    wallets::monero::{Destination, SentTransaction, TransferPriority, WalletKeys},
+    ApiError, ApiResult, AppState, ErrorResponse, Xmr,
+};
 
 /// Monero wallet balance response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct MoneroBalance {
     /// Balance in XMR
     balance: f64,
 }
 
 /// Monero wallet health response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct MoneroHealth {
     /// Whether Monero wallet is ready and operational
     ready: bool,
 }
 
 /// Refresh Monero wallet response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct RefreshResponse {
     /// New wallet height after refresh
     height: u64,
 }
 
-/// Monero deposit address response
-#[derive(Serialize)]
-pub struct MoneroAddress {
-    /// Monero deposit address
+/// Body of `POST /sweep`
+#[derive(Deserialize, ToSchema)]
+pub struct SweepRequest {
     address: String,
+    #[serde(default)]
+    priority: TransferPriority,
+    /// Only sweep outputs below this amount, in XMR
+    below_amount: Option<Xmr>,
+}
+
+/// Body of `POST /transfer-split`
+#[derive(Deserialize, ToSchema)]
+pub struct SplitTransferRequest {
+    destinations: Vec<Destination>,
+    #[serde(default)]
+    priority: TransferPriority,
+}
+
+/// Transactions created by a sweep or split transfer
+#[derive(Serialize, ToSchema)]
+pub struct SendResponse {
+    transactions: Vec<SentTransaction>,
 }
 
 /// Get Monero wallet balance
+#[utoipa::path(
+    get,
+    path = "/wallets/monero/balance",
+    tag = "monero",
+    responses(
+        (status = 200, description = "Wallet balance", body = MoneroBalance),
+        (status = 503, description = "Wallet unavailable", body = ErrorResponse),
+    )
+)]
 pub async fn get_balance(State(state): State<AppState>) -> ApiResult<Json<MoneroBalance>> {
     let balance = state
         .wallets
@@ -47,6 +82,14 @@ pub async fn get_balance(State(state): State<AppState>) -> ApiResult<Json<Monero
 }
 
 /// Check Monero wallet health
+#[utoipa::path(
+    get,
+    path = "/wallets/monero/health",
+    tag = "monero",
+    responses(
+        (status = 200, description = "Wallet RPC health", body = MoneroHealth),
+    )
+)]
 pub async fn get_health(State(state): State<AppState>) -> ApiResult<Json<MoneroHealth>> {
     let ready = state.wallets.monero.is_ready().await;
 
@@ -54,7 +97,20 @@ pub async fn get_health(State(state): State<AppState>) -> ApiResult<Json<MoneroH
 }
 
 /// Refresh Monero wallet to sync with blockchain
-pub async fn refresh_wallet(State(state): State<AppState>) -> ApiResult<Json<RefreshResponse>> {
+#[utoipa::path(
+    post,
+    path = "/wallets/monero/refresh",
+    tag = "monero",
+    responses(
+        (status = 200, description = "Wallet rescanned", body = RefreshResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn refresh_wallet(
+    _operator: OperatorAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<RefreshResponse>> {
     let height = state
         .wallets
         .refresh_monero()
@@ -64,8 +120,17 @@ pub async fn refresh_wallet(State(state): State<AppState>) -> ApiResult<Json<Ref
     Ok(Json(RefreshResponse { height }))
 }
 
-/// Get Monero deposit address
-pub async fn get_deposit_address(State(state): State<AppState>) -> ApiResult<Json<MoneroAddress>> {
+/// Get the Monero wallet's primary address
+#[utoipa::path(
+    get,
+    path = "/wallets/monero/address",
+    tag = "monero",
+    responses(
+        (status = 200, description = "Deposit address", body = DepositAddress),
+        (status = 503, description = "Wallet unavailable", body = ErrorResponse),
+    )
+)]
+pub async fn get_deposit_address(State(state): State<AppState>) -> ApiResult<Json<DepositAddress>> {
     let address = state
         .wallets
         .monero
@@ -73,7 +138,219 @@ pub async fn get_deposit_address(State(state): State<AppState>) -> ApiResult<Jso
         .await
         .map_err(ApiError::Wallet)?;
 
-    Ok(Json(MoneroAddress { address }))
+    Ok(Json(DepositAddress { address }))
+}
+
+/// Create a new Monero subaddress to deposit to
+#[utoipa::path(
+    post,
+    path = "/wallets/monero/address",
+    tag = "monero",
+    responses(
+        (status = 201, description = "New subaddress", body = DepositAddress),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+        (status = 503, description = "Wallet unavailable", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn create_deposit_address(
+    _operator: OperatorAuth,
+    State(state): State<AppState>,
+) -> ApiResult<(StatusCode, Json<DepositAddress>)> {
+    let address = state
+        .wallets
+        .monero
+        .create_subaddress(0, Some("eigenix-deposit"))
+        .await
+        .map_err(ApiError::Wallet)?
+        .address;
+
+    Ok((StatusCode::CREATED, Json(DepositAddress { address })))
+}
+
+/// Sweep unlocked outputs (all, or those below an amount) to one address
+///
+/// Held for a second key when the unlocked balance is above the approval
+/// threshold, since that is as much as the sweep can move.
+#[utoipa::path(
+    post,
+    path = "/wallets/monero/sweep",
+    tag = "monero",
+    request_body = SweepRequest,
+    responses(
+        (status = 200, description = "Sweep broadcast", body = SendResponse),
+        (status = 202, description = "Held for approval", body = Approval),
+        (status = 400, description = "Invalid sweep", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn sweep(
+    AdminAuth(caller): AdminAuth,
+    State(state): State<AppState>,
+    Json(request): Json<SweepRequest>,
+) -> ApiResult<Response> {
+    if request
+        .below_amount
+        .is_some_and(|amount| !amount.is_positive())
+    {
+        return Err(ApiError::BadRequest(
+            "below_amount must be positive".to_string(),
+        ));
+    }
+
+    let unlocked = state
+        .wallets
+        .monero
+        .get_balance()
+        .await
+        .map_err(ApiError::Wallet)?
+        .unlocked_balance;
+    let action = ApprovalAction::MoneroSweep {
+        address: request.address.clone(),
+        priority: request.priority,
+        below_amount: request.below_amount,
+    };
+    if let Some(held) = approvals::hold_if_large(&state, &caller, action, 0.0, unlocked).await? {
+        return Ok(held);
+    }
+
+    let response = run_sweep(
+        &state,
+        &request.address,
+        request.priority,
+        request.below_amount,
+    )
+    .await?;
+    Ok(Json(response).into_response())
+}
+
+/// Sweep unlocked outputs to `address`
+pub(crate) async fn run_sweep(
+    state: &AppState,
+    address: &str,
+    priority: TransferPriority,
+    below_amount: Option<Xmr>,
+) -> ApiResult<SendResponse> {
+    let transactions = state
+        .wallets
+        .sweep_monero(address, priority, below_amount)
+        .await
+        .map_err(super::wallets::wallet_error)?;
+    tracing::info!(
+        "Swept Monero outputs to {} in {} transaction(s)",
+        address,
+        transactions.len()
+    );
+
+    Ok(SendResponse { transactions })
+}
+
+/// Pay several recipients in one go
+///
+/// Held for a second key when the total is above the approval threshold.
+#[utoipa::path(
+    post,
+    path = "/wallets/monero/transfer-split",
+    tag = "monero",
+    request_body = SplitTransferRequest,
+    responses(
+        (status = 200, description = "Transfers broadcast", body = SendResponse),
+        (status = 202, description = "Held for approval", body = Approval),
+        (status = 400, description = "Invalid transfer", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn transfer_split(
+    AdminAuth(caller): AdminAuth,
+    State(state): State<AppState>,
+    Json(request): Json<SplitTransferRequest>,
+) -> ApiResult<Response> {
+    state
+        .wallets
+        .monero
+        .check_split_transfer(&request.destinations)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("{:#}", e)))?;
+
+    let xmr = request
+        .destinations
+        .iter()
+        .map(|destination| destination.amount.to_f64())
+        .sum();
+    let action = ApprovalAction::MoneroTransferSplit {
+        destinations: request.destinations.clone(),
+        priority: request.priority,
+    };
+    if let Some(held) = approvals::hold_if_large(&state, &caller, action, 0.0, xmr).await? {
+        return Ok(held);
+    }
+
+    let response = execute_transfer_split(&state, &request.destinations, request.priority).await?;
+    Ok(Json(response).into_response())
+}
+
+/// Check and run an approved split transfer against the balance as it is now
+pub(crate) async fn run_transfer_split(
+    state: &AppState,
+    destinations: &[Destination],
+    priority: TransferPriority,
+) -> ApiResult<SendResponse> {
+    state
+        .wallets
+        .monero
+        .check_split_transfer(destinations)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("{:#}", e)))?;
+
+    execute_transfer_split(state, destinations, priority).await
+}
+
+async fn execute_transfer_split(
+    state: &AppState,
+    destinations: &[Destination],
+    priority: TransferPriority,
+) -> ApiResult<SendResponse> {
+    let transactions = state
+        .wallets
+        .transfer_split_monero(destinations, priority)
+        .await
+        .map_err(super::wallets::wallet_error)?;
+    tracing::info!(
+        "Sent XMR to {} destination(s) in {} transaction(s)",
+        destinations.len(),
+        transactions.len()
+    );
+
+    Ok(SendResponse { transactions })
+}
+
+/// Export the wallet's keys and seed
+#[utoipa::path(
+    get,
+    path = "/wallets/monero/keys",
+    tag = "monero",
+    responses(
+        (status = 200, description = "Wallet view key and address", body = WalletKeys),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn get_keys(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<WalletKeys>> {
+    super::wallets::ensure_key_export(&state)?;
+
+    let keys = state
+        .wallets
+        .export_monero_keys()
+        .await
+        .map_err(ApiError::Wallet)?;
+    tracing::warn!("Exported the Monero wallet's keys");
+
+    Ok(Json(keys))
 }
 
 /// Create the Monero wallet routes router
@@ -81,6 +358,12 @@ pub fn monero_routes() -> Router<AppState> {
     Router::new()
         .route("/balance", get(get_balance))
         .route("/health", get(get_health))
-        .route("/address", get(get_deposit_address))
+        .route(
+            "/address",
+            get(get_deposit_address).post(create_deposit_address),
+        )
         .route("/refresh", post(refresh_wallet))
+        .route("/sweep", post(sweep))
+        .route("/transfer-split", post(transfer_split))
+        .route("/keys", get(get_keys))
 }
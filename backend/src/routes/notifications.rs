@@ -0,0 +1,48 @@
+use axum::{extract::State, routing::post, Json, Router};
+use chrono::Utc;
+
+use crate::{
+    alerts::{Alert, AlertNotifier, AlertSeverity},
+    auth::AdminAuth,
+    notifications::DeliveryReport,
+    ApiError, ApiResult, AppState, ErrorResponse,
+};
+
+/// Send a test alert through every configured channel (admin only)
+///
+/// Returns one report per channel, whether or not it was delivered.
+#[utoipa::path(
+    post,
+    path = "/notifications/test",
+    tag = "notifications",
+    responses(
+        (status = 200, description = "Delivery result per channel", body = Vec<DeliveryReport>),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn test_notifications(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+) -> ApiResult<Json<Vec<DeliveryReport>>> {
+    let notifier = AlertNotifier::from_config(&state.config.get());
+    if notifier.channels().is_empty() {
+        return Err(ApiError::BadRequest(
+            "No notification channels are configured".to_string(),
+        ));
+    }
+
+    let alert = Alert {
+        kind: "notification_test".to_string(),
+        severity: AlertSeverity::Warning,
+        message: "Test notification from eigenix".to_string(),
+        timestamp: Utc::now(),
+    };
+
+    Ok(Json(notifier.deliver(&alert).await))
+}
+
+/// Create the notifications routes router
+pub fn notifications_routes() -> Router<AppState> {
+    Router::new().route("/test", post(test_notifications))
+}
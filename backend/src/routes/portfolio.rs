@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use chrono::Utc;
+
+use crate::{
+    portfolio::{self, Portfolio},
+    routes::metrics::{IntervalQuery, Page},
+    ApiError, ApiResult, AppState, ErrorResponse,
+};
+
+/// Value everything we currently hold
+#[utoipa::path(
+    get,
+    path = "/portfolio",
+    tag = "portfolio",
+    responses(
+        (status = 200, description = "Current holdings and valuation", body = Portfolio),
+    )
+)]
+pub async fn get_portfolio(State(state): State<AppState>) -> ApiResult<Json<Portfolio>> {
+    Ok(Json(portfolio::current(&state).await?))
+}
+
+/// Get stored portfolio snapshots for time interval
+#[utoipa::path(
+    get,
+    path = "/portfolio/history",
+    tag = "portfolio",
+    params(
+        IntervalQuery,
+    ),
+    responses(
+        (status = 200, description = "Portfolio valuations", body = Vec<Portfolio>, headers(("x-next-cursor" = String, description = "Pass back as `cursor` for the next page"))),
+        (status = 400, description = "Invalid interval", body = ErrorResponse),
+    )
+)]
+pub async fn portfolio_history(
+    State(state): State<AppState>,
+    Query(query): Query<IntervalQuery>,
+) -> ApiResult<Page<Portfolio>> {
+    let (from, to) = query.window(Utc::now())?;
+
+    let history = state
+        .db
+        .get_portfolio_history(from, to)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(query.page(history, |row| row.timestamp))
+}
+
+/// Create the portfolio routes router
+pub fn portfolio_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_portfolio))
+        .route("/history", get(portfolio_history))
+}
@@ -0,0 +1,74 @@
+use axum::{
+    extract::{FromRef, State},
+    routing::get,
+    Json, Router,
+};
+
+use crate::{
+    auth::{OperatorKey, UserKey},
+    preferences::UserPreferences,
+    ApiError, ApiResult, ErrorResponse, MetricsDatabase,
+};
+
+/// Get the caller's display preferences, or the defaults if none are saved
+#[utoipa::path(
+    get,
+    path = "/preferences",
+    tag = "preferences",
+    responses(
+        (status = 200, description = "Saved preferences, or the defaults", body = UserPreferences)
+    ),
+    security((), ("bearer" = []))
+)]
+pub async fn get_preferences(
+    State(db): State<MetricsDatabase>,
+    UserKey(key): UserKey,
+) -> ApiResult<Json<UserPreferences>> {
+    let preferences = db
+        .get_preferences(&key)
+        .await
+        .map_err(ApiError::Database)?
+        .unwrap_or_default();
+
+    Ok(Json(preferences))
+}
+
+/// Replace the caller's display preferences
+#[utoipa::path(
+    put,
+    path = "/preferences",
+    tag = "preferences",
+    request_body = UserPreferences,
+    responses(
+        (status = 200, description = "Preferences saved", body = UserPreferences),
+        (status = 400, description = "Invalid preferences", body = ErrorResponse),
+        (status = 401, description = "No API key given", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse)
+    ),
+    security(("bearer" = []))
+)]
+pub async fn update_preferences(
+    State(db): State<MetricsDatabase>,
+    OperatorKey(key): OperatorKey,
+    Json(body): Json<serde_json::Value>,
+) -> ApiResult<Json<UserPreferences>> {
+    let preferences = UserPreferences::from_json(body).map_err(ApiError::BadRequest)?;
+
+    db.save_preferences(&key, &preferences)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(preferences))
+}
+
+/// Create the preferences routes router
+///
+/// Only needs the database, so tests can serve it without a full `AppState`.
+/// Callers are identified by the [`crate::auth::authenticate`] middleware.
+pub fn preferences_routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    MetricsDatabase: FromRef<S>,
+{
+    Router::new().route("/", get(get_preferences).put(update_preferences))
+}
@@ -1,43 +1,142 @@
 use axum::{
-    extract::State,
-    routing::{get, post, put},
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+    routing::{get, post},
     Json, Router,
 };
+use chrono::{DateTime, Duration, Utc};
+use eigenix_common::trading::{EnableRequest, EnableResponse};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::{
-    trading::{config::TradingConfig, engine::TradingStatus},
-    ApiError, ApiResult, AppState,
+    amount::Xmr,
+    auth::{AdminAuth, OperatorAuth},
+    db::StoredTradingTransaction,
+    export::{self, ExportQuery},
+    trading::{
+        config::TradingConfig,
+        control::StepOverride,
+        emergency::{self, ConversionDirection, EmergencyPlan},
+        engine::{CancelAllSummary, RebalancePreview, TradingState, TradingStatus},
+        estimate::CostEstimate,
+        lock::LockStatus,
+        pnl::PnlReport,
+    },
+    ApiError, ApiResult, AppState, ErrorResponse,
 };
 
-/// Request to enable/disable trading
-#[derive(Deserialize)]
-pub struct EnableRequest {
-    enabled: bool,
+/// Most transactions returned by a single request
+pub(crate) const MAX_TRANSACTIONS: usize = 1000;
+
+/// Engine state after a pause, resume or step override
+#[derive(Serialize, ToSchema)]
+pub struct ControlResponse {
+    paused: bool,
+    state: TradingState,
+    /// The override requested, if any; applied at the step's next poll
+    #[serde(skip_serializing_if = "Option::is_none")]
+    step_override: Option<StepOverride>,
+}
+
+/// Query parameters for a cost estimate
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EstimateQuery {
+    xmr_needed: Xmr,
+}
+
+/// Query parameters for listing recent transactions
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TransactionsQuery {
+    limit: Option<usize>,
 }
 
-/// Response for enable/disable operations
-#[derive(Serialize)]
-pub struct EnableResponse {
-    success: bool,
-    enabled: bool,
+/// Query parameters for the rebalancing P&L, defaulting to the last 30 days
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PnlQuery {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+/// Request to convert the whole balance in one direction
+#[derive(Deserialize, ToSchema)]
+pub struct EmergencyConvertRequest {
+    direction: ConversionDirection,
+    /// Must equal the direction's confirmation phrase, e.g. "CONVERT ALL XMR TO BTC"
+    confirmation: String,
 }
 
 /// Get trading engine status
+#[utoipa::path(
+    get,
+    path = "/trading/status",
+    tag = "trading",
+    responses(
+        (status = 200, description = "Engine status", body = TradingStatus),
+    )
+)]
 pub async fn get_status(State(state): State<AppState>) -> ApiResult<Json<TradingStatus>> {
     let status = state.trading_engine.get_status().await;
 
     Ok(Json(status))
 }
 
+/// Which instance holds the trading lock
+#[utoipa::path(
+    get,
+    path = "/trading/lock",
+    tag = "trading",
+    responses(
+        (status = 200, description = "Trading lock holder", body = LockStatus),
+    )
+)]
+pub async fn get_lock(State(state): State<AppState>) -> ApiResult<Json<LockStatus>> {
+    let lease = state
+        .db
+        .get_trading_lock()
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(LockStatus::new(
+        state.trading_engine.trading_lock(),
+        lease,
+        Utc::now(),
+    )))
+}
+
 /// Get current trading configuration
+#[utoipa::path(
+    get,
+    path = "/trading/config",
+    tag = "trading",
+    responses(
+        (status = 200, description = "Active trading configuration", body = TradingConfig),
+    )
+)]
 pub async fn get_config(State(state): State<AppState>) -> ApiResult<Json<TradingConfig>> {
     let config = state.trading_engine.config.get();
     Ok(Json(config))
 }
 
 /// Update trading configuration
+#[utoipa::path(
+    put,
+    path = "/trading/config",
+    tag = "trading",
+    request_body = TradingConfig,
+    responses(
+        (status = 200, description = "Configuration applied", body = TradingConfig),
+        (status = 400, description = "Invalid configuration", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn update_config(
+    OperatorAuth(caller): OperatorAuth,
     State(state): State<AppState>,
     Json(new_config): Json<TradingConfig>,
 ) -> ApiResult<Json<TradingConfig>> {
@@ -47,16 +146,132 @@ pub async fn update_config(
         .update(new_config.clone())
         .map_err(|e| ApiError::BadRequest(e))?;
 
-    tracing::info!("Trading configuration updated: {:?}", new_config);
+    tracing::info!(
+        "Trading configuration updated by {}: {:?}",
+        caller.name(),
+        new_config
+    );
     Ok(Json(new_config))
 }
 
+/// Change some fields of the trading configuration
+///
+/// Takes a JSON object of the fields to change, keyed as in the config, and
+/// leaves every other field as it is now, so concurrent edits of other fields
+/// aren't overwritten.
+#[utoipa::path(
+    patch,
+    path = "/trading/config",
+    tag = "trading",
+    request_body(content = Object, description = "Fields to change and their new values"),
+    responses(
+        (status = 200, description = "Configuration applied", body = TradingConfig),
+        (status = 400, description = "Unknown field or invalid configuration", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn patch_config(
+    OperatorAuth(caller): OperatorAuth,
+    State(state): State<AppState>,
+    Json(changes): Json<serde_json::Map<String, serde_json::Value>>,
+) -> ApiResult<Json<TradingConfig>> {
+    let config = state
+        .trading_engine
+        .config
+        .patch(&changes)
+        .map_err(ApiError::BadRequest)?;
+
+    tracing::info!(
+        "Trading configuration changed by {}: {:?}",
+        caller.name(),
+        changes
+    );
+    Ok(Json(config))
+}
+
+/// Preview what the engine would do right now under a candidate configuration
+///
+/// Uses live wallet balances and the Kraken ticker; the candidate is not applied.
+#[utoipa::path(
+    post,
+    path = "/trading/preview",
+    tag = "trading",
+    request_body = TradingConfig,
+    responses(
+        (status = 200, description = "What the engine would do with this configuration", body = RebalancePreview),
+        (status = 400, description = "Invalid configuration", body = ErrorResponse),
+    )
+)]
+pub async fn preview_config(
+    State(state): State<AppState>,
+    Json(candidate): Json<TradingConfig>,
+) -> ApiResult<Json<RebalancePreview>> {
+    candidate.validate().map_err(ApiError::BadRequest)?;
+
+    let preview = state
+        .trading_engine
+        .preview_rebalance(&candidate)
+        .await
+        .map_err(ApiError::Internal)?;
+
+    Ok(Json(preview))
+}
+
+/// Estimate the fees of a rebalance buying `xmr_needed` XMR at current rates
+#[utoipa::path(
+    get,
+    path = "/trading/estimate",
+    tag = "trading",
+    params(
+        EstimateQuery,
+    ),
+    responses(
+        (status = 200, description = "Estimated rebalance cost", body = CostEstimate),
+        (status = 400, description = "Invalid amount", body = ErrorResponse),
+    )
+)]
+pub async fn estimate_cost(
+    State(state): State<AppState>,
+    Query(query): Query<EstimateQuery>,
+) -> ApiResult<Json<CostEstimate>> {
+    if !query.xmr_needed.is_positive() {
+        return Err(ApiError::BadRequest(
+            "xmr_needed must be positive".to_string(),
+        ));
+    }
+
+    let estimate = state
+        .trading_engine
+        .estimate_rebalance_cost(query.xmr_needed)
+        .await
+        .map_err(ApiError::Internal)?;
+
+    Ok(Json(estimate))
+}
+
 /// Enable or disable the trading engine
+#[utoipa::path(
+    post,
+    path = "/trading/enable",
+    tag = "trading",
+    request_body = EnableRequest,
+    responses(
+        (status = 200, description = "Engine toggled", body = EnableResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+        (status = 503, description = "Another instance holds the trading lock", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
 pub async fn set_enabled(
+    _operator: OperatorAuth,
     State(state): State<AppState>,
     Json(request): Json<EnableRequest>,
 ) -> ApiResult<Json<EnableResponse>> {
     if request.enabled {
+        if let Some(reason) = state.trading_engine.trading_blocked() {
+            return Err(ApiError::ServiceUnavailable(anyhow::anyhow!(reason)));
+        }
         state.trading_engine.enable();
         tracing::info!("Trading engine enabled via API");
     } else {
@@ -70,11 +285,268 @@ pub async fn set_enabled(
     }))
 }
 
+/// Start an emergency conversion, bypassing the rebalance thresholds
+///
+/// Disables automatic trading and returns the sized plan; the conversion
+/// itself continues in the background and is visible through `/status`.
+#[utoipa::path(
+    post,
+    path = "/trading/emergency-convert",
+    tag = "trading",
+    request_body = EmergencyConvertRequest,
+    responses(
+        (status = 202, description = "Conversion started", body = EmergencyPlan),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn emergency_convert(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Json(request): Json<EmergencyConvertRequest>,
+) -> ApiResult<(StatusCode, Json<EmergencyPlan>)> {
+    emergency::verify_confirmation(request.direction, &request.confirmation)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    let plan = state
+        .trading_engine
+        .start_emergency_conversion(request.direction, &request.confirmation)
+        .await
+        .map_err(ApiError::Internal)?;
+
+    Ok((StatusCode::ACCEPTED, Json(plan)))
+}
+
+/// Cancel every open Kraken order
+///
+/// Pauses and disables automatic trading before cancelling so the engine
+/// can't place a new order in the meantime; a rebalance in progress holds
+/// before its next step until `POST /trading/resume`.
+#[utoipa::path(
+    post,
+    path = "/trading/cancel-all",
+    tag = "trading",
+    responses(
+        (status = 200, description = "Open orders cancelled and engine paused and disabled", body = CancelAllSummary),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn cancel_all(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<CancelAllSummary>> {
+    let summary = state
+        .trading_engine
+        .cancel_all_orders()
+        .await
+        .map_err(ApiError::Internal)?;
+
+    Ok(Json(summary))
+}
+
+/// Pause the engine after its current step
+#[utoipa::path(
+    post,
+    path = "/trading/pause",
+    tag = "trading",
+    responses(
+        (status = 200, description = "Engine paused", body = ControlResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn pause(_admin: AdminAuth, State(state): State<AppState>) -> Json<ControlResponse> {
+    let engine_state = state.trading_engine.pause();
+    tracing::info!("Trading engine paused via API");
+
+    Json(ControlResponse {
+        paused: true,
+        state: engine_state,
+        step_override: None,
+    })
+}
+
+/// Resume a paused engine
+#[utoipa::path(
+    post,
+    path = "/trading/resume",
+    tag = "trading",
+    responses(
+        (status = 200, description = "Engine resumed", body = ControlResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn resume(_admin: AdminAuth, State(state): State<AppState>) -> Json<ControlResponse> {
+    let engine_state = state.trading_engine.resume();
+    tracing::info!("Trading engine resumed via API");
+
+    Json(ControlResponse {
+        paused: false,
+        state: engine_state,
+        step_override: None,
+    })
+}
+
+/// Abandon the deposit, trade or withdrawal the engine is stuck on
+#[utoipa::path(
+    post,
+    path = "/trading/step/skip",
+    tag = "trading",
+    responses(
+        (status = 200, description = "Current step skipped", body = ControlResponse),
+        (status = 400, description = "No step is waiting", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn skip_step(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ControlResponse>> {
+    override_step(&state, StepOverride::Skip)
+}
+
+/// Treat the awaited deposit, trade or withdrawal as done and continue
+#[utoipa::path(
+    post,
+    path = "/trading/step/complete",
+    tag = "trading",
+    responses(
+        (status = 200, description = "Current step marked complete", body = ControlResponse),
+        (status = 400, description = "No step is waiting", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn complete_step(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ControlResponse>> {
+    override_step(&state, StepOverride::Complete)
+}
+
+fn override_step(
+    state: &AppState,
+    step_override: StepOverride,
+) -> ApiResult<Json<ControlResponse>> {
+    let engine_state = state
+        .trading_engine
+        .override_step(step_override)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    Ok(Json(ControlResponse {
+        paused: state.trading_engine.is_paused(),
+        state: engine_state,
+        step_override: Some(step_override),
+    }))
+}
+
+/// List the most recent trading transactions, newest first
+#[utoipa::path(
+    get,
+    path = "/trading/transactions",
+    tag = "trading",
+    params(
+        TransactionsQuery,
+    ),
+    responses(
+        (status = 200, description = "Trading transactions, newest first", body = Vec<StoredTradingTransaction>),
+    )
+)]
+pub async fn list_transactions(
+    State(state): State<AppState>,
+    Query(query): Query<TransactionsQuery>,
+) -> ApiResult<Json<Vec<StoredTradingTransaction>>> {
+    let limit = query.limit.unwrap_or(20).min(MAX_TRANSACTIONS);
+    let transactions = state
+        .db
+        .get_recent_trading_transactions(limit)
+        .await
+        .map_err(ApiError::Database)?;
+
+    Ok(Json(transactions))
+}
+
+/// Download the trading transaction history as CSV or NDJSON
+#[utoipa::path(
+    get,
+    path = "/trading/transactions/export",
+    tag = "trading",
+    params(
+        ExportQuery,
+    ),
+    responses(
+        (status = 200, description = "Transactions as CSV or NDJSON", content((String = "text/csv"), (String = "application/x-ndjson"))),
+        (status = 400, description = "Invalid range", body = ErrorResponse),
+    )
+)]
+pub async fn export_transactions(
+    State(state): State<AppState>,
+    Query(query): Query<ExportQuery>,
+) -> ApiResult<Response> {
+    let (from, to) = query.window(Utc::now());
+    export::download::<StoredTradingTransaction>(
+        state.db,
+        "trading_transactions",
+        "trading-transactions",
+        query.format,
+        from,
+        to,
+    )
+    .await
+}
+
+/// Costs of each rebalance in the window and their running total
+#[utoipa::path(
+    get,
+    path = "/trading/pnl",
+    tag = "trading",
+    params(
+        PnlQuery,
+    ),
+    responses(
+        (status = 200, description = "Profit and loss per rebalance cycle", body = PnlReport),
+        (status = 400, description = "Invalid range", body = ErrorResponse),
+    )
+)]
+pub async fn get_pnl(
+    State(state): State<AppState>,
+    Query(query): Query<PnlQuery>,
+) -> ApiResult<Json<PnlReport>> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or_else(|| to - Duration::days(30));
+    if from > to {
+        return Err(ApiError::BadRequest(
+            "from must not be after to".to_string(),
+        ));
+    }
+
+    let transactions = state.db.get_trading_transactions(from, to).await?;
+    Ok(Json(PnlReport::new(from, to, &transactions)))
+}
+
 /// Create the trading engine routes router
 pub fn trading_routes() -> Router<AppState> {
     Router::new()
         .route("/status", get(get_status))
-        .route("/config", get(get_config))
-        .route("/config", put(update_config))
+        .route("/lock", get(get_lock))
+        .route(
+            "/config",
+            get(get_config).put(update_config).patch(patch_config),
+        )
+        .route("/preview", post(preview_config))
+        .route("/estimate", get(estimate_cost))
         .route("/enable", post(set_enabled))
+        .route("/emergency-convert", post(emergency_convert))
+        .route("/cancel-all", post(cancel_all))
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .route("/step/skip", post(skip_step))
+        .route("/step/complete", post(complete_step))
+        .route("/transactions", get(list_transactions))
+        .route("/transactions/export", get(export_transactions))
+        .route("/pnl", get(get_pnl))
 }
@@ -1,29 +1,34 @@
-use axum::{extract::State, routing::get, Json, Router};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
 use serde::Serialize;
+use std::path::PathBuf;
+use utoipa::ToSchema;
 
-use crate::routes::{bitcoin, monero};
-use crate::{ApiError, ApiResult, AppState};
-
-/// Combined wallet balances response
-#[derive(Serialize)]
-pub struct WalletBalances {
-    /// Bitcoin balance in BTC
-    bitcoin: f64,
-    /// Monero balance in XMR
-    monero: f64,
-}
+pub use eigenix_common::wallets::{WalletBalances, WalletHealth};
 
-/// Wallet health status response
-#[derive(Serialize)]
-pub struct WalletHealth {
-    /// Whether wallets are healthy and operational
-    healthy: bool,
-    /// Individual wallet health status
-    bitcoin_ready: bool,
-    monero_ready: bool,
-}
+use crate::approvals::{self, Approval, ApprovalAction};
+use crate::auth::AdminAuth;
+use crate::db::StoredTradingTransaction;
+use crate::routes::{bitcoin, monero};
+use crate::wallets::backup;
+use crate::wallets::limits::LimitExceeded;
+use crate::wallets::transfer::{self, TransferAsset, TransferRequest};
+use crate::{ApiError, ApiResult, AppState, ErrorResponse};
 
 /// Get combined balances for both Bitcoin and Monero wallets
+#[utoipa::path(
+    get,
+    path = "/wallets/balances",
+    tag = "wallets",
+    responses(
+        (status = 200, description = "Balances of both wallets", body = WalletBalances),
+    )
+)]
 pub async fn get_balances(State(state): State<AppState>) -> ApiResult<Json<WalletBalances>> {
     let (bitcoin, monero) = state
         .wallets
@@ -35,6 +40,14 @@ pub async fn get_balances(State(state): State<AppState>) -> ApiResult<Json<Walle
 }
 
 /// Check wallet health status
+#[utoipa::path(
+    get,
+    path = "/wallets/health",
+    tag = "wallets",
+    responses(
+        (status = 200, description = "Health of both wallets", body = WalletHealth),
+    )
+)]
 pub async fn get_wallet_health(State(state): State<AppState>) -> ApiResult<Json<WalletHealth>> {
     let healthy = state.wallets.is_healthy().await;
     let bitcoin_ready = state.wallets.bitcoin.is_ready().await;
@@ -47,11 +60,170 @@ pub async fn get_wallet_health(State(state): State<AppState>) -> ApiResult<Json<
     }))
 }
 
+/// Move funds out of a managed wallet and record it as an internal transfer
+///
+/// Transfers above the approval threshold are held and answered with
+/// `202 Accepted` and the pending approval instead.
+#[utoipa::path(
+    post,
+    path = "/wallets/transfer",
+    tag = "wallets",
+    request_body = TransferRequest,
+    responses(
+        (status = 201, description = "Transfer broadcast", body = StoredTradingTransaction),
+        (status = 202, description = "Held for approval", body = Approval),
+        (status = 400, description = "Invalid transfer", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn transfer(
+    AdminAuth(caller): AdminAuth,
+    State(state): State<AppState>,
+    Json(request): Json<TransferRequest>,
+) -> ApiResult<Response> {
+    check_destination(&state, &request)?;
+    let balance_before = state
+        .wallets
+        .prepare_transfer(&request)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("{:#}", e)))?;
+
+    let (btc, xmr) = match request.asset {
+        TransferAsset::Btc => (request.amount, 0.0),
+        TransferAsset::Xmr => (0.0, request.amount),
+    };
+    let action = ApprovalAction::Transfer {
+        request: request.clone(),
+    };
+    if let Some(held) = approvals::hold_if_large(&state, &caller, action, btc, xmr).await? {
+        return Ok(held);
+    }
+
+    let record = execute_transfer(&state, &request, balance_before).await?;
+    Ok((StatusCode::CREATED, Json(record)).into_response())
+}
+
+/// Check and run an approved transfer against the balance as it is now
+pub(crate) async fn run_transfer(
+    state: &AppState,
+    request: &TransferRequest,
+) -> ApiResult<StoredTradingTransaction> {
+    check_destination(state, request)?;
+    let balance_before = state
+        .wallets
+        .prepare_transfer(request)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("{:#}", e)))?;
+
+    execute_transfer(state, request, balance_before).await
+}
+
+/// Refuse destinations off the wallet's network or, when
+/// `wallets.transfer_allowlist` is set, not on it
+fn check_destination(state: &AppState, request: &TransferRequest) -> ApiResult<()> {
+    let config = state.config.get();
+    let network = match request.asset {
+        TransferAsset::Btc => &config.bitcoin.network,
+        TransferAsset::Xmr => &config.monero.network,
+    };
+    transfer::check_destination(
+        request.asset,
+        &request.to_address,
+        network,
+        &config.wallets.transfer_allowlist,
+    )
+    .map_err(|e| ApiError::BadRequest(format!("{:#}", e)))
+}
+
+/// Run a checked transfer and record it
+async fn execute_transfer(
+    state: &AppState,
+    request: &TransferRequest,
+    balance_before: f64,
+) -> ApiResult<StoredTradingTransaction> {
+    let mut record = state
+        .wallets
+        .execute_transfer(request, balance_before)
+        .await
+        .map_err(wallet_error)?;
+
+    // The funds have moved at this point; a failed write only loses the record
+    let id = state
+        .db
+        .store_trading_transaction(&record)
+        .await
+        .map_err(ApiError::Database)?;
+    record.id = Some(id);
+
+    Ok(record)
+}
+
+/// Response of `POST /wallets/backup`
+#[derive(Serialize, ToSchema)]
+pub struct BackupResponse {
+    /// File the encrypted backup was written to
+    #[schema(value_type = String)]
+    path: PathBuf,
+}
+
+/// Error of a send through the wallet manager: 403 when a spend limit
+/// refused it, a wallet error otherwise
+pub(crate) fn wallet_error(e: anyhow::Error) -> ApiError {
+    if e.downcast_ref::<LimitExceeded>().is_some() {
+        return ApiError::Forbidden(e.to_string());
+    }
+    ApiError::Wallet(e)
+}
+
+/// Refuse key and descriptor exports unless `wallets.allow_key_export` is set
+pub(crate) fn ensure_key_export(state: &AppState) -> ApiResult<()> {
+    if !state.config.get().wallets.allow_key_export {
+        return Err(ApiError::Forbidden(
+            "Key export is disabled; set wallets.allow_key_export to enable it".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Write an encrypted backup of both wallets now, outside the schedule
+#[utoipa::path(
+    post,
+    path = "/wallets/backup",
+    tag = "wallets",
+    responses(
+        (status = 201, description = "Backup written", body = BackupResponse),
+        (status = 400, description = "Backups are not configured", body = ErrorResponse),
+        (status = 403, description = "Caller lacks the required role", body = ErrorResponse),
+    ),
+    security(("bearer" = []))
+)]
+pub async fn backup_wallets(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<(StatusCode, Json<BackupResponse>)> {
+    let config = state.config.get();
+    if config.backup.target().is_none() {
+        return Err(ApiError::BadRequest(
+            "Backups need [backup] dir and EIGENIX_BACKUP_PASSPHRASE".to_string(),
+        ));
+    }
+
+    let path = backup::backup_now(&state.wallets, &config.backup)
+        .await
+        .map_err(ApiError::Wallet)?;
+    tracing::info!("Wrote wallet backup {}", path.display());
+
+    Ok((StatusCode::CREATED, Json(BackupResponse { path })))
+}
+
 /// Create the wallet routes router
 pub fn wallet_routes() -> Router<AppState> {
     Router::new()
         .route("/balances", get(get_balances))
         .route("/health", get(get_wallet_health))
+        .route("/transfer", post(transfer))
+        .route("/backup", post(backup_wallets))
         .nest("/bitcoin", bitcoin::bitcoin_routes())
         .nest("/monero", monero::monero_routes())
 }
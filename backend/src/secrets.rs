@@ -0,0 +1,235 @@
+//! Keeping secrets out of the config file
+//!
+//! Two mechanisms, usable together:
+//!
+//! - Any string in the config can reference an environment variable as
+//!   `${NAME}`, expanded when the config is loaded. An unset variable is an
+//!   error rather than an empty string, so a missing secret fails startup
+//!   instead of silently disabling a feature. `$${` stands for a literal `${`.
+//! - A secrets file, a TOML file with the same layout as the config, is
+//!   merged over it. It may be encrypted with `--encrypt-secrets`, in which
+//!   case `EIGENIX_SECRETS_PASSPHRASE` decrypts it at load.
+
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+use crate::crypto;
+
+/// Identifies an encrypted secrets file
+const MAGIC: &[u8; 8] = b"EGXSEC1\0";
+
+/// Environment variable holding the secrets file passphrase
+pub const PASSPHRASE_ENV: &str = "EIGENIX_SECRETS_PASSPHRASE";
+
+/// Expand `${NAME}` references in every string of `value` from the environment
+pub fn expand_env(value: &mut Value) -> Result<()> {
+    expand_with(value, &|name| std::env::var(name).ok())
+}
+
+fn expand_with(value: &mut Value, lookup: &dyn Fn(&str) -> Option<String>) -> Result<()> {
+    match value {
+        Value::String(s) => *s = expand_str(s, lookup)?,
+        Value::Array(items) => {
+            for item in items {
+                expand_with(item, lookup)?;
+            }
+        }
+        Value::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                expand_with(item, lookup).with_context(|| format!("In `{}`", key))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand_str(s: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start..];
+
+        if let Some(escaped) = after.strip_prefix("$${") {
+            out.push_str("${");
+            rest = escaped;
+        } else if let Some(reference) = after.strip_prefix("${") {
+            let end = reference
+                .find('}')
+                .context("Unterminated `${` in config value")?;
+            let name = &reference[..end];
+            let value = lookup(name).with_context(|| {
+                format!(
+                    "Environment variable {} referenced in the config is not set",
+                    name
+                )
+            })?;
+            out.push_str(&value);
+            rest = &reference[end + 1..];
+        } else {
+            out.push('$');
+            rest = &after[1..];
+        }
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Merge `overlay` into `base`, recursing into tables present in both
+pub fn merge(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => merge(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Read a secrets file, decrypting it with `passphrase` if it was encrypted
+pub fn load_file(path: &Path, passphrase: Option<&str>) -> Result<Table> {
+    let data = std::fs::read(path)
+        .with_context(|| format!("Failed to read secrets file {}", path.display()))?;
+
+    let text = if crypto::is_sealed(MAGIC, &data) {
+        let passphrase = passphrase.with_context(|| {
+            format!(
+                "{} is encrypted but {} is not set",
+                path.display(),
+                PASSPHRASE_ENV
+            )
+        })?;
+        let plaintext = crypto::open(MAGIC, passphrase, &data)
+            .with_context(|| format!("Failed to decrypt {}", path.display()))?;
+        String::from_utf8(plaintext).context("Secrets file is not UTF-8")?
+    } else {
+        String::from_utf8(data).context("Secrets file is not UTF-8")?
+    };
+
+    toml::from_str(&text)
+        .with_context(|| format!("Failed to parse secrets file {}", path.display()))
+}
+
+/// Encrypt a plaintext secrets file with the passphrase from
+/// `EIGENIX_SECRETS_PASSPHRASE`, writing it next to the original with `.enc`
+/// appended
+///
+/// # Returns
+/// The path of the encrypted file
+pub fn encrypt_file(path: &Path) -> Result<PathBuf> {
+    let passphrase =
+        std::env::var(PASSPHRASE_ENV).with_context(|| format!("{} is not set", PASSPHRASE_ENV))?;
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    // Catch mistakes now rather than at the next startup
+    toml::from_str::<Table>(&text)
+        .with_context(|| format!("{} is not a valid secrets file", path.display()))?;
+
+    let mut sealed_path = OsString::from(path.as_os_str());
+    sealed_path.push(".enc");
+    let sealed_path = PathBuf::from(sealed_path);
+
+    crypto::write_private(
+        &sealed_path,
+        &crypto::seal(MAGIC, &passphrase, text.as_bytes())?,
+    )?;
+    Ok(sealed_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "KRAKEN_API_KEY" => Some("k3y".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand_env_references() {
+        assert_eq!(expand_str("${KRAKEN_API_KEY}", &lookup).unwrap(), "k3y");
+        assert_eq!(
+            expand_str("key=${KRAKEN_API_KEY};${EMPTY}.", &lookup).unwrap(),
+            "key=k3y;."
+        );
+        assert_eq!(
+            expand_str("$${NOT_EXPANDED}", &lookup).unwrap(),
+            "${NOT_EXPANDED}"
+        );
+        assert_eq!(expand_str("costs $5", &lookup).unwrap(), "costs $5");
+
+        assert!(expand_str("${UNSET}", &lookup).is_err());
+        assert!(expand_str("${KRAKEN_API_KEY", &lookup).is_err());
+    }
+
+    #[test]
+    fn test_secrets_merge_over_config() {
+        let mut config: Table = toml::from_str(
+            r#"
+            [kraken]
+            api_key = "${KRAKEN_API_KEY}"
+            api_secret = ""
+
+            [wallets]
+            monero_wallet_name = "eigenix"
+            "#,
+        )
+        .unwrap();
+        let secrets: Table = toml::from_str(
+            r#"
+            [kraken]
+            api_secret = "s3cret"
+
+            [wallets]
+            monero_wallet_password = "hunter2"
+            "#,
+        )
+        .unwrap();
+
+        merge(&mut config, secrets);
+        let mut config = Value::Table(config);
+        expand_with(&mut config, &lookup).unwrap();
+
+        assert_eq!(config["kraken"]["api_key"].as_str(), Some("k3y"));
+        assert_eq!(config["kraken"]["api_secret"].as_str(), Some("s3cret"));
+        assert_eq!(
+            config["wallets"]["monero_wallet_name"].as_str(),
+            Some("eigenix")
+        );
+        assert_eq!(
+            config["wallets"]["monero_wallet_password"].as_str(),
+            Some("hunter2")
+        );
+    }
+
+    #[test]
+    fn test_encrypted_secrets_file() {
+        let plain =
+            std::env::temp_dir().join(format!("eigenix-secrets-{}.toml", std::process::id()));
+        std::fs::write(&plain, "[kraken]\napi_secret = \"s3cret\"\n").unwrap();
+        let sealed = plain.with_extension("toml.enc");
+        std::fs::write(
+            &sealed,
+            crypto::seal(MAGIC, "passphrase", &std::fs::read(&plain).unwrap()).unwrap(),
+        )
+        .unwrap();
+
+        let expected = load_file(&plain, None).unwrap();
+        assert_eq!(expected["kraken"]["api_secret"].as_str(), Some("s3cret"));
+        assert_eq!(load_file(&sealed, Some("passphrase")).unwrap(), expected);
+        assert!(load_file(&sealed, Some("wrong")).is_err());
+        assert!(load_file(&sealed, None).is_err());
+
+        let _ = std::fs::remove_file(&plain);
+        let _ = std::fs::remove_file(&sealed);
+    }
+}
@@ -0,0 +1,302 @@
+//! Read-only startup self-test
+//!
+//! Exercises every external integration the backend depends on and collects
+//! the results into a [`SelftestReport`]. Nothing here moves funds or writes
+//! to the database.
+
+use anyhow::{Context, Result};
+use std::future::Future;
+use tokio::time::{timeout, Duration};
+
+use crate::config::{Config, DatabaseMode};
+use crate::db::MetricsDatabase;
+use crate::services::{AsbClient, BitcoinRpcClient, KrakenClient, MoneroRpcClient};
+use crate::wallets::MoneroWallet;
+
+/// Maximum time a single check may take before it is reported as failed
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Verification progress at which bitcoind is considered synced
+const BITCOIN_SYNCED_PROGRESS: f64 = 0.9999;
+
+/// Outcome of a single self-test check
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: String,
+    /// Whether a failure of this check should fail the whole self-test
+    pub critical: bool,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl CheckResult {
+    pub fn pass(name: &str, critical: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            critical,
+            passed: true,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn fail(name: &str, critical: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            critical,
+            passed: false,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Aggregated self-test results
+#[derive(Debug, Clone, Default)]
+pub struct SelftestReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl SelftestReport {
+    /// True when no critical check failed
+    pub fn passed(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.passed || !check.critical)
+    }
+
+    /// Critical checks that failed
+    pub fn critical_failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks
+            .iter()
+            .filter(|check| check.critical && !check.passed)
+    }
+
+    /// Process exit status for the report: 0 when all critical checks pass, 1 otherwise
+    pub fn exit_code(&self) -> i32 {
+        if self.passed() {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Render the report as a plain-text pass/fail table
+    pub fn render_table(&self) -> String {
+        let name_width = self
+            .checks
+            .iter()
+            .map(|check| check.name.len())
+            .max()
+            .unwrap_or(5)
+            .max(5);
+
+        let mut out = format!("{:<name_width$}  {:<6}  DETAIL\n", "CHECK", "RESULT");
+        for check in &self.checks {
+            let result = match (check.passed, check.critical) {
+                (true, _) => "PASS",
+                (false, true) => "FAIL",
+                (false, false) => "WARN",
+            };
+            out.push_str(&format!(
+                "{:<name_width$}  {:<6}  {}\n",
+                check.name, result, check.detail
+            ));
+        }
+
+        out
+    }
+}
+
+/// Run a check with a timeout, turning errors into a failed result
+async fn check<F>(name: &str, critical: bool, fut: F) -> CheckResult
+where
+    F: Future<Output = Result<String>>,
+{
+    match timeout(CHECK_TIMEOUT, fut).await {
+        Ok(Ok(detail)) => CheckResult::pass(name, critical, detail),
+        Ok(Err(e)) => CheckResult::fail(name, critical, format!("{:#}", e)),
+        Err(_) => CheckResult::fail(
+            name,
+            critical,
+            format!("timed out after {}s", CHECK_TIMEOUT.as_secs()),
+        ),
+    }
+}
+
+/// Run every self-test check against the given configuration
+pub async fn run_selftest(config: &Config) -> SelftestReport {
+    let mut report = SelftestReport::default();
+
+    report.checks.push(match config.validate() {
+        Ok(()) => CheckResult::pass("config", true, "valid"),
+        Err(e) => CheckResult::fail("config", true, format!("{:#}", e)),
+    });
+
+    report.checks.push(
+        check("database", true, async {
+            MetricsDatabase::open(&config.database).await?;
+            Ok(match config.database.mode {
+                DatabaseMode::Remote => format!("connected to {}", config.database.endpoint),
+                DatabaseMode::Embedded => "embedded".to_string(),
+            })
+        })
+        .await,
+    );
+
+    report.checks.push(
+        check("bitcoind", true, async {
+            let client =
+                BitcoinRpcClient::new(config.bitcoin.rpc_url.clone(), &config.bitcoin.cookie_path)?;
+            let metrics = client.get_metrics().await?;
+            let detail = format!(
+                "height {}/{}, {:.2}% verified",
+                metrics.blocks,
+                metrics.headers,
+                metrics.verification_progress * 100.0
+            );
+            if metrics.verification_progress < BITCOIN_SYNCED_PROGRESS {
+                anyhow::bail!("not synced: {}", detail);
+            }
+            Ok(detail)
+        })
+        .await,
+    );
+
+    report.checks.push(
+        check("monerod", true, async {
+            let metrics = MoneroRpcClient::new(config.monero.rpc_url.clone())
+                .get_metrics()
+                .await?;
+            let detail = format!("height {}/{}", metrics.height, metrics.target_height);
+            if metrics.target_height > metrics.height {
+                anyhow::bail!("not synced: {}", detail);
+            }
+            Ok(detail)
+        })
+        .await,
+    );
+
+    report.checks.push(
+        check("monero-wallet", true, async {
+            let wallet = MoneroWallet::connect_existing(
+                config.wallets.monero_wallet_rpc_url.clone(),
+                &config.wallets.monero_wallet_name,
+                &config.wallets.monero_wallet_password,
+            )
+            .await?;
+            let height = wallet.get_height().await?;
+            Ok(format!(
+                "wallet '{}' open at height {}",
+                config.wallets.monero_wallet_name, height
+            ))
+        })
+        .await,
+    );
+
+    report.checks.push(
+        check("asb", true, async {
+            AsbClient::new(config.asb.rpc_url.clone())
+                .check_connection()
+                .await?;
+            Ok(format!("reachable at {}", config.asb.rpc_url))
+        })
+        .await,
+    );
+
+    report.checks.push(
+        check("kraken-ticker", false, async {
+            let ticker = KrakenClient::public().get_ticker("XBTXMR").await?;
+            let last = ticker.last_trade.first().context("empty ticker")?;
+            Ok(format!("XBTXMR last {}", last))
+        })
+        .await,
+    );
+
+    if config.kraken.read_key().is_none() && config.kraken.trade_key().is_none() {
+        report.checks.push(CheckResult::pass(
+            "kraken-auth",
+            false,
+            "skipped (no credentials configured)",
+        ));
+    } else {
+        report.checks.push(
+            check("kraken-auth", true, async {
+                let balances = config.kraken.client().get_balance().await?;
+                Ok(format!("authenticated, {} asset balances", balances.len()))
+            })
+            .await,
+        );
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_passes_when_all_critical_checks_pass() {
+        let report = SelftestReport {
+            checks: vec![
+                CheckResult::pass("config", true, "valid"),
+                CheckResult::pass("database", true, "connected"),
+                // Non-critical failures only warn
+                CheckResult::fail("kraken-ticker", false, "timed out"),
+            ],
+        };
+
+        assert!(report.passed());
+        assert_eq!(report.exit_code(), 0);
+        assert_eq!(report.critical_failures().count(), 0);
+    }
+
+    #[test]
+    fn test_report_fails_on_any_critical_failure() {
+        let report = SelftestReport {
+            checks: vec![
+                CheckResult::pass("config", true, "valid"),
+                CheckResult::fail("bitcoind", true, "connection refused"),
+                CheckResult::fail("asb", true, "connection refused"),
+                CheckResult::pass("kraken-ticker", false, "ok"),
+            ],
+        };
+
+        assert!(!report.passed());
+        assert_eq!(report.exit_code(), 1);
+
+        let failed: Vec<&str> = report
+            .critical_failures()
+            .map(|check| check.name.as_str())
+            .collect();
+        assert_eq!(failed, vec!["bitcoind", "asb"]);
+    }
+
+    #[test]
+    fn test_report_table_lists_every_check() {
+        let report = SelftestReport {
+            checks: vec![
+                CheckResult::pass("config", true, "valid"),
+                CheckResult::fail("bitcoind", true, "connection refused"),
+                CheckResult::fail("kraken-ticker", false, "timed out"),
+            ],
+        };
+
+        let table = report.render_table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].contains("config") && lines[1].contains("PASS"));
+        assert!(lines[2].contains("bitcoind") && lines[2].contains("FAIL"));
+        assert!(lines[3].contains("kraken-ticker") && lines[3].contains("WARN"));
+    }
+
+    #[tokio::test]
+    async fn test_check_converts_errors_to_failures() {
+        let ok = check("ok", true, async { Ok("fine".to_string()) }).await;
+        assert!(ok.passed);
+        assert_eq!(ok.detail, "fine");
+
+        let failed = check("broken", true, async { anyhow::bail!("boom") }).await;
+        assert!(!failed.passed);
+        assert!(failed.detail.contains("boom"));
+    }
+}
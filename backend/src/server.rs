@@ -0,0 +1,441 @@
+//! The API server
+//!
+//! [`run`] is everything the backend does between loading its config and
+//! exiting: it connects to the database, starts the collectors and the
+//! trading engine, and serves the API until SIGTERM or Ctrl-C. The
+//! `eigenix-backend` binary and `eigenix server` both call it.
+
+use anyhow::Context;
+use axum::{middleware, routing::get, Router};
+use axum_server::tls_rustls::RustlsConfig;
+use std::{net::SocketAddr, sync::Arc};
+use tower_http::cors::{Any, CorsLayer};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::{
+    alerts::{AlertNotifier, ClockSkewMonitor},
+    asb_log, auth,
+    config::{Cli, Config, DatabaseMode, SharedConfig},
+    db::{prices::PriceCache, MetricsDatabase},
+    health, idempotency,
+    metrics::{backend as backend_metrics, ContainerHealthClient, MetricsCollector},
+    openapi::ApiDoc,
+    portfolio,
+    rate_limit::{self, RateLimiter},
+    reload, request_id, routes,
+    services::{breaker, retry, FeeEstimator, KrakenClient},
+    shutdown::Shutdown,
+    stream,
+    telemetry::Telemetry,
+    tls,
+    trading::{
+        config::SharedTradingConfig,
+        lock::{default_holder, TradingLock},
+        TradingEngine,
+    },
+    wallets::{backup, limits::SpendLimiter, WalletManager},
+    AppState,
+};
+
+/// Load the config `cli` points at and run the backend until it is stopped
+///
+/// Span export is started on `telemetry` once the config is loaded, and
+/// flushed before returning.
+pub async fn run(cli: Cli, telemetry: &Telemetry) -> anyhow::Result<()> {
+    // Starts the uptime the backend metrics report
+    backend_metrics::global();
+
+    let shared_config = SharedConfig::new(Config::load(cli.clone())?);
+    let config = shared_config.get();
+    let shutdown = Shutdown::new();
+
+    tracing::info!("Configuration loaded: {:?}", config);
+    retry::configure(config.retry.clone());
+    breaker::global().configure(config.circuit_breaker.clone());
+    telemetry.export(&config.tracing)?;
+
+    // Connect to SurrealDB
+    match config.database.mode {
+        DatabaseMode::Remote => {
+            tracing::info!("Connecting to SurrealDB at {}", config.database.endpoint)
+        }
+        DatabaseMode::Embedded => tracing::info!(
+            "Opening embedded SurrealDB at {}",
+            config.database.path.clone().unwrap_or_default().display()
+        ),
+    }
+    let db = MetricsDatabase::open(&config.database).await?;
+    tracing::info!("Connected to SurrealDB");
+
+    let migrations = db
+        .run_migrations()
+        .await
+        .context("Failed to migrate the database schema")?;
+    tracing::info!(
+        "Database schema at version {} (applied {:?})",
+        migrations.to_version,
+        migrations.applied
+    );
+
+    // Initialize wallets from ASB
+    tracing::info!("Initializing wallets...");
+    let wallet_config = config.to_wallet_config();
+    let spend_limits = SpendLimiter::new(config.limits.clone(), db.clone());
+    let wallets = WalletManager::initialize_or_connect(wallet_config)
+        .await
+        .context("Failed to initialize wallets")?
+        .with_spend_limits(spend_limits.clone());
+    let wallets = Arc::new(wallets);
+
+    // Log wallet balances
+    match wallets.get_balances().await {
+        Ok((btc, xmr)) => {
+            tracing::info!("Wallet balances - BTC: {:.8}, XMR: {:.12}", btc, xmr);
+        }
+        Err(e) => {
+            tracing::warn!("Failed to get initial wallet balances: {}", e);
+        }
+    }
+
+    // Detect the container runtime once; the collector and the ASB log share it
+    let containers = ContainerHealthClient::new(config.containers.container_runtime);
+    let detecting = containers.clone();
+    match tokio::task::spawn_blocking(move || detecting.select_probe().map(|probe| probe.runtime()))
+        .await
+    {
+        Ok(Ok(runtime)) => tracing::info!("Probing containers through {:?}", runtime),
+        Ok(Err(e)) => tracing::warn!("{:#}", e),
+        Err(e) => tracing::warn!("Detecting the container runtime panicked: {}", e),
+    }
+
+    // Spawn background metrics collection, one task per source
+    let collector = MetricsCollector::new(shared_config.clone(), db.clone())
+        .with_containers(containers.clone())
+        .with_shutdown(shutdown.clone());
+    let collector_task = tokio::spawn(async move {
+        collector.run().await;
+    });
+    tracing::info!("Started background metrics collection");
+
+    // Store the swap events the ASB logs; idle until a log source is configured
+    tokio::spawn(asb_log::ingest(
+        shared_config.clone(),
+        db.clone(),
+        containers,
+        shutdown.clone(),
+    ));
+
+    // Write buffered samples on the flush interval
+    let buffered_db = db.clone();
+    let flusher = buffered_db.clone();
+    let flush_shutdown = shutdown.clone();
+    tokio::spawn(async move { flusher.flush_periodically(flush_shutdown).await });
+
+    // Check the host clock against Kraken's; a skewed clock breaks request signing
+    let clock_skew = ClockSkewMonitor::new(config.alerts.clock_skew_threshold_secs);
+    let skew_monitor = clock_skew.clone();
+    let notifier = AlertNotifier::from_config(&config);
+    tokio::spawn(async move {
+        match skew_monitor.check(&KrakenClient::public()).await {
+            Ok(Some(alert)) => notifier.notify(&alert),
+            Ok(None) => tracing::info!(
+                "Host clock is within {:.1}s of Kraken's",
+                skew_monitor.last().map_or(0.0, |skew| skew.skew_secs.abs())
+            ),
+            Err(e) => tracing::warn!("Failed to check clock skew against Kraken: {:#}", e),
+        }
+    });
+
+    // Scheduled encrypted wallet backups
+    if config.backup.target().is_some() {
+        let backup_wallets = wallets.clone();
+        let backup_config = config.backup.clone();
+        tokio::spawn(async move {
+            backup::run_backups(backup_wallets, backup_config).await;
+        });
+        tracing::info!(
+            "Started wallet backups every {}s",
+            config.backup.interval_secs
+        );
+    } else if config.backup.dir.is_some() {
+        tracing::warn!("[backup] dir is set but EIGENIX_BACKUP_PASSPHRASE is not; backups are off");
+    }
+
+    let fee_estimator = FeeEstimator::new(
+        config.bitcoin.rpc_url.clone(),
+        config.bitcoin.cookie_path.clone(),
+    );
+
+    // Initialize trading engine
+    tracing::info!("Initializing trading engine...");
+    let trading_config = SharedTradingConfig::new(config.initial_trading_config()?);
+    let trading_engine = TradingEngine::new(
+        trading_config,
+        config.exchange_client(),
+        config.bitcoin.rpc_url.clone(),
+        config.bitcoin.cookie_path.clone(),
+        config.wallets.bitcoin_wallet_name.clone(),
+        config.wallets.monero_wallet_rpc_url.clone(),
+        config.wallets.monero_wallet_name.clone(),
+        config.wallets.monero_wallet_password.clone(),
+    )
+    .with_database(db.clone())
+    .with_alert_notifier(AlertNotifier::from_config(&config))
+    .with_fee_estimator(fee_estimator.clone())
+    .with_approvals(shared_config.clone())
+    .with_spend_limits(spend_limits)
+    .with_shutdown(shutdown.clone());
+    let trading_lock = config.trading_lock.enabled.then(|| {
+        TradingLock::new(
+            db.clone(),
+            config
+                .trading_lock
+                .instance_id
+                .clone()
+                .unwrap_or_else(default_holder),
+            std::time::Duration::from_secs(config.trading_lock.lease_secs),
+        )
+    });
+    let trading_engine = match &trading_lock {
+        Some(lock) => trading_engine.with_trading_lock(lock.clone()),
+        None => trading_engine,
+    };
+    let trading_engine = Arc::new(trading_engine);
+
+    // Spawn background trading engine task
+    let trading_engine_clone = (*trading_engine).clone();
+    let trading_task = tokio::spawn(async move {
+        trading_engine_clone.run().await;
+    });
+    tracing::info!("Started background trading engine task (disabled by default)");
+
+    // Reload the config file on SIGHUP
+    tokio::spawn(reload::watch(cli, shared_config.clone()));
+
+    // Create application state
+    let prices = PriceCache::new(db.clone());
+    let state = AppState {
+        config: shared_config,
+        db,
+        wallets,
+        trading_engine,
+        fee_estimator,
+        clock_skew,
+        prices,
+        streams: stream::StreamHub::new(),
+        shutdown: shutdown.clone(),
+    };
+
+    // One reader of the latest samples for every open event stream
+    tokio::spawn(stream::poll_shared(state.clone()));
+
+    // Value the portfolio on a schedule so it can be charted
+    if config.portfolio.snapshot_interval_secs > 0 {
+        let interval = std::time::Duration::from_secs(config.portfolio.snapshot_interval_secs);
+        tokio::spawn(portfolio::record_snapshots(
+            state.clone(),
+            interval,
+            shutdown.clone(),
+        ));
+    }
+
+    // Store the backend's own metrics on a schedule
+    if config.backend_metrics.snapshot_interval_secs > 0 {
+        let interval =
+            std::time::Duration::from_secs(config.backend_metrics.snapshot_interval_secs);
+        tokio::spawn(backend_metrics::record_snapshots(
+            state.db.clone(),
+            interval,
+            shutdown.clone(),
+        ));
+    }
+
+    // Build our application with routes
+    let app = Router::new()
+        .route("/health", get(health::health))
+        .route("/health/live", get(health::live))
+        .nest("/wallets", routes::wallets::wallet_routes())
+        .nest("/bitcoin", routes::bitcoin::bitcoin_node_routes())
+        .nest("/kraken", routes::kraken::kraken_routes())
+        .nest("/limits", routes::limits::limits_routes())
+        .nest("/metrics", routes::metrics::metrics_routes())
+        .nest("/trading", routes::trading::trading_routes())
+        .nest("/admin", routes::admin::admin_routes())
+        .nest("/alerts", routes::alerts::alerts_routes())
+        .nest("/approvals", routes::approvals::approvals_routes())
+        .nest(
+            "/notifications",
+            routes::notifications::notifications_routes(),
+        )
+        .nest("/asb", routes::asb::asb_routes())
+        .nest("/config", routes::config::config_routes())
+        .nest("/preferences", routes::preferences::preferences_routes())
+        .nest("/portfolio", routes::portfolio::portfolio_routes())
+        .nest("/graphql", routes::graphql::graphql_routes())
+        // Innermost, so rejected and rate-limited requests don't claim a key
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            idempotency::replay,
+        ))
+        // Runs after authentication so callers with keys get their own budget
+        .layer(middleware::from_fn_with_state(
+            RateLimiter::new(config.rate_limit.clone()),
+            rate_limit::limit,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.config.clone(),
+            auth::authenticate,
+        ))
+        // Outside authentication, so rejected requests are traced too
+        .layer(middleware::from_fn(request_id::assign))
+        .layer(middleware::from_fn(backend_metrics::track_request))
+        .with_state(state)
+        // The spec is public: served outside authentication and rate limits
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi()))
+        .layer(
+            CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods(Any)
+                .allow_headers(Any)
+                .expose_headers([
+                    routes::metrics::NEXT_CURSOR,
+                    idempotency::IDEMPOTENT_REPLAYED,
+                    request_id::REQUEST_ID,
+                ]),
+        );
+
+    // Serve until SIGTERM or Ctrl-C, then give requests, the trading engine
+    // and the collectors the shutdown timeout to wind down
+    let signal = shutdown.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        signal.trigger();
+    });
+
+    let mut server = tokio::spawn(serve(app, config.clone(), shutdown.clone()));
+    let mut server_done = false;
+    tokio::select! {
+        result = &mut server => {
+            // Only ends before shutdown on an error
+            result??;
+            server_done = true;
+        }
+        _ = shutdown.triggered() => {}
+    }
+    shutdown.trigger();
+
+    let timeout = std::time::Duration::from_secs(config.server.shutdown_timeout_secs);
+    let drained = tokio::time::timeout(timeout, async {
+        if !server_done {
+            match server.await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => tracing::warn!("Server error while shutting down: {:#}", e),
+                Err(e) => tracing::warn!("Server task failed: {}", e),
+            }
+        }
+        let _ = trading_task.await;
+        let _ = collector_task.await;
+        // Nothing writes metrics any more; store what is still queued
+        if let Err(e) = buffered_db.flush().await {
+            tracing::warn!("Failed to flush buffered metrics: {:#}", e);
+        }
+    })
+    .await;
+    if drained.is_err() {
+        tracing::warn!(
+            "Still draining after {}s, exiting anyway",
+            config.server.shutdown_timeout_secs
+        );
+    }
+
+    // Let another instance take over trading right away
+    if let Some(lock) = trading_lock {
+        match lock.release().await {
+            Ok(()) => tracing::info!("Released trading lock"),
+            Err(e) => tracing::warn!("Failed to release trading lock: {:#}", e),
+        }
+    }
+    telemetry.shutdown();
+
+    Ok(())
+}
+
+/// Serve the API until `shutdown` is triggered and in-flight requests finish
+async fn serve(app: Router, config: Arc<Config>, shutdown: Shutdown) -> anyhow::Result<()> {
+    let addr = SocketAddr::from((
+        config.server.host.parse::<std::net::IpAddr>()?,
+        config.server.port,
+    ));
+    match &config.server.tls {
+        Some(tls_config) => {
+            let rustls_config =
+                RustlsConfig::from_config(Arc::new(tls::server_config(tls_config)?));
+            tracing::info!(
+                "Listening on {} (HTTPS{})",
+                addr,
+                if tls_config.mutual() {
+                    ", client certificates required"
+                } else {
+                    ""
+                }
+            );
+
+            let handle = axum_server::Handle::new();
+            let stop = handle.clone();
+            let timeout = std::time::Duration::from_secs(config.server.shutdown_timeout_secs);
+            tokio::spawn(async move {
+                shutdown.triggered().await;
+                stop.graceful_shutdown(Some(timeout));
+            });
+            axum_server::bind_rustls(addr, rustls_config)
+                .handle(handle)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await?;
+        }
+        None => {
+            tracing::info!("Listening on {}", addr);
+
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(async move { shutdown.triggered().await })
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve on Ctrl-C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    tracing::info!("Shutting down");
+}
@@ -1,5 +1,9 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::retry;
+use crate::metrics::clients;
 
 /// ASB (Automated Swap Backend) JSON-RPC client
 ///
@@ -59,6 +63,15 @@ pub struct Multiaddresses {
     pub addresses: Vec<String>,
 }
 
+/// A peer the ASB is connected to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct PeerInfo {
+    pub peer_id: String,
+    /// Addresses the connection to this peer goes through
+    #[serde(default)]
+    pub addresses: Vec<String>,
+}
+
 /// Active P2P connection count
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ActiveConnections {
@@ -73,6 +86,42 @@ pub struct SwapInfo {
     // Add more fields as needed based on actual ASB response
 }
 
+/// A single swap as reported by `get_swaps`, with the transactions it has seen
+///
+/// Only the id and state are guaranteed; the ASB fills in txids as the swap
+/// progresses.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SwapDetails {
+    #[serde(alias = "id")]
+    pub swap_id: String,
+    #[serde(alias = "state")]
+    pub status: String,
+    #[serde(default)]
+    pub start_date: Option<String>,
+    #[serde(default)]
+    pub btc_lock_txid: Option<String>,
+    #[serde(default)]
+    pub xmr_lock_txid: Option<String>,
+    #[serde(default)]
+    pub btc_redeem_txid: Option<String>,
+    #[serde(default)]
+    pub btc_refund_txid: Option<String>,
+    #[serde(default)]
+    pub xmr_refund_txid: Option<String>,
+    #[serde(default)]
+    pub btc_punish_txid: Option<String>,
+}
+
+/// The ASB's current offer to sell XMR, as it quotes it to takers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AsbQuote {
+    /// BTC per XMR
+    pub price: f64,
+    /// Smallest and largest swap the ASB accepts, in BTC
+    pub min_quantity: f64,
+    pub max_quantity: f64,
+}
+
 /// List of swaps
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Swaps {
@@ -104,27 +153,34 @@ impl AsbClient {
             "id": 1
         });
 
-        let response = self
-            .client
-            .post(&self.url)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .context("Failed to send ASB JSON-RPC request")?;
-
-        let rpc_response: RpcResponse<T> = response
-            .json()
-            .await
-            .context("Failed to parse ASB JSON-RPC response")?;
-
-        if let Some(error) = rpc_response.error {
-            anyhow::bail!("ASB JSON-RPC error {}: {}", error.code, error.message);
-        }
-
-        rpc_response
-            .result
-            .context("ASB JSON-RPC response missing result")
+        let body = &body;
+        retry::retry("asb", method, true, || {
+            clients::track("asb", method, async move {
+                let response = self
+                    .client
+                    .post(&self.url)
+                    .header("Content-Type", "application/json")
+                    .json(body)
+                    .send()
+                    .await
+                    .context("Failed to send ASB JSON-RPC request")?;
+
+                let rpc_response: RpcResponse<T> = retry::check_status(response)
+                    .await?
+                    .json()
+                    .await
+                    .context("Failed to parse ASB JSON-RPC response")?;
+
+                if let Some(error) = rpc_response.error {
+                    anyhow::bail!("ASB JSON-RPC error {}: {}", error.code, error.message);
+                }
+
+                rpc_response
+                    .result
+                    .context("ASB JSON-RPC response missing result")
+            })
+        })
+        .await
     }
 
     /// Check connection to ASB server
@@ -240,24 +296,23 @@ impl AsbClient {
         let result: serde_json::Value = self.call("multiaddresses", serde_json::json!({})).await?;
 
         // The result might be an array or wrapped in an object with key "multiaddresses"
-        if let Some(addresses) = result.as_array() {
-            Ok(addresses
-                .iter()
-                .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                .collect())
-        } else if let Some(addresses) = result.get("multiaddresses").and_then(|v| v.as_array()) {
-            Ok(addresses
-                .iter()
-                .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                .collect())
-        } else if let Some(addresses) = result.get("addresses").and_then(|v| v.as_array()) {
-            Ok(addresses
-                .iter()
-                .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                .collect())
-        } else {
-            anyhow::bail!("Unexpected multiaddresses response format: {:?}", result)
-        }
+        parse_address_list(&result, "multiaddresses")
+    }
+
+    /// Get the addresses the ASB's libp2p swarm is listening on
+    ///
+    /// Unlike [`get_multiaddresses`](Self::get_multiaddresses), these are the
+    /// local sockets, not the externally advertised addresses.
+    pub async fn get_listen_addresses(&self) -> Result<Vec<String>> {
+        let result: serde_json::Value =
+            self.call("listen_addresses", serde_json::json!({})).await?;
+        parse_address_list(&result, "listen_addresses")
+    }
+
+    /// Get the peers the ASB is currently connected to
+    pub async fn get_peers(&self) -> Result<Vec<PeerInfo>> {
+        let result: serde_json::Value = self.call("peers", serde_json::json!({})).await?;
+        parse_peers(result)
     }
 
     /// Get active P2P connection count
@@ -284,6 +339,12 @@ impl AsbClient {
         }
     }
 
+    /// Get the quote the ASB currently offers takers
+    pub async fn get_quote(&self) -> Result<AsbQuote> {
+        let result: serde_json::Value = self.call("quote", serde_json::json!({})).await?;
+        parse_quote(result)
+    }
+
     /// Get list of swaps
     ///
     /// # Returns
@@ -320,6 +381,14 @@ impl AsbClient {
         }
     }
 
+    /// Get the details of one swap, `None` if the ASB doesn't know it
+    pub async fn get_swap(&self, swap_id: &str) -> Result<Option<SwapDetails>> {
+        let result: serde_json::Value = self.call("get_swaps", serde_json::json!({})).await?;
+        Ok(parse_swap_details(&result)
+            .into_iter()
+            .find(|swap| swap.swap_id == swap_id))
+    }
+
     /// Check if ASB is healthy and reachable
     ///
     /// This is a convenience method that tries to check connection
@@ -359,6 +428,58 @@ impl AsbClient {
     }
 }
 
+/// Parse a list of multiaddresses, either a bare array or wrapped as `{key: [...]}` / `{"addresses": [...]}`
+fn parse_address_list(result: &serde_json::Value, key: &str) -> Result<Vec<String>> {
+    let addresses = result
+        .as_array()
+        .or_else(|| result.get(key).and_then(|v| v.as_array()))
+        .or_else(|| result.get("addresses").and_then(|v| v.as_array()))
+        .with_context(|| format!("Unexpected {} response format: {:?}", key, result))?;
+
+    Ok(addresses
+        .iter()
+        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+        .collect())
+}
+
+/// Parse the `peers` response, a bare array or `{"peers": [...]}`
+fn parse_peers(result: serde_json::Value) -> Result<Vec<PeerInfo>> {
+    let peers = match result {
+        serde_json::Value::Object(mut object) if object.contains_key("peers") => {
+            object.remove("peers").unwrap_or_default()
+        }
+        other => other,
+    };
+
+    serde_json::from_value(peers).context("Unexpected peers response format")
+}
+
+/// Parse the `quote` response, a bare quote or `{"quote": {...}}`
+fn parse_quote(result: serde_json::Value) -> Result<AsbQuote> {
+    let quote = match result {
+        serde_json::Value::Object(mut object) if object.contains_key("quote") => {
+            object.remove("quote").unwrap_or_default()
+        }
+        other => other,
+    };
+
+    serde_json::from_value(quote).context("Unexpected quote response format")
+}
+
+/// Parse the `get_swaps` response, a bare array or `{"swaps": [...]}`, skipping malformed entries
+fn parse_swap_details(result: &serde_json::Value) -> Vec<SwapDetails> {
+    result
+        .as_array()
+        .or_else(|| result.get("swaps").and_then(|v| v.as_array()))
+        .map(|swaps| {
+            swaps
+                .iter()
+                .filter_map(|v| serde_json::from_value(v.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Comprehensive ASB status
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AsbStatus {
@@ -373,6 +494,92 @@ pub struct AsbStatus {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_peers() {
+        let response = serde_json::json!({
+            "peers": [
+                {
+                    "peer_id": "12D3KooWCdMKjesXMJz1SiZ7HgotrxuqhQJbP5sgBm2BwP1cqThi",
+                    "addresses": ["/ip4/203.0.113.7/tcp/9939"]
+                },
+                { "peer_id": "12D3KooWQsAFHUm32ThqfQRJhtcc57qqkYckSu8JkMsbGKkwTS6p" }
+            ]
+        });
+
+        let peers = parse_peers(response.clone()).unwrap();
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[0].addresses, vec!["/ip4/203.0.113.7/tcp/9939"]);
+        assert!(peers[1].addresses.is_empty());
+
+        // A bare array parses the same way
+        assert_eq!(parse_peers(response["peers"].clone()).unwrap(), peers);
+        assert!(parse_peers(serde_json::json!({"count": 2})).is_err());
+    }
+
+    #[test]
+    fn test_parse_quote() {
+        let response = serde_json::json!({
+            "quote": { "price": 0.0071, "min_quantity": 0.0005, "max_quantity": 0.25 }
+        });
+
+        let quote = parse_quote(response.clone()).unwrap();
+        assert_eq!(quote.price, 0.0071);
+        assert_eq!(quote.min_quantity, 0.0005);
+        assert_eq!(quote.max_quantity, 0.25);
+
+        // A bare quote parses the same way
+        assert_eq!(parse_quote(response["quote"].clone()).unwrap(), quote);
+        assert!(parse_quote(serde_json::json!({"price": 0.0071})).is_err());
+    }
+
+    #[test]
+    fn test_parse_swap_details() {
+        let response = serde_json::json!({
+            "swaps": [
+                {
+                    "id": "a1b2c3d4-0000-4000-8000-000000000001",
+                    "state": "BtcRedeemed",
+                    "start_date": "2024-05-01T12:00:00Z",
+                    "btc_lock_txid": "aa",
+                    "xmr_lock_txid": "bb",
+                    "btc_redeem_txid": "cc"
+                },
+                { "swap_id": "a1b2c3d4-0000-4000-8000-000000000002", "status": "Started" },
+                { "state": "missing id" }
+            ]
+        });
+
+        let swaps = parse_swap_details(&response);
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(swaps[0].status, "BtcRedeemed");
+        assert_eq!(swaps[0].btc_redeem_txid.as_deref(), Some("cc"));
+        assert!(swaps[0].btc_refund_txid.is_none());
+        assert_eq!(swaps[1].swap_id, "a1b2c3d4-0000-4000-8000-000000000002");
+
+        // A bare array parses the same way
+        assert_eq!(parse_swap_details(&response["swaps"]), swaps);
+        assert!(parse_swap_details(&serde_json::json!(null)).is_empty());
+    }
+
+    #[test]
+    fn test_parse_listen_addresses() {
+        let wrapped = serde_json::json!({
+            "listen_addresses": ["/ip4/0.0.0.0/tcp/9939", "/ip4/127.0.0.1/tcp/9940/ws"]
+        });
+        let addresses = parse_address_list(&wrapped, "listen_addresses").unwrap();
+        assert_eq!(
+            addresses,
+            vec!["/ip4/0.0.0.0/tcp/9939", "/ip4/127.0.0.1/tcp/9940/ws"]
+        );
+
+        let bare = serde_json::json!(["/ip4/0.0.0.0/tcp/9939"]);
+        assert_eq!(
+            parse_address_list(&bare, "listen_addresses").unwrap(),
+            vec!["/ip4/0.0.0.0/tcp/9939"]
+        );
+        assert!(parse_address_list(&serde_json::json!(null), "listen_addresses").is_err());
+    }
+
     #[tokio::test]
     #[ignore] // Only run with actual ASB instance
     async fn test_check_connection() {
@@ -0,0 +1,758 @@
+//! Binance spot API client, trading the XMR/BTC market
+//!
+//! Binance lists the market as `XMRBTC`: XMR is the base asset and prices are
+//! in BTC per XMR. Private requests are signed with HMAC-SHA256 over the query
+//! string and carry a millisecond timestamp, so the host clock must be within
+//! Binance's receive window.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::Method;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use super::exchange::{
+    Asset, Deposit, Exchange, ExchangeBalances, OrderReport, OrderRequest, OrderSide, OrderState,
+    Ticker, TransferState, Withdrawal,
+};
+use super::retry;
+use crate::metrics::clients;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const BINANCE_API_URL: &str = "https://api.binance.com";
+
+const SYMBOL: &str = "XMRBTC";
+
+/// How long (ms) after its timestamp Binance still accepts a signed request
+const RECV_WINDOW_MS: u64 = 5000;
+
+/// Binance API client
+#[derive(Clone)]
+pub struct BinanceClient {
+    api_key: String,
+    api_secret: String,
+    base_url: String,
+    client: reqwest::Client,
+    /// XMRBTC precision rules, fetched with the first order
+    rules: Arc<tokio::sync::OnceCell<SymbolRules>>,
+}
+
+impl std::fmt::Debug for BinanceClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BinanceClient")
+            .field("api_key", &self.api_key)
+            .field("api_secret", &"<redacted>")
+            .field("base_url", &self.base_url)
+            .finish()
+    }
+}
+
+/// Binance error body
+#[derive(Debug, Deserialize)]
+struct BinanceError {
+    code: i64,
+    msg: String,
+}
+
+impl std::fmt::Display for BinanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Binance API error {}: {}", self.code, self.msg)
+    }
+}
+
+impl std::error::Error for BinanceError {}
+
+/// Error code Binance returns when there is nothing to cancel
+const NO_SUCH_ORDER: i64 = -2011;
+
+/// Decimal places XMRBTC accepts in each order field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SymbolRules {
+    /// BTC amounts (`quoteOrderQty`)
+    quote_decimals: u32,
+    /// XMR quantities, from the `LOT_SIZE` step
+    quantity_decimals: u32,
+    /// Prices, from the `PRICE_FILTER` tick
+    price_decimals: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExchangeInfo {
+    symbols: Vec<SymbolInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SymbolInfo {
+    quote_asset_precision: u32,
+    filters: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Ticker24h {
+    bid_price: String,
+    ask_price: String,
+    last_price: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Account {
+    balances: Vec<AccountBalance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountBalance {
+    asset: String,
+    free: String,
+    locked: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlacedOrder {
+    order_id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueriedOrder {
+    status: String,
+    executed_qty: String,
+    cummulative_quote_qty: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DepositAddress {
+    address: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WithdrawApplied {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DepositRecord {
+    id: String,
+    amount: String,
+    status: u8,
+    #[serde(default)]
+    tx_id: String,
+    /// Unix milliseconds
+    insert_time: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TradeFee {
+    /// Fraction of the order value, e.g. "0.001"
+    taker_commission: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CoinConfig {
+    coin: String,
+    network_list: Vec<CoinNetwork>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CoinNetwork {
+    is_default: bool,
+    withdraw_fee: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WithdrawRecord {
+    id: String,
+    status: u8,
+    #[serde(default)]
+    tx_id: String,
+    #[serde(default)]
+    transaction_fee: String,
+}
+
+impl BinanceClient {
+    pub fn new(api_key: String, api_secret: String) -> Self {
+        Self {
+            api_key,
+            api_secret,
+            base_url: BINANCE_API_URL.to_string(),
+            client: reqwest::Client::new(),
+            rules: Arc::default(),
+        }
+    }
+
+    /// Send requests to another API root, e.g. a local mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Hex HMAC-SHA256 of a query string
+    fn sign(&self, query: &str) -> Result<String> {
+        let mut mac = HmacSha256::new_from_slice(self.api_secret.as_bytes())
+            .context("Failed to create HMAC")?;
+        mac.update(query.as_bytes());
+        Ok(mac
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect())
+    }
+
+    /// Send a request, signing it when `signed` is set
+    ///
+    /// Signed requests carry a timestamp, so each attempt is signed anew.
+    /// Only `GET`s are retried after a timeout or server error, as an order
+    /// may have gone through.
+    async fn request<T>(
+        &self,
+        method: Method,
+        path: &str,
+        params: &[(&str, String)],
+        signed: bool,
+    ) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        if signed && (self.api_key.is_empty() || self.api_secret.is_empty()) {
+            anyhow::bail!("No Binance API key configured, required for {}", path);
+        }
+        let params = params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join("&");
+        let (method, params) = (&method, &params);
+
+        retry::retry("binance", path, *method == Method::GET, || async move {
+            let request = if signed {
+                let mut query = params.clone();
+                if !query.is_empty() {
+                    query.push('&');
+                }
+                query.push_str(&format!(
+                    "recvWindow={}&timestamp={}",
+                    RECV_WINDOW_MS,
+                    Utc::now().timestamp_millis()
+                ));
+                let signature = self.sign(&query)?;
+                query.push_str(&format!("&signature={}", signature));
+                self.client
+                    .request(
+                        method.clone(),
+                        format!("{}{}?{}", self.base_url, path, query),
+                    )
+                    .header("X-MBX-APIKEY", &self.api_key)
+            } else {
+                self.client.request(
+                    method.clone(),
+                    format!("{}{}?{}", self.base_url, path, params),
+                )
+            };
+
+            clients::track("binance", path, async {
+                let response = request.send().await.context("Failed to send request")?;
+                let response = retry::check_status(response).await?;
+                let status = response.status();
+                let body = response.text().await.context("Failed to read response")?;
+
+                if !status.is_success() {
+                    return match serde_json::from_str::<BinanceError>(&body) {
+                        Ok(error) => Err(error.into()),
+                        Err(_) => Err(anyhow::anyhow!("Binance API returned {}: {}", status, body)),
+                    };
+                }
+
+                serde_json::from_str(&body).context("Failed to parse response")
+            })
+            .await
+        })
+        .await
+    }
+
+    async fn rules(&self) -> Result<SymbolRules> {
+        self.rules
+            .get_or_try_init(|| async {
+                let info: ExchangeInfo = self
+                    .request(
+                        Method::GET,
+                        "/api/v3/exchangeInfo",
+                        &[("symbol", SYMBOL.to_string())],
+                        false,
+                    )
+                    .await?;
+                let symbol = info
+                    .symbols
+                    .into_iter()
+                    .next()
+                    .context("Binance does not list XMRBTC")?;
+                Ok(SymbolRules {
+                    quote_decimals: symbol.quote_asset_precision,
+                    quantity_decimals: filter_decimals(&symbol.filters, "LOT_SIZE", "stepSize")?,
+                    price_decimals: filter_decimals(&symbol.filters, "PRICE_FILTER", "tickSize")?,
+                })
+            })
+            .await
+            .copied()
+    }
+}
+
+/// Decimal places of a step such as `"0.00100000"` (3) in one of a symbol's filters
+fn filter_decimals(filters: &[serde_json::Value], filter: &str, field: &str) -> Result<u32> {
+    let step = filters
+        .iter()
+        .find(|f| f["filterType"] == filter)
+        .and_then(|f| f[field].as_str())
+        .with_context(|| format!("XMRBTC has no {} {}", filter, field))?;
+    Ok(step_decimals(step))
+}
+
+fn step_decimals(step: &str) -> u32 {
+    match step.trim_end_matches('0').split_once('.') {
+        Some((_, fraction)) => fraction.len() as u32,
+        None => 0,
+    }
+}
+
+/// Parse an amount or price, also when written in scientific notation
+fn parse_decimal(value: &str, name: &str) -> Result<Decimal> {
+    let value = value.trim();
+    Decimal::from_str(value)
+        .or_else(|_| Decimal::from_scientific(value))
+        .with_context(|| format!("Invalid {}: '{}'", name, value))
+}
+
+/// `value` cut down to `decimals` places without rounding up, in plain notation
+fn truncate_decimals(value: Decimal, decimals: u32) -> String {
+    value
+        .round_dp_with_strategy(decimals, RoundingStrategy::ToZero)
+        .to_string()
+}
+
+fn binance_coin(asset: Asset) -> &'static str {
+    match asset {
+        Asset::Btc => "BTC",
+        Asset::Xmr => "XMR",
+    }
+}
+
+fn parse_price(value: &str, name: &str) -> Result<f64> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|price| price.is_finite() && *price > 0.0)
+        .with_context(|| format!("Invalid XMRBTC {} price: '{}'", name, value))
+}
+
+impl Exchange for BinanceClient {
+    fn name(&self) -> &'static str {
+        "Binance"
+    }
+
+    async fn ticker(&self) -> Result<Ticker> {
+        let ticker: Ticker24h = self
+            .request(
+                Method::GET,
+                "/api/v3/ticker/24hr",
+                &[("symbol", SYMBOL.to_string())],
+                false,
+            )
+            .await?;
+        Ok(Ticker {
+            bid: parse_price(&ticker.bid_price, "bid")?,
+            ask: parse_price(&ticker.ask_price, "ask")?,
+            last: parse_price(&ticker.last_price, "last")?,
+        })
+    }
+
+    async fn balances(&self) -> Result<ExchangeBalances> {
+        let account: Account = self
+            .request(Method::GET, "/api/v3/account", &[], true)
+            .await?;
+        let total = |coin: &str| -> Result<Option<f64>> {
+            account
+                .balances
+                .iter()
+                .find(|balance| balance.asset == coin)
+                .map(|balance| {
+                    let free: f64 = balance.free.parse().context("Invalid free balance")?;
+                    let locked: f64 = balance.locked.parse().context("Invalid locked balance")?;
+                    Ok::<_, anyhow::Error>(free + locked)
+                })
+                .transpose()
+                .with_context(|| format!("Failed to parse Binance {} balance", coin))
+        };
+        Ok(ExchangeBalances {
+            btc: total("BTC")?,
+            xmr: total("XMR")?,
+        })
+    }
+
+    async fn volume_decimals(&self) -> Result<u32> {
+        // A market buy is sized in BTC; a limit buy's BTC amount is turned
+        // into an XMR quantity, which is truncated separately
+        Ok(self.rules().await?.quote_decimals)
+    }
+
+    async fn deposit_address(&self, asset: Asset, _new: bool) -> Result<String> {
+        // Binance hands out one address per coin and network
+        let address: DepositAddress = self
+            .request(
+                Method::GET,
+                "/sapi/v1/capital/deposit/address",
+                &[("coin", binance_coin(asset).to_string())],
+                true,
+            )
+            .await?;
+        Ok(address.address)
+    }
+
+    async fn submit_order(&self, order: &OrderRequest) -> Result<String> {
+        let rules = self.rules().await?;
+        let mut params = vec![("symbol", SYMBOL.to_string())];
+
+        let volume = parse_decimal(&order.volume, "order volume")?;
+
+        match (order.side, &order.limit_price) {
+            (OrderSide::BuyXmr, None) => {
+                params.push(("side", "BUY".to_string()));
+                params.push(("type", "MARKET".to_string()));
+                params.push((
+                    "quoteOrderQty",
+                    truncate_decimals(volume, rules.quote_decimals),
+                ));
+            }
+            (OrderSide::SellXmr, None) => {
+                params.push(("side", "SELL".to_string()));
+                params.push(("type", "MARKET".to_string()));
+                params.push((
+                    "quantity",
+                    truncate_decimals(volume, rules.quantity_decimals),
+                ));
+            }
+            (side, Some(price)) => {
+                let price = parse_decimal(price, "limit price")?
+                    .round_dp_with_strategy(rules.price_decimals, RoundingStrategy::ToZero);
+                if price <= Decimal::ZERO {
+                    anyhow::bail!("Invalid XMRBTC limit price: '{}'", price);
+                }
+                let quantity = match side {
+                    // Limit orders are sized in XMR, so convert the BTC to spend
+                    OrderSide::BuyXmr => volume
+                        .checked_div(price)
+                        .context("Order volume out of range")?,
+                    OrderSide::SellXmr => volume,
+                };
+                params.push((
+                    "side",
+                    match side {
+                        OrderSide::BuyXmr => "BUY",
+                        OrderSide::SellXmr => "SELL",
+                    }
+                    .to_string(),
+                ));
+                params.push(("type", "LIMIT".to_string()));
+                params.push(("timeInForce", "GTC".to_string()));
+                params.push((
+                    "quantity",
+                    truncate_decimals(quantity, rules.quantity_decimals),
+                ));
+                params.push(("price", price.to_string()));
+            }
+        }
+
+        let placed: PlacedOrder = self
+            .request(Method::POST, "/api/v3/order", &params, true)
+            .await?;
+        Ok(placed.order_id.to_string())
+    }
+
+    async fn order_status(&self, order_id: &str) -> Result<Option<OrderReport>> {
+        let params = [
+            ("symbol", SYMBOL.to_string()),
+            ("orderId", order_id.to_string()),
+        ];
+        let order: QueriedOrder = self
+            .request(Method::GET, "/api/v3/order", &params, true)
+            .await?;
+
+        // Binance reports totals; the average price follows from them
+        let executed: f64 = order.executed_qty.parse().unwrap_or(0.0);
+        let cost: f64 = order.cummulative_quote_qty.parse().unwrap_or(0.0);
+        let price = if executed > 0.0 {
+            format!("{:.8}", cost / executed)
+        } else {
+            String::new()
+        };
+
+        Ok(Some(OrderReport {
+            state: match order.status.as_str() {
+                "FILLED" => OrderState::Closed,
+                "CANCELED" | "REJECTED" => OrderState::Canceled,
+                "EXPIRED" | "EXPIRED_IN_MATCH" => OrderState::Expired,
+                _ => OrderState::Open,
+            },
+            executed: order.executed_qty,
+            cost: order.cummulative_quote_qty,
+            price,
+            // Commission is only listed per trade, not on the order
+            fee: String::new(),
+        }))
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> Result<bool> {
+        let params = [
+            ("symbol", SYMBOL.to_string()),
+            ("orderId", order_id.to_string()),
+        ];
+        let result: Result<serde_json::Value> = self
+            .request(Method::DELETE, "/api/v3/order", &params, true)
+            .await;
+        match result {
+            Ok(_) => Ok(true),
+            // Already filled, cancelled or expired
+            Err(e)
+                if e.downcast_ref::<BinanceError>()
+                    .is_some_and(|error| error.code == NO_SUCH_ORDER) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn cancel_open_orders(&self) -> Result<u64> {
+        let result: Result<Vec<serde_json::Value>> = self
+            .request(
+                Method::DELETE,
+                "/api/v3/openOrders",
+                &[("symbol", SYMBOL.to_string())],
+                true,
+            )
+            .await;
+        match result {
+            Ok(cancelled) => Ok(cancelled.len() as u64),
+            Err(e)
+                if e.downcast_ref::<BinanceError>()
+                    .is_some_and(|error| error.code == NO_SUCH_ORDER) =>
+            {
+                Ok(0)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn withdraw_to(&self, asset: Asset, amount: &str, address: &str) -> Result<String> {
+        let params = [
+            ("coin", binance_coin(asset).to_string()),
+            ("address", address.to_string()),
+            (
+                "amount",
+                parse_decimal(amount, "withdrawal amount")?
+                    .normalize()
+                    .to_string(),
+            ),
+        ];
+        let applied: WithdrawApplied = self
+            .request(
+                Method::POST,
+                "/sapi/v1/capital/withdraw/apply",
+                &params,
+                true,
+            )
+            .await?;
+        Ok(applied.id)
+    }
+
+    async fn deposits(&self, asset: Asset) -> Result<Vec<Deposit>> {
+        let records: Vec<DepositRecord> = self
+            .request(
+                Method::GET,
+                "/sapi/v1/capital/deposit/hisrec",
+                &[("coin", binance_coin(asset).to_string())],
+                true,
+            )
+            .await?;
+        Ok(records
+            .into_iter()
+            .map(|record| Deposit {
+                state: match record.status {
+                    // 6: credited, but not yet withdrawable
+                    1 | 6 => TransferState::Succeeded,
+                    7 => TransferState::Failed,
+                    _ => TransferState::Pending,
+                },
+                refid: record.id,
+                txid: record.tx_id,
+                amount: record.amount,
+                time: record.insert_time / 1000,
+            })
+            .collect())
+    }
+
+    async fn withdrawals(&self, asset: Asset) -> Result<Vec<Withdrawal>> {
+        let records: Vec<WithdrawRecord> = self
+            .request(
+                Method::GET,
+                "/sapi/v1/capital/withdraw/history",
+                &[("coin", binance_coin(asset).to_string())],
+                true,
+            )
+            .await?;
+        Ok(records
+            .into_iter()
+            .map(|record| Withdrawal {
+                state: match record.status {
+                    6 => TransferState::Succeeded,
+                    1 => TransferState::Canceled,
+                    3 | 5 => TransferState::Failed,
+                    _ => TransferState::Pending,
+                },
+                refid: record.id,
+                txid: record.tx_id,
+                fee: record.transaction_fee,
+            })
+            .collect())
+    }
+
+    async fn trading_fee_percent(&self) -> Result<f64> {
+        let fees: Vec<TradeFee> = self
+            .request(
+                Method::GET,
+                "/sapi/v1/asset/tradeFee",
+                &[("symbol", SYMBOL.to_string())],
+                true,
+            )
+            .await?;
+        let fee = fees.first().context("Binance returned no fee for XMRBTC")?;
+        let fraction: f64 = fee
+            .taker_commission
+            .parse()
+            .context("Failed to parse Binance taker fee")?;
+        Ok(fraction * 100.0)
+    }
+
+    async fn withdrawal_fee(&self, asset: Asset, _amount: &str) -> Result<f64> {
+        // Flat per network; withdrawals go over the coin's default network
+        let coins: Vec<CoinConfig> = self
+            .request(Method::GET, "/sapi/v1/capital/config/getall", &[], true)
+            .await?;
+        let coin = binance_coin(asset);
+        let network = coins
+            .iter()
+            .find(|config| config.coin == coin)
+            .and_then(|config| config.network_list.iter().find(|n| n.is_default))
+            .with_context(|| format!("Binance has no default {} network", coin))?;
+        network
+            .withdraw_fee
+            .parse()
+            .with_context(|| format!("Failed to parse Binance {} withdrawal fee", coin))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::RawQuery, http::HeaderMap, routing::get, Json, Router};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_precision_helpers() {
+        assert_eq!(step_decimals("0.00100000"), 3);
+        assert_eq!(step_decimals("1.00000000"), 0);
+        assert_eq!(step_decimals("0.00000100"), 6);
+
+        let truncate = |value: &str, decimals| {
+            truncate_decimals(parse_decimal(value, "amount").unwrap(), decimals)
+        };
+        assert_eq!(truncate("1.23456789", 3), "1.234");
+        assert_eq!(truncate("1.2", 3), "1.2");
+        assert_eq!(truncate("1.999", 0), "1");
+        assert_eq!(truncate("5", 3), "5");
+        // Never in scientific notation, whichever way the amount came in
+        assert_eq!(truncate("1.5e-7", 8), "0.00000015");
+        assert_eq!(truncate("0.000000123", 8), "0.00000012");
+        assert!(parse_decimal("lots", "amount").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_market_buy_is_signed_and_sized_in_btc() {
+        let queries: Arc<Mutex<Vec<(String, String)>>> = Arc::default();
+        let recorded = queries.clone();
+        let app = Router::new()
+            .route(
+                "/api/v3/exchangeInfo",
+                get(|| async {
+                    Json(serde_json::json!({"symbols": [{
+                        "quoteAssetPrecision": 8,
+                        "filters": [
+                            {"filterType": "PRICE_FILTER", "tickSize": "0.00000100"},
+                            {"filterType": "LOT_SIZE", "stepSize": "0.00100000"}
+                        ]
+                    }]}))
+                }),
+            )
+            .route(
+                "/api/v3/order",
+                axum::routing::post(
+                    move |headers: HeaderMap, RawQuery(query): RawQuery| async move {
+                        let key = headers
+                            .get("X-MBX-APIKEY")
+                            .and_then(|v| v.to_str().ok())
+                            .unwrap_or_default()
+                            .to_string();
+                        recorded
+                            .lock()
+                            .unwrap()
+                            .push((key, query.unwrap_or_default()));
+                        Json(serde_json::json!({"orderId": 42}))
+                    },
+                )
+                .get(|| async {
+                    Json(serde_json::json!({
+                        "status": "FILLED",
+                        "executedQty": "2.00000000",
+                        "cummulativeQuoteQty": "0.01000000"
+                    }))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client =
+            BinanceClient::new("key".to_string(), "secret".to_string()).with_base_url(base_url);
+        let order_id = client
+            .submit_order(&OrderRequest {
+                side: OrderSide::BuyXmr,
+                volume: "0.0123456789".to_string(),
+                limit_price: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(order_id, "42");
+
+        let (key, query) = queries.lock().unwrap()[0].clone();
+        assert_eq!(key, "key");
+        assert!(query.contains("side=BUY&type=MARKET&quoteOrderQty=0.01234567&"));
+        let (unsigned, signature) = query.rsplit_once("&signature=").unwrap();
+        assert_eq!(signature, client.sign(unsigned).unwrap());
+
+        let report = client.order_status(&order_id).await.unwrap().unwrap();
+        assert_eq!(report.state, OrderState::Closed);
+        assert_eq!(report.executed, "2.00000000");
+        assert_eq!(report.price, "0.00500000");
+    }
+}
@@ -1,9 +1,21 @@
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose, Engine as _};
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::fs;
+use std::sync::{Arc, RwLock};
+use utoipa::ToSchema;
 
-use crate::metrics::BitcoinMetrics;
+use super::retry;
+use crate::metrics::{clients, BitcoinMetrics, BitcoinNetworkMetrics};
+
+/// Confirmation targets, in blocks, behind the fast, medium and slow estimates
+pub const FAST_CONF_TARGET: u16 = 2;
+pub const MEDIUM_CONF_TARGET: u16 = 6;
+pub const SLOW_CONF_TARGET: u16 = 24;
+
+/// How long [`FeeEstimator`] serves estimates before asking bitcoind again
+const FEE_CACHE_TTL_SECS: i64 = 60;
 
 /// Bitcoin node RPC client for blockchain information
 pub struct BitcoinRpcClient {
@@ -19,9 +31,13 @@ struct RpcResponse<T> {
 
 #[derive(Deserialize)]
 struct RpcError {
+    code: i64,
     message: String,
 }
 
+/// Error code bitcoind answers with while it is still loading
+const RPC_IN_WARMUP: i64 = -28;
+
 #[derive(Deserialize)]
 struct BlockchainInfo {
     blocks: u64,
@@ -31,6 +47,45 @@ struct BlockchainInfo {
     size_on_disk: u64,
 }
 
+#[derive(Deserialize)]
+struct PeerInfo {
+    inbound: bool,
+}
+
+#[derive(Deserialize)]
+struct NetTotals {
+    #[serde(rename = "totalbytessent")]
+    total_bytes_sent: u64,
+    #[serde(rename = "totalbytesrecv")]
+    total_bytes_recv: u64,
+}
+
+#[derive(Deserialize)]
+struct NetworkInfo {
+    /// BTC/kvB
+    #[serde(rename = "relayfee")]
+    relay_fee: f64,
+}
+
+impl BitcoinNetworkMetrics {
+    fn from_rpc(
+        peers: &[PeerInfo],
+        banned: &[serde_json::Value],
+        totals: &NetTotals,
+        info: &NetworkInfo,
+    ) -> Self {
+        Self {
+            peers: peers.len() as u64,
+            inbound_peers: peers.iter().filter(|peer| peer.inbound).count() as u64,
+            banned_peers: banned.len() as u64,
+            bytes_sent: totals.total_bytes_sent,
+            bytes_received: totals.total_bytes_recv,
+            // BTC/kvB to sat/vB
+            min_relay_fee: info.relay_fee * 1e5,
+        }
+    }
+}
+
 impl BitcoinRpcClient {
     /// Create a new Bitcoin RPC client using cookie authentication
     /// First tries BITCOIN_RPC_COOKIE env var, then tries sudo, then direct read
@@ -62,55 +117,97 @@ impl BitcoinRpcClient {
     }
 
     /// Call a Bitcoin RPC method
-    async fn call<T: for<'de> Deserialize<'de>>(&self, method: &str) -> Result<T> {
+    async fn call<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T> {
         let client = reqwest::Client::new();
 
         let body = serde_json::json!({
             "jsonrpc": "1.0",
             "id": "eigenix",
             "method": method,
-            "params": []
+            "params": params
         });
 
-        let response = client
-            .post(&self.url)
-            .header("Authorization", &self.auth)
-            .header("Content-Type", "text/plain")
-            .json(&body)
-            .send()
-            .await
-            .context("Failed to send RPC request")?;
-
-        let rpc_response: RpcResponse<T> = response
-            .json()
-            .await
-            .context("Failed to parse RPC response")?;
-
-        if let Some(error) = rpc_response.error {
-            anyhow::bail!("RPC error: {}", error.message);
-        }
+        let (client, body) = (&client, &body);
+        retry::retry("bitcoind", method, true, || {
+            clients::track("bitcoind", method, async move {
+                let response = client
+                    .post(&self.url)
+                    .header("Authorization", &self.auth)
+                    .header("Content-Type", "text/plain")
+                    .json(body)
+                    .send()
+                    .await
+                    .context("Failed to send RPC request")?;
 
-        rpc_response
-            .result
-            .context("RPC response missing result field")
+                let rpc_response: RpcResponse<T> = retry::check_status(response)
+                    .await?
+                    .json()
+                    .await
+                    .context("Failed to parse RPC response")?;
+
+                if let Some(error) = rpc_response.error {
+                    if error.code == RPC_IN_WARMUP {
+                        return Err(
+                            retry::Unavailable(format!("RPC error: {}", error.message)).into()
+                        );
+                    }
+                    anyhow::bail!("RPC error: {}", error.message);
+                }
+
+                rpc_response
+                    .result
+                    .context("RPC response missing result field")
+            })
+        })
+        .await
     }
 
     /// Get Bitcoin blockchain metrics
     pub async fn get_metrics(&self) -> Result<BitcoinMetrics> {
-        let info: BlockchainInfo = self.call("getblockchaininfo").await?;
+        let info: BlockchainInfo = self
+            .call("getblockchaininfo", serde_json::json!([]))
+            .await?;
 
         // Try to get wallet balance (may fail if no wallet loaded)
         let wallet_balance = self.get_wallet_balance().await.ok();
 
+        // Chain metrics are still worth storing if these calls fail
+        let network = match self.get_network_metrics().await {
+            Ok(network) => Some(network),
+            Err(e) => {
+                tracing::warn!("Failed to collect Bitcoin network metrics: {:#}", e);
+                None
+            }
+        };
+
         Ok(BitcoinMetrics {
             blocks: info.blocks,
             headers: info.headers,
             verification_progress: info.verification_progress,
             size_on_disk: info.size_on_disk,
             wallet_balance,
+            network,
         })
     }
 
+    /// Peer counts, traffic totals and relay fee of the node
+    pub async fn get_network_metrics(&self) -> Result<BitcoinNetworkMetrics> {
+        let (peers, banned, totals, info) = tokio::try_join!(
+            self.call::<Vec<PeerInfo>>("getpeerinfo", serde_json::json!([])),
+            self.call::<Vec<serde_json::Value>>("listbanned", serde_json::json!([])),
+            self.call::<NetTotals>("getnettotals", serde_json::json!([])),
+            self.call::<NetworkInfo>("getnetworkinfo", serde_json::json!([])),
+        )?;
+
+        Ok(BitcoinNetworkMetrics::from_rpc(
+            &peers, &banned, &totals, &info,
+        ))
+    }
+
     /// Get wallet balance in BTC
     async fn get_wallet_balance(&self) -> Result<f64> {
         #[derive(Deserialize)]
@@ -118,15 +215,184 @@ impl BitcoinRpcClient {
             balance: f64,
         }
 
-        let result: BalanceResult = self.call("getbalances").await?;
+        let result: BalanceResult = self.call("getbalances", serde_json::json!([])).await?;
         Ok(result.balance)
     }
+
+    /// Feerate bitcoind expects to confirm within `conf_target` blocks
+    ///
+    /// # Returns
+    /// Feerate in BTC/kvB, or `None` while the node has too little data to estimate
+    pub async fn estimate_smart_fee(&self, conf_target: u16) -> Result<Option<f64>> {
+        #[derive(Deserialize)]
+        struct SmartFeeResult {
+            feerate: Option<f64>,
+        }
+
+        let result: SmartFeeResult = self
+            .call("estimatesmartfee", serde_json::json!([conf_target]))
+            .await?;
+        Ok(result.feerate)
+    }
+
+    /// Lowest feerate the node's mempool currently accepts, in BTC/kvB
+    pub async fn mempool_min_fee(&self) -> Result<f64> {
+        #[derive(Deserialize)]
+        struct MempoolInfo {
+            mempoolminfee: f64,
+        }
+
+        let info: MempoolInfo = self.call("getmempoolinfo", serde_json::json!([])).await?;
+        Ok(info.mempoolminfee)
+    }
+
+    /// Fast, medium and slow feerates, read fresh from the node
+    pub async fn get_fee_estimates(&self) -> Result<FeeEstimates> {
+        let fast = self.estimate_smart_fee(FAST_CONF_TARGET).await?;
+        let medium = self.estimate_smart_fee(MEDIUM_CONF_TARGET).await?;
+        let slow = self.estimate_smart_fee(SLOW_CONF_TARGET).await?;
+        let mempool_min = self.mempool_min_fee().await?;
+
+        Ok(FeeEstimates::from_rpc(
+            [fast, medium, slow],
+            mempool_min,
+            Utc::now(),
+        ))
+    }
+}
+
+/// Current feerates in sat/vB, served by `GET /bitcoin/fees`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FeeEstimates {
+    /// To confirm within [`FAST_CONF_TARGET`] blocks
+    pub fast: f64,
+    /// Within [`MEDIUM_CONF_TARGET`] blocks
+    pub medium: f64,
+    /// Within [`SLOW_CONF_TARGET`] blocks
+    pub slow: f64,
+    /// Below this the node's mempool drops transactions
+    pub mempool_min: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FeeEstimates {
+    /// Build from `estimatesmartfee` results for the fast, medium and slow
+    /// targets and `mempoolminfee`, all in BTC/kvB
+    ///
+    /// A target bitcoind has no estimate for takes the next slower one's,
+    /// and no rate goes below the mempool minimum.
+    fn from_rpc(estimates: [Option<f64>; 3], mempool_min: f64, updated_at: DateTime<Utc>) -> Self {
+        // BTC/kvB -> sat/vB
+        let mempool_min = mempool_min * 1e5;
+        let mut rates = [mempool_min; 3];
+        let mut slower = mempool_min;
+        for (rate, estimate) in rates.iter_mut().zip(estimates).rev() {
+            slower = estimate.map_or(slower, |estimate| (estimate * 1e5).max(slower));
+            *rate = slower;
+        }
+
+        Self {
+            fast: rates[0],
+            medium: rates[1],
+            slow: rates[2],
+            mempool_min,
+            updated_at,
+        }
+    }
+}
+
+/// Cached fee estimates, shared by the API and the trading engine
+///
+/// Connects to bitcoind per refresh, like the metrics collector, so a
+/// restarted node's new cookie is picked up.
+#[derive(Clone)]
+pub struct FeeEstimator {
+    url: String,
+    cookie_path: String,
+    cached: Arc<RwLock<Option<FeeEstimates>>>,
+}
+
+impl FeeEstimator {
+    pub fn new(url: String, cookie_path: String) -> Self {
+        Self {
+            url,
+            cookie_path,
+            cached: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Current estimates, at most a minute old
+    pub async fn estimates(&self) -> Result<FeeEstimates> {
+        let cached = *self.cached.read().unwrap();
+        if let Some(estimates) = cached {
+            if (Utc::now() - estimates.updated_at).num_seconds() < FEE_CACHE_TTL_SECS {
+                return Ok(estimates);
+            }
+        }
+
+        let client = BitcoinRpcClient::new(self.url.clone(), &self.cookie_path)?;
+        let estimates = client.get_fee_estimates().await?;
+        *self.cached.write().unwrap() = Some(estimates);
+        Ok(estimates)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_fee_estimates_from_rpc() {
+        let now = Utc::now();
+        let estimates =
+            FeeEstimates::from_rpc([Some(0.0002), Some(0.0001), Some(0.00003)], 0.00001, now);
+        assert!((estimates.fast - 20.0).abs() < 1e-9);
+        assert!((estimates.medium - 10.0).abs() < 1e-9);
+        assert!((estimates.slow - 3.0).abs() < 1e-9);
+        assert!((estimates.mempool_min - 1.0).abs() < 1e-9);
+
+        // Missing targets take the next slower estimate, never below the mempool minimum
+        let sparse = FeeEstimates::from_rpc([None, Some(0.00005), None], 0.00002, now);
+        assert!((sparse.fast - 5.0).abs() < 1e-9);
+        assert!((sparse.medium - 5.0).abs() < 1e-9);
+        assert!((sparse.slow - 2.0).abs() < 1e-9);
+
+        // A fast estimate below a slower one is raised to it
+        let inverted = FeeEstimates::from_rpc([Some(0.00001), Some(0.00004), None], 0.0, now);
+        assert!((inverted.fast - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_network_metrics_from_rpc() {
+        let peers: Vec<PeerInfo> = serde_json::from_value(serde_json::json!([
+            {"id": 0, "addr": "203.0.113.1:8333", "inbound": false},
+            {"id": 1, "addr": "203.0.113.2:8333", "inbound": false},
+            {"id": 2, "addr": "198.51.100.7:51234", "inbound": true}
+        ]))
+        .unwrap();
+        let banned = vec![serde_json::json!({"address": "192.0.2.9/32"})];
+        let totals: NetTotals = serde_json::from_value(serde_json::json!({
+            "totalbytesrecv": 7_340_032,
+            "totalbytessent": 1_048_576,
+            "timemillis": 1_717_243_200_000u64
+        }))
+        .unwrap();
+        let info: NetworkInfo = serde_json::from_value(serde_json::json!({
+            "version": 270000,
+            "connections": 3,
+            "relayfee": 0.00001
+        }))
+        .unwrap();
+
+        let network = BitcoinNetworkMetrics::from_rpc(&peers, &banned, &totals, &info);
+        assert_eq!(network.peers, 3);
+        assert_eq!(network.inbound_peers, 1);
+        assert_eq!(network.banned_peers, 1);
+        assert_eq!(network.bytes_sent, 1_048_576);
+        assert_eq!(network.bytes_received, 7_340_032);
+        assert!((network.min_relay_fee - 1.0).abs() < 1e-9);
+    }
+
     #[tokio::test]
     #[ignore] // Only run with actual Bitcoin node
     async fn test_get_bitcoin_metrics() {
@@ -0,0 +1,324 @@
+//! Circuit breakers for the external services
+//!
+//! Each client has a circuit that opens after `[circuit_breaker]
+//! failure_threshold` calls in a row failed the way a dead or overloaded
+//! service fails (after their retries, see [`super::retry`]). While it is
+//! open, calls to that client fail straight away with [`CircuitOpen`]
+//! instead of waiting on timeouts. Once `cooldown_secs` have passed, the next
+//! call is let through as a probe: if it succeeds the circuit closes, if not
+//! it stays open for another cooldown.
+//!
+//! A call the service answered with an error still counts as a success here;
+//! the service is up, it just didn't like the request.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Failed calls in a row that open a client's circuit. 0 disables.
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// Seconds an open circuit turns calls away before letting one through
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_cooldown_secs() -> u64 {
+    60
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: default_failure_threshold(),
+            cooldown_secs: default_cooldown_secs(),
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.failure_threshold > 0 && self.cooldown_secs == 0 {
+            anyhow::bail!("circuit_breaker.cooldown_secs must be at least 1");
+        }
+        Ok(())
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::seconds(self.cooldown_secs as i64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    /// Calls are turned away until the cooldown ends
+    Open,
+    /// The cooldown has ended; the next call goes through as a probe
+    HalfOpen,
+}
+
+/// A call turned away because its client's circuit is open
+#[derive(Debug)]
+pub struct CircuitOpen {
+    pub client: String,
+    pub retry_at: DateTime<Utc>,
+}
+
+impl fmt::Display for CircuitOpen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is failing, calls are paused until {}",
+            self.client,
+            self.retry_at.to_rfc3339()
+        )
+    }
+}
+
+impl std::error::Error for CircuitOpen {}
+
+/// Circuit of one client, as served by `/health`
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CircuitStatus {
+    pub client: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// When the circuit last opened, or the last probe was let through
+    pub opened_at: Option<DateTime<Utc>>,
+    /// When calls are let through again, while open
+    pub retry_at: Option<DateTime<Utc>>,
+    /// Calls turned away since the process started
+    pub rejected_calls: u64,
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    opened_at: Option<DateTime<Utc>>,
+    rejected_calls: u64,
+}
+
+impl Circuit {
+    fn state(&self, cooldown: Duration, now: DateTime<Utc>) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if now - opened_at < cooldown => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+}
+
+/// Circuits of every client, keyed by the names used in the client metrics
+#[derive(Default)]
+pub struct CircuitBreaker {
+    config: Mutex<CircuitBreakerConfig>,
+    circuits: Mutex<BTreeMap<String, Circuit>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config: Mutex::new(config),
+            circuits: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Replace the thresholds; circuits keep their state
+    pub fn configure(&self, config: CircuitBreakerConfig) {
+        *self.config.lock().unwrap() = config;
+    }
+
+    /// Ask to call `client`
+    ///
+    /// Once the cooldown is over, the call that asks first is let through and
+    /// the cooldown starts again, so only one probe goes out per cooldown even
+    /// if it never reports back.
+    pub fn acquire(&self, client: &str, now: DateTime<Utc>) -> Result<(), CircuitOpen> {
+        let config = self.config.lock().unwrap().clone();
+        if config.failure_threshold == 0 {
+            return Ok(());
+        }
+
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits.entry(client.to_string()).or_default();
+        match circuit.state(config.cooldown(), now) {
+            CircuitState::Closed => Ok(()),
+            CircuitState::HalfOpen => {
+                tracing::info!("Probing {} after its circuit breaker cooldown", client);
+                circuit.opened_at = Some(now);
+                Ok(())
+            }
+            CircuitState::Open => {
+                circuit.rejected_calls += 1;
+                Err(CircuitOpen {
+                    client: client.to_string(),
+                    retry_at: circuit.opened_at.unwrap_or(now) + config.cooldown(),
+                })
+            }
+        }
+    }
+
+    /// Record how a call to `client` went
+    ///
+    /// `failed` is for failures that point at the service being down, not
+    /// for errors it answered with.
+    pub fn record(&self, client: &str, failed: bool, now: DateTime<Utc>) {
+        let threshold = self.config.lock().unwrap().failure_threshold;
+        if threshold == 0 {
+            return;
+        }
+
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits.entry(client.to_string()).or_default();
+        if !failed {
+            if circuit.opened_at.take().is_some() {
+                tracing::info!("{} is answering again, closing its circuit", client);
+            }
+            circuit.consecutive_failures = 0;
+            return;
+        }
+
+        circuit.consecutive_failures = circuit.consecutive_failures.saturating_add(1);
+        if circuit.opened_at.is_none() && circuit.consecutive_failures >= threshold {
+            tracing::warn!(
+                "{} failed {} calls in a row, opening its circuit",
+                client,
+                circuit.consecutive_failures
+            );
+            circuit.opened_at = Some(now);
+        }
+    }
+
+    /// State of the circuit of `client`, `None` before its first call
+    pub fn state(&self, client: &str, now: DateTime<Utc>) -> Option<CircuitState> {
+        let cooldown = self.config.lock().unwrap().cooldown();
+        self.circuits
+            .lock()
+            .unwrap()
+            .get(client)
+            .map(|circuit| circuit.state(cooldown, now))
+    }
+
+    /// Every circuit that has seen a call, by client name
+    pub fn status(&self, now: DateTime<Utc>) -> Vec<CircuitStatus> {
+        let cooldown = self.config.lock().unwrap().cooldown();
+        self.circuits
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(client, circuit)| {
+                let state = circuit.state(cooldown, now);
+                CircuitStatus {
+                    client: client.clone(),
+                    state,
+                    consecutive_failures: circuit.consecutive_failures,
+                    opened_at: circuit.opened_at,
+                    retry_at: match state {
+                        CircuitState::Open => circuit.opened_at.map(|opened| opened + cooldown),
+                        _ => None,
+                    },
+                    rejected_calls: circuit.rejected_calls,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Process-wide circuit breaker shared by all service clients
+pub fn global() -> &'static CircuitBreaker {
+    static BREAKER: OnceLock<CircuitBreaker> = OnceLock::new();
+    BREAKER.get_or_init(CircuitBreaker::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 3,
+            cooldown_secs: 60,
+        })
+    }
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker = breaker();
+        let now = Utc::now();
+
+        for _ in 0..2 {
+            breaker.record("bitcoind", true, now);
+        }
+        assert_eq!(breaker.state("bitcoind", now), Some(CircuitState::Closed));
+        assert!(breaker.acquire("bitcoind", now).is_ok());
+
+        breaker.record("bitcoind", true, now);
+        assert_eq!(breaker.state("bitcoind", now), Some(CircuitState::Open));
+        let refused = breaker.acquire("bitcoind", now).unwrap_err();
+        assert_eq!(refused.retry_at, now + Duration::seconds(60));
+
+        // Other clients are unaffected
+        assert!(breaker.acquire("kraken", now).is_ok());
+        assert_eq!(breaker.status(now)[0].rejected_calls, 1);
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let breaker = breaker();
+        let now = Utc::now();
+
+        breaker.record("asb", true, now);
+        breaker.record("asb", true, now);
+        breaker.record("asb", false, now);
+        breaker.record("asb", true, now);
+        assert_eq!(breaker.state("asb", now), Some(CircuitState::Closed));
+    }
+
+    #[test]
+    fn test_probe_after_cooldown() {
+        let breaker = breaker();
+        let opened = Utc::now();
+        for _ in 0..3 {
+            breaker.record("monerod", true, opened);
+        }
+
+        let later = opened + Duration::seconds(61);
+        assert_eq!(
+            breaker.state("monerod", later),
+            Some(CircuitState::HalfOpen)
+        );
+        assert!(breaker.acquire("monerod", later).is_ok());
+        // Only one probe per cooldown
+        assert!(breaker.acquire("monerod", later).is_err());
+
+        // A failed probe keeps it open, a successful one closes it
+        breaker.record("monerod", true, later);
+        assert_eq!(breaker.state("monerod", later), Some(CircuitState::Open));
+        breaker.record("monerod", false, later);
+        assert_eq!(breaker.state("monerod", later), Some(CircuitState::Closed));
+    }
+
+    #[test]
+    fn test_disabled() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 0,
+            ..CircuitBreakerConfig::default()
+        });
+        let now = Utc::now();
+        for _ in 0..10 {
+            breaker.record("kraken", true, now);
+        }
+        assert!(breaker.acquire("kraken", now).is_ok());
+    }
+}
@@ -0,0 +1,421 @@
+//! Exchange-neutral interface the trading engine trades through
+//!
+//! The engine only ever trades the BTC/XMR market, so the trait is phrased in
+//! those terms rather than as a general exchange API: an order either buys
+//! XMR with BTC or sells XMR for BTC, and deposits and withdrawals are of one
+//! of the two [`Asset`]s.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use utoipa::ToSchema;
+
+use super::binance::BinanceClient;
+use super::kraken::{parse_kraken_f64, KrakenClient};
+
+/// An asset moved between our wallets and the exchange
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Asset {
+    Btc,
+    Xmr,
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Asset::Btc => "BTC",
+            Asset::Xmr => "XMR",
+        })
+    }
+}
+
+/// Top of book and last trade on the BTC/XMR market
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ticker {
+    pub bid: f64,
+    pub ask: f64,
+    pub last: f64,
+}
+
+impl Ticker {
+    /// Halfway between the bid and the ask
+    pub fn mid(&self) -> f64 {
+        (self.bid + self.ask) / 2.0
+    }
+}
+
+/// Funds held on the exchange; `None` for an asset the account has never held
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExchangeBalances {
+    pub btc: Option<f64>,
+    pub xmr: Option<f64>,
+}
+
+/// Direction of an order on the BTC/XMR market
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderSide {
+    /// Spend BTC on XMR; the volume is in BTC
+    BuyXmr,
+    /// Sell XMR for BTC; the volume is in XMR
+    SellXmr,
+}
+
+/// An order to place; market unless a limit price is given
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderRequest {
+    pub side: OrderSide,
+    pub volume: String,
+    pub limit_price: Option<String>,
+}
+
+/// Lifecycle of a placed order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    /// Accepted and not yet fully filled
+    Open,
+    /// Fully filled
+    Closed,
+    Canceled,
+    Expired,
+}
+
+/// Fill progress of an order, with the exchange's numeric strings left unparsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderReport {
+    pub state: OrderState,
+    /// Volume filled so far
+    pub executed: String,
+    /// BTC value of the fills
+    pub cost: String,
+    /// Average fill price
+    pub price: String,
+    /// Fee charged on the fills, in BTC; empty if the exchange doesn't report it
+    pub fee: String,
+}
+
+/// Where a deposit or withdrawal stands on the exchange
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferState {
+    Pending,
+    /// Credited to the account (deposit) or sent on-chain (withdrawal)
+    Succeeded,
+    Failed,
+    Canceled,
+}
+
+impl fmt::Display for TransferState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TransferState::Pending => "pending",
+            TransferState::Succeeded => "succeeded",
+            TransferState::Failed => "failed",
+            TransferState::Canceled => "canceled",
+        })
+    }
+}
+
+/// A deposit as listed by the exchange
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deposit {
+    pub refid: String,
+    /// On-chain txid; empty until the exchange has recorded it
+    pub txid: String,
+    pub amount: String,
+    /// Unix seconds
+    pub time: u64,
+    pub state: TransferState,
+}
+
+impl Deposit {
+    /// Whether the exchange has credited the deposit
+    pub fn is_credited(&self) -> bool {
+        self.state == TransferState::Succeeded
+    }
+}
+
+/// A withdrawal as listed by the exchange
+#[derive(Debug, Clone, PartialEq)]
+pub struct Withdrawal {
+    pub refid: String,
+    pub txid: String,
+    pub state: TransferState,
+    /// Fee charged, in the withdrawn asset; empty if the exchange doesn't report it
+    pub fee: String,
+}
+
+/// What the trading engine needs from an exchange
+pub trait Exchange: Clone + Send + Sync + 'static {
+    /// Name used in logs and transaction notes
+    fn name(&self) -> &'static str;
+
+    fn ticker(&self) -> impl Future<Output = Result<Ticker>> + Send;
+
+    fn balances(&self) -> impl Future<Output = Result<ExchangeBalances>> + Send;
+
+    /// Decimal places allowed in the volume of a [`OrderSide::BuyXmr`] order
+    fn volume_decimals(&self) -> impl Future<Output = Result<u32>> + Send;
+
+    /// Address to deposit `asset` to; `new` asks for a fresh one where the exchange allows it
+    fn deposit_address(
+        &self,
+        asset: Asset,
+        new: bool,
+    ) -> impl Future<Output = Result<String>> + Send;
+
+    /// Place an order, returning its id
+    fn submit_order(&self, order: &OrderRequest) -> impl Future<Output = Result<String>> + Send;
+
+    /// Fill progress of an order, `None` if the exchange doesn't know it
+    fn order_status(
+        &self,
+        order_id: &str,
+    ) -> impl Future<Output = Result<Option<OrderReport>>> + Send;
+
+    /// Cancel the order `order_id`, returning whether it was still open
+    fn cancel_order(&self, order_id: &str) -> impl Future<Output = Result<bool>> + Send;
+
+    /// Cancel every open order, returning how many were cancelled
+    fn cancel_open_orders(&self) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Withdraw `amount` of `asset` to `address`, returning the withdrawal's reference
+    ///
+    /// Exchanges that only pay out to destinations registered in advance
+    /// (Kraken's withdrawal keys) check that the registered one is `address`
+    /// and fail without withdrawing when it isn't.
+    fn withdraw_to(
+        &self,
+        asset: Asset,
+        amount: &str,
+        address: &str,
+    ) -> impl Future<Output = Result<String>> + Send;
+
+    /// Recent deposits of `asset`
+    fn deposits(&self, asset: Asset) -> impl Future<Output = Result<Vec<Deposit>>> + Send;
+
+    /// Recent withdrawals of `asset`
+    fn withdrawals(&self, asset: Asset) -> impl Future<Output = Result<Vec<Withdrawal>>> + Send;
+
+    /// Taker fee on BTC/XMR orders, in percent of the order value
+    fn trading_fee_percent(&self) -> impl Future<Output = Result<f64>> + Send;
+
+    /// Fee for withdrawing `amount` of `asset` to our wallet, in that asset
+    fn withdrawal_fee(
+        &self,
+        asset: Asset,
+        amount: &str,
+    ) -> impl Future<Output = Result<f64>> + Send;
+}
+
+/// The exchange selected in the configuration
+#[derive(Debug, Clone)]
+pub enum ExchangeClient {
+    Kraken(KrakenClient),
+    Binance(BinanceClient),
+}
+
+impl From<KrakenClient> for ExchangeClient {
+    fn from(client: KrakenClient) -> Self {
+        Self::Kraken(client)
+    }
+}
+
+impl From<BinanceClient> for ExchangeClient {
+    fn from(client: BinanceClient) -> Self {
+        Self::Binance(client)
+    }
+}
+
+impl Exchange for ExchangeClient {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Kraken(client) => client.name(),
+            Self::Binance(client) => client.name(),
+        }
+    }
+
+    async fn ticker(&self) -> Result<Ticker> {
+        match self {
+            Self::Kraken(client) => client.ticker().await,
+            Self::Binance(client) => client.ticker().await,
+        }
+    }
+
+    async fn balances(&self) -> Result<ExchangeBalances> {
+        match self {
+            Self::Kraken(client) => client.balances().await,
+            Self::Binance(client) => client.balances().await,
+        }
+    }
+
+    async fn volume_decimals(&self) -> Result<u32> {
+        match self {
+            Self::Kraken(client) => client.volume_decimals().await,
+            Self::Binance(client) => client.volume_decimals().await,
+        }
+    }
+
+    async fn deposit_address(&self, asset: Asset, new: bool) -> Result<String> {
+        match self {
+            Self::Kraken(client) => client.deposit_address(asset, new).await,
+            Self::Binance(client) => client.deposit_address(asset, new).await,
+        }
+    }
+
+    async fn submit_order(&self, order: &OrderRequest) -> Result<String> {
+        match self {
+            Self::Kraken(client) => client.submit_order(order).await,
+            Self::Binance(client) => client.submit_order(order).await,
+        }
+    }
+
+    async fn order_status(&self, order_id: &str) -> Result<Option<OrderReport>> {
+        match self {
+            Self::Kraken(client) => client.order_status(order_id).await,
+            Self::Binance(client) => client.order_status(order_id).await,
+        }
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> Result<bool> {
+        match self {
+            Self::Kraken(client) => Exchange::cancel_order(client, order_id).await,
+            Self::Binance(client) => client.cancel_order(order_id).await,
+        }
+    }
+
+    async fn cancel_open_orders(&self) -> Result<u64> {
+        match self {
+            Self::Kraken(client) => client.cancel_open_orders().await,
+            Self::Binance(client) => client.cancel_open_orders().await,
+        }
+    }
+
+    async fn withdraw_to(&self, asset: Asset, amount: &str, address: &str) -> Result<String> {
+        match self {
+            Self::Kraken(client) => client.withdraw_to(asset, amount, address).await,
+            Self::Binance(client) => client.withdraw_to(asset, amount, address).await,
+        }
+    }
+
+    async fn deposits(&self, asset: Asset) -> Result<Vec<Deposit>> {
+        match self {
+            Self::Kraken(client) => client.deposits(asset).await,
+            Self::Binance(client) => client.deposits(asset).await,
+        }
+    }
+
+    async fn withdrawals(&self, asset: Asset) -> Result<Vec<Withdrawal>> {
+        match self {
+            Self::Kraken(client) => client.withdrawals(asset).await,
+            Self::Binance(client) => client.withdrawals(asset).await,
+        }
+    }
+
+    async fn trading_fee_percent(&self) -> Result<f64> {
+        match self {
+            Self::Kraken(client) => client.trading_fee_percent().await,
+            Self::Binance(client) => client.trading_fee_percent().await,
+        }
+    }
+
+    async fn withdrawal_fee(&self, asset: Asset, amount: &str) -> Result<f64> {
+        match self {
+            Self::Kraken(client) => client.withdrawal_fee(asset, amount).await,
+            Self::Binance(client) => client.withdrawal_fee(asset, amount).await,
+        }
+    }
+}
+
+/// Largest difference between a sent amount and the exchange's figure that still counts as a match
+const DEPOSIT_AMOUNT_TOLERANCE: f64 = 1e-8;
+
+/// Find the deposit for the on-chain transaction `txid`
+///
+/// An exchange can list a deposit before it has recorded the txid, so when no
+/// entry carries it, fall back to an entry without a txid for exactly
+/// `amount` that arrived at or after `since` (Unix seconds). The fallback only
+/// matches when a single entry fits; several identical deposits can't be told
+/// apart.
+pub fn find_deposit<'a>(
+    deposits: &'a [Deposit],
+    txid: &str,
+    amount: f64,
+    since: u64,
+) -> Option<&'a Deposit> {
+    if let Some(deposit) = deposits.iter().find(|d| d.txid == txid) {
+        return Some(deposit);
+    }
+
+    let mut candidates = deposits.iter().filter(|d| {
+        d.txid.trim().is_empty()
+            && d.time >= since
+            && parse_kraken_f64(Some(&d.amount))
+                .ok()
+                .flatten()
+                .is_some_and(|credited| (credited - amount).abs() <= DEPOSIT_AMOUNT_TOLERANCE)
+    });
+    match (candidates.next(), candidates.next()) {
+        (Some(deposit), None) => Some(deposit),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deposit(txid: &str, amount: &str, time: u64, state: TransferState) -> Deposit {
+        Deposit {
+            refid: format!("ref-{}", time),
+            txid: txid.to_string(),
+            amount: amount.to_string(),
+            time,
+            state,
+        }
+    }
+
+    #[test]
+    fn test_find_deposit_by_txid() {
+        // Another deposit is newer and already credited; it must not be ours
+        let deposits = vec![
+            deposit("other", "0.50000000", 2_000, TransferState::Succeeded),
+            deposit("ours", "0.10000000", 1_500, TransferState::Pending),
+        ];
+
+        let found = find_deposit(&deposits, "ours", 0.1, 1_000).unwrap();
+        assert_eq!(found.txid, "ours");
+        assert!(!found.is_credited());
+
+        assert!(find_deposit(&deposits, "missing", 0.3, 1_000).is_none());
+    }
+
+    #[test]
+    fn test_find_deposit_falls_back_to_amount_and_time() {
+        let deposits = vec![
+            deposit("", "0.10000000", 500, TransferState::Succeeded),
+            deposit("", "0.10000000", 1_500, TransferState::Succeeded),
+            deposit("", "0.20000000", 1_600, TransferState::Succeeded),
+        ];
+
+        // Only the entry after the send time with the right amount matches
+        let found = find_deposit(&deposits, "ours", 0.1, 1_000).unwrap();
+        assert_eq!(found.time, 1_500);
+
+        // Entries with some other txid are never claimed
+        let deposits = vec![deposit(
+            "theirs",
+            "0.10000000",
+            1_500,
+            TransferState::Succeeded,
+        )];
+        assert!(find_deposit(&deposits, "ours", 0.1, 1_000).is_none());
+
+        // Two identical candidates are ambiguous
+        let deposits = vec![
+            deposit("", "0.10000000", 1_500, TransferState::Succeeded),
+            deposit("", "0.10000000", 1_700, TransferState::Succeeded),
+        ];
+        assert!(find_deposit(&deposits, "ours", 0.1, 1_000).is_none());
+    }
+}
@@ -1,9 +1,19 @@
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
 use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256, Sha512};
 use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::exchange::{
+    Asset, Deposit, Exchange, ExchangeBalances, OrderReport, OrderRequest, OrderSide, OrderState,
+    Ticker, TransferState, Withdrawal,
+};
+use super::kraken_nonce::NonceSource;
+use super::retry;
+use crate::metrics::{clients, PriceMetrics};
 
 type HmacSha512 = Hmac<Sha512>;
 
@@ -14,15 +24,73 @@ const KRAKEN_API_URL: &str = "https://api.kraken.com";
 /// API keys can have different permissions configured in the Kraken dashboard.
 /// For testing, create API keys with limited permissions (query only, no trading/withdrawals).
 ///
+/// The client can hold a query-only read key next to the trade key; each
+/// private endpoint is signed with the key for its [`KeyScope`]. Public
+/// endpoints such as the ticker are sent without any key.
+///
 /// # Environment Variables for Testing
 /// - KRAKEN_API_KEY: Kraken API key
 /// - KRAKEN_API_SECRET: Kraken API secret
+#[derive(Debug, Clone)]
 pub struct KrakenClient {
-    api_key: String,
-    api_secret: String,
+    read_key: Option<KrakenCredentials>,
+    trade_key: Option<KrakenCredentials>,
+    base_url: String,
+    nonce: NonceSource,
     client: reqwest::Client,
 }
 
+/// API key and base64 secret pair
+#[derive(Clone, PartialEq, Eq)]
+pub struct KrakenCredentials {
+    pub api_key: String,
+    pub api_secret: String,
+}
+
+impl KrakenCredentials {
+    /// Credentials from a key/secret pair, `None` when either is empty
+    pub fn new(api_key: String, api_secret: String) -> Option<Self> {
+        if api_key.is_empty() || api_secret.is_empty() {
+            None
+        } else {
+            Some(Self {
+                api_key,
+                api_secret,
+            })
+        }
+    }
+}
+
+impl std::fmt::Debug for KrakenCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KrakenCredentials")
+            .field("api_key", &self.api_key)
+            .field("api_secret", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Permission level a private endpoint needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyScope {
+    /// Balances and order/deposit/withdrawal status
+    Read,
+    /// Placing or cancelling orders, deposit addresses and withdrawals
+    Trade,
+}
+
+impl KeyScope {
+    /// Scope required by a private endpoint; unknown endpoints need the trade key
+    pub fn for_endpoint(endpoint: &str) -> Self {
+        match endpoint {
+            "Balance" | "QueryOrders" | "DepositStatus" | "WithdrawStatus" | "TradeVolume" => {
+                Self::Read
+            }
+            _ => Self::Trade,
+        }
+    }
+}
+
 /// Kraken API error response
 #[derive(Debug, Deserialize)]
 struct KrakenErrorResponse {
@@ -36,6 +104,27 @@ struct KrakenResponse<T> {
     result: Option<T>,
 }
 
+/// Errors Kraken answers with when it turned a request away unprocessed
+const TRANSIENT_ERRORS: [&str; 4] = [
+    "EAPI:Rate limit exceeded",
+    "EAPI:Invalid nonce",
+    "EService:Unavailable",
+    "EService:Busy",
+];
+
+/// Error for a response listing `errors`, retryable if Kraken didn't act on it
+fn api_error(errors: &[String]) -> anyhow::Error {
+    let message = format!("Kraken API error: {:?}", errors);
+    if errors
+        .iter()
+        .any(|error| TRANSIENT_ERRORS.contains(&error.as_str()))
+    {
+        retry::Unavailable(message).into()
+    } else {
+        anyhow::anyhow!(message)
+    }
+}
+
 /// Ticker information
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TickerInfo {
@@ -53,6 +142,37 @@ pub struct TickerInfo {
     pub open: String, // Today's opening price
 }
 
+/// Server time from the public `Time` endpoint
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ServerTime {
+    pub unixtime: i64,
+    pub rfc1123: String,
+}
+
+/// Asset metadata from the public `Assets` endpoint
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AssetInfo {
+    /// Short name, e.g. "XBT" for the "XXBT" entry
+    pub altname: String,
+    /// Decimals Kraken records amounts of this asset with
+    pub decimals: u32,
+    /// Decimals Kraken shows amounts with
+    pub display_decimals: u32,
+}
+
+/// Trading pair metadata from the public `AssetPairs` endpoint
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AssetPairInfo {
+    pub altname: String,
+    /// Decimals allowed in an order volume; volumes must be multiples of `10^-lot_decimals`
+    pub lot_decimals: u32,
+    /// Decimals allowed in an order price
+    pub pair_decimals: u32,
+    /// Minimum order volume
+    #[serde(default)]
+    pub ordermin: Option<String>,
+}
+
 /// Order information
 #[derive(Debug, Deserialize, Serialize)]
 pub struct OrderInfo {
@@ -75,8 +195,13 @@ pub struct OrderStatus {
     pub closetm: Option<f64>,
     pub vol: String,
     pub vol_exec: String,
+    /// Numeric fields below may be missing depending on order type; read
+    /// them with [`parse_kraken_f64`]
+    #[serde(default)]
     pub cost: String,
+    #[serde(default)]
     pub fee: String,
+    #[serde(default)]
     pub price: String,
     pub descr: OrderStatusDescription,
 }
@@ -111,12 +236,58 @@ pub struct DepositMethod {
     pub gen_address: Option<bool>,
 }
 
+/// Result of cancelling one or all open orders
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CancelAllResult {
+    /// Number of orders cancelled
+    pub count: u64,
+}
+
 /// Withdrawal information
 #[derive(Debug, Deserialize, Serialize)]
 pub struct WithdrawalInfo {
     pub refid: String, // Reference ID for the withdrawal
 }
 
+/// A withdrawal destination registered in the account, from `WithdrawAddresses`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WithdrawAddress {
+    pub address: String,
+    pub asset: String,
+    pub method: String,
+    /// Name the destination is withdrawn to by
+    pub key: String,
+    #[serde(default)]
+    pub verified: bool,
+}
+
+/// 30-day volume and fee tier from the `TradeVolume` endpoint
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TradeVolume {
+    pub currency: String,
+    pub volume: String,
+    /// Taker fees by pair, only for the pairs asked for
+    #[serde(default)]
+    pub fees: HashMap<String, FeeTier>,
+}
+
+/// Fee schedule position for one pair
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FeeTier {
+    /// Current fee in percent
+    pub fee: String,
+}
+
+/// Withdrawal quote from the `WithdrawInfo` endpoint
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WithdrawQuote {
+    pub method: String,
+    pub limit: String,
+    /// Amount that would arrive after the fee
+    pub amount: String,
+    pub fee: String,
+}
+
 /// Deposit status
 #[derive(Debug, Deserialize, Serialize)]
 pub struct DepositStatus {
@@ -147,21 +318,92 @@ pub struct WithdrawalStatus {
     pub status: String,
 }
 
+/// Parse one of Kraken's numeric string fields
+///
+/// Missing and empty (or whitespace-only) fields are `None`, as Kraken omits
+/// or blanks some of them depending on order type. Anything else that is not
+/// a finite number is an error.
+pub fn parse_kraken_f64(value: Option<&str>) -> Result<Option<f64>> {
+    let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+        return Ok(None);
+    };
+
+    let number: f64 = value
+        .parse()
+        .with_context(|| format!("Invalid Kraken number: '{}'", value))?;
+    if !number.is_finite() {
+        anyhow::bail!("Invalid Kraken number: '{}'", value);
+    }
+
+    Ok(Some(number))
+}
+
 impl KrakenClient {
-    /// Create a new Kraken API client
+    /// Create a new Kraken API client using a single key for every operation
     pub fn new(api_key: String, api_secret: String) -> Self {
+        Self::with_keys(None, KrakenCredentials::new(api_key, api_secret))
+    }
+
+    /// Create a client for public endpoints only
+    pub fn public() -> Self {
+        Self::with_keys(None, None)
+    }
+
+    /// Create a client with separate read and trade keys
+    ///
+    /// Read operations fall back to the trade key when no read key is given;
+    /// trade operations never use the read key.
+    pub fn with_keys(
+        read_key: Option<KrakenCredentials>,
+        trade_key: Option<KrakenCredentials>,
+    ) -> Self {
         Self {
-            api_key,
-            api_secret,
+            read_key,
+            trade_key,
+            base_url: KRAKEN_API_URL.to_string(),
+            nonce: NonceSource::default(),
             client: reqwest::Client::new(),
         }
     }
 
+    /// Draw nonces from a file shared with other processes using the same key
+    ///
+    /// Without one, nonces come from a counter shared within this process only.
+    pub fn with_nonce_file(mut self, path: Option<PathBuf>) -> Self {
+        self.nonce = NonceSource::from_file(path);
+        self
+    }
+
+    /// Send requests to another API root, e.g. a local mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Credentials used to sign a request to a private endpoint
+    pub fn credentials_for(&self, endpoint: &str) -> Result<&KrakenCredentials> {
+        match KeyScope::for_endpoint(endpoint) {
+            KeyScope::Read => self
+                .read_key
+                .as_ref()
+                .or(self.trade_key.as_ref())
+                .context("No Kraken API key configured"),
+            KeyScope::Trade => self.trade_key.as_ref().with_context(|| {
+                format!("No Kraken trade key configured, required for {}", endpoint)
+            }),
+        }
+    }
+
     /// Generate API signature for authenticated requests
-    fn generate_signature(&self, url_path: &str, nonce: u64, postdata: &str) -> Result<String> {
+    fn generate_signature(
+        api_secret: &str,
+        url_path: &str,
+        nonce: u64,
+        postdata: &str,
+    ) -> Result<String> {
         // Decode base64 secret
         let secret = general_purpose::STANDARD
-            .decode(&self.api_secret)
+            .decode(api_secret)
             .context("Failed to decode API secret")?;
 
         // Create SHA256 hash of (nonce + postdata)
@@ -187,44 +429,49 @@ impl KrakenClient {
     where
         T: for<'de> Deserialize<'de>,
     {
-        let url = format!("{}/0/public/{}", KRAKEN_API_URL, endpoint);
-
-        let response = self
-            .client
-            .get(&url)
-            .query(params)
-            .send()
-            .await
-            .context("Failed to send request")?;
-
-        let kraken_response: KrakenResponse<T> =
-            response.json().await.context("Failed to parse response")?;
-
-        if !kraken_response.error.is_empty() {
-            anyhow::bail!("Kraken API error: {:?}", kraken_response.error);
-        }
-
-        kraken_response.result.context("Missing result in response")
+        let url = format!("{}/0/public/{}", self.base_url, endpoint);
+        let url = &url;
+
+        retry::retry("kraken", endpoint, true, || {
+            clients::track("kraken", endpoint, async move {
+                let response = self
+                    .client
+                    .get(url)
+                    .query(params)
+                    .send()
+                    .await
+                    .context("Failed to send request")?;
+
+                let kraken_response: KrakenResponse<T> = retry::check_status(response)
+                    .await?
+                    .json()
+                    .await
+                    .context("Failed to parse response")?;
+
+                if !kraken_response.error.is_empty() {
+                    return Err(api_error(&kraken_response.error));
+                }
+
+                kraken_response.result.context("Missing result in response")
+            })
+        })
+        .await
     }
 
-    /// Make a private API request (with authentication)
-    async fn private_request<T>(
+    /// Sign `params` with a fresh nonce for a private endpoint
+    ///
+    /// # Returns
+    /// The POST data and its signature
+    fn sign_private(
         &self,
-        endpoint: &str,
+        credentials: &KrakenCredentials,
+        url_path: &str,
         params: &mut HashMap<String, String>,
-    ) -> Result<T>
-    where
-        T: for<'de> Deserialize<'de>,
-    {
-        let nonce = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)?
-            .as_millis() as u64;
+    ) -> Result<(String, String)> {
+        let nonce = self.nonce.next()?;
 
         params.insert("nonce".to_string(), nonce.to_string());
 
-        let url_path = format!("/0/private/{}", endpoint);
-        let url = format!("{}{}", KRAKEN_API_URL, url_path);
-
         // Build POST data
         let postdata: String = params
             .iter()
@@ -233,27 +480,64 @@ impl KrakenClient {
             .join("&");
 
         // Generate signature
-        let signature = self.generate_signature(&url_path, nonce, &postdata)?;
-
-        let response = self
-            .client
-            .post(&url)
-            .header("API-Key", &self.api_key)
-            .header("API-Sign", signature)
-            .header("Content-Type", "application/x-www-form-urlencoded")
-            .body(postdata)
-            .send()
-            .await
-            .context("Failed to send request")?;
+        let signature =
+            Self::generate_signature(&credentials.api_secret, url_path, nonce, &postdata)?;
 
-        let kraken_response: KrakenResponse<T> =
-            response.json().await.context("Failed to parse response")?;
+        Ok((postdata, signature))
+    }
 
-        if !kraken_response.error.is_empty() {
-            anyhow::bail!("Kraken API error: {:?}", kraken_response.error);
-        }
+    /// Make a private API request (with authentication)
+    ///
+    /// Each attempt is signed with a new nonce. Only read-scope requests are
+    /// retried after a timeout, as an order or withdrawal may have gone through.
+    async fn private_request<T>(
+        &self,
+        endpoint: &str,
+        params: &mut HashMap<String, String>,
+    ) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let credentials = self.credentials_for(endpoint)?;
 
-        kraken_response.result.context("Missing result in response")
+        let url_path = format!("/0/private/{}", endpoint);
+        let url = format!("{}{}", self.base_url, url_path);
+        let url = &url;
+        let idempotent = KeyScope::for_endpoint(endpoint) == KeyScope::Read;
+
+        retry::retry("kraken", endpoint, idempotent, || {
+            let signed = self.sign_private(credentials, &url_path, params);
+            async move {
+                let (postdata, signature) = signed?;
+
+                clients::track("kraken", endpoint, async move {
+                    let response = self
+                        .client
+                        .post(url)
+                        .header("API-Key", &credentials.api_key)
+                        .header("API-Sign", signature)
+                        .header("Content-Type", "application/x-www-form-urlencoded")
+                        .body(postdata)
+                        .send()
+                        .await
+                        .context("Failed to send request")?;
+
+                    let kraken_response: KrakenResponse<T> = retry::check_status(response)
+                        .await?
+                        .json()
+                        .await
+                        .context("Failed to parse response")?;
+
+                    if !kraken_response.error.is_empty() {
+                        return Err(api_error(&kraken_response.error));
+                    }
+
+                    kraken_response.result.context("Missing result in response")
+                })
+                .await
+            }
+        })
+        .await
     }
 
     /// Get ticker information for a trading pair
@@ -269,6 +553,56 @@ impl KrakenClient {
             .context("No ticker info returned")
     }
 
+    /// Get BTC/USD, XMR/USD and XMR/BTC last trades with their change since
+    /// today's open
+    pub async fn get_prices(&self) -> Result<PriceMetrics> {
+        let (btc_usd, xmr_usd, xmr_btc) = tokio::try_join!(
+            self.get_ticker("XBTUSD"),
+            self.get_ticker("XMRUSD"),
+            self.get_ticker("XMRXBT"),
+        )?;
+
+        let (btc_usd, btc_usd_change_24h) = price_and_change(&btc_usd, "BTC/USD")?;
+        let (xmr_usd, xmr_usd_change_24h) = price_and_change(&xmr_usd, "XMR/USD")?;
+        let (xmr_btc, xmr_btc_change_24h) = price_and_change(&xmr_btc, "XMR/BTC")?;
+
+        Ok(PriceMetrics {
+            btc_usd,
+            btc_usd_change_24h,
+            xmr_usd,
+            xmr_usd_change_24h,
+            xmr_btc,
+            xmr_btc_change_24h,
+        })
+    }
+
+    /// Get Kraken's current server time (one-second resolution)
+    pub async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let time: ServerTime = self.public_request("Time", &[]).await?;
+
+        DateTime::from_timestamp(time.unixtime, 0)
+            .with_context(|| format!("Invalid Kraken server time {}", time.unixtime))
+    }
+
+    /// Get trading metadata (lot and price precision) for a pair
+    pub async fn get_asset_pair(&self, pair: &str) -> Result<AssetPairInfo> {
+        let result: HashMap<String, AssetPairInfo> =
+            self.public_request("AssetPairs", &[("pair", pair)]).await?;
+
+        result
+            .into_values()
+            .next()
+            .context("No asset pair info returned")
+    }
+
+    /// Get metadata for assets by name, e.g. `get_assets(&["XBT", "XMR"])`
+    ///
+    /// Keyed by Kraken's asset name ("XXBT"); match on [`AssetInfo::altname`].
+    pub async fn get_assets(&self, assets: &[&str]) -> Result<HashMap<String, AssetInfo>> {
+        self.public_request("Assets", &[("asset", &assets.join(","))])
+            .await
+    }
+
     /// Get account balance
     pub async fn get_balance(&self) -> Result<HashMap<String, String>> {
         self.private_request("Balance", &mut HashMap::new()).await
@@ -340,11 +674,25 @@ impl KrakenClient {
     ///
     /// # Arguments
     /// * `txid` - Transaction ID of the order to cancel
-    pub async fn cancel_order(&self, txid: &str) -> Result<HashMap<String, String>> {
+    ///
+    /// Returns the number of orders Kraken cancelled.
+    pub async fn cancel_order(&self, txid: &str) -> Result<u64> {
         let mut params = HashMap::new();
         params.insert("txid".to_string(), txid.to_string());
 
-        self.private_request("CancelOrder", &mut params).await
+        let result: CancelAllResult = self.private_request("CancelOrder", &mut params).await?;
+        Ok(result.count)
+    }
+
+    /// Cancel every open order on the account
+    ///
+    /// Returns the number of orders Kraken cancelled.
+    pub async fn cancel_all_orders(&self) -> Result<u64> {
+        let result: CancelAllResult = self
+            .private_request("CancelAll", &mut HashMap::new())
+            .await?;
+
+        Ok(result.count)
     }
 
     // ===== Deposit and Withdrawal Methods =====
@@ -431,6 +779,19 @@ impl KrakenClient {
         self.private_request("Withdraw", &mut params).await
     }
 
+    /// List the withdrawal destinations registered for `asset` under `key`
+    pub async fn get_withdraw_addresses(
+        &self,
+        asset: &str,
+        key: &str,
+    ) -> Result<Vec<WithdrawAddress>> {
+        let mut params = HashMap::new();
+        params.insert("asset".to_string(), asset.to_string());
+        params.insert("key".to_string(), key.to_string());
+
+        self.private_request("WithdrawAddresses", &mut params).await
+    }
+
     /// Withdraw Bitcoin to a pre-configured address
     ///
     /// # Arguments
@@ -449,6 +810,34 @@ impl KrakenClient {
         self.withdraw("XMR", key, amount).await
     }
 
+    /// Get the 30-day trade volume and current fee for `pair`
+    pub async fn get_trade_volume(&self, pair: &str) -> Result<TradeVolume> {
+        let mut params = HashMap::new();
+        params.insert("pair".to_string(), pair.to_string());
+
+        self.private_request("TradeVolume", &mut params).await
+    }
+
+    /// Quote the fee for a withdrawal without making it
+    ///
+    /// # Arguments
+    /// * `asset` - Asset to withdraw (e.g., "XBT", "XMR")
+    /// * `key` - Withdrawal key name configured in Kraken account
+    /// * `amount` - Amount to withdraw
+    pub async fn get_withdraw_info(
+        &self,
+        asset: &str,
+        key: &str,
+        amount: &str,
+    ) -> Result<WithdrawQuote> {
+        let mut params = HashMap::new();
+        params.insert("asset".to_string(), asset.to_string());
+        params.insert("key".to_string(), key.to_string());
+        params.insert("amount".to_string(), amount.to_string());
+
+        self.private_request("WithdrawInfo", &mut params).await
+    }
+
     /// Get status of recent deposits
     ///
     /// # Arguments
@@ -479,14 +868,488 @@ impl KrakenClient {
     }
 }
 
+/// Kraken's pair for the BTC/XMR market
+const XBTXMR: &str = "XBTXMR";
+
+/// Withdrawal keys the account must have set up for our own wallets
+const BTC_WITHDRAWAL_KEY: &str = "bitcoin_primary";
+const XMR_WITHDRAWAL_KEY: &str = "monero_primary";
+
+/// Kraken's code for an asset in requests
+fn kraken_asset(asset: Asset) -> &'static str {
+    match asset {
+        Asset::Btc => "XBT",
+        Asset::Xmr => "XMR",
+    }
+}
+
+/// Withdrawal key for our own wallet of `asset`
+fn withdrawal_key(asset: Asset) -> &'static str {
+    match asset {
+        Asset::Btc => BTC_WITHDRAWAL_KEY,
+        Asset::Xmr => XMR_WITHDRAWAL_KEY,
+    }
+}
+
+/// Check that the withdrawal key `key` pays out to `address`
+fn check_withdrawal_key(key: &str, registered: &[WithdrawAddress], address: &str) -> Result<()> {
+    let destination = registered
+        .iter()
+        .find(|destination| destination.key == key)
+        .with_context(|| format!("No Kraken withdrawal key '{}' configured", key))?;
+    if destination.address != address {
+        anyhow::bail!(
+            "Kraken withdrawal key '{}' pays out to {}, not to our wallet address {}",
+            key,
+            destination.address,
+            address
+        );
+    }
+    Ok(())
+}
+
+/// Deposit and withdrawal statuses as Kraken reports them
+fn transfer_state(status: &str) -> TransferState {
+    match status {
+        "Success" => TransferState::Succeeded,
+        "Failure" => TransferState::Failed,
+        "Canceled" => TransferState::Canceled,
+        _ => TransferState::Pending,
+    }
+}
+
+/// First value of a ticker field (`[price, ...]`) as a price
+fn ticker_price(field: &[String], name: &str) -> Result<f64> {
+    parse_kraken_f64(field.first().map(String::as_str))
+        .with_context(|| format!("Failed to parse BTC/XMR {} price", name))?
+        .with_context(|| format!("BTC/XMR ticker has no {} price", name))
+}
+
+/// Last trade price of a ticker and its percentage change since the open
+fn price_and_change(ticker: &TickerInfo, pair: &str) -> Result<(f64, f64)> {
+    let last: f64 = ticker
+        .last_trade
+        .first()
+        .with_context(|| format!("{} ticker has no last trade", pair))?
+        .parse()
+        .with_context(|| format!("Failed to parse {} price", pair))?;
+    let open: f64 = ticker
+        .open
+        .parse()
+        .with_context(|| format!("Failed to parse {} opening price", pair))?;
+
+    let change = if open != 0.0 {
+        (last - open) / open * 100.0
+    } else {
+        0.0
+    };
+    Ok((last, change))
+}
+
+impl Exchange for KrakenClient {
+    fn name(&self) -> &'static str {
+        "Kraken"
+    }
+
+    async fn ticker(&self) -> Result<Ticker> {
+        let ticker = self.get_ticker(XBTXMR).await?;
+        Ok(Ticker {
+            bid: ticker_price(&ticker.bid, "bid")?,
+            ask: ticker_price(&ticker.ask, "ask")?,
+            last: ticker_price(&ticker.last_trade, "last trade")?,
+        })
+    }
+
+    async fn balances(&self) -> Result<ExchangeBalances> {
+        let balances = self.get_balance().await?;
+        Ok(ExchangeBalances {
+            btc: parse_kraken_f64(balances.get("XXBT").map(String::as_str))
+                .context("Failed to parse Kraken BTC balance")?,
+            xmr: parse_kraken_f64(balances.get("XXMR").map(String::as_str))
+                .context("Failed to parse Kraken XMR balance")?,
+        })
+    }
+
+    async fn volume_decimals(&self) -> Result<u32> {
+        Ok(self.get_asset_pair(XBTXMR).await?.lot_decimals)
+    }
+
+    async fn deposit_address(&self, asset: Asset, new: bool) -> Result<String> {
+        match asset {
+            Asset::Btc => self.get_btc_deposit_address(new).await,
+            Asset::Xmr => self.get_xmr_deposit_address(new).await,
+        }
+    }
+
+    async fn submit_order(&self, order: &OrderRequest) -> Result<String> {
+        let side = match order.side {
+            OrderSide::BuyXmr => "buy",
+            OrderSide::SellXmr => "sell",
+        };
+        let order_type = if order.limit_price.is_some() {
+            "limit"
+        } else {
+            "market"
+        };
+
+        let placed = self
+            .place_order(
+                XBTXMR,
+                side,
+                order_type,
+                &order.volume,
+                order.limit_price.as_deref(),
+            )
+            .await?;
+        placed
+            .txid
+            .into_iter()
+            .next()
+            .context("No order ID returned from Kraken")
+    }
+
+    async fn order_status(&self, order_id: &str) -> Result<Option<OrderReport>> {
+        let mut orders = self.query_order(order_id).await?;
+        Ok(orders.remove(order_id).map(|order| OrderReport {
+            state: match order.status.as_str() {
+                "closed" => OrderState::Closed,
+                "canceled" => OrderState::Canceled,
+                "expired" => OrderState::Expired,
+                _ => OrderState::Open,
+            },
+            executed: order.vol_exec,
+            cost: order.cost,
+            price: order.price,
+            fee: order.fee,
+        }))
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> Result<bool> {
+        Ok(KrakenClient::cancel_order(self, order_id).await? > 0)
+    }
+
+    async fn cancel_open_orders(&self) -> Result<u64> {
+        self.cancel_all_orders().await
+    }
+
+    async fn withdraw_to(&self, asset: Asset, amount: &str, address: &str) -> Result<String> {
+        // Kraken only withdraws to destinations registered under a key name
+        let key = withdrawal_key(asset);
+        let registered = self
+            .get_withdraw_addresses(kraken_asset(asset), key)
+            .await?;
+        check_withdrawal_key(key, &registered, address)?;
+
+        Ok(self.withdraw(kraken_asset(asset), key, amount).await?.refid)
+    }
+
+    async fn deposits(&self, asset: Asset) -> Result<Vec<Deposit>> {
+        let deposits = self.get_deposit_status(Some(kraken_asset(asset))).await?;
+        Ok(deposits
+            .into_iter()
+            .map(|deposit| Deposit {
+                state: transfer_state(&deposit.status),
+                refid: deposit.refid,
+                txid: deposit.txid,
+                amount: deposit.amount,
+                time: deposit.time,
+            })
+            .collect())
+    }
+
+    async fn withdrawals(&self, asset: Asset) -> Result<Vec<Withdrawal>> {
+        let withdrawals = self
+            .get_withdrawal_status(Some(kraken_asset(asset)))
+            .await?;
+        Ok(withdrawals
+            .into_iter()
+            .map(|withdrawal| Withdrawal {
+                state: transfer_state(&withdrawal.status),
+                refid: withdrawal.refid,
+                txid: withdrawal.txid,
+                fee: withdrawal.fee,
+            })
+            .collect())
+    }
+
+    async fn trading_fee_percent(&self) -> Result<f64> {
+        let volume = self.get_trade_volume(XBTXMR).await?;
+        // Kraken keys the fee by its own name for the pair, so take the only entry
+        let tier = volume
+            .fees
+            .values()
+            .next()
+            .context("Kraken returned no fee for the BTC/XMR pair")?;
+        parse_kraken_f64(Some(&tier.fee))
+            .context("Failed to parse Kraken trading fee")?
+            .context("Kraken returned an empty trading fee")
+    }
+
+    async fn withdrawal_fee(&self, asset: Asset, amount: &str) -> Result<f64> {
+        let quote = self
+            .get_withdraw_info(kraken_asset(asset), withdrawal_key(asset), amount)
+            .await?;
+        parse_kraken_f64(Some(&quote.fee))
+            .with_context(|| format!("Failed to parse Kraken {} withdrawal fee", asset))?
+            .with_context(|| format!("Kraken returned an empty {} withdrawal fee", asset))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_price_and_change() {
+        let ticker = |last: &str, open: &str| TickerInfo {
+            ask: vec![],
+            bid: vec![],
+            last_trade: vec![last.to_string(), "0.1".to_string()],
+            volume: vec![],
+            vwap: vec![],
+            open: open.to_string(),
+        };
+
+        let (price, change) = price_and_change(&ticker("105.0", "100.0"), "XMR/USD").unwrap();
+        assert_eq!(price, 105.0);
+        assert!((change - 5.0).abs() < 1e-9);
+
+        // No open yet reads as unchanged rather than dividing by zero
+        assert_eq!(
+            price_and_change(&ticker("105.0", "0"), "XMR/USD").unwrap(),
+            (105.0, 0.0)
+        );
+        assert!(price_and_change(&ticker("n/a", "100.0"), "XMR/USD").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_all_orders_calls_cancel_all() {
+        use axum::{http::HeaderMap, routing::post, Json, Router};
+        use std::sync::{Arc, Mutex};
+
+        // Mock Kraken: records the calls it receives and cancels three orders
+        let calls: Arc<Mutex<Vec<String>>> = Arc::default();
+        let recorded = calls.clone();
+        let app = Router::new().route(
+            "/0/private/CancelAll",
+            post(move |headers: HeaderMap| async move {
+                let key = headers
+                    .get("API-Key")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                recorded.lock().unwrap().push(key);
+                Json(serde_json::json!({"error": [], "result": {"count": 3}}))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        // Signing needs a base64 secret
+        let trade = KrakenCredentials::new(
+            "trade_key".to_string(),
+            general_purpose::STANDARD.encode("trade_secret"),
+        );
+        let client =
+            KrakenClient::with_keys(Some(credentials("read")), trade).with_base_url(base_url);
+        let cancelled = client.cancel_all_orders().await.unwrap();
+
+        assert_eq!(cancelled, 3);
+        // Signed with the trade key, never the query-only key
+        assert_eq!(*calls.lock().unwrap(), vec!["trade_key".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_kraken_f64() {
+        // Missing and empty fields degrade to None
+        assert_eq!(parse_kraken_f64(None).unwrap(), None);
+        assert_eq!(parse_kraken_f64(Some("")).unwrap(), None);
+        assert_eq!(parse_kraken_f64(Some("  ")).unwrap(), None);
+
+        assert_eq!(parse_kraken_f64(Some("0")).unwrap(), Some(0.0));
+        assert_eq!(parse_kraken_f64(Some("0.00000")).unwrap(), Some(0.0));
+        assert_eq!(
+            parse_kraken_f64(Some("0.00612340")).unwrap(),
+            Some(0.0061234)
+        );
+        assert_eq!(parse_kraken_f64(Some(" 155.5 ")).unwrap(), Some(155.5));
+
+        assert!(parse_kraken_f64(Some("abc")).is_err());
+        assert!(parse_kraken_f64(Some("1.2.3")).is_err());
+        assert!(parse_kraken_f64(Some("NaN")).is_err());
+        assert!(parse_kraken_f64(Some("inf")).is_err());
+    }
+
+    #[test]
+    fn test_order_status_tolerates_missing_numeric_fields() {
+        let json = r#"{
+            "status": "closed",
+            "opentm": 1700000000.0,
+            "closetm": 1700000001.0,
+            "vol": "1.50000000",
+            "vol_exec": "1.50000000",
+            "descr": {"pair": "XMRXBT", "type": "buy", "ordertype": "market", "price": "0", "price2": "0"}
+        }"#;
+        let status: OrderStatus = serde_json::from_str(json).unwrap();
+
+        assert_eq!(parse_kraken_f64(Some(&status.price)).unwrap(), None);
+        assert_eq!(parse_kraken_f64(Some(&status.cost)).unwrap(), None);
+    }
+
+    fn credentials(name: &str) -> KrakenCredentials {
+        KrakenCredentials::new(format!("{}_key", name), format!("{}_secret", name)).unwrap()
+    }
+
+    #[test]
+    fn test_operations_use_key_for_their_scope() {
+        let client = KrakenClient::with_keys(Some(credentials("read")), Some(credentials("trade")));
+
+        assert_eq!(
+            client.credentials_for("Balance").unwrap().api_key,
+            "read_key"
+        );
+        assert_eq!(
+            client.credentials_for("QueryOrders").unwrap().api_key,
+            "read_key"
+        );
+        assert_eq!(
+            client.credentials_for("Withdraw").unwrap().api_key,
+            "trade_key"
+        );
+        assert_eq!(
+            client.credentials_for("AddOrder").unwrap().api_key,
+            "trade_key"
+        );
+        assert_eq!(
+            client.credentials_for("DepositAddresses").unwrap().api_key,
+            "trade_key"
+        );
+        assert_eq!(
+            client.credentials_for("TradeVolume").unwrap().api_key,
+            "read_key"
+        );
+        // Quoting a withdrawal fee needs the withdraw permission
+        assert_eq!(
+            client.credentials_for("WithdrawInfo").unwrap().api_key,
+            "trade_key"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_trading_fee_from_trade_volume() {
+        use axum::{routing::post, Json, Router};
+
+        let app = Router::new().route(
+            "/0/private/TradeVolume",
+            post(|| async {
+                Json(serde_json::json!({"error": [], "result": {
+                    "currency": "ZUSD",
+                    "volume": "1200.5000",
+                    "fees": {"XXMRXXBT": {"fee": "0.2600", "minfee": "0.1000", "maxfee": "0.2600"}}
+                }}))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let read = KrakenCredentials::new(
+            "read_key".to_string(),
+            general_purpose::STANDARD.encode("read_secret"),
+        );
+        let client = KrakenClient::with_keys(read, None).with_base_url(base_url);
+
+        assert_eq!(client.trading_fee_percent().await.unwrap(), 0.26);
+    }
+
+    #[tokio::test]
+    async fn test_withdrawal_only_to_the_registered_address() {
+        use axum::{routing::post, Json, Router};
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        let withdrawals = Arc::new(AtomicU32::new(0));
+        let counter = withdrawals.clone();
+        let app = Router::new()
+            .route(
+                "/0/private/WithdrawAddresses",
+                post(|| async {
+                    Json(serde_json::json!({"error": [], "result": [{
+                        "address": "bc1qours", "asset": "XXBT", "method": "Bitcoin",
+                        "key": "bitcoin_primary", "verified": true
+                    }]}))
+                }),
+            )
+            .route(
+                "/0/private/Withdraw",
+                post(move || async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    Json(serde_json::json!({"error": [], "result": {"refid": "AGBSO6T-UFMTTQ-I7KGS6"}}))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let trade = KrakenCredentials::new(
+            "trade_key".to_string(),
+            general_purpose::STANDARD.encode("trade_secret"),
+        );
+        let client = KrakenClient::with_keys(None, trade).with_base_url(base_url);
+
+        let error = client
+            .withdraw_to(Asset::Btc, "0.1", "bc1qsomeoneelse")
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("pays out to bc1qours"),
+            "{error}"
+        );
+        assert_eq!(withdrawals.load(Ordering::SeqCst), 0);
+
+        let refid = client
+            .withdraw_to(Asset::Btc, "0.1", "bc1qours")
+            .await
+            .unwrap();
+        assert_eq!(refid, "AGBSO6T-UFMTTQ-I7KGS6");
+        assert_eq!(withdrawals.load(Ordering::SeqCst), 1);
+
+        // A key that isn't registered at all is refused too
+        let error = client
+            .withdraw_to(Asset::Xmr, "1.0", "4ours")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("monero_primary"), "{error}");
+        assert_eq!(withdrawals.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_read_key_never_used_for_trading() {
+        // Without a read key, reads fall back to the trade key
+        let trade_only = KrakenClient::with_keys(None, Some(credentials("trade")));
+        assert_eq!(
+            trade_only.credentials_for("Balance").unwrap().api_key,
+            "trade_key"
+        );
+
+        // Without a trade key, trade operations fail rather than using the read key
+        let read_only = KrakenClient::with_keys(Some(credentials("read")), None);
+        assert_eq!(
+            read_only.credentials_for("Balance").unwrap().api_key,
+            "read_key"
+        );
+        assert!(read_only.credentials_for("Withdraw").is_err());
+
+        // Public clients have no keys at all
+        assert!(KrakenClient::public().credentials_for("Balance").is_err());
+        assert!(KrakenCredentials::new(String::new(), "secret".to_string()).is_none());
+    }
+
     #[tokio::test]
     #[ignore] // Requires network access and can be flaky
     async fn test_get_ticker() {
-        let client = KrakenClient::new("".to_string(), "".to_string());
+        let client = KrakenClient::public();
         let ticker = client.get_ticker("XBTXMR").await;
         // Don't assert success - this is a basic smoke test
         // Proper testing is done in integration tests
@@ -0,0 +1,176 @@
+//! Nonces for Kraken private API requests
+//!
+//! Kraken rejects any request whose nonce is not higher than the last one
+//! seen for the API key. Clients in one process share an in-memory counter;
+//! processes sharing a key (the server and `eigenix selftest`, say) can point
+//! at the same nonce file, whose lock makes them draw one strictly increasing
+//! sequence. The file also keeps the sequence increasing across restarts,
+//! even if the clock steps back.
+
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Last nonce handed out by any in-memory source in this process
+static LAST_MEMORY_NONCE: AtomicU64 = AtomicU64::new(0);
+
+/// Where a client draws its nonces from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum NonceSource {
+    /// Process-wide counter
+    #[default]
+    Memory,
+    /// Counter persisted in a file, shared by every process using it
+    Shared(PathBuf),
+}
+
+impl NonceSource {
+    /// Shared file source when a path is given, in-memory otherwise
+    pub fn from_file(path: Option<PathBuf>) -> Self {
+        path.map(Self::Shared).unwrap_or_default()
+    }
+
+    /// Draw the next nonce
+    pub fn next(&self) -> Result<u64> {
+        let now = now_millis()?;
+        match self {
+            Self::Memory => {
+                let previous = LAST_MEMORY_NONCE
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+                        Some(next_after(last, now))
+                    })
+                    .expect("update closure never fails");
+                Ok(next_after(previous, now))
+            }
+            Self::Shared(path) => next_shared(path, now),
+        }
+    }
+}
+
+/// Millisecond timestamp, the nonce base Kraken expects
+fn now_millis() -> Result<u64> {
+    Ok(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_millis() as u64)
+}
+
+/// The current time, or one past the last nonce if the clock hasn't moved past it
+fn next_after(last: u64, now: u64) -> u64 {
+    now.max(last + 1)
+}
+
+/// Draw a nonce from the file at `path` under an exclusive lock
+fn next_shared(path: &Path, now: u64) -> Result<u64> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .with_context(|| format!("Failed to open nonce file {}", path.display()))?;
+    // Released when the file is closed
+    file.lock()
+        .with_context(|| format!("Failed to lock nonce file {}", path.display()))?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .context("Failed to read nonce file")?;
+    let last = match contents.trim() {
+        "" => 0,
+        value => value
+            .parse()
+            .with_context(|| format!("Corrupt nonce file {}: '{}'", path.display(), value))?,
+    };
+
+    let nonce = next_after(last, now);
+    file.set_len(0).context("Failed to write nonce file")?;
+    file.seek(SeekFrom::Start(0))
+        .context("Failed to write nonce file")?;
+    file.write_all(nonce.to_string().as_bytes())
+        .context("Failed to write nonce file")?;
+
+    Ok(nonce)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_nonce_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "eigenix-nonce-{}-{}-{}",
+            name,
+            std::process::id(),
+            now_millis().unwrap()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_shared_file_nonces_are_globally_monotonic() {
+        let path = temp_nonce_file("shared");
+
+        // Two independent clients drawing concurrently from the same file
+        let draws: Vec<Vec<u64>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..2)
+                .map(|_| {
+                    let source = NonceSource::from_file(Some(path.clone()));
+                    scope.spawn(move || {
+                        (0..200)
+                            .map(|_| source.next().unwrap())
+                            .collect::<Vec<u64>>()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        for nonces in &draws {
+            assert!(nonces.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+
+        // No nonce was handed out twice, and the file holds the highest
+        let mut all: Vec<u64> = draws.concat();
+        all.sort_unstable();
+        all.dedup();
+        assert_eq!(all.len(), 400);
+        let stored: u64 = std::fs::read_to_string(&path).unwrap().parse().unwrap();
+        assert_eq!(stored, *all.last().unwrap());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_shared_file_survives_restart_with_clock_behind() {
+        let path = temp_nonce_file("restart");
+        let now = now_millis().unwrap();
+
+        // A previous process left a nonce ahead of the current clock
+        std::fs::write(&path, (now + 60_000).to_string()).unwrap();
+        assert_eq!(next_shared(&path, now).unwrap(), now + 60_001);
+        assert_eq!(next_shared(&path, now).unwrap(), now + 60_002);
+
+        std::fs::write(&path, "garbage").unwrap();
+        assert!(next_shared(&path, now).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_memory_nonces_increase_across_clients() {
+        let first = NonceSource::Memory;
+        let second = NonceSource::Memory;
+
+        let mut last = 0;
+        for _ in 0..100 {
+            for source in [&first, &second] {
+                let nonce = source.next().unwrap();
+                assert!(nonce > last);
+                last = nonce;
+            }
+        }
+    }
+}
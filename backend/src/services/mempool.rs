@@ -0,0 +1,180 @@
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use super::retry;
+use crate::metrics::{clients, AddressMetrics, MempoolMetrics};
+
+/// Satoshis per bitcoin
+const SATS_PER_BTC: f64 = 100_000_000.0;
+
+/// Client for an Esplora-style HTTP API
+///
+/// Both the Blockstream electrs fork (`--http-addr`) and mempool.space
+/// (including self-hosted instances, under `/api`) serve this API.
+pub struct MempoolClient {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+/// Response of `GET /mempool`
+#[derive(Debug, Deserialize)]
+struct MempoolInfo {
+    count: u64,
+    vsize: u64,
+    total_fee: u64,
+    /// `[feerate, vsize]` pairs, highest feerate first
+    #[serde(default)]
+    fee_histogram: Vec<(f64, u64)>,
+}
+
+/// Funding and spending totals of an address, in satoshis
+#[derive(Debug, Default, Deserialize)]
+struct AddressStats {
+    funded_txo_sum: u64,
+    spent_txo_sum: u64,
+    tx_count: u64,
+}
+
+impl AddressStats {
+    fn balance_sats(&self) -> i64 {
+        self.funded_txo_sum as i64 - self.spent_txo_sum as i64
+    }
+}
+
+/// Response of `GET /address/{address}`
+#[derive(Debug, Deserialize)]
+struct AddressInfo {
+    address: String,
+    chain_stats: AddressStats,
+    #[serde(default)]
+    mempool_stats: AddressStats,
+}
+
+impl From<MempoolInfo> for MempoolMetrics {
+    fn from(info: MempoolInfo) -> Self {
+        Self {
+            tx_count: info.count,
+            vsize: info.vsize,
+            total_fee_sat: info.total_fee,
+            fee_histogram: info.fee_histogram,
+        }
+    }
+}
+
+impl From<AddressInfo> for AddressMetrics {
+    fn from(info: AddressInfo) -> Self {
+        Self {
+            confirmed_balance: info.chain_stats.balance_sats() as f64 / SATS_PER_BTC,
+            // Negative while a spend from the address is unconfirmed
+            unconfirmed_balance: info.mempool_stats.balance_sats() as f64 / SATS_PER_BTC,
+            tx_count: info.chain_stats.tx_count,
+            mempool_tx_count: info.mempool_stats.tx_count,
+            address: info.address,
+        }
+    }
+}
+
+impl MempoolClient {
+    /// Create a new client
+    ///
+    /// # Arguments
+    /// * `base_url` - API root, e.g. "http://127.0.0.1:3002" or "https://mempool.space/api"
+    pub fn new(base_url: String) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// GET `path` and parse the JSON response
+    async fn get<T: DeserializeOwned>(&self, method: &str, path: &str) -> Result<T> {
+        let url = format!("{}{}", self.base_url, path);
+        let url = &url;
+        retry::retry("mempool", method, true, || {
+            clients::track("mempool", method, async move {
+                let response = self
+                    .client
+                    .get(url)
+                    .send()
+                    .await
+                    .context("Failed to send mempool API request")?;
+
+                retry::check_status(response)
+                    .await?
+                    .error_for_status()
+                    .context("Mempool API request failed")?
+                    .json()
+                    .await
+                    .context("Failed to parse mempool API response")
+            })
+        })
+        .await
+    }
+
+    /// Current mempool size and fee histogram
+    pub async fn get_metrics(&self) -> Result<MempoolMetrics> {
+        let info: MempoolInfo = self.get("mempool", "/mempool").await?;
+        Ok(info.into())
+    }
+
+    /// Confirmed and unconfirmed balance of an address
+    pub async fn get_address(&self, address: &str) -> Result<AddressMetrics> {
+        let info: AddressInfo = self
+            .get("address", &format!("/address/{}", address))
+            .await
+            .with_context(|| format!("Failed to look up address {}", address))?;
+        Ok(info.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mempool() {
+        let info: MempoolInfo = serde_json::from_value(serde_json::json!({
+            "count": 4120,
+            "vsize": 2_345_678,
+            "total_fee": 9_876_543,
+            "fee_histogram": [[53.5, 102_400], [20.1, 250_000], [1.0, 1_993_278]]
+        }))
+        .unwrap();
+
+        let metrics = MempoolMetrics::from(info);
+        assert_eq!(metrics.tx_count, 4120);
+        assert_eq!(metrics.vsize, 2_345_678);
+        assert_eq!(metrics.total_fee_sat, 9_876_543);
+        assert_eq!(metrics.fee_histogram[0], (53.5, 102_400));
+        assert_eq!(metrics.fee_histogram.len(), 3);
+    }
+
+    #[test]
+    fn test_address_balances() {
+        let info: AddressInfo = serde_json::from_value(serde_json::json!({
+            "address": "bc1qexample",
+            "chain_stats": {
+                "funded_txo_count": 3,
+                "funded_txo_sum": 150_000_000,
+                "spent_txo_count": 1,
+                "spent_txo_sum": 50_000_000,
+                "tx_count": 4
+            },
+            "mempool_stats": {
+                "funded_txo_count": 0,
+                "funded_txo_sum": 0,
+                "spent_txo_count": 1,
+                "spent_txo_sum": 25_000_000,
+                "tx_count": 1
+            }
+        }))
+        .unwrap();
+
+        let metrics = AddressMetrics::from(info);
+        assert_eq!(metrics.address, "bc1qexample");
+        assert!((metrics.confirmed_balance - 1.0).abs() < 1e-9);
+        assert!((metrics.unconfirmed_balance + 0.25).abs() < 1e-9);
+        assert_eq!(metrics.tx_count, 4);
+        assert_eq!(metrics.mempool_tx_count, 1);
+    }
+}
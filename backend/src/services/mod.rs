@@ -4,13 +4,26 @@
 /// - Bitcoin node RPC (blockchain info, metrics)
 /// - Monero node RPC (blockchain info, metrics)
 /// - Kraken exchange operations (trading, deposits, withdrawals)
+/// - Binance as an alternative exchange, behind the common `Exchange` trait
 /// - ASB (Automated Swap Backend) operations (atomic swaps)
+/// - Esplora/mempool HTTP API (mempool size, fee histogram, address status)
+/// - Retrying their calls on transient failures, and pausing them while a
+///   service keeps failing
 pub mod asb;
+pub mod binance;
 pub mod bitcoin;
+pub mod breaker;
+pub mod exchange;
 pub mod kraken;
+pub mod kraken_nonce;
+pub mod mempool;
 pub mod monero;
+pub mod retry;
 
-pub use asb::AsbClient;
-pub use bitcoin::BitcoinRpcClient;
+pub use asb::{AsbClient, AsbQuote};
+pub use binance::BinanceClient;
+pub use bitcoin::{BitcoinRpcClient, FeeEstimator};
+pub use exchange::{Exchange, ExchangeClient};
 pub use kraken::KrakenClient;
+pub use mempool::MempoolClient;
 pub use monero::MoneroRpcClient;
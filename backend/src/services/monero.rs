@@ -1,7 +1,8 @@
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 
-use crate::metrics::MoneroMetrics;
+use super::retry;
+use crate::metrics::{clients, MoneroMetrics, MoneroNetworkMetrics};
 
 /// Monero node RPC client for blockchain information
 pub struct MoneroRpcClient {
@@ -19,6 +20,36 @@ struct MoneroInfo {
     target_height: u64,
     difficulty: u64,
     tx_count: u64,
+    /// Left out when the daemon doesn't report all of these
+    #[serde(flatten)]
+    connections: Option<ConnectionInfo>,
+}
+
+#[derive(Deserialize)]
+struct ConnectionInfo {
+    incoming_connections_count: u64,
+    outgoing_connections_count: u64,
+    database_size: u64,
+    synchronized: bool,
+    offline: bool,
+}
+
+#[derive(Deserialize)]
+struct FeeEstimate {
+    fee: u64,
+}
+
+impl ConnectionInfo {
+    fn into_metrics(self, fee_per_byte: Option<u64>) -> MoneroNetworkMetrics {
+        MoneroNetworkMetrics {
+            incoming_connections: self.incoming_connections_count,
+            outgoing_connections: self.outgoing_connections_count,
+            database_size: self.database_size,
+            synchronized: self.synchronized,
+            offline: self.offline,
+            fee_per_byte,
+        }
+    }
 }
 
 impl MoneroRpcClient {
@@ -26,41 +57,68 @@ impl MoneroRpcClient {
         Self { url }
     }
 
-    pub async fn get_metrics(&self) -> Result<MoneroMetrics> {
+    /// Call a monerod JSON-RPC method
+    async fn call<T: DeserializeOwned>(&self, method: &str) -> Result<T> {
         let client = reqwest::Client::new();
 
         let body = serde_json::json!({
             "jsonrpc": "2.0",
             "id": "0",
-            "method": "get_info"
+            "method": method
         });
 
-        let response = client
-            .post(&self.url)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .context("Failed to send Monero RPC request")?;
-
-        let rpc_response: MoneroRpcResponse<MoneroInfo> = response
-            .json()
-            .await
-            .context("Failed to parse Monero RPC response")?;
+        let (client, body) = (&client, &body);
+        retry::retry("monerod", method, true, || {
+            clients::track("monerod", method, async move {
+                let response = client
+                    .post(&self.url)
+                    .header("Content-Type", "application/json")
+                    .json(body)
+                    .send()
+                    .await
+                    .context("Failed to send Monero RPC request")?;
+
+                let rpc_response: MoneroRpcResponse<T> = retry::check_status(response)
+                    .await?
+                    .json()
+                    .await
+                    .context("Failed to parse Monero RPC response")?;
+
+                rpc_response
+                    .result
+                    .context("Monero RPC response missing result")
+            })
+        })
+        .await
+    }
 
-        let info = rpc_response
-            .result
-            .context("Monero RPC response missing result")?;
+    pub async fn get_metrics(&self) -> Result<MoneroMetrics> {
+        let info: MoneroInfo = self.call("get_info").await?;
 
         // Try to get wallet balance (may fail if wallet RPC not available)
         let wallet_balance = self.get_wallet_balance().await.ok();
 
+        let network = match info.connections {
+            Some(connections) => {
+                let fee_per_byte = match self.call::<FeeEstimate>("get_fee_estimate").await {
+                    Ok(estimate) => Some(estimate.fee),
+                    Err(e) => {
+                        tracing::warn!("Failed to get Monero fee estimate: {:#}", e);
+                        None
+                    }
+                };
+                Some(connections.into_metrics(fee_per_byte))
+            }
+            None => None,
+        };
+
         Ok(MoneroMetrics {
             height: info.height,
             target_height: info.target_height,
             difficulty: info.difficulty,
             tx_count: info.tx_count,
             wallet_balance,
+            network,
         })
     }
 
@@ -78,22 +136,29 @@ impl MoneroRpcClient {
             "method": "get_balance"
         });
 
-        let response = client
-            .post(&self.url)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .context("Failed to send Monero wallet RPC request")?;
-
-        let rpc_response: MoneroRpcResponse<BalanceResult> = response
-            .json()
-            .await
-            .context("Failed to parse Monero wallet RPC response")?;
-
-        let balance_result = rpc_response
-            .result
-            .context("Monero wallet RPC response missing result")?;
+        let (client, body) = (&client, &body);
+        let balance_result = retry::retry("monerod", "get_balance", true, || {
+            clients::track("monerod", "get_balance", async move {
+                let response = client
+                    .post(&self.url)
+                    .header("Content-Type", "application/json")
+                    .json(body)
+                    .send()
+                    .await
+                    .context("Failed to send Monero wallet RPC request")?;
+
+                let rpc_response: MoneroRpcResponse<BalanceResult> = retry::check_status(response)
+                    .await?
+                    .json()
+                    .await
+                    .context("Failed to parse Monero wallet RPC response")?;
+
+                rpc_response
+                    .result
+                    .context("Monero wallet RPC response missing result")
+            })
+        })
+        .await?;
 
         // Convert atomic units to XMR (1 XMR = 10^12 atomic units)
         Ok(balance_result.balance as f64 / 1_000_000_000_000.0)
@@ -104,6 +169,41 @@ impl MoneroRpcClient {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_info_connections() {
+        let info: MoneroInfo = serde_json::from_value(serde_json::json!({
+            "height": 3_200_000,
+            "target_height": 0,
+            "difficulty": 352_890_000_000u64,
+            "tx_count": 40_000_000,
+            "incoming_connections_count": 12,
+            "outgoing_connections_count": 8,
+            "database_size": 250_000_000_000u64,
+            "synchronized": true,
+            "offline": false,
+            "status": "OK"
+        }))
+        .unwrap();
+
+        let network = info.connections.unwrap().into_metrics(Some(20_000));
+        assert_eq!(network.incoming_connections, 12);
+        assert_eq!(network.outgoing_connections, 8);
+        assert_eq!(network.database_size, 250_000_000_000);
+        assert!(network.synchronized);
+        assert!(!network.offline);
+        assert_eq!(network.fee_per_byte, Some(20_000));
+
+        // Older or restricted daemons may leave the connection fields out
+        let info: MoneroInfo = serde_json::from_value(serde_json::json!({
+            "height": 3_200_000,
+            "target_height": 0,
+            "difficulty": 1,
+            "tx_count": 1
+        }))
+        .unwrap();
+        assert!(info.connections.is_none());
+    }
+
     #[tokio::test]
     #[ignore] // Only run with actual Monero node
     async fn test_get_monero_metrics() {
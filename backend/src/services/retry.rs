@@ -0,0 +1,445 @@
+//! Retrying failed calls to the external services
+//!
+//! Every call made by the service clients goes through [`retry`], which tries
+//! it again with exponential backoff and jitter when it failed for a reason
+//! that may go away: the connection was refused or timed out, the server was
+//! overloaded or rate limited, or a node was still warming up. Anything else,
+//! such as an RPC error or a response that doesn't parse, fails right away.
+//!
+//! Calls that change something (placing an order, withdrawing) are only
+//! retried when the server can't have acted on them, i.e. the request never
+//! got through or was refused outright. A timeout on those leaves the outcome
+//! unknown, so it is returned instead of risking doing the same thing twice.
+//!
+//! The policy of each client comes from its `[retry.<client>]` section and is
+//! set once at startup with [`configure`]. Calls whose retries all failed
+//! count towards the client's circuit breaker (see [`super::breaker`]).
+
+use anyhow::Result;
+use chrono::Utc;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use super::breaker;
+
+/// How calls are retried, by one of the service clients or by alert delivery
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Attempts per call, the first one included. 1 disables retries.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubles for each one after
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Longest wait between two attempts
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Fraction of each wait that is randomized, from 0 (none) to 1, so
+    /// callers failing together don't all come back at once
+    #[serde(default = "default_jitter")]
+    pub jitter: f64,
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_backoff_ms() -> u64 {
+    10_000
+}
+
+fn default_jitter() -> f64 {
+    0.2
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            jitter: default_jitter(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `retry` (starting at 1), before jitter
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff_ms
+            .saturating_mul(2u64.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff_ms);
+        Duration::from_millis(backoff)
+    }
+
+    /// [`backoff`](Self::backoff) with up to `jitter` of it taken off at random
+    fn jittered_backoff(&self, retry: u32) -> Duration {
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        self.backoff(retry).mul_f64(1.0 - self.jitter * random)
+    }
+
+    fn validate(&self, client: &str) -> Result<()> {
+        if self.max_attempts == 0 {
+            anyhow::bail!("retry.{}.max_attempts must be at least 1", client);
+        }
+        if self.initial_backoff_ms > self.max_backoff_ms {
+            anyhow::bail!(
+                "retry.{}.initial_backoff_ms must not exceed max_backoff_ms",
+                client
+            );
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            anyhow::bail!("retry.{}.jitter must be between 0 and 1", client);
+        }
+        Ok(())
+    }
+}
+
+/// Retry policy of each client, keyed by the names used in the client metrics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetryConfig {
+    #[serde(default)]
+    pub bitcoind: RetryPolicy,
+    #[serde(default)]
+    pub monerod: RetryPolicy,
+    #[serde(default)]
+    pub asb: RetryPolicy,
+    #[serde(default)]
+    pub kraken: RetryPolicy,
+    #[serde(default)]
+    pub binance: RetryPolicy,
+    #[serde(default)]
+    pub mempool: RetryPolicy,
+}
+
+impl RetryConfig {
+    fn policies(&self) -> [(&'static str, &RetryPolicy); 6] {
+        [
+            ("bitcoind", &self.bitcoind),
+            ("monerod", &self.monerod),
+            ("asb", &self.asb),
+            ("kraken", &self.kraken),
+            ("binance", &self.binance),
+            ("mempool", &self.mempool),
+        ]
+    }
+
+    /// Policy of `client`; the default one for a client without a section
+    pub fn policy(&self, client: &str) -> RetryPolicy {
+        self.policies()
+            .into_iter()
+            .find(|(name, _)| *name == client)
+            .map(|(_, policy)| *policy)
+            .unwrap_or_default()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (client, policy) in self.policies() {
+            policy.validate(client)?;
+        }
+        Ok(())
+    }
+}
+
+fn global() -> &'static RwLock<RetryConfig> {
+    static CONFIG: OnceLock<RwLock<RetryConfig>> = OnceLock::new();
+    CONFIG.get_or_init(Default::default)
+}
+
+/// Set the retry policies used by every client from now on
+pub fn configure(config: RetryConfig) {
+    *global().write().unwrap() = config;
+}
+
+/// Current retry policy of `client`
+pub fn policy(client: &str) -> RetryPolicy {
+    global().read().unwrap().policy(client)
+}
+
+/// A response status that says to come back later
+#[derive(Debug)]
+pub struct TransientStatus {
+    pub status: StatusCode,
+    pub body: String,
+}
+
+impl fmt::Display for TransientStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Server returned {}: {}", self.status, self.body)
+    }
+}
+
+impl std::error::Error for TransientStatus {}
+
+/// A request the server turned away without acting on it, e.g. because it
+/// was rate limited or still starting up
+#[derive(Debug)]
+pub struct Unavailable(pub String);
+
+impl fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+/// Fail with [`TransientStatus`] on a status worth retrying, pass anything
+/// else on for the client to interpret
+pub async fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if !matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    ) {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    Err(TransientStatus { status, body }.into())
+}
+
+/// Whether a failed call may succeed if tried again, and whether it can have
+/// taken effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transience {
+    /// Retrying won't help
+    Permanent,
+    /// The server didn't act on the request; safe to send again
+    NotApplied,
+    /// The request may or may not have been acted on
+    Unknown,
+}
+
+/// Classify a failed call by the first error in its chain that says anything
+pub fn classify(error: &anyhow::Error) -> Transience {
+    for cause in error.chain() {
+        if cause.is::<Unavailable>() {
+            return Transience::NotApplied;
+        }
+        if let Some(transient) = cause.downcast_ref::<TransientStatus>() {
+            return match transient.status {
+                StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS => {
+                    Transience::NotApplied
+                }
+                // A gateway or an overloaded server may have passed it on
+                _ => Transience::Unknown,
+            };
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return if e.is_connect() {
+                Transience::NotApplied
+            } else if e.is_timeout() {
+                Transience::Unknown
+            } else {
+                Transience::Permanent
+            };
+        }
+    }
+    Transience::Permanent
+}
+
+/// Run a call to `client`, retrying it as its policy says
+///
+/// `attempt` is called once per attempt, so anything that must differ between
+/// attempts (nonces, timestamps, signatures) is built inside it. Pass
+/// `idempotent: false` for calls that must not run twice. Fails with
+/// [`breaker::CircuitOpen`] without calling at all while the client's circuit
+/// is open.
+pub async fn retry<T, F, Fut>(client: &str, method: &str, idempotent: bool, attempt: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let breaker = breaker::global();
+    breaker.acquire(client, Utc::now())?;
+
+    let result = retry_with(policy(client), client, method, idempotent, attempt).await;
+    let failed = matches!(&result, Err(e) if classify(e) != Transience::Permanent);
+    breaker.record(client, failed, Utc::now());
+    result
+}
+
+/// [`retry`] with an explicit policy
+pub async fn retry_with<T, F, Fut>(
+    policy: RetryPolicy,
+    client: &str,
+    method: &str,
+    idempotent: bool,
+    mut attempt: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let error = match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+
+        let retryable = match classify(&error) {
+            Transience::Permanent => false,
+            Transience::NotApplied => true,
+            Transience::Unknown => idempotent,
+        };
+        if !retryable || attempts >= policy.max_attempts {
+            return Err(error);
+        }
+
+        let backoff = policy.jittered_backoff(attempts);
+        tracing::warn!(
+            "{} {} failed (attempt {}/{}), retrying in {:?}: {:#}",
+            client,
+            method,
+            attempts,
+            policy.max_attempts,
+            backoff,
+            error
+        );
+        tokio::time::sleep(backoff).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 1,
+            jitter: 0.0,
+        }
+    }
+
+    /// Fail the first `failures` calls with `error`, then succeed
+    async fn flaky(calls: &AtomicU32, failures: u32, error: fn() -> anyhow::Error) -> Result<u32> {
+        let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+        if call <= failures {
+            return Err(error());
+        }
+        Ok(call)
+    }
+
+    fn unavailable() -> anyhow::Error {
+        anyhow::Error::new(Unavailable("EService:Busy".to_string())).context("Kraken API error")
+    }
+
+    fn gateway_timeout() -> anyhow::Error {
+        TransientStatus {
+            status: StatusCode::GATEWAY_TIMEOUT,
+            body: String::new(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let policy = RetryPolicy {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1_000,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(40), Duration::from_millis(1_000));
+
+        let jittered = RetryPolicy {
+            jitter: 0.5,
+            ..policy
+        }
+        .jittered_backoff(1);
+        assert!(jittered >= Duration::from_millis(50) && jittered <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(&unavailable()), Transience::NotApplied);
+        assert_eq!(classify(&gateway_timeout()), Transience::Unknown);
+        assert_eq!(
+            classify(&anyhow::anyhow!("RPC error: Insufficient funds")),
+            Transience::Permanent
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_are_retried() {
+        let calls = AtomicU32::new(0);
+        let result = retry_with(fast_policy(), "kraken", "Ticker", true, || {
+            flaky(&calls, 2, unavailable)
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+
+        let calls = AtomicU32::new(0);
+        let result = retry_with(fast_policy(), "kraken", "Ticker", true, || {
+            flaky(&calls, 3, unavailable)
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_outcome_is_not_retried_unless_idempotent() {
+        let calls = AtomicU32::new(0);
+        let result = retry_with(fast_policy(), "kraken", "AddOrder", false, || {
+            flaky(&calls, 1, gateway_timeout)
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // A refused request never ran, so even an order is sent again
+        let calls = AtomicU32::new(0);
+        let result = retry_with(fast_policy(), "kraken", "AddOrder", false, || {
+            flaky(&calls, 1, unavailable)
+        })
+        .await;
+        assert_eq!(result.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_failures_are_not_retried() {
+        let calls = AtomicU32::new(0);
+        let result = retry_with(fast_policy(), "bitcoind", "getbalances", true, || {
+            flaky(&calls, 1, || anyhow::anyhow!("RPC error: Method not found"))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(RetryConfig::default().validate().is_ok());
+
+        let mut config = RetryConfig::default();
+        config.kraken.max_attempts = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = RetryConfig::default();
+        config.asb.jitter = 1.5;
+        assert!(config.validate().is_err());
+    }
+}
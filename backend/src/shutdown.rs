@@ -0,0 +1,73 @@
+//! Process-wide shutdown signal
+//!
+//! Triggered once on SIGTERM or Ctrl-C. Background loops check it between
+//! units of work and wait on it while idle, so they stop at a point where
+//! everything they did is recorded, instead of being dropped mid-write when
+//! the runtime exits.
+
+use std::sync::Arc;
+use tokio::sync::watch;
+
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    triggered: Arc<watch::Sender<bool>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            triggered: Arc::new(watch::Sender::new(false)),
+        }
+    }
+
+    /// Ask everything holding a clone to stop
+    pub fn trigger(&self) {
+        self.triggered.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    /// Resolve once shutdown has been triggered
+    pub async fn triggered(&self) {
+        let mut receiver = self.triggered.subscribe();
+        // The sender lives in `self`, so this only ends by seeing `true`
+        let _ = receiver.wait_for(|triggered| *triggered).await;
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_trigger_wakes_waiters() {
+        let shutdown = Shutdown::new();
+        let waiter = shutdown.clone();
+        let waiting = tokio::spawn(async move { waiter.triggered().await });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!waiting.is_finished());
+        assert!(!shutdown.is_triggered());
+
+        shutdown.trigger();
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .expect("waiter should wake on trigger")
+            .unwrap();
+        assert!(shutdown.is_triggered());
+
+        // Waiting after the fact returns right away
+        tokio::time::timeout(Duration::from_secs(1), shutdown.triggered())
+            .await
+            .unwrap();
+    }
+}
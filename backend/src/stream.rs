@@ -0,0 +1,394 @@
+//! Live metric and trading updates as server-sent events
+//!
+//! Served at `/metrics/stream` for dashboards behind proxies that break
+//! WebSocket upgrades. One task, [`poll_shared`], reads the latest stored
+//! sample of every source and the trading engine's state for all connections
+//! and publishes it over a watch channel, so open streams add no database load
+//! of their own. Each connection checks that reading at its own interval and
+//! sends an event only for what changed since its last check: one named after
+//! the source (`bitcoin`, `monero`, `asb`, `electrs`, `containers`, `disk`,
+//! `system`) carrying the latest sample, or `trading` with the engine's state.
+//! The first check sends everything there is, so a client that reconnects is
+//! caught up at once. At most [`MAX_CONNECTIONS`] streams are open at a time.
+//! Streams end on shutdown so they don't hold up the server's drain.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::response::sse::Event;
+use chrono::{DateTime, Utc};
+use eigenix_common::metrics::{BitcoinMetricsView, MoneroMetricsView};
+use futures_util::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
+use tokio::time::{interval, MissedTickBehavior};
+use utoipa::IntoParams;
+
+use crate::{db::MetricsSummary, trading::engine::TradingState, ApiError, ApiResult, AppState};
+
+/// Seconds between polls when the client doesn't ask
+const DEFAULT_INTERVAL_SECS: u64 = 5;
+
+/// Slowest poll a client may ask for, in seconds
+const MAX_INTERVAL_SECS: u64 = 300;
+
+/// How often the shared poller reads, which is the fastest a client may ask for
+const SHARED_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Most event streams open at once
+pub const MAX_CONNECTIONS: usize = 64;
+
+/// How long a client turned away for too many open streams is asked to wait
+const CONNECTION_RETRY: Duration = Duration::from_secs(10);
+
+/// Query parameters for the event stream
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StreamQuery {
+    /// Seconds between checks for new samples (1 to 300, default 5)
+    interval: Option<u64>,
+}
+
+impl StreamQuery {
+    pub fn period(&self) -> ApiResult<Duration> {
+        let secs = self.interval.unwrap_or(DEFAULT_INTERVAL_SECS);
+        if !(1..=MAX_INTERVAL_SECS).contains(&secs) {
+            return Err(ApiError::BadRequest(format!(
+                "interval must be between 1 and {} seconds",
+                MAX_INTERVAL_SECS
+            )));
+        }
+        Ok(Duration::from_secs(secs))
+    }
+}
+
+/// Payload of the `trading` event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TradingUpdate {
+    pub state: TradingState,
+    pub enabled: bool,
+    pub paused: bool,
+}
+
+/// An event to send: its name and JSON payload
+type Update = (&'static str, Value);
+
+/// What the shared poller read last
+#[derive(Debug)]
+struct Snapshot {
+    /// `None` when the database couldn't be read
+    summary: Option<MetricsSummary>,
+    trading: TradingUpdate,
+}
+
+/// The shared poller's latest reading, and the budget of open streams
+#[derive(Clone)]
+pub struct StreamHub {
+    /// `None` until the first reading after a stream opens
+    latest: watch::Sender<Option<Arc<Snapshot>>>,
+    connections: Arc<Semaphore>,
+}
+
+impl StreamHub {
+    pub fn new() -> Self {
+        Self::with_capacity(MAX_CONNECTIONS)
+    }
+
+    fn with_capacity(max_connections: usize) -> Self {
+        Self {
+            latest: watch::Sender::new(None),
+            connections: Arc::new(Semaphore::new(max_connections)),
+        }
+    }
+
+    /// Open a stream: a subscription to the readings and the slot it holds
+    /// until it is dropped, or an error if every slot is taken
+    fn connect(&self) -> ApiResult<(watch::Receiver<Option<Arc<Snapshot>>>, OwnedSemaphorePermit)> {
+        let permit = self
+            .connections
+            .clone()
+            .try_acquire_owned()
+            .map_err(|_| ApiError::RateLimited(CONNECTION_RETRY))?;
+        Ok((self.latest.subscribe(), permit))
+    }
+}
+
+impl Default for StreamHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What one connection has already sent
+#[derive(Debug, Default)]
+struct Seen {
+    /// Timestamp of the newest sample sent per source
+    samples: HashMap<&'static str, DateTime<Utc>>,
+    trading: Option<TradingUpdate>,
+}
+
+impl Seen {
+    /// Queue `sample` unless nothing newer than what was sent has arrived
+    fn sample<T: Serialize>(
+        &mut self,
+        source: &'static str,
+        timestamp: Option<DateTime<Utc>>,
+        sample: &T,
+        updates: &mut Vec<Update>,
+    ) {
+        let Some(timestamp) = timestamp else {
+            return;
+        };
+        if self
+            .samples
+            .get(source)
+            .is_some_and(|sent| *sent >= timestamp)
+        {
+            return;
+        }
+
+        match serde_json::to_value(sample) {
+            Ok(value) => {
+                self.samples.insert(source, timestamp);
+                updates.push((source, value));
+            }
+            Err(e) => tracing::warn!("Failed to encode {} sample for the stream: {}", source, e),
+        }
+    }
+
+    /// Sources with a sample newer than the last one sent
+    fn metrics(&mut self, summary: &MetricsSummary) -> Vec<Update> {
+        let mut updates = Vec::new();
+        let bitcoin = summary.bitcoin.clone().map(BitcoinMetricsView::from);
+        let timestamp = bitcoin.as_ref().map(|row| row.timestamp);
+        self.sample("bitcoin", timestamp, &bitcoin, &mut updates);
+        let monero = summary.monero.clone().map(MoneroMetricsView::from);
+        let timestamp = monero.as_ref().map(|row| row.timestamp);
+        self.sample("monero", timestamp, &monero, &mut updates);
+        let asb = summary.asb.as_ref().map(|row| row.timestamp);
+        self.sample("asb", asb, &summary.asb, &mut updates);
+        let electrs = summary.electrs.as_ref().map(|row| row.timestamp);
+        self.sample("electrs", electrs, &summary.electrs, &mut updates);
+        let system = summary.system.as_ref().map(|row| row.timestamp);
+        self.sample("system", system, &summary.system, &mut updates);
+
+        // One event per collection cycle, carrying every container or path
+        let containers = summary.containers.iter().map(|row| row.timestamp).max();
+        self.sample("containers", containers, &summary.containers, &mut updates);
+        let disk = summary.disk.iter().map(|row| row.timestamp).max();
+        self.sample("disk", disk, &summary.disk, &mut updates);
+
+        updates
+    }
+
+    /// The trading state, if it changed
+    fn trading(&mut self, trading: TradingUpdate) -> Option<Update> {
+        if self.trading.as_ref() == Some(&trading) {
+            return None;
+        }
+        let value = serde_json::to_value(&trading).ok()?;
+        self.trading = Some(trading);
+        Some(("trading", value))
+    }
+
+    /// What changed in `snapshot` since the last check
+    fn updates(&mut self, snapshot: &Snapshot) -> Vec<Update> {
+        let mut updates = snapshot
+            .summary
+            .as_ref()
+            .map(|summary| self.metrics(summary))
+            .unwrap_or_default();
+        updates.extend(self.trading(snapshot.trading.clone()));
+        updates
+    }
+}
+
+/// Read the latest samples and the trading state once
+async fn read(state: &AppState) -> Snapshot {
+    let summary = match state.db.get_summary().await {
+        Ok(summary) => Some(summary),
+        Err(e) => {
+            tracing::warn!("Failed to read metrics for the stream: {:#}", e);
+            None
+        }
+    };
+
+    let engine = &state.trading_engine;
+    Snapshot {
+        summary,
+        trading: TradingUpdate {
+            state: engine.get_state(),
+            enabled: engine.is_enabled(),
+            paused: engine.is_paused(),
+        },
+    }
+}
+
+/// Read for every open stream until shutdown
+///
+/// Nothing is read while no stream is open, and the last reading is dropped
+/// then so the next stream doesn't start from a stale one.
+pub async fn poll_shared(state: AppState) {
+    let hub = state.streams.clone();
+    let mut ticker = interval(SHARED_POLL_INTERVAL);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = state.shutdown.triggered() => return,
+        }
+        if hub.latest.receiver_count() == 0 {
+            hub.latest.send_replace(None);
+            continue;
+        }
+        let snapshot = read(&state).await;
+        hub.latest.send_replace(Some(Arc::new(snapshot)));
+    }
+}
+
+/// Events for one client, checked every `period` until shutdown
+///
+/// Fails when [`MAX_CONNECTIONS`] streams are open already.
+pub fn updates(
+    state: &AppState,
+    period: Duration,
+) -> ApiResult<impl Stream<Item = Result<Event, Infallible>>> {
+    let (latest, permit) = state.streams.connect()?;
+    let mut ticker = interval(period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let events = stream::unfold(
+        (
+            latest,
+            ticker,
+            Seen::default(),
+            state.shutdown.clone(),
+            permit,
+        ),
+        |(mut latest, mut ticker, mut seen, shutdown, permit)| async move {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.triggered() => return None,
+            }
+            // A stream opened while nothing was being read waits for the
+            // first reading
+            let snapshot = tokio::select! {
+                snapshot = latest.wait_for(Option::is_some) => snapshot.ok()?.clone()?,
+                _ = shutdown.triggered() => return None,
+            };
+            let updates = seen.updates(&snapshot);
+            Some((updates, (latest, ticker, seen, shutdown, permit)))
+        },
+    )
+    .flat_map(stream::iter)
+    .map(|(name, data)| Ok(Event::default().event(name).data(data.to_string())));
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::StoredAsbMetrics;
+    use chrono::TimeZone;
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 1, 12, minute, 0).unwrap()
+    }
+
+    fn summary(asb: Option<DateTime<Utc>>) -> MetricsSummary {
+        MetricsSummary {
+            bitcoin: None,
+            monero: None,
+            asb: asb.map(|timestamp| StoredAsbMetrics {
+                timestamp,
+                balance_btc: 0.5,
+                pending_swaps: 0,
+                completed_swaps: 3,
+                failed_swaps: 0,
+                up: true,
+            }),
+            electrs: None,
+            containers: Vec::new(),
+            disk: Vec::new(),
+            system: None,
+        }
+    }
+
+    fn names(updates: &[Update]) -> Vec<&'static str> {
+        updates.iter().map(|(name, _)| *name).collect()
+    }
+
+    #[test]
+    fn test_only_new_samples_are_sent() {
+        let mut seen = Seen::default();
+
+        assert_eq!(names(&seen.metrics(&summary(Some(at(0))))), vec!["asb"]);
+        // The same sample again is not news, nor is nothing at all
+        assert!(seen.metrics(&summary(Some(at(0)))).is_empty());
+        assert!(seen.metrics(&summary(None)).is_empty());
+
+        let updates = seen.metrics(&summary(Some(at(1))));
+        assert_eq!(names(&updates), vec!["asb"]);
+        assert_eq!(updates[0].1["timestamp"], "2025-01-01T12:01:00Z");
+    }
+
+    #[test]
+    fn test_snapshot_updates_skip_a_failed_read() {
+        let mut seen = Seen::default();
+        let trading = TradingUpdate {
+            state: TradingState::Monitoring,
+            enabled: false,
+            paused: false,
+        };
+        let snapshot = Snapshot {
+            summary: Some(summary(Some(at(0)))),
+            trading: trading.clone(),
+        };
+        assert_eq!(names(&seen.updates(&snapshot)), vec!["asb", "trading"]);
+        assert!(seen.updates(&snapshot).is_empty());
+
+        let failed = Snapshot {
+            summary: None,
+            trading,
+        };
+        assert!(seen.updates(&failed).is_empty());
+    }
+
+    #[test]
+    fn test_open_streams_are_capped() {
+        let hub = StreamHub::with_capacity(2);
+        let (_first_rx, first) = hub.connect().unwrap();
+        let (_second_rx, _second) = hub.connect().unwrap();
+        assert!(matches!(hub.connect(), Err(ApiError::RateLimited(_))));
+        assert_eq!(hub.latest.receiver_count(), 2);
+
+        // A closed stream frees its slot
+        drop(first);
+        assert!(hub.connect().is_ok());
+    }
+
+    #[test]
+    fn test_trading_changes_are_sent_once() {
+        let mut seen = Seen::default();
+        let monitoring = TradingUpdate {
+            state: TradingState::Monitoring,
+            enabled: true,
+            paused: false,
+        };
+
+        assert!(seen.trading(monitoring.clone()).is_some());
+        assert!(seen.trading(monitoring.clone()).is_none());
+
+        let paused = TradingUpdate {
+            paused: true,
+            ..monitoring
+        };
+        let (name, data) = seen.trading(paused).unwrap();
+        assert_eq!(name, "trading");
+        assert_eq!(data["paused"], true);
+    }
+}
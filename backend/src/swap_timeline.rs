@@ -0,0 +1,274 @@
+//! Lifecycle timeline of a single swap
+//!
+//! The ASB reports a swap's state and the txids it has seen so far. Each
+//! txid is looked up in the managed wallet for its chain to find when it
+//! confirmed. Transactions the wallets don't track (the BTC lock output
+//! belongs to the taker until redeemed) keep their txid but get no time.
+//! When the ASB reports a finished state without the matching txid, the
+//! final event is still listed, without a transaction.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::services::asb::SwapDetails;
+use crate::wallets::WalletManager;
+
+/// Step in a swap's lifecycle, declared in protocol order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapEventKind {
+    Started,
+    /// The taker locked BTC
+    BtcLock,
+    /// The ASB locked XMR
+    XmrLock,
+    /// The ASB redeemed the BTC, completing the swap
+    BtcRedeem,
+    /// The taker took the BTC back after a cancel
+    BtcRefund,
+    /// The ASB swept its locked XMR back after the BTC refund
+    XmrRefund,
+    /// The ASB claimed the BTC after the taker failed to refund in time
+    BtcPunish,
+}
+
+impl SwapEventKind {
+    /// Chain the event's transaction is on
+    pub fn chain(self) -> Option<Chain> {
+        match self {
+            Self::Started => None,
+            Self::BtcLock | Self::BtcRedeem | Self::BtcRefund | Self::BtcPunish => {
+                Some(Chain::Bitcoin)
+            }
+            Self::XmrLock | Self::XmrRefund => Some(Chain::Monero),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Chain {
+    Bitcoin,
+    Monero,
+}
+
+/// One entry on the timeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SwapEvent {
+    pub kind: SwapEventKind,
+    pub chain: Option<Chain>,
+    pub txid: Option<String>,
+    /// Block time, or the ASB's start date for `started`
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Unset when the transaction isn't in the managed wallet
+    pub confirmations: Option<u64>,
+}
+
+impl SwapEvent {
+    fn new(kind: SwapEventKind, txid: Option<String>) -> Self {
+        Self {
+            kind,
+            chain: kind.chain(),
+            txid,
+            timestamp: None,
+            confirmations: None,
+        }
+    }
+}
+
+/// Response of `GET /asb/swaps/{id}/timeline`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SwapTimeline {
+    pub swap_id: String,
+    pub status: String,
+    /// In protocol order
+    pub events: Vec<SwapEvent>,
+}
+
+/// Terminal event a state name implies, matched loosely since the ASB's
+/// state names vary between versions ("BtcRedeemed", "btc is redeemed")
+fn implied_by_status(status: &str) -> Option<SwapEventKind> {
+    let normalized: String = status
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    if normalized.contains("punished") {
+        Some(SwapEventKind::BtcPunish)
+    } else if normalized.contains("xmr") && normalized.contains("refunded") {
+        Some(SwapEventKind::XmrRefund)
+    } else if normalized.contains("refunded") {
+        Some(SwapEventKind::BtcRefund)
+    } else if normalized.contains("redeemed") {
+        Some(SwapEventKind::BtcRedeem)
+    } else {
+        None
+    }
+}
+
+/// Events the ASB's record of the swap accounts for, before any lookups
+pub fn swap_events(swap: &SwapDetails) -> Vec<SwapEvent> {
+    let mut started = SwapEvent::new(SwapEventKind::Started, None);
+    started.timestamp = swap
+        .start_date
+        .as_deref()
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map(|date| date.with_timezone(&Utc));
+
+    let mut events = vec![started];
+    let txids = [
+        (SwapEventKind::BtcLock, &swap.btc_lock_txid),
+        (SwapEventKind::XmrLock, &swap.xmr_lock_txid),
+        (SwapEventKind::BtcRedeem, &swap.btc_redeem_txid),
+        (SwapEventKind::BtcRefund, &swap.btc_refund_txid),
+        (SwapEventKind::XmrRefund, &swap.xmr_refund_txid),
+        (SwapEventKind::BtcPunish, &swap.btc_punish_txid),
+    ];
+    events.extend(
+        txids
+            .into_iter()
+            .filter_map(|(kind, txid)| Some(SwapEvent::new(kind, Some(txid.clone()?)))),
+    );
+
+    if let Some(kind) = implied_by_status(&swap.status) {
+        if !events.iter().any(|event| event.kind == kind) {
+            events.push(SwapEvent::new(kind, None));
+        }
+    }
+
+    events.sort_by_key(|event| event.kind);
+    events
+}
+
+/// Fill in confirmation times from the wallet on the event's chain
+///
+/// A failed lookup leaves the event without a time rather than failing the
+/// timeline.
+async fn resolve(event: &mut SwapEvent, wallets: &WalletManager) {
+    let Some(txid) = event.txid.as_deref() else {
+        return;
+    };
+
+    let found = match event.chain {
+        Some(Chain::Bitcoin) => wallets
+            .bitcoin
+            .get_transaction(txid)
+            .await
+            .map(|tx| (tx.blocktime.unwrap_or(tx.time), tx.confirmations)),
+        Some(Chain::Monero) => wallets
+            .monero
+            .get_transfer_by_txid(txid)
+            .await
+            .map(|transfer| (transfer.timestamp, transfer.confirmations)),
+        None => return,
+    };
+
+    match found {
+        Ok((time, confirmations)) => {
+            // Unconfirmed Monero transfers report a zero timestamp
+            event.timestamp = i64::try_from(time)
+                .ok()
+                .filter(|&secs| secs > 0)
+                .and_then(|secs| DateTime::from_timestamp(secs, 0));
+            event.confirmations = Some(confirmations);
+        }
+        Err(e) => {
+            tracing::debug!("No wallet record of swap transaction {}: {:#}", txid, e);
+        }
+    }
+}
+
+/// Build the timeline of `swap`, looking its transactions up in `wallets`
+pub async fn build_timeline(swap: SwapDetails, wallets: &WalletManager) -> SwapTimeline {
+    let mut events = swap_events(&swap);
+    for event in &mut events {
+        resolve(event, wallets).await;
+    }
+
+    SwapTimeline {
+        swap_id: swap.swap_id,
+        status: swap.status,
+        events,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swap(status: &str) -> SwapDetails {
+        SwapDetails {
+            swap_id: "a1b2c3d4-0000-4000-8000-000000000001".to_string(),
+            status: status.to_string(),
+            start_date: Some("2024-05-01T12:00:00Z".to_string()),
+            btc_lock_txid: None,
+            xmr_lock_txid: None,
+            btc_redeem_txid: None,
+            btc_refund_txid: None,
+            xmr_refund_txid: None,
+            btc_punish_txid: None,
+        }
+    }
+
+    fn kinds(events: &[SwapEvent]) -> Vec<SwapEventKind> {
+        events.iter().map(|event| event.kind).collect()
+    }
+
+    #[test]
+    fn test_events_follow_protocol_order() {
+        let mut redeemed = swap("BtcRedeemed");
+        redeemed.btc_redeem_txid = Some("cc".to_string());
+        redeemed.xmr_lock_txid = Some("bb".to_string());
+        redeemed.btc_lock_txid = Some("aa".to_string());
+
+        let events = swap_events(&redeemed);
+        assert_eq!(
+            kinds(&events),
+            vec![
+                SwapEventKind::Started,
+                SwapEventKind::BtcLock,
+                SwapEventKind::XmrLock,
+                SwapEventKind::BtcRedeem,
+            ]
+        );
+        assert_eq!(
+            events[0].timestamp,
+            DateTime::from_timestamp(1_714_564_800, 0)
+        );
+        assert_eq!(events[2].chain, Some(Chain::Monero));
+        // The redeem txid is known, so the state doesn't add a second event
+        assert_eq!(events[3].txid.as_deref(), Some("cc"));
+    }
+
+    #[test]
+    fn test_final_state_without_txid() {
+        let mut punished = swap("btc is punished");
+        punished.btc_lock_txid = Some("aa".to_string());
+        let events = swap_events(&punished);
+        assert_eq!(
+            kinds(&events),
+            vec![
+                SwapEventKind::Started,
+                SwapEventKind::BtcLock,
+                SwapEventKind::BtcPunish
+            ]
+        );
+        assert_eq!(events[2].txid, None);
+
+        assert_eq!(
+            kinds(&swap_events(&swap("XmrRefunded"))),
+            vec![SwapEventKind::Started, SwapEventKind::XmrRefund]
+        );
+        assert_eq!(
+            kinds(&swap_events(&swap("BtcRefunded"))),
+            vec![SwapEventKind::Started, SwapEventKind::BtcRefund]
+        );
+        // In-progress states imply nothing
+        assert_eq!(
+            kinds(&swap_events(&swap("XmrLockTransactionSent"))),
+            vec![SwapEventKind::Started]
+        );
+    }
+}
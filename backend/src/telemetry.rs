@@ -0,0 +1,193 @@
+//! Logging and span export
+//!
+//! Logs go to stdout at info level as before. Spans (one per request, see
+//! [`crate::request_id`], with the service client, wallet RPC and database
+//! calls made under it) can also be exported over OTLP/HTTP to a collector
+//! such as Jaeger or Tempo by setting `[tracing] otlp_endpoint`. The exporter
+//! is started once the config is loaded, so the subscriber is installed
+//! before that with an empty slot for it.
+
+use anyhow::{Context, Result};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    trace::{Sampler, SdkTracerProvider},
+    Resource,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tracing::Level;
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    layer::SubscriberExt,
+    reload,
+    util::SubscriberInitExt,
+    Layer, Registry,
+};
+
+use crate::metrics::backend::DbTimingLayer;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracingConfig {
+    /// OTLP/HTTP traces endpoint, e.g. `http://127.0.0.1:4318/v1/traces`;
+    /// spans aren't exported when unset
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    /// `service.name` the spans are exported under
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+    /// Fraction of traces exported, from 0 to 1
+    #[serde(default = "default_sample_ratio")]
+    pub sample_ratio: f64,
+}
+
+fn default_service_name() -> String {
+    "eigenix-backend".to_string()
+}
+
+fn default_sample_ratio() -> f64 {
+    1.0
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: default_service_name(),
+            sample_ratio: default_sample_ratio(),
+        }
+    }
+}
+
+impl TracingConfig {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(endpoint) = &self.otlp_endpoint {
+            reqwest::Url::parse(endpoint).with_context(|| {
+                format!("tracing.otlp_endpoint '{}' is not a valid URL", endpoint)
+            })?;
+        }
+        if self.service_name.trim().is_empty() {
+            anyhow::bail!("tracing.service_name must not be empty");
+        }
+        if !(0.0..=1.0).contains(&self.sample_ratio) {
+            anyhow::bail!("tracing.sample_ratio must be between 0 and 1");
+        }
+        Ok(())
+    }
+}
+
+type ExportLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// The installed subscriber, and the exporter once one is started
+pub struct Telemetry {
+    layer: reload::Handle<Option<ExportLayer>, Registry>,
+    filter: reload::Handle<Targets, Registry>,
+    provider: Mutex<Option<SdkTracerProvider>>,
+}
+
+/// Install the global subscriber, logging only until [`Telemetry::export`]
+///
+/// The database calls' spans also go to [`DbTimingLayer`] from the start.
+pub fn init() -> Telemetry {
+    let (layer, layer_handle) = reload::Layer::new(None::<ExportLayer>);
+    // Nothing passes until export starts, so the debug spans cost nothing
+    let (filter, filter_handle) = reload::Layer::new(Targets::new());
+
+    tracing_subscriber::registry()
+        .with(layer.with_filter(filter))
+        .with(DbTimingLayer.with_filter(
+            Targets::new().with_target(format!("{}::db", env!("CARGO_CRATE_NAME")), Level::DEBUG),
+        ))
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .compact()
+                .with_filter(LevelFilter::INFO),
+        )
+        .init();
+
+    Telemetry {
+        layer: layer_handle,
+        filter: filter_handle,
+        provider: Mutex::new(None),
+    }
+}
+
+impl Telemetry {
+    /// Start exporting spans as `config` says, if it names an endpoint
+    ///
+    /// Exports this crate's spans down to debug level, which is where the
+    /// client, wallet and database calls are, and other crates' at info.
+    pub fn export(&self, config: &TracingConfig) -> Result<()> {
+        let Some(endpoint) = &config.otlp_endpoint else {
+            return Ok(());
+        };
+
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint.clone())
+            .build()
+            .context("Failed to create the OTLP span exporter")?;
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                config.sample_ratio,
+            ))))
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(config.service_name.clone())
+                    .build(),
+            )
+            .build();
+        let tracer = provider.tracer("eigenix-backend");
+
+        self.layer
+            .reload(Some(
+                tracing_opentelemetry::layer().with_tracer(tracer).boxed(),
+            ))
+            .context("Failed to install the OTLP layer")?;
+        self.filter
+            .reload(
+                Targets::new()
+                    .with_target(env!("CARGO_CRATE_NAME"), Level::DEBUG)
+                    .with_default(Level::INFO),
+            )
+            .context("Failed to install the OTLP filter")?;
+        *self.provider.lock().unwrap() = Some(provider);
+
+        tracing::info!("Exporting spans to {}", endpoint);
+        Ok(())
+    }
+
+    /// Send the spans still queued for export
+    pub fn shutdown(&self) {
+        if let Some(provider) = self.provider.lock().unwrap().take() {
+            if let Err(e) = provider.shutdown() {
+                tracing::warn!("Failed to flush exported spans: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(TracingConfig::default().validate().is_ok());
+
+        let config = TracingConfig {
+            otlp_endpoint: Some("127.0.0.1:4318".to_string()),
+            ..TracingConfig::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = TracingConfig {
+            otlp_endpoint: Some("http://127.0.0.1:4318/v1/traces".to_string()),
+            sample_ratio: 1.5,
+            ..TracingConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -1,77 +1,35 @@
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
+use serde_json::{Map, Value};
 use std::sync::{Arc, RwLock};
 
-/// Trading configuration with runtime-updatable parameters
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TradingConfig {
-    /// Minimum Monero balance threshold (in XMR) before triggering rebalance
-    pub monero_min_threshold: f64,
+pub use eigenix_common::trading::{TradingConfig, TradingSchedule, TradingWindow};
 
-    /// Target Monero balance to maintain after rebalancing (in XMR)
-    pub monero_target_balance: f64,
-
-    /// Minimum Bitcoin balance to keep (don't trade below this, in BTC)
-    pub bitcoin_reserve_minimum: f64,
-
-    /// Maximum amount of Bitcoin to use in a single rebalance operation (in BTC)
-    pub max_btc_per_rebalance: f64,
-
-    /// Check interval in seconds (how often to check balances)
-    pub check_interval_secs: u64,
-
-    /// Maximum time to wait for Kraken order execution in seconds
-    pub order_timeout_secs: u64,
-
-    /// Slippage tolerance percentage (e.g., 0.5 for 0.5%)
-    pub slippage_tolerance_percent: f64,
-
-    /// Whether to use limit orders (true) or market orders (false)
-    pub use_limit_orders: bool,
+/// Trading-relevant subset of the deployment `parameters.json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeploymentParameters {
+    pub asb: AsbParameters,
 }
 
-impl Default for TradingConfig {
-    fn default() -> Self {
-        Self {
-            monero_min_threshold: 1.0,        // Rebalance if XMR drops below 1.0
-            monero_target_balance: 5.0,       // Target 5.0 XMR after rebalancing
-            bitcoin_reserve_minimum: 0.00001, // Keep at least 0.00001 BTC
-            max_btc_per_rebalance: 0.01,      // Max 0.1 BTC per operation
-            check_interval_secs: 300,         // Check every 5 minutes
-            order_timeout_secs: 600,          // Wait max 10 minutes for order
-            slippage_tolerance_percent: 1.0,  // 1% slippage tolerance
-            use_limit_orders: true,           // Use limit orders by default
-        }
-    }
+/// ASB swap limits from the deployment parameters
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AsbParameters {
+    pub min_buy_btc: f64,
+    pub max_buy_btc: f64,
+    /// Spread over market price as a fraction (0.02 = 2%)
+    pub ask_spread: f64,
 }
 
-impl TradingConfig {
-    /// Validate configuration parameters
-    pub fn validate(&self) -> Result<(), String> {
-        if self.monero_min_threshold >= self.monero_target_balance {
-            return Err("monero_min_threshold must be less than monero_target_balance".to_string());
-        }
-
-        if self.monero_min_threshold < 0.0 {
-            return Err("monero_min_threshold must be positive".to_string());
-        }
-
-        if self.bitcoin_reserve_minimum < 0.0 {
-            return Err("bitcoin_reserve_minimum must be positive".to_string());
-        }
-
-        if self.max_btc_per_rebalance <= 0.0 {
-            return Err("max_btc_per_rebalance must be positive".to_string());
-        }
-
-        if self.check_interval_secs == 0 {
-            return Err("check_interval_secs must be greater than 0".to_string());
-        }
-
-        if self.slippage_tolerance_percent < 0.0 || self.slippage_tolerance_percent > 100.0 {
-            return Err("slippage_tolerance_percent must be between 0 and 100".to_string());
-        }
-
-        Ok(())
+impl AsbParameters {
+    /// Seed trade sizing in `config` from these limits
+    ///
+    /// A rebalance never needs to exceed the largest swap the ASB accepts, is
+    /// pointless below the smallest one, and should give up at most half of
+    /// the ASB's ask spread to slippage.
+    pub fn apply_to(&self, config: &mut TradingConfig) {
+        config.max_btc_per_rebalance = self.max_buy_btc;
+        config.min_btc_per_rebalance = self.min_buy_btc;
+        config.slippage_tolerance_percent = self.ask_spread * 100.0 / 2.0;
     }
 }
 
@@ -99,6 +57,33 @@ impl SharedTradingConfig {
         *self.config.write().unwrap() = new_config;
         Ok(())
     }
+
+    /// Change only the fields in `changes`, keyed as in the JSON config
+    ///
+    /// Reads and replaces the configuration under one lock, so fields changed
+    /// meanwhile by someone else are kept.
+    ///
+    /// # Returns
+    /// The configuration now in effect
+    pub fn patch(&self, changes: &Map<String, Value>) -> Result<TradingConfig, String> {
+        let mut config = self.config.write().unwrap();
+        let mut fields = match serde_json::to_value(&*config) {
+            Ok(Value::Object(fields)) => fields,
+            _ => return Err("The trading config is not a JSON object".to_string()),
+        };
+        for (key, value) in changes {
+            let field = fields
+                .get_mut(key)
+                .ok_or_else(|| format!("Unknown trading config key '{}'", key))?;
+            *field = value.clone();
+        }
+
+        let patched: TradingConfig =
+            serde_json::from_value(Value::Object(fields)).map_err(|e| e.to_string())?;
+        patched.validate()?;
+        *config = patched.clone();
+        Ok(patched)
+    }
 }
 
 impl Default for SharedTradingConfig {
@@ -106,3 +91,65 @@ impl Default for SharedTradingConfig {
         Self::new(TradingConfig::default())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn changes(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_patch_keeps_other_fields() {
+        let shared = SharedTradingConfig::default();
+        let mut elsewhere = shared.get();
+        elsewhere.use_limit_orders = !elsewhere.use_limit_orders;
+        shared.update(elsewhere.clone()).unwrap();
+
+        let patched = shared
+            .patch(&changes(json!({ "check_interval_secs": 120 })))
+            .unwrap();
+        assert_eq!(patched.check_interval_secs, 120);
+        assert_eq!(patched.use_limit_orders, elsewhere.use_limit_orders);
+        assert_eq!(shared.get().check_interval_secs, 120);
+    }
+
+    #[test]
+    fn test_patch_rejects_unknown_and_invalid_fields() {
+        let shared = SharedTradingConfig::default();
+        let before = shared.get().check_interval_secs;
+
+        assert!(shared.patch(&changes(json!({ "no_such_key": 1 }))).is_err());
+        assert!(shared
+            .patch(&changes(json!({ "check_interval_secs": "soon" })))
+            .is_err());
+        assert!(shared
+            .patch(&changes(json!({ "check_interval_secs": 0 })))
+            .is_err());
+        assert_eq!(shared.get().check_interval_secs, before);
+    }
+
+    #[test]
+    fn test_config_without_optional_keys_is_valid() {
+        // Only the keys configs had before the optional ones were added
+        let config: TradingConfig = serde_json::from_value(json!({
+            "monero_min_threshold": 1.0,
+            "monero_target_balance": 2.0,
+            "bitcoin_reserve_minimum": 0.00001,
+            "max_btc_per_rebalance": 0.01,
+            "check_interval_secs": 300,
+            "order_timeout_secs": 600,
+            "slippage_tolerance_percent": 1.0,
+            "use_limit_orders": true
+        }))
+        .unwrap();
+
+        assert_eq!(
+            config.max_pending_transactions,
+            TradingConfig::default().max_pending_transactions
+        );
+        assert!(config.validate().is_ok());
+    }
+}
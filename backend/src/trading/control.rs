@@ -0,0 +1,234 @@
+//! Operator control over a running rebalance
+//!
+//! Beyond enabling and disabling the engine, an operator can pause it (it
+//! stops at the next step boundary, or before the next check when idle),
+//! resume it, and override the step it is waiting on: `complete` treats the
+//! awaited deposit, trade or withdrawal as done (e.g. confirmed out-of-band)
+//! and carries on, `skip` gives up on it and abandons the rest of that
+//! rebalance.
+//!
+//! On shutdown the engine stops the same way it pauses: at the next step
+//! boundary, or at the next poll of the step it is waiting on, failing the
+//! rebalance with [`Interrupted`] and leaving its records pending.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use utoipa::ToSchema;
+
+use crate::shutdown::Shutdown;
+
+/// What to do with the step the engine is waiting on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StepOverride {
+    /// Abandon the step and the rest of the rebalance
+    Skip,
+    /// Treat the step as done and continue with the next one
+    Complete,
+}
+
+/// A rebalance stopped early because the backend is shutting down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interrupted;
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Interrupted by shutdown")
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+#[derive(Debug, Default)]
+struct ControlState {
+    paused: bool,
+    step_override: Option<StepOverride>,
+}
+
+/// Pause flag and pending step override, shared by the engine and the API
+#[derive(Debug, Clone, Default)]
+pub struct StepControl {
+    state: Arc<Mutex<ControlState>>,
+    changed: Arc<Notify>,
+    shutdown: Shutdown,
+}
+
+impl StepControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop waiting steps and pauses when `shutdown` is triggered
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_triggered()
+    }
+
+    /// Fail with [`Interrupted`] once shutdown has been triggered
+    pub fn checkpoint(&self) -> anyhow::Result<()> {
+        if self.is_shutting_down() {
+            return Err(Interrupted.into());
+        }
+        Ok(())
+    }
+
+    /// Sleep, waking early on shutdown
+    pub async fn sleep(&self, duration: Duration) {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = self.shutdown.triggered() => {}
+        }
+    }
+
+    /// Stop at the next step boundary
+    pub fn pause(&self) {
+        self.state.lock().unwrap().paused = true;
+        self.changed.notify_waiters();
+    }
+
+    pub fn resume(&self) {
+        self.state.lock().unwrap().paused = false;
+        self.changed.notify_waiters();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().paused
+    }
+
+    /// Queue an override for the step currently being waited on
+    pub fn request_override(&self, step_override: StepOverride) {
+        self.state.lock().unwrap().step_override = Some(step_override);
+        self.changed.notify_waiters();
+    }
+
+    /// Drop an override that no step picked up
+    pub fn clear_override(&self) {
+        self.state.lock().unwrap().step_override = None;
+    }
+
+    fn take_override(&self) -> Option<StepOverride> {
+        self.state.lock().unwrap().step_override.take()
+    }
+
+    /// Wait until the engine is no longer paused, or is shutting down
+    pub async fn wait_while_paused(&self) {
+        loop {
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            // Register before checking so a resume in between isn't missed
+            changed.as_mut().enable();
+            if !self.is_paused() || self.is_shutting_down() {
+                return;
+            }
+            tokio::select! {
+                _ = changed => {}
+                _ = self.shutdown.triggered() => return,
+            }
+        }
+    }
+
+    /// Sleep between polls of a waiting step, waking early for an override
+    /// or on shutdown
+    ///
+    /// Returns the override if one was requested.
+    pub async fn sleep_or_override(&self, duration: Duration) -> Option<StepOverride> {
+        let deadline = tokio::time::Instant::now() + duration;
+        loop {
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+            if let Some(step_override) = self.take_override() {
+                return Some(step_override);
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => return self.take_override(),
+                _ = self.shutdown.triggered() => return self.take_override(),
+                _ = changed => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_override_wakes_a_waiting_step() {
+        let control = StepControl::new();
+        let waiter = control.clone();
+        let step =
+            tokio::spawn(async move { waiter.sleep_or_override(Duration::from_secs(60)).await });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        control.request_override(StepOverride::Complete);
+
+        let received = tokio::time::timeout(Duration::from_secs(1), step)
+            .await
+            .expect("override should end the wait early")
+            .unwrap();
+        assert_eq!(received, Some(StepOverride::Complete));
+
+        // The override is consumed
+        assert_eq!(
+            control.sleep_or_override(Duration::from_millis(1)).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pause_holds_until_resumed() {
+        let control = StepControl::new();
+        control.pause();
+
+        let waiter = control.clone();
+        let paused = tokio::spawn(async move { waiter.wait_while_paused().await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!paused.is_finished());
+
+        control.resume();
+        tokio::time::timeout(Duration::from_secs(1), paused)
+            .await
+            .expect("resume should release the engine")
+            .unwrap();
+
+        // Not paused: returns straight away
+        control.wait_while_paused().await;
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_interrupts_waits() {
+        let shutdown = Shutdown::new();
+        let control = StepControl::new().with_shutdown(shutdown.clone());
+        control.pause();
+        assert!(control.checkpoint().is_ok());
+
+        let waiter = control.clone();
+        let paused = tokio::spawn(async move { waiter.wait_while_paused().await });
+        let waiter = control.clone();
+        let step =
+            tokio::spawn(async move { waiter.sleep_or_override(Duration::from_secs(60)).await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        shutdown.trigger();
+        tokio::time::timeout(Duration::from_secs(1), paused)
+            .await
+            .expect("shutdown should end the pause")
+            .unwrap();
+        let received = tokio::time::timeout(Duration::from_secs(1), step)
+            .await
+            .expect("shutdown should end the wait")
+            .unwrap();
+        assert_eq!(received, None);
+
+        let err = control.checkpoint().unwrap_err();
+        assert!(err.is::<Interrupted>());
+    }
+}
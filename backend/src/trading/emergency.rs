@@ -0,0 +1,237 @@
+//! Emergency ("panic sell") conversions
+//!
+//! An emergency conversion moves the whole balance of one asset into the
+//! other in a single pass, ignoring the usual thresholds. It still goes
+//! through the normal deposit/trade/withdraw legs, so the config validation,
+//! pending-transaction and `max_btc_per_rebalance` guards all apply.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::amount::{Btc, Xmr};
+
+use super::config::TradingConfig;
+
+/// Which way an emergency conversion moves funds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversionDirection {
+    /// Sell all XMR for BTC
+    XmrToBtc,
+    /// Spend all BTC above the reserve on XMR
+    BtcToXmr,
+}
+
+impl ConversionDirection {
+    /// Phrase the operator must send back to confirm the conversion
+    pub fn confirmation_phrase(self) -> &'static str {
+        match self {
+            Self::XmrToBtc => "CONVERT ALL XMR TO BTC",
+            Self::BtcToXmr => "CONVERT ALL BTC TO XMR",
+        }
+    }
+}
+
+/// Sized emergency conversion, returned to the caller before the legs run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct EmergencyPlan {
+    pub direction: ConversionDirection,
+    /// BTC spent (BTC→XMR) or expected to be received (XMR→BTC)
+    pub btc_amount: Btc,
+    /// XMR sold (XMR→BTC) or expected to be received (BTC→XMR)
+    pub xmr_amount: Xmr,
+    /// Price used for sizing, in BTC per XMR
+    pub btc_per_xmr: f64,
+    /// Whether `max_btc_per_rebalance` limited the conversion below the full balance
+    pub capped: bool,
+}
+
+/// Reject the request unless the confirmation matches the direction's phrase
+pub fn verify_confirmation(direction: ConversionDirection, confirmation: &str) -> Result<()> {
+    if confirmation.trim() != direction.confirmation_phrase() {
+        anyhow::bail!(
+            "Confirmation does not match; send \"{}\" to proceed",
+            direction.confirmation_phrase()
+        );
+    }
+
+    Ok(())
+}
+
+/// Size an emergency conversion from the current wallet balances
+///
+/// BTC→XMR keeps `bitcoin_reserve_minimum` in the wallet. Both directions are
+/// capped at `max_btc_per_rebalance` worth of BTC.
+pub fn plan_emergency_conversion(
+    direction: ConversionDirection,
+    btc_balance: Btc,
+    xmr_balance: Xmr,
+    btc_per_xmr: f64,
+    config: &TradingConfig,
+) -> Result<EmergencyPlan> {
+    if !btc_per_xmr.is_finite() || btc_per_xmr <= 0.0 {
+        anyhow::bail!("Invalid BTC/XMR price: {}", btc_per_xmr);
+    }
+
+    let max_btc =
+        Btc::from_f64(config.max_btc_per_rebalance).context("Invalid max_btc_per_rebalance")?;
+
+    let plan = match direction {
+        ConversionDirection::BtcToXmr => {
+            let reserve = Btc::from_f64(config.bitcoin_reserve_minimum)
+                .context("Invalid bitcoin_reserve_minimum")?;
+            let available = btc_balance
+                .checked_sub(reserve)
+                .context("BTC amount overflow")?;
+            let btc_amount = available.min(max_btc);
+            EmergencyPlan {
+                direction,
+                btc_amount,
+                xmr_amount: Xmr::from_f64(btc_amount.to_f64() / btc_per_xmr)
+                    .context("XMR amount overflow")?,
+                btc_per_xmr,
+                capped: btc_amount < available,
+            }
+        }
+        ConversionDirection::XmrToBtc => {
+            let max_xmr =
+                Xmr::from_f64(max_btc.to_f64() / btc_per_xmr).context("XMR amount overflow")?;
+            let xmr_amount = xmr_balance.min(max_xmr);
+            EmergencyPlan {
+                direction,
+                btc_amount: xmr_amount
+                    .to_btc_at(btc_per_xmr)
+                    .context("BTC amount overflow")?,
+                xmr_amount,
+                btc_per_xmr,
+                capped: xmr_amount < xmr_balance,
+            }
+        }
+    };
+
+    let sold_positive = match direction {
+        ConversionDirection::BtcToXmr => plan.btc_amount.is_positive(),
+        ConversionDirection::XmrToBtc => plan.xmr_amount.is_positive(),
+    };
+    if !sold_positive {
+        anyhow::bail!("Nothing to convert: balance is empty or below the reserve");
+    }
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TradingConfig {
+        TradingConfig {
+            max_btc_per_rebalance: 0.1,
+            bitcoin_reserve_minimum: 0.005,
+            ..TradingConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_confirmation_must_match_direction() {
+        let xmr_to_btc = ConversionDirection::XmrToBtc;
+
+        assert!(verify_confirmation(xmr_to_btc, "").is_err());
+        assert!(verify_confirmation(xmr_to_btc, "yes").is_err());
+        assert!(verify_confirmation(
+            xmr_to_btc,
+            ConversionDirection::BtcToXmr.confirmation_phrase()
+        )
+        .is_err());
+        assert!(verify_confirmation(xmr_to_btc, "CONVERT ALL XMR TO BTC").is_ok());
+    }
+
+    #[test]
+    fn test_plan_sells_full_xmr_balance() {
+        let plan = plan_emergency_conversion(
+            ConversionDirection::XmrToBtc,
+            Btc::ZERO,
+            Xmr::from_f64(2.0).unwrap(),
+            0.005,
+            &config(),
+        )
+        .unwrap();
+
+        assert_eq!(plan.xmr_amount, Xmr::from_f64(2.0).unwrap());
+        assert_eq!(plan.btc_amount, Btc::from_sats(1_000_000));
+        assert!(!plan.capped);
+    }
+
+    #[test]
+    fn test_plan_respects_max_trade() {
+        // 100 XMR at 0.005 BTC is 0.5 BTC, above the 0.1 BTC cap
+        let plan = plan_emergency_conversion(
+            ConversionDirection::XmrToBtc,
+            Btc::ZERO,
+            Xmr::from_f64(100.0).unwrap(),
+            0.005,
+            &config(),
+        )
+        .unwrap();
+        assert_eq!(plan.xmr_amount, Xmr::from_f64(20.0).unwrap());
+        assert_eq!(plan.btc_amount, Btc::from_f64(0.1).unwrap());
+        assert!(plan.capped);
+
+        let plan = plan_emergency_conversion(
+            ConversionDirection::BtcToXmr,
+            Btc::from_f64(1.0).unwrap(),
+            Xmr::ZERO,
+            0.005,
+            &config(),
+        )
+        .unwrap();
+        assert_eq!(plan.btc_amount, Btc::from_f64(0.1).unwrap());
+        assert!(plan.capped);
+    }
+
+    #[test]
+    fn test_plan_keeps_bitcoin_reserve() {
+        let plan = plan_emergency_conversion(
+            ConversionDirection::BtcToXmr,
+            Btc::from_f64(0.05).unwrap(),
+            Xmr::ZERO,
+            0.005,
+            &config(),
+        )
+        .unwrap();
+        assert_eq!(plan.btc_amount, Btc::from_f64(0.045).unwrap());
+        assert!(!plan.capped);
+
+        // Only the reserve left: nothing to convert
+        assert!(plan_emergency_conversion(
+            ConversionDirection::BtcToXmr,
+            Btc::from_f64(0.005).unwrap(),
+            Xmr::ZERO,
+            0.005,
+            &config(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_plan_rejects_empty_balance_and_bad_price() {
+        assert!(plan_emergency_conversion(
+            ConversionDirection::XmrToBtc,
+            Btc::ZERO,
+            Xmr::ZERO,
+            0.005,
+            &config(),
+        )
+        .is_err());
+
+        assert!(plan_emergency_conversion(
+            ConversionDirection::XmrToBtc,
+            Btc::ZERO,
+            Xmr::from_f64(1.0).unwrap(),
+            0.0,
+            &config(),
+        )
+        .is_err());
+    }
+}
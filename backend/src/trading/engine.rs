@@ -1,59 +1,86 @@
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 use tokio::time::{sleep, Duration};
+use utoipa::ToSchema;
 
+use crate::alerts::{Alert, AlertNotifier, AlertSeverity};
+use crate::amount::{Btc, Xmr};
+use crate::approvals::{Approval, ApprovalAction, ApprovalStatus, ENGINE_REQUESTER};
+use crate::auth::Principal;
+use crate::config::SharedConfig;
 use crate::db::{MetricsDatabase, StoredTradingTransaction, TransactionStatus, TransactionType};
-use crate::services::kraken::KrakenClient;
+use crate::services::bitcoin::FeeEstimator;
+use crate::services::exchange::{
+    find_deposit, Asset, Exchange, ExchangeClient, OrderReport, OrderRequest, OrderSide,
+    OrderState, TransferState,
+};
+use crate::services::kraken::parse_kraken_f64;
+use crate::shutdown::Shutdown;
+use crate::wallets::bitcoin::FeeBump;
+use crate::wallets::limits::SpendLimiter;
+use crate::wallets::monero::{TransferPriority, WalletBalance};
+use crate::wallets::psbt;
+use crate::wallets::transfer::TransferAsset;
 use crate::wallets::{BitcoinWallet, MoneroWallet};
 
-use super::config::SharedTradingConfig;
-
-/// Current state of the trading engine
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum TradingState {
-    /// Engine is disabled
-    Disabled,
-    /// Engine is idle, monitoring balances
-    Monitoring,
-    /// Currently depositing Bitcoin to Kraken
-    DepositingBitcoin { amount: f64 },
-    /// Waiting for Bitcoin deposit to confirm on Kraken
-    WaitingForBitcoinDeposit { txid: String },
-    /// Executing BTC->XMR trade on Kraken
-    Trading { btc_amount: f64 },
-    /// Waiting for trade order to complete
-    WaitingForTradeExecution { order_id: String },
-    /// Withdrawing Monero from Kraken
-    WithdrawingMonero { amount: f64 },
-    /// Waiting for Monero withdrawal to complete
-    WaitingForMoneroWithdrawal { refid: String },
-    /// Error occurred during operation
-    Error { message: String },
+use super::config::{SharedTradingConfig, TradingConfig};
+use super::control::{Interrupted, StepControl, StepOverride};
+use super::emergency::{
+    plan_emergency_conversion, verify_confirmation, ConversionDirection, EmergencyPlan,
+};
+use super::estimate::{CostEstimate, FeeQuotes, DEPOSIT_CONF_TARGET};
+use super::holdings::HoldingsSnapshot;
+use super::lock::{TradingLease, TradingLock};
+
+pub use eigenix_common::trading::{TradingState, TradingStatus};
+
+/// What the engine would do about the current balances
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RebalanceDecision {
+    /// XMR balance is at or above `monero_min_threshold` and no reverse rebalance is
+    /// due, or the trade that is due comes out below `min_btc_per_rebalance`
+    Hold,
+    /// Buy `xmr_needed` XMR, spending `btc_to_spend` BTC
+    Rebalance { xmr_needed: Xmr, btc_to_spend: Btc },
+    /// BTC is below `bitcoin_min_threshold` with XMR above `monero_max_threshold`:
+    /// sell `xmr_to_sell` XMR towards the `btc_needed` BTC shortfall
+    SellXmr { btc_needed: Btc, xmr_to_sell: Xmr },
+    /// A rebalance is due but can't go ahead, e.g. insufficient BTC; `xmr_needed`
+    /// is zero when it is a reverse rebalance that is blocked
+    Blocked { xmr_needed: Xmr, reason: String },
 }
 
-/// Status information about the trading engine
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TradingStatus {
-    pub state: TradingState,
-    pub enabled: bool,
-    pub last_check: Option<String>,
-    pub last_rebalance: Option<String>,
-    pub current_btc_balance: Option<f64>,
-    pub current_xmr_balance: Option<f64>,
-    pub kraken_btc_balance: Option<f64>,
-    pub kraken_xmr_balance: Option<f64>,
+/// Rebalance decision for a candidate config against live balances
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RebalancePreview {
+    pub btc_balance: Btc,
+    pub xmr_balance: Xmr,
+    /// Exchange price used for sizing, in BTC per XMR
+    pub btc_per_xmr: f64,
+    pub decision: RebalanceDecision,
 }
 
-/// Thread-safe trading engine
+/// Outcome of cancelling every open exchange order
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CancelAllSummary {
+    /// Orders the exchange reported as cancelled
+    pub orders_cancelled: u64,
+    /// Pending trade transactions marked as cancelled
+    pub transactions_cancelled: usize,
+}
+
+/// Thread-safe trading engine, trading on the exchange `E`
 #[derive(Clone)]
-pub struct TradingEngine {
+pub struct TradingEngine<E = ExchangeClient> {
     pub config: SharedTradingConfig,
     state: Arc<RwLock<TradingState>>,
     enabled: Arc<RwLock<bool>>,
-    kraken_api_key: String,
-    kraken_api_secret: String,
+    exchange: E,
     bitcoin_wallet_url: String,
     bitcoin_wallet_cookie: String,
     bitcoin_wallet_name: String,
@@ -61,14 +88,36 @@ pub struct TradingEngine {
     monero_wallet_name: String,
     monero_wallet_password: String,
     db: Option<MetricsDatabase>,
+    clock: Clock,
+    /// Set while an emergency conversion is being sized or executed
+    emergency_active: Arc<AtomicBool>,
+    /// Decimals allowed in a BTC->XMR order volume, fetched on the first trade
+    volume_decimals: Arc<tokio::sync::OnceCell<u32>>,
+    /// Holdings at the previous check, for the unexplained-drop guard
+    last_holdings: Arc<RwLock<Option<HoldingsSnapshot>>>,
+    notifier: Option<AlertNotifier>,
+    /// Single-instance lease, taken before the trading loop starts
+    lock: Option<TradingLock>,
+    /// Why this instance may not trade (lock not held), if it may not
+    lock_conflict: Arc<RwLock<Option<String>>>,
+    /// Operator pause flag and step overrides
+    control: StepControl,
+    /// Shared feerate cache; the wallet's own estimate is used without one
+    fee_estimator: Option<FeeEstimator>,
+    /// Server config, read for the approval thresholds at each rebalance
+    app_config: Option<SharedConfig>,
+    /// Caps on what the deposits to the exchange may send
+    spend_limits: SpendLimiter,
 }
 
-impl TradingEngine {
+/// Source of the current time, replaceable in tests
+type Clock = Arc<dyn Fn() -> DateTime<Utc> + Send + Sync>;
+
+impl<E: Exchange> TradingEngine<E> {
     /// Create a new trading engine
     pub fn new(
         config: SharedTradingConfig,
-        kraken_api_key: String,
-        kraken_api_secret: String,
+        exchange: E,
         bitcoin_wallet_url: String,
         bitcoin_wallet_cookie: String,
         bitcoin_wallet_name: String,
@@ -80,8 +129,7 @@ impl TradingEngine {
             config,
             state: Arc::new(RwLock::new(TradingState::Disabled)),
             enabled: Arc::new(RwLock::new(false)),
-            kraken_api_key,
-            kraken_api_secret,
+            exchange,
             bitcoin_wallet_url,
             bitcoin_wallet_cookie,
             bitcoin_wallet_name,
@@ -89,6 +137,17 @@ impl TradingEngine {
             monero_wallet_name,
             monero_wallet_password,
             db: None,
+            clock: Arc::new(Utc::now),
+            emergency_active: Arc::new(AtomicBool::new(false)),
+            volume_decimals: Arc::new(tokio::sync::OnceCell::new()),
+            last_holdings: Arc::new(RwLock::new(None)),
+            notifier: None,
+            lock: None,
+            lock_conflict: Arc::new(RwLock::new(None)),
+            control: StepControl::new(),
+            fee_estimator: None,
+            app_config: None,
+            spend_limits: SpendLimiter::default(),
         }
     }
 
@@ -98,6 +157,108 @@ impl TradingEngine {
         self
     }
 
+    /// Send engine alerts (e.g. a halt on an unexplained holdings drop) here
+    pub fn with_alert_notifier(mut self, notifier: AlertNotifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Stop trading when `shutdown` is triggered
+    ///
+    /// A rebalance in progress stops before its next step or at the next poll
+    /// of the step it waits on, leaving its records pending for the next run.
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.control = self.control.with_shutdown(shutdown);
+        self
+    }
+
+    /// Take deposit feerates from this cache instead of asking the wallet each time
+    pub fn with_fee_estimator(mut self, estimator: FeeEstimator) -> Self {
+        self.fee_estimator = Some(estimator);
+        self
+    }
+
+    /// Hold rebalances above the `[approvals]` thresholds of `config` until a
+    /// second key approves them; the section is read afresh each time
+    pub fn with_approvals(mut self, config: SharedConfig) -> Self {
+        self.app_config = Some(config);
+        self
+    }
+
+    /// Count deposits to the exchange against these spend limits, shared
+    /// with the wallet routes
+    pub fn with_spend_limits(mut self, limits: SpendLimiter) -> Self {
+        self.spend_limits = limits;
+        self
+    }
+
+    /// Only trade while holding this lock
+    ///
+    /// Trading is refused until [`TradingEngine::run`] has acquired it.
+    pub fn with_trading_lock(mut self, lock: TradingLock) -> Self {
+        self.lock = Some(lock);
+        *self.lock_conflict.write().unwrap() = Some("Trading lock not acquired yet".to_string());
+        self
+    }
+
+    /// The lock this instance takes before trading, if it takes one
+    pub fn trading_lock(&self) -> Option<&TradingLock> {
+        self.lock.as_ref()
+    }
+
+    /// Why this instance may not trade, if it holds no trading lock
+    pub fn trading_blocked(&self) -> Option<String> {
+        self.lock_conflict.read().unwrap().clone()
+    }
+
+    /// Keep this instance from trading while another holds the lock
+    fn stand_by(&self, reason: String) {
+        self.set_state(TradingState::Standby {
+            message: reason.clone(),
+        });
+        *self.lock_conflict.write().unwrap() = Some(reason);
+    }
+
+    /// Wait on standby until the trading lock is ours, retrying every renew
+    /// interval
+    ///
+    /// Returns `None` on shutdown.
+    async fn wait_for_lock(&self, lock: &TradingLock) -> Option<TradingLease> {
+        let mut logged = false;
+        while !self.control.is_shutting_down() {
+            match lock.acquire().await {
+                Ok(lease) => {
+                    tracing::info!("Acquired trading lock as {}", lease.holder);
+                    *self.lock_conflict.write().unwrap() = None;
+                    return Some(lease);
+                }
+                Err(e) => {
+                    let reason = format!("{:#}; this instance is on standby", e);
+                    if logged {
+                        tracing::debug!("{}", reason);
+                    } else {
+                        tracing::warn!("{}", reason);
+                        logged = true;
+                    }
+                    self.stand_by(reason);
+                }
+            }
+            self.control.sleep(lock.renew_interval()).await;
+        }
+        None
+    }
+
+    /// Override the clock used for schedule checks
+    pub fn with_clock(mut self, clock: impl Fn() -> DateTime<Utc> + Send + Sync + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Current time according to the engine clock
+    fn now(&self) -> DateTime<Utc> {
+        (self.clock)()
+    }
+
     /// Get the database if available
     fn get_db(&self) -> Option<&MetricsDatabase> {
         self.db.as_ref()
@@ -122,6 +283,71 @@ impl TradingEngine {
         *self.enabled.read().unwrap()
     }
 
+    /// Pause the engine
+    ///
+    /// An idle engine pauses right away; a rebalance in progress finishes
+    /// its current step first.
+    pub fn pause(&self) -> TradingState {
+        self.control.pause();
+        let mut state = self.state.write().unwrap();
+        if state.is_idle() {
+            *state = TradingState::Paused;
+        }
+        tracing::info!("Trading engine paused");
+        state.clone()
+    }
+
+    /// Resume a paused engine
+    pub fn resume(&self) -> TradingState {
+        self.control.resume();
+        let mut state = self.state.write().unwrap();
+        if *state == TradingState::Paused {
+            *state = if self.is_enabled() {
+                TradingState::Monitoring
+            } else {
+                TradingState::Disabled
+            };
+        }
+        tracing::info!("Trading engine resumed");
+        state.clone()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.control.is_paused()
+    }
+
+    /// Skip or complete the deposit, trade or withdrawal the engine is waiting on
+    ///
+    /// Fails when no step is being waited on. The override is applied at the
+    /// waiting step's next poll.
+    pub fn override_step(&self, step_override: StepOverride) -> Result<TradingState> {
+        let state = self.get_state();
+        if !state.awaits_step() {
+            anyhow::bail!("No step is waiting for an override (state: {:?})", state);
+        }
+        tracing::warn!("Operator requested {:?} of the current step", step_override);
+        self.control.request_override(step_override);
+        Ok(state)
+    }
+
+    /// Hold here while paused; called before each step of a rebalance
+    ///
+    /// Fails with [`Interrupted`] on shutdown, before the step starts.
+    async fn step_boundary(&self) -> Result<()> {
+        self.control.checkpoint()?;
+        if !self.control.is_paused() {
+            return Ok(());
+        }
+        let previous = self.get_state();
+        self.set_state(TradingState::Paused);
+        tracing::info!("Trading engine paused before the next step");
+        self.control.wait_while_paused().await;
+        if self.get_state() == TradingState::Paused {
+            self.set_state(previous);
+        }
+        self.control.checkpoint()
+    }
+
     /// Get the current state
     pub fn get_state(&self) -> TradingState {
         self.state.read().unwrap().clone()
@@ -134,16 +360,24 @@ impl TradingEngine {
 
     /// Get trading status with balance information
     pub async fn get_status(&self) -> TradingStatus {
-        let (btc_balance, xmr_balance) = self.get_wallet_balances().await.unwrap_or((None, None));
-        let (kraken_btc, kraken_xmr) = self.get_kraken_balances().await.unwrap_or((None, None));
+        let btc_balance = self.get_bitcoin_balance().await;
+        let monero = self.get_monero_balance().await;
+        let xmr_balance = monero.as_ref().map(|balance| balance.unlocked_balance);
+        let xmr_total = monero.as_ref().map(|balance| balance.balance);
+        let (kraken_btc, kraken_xmr) = self.get_exchange_balances().await.unwrap_or((None, None));
 
         TradingStatus {
             state: self.get_state(),
             enabled: self.is_enabled(),
+            paused: self.is_paused(),
             last_check: None,     // TODO: Track this
             last_rebalance: None, // TODO: Track this
             current_btc_balance: btc_balance,
             current_xmr_balance: xmr_balance,
+            current_xmr_total_balance: xmr_total,
+            current_xmr_locked_balance: xmr_total
+                .zip(xmr_balance)
+                .map(|(total, unlocked)| (total - unlocked).max(0.0)),
             kraken_btc_balance: kraken_btc,
             kraken_xmr_balance: kraken_xmr,
         }
@@ -151,12 +385,31 @@ impl TradingEngine {
 
     /// Main trading loop
     pub async fn run(self) {
+        if let Some(lock) = self.lock.clone() {
+            let Some(lease) = self.wait_for_lock(&lock).await else {
+                tracing::info!("Trading engine stopped before acquiring the trading lock");
+                return;
+            };
+            self.set_state(if self.is_enabled() {
+                TradingState::Monitoring
+            } else {
+                TradingState::Disabled
+            });
+            tokio::spawn(self.clone().renew_lock(lock, lease.expires_at));
+        }
+
         tracing::info!("Trading engine started");
 
-        loop {
+        while !self.control.is_shutting_down() {
             if !self.is_enabled() {
                 // Sleep for a while when disabled
-                sleep(Duration::from_secs(10)).await;
+                self.control.sleep(Duration::from_secs(10)).await;
+                continue;
+            }
+
+            if self.is_paused() {
+                self.set_state(TradingState::Paused);
+                self.control.wait_while_paused().await;
                 continue;
             }
 
@@ -166,12 +419,19 @@ impl TradingEngine {
 
             // Run one iteration of the trading logic
             if let Err(e) = self.check_and_rebalance().await {
+                if e.is::<Interrupted>() {
+                    tracing::warn!(
+                        "Rebalance interrupted by shutdown in state {:?}; its transactions stay pending",
+                        self.get_state()
+                    );
+                    break;
+                }
                 tracing::error!("Trading engine error: {}", e);
                 self.set_state(TradingState::Error {
                     message: e.to_string(),
                 });
                 // Wait a bit before retrying after error
-                sleep(Duration::from_secs(60)).await;
+                self.control.sleep(Duration::from_secs(60)).await;
                 continue;
             }
 
@@ -181,7 +441,52 @@ impl TradingEngine {
             );
 
             // Sleep until next check
-            sleep(Duration::from_secs(config.check_interval_secs)).await;
+            self.control
+                .sleep(Duration::from_secs(config.check_interval_secs))
+                .await;
+        }
+
+        // Let an emergency conversion started from the API reach a checkpoint too
+        while self.emergency_active.load(Ordering::SeqCst) {
+            sleep(Duration::from_millis(100)).await;
+        }
+        tracing::info!("Trading engine stopped");
+    }
+
+    /// Keep renewing the trading lock
+    ///
+    /// A failed renewal is retried while the last lease is still valid. Once
+    /// it is lost trading stops and the engine goes back on standby, picking
+    /// up where it was (enabled or not) if it gets the lock back.
+    async fn renew_lock(self, lock: TradingLock, mut expires_at: DateTime<Utc>) {
+        loop {
+            self.control.sleep(lock.renew_interval()).await;
+            if self.control.is_shutting_down() {
+                return;
+            }
+
+            match lock.acquire().await {
+                Ok(lease) => expires_at = lease.expires_at,
+                Err(e) if Utc::now() < expires_at => {
+                    tracing::warn!("Failed to renew trading lock, will retry: {:#}", e)
+                }
+                Err(e) => {
+                    tracing::error!("Lost the trading lock: {:#}", e);
+                    let was_enabled = self.is_enabled();
+                    self.disable();
+                    self.stand_by(format!("Lost the trading lock: {:#}", e));
+
+                    let Some(lease) = self.wait_for_lock(&lock).await else {
+                        return;
+                    };
+                    expires_at = lease.expires_at;
+                    if was_enabled {
+                        self.enable();
+                    } else {
+                        self.set_state(TradingState::Disabled);
+                    }
+                }
+            }
         }
     }
 
@@ -191,11 +496,21 @@ impl TradingEngine {
 
         let config = self.config.get();
 
-        // Get current balances
-        let (btc_balance, xmr_balance) = self.get_wallet_balances().await?;
+        self.guard_holdings(&config).await?;
 
-        let btc_balance = btc_balance.context("Bitcoin balance not available")?;
-        let xmr_balance = xmr_balance.context("Monero balance not available")?;
+        // Defer rebalancing entirely while outside the configured trading windows
+        let now = self.now();
+        if !config.is_within_schedule(now) {
+            self.set_state(TradingState::OutsideSchedule);
+            tracing::info!(
+                "Outside trading schedule at {}, deferring rebalance",
+                now.format("%a %H:%M UTC")
+            );
+            return Ok(());
+        }
+
+        // Get current balances
+        let (btc_balance, xmr_balance) = self.wallet_amounts().await?;
 
         tracing::info!(
             "Trading check - Current balances: BTC={:.8}, XMR={:.8} (threshold={:.8}, target={:.8})",
@@ -206,229 +521,384 @@ impl TradingEngine {
         );
 
         // Check if rebalancing is needed
-        if xmr_balance >= config.monero_min_threshold {
-            tracing::info!(
-                "✓ No trade needed - XMR balance ({:.8}) is above minimum threshold ({:.8})",
+        if xmr_balance >= xmr_amount(config.monero_min_threshold)? {
+            if reverse_shortfall(btc_balance, xmr_balance, &config)?.is_none() {
+                tracing::info!(
+                    "✓ No trade needed - XMR balance ({}) is above minimum threshold ({:.8})",
+                    xmr_balance,
+                    config.monero_min_threshold
+                );
+                return Ok(());
+            }
+
+            tracing::warn!(
+                "⚠ Reverse trade required - BTC balance ({}) below minimum threshold ({:.8}) with XMR ({}) above ceiling ({:.8})",
+                btc_balance,
+                config.bitcoin_min_threshold,
                 xmr_balance,
-                config.monero_min_threshold
+                config.monero_max_threshold
             );
+
+            self.ensure_pending_capacity().await?;
+            self.execute_reverse_rebalance(btc_balance, xmr_balance)
+                .await?;
+
+            tracing::info!("✓ Reverse rebalance completed successfully");
             return Ok(());
         }
 
         tracing::warn!(
-            "⚠ Trade required - XMR balance ({:.8}) below minimum threshold ({:.8})",
+            "⚠ Trade required - XMR balance ({}) below minimum threshold ({:.8})",
             xmr_balance,
             config.monero_min_threshold
         );
 
-        // Calculate how much XMR we need to reach target
-        let xmr_needed = config.monero_target_balance - xmr_balance;
-        tracing::info!(
-            "→ Initiating rebalance to acquire {:.8} XMR (target balance: {:.8})",
-            xmr_needed,
-            config.monero_target_balance
-        );
+        // Received XMR that is still locked may already cover the shortfall
+        if config.wait_for_xmr_unlock {
+            let total = self
+                .get_monero_balance()
+                .await
+                .context("Monero balance not available")?;
+            let locked = pending_unlock(xmr_balance, xmr_amount(total.balance)?, &config)?;
+            if let Some(locked) = locked {
+                self.set_state(TradingState::WaitingForUnlock {
+                    locked: locked.to_f64(),
+                });
+                tracing::info!(
+                    "Waiting for {} locked XMR to unlock before rebalancing",
+                    locked
+                );
+                return Ok(());
+            }
+        }
+
+        // Refuse to start while earlier activity is still unresolved
+        self.ensure_pending_capacity().await?;
 
         // Execute the rebalancing workflow
-        self.execute_rebalance(xmr_needed).await?;
+        self.execute_rebalance(btc_balance, xmr_balance).await?;
 
         tracing::info!("✓ Rebalance completed successfully");
 
         Ok(())
     }
 
-    /// Execute the full rebalancing workflow
-    async fn execute_rebalance(&self, xmr_needed: f64) -> Result<()> {
-        let config = self.config.get();
-
-        tracing::info!("══════════════════════════════════════════════════════");
-        tracing::info!("  REBALANCE WORKFLOW STARTING");
-        tracing::info!("══════════════════════════════════════════════════════");
-
-        // Step 1: Get current BTC/XMR price from Kraken
-        let kraken = KrakenClient::new(self.kraken_api_key.clone(), self.kraken_api_secret.clone());
-
-        tracing::info!("[1/6] Fetching BTC/XMR exchange rate from Kraken...");
-        let ticker = kraken
-            .get_ticker("XBTXMR")
-            .await
-            .context("Failed to get BTC/XMR ticker from Kraken")?;
-
-        let btc_xmr_price: f64 = ticker.last_trade[0]
-            .parse()
-            .context("Failed to parse BTC/XMR price")?;
-
-        tracing::info!("  Exchange rate: 1 BTC = {:.8} XMR", 1.0 / btc_xmr_price);
-        tracing::info!("  Exchange rate: 1 XMR = {:.8} BTC", btc_xmr_price);
-
-        // Calculate how much BTC we need (with slippage buffer)
-        let slippage_multiplier = 1.0 + (config.slippage_tolerance_percent / 100.0);
-        let btc_needed = xmr_needed * btc_xmr_price * slippage_multiplier;
-
-        // Cap at max BTC per rebalance
-        let btc_to_use = btc_needed.min(config.max_btc_per_rebalance);
+    /// Value current holdings and halt on an unexplained drop since the last check
+    async fn guard_holdings(&self, config: &TradingConfig) -> Result<()> {
+        if config.max_holdings_drop_percent <= 0.0 {
+            return Ok(());
+        }
 
-        tracing::info!(
-            "  BTC needed: {:.8} (includes {:.1}% slippage tolerance)",
-            btc_to_use,
-            config.slippage_tolerance_percent
-        );
+        let snapshot = match self.holdings_snapshot().await {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                tracing::warn!("Skipping holdings check: {:#}", e);
+                return Ok(());
+            }
+        };
+        let previous = *self.last_holdings.read().unwrap();
+        let explained = match previous {
+            Some(previous) => self.transactions_since(previous.at).await,
+            None => false,
+        };
 
-        // Check if we have enough BTC (keeping reserve)
-        let (btc_balance, _) = self.get_wallet_balances().await?;
-        let btc_balance = btc_balance.context("Bitcoin balance not available")?;
+        let Some(alert) =
+            self.observe_holdings(snapshot, explained, config.max_holdings_drop_percent)
+        else {
+            return Ok(());
+        };
 
-        let btc_available = btc_balance - config.bitcoin_reserve_minimum;
-        if btc_available < btc_to_use {
-            anyhow::bail!(
-                "Insufficient BTC: need {:.8}, have {:.8} available (after reserve)",
-                btc_to_use,
-                btc_available
-            );
+        match &self.notifier {
+            Some(notifier) => notifier.notify(&alert),
+            None => tracing::error!("ALERT [{}] {}", alert.kind, alert.message),
         }
+        anyhow::bail!(alert.message)
+    }
 
-        // Step 2: Deposit BTC to Kraken
-        tracing::info!("[2/6] Depositing {:.8} BTC to Kraken", btc_to_use);
-        let btc_txid = self.deposit_bitcoin_to_kraken(btc_to_use).await?;
-        tracing::info!("  Bitcoin sent, txid: {}", btc_txid);
-
-        // Step 3: Wait for deposit to confirm
-        tracing::info!("[3/6] Waiting for BTC deposit confirmation...");
-        self.wait_for_bitcoin_deposit(&kraken, &btc_txid).await?;
-        tracing::info!("  ✓ Bitcoin deposit confirmed on Kraken");
+    /// Wallet and exchange holdings, with the current price
+    async fn holdings_snapshot(&self) -> Result<HoldingsSnapshot> {
+        let (wallet_btc, wallet_xmr) = self.wallet_amounts().await?;
+        let (kraken_btc, kraken_xmr) = self.get_exchange_balances().await?;
+        let btc_per_xmr = fetch_btc_per_xmr(&self.exchange).await?;
+
+        Ok(HoldingsSnapshot::new(
+            wallet_btc.to_f64() + kraken_btc.unwrap_or(0.0),
+            wallet_xmr.to_f64() + kraken_xmr.unwrap_or(0.0),
+            btc_per_xmr,
+            self.now(),
+        ))
+    }
 
-        // Step 4: Execute BTC->XMR trade on Kraken
-        tracing::info!("[4/6] Placing BTC→XMR trade order on Kraken");
-        let order_id = self
-            .execute_btc_to_xmr_trade(&kraken, btc_to_use, &config)
-            .await?;
-        tracing::info!("  Order placed, order_id: {}", order_id);
+    /// Whether recorded transactions could account for a change in holdings
+    ///
+    /// True if any transaction is still pending or was recorded since `since`.
+    /// Without a database nothing is recorded, so nothing is explained.
+    async fn transactions_since(&self, since: DateTime<Utc>) -> bool {
+        let Some(db) = self.get_db() else {
+            return false;
+        };
 
-        // Step 5: Wait for trade to execute
-        tracing::info!("[5/6] Waiting for trade execution...");
-        let xmr_amount = self
-            .wait_for_trade_execution(&kraken, &order_id, &config)
-            .await?;
-        tracing::info!("  ✓ Trade executed, received {:.8} XMR", xmr_amount);
+        let pending = db
+            .get_trading_transactions_by_status(TransactionStatus::Pending)
+            .await;
+        let recent = db.get_trading_transactions(since, self.now()).await;
+        match (pending, recent) {
+            (Ok(pending), Ok(recent)) => !pending.is_empty() || !recent.is_empty(),
+            (Err(e), _) | (_, Err(e)) => {
+                tracing::warn!(
+                    "Failed to read transactions for the holdings check, assuming explained: {:#}",
+                    e
+                );
+                true
+            }
+        }
+    }
 
-        // Step 6: Withdraw XMR from Kraken
-        tracing::info!(
-            "[6/6] Withdrawing {:.8} XMR from Kraken to wallet",
-            xmr_amount
+    /// Record a holdings snapshot, halting the engine on an unexplained drop
+    ///
+    /// Returns the alert to send when the engine was halted.
+    fn observe_holdings(
+        &self,
+        snapshot: HoldingsSnapshot,
+        explained: bool,
+        max_drop_percent: f64,
+    ) -> Option<Alert> {
+        let previous = self.last_holdings.write().unwrap().replace(snapshot)?;
+        if explained {
+            return None;
+        }
+        let drop_percent = snapshot.drop_exceeding(&previous, max_drop_percent)?;
+
+        let message = format!(
+            "Holdings dropped {:.1}% ({:.8} -> {:.8} BTC at the current price) since {} with no recorded transaction; trading halted",
+            drop_percent,
+            previous.total_btc_at(snapshot.btc_per_xmr),
+            snapshot.total_btc(),
+            previous.at.format("%Y-%m-%d %H:%M:%S UTC")
         );
-        let withdraw_refid = self
-            .withdraw_monero_from_kraken(&kraken, xmr_amount)
-            .await?;
-        tracing::info!("  Withdrawal initiated, refid: {}", withdraw_refid);
-
-        // Step 7: Wait for withdrawal to complete
-        tracing::info!("  Waiting for XMR withdrawal confirmation...");
-        self.wait_for_monero_withdrawal(&kraken, &withdraw_refid)
-            .await?;
-        tracing::info!("  ✓ XMR received in wallet");
+        self.disable();
+        self.set_state(TradingState::Error {
+            message: message.clone(),
+        });
 
-        tracing::info!("══════════════════════════════════════════════════════");
-        tracing::info!("  REBALANCE WORKFLOW COMPLETED");
-        tracing::info!("  Traded {:.8} BTC → {:.8} XMR", btc_to_use, xmr_amount);
-        tracing::info!("══════════════════════════════════════════════════════");
-        Ok(())
+        Some(Alert {
+            kind: "holdings_drop".to_string(),
+            severity: AlertSeverity::Critical,
+            message,
+            timestamp: snapshot.at,
+        })
     }
 
-    /// Get wallet balances (BTC, XMR)
-    async fn get_wallet_balances(&self) -> Result<(Option<f64>, Option<f64>)> {
-        let btc_balance = match BitcoinWallet::connect_existing(
-            self.bitcoin_wallet_url.clone(),
-            &self.bitcoin_wallet_cookie,
-            &self.bitcoin_wallet_name,
-        )
-        .await
-        {
-            Ok(wallet) => match wallet.get_balance().await {
-                Ok(balance) => Some(balance.balance),
-                Err(_) => None,
-            },
-            Err(_) => None,
+    /// Fail if too many transactions are still pending to safely start a rebalance
+    pub async fn ensure_pending_capacity(&self) -> Result<()> {
+        let Some(db) = self.get_db() else {
+            return Ok(());
         };
 
-        let xmr_balance = match MoneroWallet::connect_existing(
-            self.monero_wallet_url.clone(),
-            &self.monero_wallet_name,
-            &self.monero_wallet_password,
-        )
-        .await
-        {
-            Ok(wallet) => match wallet.get_balance().await {
-                Ok(balance) => Some(balance.unlocked_balance),
-                Err(_) => None,
-            },
-            Err(_) => None,
-        };
+        let max_pending = self.config.get().max_pending_transactions;
+        let pending = db
+            .get_trading_transactions_by_status(TransactionStatus::Pending)
+            .await
+            .context("Failed to count pending transactions")?
+            .len();
 
-        Ok((btc_balance, xmr_balance))
+        check_pending_limit(pending, max_pending)
     }
 
-    /// Get Kraken balances (BTC, XMR)
-    async fn get_kraken_balances(&self) -> Result<(Option<f64>, Option<f64>)> {
-        let kraken = KrakenClient::new(self.kraken_api_key.clone(), self.kraken_api_secret.clone());
+    /// Start a one-shot emergency conversion of the whole balance
+    ///
+    /// The confirmation must equal the direction's phrase. Automatic trading
+    /// is disabled so the engine doesn't trade straight back, the conversion
+    /// is sized against current balances and the legs then run in the
+    /// background. Returns the plan once sizing succeeds.
+    pub async fn start_emergency_conversion(
+        &self,
+        direction: ConversionDirection,
+        confirmation: &str,
+    ) -> Result<EmergencyPlan> {
+        verify_confirmation(direction, confirmation)?;
+        if let Some(reason) = self.trading_blocked() {
+            anyhow::bail!(reason);
+        }
+
+        let config = self.config.get();
+        config.validate().map_err(anyhow::Error::msg)?;
+
+        if self.emergency_active.swap(true, Ordering::SeqCst) {
+            anyhow::bail!("An emergency conversion is already in progress");
+        }
+        let guard = EmergencyGuard(self.emergency_active.clone());
 
-        let balances = kraken.get_balance().await?;
+        self.ensure_pending_capacity().await?;
 
-        let btc = balances.get("XXBT").and_then(|s| s.parse::<f64>().ok());
-        let xmr = balances.get("XXMR").and_then(|s| s.parse::<f64>().ok());
+        let (btc_balance, xmr_balance) = self.get_wallet_balances().await?;
+        let btc_balance = btc_amount(btc_balance.context("Bitcoin balance not available")?)?;
+        let xmr_balance = xmr_amount(xmr_balance.context("Monero balance not available")?)?;
 
-        Ok((btc, xmr))
-    }
+        let exchange = &self.exchange;
+        let btc_per_xmr = fetch_btc_per_xmr(exchange).await?;
 
-    /// Deposit Bitcoin to Kraken
-    async fn deposit_bitcoin_to_kraken(&self, amount: f64) -> Result<String> {
-        self.set_state(TradingState::DepositingBitcoin { amount });
+        let plan =
+            plan_emergency_conversion(direction, btc_balance, xmr_balance, btc_per_xmr, &config)?;
 
-        let kraken = KrakenClient::new(self.kraken_api_key.clone(), self.kraken_api_secret.clone());
+        self.disable();
+        tracing::warn!(
+            "EMERGENCY CONVERSION {:?}: {} BTC / {} XMR at {} BTC/XMR{}",
+            plan.direction,
+            plan.btc_amount,
+            plan.xmr_amount,
+            plan.btc_per_xmr,
+            if plan.capped {
+                " (capped by max_btc_per_rebalance)"
+            } else {
+                ""
+            }
+        );
 
-        // Get Kraken BTC deposit address
-        let deposit_address = kraken
-            .get_btc_deposit_address(false)
-            .await
-            .context("Failed to get Kraken BTC deposit address")?;
+        let engine = self.clone();
+        let background_plan = plan.clone();
+        tokio::spawn(async move {
+            let _guard = guard;
+            match engine.execute_emergency_conversion(&background_plan).await {
+                Ok(()) => engine.set_state(TradingState::Disabled),
+                Err(e) => {
+                    tracing::error!("Emergency conversion failed: {:#}", e);
+                    engine.set_state(TradingState::Error {
+                        message: format!("Emergency conversion failed: {}", e),
+                    });
+                }
+            }
+        });
 
-        tracing::debug!("Kraken BTC deposit address: {}", deposit_address);
+        Ok(plan)
+    }
 
-        // Create transaction record before sending
-        let transaction = StoredTradingTransaction {
+    /// Run the legs of an emergency conversion, recording it in the audit log
+    async fn execute_emergency_conversion(&self, plan: &EmergencyPlan) -> Result<()> {
+        let config = self.config.get();
+        let exchange = &self.exchange;
+
+        let record = StoredTradingTransaction {
             id: None,
             timestamp: Utc::now(),
-            transaction_type: TransactionType::BitcoinDeposit,
+            transaction_type: TransactionType::EmergencyConversion,
             status: TransactionStatus::Pending,
-            btc_amount: Some(amount),
-            xmr_amount: None,
-            exchange_rate: None,
+            btc_amount: Some(plan.btc_amount.to_f64()),
+            xmr_amount: Some(plan.xmr_amount.to_f64()),
+            exchange_rate: Some(plan.btc_per_xmr),
             txid: None,
             order_id: None,
             refid: None,
             from_address: None,
-            to_address: Some(deposit_address.clone()),
+            to_address: None,
             fee: None,
-            notes: Some(format!("Depositing {:.8} BTC to Kraken", amount)),
+            notes: Some(format!(
+                "Emergency {:?} conversion requested by operator",
+                plan.direction
+            )),
             error_message: None,
             completed_at: None,
+            balance_before: None,
+            balance_after: None,
+            replaced_txids: Vec::new(),
+            psbt: None,
+            mid_price: None,
+        };
+        let record_id = match self.get_db() {
+            Some(db) => db.store_trading_transaction(&record).await.ok(),
+            None => None,
         };
 
-        let transaction_id = if let Some(db) = self.get_db() {
-            match db.store_trading_transaction(&transaction).await {
-                Ok(id) => {
-                    tracing::debug!("Created transaction record: {}", id);
-                    Some(id)
+        let result = match plan.direction {
+            ConversionDirection::BtcToXmr => {
+                async {
+                    let sent_at = self.now();
+                    let txid = self.deposit_bitcoin_to_exchange(plan.btc_amount).await?;
+                    self.wait_for_bitcoin_deposit(exchange, &txid, plan.btc_amount, sent_at)
+                        .await?;
+                    let order_id = self
+                        .execute_btc_to_xmr_trade(exchange, plan.btc_amount, &config)
+                        .await?;
+                    let fill = self
+                        .wait_for_trade_execution(exchange, &order_id, &config)
+                        .await?;
+                    let refid = self
+                        .withdraw_monero_from_exchange(exchange, fill.xmr)
+                        .await?;
+                    self.wait_for_withdrawal(exchange, Asset::Xmr, &refid)
+                        .await?;
+                    anyhow::Ok((plan.btc_amount, fill.xmr))
                 }
-                Err(e) => {
-                    tracing::warn!("Failed to store transaction record: {}", e);
-                    None
+                .await
+            }
+            ConversionDirection::XmrToBtc => {
+                async {
+                    let txid = self
+                        .deposit_monero_to_exchange(exchange, plan.xmr_amount, !plan.capped)
+                        .await?;
+                    let deposited = self.wait_for_monero_deposit(exchange, &txid).await?;
+                    let order_id = self
+                        .execute_xmr_to_btc_trade(exchange, deposited, &config)
+                        .await?;
+                    let fill = self
+                        .wait_for_trade_execution(exchange, &order_id, &config)
+                        .await?;
+                    let btc = fill
+                        .btc
+                        .context("The exchange did not report the BTC proceeds of the sale")?;
+                    let refid = self.withdraw_bitcoin_from_exchange(exchange, btc).await?;
+                    self.wait_for_withdrawal(exchange, Asset::Btc, &refid)
+                        .await?;
+                    anyhow::Ok((btc, fill.xmr))
                 }
+                .await
             }
-        } else {
-            None
         };
 
-        // Send BTC from our wallet to Kraken
+        if let (Some(db), Some(id)) = (self.get_db(), record_id.as_ref()) {
+            let _ = match &result {
+                Ok((btc, xmr)) => {
+                    let mut completed = record.clone();
+                    completed.status = TransactionStatus::Completed;
+                    completed.completed_at = Some(Utc::now());
+                    completed.btc_amount = Some(btc.to_f64());
+                    completed.xmr_amount = Some(xmr.to_f64());
+                    db.update_trading_transaction(id, &completed).await
+                }
+                Err(e) => db.fail_trading_transaction(id, format!("{:#}", e)).await,
+            };
+        }
+
+        let (btc, xmr) = result?;
+        tracing::warn!(
+            "EMERGENCY CONVERSION COMPLETE: {:?} {} BTC / {} XMR",
+            plan.direction,
+            btc,
+            xmr
+        );
+        Ok(())
+    }
+
+    /// Work out what the engine would do under `config` right now, without acting on it
+    pub async fn preview_rebalance(&self, config: &TradingConfig) -> Result<RebalancePreview> {
+        let (btc_balance, xmr_balance) = self.wallet_amounts().await?;
+
+        let exchange = &self.exchange;
+        let btc_per_xmr = fetch_btc_per_xmr(exchange).await?;
+
+        Ok(RebalancePreview {
+            btc_balance,
+            xmr_balance,
+            btc_per_xmr,
+            decision: decide_rebalance(btc_balance, xmr_balance, btc_per_xmr, config),
+        })
+    }
+
+    /// Estimate the fees of a BTC->XMR rebalance buying `xmr_needed`, without moving funds
+    pub async fn estimate_rebalance_cost(&self, xmr_needed: Xmr) -> Result<CostEstimate> {
+        let exchange = &self.exchange;
+        let btc_per_xmr = fetch_btc_per_xmr(exchange).await?;
+
         let btc_wallet = BitcoinWallet::connect_existing(
             self.bitcoin_wallet_url.clone(),
             &self.bitcoin_wallet_cookie,
@@ -436,136 +906,527 @@ impl TradingEngine {
         )
         .await
         .context("Failed to connect to Bitcoin wallet")?;
-
-        let txid = match btc_wallet
-            .send_to_address(&deposit_address, amount, false)
+        // sat/vB -> BTC/kvB
+        let bitcoin_feerate = self
+            .deposit_fee_rate(&btc_wallet)
             .await
-        {
-            Ok(txid) => txid,
-            Err(e) => {
-                // Mark transaction as failed
-                if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
-                    let _ = db.fail_trading_transaction(id, e.to_string()).await;
-                }
-                return Err(e).context("Failed to send Bitcoin to Kraken");
-            }
-        };
+            .context("Failed to estimate the Bitcoin feerate")?
+            .context("bitcoind has too little data for a feerate estimate")?
+            / 1e5;
 
-        tracing::debug!("Bitcoin transaction broadcast, txid: {}", txid);
+        let trading_fee_percent = exchange
+            .trading_fee_percent()
+            .await
+            .with_context(|| format!("Failed to get {} trading fee", exchange.name()))?;
+        let xmr_withdrawal_fee = exchange
+            .withdrawal_fee(Asset::Xmr, &xmr_needed.to_string())
+            .await
+            .with_context(|| format!("Failed to get {} XMR withdrawal fee", exchange.name()))?;
 
-        // Update transaction with txid
-        if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
-            let mut updated_transaction = transaction.clone();
-            updated_transaction.txid = Some(txid.clone());
-            let _ = db
-                .update_trading_transaction(id, &updated_transaction)
-                .await;
-        }
+        Ok(CostEstimate::new(
+            exchange.name(),
+            xmr_needed,
+            &FeeQuotes {
+                btc_per_xmr,
+                bitcoin_feerate,
+                trading_fee_percent,
+                xmr_withdrawal_fee,
+            },
+        ))
+    }
 
-        self.set_state(TradingState::WaitingForBitcoinDeposit { txid: txid.clone() });
+    /// Cancel every open exchange order
+    ///
+    /// Pauses and disables the engine first so no new order is placed while
+    /// the cancel is in flight, nor by a rebalance in progress once it reaches
+    /// its next step; that rebalance holds there until the engine is resumed.
+    /// Pending trade transactions are marked cancelled.
+    pub async fn cancel_all_orders(&self) -> Result<CancelAllSummary> {
+        self.pause();
+        self.disable();
+
+        let orders_cancelled =
+            self.exchange.cancel_open_orders().await.with_context(|| {
+                format!("Failed to cancel open {} orders", self.exchange.name())
+            })?;
+        tracing::warn!(
+            "Cancelled {} open {} orders",
+            orders_cancelled,
+            self.exchange.name()
+        );
 
-        Ok(txid)
+        let mut transactions_cancelled = 0;
+        if let Some(db) = self.get_db() {
+            let pending = db
+                .get_trading_transactions_by_status(TransactionStatus::Pending)
+                .await?;
+            for tx in pending
+                .iter()
+                .filter(|tx| tx.transaction_type == TransactionType::Trade && tx.order_id.is_some())
+            {
+                if let Some(id) = &tx.id {
+                    db.cancel_trading_transaction(id, "Cancelled by cancel-all".to_string())
+                        .await?;
+                    transactions_cancelled += 1;
+                }
+            }
+        }
+
+        Ok(CancelAllSummary {
+            orders_cancelled,
+            transactions_cancelled,
+        })
     }
 
-    /// Wait for Bitcoin deposit to confirm on Kraken
-    async fn wait_for_bitcoin_deposit(&self, kraken: &KrakenClient, txid: &str) -> Result<()> {
-        // Poll deposit status until confirmed
-        let timeout = Duration::from_secs(3600); // 1 hour timeout
-        let start = std::time::Instant::now();
+    /// Whether a rebalance moving `btc` and `xmr` may start now
+    ///
+    /// Below the approval thresholds it always may. Above them it needs an
+    /// unexpired approved rebalance for about the same amounts, which is used
+    /// up here; until there is one, the engine asks for approval once and then
+    /// waits in [`TradingState::WaitingForApproval`], checking again each
+    /// interval.
+    async fn rebalance_approved(
+        &self,
+        direction: ConversionDirection,
+        btc: Btc,
+        xmr: Xmr,
+    ) -> Result<bool> {
+        let (btc, xmr) = (btc.to_f64(), xmr.to_f64());
+        let approvals = self
+            .app_config
+            .as_ref()
+            .map(|config| config.get().approvals.clone())
+            .unwrap_or_default();
+        if !approvals.requires_approval(btc, xmr) {
+            return Ok(true);
+        }
+        let db = self
+            .get_db()
+            .context("Rebalances above the approval threshold need the database")?;
+        let now = self.now();
+
+        for approval in db.get_approvals(Some(ApprovalStatus::Approved)).await? {
+            let id = approval.id.clone().context("Approval without an id")?;
+            if approval.is_expired(now, approvals.expiry_secs) {
+                let expired = Approval {
+                    status: ApprovalStatus::Expired,
+                    ..approval
+                };
+                db.transition_approval(&id, ApprovalStatus::Approved, &expired)
+                    .await?;
+                continue;
+            }
+            if !approval.covers_rebalance(direction, btc, xmr) {
+                continue;
+            }
+            let used = Approval {
+                status: ApprovalStatus::Executed,
+                ..approval
+            };
+            if db
+                .transition_approval(&id, ApprovalStatus::Approved, &used)
+                .await?
+            {
+                tracing::info!("Starting {:?} rebalance under approval {}", direction, id);
+                return Ok(true);
+            }
+        }
 
-        loop {
-            if start.elapsed() > timeout {
-                anyhow::bail!("Timeout waiting for Bitcoin deposit confirmation");
+        let pending = db
+            .get_approvals(Some(ApprovalStatus::Pending))
+            .await?
+            .into_iter()
+            .find(|approval| {
+                approval.matches_rebalance(direction, btc, xmr)
+                    && !approval.is_expired(now, approvals.expiry_secs)
+            });
+        let approval_id = match pending.and_then(|approval| approval.id) {
+            Some(id) => id,
+            None => {
+                let approval = Approval::new(
+                    ApprovalAction::Rebalance { direction },
+                    btc,
+                    xmr,
+                    Principal::TradingEngine,
+                    now,
+                );
+                let id = db.store_approval(&approval).await?;
+                tracing::warn!(
+                    "{:?} rebalance of {} BTC / {} XMR needs approval: {}",
+                    direction,
+                    btc,
+                    xmr,
+                    id
+                );
+                id
             }
+        };
 
-            let deposits = kraken.get_deposit_status(Some("XBT")).await?;
+        self.set_state(TradingState::WaitingForApproval { approval_id });
+        Ok(false)
+    }
 
-            // Check if we have a recent confirmed deposit
-            // Note: This is simplified - in production you'd want to match the specific txid
-            if let Some(deposit) = deposits.first() {
-                if deposit.status == "Success" {
-                    tracing::debug!("Bitcoin deposit confirmed on Kraken");
+    /// Execute the full rebalancing workflow
+    async fn execute_rebalance(&self, btc_balance: Btc, xmr_balance: Xmr) -> Result<()> {
+        let config = self.config.get();
 
-                    // Mark transaction as completed
-                    if let Some(db) = self.get_db() {
-                        if let Ok(transactions) = db.get_recent_trading_transactions(10).await {
-                            if let Some(tx) = transactions.iter().find(|t| {
-                                t.txid.as_ref() == Some(&txid.to_string())
-                                    && t.status == TransactionStatus::Pending
-                            }) {
-                                if let Some(id) = &tx.id {
-                                    let _ = db.complete_trading_transaction(id, None, None).await;
-                                }
-                            }
-                        }
-                    }
+        tracing::info!("══════════════════════════════════════════════════════");
+        tracing::info!("  REBALANCE WORKFLOW STARTING");
+        tracing::info!("══════════════════════════════════════════════════════");
+
+        // Step 1: Get current BTC/XMR price from the exchange
+        let exchange = &self.exchange;
+
+        tracing::info!("[1/6] Fetching BTC/XMR exchange rate from the exchange...");
+        let btc_xmr_price = fetch_btc_per_xmr(exchange).await?;
+
+        tracing::info!("  Exchange rate: 1 BTC = {:.8} XMR", 1.0 / btc_xmr_price);
+        tracing::info!("  Exchange rate: 1 XMR = {:.8} BTC", btc_xmr_price);
 
+        let (xmr_needed, btc_to_use) =
+            match decide_rebalance(btc_balance, xmr_balance, btc_xmr_price, &config) {
+                RebalanceDecision::Hold | RebalanceDecision::SellXmr { .. } => {
+                    tracing::info!("  No rebalance due at this price, nothing to do");
                     return Ok(());
                 }
-            }
+                RebalanceDecision::Blocked { reason, .. } => anyhow::bail!(reason),
+                RebalanceDecision::Rebalance {
+                    xmr_needed,
+                    btc_to_spend,
+                } => {
+                    tracing::info!(
+                        "→ Acquiring {} XMR (target balance: {:.8})",
+                        xmr_needed,
+                        config.monero_target_balance
+                    );
+                    (xmr_needed, btc_to_spend)
+                }
+            };
 
-            tracing::debug!("Waiting for Bitcoin deposit confirmation...");
-            sleep(Duration::from_secs(30)).await;
+        tracing::info!(
+            "  BTC needed: {} (includes {:.1}% slippage tolerance)",
+            btc_to_use,
+            config.slippage_tolerance_percent
+        );
+
+        if !self
+            .rebalance_approved(ConversionDirection::BtcToXmr, btc_to_use, xmr_needed)
+            .await?
+        {
+            return Ok(());
         }
+
+        // Step 2: Deposit BTC to the exchange
+        tracing::info!("[2/6] Depositing {} BTC to the exchange", btc_to_use);
+        let sent_at = self.now();
+        let btc_txid = self.deposit_bitcoin_to_exchange(btc_to_use).await?;
+        tracing::info!("  Bitcoin sent, txid: {}", btc_txid);
+
+        // Step 3: Wait for deposit to confirm
+        tracing::info!("[3/6] Waiting for BTC deposit confirmation...");
+        self.wait_for_bitcoin_deposit(exchange, &btc_txid, btc_to_use, sent_at)
+            .await?;
+        tracing::info!("  ✓ Bitcoin deposit confirmed on the exchange");
+
+        // Step 4: Execute BTC->XMR trade on the exchange
+        tracing::info!("[4/6] Placing BTC→XMR trade order on the exchange");
+        let order_id = self
+            .execute_btc_to_xmr_trade(exchange, btc_to_use, &config)
+            .await?;
+        tracing::info!("  Order placed, order_id: {}", order_id);
+
+        // Step 5: Wait for trade to execute
+        tracing::info!("[5/6] Waiting for trade execution...");
+        let xmr_amount = self
+            .wait_for_trade_execution(exchange, &order_id, &config)
+            .await?
+            .xmr;
+        tracing::info!("  ✓ Trade executed, received {} XMR", xmr_amount);
+
+        // Step 6: Withdraw XMR from the exchange
+        tracing::info!(
+            "[6/6] Withdrawing {} XMR from the exchange to wallet",
+            xmr_amount
+        );
+        let withdraw_refid = self
+            .withdraw_monero_from_exchange(exchange, xmr_amount)
+            .await?;
+        tracing::info!("  Withdrawal initiated, refid: {}", withdraw_refid);
+
+        // Step 7: Wait for withdrawal to complete
+        tracing::info!("  Waiting for XMR withdrawal confirmation...");
+        self.wait_for_withdrawal(exchange, Asset::Xmr, &withdraw_refid)
+            .await?;
+        tracing::info!("  ✓ XMR received in wallet");
+
+        tracing::info!("══════════════════════════════════════════════════════");
+        tracing::info!("  REBALANCE WORKFLOW COMPLETED");
+        tracing::info!("  Traded {} BTC → {} XMR", btc_to_use, xmr_amount);
+        tracing::info!("══════════════════════════════════════════════════════");
+        Ok(())
     }
 
-    /// Execute BTC->XMR trade on Kraken
-    async fn execute_btc_to_xmr_trade(
-        &self,
-        kraken: &KrakenClient,
-        btc_amount: f64,
-        config: &crate::trading::config::TradingConfig,
-    ) -> Result<String> {
-        self.set_state(TradingState::Trading { btc_amount });
+    /// Execute the reverse workflow, selling XMR to top the Bitcoin wallet back up
+    async fn execute_reverse_rebalance(&self, btc_balance: Btc, xmr_balance: Xmr) -> Result<()> {
+        let config = self.config.get();
 
-        let order_type = if config.use_limit_orders {
-            "limit"
-        } else {
-            "market"
-        };
+        tracing::info!("══════════════════════════════════════════════════════");
+        tracing::info!("  REVERSE REBALANCE WORKFLOW STARTING");
+        tracing::info!("══════════════════════════════════════════════════════");
 
-        // For limit orders, calculate a price with slippage tolerance
-        let (price, exchange_rate) = if config.use_limit_orders {
-            let ticker = kraken.get_ticker("XBTXMR").await?;
-            let current_price: f64 = ticker.ask[0].parse()?;
-            let price_with_slippage =
-                current_price * (1.0 + config.slippage_tolerance_percent / 100.0);
-            (
-                Some(format!("{:.8}", price_with_slippage)),
-                Some(current_price),
-            )
-        } else {
-            (None, None)
-        };
+        // Step 1: Get current BTC/XMR price from the exchange
+        let exchange = &self.exchange;
 
-        // Create transaction record before placing order
+        tracing::info!("[1/6] Fetching BTC/XMR exchange rate from the exchange...");
+        let btc_xmr_price = fetch_btc_per_xmr(exchange).await?;
+
+        tracing::info!("  Exchange rate: 1 XMR = {:.8} BTC", btc_xmr_price);
+
+        let (btc_needed, xmr_to_sell) =
+            match decide_rebalance(btc_balance, xmr_balance, btc_xmr_price, &config) {
+                RebalanceDecision::SellXmr {
+                    btc_needed,
+                    xmr_to_sell,
+                } => {
+                    tracing::info!(
+                        "→ Recovering {} BTC (target balance: {:.8})",
+                        btc_needed,
+                        config.bitcoin_target_balance
+                    );
+                    (btc_needed, xmr_to_sell)
+                }
+                RebalanceDecision::Blocked { reason, .. } => anyhow::bail!(reason),
+                RebalanceDecision::Hold | RebalanceDecision::Rebalance { .. } => {
+                    tracing::info!("  Reverse rebalance no longer due, nothing to do");
+                    return Ok(());
+                }
+            };
+
+        tracing::info!(
+            "  XMR to sell: {} (includes {:.1}% slippage tolerance)",
+            xmr_to_sell,
+            config.slippage_tolerance_percent
+        );
+
+        if !self
+            .rebalance_approved(ConversionDirection::XmrToBtc, btc_needed, xmr_to_sell)
+            .await?
+        {
+            return Ok(());
+        }
+
+        // Step 2: Deposit XMR to the exchange
+        tracing::info!("[2/6] Depositing {} XMR to the exchange", xmr_to_sell);
+        let xmr_txid = self
+            .deposit_monero_to_exchange(exchange, xmr_to_sell, false)
+            .await?;
+        tracing::info!("  Monero sent, txid: {}", xmr_txid);
+
+        // Step 3: Wait for deposit to be credited
+        tracing::info!("[3/6] Waiting for XMR deposit confirmation...");
+        let deposited = self.wait_for_monero_deposit(exchange, &xmr_txid).await?;
+        tracing::info!("  ✓ {} XMR credited on the exchange", deposited);
+
+        // Step 4: Execute XMR->BTC trade on the exchange
+        tracing::info!("[4/6] Placing XMR→BTC trade order on the exchange");
+        let order_id = self
+            .execute_xmr_to_btc_trade(exchange, deposited, &config)
+            .await?;
+        tracing::info!("  Order placed, order_id: {}", order_id);
+
+        // Step 5: Wait for trade to execute
+        tracing::info!("[5/6] Waiting for trade execution...");
+        let fill = self
+            .wait_for_trade_execution(exchange, &order_id, &config)
+            .await?;
+        let btc_received = fill
+            .btc
+            .context("The exchange did not report the BTC proceeds of the sale")?;
+        tracing::info!("  ✓ Trade executed, received {} BTC", btc_received);
+
+        // Step 6: Withdraw BTC from the exchange
+        tracing::info!(
+            "[6/6] Withdrawing {} BTC from the exchange to wallet",
+            btc_received
+        );
+        let withdraw_refid = self
+            .withdraw_bitcoin_from_exchange(exchange, btc_received)
+            .await?;
+        tracing::info!("  Withdrawal initiated, refid: {}", withdraw_refid);
+
+        // Step 7: Wait for withdrawal to complete
+        tracing::info!("  Waiting for BTC withdrawal confirmation...");
+        self.wait_for_withdrawal(exchange, Asset::Btc, &withdraw_refid)
+            .await?;
+        tracing::info!("  ✓ BTC received in wallet");
+
+        tracing::info!("══════════════════════════════════════════════════════");
+        tracing::info!("  REVERSE REBALANCE WORKFLOW COMPLETED");
+        tracing::info!("  Traded {} XMR → {} BTC", fill.xmr, btc_received);
+        tracing::info!("══════════════════════════════════════════════════════");
+        Ok(())
+    }
+
+    /// Get wallet balances (BTC, XMR)
+    async fn get_wallet_balances(&self) -> Result<(Option<f64>, Option<f64>)> {
+        let btc_balance = self.get_bitcoin_balance().await;
+        let xmr_balance = self
+            .get_monero_balance()
+            .await
+            .map(|balance| balance.unlocked_balance);
+
+        Ok((btc_balance, xmr_balance))
+    }
+
+    /// Confirmed Bitcoin wallet balance
+    async fn get_bitcoin_balance(&self) -> Option<f64> {
+        let wallet = BitcoinWallet::connect_existing(
+            self.bitcoin_wallet_url.clone(),
+            &self.bitcoin_wallet_cookie,
+            &self.bitcoin_wallet_name,
+        )
+        .await
+        .ok()?;
+
+        wallet
+            .get_balance()
+            .await
+            .ok()
+            .map(|balance| balance.balance)
+    }
+
+    /// Monero wallet balance, both total and unlocked
+    async fn get_monero_balance(&self) -> Option<WalletBalance> {
+        let wallet = MoneroWallet::connect_existing(
+            self.monero_wallet_url.clone(),
+            &self.monero_wallet_name,
+            &self.monero_wallet_password,
+        )
+        .await
+        .ok()?;
+
+        wallet.get_balance().await.ok()
+    }
+
+    /// Wallet balances as exact amounts, failing if either wallet is unavailable
+    async fn wallet_amounts(&self) -> Result<(Btc, Xmr)> {
+        let (btc_balance, xmr_balance) = self.get_wallet_balances().await?;
+
+        let btc_balance = btc_balance.context("Bitcoin balance not available")?;
+        let xmr_balance = xmr_balance.context("Monero balance not available")?;
+
+        Ok((btc_amount(btc_balance)?, xmr_amount(xmr_balance)?))
+    }
+
+    /// Get exchange balances (BTC, XMR)
+    async fn get_exchange_balances(&self) -> Result<(Option<f64>, Option<f64>)> {
+        let balances = self.exchange.balances().await?;
+        Ok((balances.btc, balances.xmr))
+    }
+
+    /// Deposit Bitcoin to the exchange
+    async fn deposit_bitcoin_to_exchange(&self, amount: Btc) -> Result<String> {
+        self.step_boundary().await?;
+        self.set_state(TradingState::DepositingBitcoin {
+            amount: amount.to_f64(),
+        });
+
+        let exchange = &self.exchange;
+
+        let btc_wallet = BitcoinWallet::connect_existing(
+            self.bitcoin_wallet_url.clone(),
+            &self.bitcoin_wallet_cookie,
+            &self.bitcoin_wallet_name,
+        )
+        .await
+        .context("Failed to connect to Bitcoin wallet")?;
+
+        // Get an exchange BTC deposit address our node accepts
+        let wallet = &btc_wallet;
+        let (deposit_address, rejected) = valid_deposit_address(
+            |new| exchange.deposit_address(Asset::Btc, new),
+            move |address| async move { wallet.validate_address(&address).await },
+        )
+        .await
+        .with_context(|| format!("Failed to get {} BTC deposit address", exchange.name()))?;
+
+        tracing::debug!(
+            "{} BTC deposit address: {}",
+            exchange.name(),
+            deposit_address
+        );
+
+        let mut notes = format!("Depositing {} BTC to {}", amount, exchange.name());
+        if !rejected.is_empty() {
+            notes.push_str(&format!(
+                "; rejected {} invalid deposit address(es): {}",
+                rejected.len(),
+                rejected.join(", ")
+            ));
+        }
+
+        let reservation = self
+            .spend_limits
+            .reserve(TransferAsset::Btc, amount.to_f64())
+            .await?;
+
+        let config = self.config.get();
+        if config.psbt_enabled() && amount >= btc_amount(config.psbt_min_btc)? {
+            let txid = match self
+                .deposit_bitcoin_via_psbt(&btc_wallet, &deposit_address, amount, notes, &config)
+                .await
+            {
+                Ok(txid) => txid,
+                // Only the wait was cut short, and the PSBT may still be
+                // signed and broadcast, so it stays counted
+                Err(e) if e.is::<Interrupted>() => {
+                    reservation
+                        .commit(amount.to_f64(), ENGINE_REQUESTER, None)
+                        .await;
+                    return Err(e);
+                }
+                Err(e) => return Err(e),
+            };
+            reservation
+                .commit(amount.to_f64(), ENGINE_REQUESTER, Some(txid.clone()))
+                .await;
+            self.set_state(TradingState::WaitingForBitcoinDeposit { txid: txid.clone() });
+            return Ok(txid);
+        }
+
+        // Create transaction record before sending
         let transaction = StoredTradingTransaction {
             id: None,
             timestamp: Utc::now(),
-            transaction_type: TransactionType::Trade,
+            transaction_type: TransactionType::BitcoinDeposit,
             status: TransactionStatus::Pending,
-            btc_amount: Some(btc_amount),
+            btc_amount: Some(amount.to_f64()),
             xmr_amount: None,
-            exchange_rate,
+            exchange_rate: None,
             txid: None,
             order_id: None,
             refid: None,
             from_address: None,
-            to_address: None,
+            to_address: Some(deposit_address.clone()),
             fee: None,
-            notes: Some(format!("Trading {:.8} BTC for XMR", btc_amount)),
+            notes: Some(notes),
             error_message: None,
             completed_at: None,
+            balance_before: None,
+            balance_after: None,
+            replaced_txids: Vec::new(),
+            psbt: None,
+            mid_price: None,
         };
 
         let transaction_id = if let Some(db) = self.get_db() {
             match db.store_trading_transaction(&transaction).await {
                 Ok(id) => {
-                    tracing::debug!("Created trade transaction record: {}", id);
+                    tracing::debug!("Created transaction record: {}", id);
                     Some(id)
                 }
                 Err(e) => {
-                    tracing::warn!("Failed to store trade transaction record: {}", e);
+                    tracing::warn!("Failed to store transaction record: {}", e);
                     None
                 }
             }
@@ -573,194 +1434,443 @@ impl TradingEngine {
             None
         };
 
-        let order = match kraken
-            .place_order(
-                "XBTXMR",
-                "buy",
-                order_type,
-                &format!("{:.8}", btc_amount),
-                price.as_deref(),
-            )
+        // Send BTC from our wallet to the exchange
+        let txid = match btc_wallet
+            .send_to_address(&deposit_address, amount.to_f64(), false)
             .await
         {
-            Ok(order) => order,
+            Ok(txid) => txid,
             Err(e) => {
                 // Mark transaction as failed
                 if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
                     let _ = db.fail_trading_transaction(id, e.to_string()).await;
                 }
-                return Err(e).context("Failed to place order on Kraken");
+                return Err(e)
+                    .with_context(|| format!("Failed to send Bitcoin to {}", exchange.name()));
             }
         };
 
-        let order_id = order
-            .txid
-            .first()
-            .context("No order ID returned from Kraken")?
-            .clone();
-
-        tracing::debug!("Order placed on Kraken: {}", order_id);
+        tracing::debug!("Bitcoin transaction broadcast, txid: {}", txid);
+        reservation
+            .commit(amount.to_f64(), ENGINE_REQUESTER, Some(txid.clone()))
+            .await;
 
-        // Update transaction with order_id
+        // Update transaction with txid and the network fee paid
         if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
             let mut updated_transaction = transaction.clone();
-            updated_transaction.order_id = Some(order_id.clone());
+            updated_transaction.txid = Some(txid.clone());
+            match btc_wallet.get_transaction(&txid).await {
+                // bitcoind reports the fee of a send as negative
+                Ok(sent) => updated_transaction.fee = sent.fee.map(f64::abs),
+                Err(e) => tracing::warn!("Failed to look up the fee of {}: {}", txid, e),
+            }
             let _ = db
                 .update_trading_transaction(id, &updated_transaction)
                 .await;
         }
 
-        self.set_state(TradingState::WaitingForTradeExecution {
-            order_id: order_id.clone(),
-        });
+        self.set_state(TradingState::WaitingForBitcoinDeposit { txid: txid.clone() });
 
-        Ok(order_id)
+        Ok(txid)
     }
 
-    /// Wait for trade to execute
-    async fn wait_for_trade_execution(
+    /// Fund the deposit as a PSBT and wait for an operator to sign it offline
+    ///
+    /// # Returns
+    /// The txid of the signed deposit, once broadcast
+    async fn deposit_bitcoin_via_psbt(
         &self,
-        kraken: &KrakenClient,
-        order_id: &str,
-        config: &crate::trading::config::TradingConfig,
-    ) -> Result<f64> {
-        let timeout = Duration::from_secs(config.order_timeout_secs);
-        let start = std::time::Instant::now();
+        btc_wallet: &BitcoinWallet,
+        deposit_address: &str,
+        amount: Btc,
+        notes: String,
+        config: &TradingConfig,
+    ) -> Result<String> {
+        // The record is the only way to get the PSBT to the signer
+        let db = self
+            .get_db()
+            .context("Signing deposits offline requires the database")?;
 
-        loop {
-            if start.elapsed() > timeout {
-                let error_msg = "Timeout waiting for order execution".to_string();
+        let funded = btc_wallet
+            .create_funded_psbt(deposit_address, amount, None)
+            .await
+            .context("Failed to create deposit PSBT")?;
+        let record = psbt::psbt_record(
+            TransactionType::BitcoinDeposit,
+            deposit_address,
+            amount,
+            &funded,
+            format!("{}; awaiting offline signature", notes),
+        );
+        let transaction_id = match db.store_trading_transaction(&record).await {
+            Ok(id) => id,
+            Err(e) => {
+                let _ = btc_wallet.unlock_psbt_inputs(&funded.psbt).await;
+                return Err(e).context("Failed to store deposit PSBT");
+            }
+        };
 
-                // Mark transaction as failed
-                if let Some(db) = self.get_db() {
-                    if let Ok(transactions) = db.get_recent_trading_transactions(10).await {
-                        if let Some(tx) = transactions.iter().find(|t| {
-                            t.order_id.as_ref() == Some(&order_id.to_string())
-                                && t.status == TransactionStatus::Pending
-                        }) {
-                            if let Some(id) = &tx.id {
-                                let _ = db.fail_trading_transaction(id, error_msg.clone()).await;
-                            }
-                        }
-                    }
-                }
+        self.set_state(TradingState::WaitingForSignature {
+            transaction_id: transaction_id.clone(),
+        });
+        let alert = Alert {
+            kind: "psbt_awaiting_signature".to_string(),
+            severity: AlertSeverity::Warning,
+            message: format!(
+                "Deposit of {} BTC to {} is waiting for an offline signature: fetch the PSBT from /wallets/bitcoin/psbt/{}",
+                amount,
+                self.exchange.name(),
+                transaction_id
+            ),
+            timestamp: Utc::now(),
+        };
+        match &self.notifier {
+            Some(notifier) => notifier.notify(&alert),
+            None => tracing::warn!("ALERT [{}] {}", alert.kind, alert.message),
+        }
+
+        let result = self
+            .wait_for_signature(db, &transaction_id, &funded.txid, config)
+            .await;
+        // Nothing was broadcast, so the coins can go to the next send, unless
+        // only the wait was cut short and the PSBT may still be signed
+        if result.as_ref().is_err_and(|e| !e.is::<Interrupted>()) {
+            if let Err(e) = btc_wallet.unlock_psbt_inputs(&funded.psbt).await {
+                tracing::warn!("Failed to unlock inputs of deposit PSBT: {:#}", e);
+            }
+        }
+        result
+    }
+
+    /// Poll the deposit record `transaction_id` until its signed PSBT is broadcast
+    async fn wait_for_signature(
+        &self,
+        db: &MetricsDatabase,
+        transaction_id: &str,
+        txid: &str,
+        config: &TradingConfig,
+    ) -> Result<String> {
+        self.step_boundary().await?;
+        self.control.clear_override();
+        let timeout = Duration::from_secs(config.psbt_signing_timeout_secs);
+        let start = std::time::Instant::now();
 
+        loop {
+            if start.elapsed() > timeout {
+                let error_msg = "Timeout waiting for the deposit PSBT to be signed".to_string();
+                let _ = db
+                    .fail_trading_transaction(transaction_id, error_msg.clone())
+                    .await;
                 anyhow::bail!(error_msg);
             }
 
-            let order_status = kraken.query_order(order_id).await?;
+            match db.get_trading_transaction(transaction_id).await {
+                Ok(Some(record)) => match record.status {
+                    TransactionStatus::Pending if record.psbt.is_none() => {
+                        tracing::debug!("Signed deposit PSBT broadcast as {}", txid);
+                        return Ok(record.txid.unwrap_or_else(|| txid.to_string()));
+                    }
+                    TransactionStatus::Pending => {}
+                    status => anyhow::bail!("Deposit PSBT was {:?} before being signed", status),
+                },
+                Ok(None) => anyhow::bail!("Deposit PSBT record {} disappeared", transaction_id),
+                Err(e) => tracing::warn!("Failed to read deposit PSBT record: {:#}", e),
+            }
 
-            if let Some(order_info) = order_status.get(order_id) {
-                let status = &order_info.status;
+            tracing::debug!("Waiting for the deposit PSBT to be signed...");
+            self.control.checkpoint()?;
+            if let Some(step_override) = self
+                .control
+                .sleep_or_override(Duration::from_secs(30))
+                .await
+            {
+                return match step_override {
+                    // Signed and broadcast some other way
+                    StepOverride::Complete => {
+                        if let Ok(Some(mut record)) =
+                            db.get_trading_transaction(transaction_id).await
+                        {
+                            record.psbt = None;
+                            let _ = db.update_trading_transaction(transaction_id, &record).await;
+                        }
+                        Ok(txid.to_string())
+                    }
+                    StepOverride::Skip => {
+                        self.settle_overridden_step(step_override, None, None, |t| {
+                            t.txid.as_deref() == Some(txid)
+                        })
+                        .await;
+                        Err(skipped_step("Deposit signature"))
+                    }
+                };
+            }
+        }
+    }
 
-                if status == "closed" {
-                    // Order executed successfully
-                    let vol_exec = order_info
-                        .vol_exec
-                        .parse::<f64>()
-                        .context("Failed to parse executed volume")?;
+    /// Wait for the Bitcoin deposit `txid`, sent at `sent_at`, to confirm on the exchange
+    async fn wait_for_bitcoin_deposit(
+        &self,
+        exchange: &E,
+        txid: &str,
+        amount: Btc,
+        sent_at: DateTime<Utc>,
+    ) -> Result<()> {
+        self.step_boundary().await?;
+        self.control.clear_override();
+        // Poll deposit status until confirmed
+        let timeout = Duration::from_secs(3600); // 1 hour timeout
+        let start = std::time::Instant::now();
+        // Allow for the exchange's clock disagreeing with ours
+        let since = (sent_at.timestamp() - DEPOSIT_TIME_SLACK_SECS).max(0) as u64;
+        let config = self.config.get();
+        let bump_interval = Duration::from_secs(config.rbf_bump_after_mins * 60);
+        // A fee bump replaces the transaction, so the txid to look for can change
+        let mut txid = txid.to_string();
+        let mut last_broadcast = std::time::Instant::now();
 
-                    // Get actual executed price for exchange rate
-                    let price = order_info.price.parse::<f64>().ok();
+        loop {
+            if start.elapsed() > timeout {
+                anyhow::bail!("Timeout waiting for Bitcoin deposit confirmation");
+            }
 
-                    tracing::debug!("Trade executed successfully, received {:.8} XMR", vol_exec);
+            if config.rbf_bump_enabled() && last_broadcast.elapsed() >= bump_interval {
+                // Retried at the same interval when bumping fails
+                last_broadcast = std::time::Instant::now();
+                match self.bump_stuck_deposit(&txid, &config).await {
+                    Ok(Some(replacement)) => txid = replacement,
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Failed to fee-bump deposit {}: {:#}", txid, e),
+                }
+            }
 
-                    // Mark transaction as completed
-                    if let Some(db) = self.get_db() {
-                        if let Ok(transactions) = db.get_recent_trading_transactions(10).await {
-                            if let Some(tx) = transactions.iter().find(|t| {
-                                t.order_id.as_ref() == Some(&order_id.to_string())
-                                    && t.status == TransactionStatus::Pending
-                            }) {
-                                if let Some(id) = &tx.id {
-                                    let _ = db
-                                        .complete_trading_transaction(id, Some(vol_exec), price)
-                                        .await;
-                                }
-                            }
-                        }
-                    }
+            let deposits = exchange.deposits(Asset::Btc).await?;
 
-                    return Ok(vol_exec);
-                } else if status == "canceled" || status == "expired" {
-                    let error_msg = format!("Order was {} ", status);
+            // Other deposits may be in flight, so only ours counts
+            if let Some(deposit) = find_deposit(&deposits, &txid, amount.to_f64(), since) {
+                if deposit.is_credited() {
+                    tracing::debug!(
+                        "Bitcoin deposit {} confirmed on {} (refid {})",
+                        txid,
+                        exchange.name(),
+                        deposit.refid
+                    );
 
-                    // Mark transaction as failed
+                    // Mark transaction as completed
                     if let Some(db) = self.get_db() {
                         if let Ok(transactions) = db.get_recent_trading_transactions(10).await {
                             if let Some(tx) = transactions.iter().find(|t| {
-                                t.order_id.as_ref() == Some(&order_id.to_string())
+                                t.txid.as_ref() == Some(&txid)
                                     && t.status == TransactionStatus::Pending
                             }) {
                                 if let Some(id) = &tx.id {
                                     let _ =
-                                        db.fail_trading_transaction(id, error_msg.clone()).await;
+                                        db.complete_trading_transaction(id, None, None, None).await;
                                 }
                             }
                         }
                     }
 
-                    anyhow::bail!(error_msg);
+                    return Ok(());
                 }
             }
 
-            tracing::debug!("Waiting for order execution...");
-            sleep(Duration::from_secs(10)).await;
+            tracing::debug!("Waiting for Bitcoin deposit confirmation...");
+            self.control.checkpoint()?;
+            if let Some(step_override) = self
+                .control
+                .sleep_or_override(Duration::from_secs(30))
+                .await
+            {
+                self.settle_overridden_step(step_override, None, None, |t| {
+                    t.txid.as_deref() == Some(txid.as_str())
+                })
+                .await;
+                return match step_override {
+                    StepOverride::Complete => Ok(()),
+                    StepOverride::Skip => Err(skipped_step("Bitcoin deposit")),
+                };
+            }
         }
     }
 
-    /// Withdraw Monero from Kraken
-    async fn withdraw_monero_from_kraken(
+    /// Replace the unconfirmed deposit `txid` with a higher-fee one
+    ///
+    /// # Returns
+    /// The replacement's txid, or `None` if the deposit confirmed meanwhile or
+    /// already pays `rbf_max_fee_rate`
+    async fn bump_stuck_deposit(
         &self,
-        kraken: &KrakenClient,
-        amount: f64,
-    ) -> Result<String> {
-        self.set_state(TradingState::WithdrawingMonero { amount });
-
-        // Get our Monero wallet address
-        let xmr_wallet = MoneroWallet::connect_existing(
-            self.monero_wallet_url.clone(),
-            &self.monero_wallet_name,
-            &self.monero_wallet_password,
+        txid: &str,
+        config: &TradingConfig,
+    ) -> Result<Option<String>> {
+        let btc_wallet = BitcoinWallet::connect_existing(
+            self.bitcoin_wallet_url.clone(),
+            &self.bitcoin_wallet_cookie,
+            &self.bitcoin_wallet_name,
         )
         .await
-        .context("Failed to connect to Monero wallet")?;
+        .context("Failed to connect to Bitcoin wallet")?;
 
-        let address = xmr_wallet
-            .get_address()
+        if btc_wallet.get_transaction(txid).await?.confirmations > 0 {
+            return Ok(None);
+        }
+
+        let current = btc_wallet.mempool_fee_rate(txid).await?;
+        let estimate = self.deposit_fee_rate(&btc_wallet).await?;
+        let Some(fee_rate) = bump_fee_rate(current, estimate, config.rbf_max_fee_rate) else {
+            tracing::warn!(
+                "Deposit {} is still unconfirmed at {:.1} sat/vB, already at the {} sat/vB bump limit",
+                txid,
+                current,
+                config.rbf_max_fee_rate
+            );
+            return Ok(None);
+        };
+
+        let bump = btc_wallet.bump_fee(txid, fee_rate).await?;
+        tracing::info!(
+            "Fee-bumped deposit {} from {:.1} to {:.1} sat/vB (fee {} -> {} BTC), replacement {}",
+            txid,
+            current,
+            fee_rate,
+            bump.original_fee,
+            bump.fee,
+            bump.txid
+        );
+
+        if let Some(db) = self.get_db() {
+            self.record_replacement(db, txid, &bump).await;
+        }
+        self.set_state(TradingState::WaitingForBitcoinDeposit {
+            txid: bump.txid.clone(),
+        });
+
+        Ok(Some(bump.txid))
+    }
+
+    /// Feerate (sat/vB) for a deposit to confirm within [`DEPOSIT_CONF_TARGET`] blocks
+    ///
+    /// The shared cache's medium estimate is for the same target.
+    async fn deposit_fee_rate(&self, btc_wallet: &BitcoinWallet) -> Result<Option<f64>> {
+        if let Some(estimator) = &self.fee_estimator {
+            return Ok(Some(estimator.estimates().await?.medium));
+        }
+
+        // BTC/kvB -> sat/vB
+        Ok(btc_wallet
+            .estimate_smart_fee(DEPOSIT_CONF_TARGET)
+            .await?
+            .map(|feerate| feerate * 1e5))
+    }
+
+    /// Point the pending deposit record at the replacement transaction
+    async fn record_replacement(&self, db: &MetricsDatabase, txid: &str, bump: &FeeBump) {
+        let transactions = match db.get_recent_trading_transactions(10).await {
+            Ok(transactions) => transactions,
+            Err(e) => {
+                tracing::warn!("Failed to load transactions to record fee bump: {}", e);
+                return;
+            }
+        };
+        let Some(mut transaction) = transactions
+            .into_iter()
+            .find(|t| t.txid.as_deref() == Some(txid) && t.status == TransactionStatus::Pending)
+        else {
+            return;
+        };
+        let Some(id) = transaction.id.take() else {
+            return;
+        };
+
+        transaction.replaced_txids.push(txid.to_string());
+        transaction.txid = Some(bump.txid.clone());
+        transaction.fee = Some(bump.fee);
+        if let Err(e) = db.update_trading_transaction(&id, &transaction).await {
+            tracing::warn!("Failed to record fee bump of {}: {}", txid, e);
+        }
+    }
+
+    /// Execute BTC->XMR trade on the exchange
+    async fn execute_btc_to_xmr_trade(
+        &self,
+        exchange: &E,
+        btc_amount: Btc,
+        config: &TradingConfig,
+    ) -> Result<String> {
+        self.step_boundary().await?;
+        let volume_decimals = self
+            .volume_decimals
+            .get_or_try_init(|| exchange.volume_decimals())
             .await
-            .context("Failed to get Monero address")?;
+            .with_context(|| format!("Failed to get BTC/XMR lot size from {}", exchange.name()))?;
+        let (btc_amount, remainder) = round_down_to_lot(btc_amount, *volume_decimals)?;
+        if remainder.is_positive() {
+            tracing::info!(
+                "Rounded trade volume down to {} BTC ({} BTC below the lot step stays unspent)",
+                btc_amount,
+                remainder
+            );
+        }
 
-        tracing::debug!("Withdrawing to Monero address: {}", address);
+        self.set_state(TradingState::Trading {
+            btc_amount: btc_amount.to_f64(),
+        });
 
-        // Create transaction record before withdrawing
+        let ticker = exchange.ticker().await?;
+
+        // For limit orders, calculate a price with slippage tolerance
+        let (price, exchange_rate) = if config.use_limit_orders {
+            let current_price = ticker.ask;
+            let price_with_slippage =
+                current_price * (1.0 + config.slippage_tolerance_percent / 100.0);
+            (
+                Some(format!("{:.8}", price_with_slippage)),
+                Some(current_price),
+            )
+        } else {
+            (None, None)
+        };
+
+        // Create transaction record before placing order
         let transaction = StoredTradingTransaction {
             id: None,
             timestamp: Utc::now(),
-            transaction_type: TransactionType::MoneroWithdrawal,
+            transaction_type: TransactionType::Trade,
             status: TransactionStatus::Pending,
-            btc_amount: None,
-            xmr_amount: Some(amount),
-            exchange_rate: None,
+            btc_amount: Some(btc_amount.to_f64()),
+            xmr_amount: None,
+            exchange_rate,
             txid: None,
             order_id: None,
             refid: None,
             from_address: None,
-            to_address: Some(address.clone()),
+            to_address: None,
             fee: None,
-            notes: Some(format!("Withdrawing {:.8} XMR from Kraken", amount)),
+            notes: Some(if remainder.is_positive() {
+                format!(
+                    "Trading {} BTC for XMR ({} BTC rounded off to the lot step)",
+                    btc_amount, remainder
+                )
+            } else {
+                format!("Trading {} BTC for XMR", btc_amount)
+            }),
             error_message: None,
             completed_at: None,
+            balance_before: None,
+            balance_after: None,
+            replaced_txids: Vec::new(),
+            psbt: None,
+            mid_price: Some(ticker.mid()),
         };
 
         let transaction_id = if let Some(db) = self.get_db() {
             match db.store_trading_transaction(&transaction).await {
                 Ok(id) => {
-                    tracing::debug!("Created withdrawal transaction record: {}", id);
+                    tracing::debug!("Created trade transaction record: {}", id);
                     Some(id)
                 }
                 Err(e) => {
-                    tracing::warn!("Failed to store withdrawal transaction record: {}", e);
+                    tracing::warn!("Failed to store trade transaction record: {}", e);
                     None
                 }
             }
@@ -768,56 +1878,62 @@ impl TradingEngine {
             None
         };
 
-        // Initiate withdrawal from Kraken
-        // Note: First parameter is the withdrawal key name configured in Kraken, not the address
-        // For now, we'll use a default key name - this should be configurable
-        let withdraw_result = match kraken
-            .withdraw_xmr("monero_primary", &format!("{:.12}", amount))
-            .await
-        {
-            Ok(result) => result,
+        let order = OrderRequest {
+            side: OrderSide::BuyXmr,
+            volume: btc_amount.to_string(),
+            limit_price: price.clone(),
+        };
+        let order_id = match exchange.submit_order(&order).await {
+            Ok(order_id) => order_id,
             Err(e) => {
                 // Mark transaction as failed
                 if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
                     let _ = db.fail_trading_transaction(id, e.to_string()).await;
                 }
-                return Err(e).context("Failed to initiate Monero withdrawal from Kraken");
+                return Err(e)
+                    .with_context(|| format!("Failed to place order on {}", exchange.name()));
             }
         };
 
-        let refid = withdraw_result.refid;
-        tracing::debug!("Monero withdrawal initiated: {}", refid);
+        tracing::debug!("Order placed on {}: {}", exchange.name(), order_id);
 
-        // Update transaction with refid
+        // Update transaction with order_id
         if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
             let mut updated_transaction = transaction.clone();
-            updated_transaction.refid = Some(refid.clone());
+            updated_transaction.order_id = Some(order_id.clone());
             let _ = db
                 .update_trading_transaction(id, &updated_transaction)
                 .await;
         }
 
-        self.set_state(TradingState::WaitingForMoneroWithdrawal {
-            refid: refid.clone(),
+        self.set_state(TradingState::WaitingForTradeExecution {
+            order_id: order_id.clone(),
         });
 
-        Ok(refid)
+        Ok(order_id)
     }
 
-    /// Wait for Monero withdrawal to complete
-    async fn wait_for_monero_withdrawal(&self, kraken: &KrakenClient, refid: &str) -> Result<()> {
-        let timeout = Duration::from_secs(3600); // 1 hour timeout
+    /// Wait for trade to execute
+    async fn wait_for_trade_execution(
+        &self,
+        exchange: &E,
+        order_id: &str,
+        config: &TradingConfig,
+    ) -> Result<TradeFill> {
+        self.step_boundary().await?;
+        self.control.clear_override();
+        let timeout = Duration::from_secs(config.order_timeout_secs);
         let start = std::time::Instant::now();
 
         loop {
             if start.elapsed() > timeout {
-                let error_msg = "Timeout waiting for Monero withdrawal".to_string();
+                let error_msg = "Timeout waiting for order execution".to_string();
 
                 // Mark transaction as failed
                 if let Some(db) = self.get_db() {
                     if let Ok(transactions) = db.get_recent_trading_transactions(10).await {
                         if let Some(tx) = transactions.iter().find(|t| {
-                            t.refid.as_ref() == Some(&refid.to_string())
+                            t.order_id.as_ref() == Some(&order_id.to_string())
                                 && t.status == TransactionStatus::Pending
                         }) {
                             if let Some(id) = &tx.id {
@@ -830,37 +1946,46 @@ impl TradingEngine {
                 anyhow::bail!(error_msg);
             }
 
-            let withdrawals = kraken.get_withdrawal_status(Some("XMR")).await?;
+            if let Some(order_info) = exchange.order_status(order_id).await? {
+                let state = order_info.state;
 
-            // Find our withdrawal
-            if let Some(withdrawal) = withdrawals.iter().find(|w| w.refid == refid) {
-                if withdrawal.status == "Success" {
-                    tracing::debug!("Monero withdrawal completed successfully");
+                if state == OrderState::Closed {
+                    // Order executed successfully
+                    let (fill, price) = order_fill(&order_info)?;
+                    let vol_exec = fill.xmr;
+
+                    tracing::debug!("Trade executed successfully, received {} XMR", vol_exec);
 
                     // Mark transaction as completed
                     if let Some(db) = self.get_db() {
                         if let Ok(transactions) = db.get_recent_trading_transactions(10).await {
                             if let Some(tx) = transactions.iter().find(|t| {
-                                t.refid.as_ref() == Some(&refid.to_string())
+                                t.order_id.as_ref() == Some(&order_id.to_string())
                                     && t.status == TransactionStatus::Pending
                             }) {
                                 if let Some(id) = &tx.id {
-                                    let _ = db.complete_trading_transaction(id, None, None).await;
+                                    let _ = db
+                                        .complete_trading_transaction(
+                                            id,
+                                            Some(vol_exec.to_f64()),
+                                            price,
+                                            fill.fee,
+                                        )
+                                        .await;
                                 }
                             }
                         }
                     }
 
-                    return Ok(());
-                } else if withdrawal.status == "Failure" || withdrawal.status == "Canceled" {
-                    let error_msg =
-                        format!("Monero withdrawal {}", withdrawal.status.to_lowercase());
+                    return Ok(fill);
+                } else if matches!(state, OrderState::Canceled | OrderState::Expired) {
+                    let error_msg = format!("Order was {:?}", state).to_lowercase();
 
                     // Mark transaction as failed
                     if let Some(db) = self.get_db() {
                         if let Ok(transactions) = db.get_recent_trading_transactions(10).await {
                             if let Some(tx) = transactions.iter().find(|t| {
-                                t.refid.as_ref() == Some(&refid.to_string())
+                                t.order_id.as_ref() == Some(&order_id.to_string())
                                     && t.status == TransactionStatus::Pending
                             }) {
                                 if let Some(id) = &tx.id {
@@ -875,530 +2000,2628 @@ impl TradingEngine {
                 }
             }
 
-            tracing::debug!("Waiting for Monero withdrawal completion...");
-            sleep(Duration::from_secs(30)).await;
+            tracing::debug!("Waiting for order execution...");
+            let by_order = |t: &StoredTradingTransaction| t.order_id.as_deref() == Some(order_id);
+            self.control.checkpoint()?;
+            match self
+                .control
+                .sleep_or_override(Duration::from_secs(10))
+                .await
+            {
+                Some(StepOverride::Complete) => {
+                    // Carry on with whatever has filled so far
+                    let filled = exchange
+                        .order_status(order_id)
+                        .await
+                        .and_then(|order| {
+                            let order = order.with_context(|| {
+                                format!("{} did not return the order", exchange.name())
+                            })?;
+                            order_fill(&order)
+                        })
+                        .and_then(|(fill, price)| {
+                            if !fill.xmr.is_positive() {
+                                anyhow::bail!("nothing has filled yet");
+                            }
+                            Ok((fill, price))
+                        });
+                    match filled {
+                        Ok((fill, _)) => {
+                            self.settle_overridden_step(
+                                StepOverride::Complete,
+                                Some(fill.xmr),
+                                None,
+                                by_order,
+                            )
+                            .await;
+                            tracing::warn!(
+                                "Order {} marked complete with {} XMR filled; any remainder stays open",
+                                order_id,
+                                fill.xmr
+                            );
+                            return Ok(fill);
+                        }
+                        Err(e) => tracing::warn!(
+                            "Cannot mark order {} complete, still waiting: {:#}",
+                            order_id,
+                            e
+                        ),
+                    }
+                }
+                Some(StepOverride::Skip) => {
+                    // Cancel it first, so it can't fill later with nothing tracking it
+                    let outcome = match exchange.cancel_order(order_id).await {
+                        Ok(true) => format!("order cancelled on {}", exchange.name()),
+                        Ok(false) => format!("order no longer open on {}", exchange.name()),
+                        Err(e) => {
+                            tracing::error!(
+                                "Failed to cancel skipped order {} on {}, cancel it by hand: {:#}",
+                                order_id,
+                                exchange.name(),
+                                e
+                            );
+                            format!(
+                                "order could not be cancelled on {}: {:#}",
+                                exchange.name(),
+                                e
+                            )
+                        }
+                    };
+                    self.settle_overridden_step(StepOverride::Skip, None, Some(&outcome), by_order)
+                        .await;
+                    tracing::warn!("Order {} skipped; {}", order_id, outcome);
+                    return Err(skipped_step("Trade"));
+                }
+                None => {}
+            }
         }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::trading::config::{SharedTradingConfig, TradingConfig};
 
-    fn create_test_engine() -> TradingEngine {
-        let config = TradingConfig::default();
-        let shared_config = SharedTradingConfig::new(config);
+    /// Withdraw Monero from the exchange
+    async fn withdraw_monero_from_exchange(&self, exchange: &E, amount: Xmr) -> Result<String> {
+        self.step_boundary().await?;
+        self.set_state(TradingState::WithdrawingMonero {
+            amount: amount.to_f64(),
+        });
 
-        TradingEngine::new(
-            shared_config,
-            "test_key".to_string(),
-            "test_secret".to_string(),
-            "http://localhost:8332".to_string(),
-            "/tmp/cookie".to_string(),
-            "test_wallet".to_string(),
-            "http://localhost:18082/json_rpc".to_string(),
-            "test_xmr_wallet".to_string(),
-            "".to_string(),
+        // Get our Monero wallet address
+        let xmr_wallet = MoneroWallet::connect_existing(
+            self.monero_wallet_url.clone(),
+            &self.monero_wallet_name,
+            &self.monero_wallet_password,
         )
-    }
-
-    #[allow(dead_code)]
-    fn create_test_engine_with_config(config: TradingConfig) -> TradingEngine {
-        let shared_config = SharedTradingConfig::new(config);
+        .await
+        .context("Failed to connect to Monero wallet")?;
 
-        TradingEngine::new(
-            shared_config,
-            "test_key".to_string(),
-            "test_secret".to_string(),
-            "http://localhost:8332".to_string(),
-            "/tmp/cookie".to_string(),
-            "test_wallet".to_string(),
-            "http://localhost:18082/json_rpc".to_string(),
-            "test_xmr_wallet".to_string(),
-            "".to_string(),
-        )
-    }
+        let address = xmr_wallet
+            .get_address()
+            .await
+            .context("Failed to get Monero address")?;
 
-    #[test]
-    fn test_engine_creation() {
-        let engine = create_test_engine();
-        assert!(!engine.is_enabled());
-        assert_eq!(engine.get_state(), TradingState::Disabled);
-    }
+        tracing::debug!("Withdrawing to Monero address: {}", address);
 
-    #[test]
-    fn test_engine_enable_disable() {
-        let engine = create_test_engine();
+        // Create transaction record before withdrawing
+        let transaction = StoredTradingTransaction {
+            id: None,
+            timestamp: Utc::now(),
+            transaction_type: TransactionType::MoneroWithdrawal,
+            status: TransactionStatus::Pending,
+            btc_amount: None,
+            xmr_amount: Some(amount.to_f64()),
+            exchange_rate: None,
+            txid: None,
+            order_id: None,
+            refid: None,
+            from_address: None,
+            to_address: Some(address.clone()),
+            fee: None,
+            notes: Some(format!(
+                "Withdrawing {} XMR from {}",
+                amount,
+                exchange.name()
+            )),
+            error_message: None,
+            completed_at: None,
+            balance_before: None,
+            balance_after: None,
+            replaced_txids: Vec::new(),
+            psbt: None,
+            mid_price: None,
+        };
 
+        let transaction_id = if let Some(db) = self.get_db() {
+            match db.store_trading_transaction(&transaction).await {
+                Ok(id) => {
+                    tracing::debug!("Created withdrawal transaction record: {}", id);
+                    Some(id)
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to store withdrawal transaction record: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let refid = match exchange
+            .withdraw_to(Asset::Xmr, &amount.to_string(), &address)
+            .await
+        {
+            Ok(refid) => refid,
+            Err(e) => {
+                // Mark transaction as failed
+                if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
+                    let _ = db.fail_trading_transaction(id, e.to_string()).await;
+                }
+                return Err(e).with_context(|| {
+                    format!(
+                        "Failed to initiate Monero withdrawal from {}",
+                        exchange.name()
+                    )
+                });
+            }
+        };
+        tracing::debug!("Monero withdrawal initiated: {}", refid);
+
+        // Update transaction with refid
+        if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
+            let mut updated_transaction = transaction.clone();
+            updated_transaction.refid = Some(refid.clone());
+            let _ = db
+                .update_trading_transaction(id, &updated_transaction)
+                .await;
+        }
+
+        self.set_state(TradingState::WaitingForMoneroWithdrawal {
+            refid: refid.clone(),
+        });
+
+        Ok(refid)
+    }
+
+    /// Deposit Monero to the exchange, sweeping the whole unlocked balance when `sweep` is set
+    async fn deposit_monero_to_exchange(
+        &self,
+        exchange: &E,
+        amount: Xmr,
+        sweep: bool,
+    ) -> Result<String> {
+        self.step_boundary().await?;
+        self.set_state(TradingState::DepositingMonero {
+            amount: amount.to_f64(),
+        });
+
+        let deposit_address = exchange
+            .deposit_address(Asset::Xmr, false)
+            .await
+            .with_context(|| format!("Failed to get {} XMR deposit address", exchange.name()))?;
+
+        tracing::debug!(
+            "{} XMR deposit address: {}",
+            exchange.name(),
+            deposit_address
+        );
+
+        // A sweep sends about `amount`, the unlocked balance it was sized from
+        let reservation = self
+            .spend_limits
+            .reserve(TransferAsset::Xmr, amount.to_f64())
+            .await?;
+
+        let transaction = StoredTradingTransaction {
+            id: None,
+            timestamp: Utc::now(),
+            transaction_type: TransactionType::MoneroDeposit,
+            status: TransactionStatus::Pending,
+            btc_amount: None,
+            xmr_amount: Some(amount.to_f64()),
+            exchange_rate: None,
+            txid: None,
+            order_id: None,
+            refid: None,
+            from_address: None,
+            to_address: Some(deposit_address.clone()),
+            fee: None,
+            notes: Some(format!("Depositing {} XMR to {}", amount, exchange.name())),
+            error_message: None,
+            completed_at: None,
+            balance_before: None,
+            balance_after: None,
+            replaced_txids: Vec::new(),
+            psbt: None,
+            mid_price: None,
+        };
+
+        let transaction_id = match self.get_db() {
+            Some(db) => match db.store_trading_transaction(&transaction).await {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    tracing::warn!("Failed to store Monero deposit record: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let xmr_wallet = MoneroWallet::connect_existing(
+            self.monero_wallet_url.clone(),
+            &self.monero_wallet_name,
+            &self.monero_wallet_password,
+        )
+        .await
+        .context("Failed to connect to Monero wallet")?;
+
+        let sent = if sweep {
+            xmr_wallet
+                .sweep_all(&deposit_address, TransferPriority::Default)
+                .await
+        } else {
+            xmr_wallet
+                .transfer(&deposit_address, amount.to_f64(), TransferPriority::Default)
+                .await
+        };
+
+        let (txid, fee) = match sent {
+            Ok(sent) => sent,
+            Err(e) => {
+                if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
+                    let _ = db.fail_trading_transaction(id, e.to_string()).await;
+                }
+                return Err(e)
+                    .with_context(|| format!("Failed to send Monero to {}", exchange.name()));
+            }
+        };
+        reservation
+            .commit(amount.to_f64(), ENGINE_REQUESTER, Some(txid.clone()))
+            .await;
+
+        if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
+            let mut updated_transaction = transaction.clone();
+            updated_transaction.txid = Some(txid.clone());
+            updated_transaction.fee = Some(fee);
+            let _ = db
+                .update_trading_transaction(id, &updated_transaction)
+                .await;
+        }
+
+        self.set_state(TradingState::WaitingForMoneroDeposit { txid: txid.clone() });
+
+        Ok(txid)
+    }
+
+    /// Wait for a Monero deposit to be credited on the exchange, returning the credited amount
+    async fn wait_for_monero_deposit(&self, exchange: &E, txid: &str) -> Result<Xmr> {
+        self.step_boundary().await?;
+        self.control.clear_override();
+        // Exchanges wait for a long confirmation depth on XMR deposits
+        let timeout = Duration::from_secs(7200);
+        let start = std::time::Instant::now();
+
+        loop {
+            if start.elapsed() > timeout {
+                anyhow::bail!("Timeout waiting for Monero deposit confirmation");
+            }
+
+            let deposits = exchange.deposits(Asset::Xmr).await?;
+
+            if let Some(deposit) = deposits.iter().find(|d| d.txid == txid && d.is_credited()) {
+                let credited: Xmr = deposit
+                    .amount
+                    .parse()
+                    .map_err(|e: String| anyhow::anyhow!(e))
+                    .context("Failed to parse deposited XMR amount")?;
+
+                tracing::debug!(
+                    "Monero deposit of {} XMR confirmed on {}",
+                    credited,
+                    exchange.name()
+                );
+
+                if let Some(db) = self.get_db() {
+                    if let Ok(transactions) = db.get_recent_trading_transactions(10).await {
+                        if let Some(id) = transactions
+                            .iter()
+                            .find(|t| {
+                                t.txid.as_deref() == Some(txid)
+                                    && t.status == TransactionStatus::Pending
+                            })
+                            .and_then(|t| t.id.as_ref())
+                        {
+                            let _ = db
+                                .complete_trading_transaction(
+                                    id,
+                                    Some(credited.to_f64()),
+                                    None,
+                                    None,
+                                )
+                                .await;
+                        }
+                    }
+                }
+
+                return Ok(credited);
+            }
+
+            tracing::debug!("Waiting for Monero deposit confirmation...");
+            self.control.checkpoint()?;
+            if let Some(step_override) = self
+                .control
+                .sleep_or_override(Duration::from_secs(30))
+                .await
+            {
+                let pending = self
+                    .settle_overridden_step(step_override, None, None, |t| {
+                        t.txid.as_deref() == Some(txid)
+                    })
+                    .await;
+                if step_override == StepOverride::Skip {
+                    return Err(skipped_step("Monero deposit"));
+                }
+                // Trust the amount we sent, since the exchange has not reported a credit
+                return pending
+                    .and_then(|t| t.xmr_amount)
+                    .context("No recorded amount for the Monero deposit being completed")
+                    .and_then(xmr_amount);
+            }
+        }
+    }
+
+    /// Execute XMR->BTC trade on the exchange
+    async fn execute_xmr_to_btc_trade(
+        &self,
+        exchange: &E,
+        xmr_amount: Xmr,
+        config: &TradingConfig,
+    ) -> Result<String> {
+        self.step_boundary().await?;
+        let ticker = exchange.ticker().await?;
+        let current_price = ticker.bid;
+
+        self.set_state(TradingState::SellingMonero {
+            xmr_amount: xmr_amount.to_f64(),
+        });
+
+        let price = config.use_limit_orders.then(|| {
+            let price_with_slippage =
+                current_price * (1.0 - config.slippage_tolerance_percent / 100.0);
+            format!("{:.8}", price_with_slippage)
+        });
+
+        let transaction = StoredTradingTransaction {
+            id: None,
+            timestamp: Utc::now(),
+            transaction_type: TransactionType::Trade,
+            status: TransactionStatus::Pending,
+            btc_amount: None,
+            xmr_amount: Some(xmr_amount.to_f64()),
+            exchange_rate: Some(current_price),
+            txid: None,
+            order_id: None,
+            refid: None,
+            from_address: None,
+            to_address: None,
+            fee: None,
+            notes: Some(format!("Selling {} XMR for BTC", xmr_amount)),
+            error_message: None,
+            completed_at: None,
+            balance_before: None,
+            balance_after: None,
+            replaced_txids: Vec::new(),
+            psbt: None,
+            mid_price: Some(ticker.mid()),
+        };
+
+        let transaction_id = match self.get_db() {
+            Some(db) => match db.store_trading_transaction(&transaction).await {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    tracing::warn!("Failed to store trade transaction record: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let order = OrderRequest {
+            side: OrderSide::SellXmr,
+            volume: xmr_amount.to_string(),
+            limit_price: price,
+        };
+        let order_id = match exchange.submit_order(&order).await {
+            Ok(order_id) => order_id,
+            Err(e) => {
+                if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
+                    let _ = db.fail_trading_transaction(id, e.to_string()).await;
+                }
+                return Err(e)
+                    .with_context(|| format!("Failed to place sell order on {}", exchange.name()));
+            }
+        };
+
+        if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
+            let mut updated_transaction = transaction.clone();
+            updated_transaction.order_id = Some(order_id.clone());
+            let _ = db
+                .update_trading_transaction(id, &updated_transaction)
+                .await;
+        }
+
+        self.set_state(TradingState::WaitingForTradeExecution {
+            order_id: order_id.clone(),
+        });
+
+        Ok(order_id)
+    }
+
+    /// Withdraw Bitcoin from the exchange to our wallet
+    async fn withdraw_bitcoin_from_exchange(&self, exchange: &E, amount: Btc) -> Result<String> {
+        self.step_boundary().await?;
+        self.set_state(TradingState::WithdrawingBitcoin {
+            amount: amount.to_f64(),
+        });
+
+        let btc_wallet = BitcoinWallet::connect_existing(
+            self.bitcoin_wallet_url.clone(),
+            &self.bitcoin_wallet_cookie,
+            &self.bitcoin_wallet_name,
+        )
+        .await
+        .context("Failed to connect to Bitcoin wallet")?;
+
+        let address = btc_wallet
+            .get_new_address(None)
+            .await
+            .context("Failed to get Bitcoin address")?;
+
+        let transaction = StoredTradingTransaction {
+            id: None,
+            timestamp: Utc::now(),
+            transaction_type: TransactionType::BitcoinWithdrawal,
+            status: TransactionStatus::Pending,
+            btc_amount: Some(amount.to_f64()),
+            xmr_amount: None,
+            exchange_rate: None,
+            txid: None,
+            order_id: None,
+            refid: None,
+            from_address: None,
+            to_address: Some(address.clone()),
+            fee: None,
+            notes: Some(format!(
+                "Withdrawing {} BTC from {}",
+                amount,
+                exchange.name()
+            )),
+            error_message: None,
+            completed_at: None,
+            balance_before: None,
+            balance_after: None,
+            replaced_txids: Vec::new(),
+            psbt: None,
+            mid_price: None,
+        };
+
+        let transaction_id = match self.get_db() {
+            Some(db) => match db.store_trading_transaction(&transaction).await {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    tracing::warn!("Failed to store withdrawal transaction record: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let refid = match exchange
+            .withdraw_to(Asset::Btc, &amount.to_string(), &address)
+            .await
+        {
+            Ok(refid) => refid,
+            Err(e) => {
+                if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
+                    let _ = db.fail_trading_transaction(id, e.to_string()).await;
+                }
+                return Err(e).with_context(|| {
+                    format!(
+                        "Failed to initiate Bitcoin withdrawal from {}",
+                        exchange.name()
+                    )
+                });
+            }
+        };
+
+        if let (Some(db), Some(id)) = (self.get_db(), transaction_id.as_ref()) {
+            let mut updated_transaction = transaction.clone();
+            updated_transaction.refid = Some(refid.clone());
+            let _ = db
+                .update_trading_transaction(id, &updated_transaction)
+                .await;
+        }
+
+        self.set_state(TradingState::WaitingForBitcoinWithdrawal {
+            refid: refid.clone(),
+        });
+
+        Ok(refid)
+    }
+
+    /// Record an operator override on the pending transaction for a step
+    ///
+    /// `detail` is added to the record of a skip, e.g. what became of the
+    /// step's exchange order. Returns the transaction as it was before the
+    /// override, if one was found.
+    async fn settle_overridden_step(
+        &self,
+        step_override: StepOverride,
+        xmr_amount: Option<Xmr>,
+        detail: Option<&str>,
+        is_step: impl Fn(&StoredTradingTransaction) -> bool,
+    ) -> Option<StoredTradingTransaction> {
+        let db = self.get_db()?;
+        let pending = db
+            .get_recent_trading_transactions(10)
+            .await
+            .ok()?
+            .into_iter()
+            .find(|t| t.status == TransactionStatus::Pending && is_step(t))?;
+        let id = pending.id.clone()?;
+
+        let mut settled = pending.clone();
+        match step_override {
+            StepOverride::Complete => {
+                settled.status = TransactionStatus::Completed;
+                if let Some(amount) = xmr_amount {
+                    settled.xmr_amount = Some(amount.to_f64());
+                }
+                settled.notes = Some(match &pending.notes {
+                    Some(notes) => format!("{} (marked complete by operator)", notes),
+                    None => "Marked complete by operator".to_string(),
+                });
+            }
+            StepOverride::Skip => {
+                settled.status = TransactionStatus::Cancelled;
+                settled.error_message = Some(match detail {
+                    Some(detail) => format!("Skipped by operator; {}", detail),
+                    None => "Skipped by operator".to_string(),
+                });
+            }
+        }
+        settled.completed_at = Some(Utc::now());
+
+        if let Err(e) = db.update_trading_transaction(&id, &settled).await {
+            tracing::warn!(
+                "Failed to record {:?} override on {}: {:#}",
+                step_override,
+                id,
+                e
+            );
+        }
+        Some(pending)
+    }
+
+    /// Wait for an exchange withdrawal of `asset` to complete
+    async fn wait_for_withdrawal(&self, exchange: &E, asset: Asset, refid: &str) -> Result<()> {
+        self.step_boundary().await?;
+        self.control.clear_override();
+        let timeout = Duration::from_secs(3600); // 1 hour timeout
+        let start = std::time::Instant::now();
+
+        loop {
+            if start.elapsed() > timeout {
+                let error_msg = format!("Timeout waiting for {} withdrawal", asset);
+
+                // Mark transaction as failed
+                if let Some(db) = self.get_db() {
+                    if let Ok(transactions) = db.get_recent_trading_transactions(10).await {
+                        if let Some(tx) = transactions.iter().find(|t| {
+                            t.refid.as_ref() == Some(&refid.to_string())
+                                && t.status == TransactionStatus::Pending
+                        }) {
+                            if let Some(id) = &tx.id {
+                                let _ = db.fail_trading_transaction(id, error_msg.clone()).await;
+                            }
+                        }
+                    }
+                }
+
+                anyhow::bail!(error_msg);
+            }
+
+            let withdrawals = exchange.withdrawals(asset).await?;
+
+            // Find our withdrawal
+            if let Some(withdrawal) = withdrawals.iter().find(|w| w.refid == refid) {
+                if withdrawal.state == TransferState::Succeeded {
+                    tracing::debug!("{} withdrawal completed successfully", asset);
+
+                    // Mark transaction as completed
+                    if let Some(db) = self.get_db() {
+                        if let Ok(transactions) = db.get_recent_trading_transactions(10).await {
+                            if let Some(tx) = transactions.iter().find(|t| {
+                                t.refid.as_ref() == Some(&refid.to_string())
+                                    && t.status == TransactionStatus::Pending
+                            }) {
+                                if let Some(id) = &tx.id {
+                                    let fee = fill_field(&withdrawal.fee, "withdrawal fee");
+                                    let _ =
+                                        db.complete_trading_transaction(id, None, None, fee).await;
+                                }
+                            }
+                        }
+                    }
+
+                    return Ok(());
+                } else if matches!(
+                    withdrawal.state,
+                    TransferState::Failed | TransferState::Canceled
+                ) {
+                    let error_msg = format!("{} withdrawal {}", asset, withdrawal.state);
+
+                    // Mark transaction as failed
+                    if let Some(db) = self.get_db() {
+                        if let Ok(transactions) = db.get_recent_trading_transactions(10).await {
+                            if let Some(tx) = transactions.iter().find(|t| {
+                                t.refid.as_ref() == Some(&refid.to_string())
+                                    && t.status == TransactionStatus::Pending
+                            }) {
+                                if let Some(id) = &tx.id {
+                                    let _ =
+                                        db.fail_trading_transaction(id, error_msg.clone()).await;
+                                }
+                            }
+                        }
+                    }
+
+                    anyhow::bail!(error_msg);
+                }
+            }
+
+            tracing::debug!("Waiting for {} withdrawal completion...", asset);
+            self.control.checkpoint()?;
+            if let Some(step_override) = self
+                .control
+                .sleep_or_override(Duration::from_secs(30))
+                .await
+            {
+                self.settle_overridden_step(step_override, None, None, |t| {
+                    t.refid.as_deref() == Some(refid)
+                })
+                .await;
+                return match step_override {
+                    StepOverride::Complete => Ok(()),
+                    StepOverride::Skip => Err(skipped_step(&format!("{} withdrawal", asset))),
+                };
+            }
+        }
+    }
+}
+
+/// Smallest feerate increase (sat/vB) nodes relay a replacement for
+const RBF_MIN_INCREMENT_SAT_VB: f64 = 1.0;
+
+/// How far before our send time an exchange deposit may be timestamped and still match
+const DEPOSIT_TIME_SLACK_SECS: i64 = 600;
+
+/// Result of a closed order
+struct TradeFill {
+    /// Executed volume in XMR
+    xmr: Xmr,
+    /// Order cost in BTC, when the exchange reported one
+    btc: Option<Btc>,
+    /// Exchange fee in BTC, when the exchange reported one
+    fee: Option<f64>,
+}
+
+/// Clears the emergency flag when the conversion finishes or is abandoned
+struct EmergencyGuard(Arc<AtomicBool>);
+
+impl Drop for EmergencyGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Executed volume, cost and average price of an order
+///
+/// The volume is required; price and cost only add detail, so a bad or
+/// missing field is dropped.
+fn order_fill(order: &OrderReport) -> Result<(TradeFill, Option<f64>)> {
+    let xmr: Xmr = order
+        .executed
+        .parse()
+        .map_err(|e: String| anyhow::anyhow!(e))
+        .context("Failed to parse executed volume")?;
+    let price = fill_field(&order.price, "price").filter(|p| *p > 0.0);
+    let btc = fill_field(&order.cost, "cost").and_then(Btc::from_f64);
+    let fee = fill_field(&order.fee, "fee");
+    Ok((TradeFill { xmr, btc, fee }, price))
+}
+
+/// Error ending a rebalance whose step an operator skipped
+fn skipped_step(step: &str) -> anyhow::Error {
+    anyhow::anyhow!("{} skipped by operator; rebalance abandoned", step)
+}
+
+/// Convert a BTC amount from the JSON/RPC edge into exact units
+fn btc_amount(value: f64) -> Result<Btc> {
+    Btc::from_f64(value).with_context(|| format!("Invalid BTC amount: {}", value))
+}
+
+/// Convert an XMR amount from the JSON/RPC edge into exact units
+fn xmr_amount(value: f64) -> Result<Xmr> {
+    Xmr::from_f64(value).with_context(|| format!("Invalid XMR amount: {}", value))
+}
+
+/// Current BTC/XMR price from the exchange's last trade
+async fn fetch_btc_per_xmr(exchange: &impl Exchange) -> Result<f64> {
+    let ticker = exchange
+        .ticker()
+        .await
+        .with_context(|| format!("Failed to get BTC/XMR ticker from {}", exchange.name()))?;
+
+    Ok(ticker.last)
+}
+
+/// Round an order volume down to the exchange's lot step of `10^-lot_decimals`
+///
+/// Returns the volume to submit and the remainder left over. Never rounds up,
+/// so the order can't exceed the funds it was sized from.
+fn round_down_to_lot(amount: Btc, lot_decimals: u32) -> Result<(Btc, Btc)> {
+    let volume = amount.floor_to_decimals(lot_decimals);
+    if !volume.is_positive() {
+        anyhow::bail!(
+            "Trade volume {} BTC is below the {}-decimal lot step",
+            amount,
+            lot_decimals
+        );
+    }
+    let remainder = amount.checked_sub(volume).context("BTC amount overflow")?;
+
+    Ok((volume, remainder))
+}
+
+/// Deposit addresses requested from the exchange before a deposit gives up
+const MAX_DEPOSIT_ADDRESS_ATTEMPTS: usize = 3;
+
+/// Fetch a deposit address, asking for a fresh one while validation rejects it
+///
+/// The first attempt reuses the exchange's current address (`fetch(false)`), later
+/// ones request a new one. Returns the accepted address and the rejected ones.
+async fn valid_deposit_address<F, FFut, V, VFut>(
+    mut fetch: F,
+    mut is_valid: V,
+) -> Result<(String, Vec<String>)>
+where
+    F: FnMut(bool) -> FFut,
+    FFut: Future<Output = Result<String>>,
+    V: FnMut(String) -> VFut,
+    VFut: Future<Output = Result<bool>>,
+{
+    let mut rejected = Vec::new();
+
+    for attempt in 0..MAX_DEPOSIT_ADDRESS_ATTEMPTS {
+        let address = fetch(attempt > 0).await?;
+        if is_valid(address.clone())
+            .await
+            .context("Failed to validate deposit address")?
+        {
+            return Ok((address, rejected));
+        }
+
+        tracing::warn!(
+            "Exchange deposit address {} failed validation (attempt {}/{})",
+            address,
+            attempt + 1,
+            MAX_DEPOSIT_ADDRESS_ATTEMPTS
+        );
+        rejected.push(address);
+    }
+
+    anyhow::bail!(
+        "No valid deposit address after {} attempts (rejected: {})",
+        MAX_DEPOSIT_ADDRESS_ATTEMPTS,
+        rejected.join(", ")
+    )
+}
+
+/// Numeric field of a filled order, logging rather than failing on bad input
+fn fill_field(value: &str, name: &str) -> Option<f64> {
+    parse_kraken_f64(Some(value)).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring order {}: {:#}", name, e);
+        None
+    })
+}
+
+/// Decide whether and how much to rebalance for the given balances and price
+///
+/// Pure function of its inputs, shared by the trading loop and
+/// `POST /trading/preview`.
+pub fn decide_rebalance(
+    btc_balance: Btc,
+    xmr_balance: Xmr,
+    btc_per_xmr: f64,
+    config: &TradingConfig,
+) -> RebalanceDecision {
+    let threshold = match xmr_amount(config.monero_min_threshold) {
+        Ok(threshold) => threshold,
+        Err(e) => {
+            return RebalanceDecision::Blocked {
+                xmr_needed: Xmr::ZERO,
+                reason: e.to_string(),
+            }
+        }
+    };
+    if xmr_balance >= threshold {
+        return decide_reverse_rebalance(btc_balance, xmr_balance, btc_per_xmr, config);
+    }
+
+    let xmr_needed = match xmr_amount(config.monero_target_balance).and_then(|target| {
+        target
+            .checked_sub(xmr_balance)
+            .context("XMR amount overflow")
+    }) {
+        Ok(xmr_needed) => xmr_needed,
+        Err(e) => {
+            return RebalanceDecision::Blocked {
+                xmr_needed: Xmr::ZERO,
+                reason: e.to_string(),
+            }
+        }
+    };
+
+    match size_rebalance(xmr_needed, btc_per_xmr, btc_balance, config) {
+        Ok(Some(btc_to_spend)) => RebalanceDecision::Rebalance {
+            xmr_needed,
+            btc_to_spend,
+        },
+        Ok(None) => RebalanceDecision::Hold,
+        Err(e) => RebalanceDecision::Blocked {
+            xmr_needed,
+            reason: e.to_string(),
+        },
+    }
+}
+
+/// Reverse half of [`decide_rebalance`], once XMR needs no topping up
+fn decide_reverse_rebalance(
+    btc_balance: Btc,
+    xmr_balance: Xmr,
+    btc_per_xmr: f64,
+    config: &TradingConfig,
+) -> RebalanceDecision {
+    let sized = reverse_shortfall(btc_balance, xmr_balance, config).and_then(|shortfall| {
+        let Some(btc_needed) = shortfall else {
+            return Ok(None);
+        };
+        let xmr_to_sell = size_reverse_rebalance(btc_needed, btc_per_xmr, xmr_balance, config)?;
+        Ok(xmr_to_sell.map(|xmr_to_sell| (btc_needed, xmr_to_sell)))
+    });
+
+    match sized {
+        Ok(None) => RebalanceDecision::Hold,
+        Ok(Some((btc_needed, xmr_to_sell))) => RebalanceDecision::SellXmr {
+            btc_needed,
+            xmr_to_sell,
+        },
+        Err(e) => RebalanceDecision::Blocked {
+            xmr_needed: Xmr::ZERO,
+            reason: e.to_string(),
+        },
+    }
+}
+
+/// BTC needed to reach `bitcoin_target_balance`, if a reverse rebalance is due
+///
+/// Due when it is enabled, BTC is below `bitcoin_min_threshold` and XMR is
+/// above `monero_max_threshold`.
+fn reverse_shortfall(
+    btc_balance: Btc,
+    xmr_balance: Xmr,
+    config: &TradingConfig,
+) -> Result<Option<Btc>> {
+    if !config.reverse_rebalance_enabled()
+        || btc_balance >= btc_amount(config.bitcoin_min_threshold)?
+        || xmr_balance <= xmr_amount(config.monero_max_threshold)?
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(
+        btc_amount(config.bitcoin_target_balance)?
+            .checked_sub(btc_balance)
+            .context("BTC amount overflow")?,
+    ))
+}
+
+/// Work out how much XMR to sell towards a `btc_needed` shortfall
+///
+/// Adds the slippage buffer and caps the result at `max_btc_per_rebalance`
+/// worth of XMR and at the XMR above `monero_max_threshold`. `None` if the
+/// sale would bring in less than `min_btc_per_rebalance`, so the engine holds
+/// until there is enough to sell.
+fn size_reverse_rebalance(
+    btc_needed: Btc,
+    btc_per_xmr: f64,
+    xmr_balance: Xmr,
+    config: &TradingConfig,
+) -> Result<Option<Xmr>> {
+    if btc_per_xmr <= 0.0 {
+        anyhow::bail!("Invalid BTC/XMR price: {}", btc_per_xmr);
+    }
+
+    let slippage_multiplier = 1.0 + (config.slippage_tolerance_percent / 100.0);
+    let xmr_needed = xmr_amount(btc_needed.to_f64() / btc_per_xmr * slippage_multiplier)?;
+    let xmr_cap = xmr_amount(config.max_btc_per_rebalance / btc_per_xmr)?;
+    let xmr_excess = xmr_balance
+        .checked_sub(xmr_amount(config.monero_max_threshold)?)
+        .context("XMR amount overflow")?;
+
+    let xmr_to_sell = xmr_needed.min(xmr_cap).min(xmr_excess);
+
+    let btc_proceeds = xmr_to_sell
+        .to_btc_at(btc_per_xmr)
+        .context("BTC amount overflow")?;
+    let btc_minimum = btc_amount(config.min_btc_per_rebalance)?;
+    if !btc_proceeds.is_positive() || btc_proceeds < btc_minimum {
+        tracing::debug!(
+            "Reverse rebalance of {} XMR ({} BTC) is below the {} BTC minimum, holding",
+            xmr_to_sell,
+            btc_proceeds,
+            btc_minimum
+        );
+        return Ok(None);
+    }
+
+    Ok(Some(xmr_to_sell))
+}
+
+/// Locked XMR to wait for instead of rebalancing, if any
+///
+/// Only when `wait_for_xmr_unlock` is set, the unlocked balance is below
+/// `monero_min_threshold` and the total including locked funds is not.
+fn pending_unlock(unlocked: Xmr, total: Xmr, config: &TradingConfig) -> Result<Option<Xmr>> {
+    let threshold = xmr_amount(config.monero_min_threshold)?;
+    if !config.wait_for_xmr_unlock || unlocked >= threshold || total < threshold {
+        return Ok(None);
+    }
+
+    Ok(Some(
+        total.checked_sub(unlocked).context("XMR amount overflow")?,
+    ))
+}
+
+/// Work out how much BTC to spend on acquiring `xmr_needed`
+///
+/// Adds the slippage buffer, caps the result at `max_btc_per_rebalance` and
+/// fails if the wallet can't cover it while keeping `bitcoin_reserve_minimum`.
+/// `None` if it comes out below `min_btc_per_rebalance`, so the engine holds
+/// until the shortfall is worth a trade.
+fn size_rebalance(
+    xmr_needed: Xmr,
+    btc_per_xmr: f64,
+    btc_balance: Btc,
+    config: &TradingConfig,
+) -> Result<Option<Btc>> {
+    let slippage_multiplier = 1.0 + (config.slippage_tolerance_percent / 100.0);
+    let btc_needed = xmr_needed
+        .to_btc_at(btc_per_xmr * slippage_multiplier)
+        .context("BTC amount overflow")?;
+
+    let btc_to_use = btc_needed.min(btc_amount(config.max_btc_per_rebalance)?);
+
+    let btc_minimum = btc_amount(config.min_btc_per_rebalance)?;
+    if btc_to_use < btc_minimum {
+        tracing::debug!(
+            "Rebalance of {} BTC is below the {} BTC minimum, holding",
+            btc_to_use,
+            btc_minimum
+        );
+        return Ok(None);
+    }
+
+    let btc_available = btc_balance
+        .checked_sub(btc_amount(config.bitcoin_reserve_minimum)?)
+        .context("BTC amount overflow")?;
+    if btc_available < btc_to_use {
+        anyhow::bail!(
+            "Insufficient BTC: need {}, have {} available (after reserve)",
+            btc_to_use,
+            btc_available
+        );
+    }
+
+    Ok(Some(btc_to_use))
+}
+
+/// Feerate (sat/vB) to bump a stuck deposit paying `current` sat/vB to
+///
+/// At least bitcoind's `estimate` and a quarter (and one sat/vB, the minimum
+/// relay increment) above the current rate, capped at `max_fee_rate`. `None`
+/// once the cap leaves no room for a valid replacement.
+fn bump_fee_rate(current: f64, estimate: Option<f64>, max_fee_rate: f64) -> Option<f64> {
+    let minimum = current + RBF_MIN_INCREMENT_SAT_VB;
+    let target = (current * 1.25)
+        .max(minimum)
+        .max(estimate.unwrap_or(0.0))
+        .min(max_fee_rate);
+
+    (target >= minimum).then_some(target)
+}
+
+/// Refuse to proceed when the pending transaction count has reached the cap
+fn check_pending_limit(pending: usize, max_pending: usize) -> Result<()> {
+    if pending >= max_pending {
+        tracing::error!(
+            "Refusing to start rebalance: {} pending transactions (limit {}); resolve them first",
+            pending,
+            max_pending
+        );
+        anyhow::bail!(
+            "Too many pending transactions ({} >= {}), rebalance blocked until they resolve",
+            pending,
+            max_pending
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::KrakenClient;
+    use crate::trading::config::{
+        SharedTradingConfig, TradingConfig, TradingSchedule, TradingWindow,
+    };
+    use chrono::{NaiveTime, TimeZone, Weekday};
+
+    fn create_test_engine() -> TradingEngine {
+        let config = TradingConfig::default();
+        let shared_config = SharedTradingConfig::new(config);
+
+        TradingEngine::new(
+            shared_config,
+            KrakenClient::new("test_key".to_string(), "test_secret".to_string()).into(),
+            "http://localhost:8332".to_string(),
+            "/tmp/cookie".to_string(),
+            "test_wallet".to_string(),
+            "http://localhost:18082/json_rpc".to_string(),
+            "test_xmr_wallet".to_string(),
+            "".to_string(),
+        )
+    }
+
+    fn create_test_engine_with_config(config: TradingConfig) -> TradingEngine {
+        let shared_config = SharedTradingConfig::new(config);
+
+        TradingEngine::new(
+            shared_config,
+            KrakenClient::new("test_key".to_string(), "test_secret".to_string()).into(),
+            "http://localhost:8332".to_string(),
+            "/tmp/cookie".to_string(),
+            "test_wallet".to_string(),
+            "http://localhost:18082/json_rpc".to_string(),
+            "test_xmr_wallet".to_string(),
+            "".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_engine_creation() {
+        let engine = create_test_engine();
+        assert!(!engine.is_enabled());
+        assert_eq!(engine.get_state(), TradingState::Disabled);
+    }
+
+    #[test]
+    fn test_engine_enable_disable() {
+        let engine = create_test_engine();
+
+        engine.enable();
+        assert!(engine.is_enabled());
+        assert_eq!(engine.get_state(), TradingState::Monitoring);
+
+        engine.disable();
+        assert!(!engine.is_enabled());
+        assert_eq!(engine.get_state(), TradingState::Disabled);
+    }
+
+    #[test]
+    fn test_engine_state_management() {
+        let engine = create_test_engine();
+
+        // Test different state transitions
+        engine.enable();
+        assert_eq!(engine.get_state(), TradingState::Monitoring);
+
+        // Manually set different states (private method, but testing the storage)
+        assert_eq!(engine.get_state(), TradingState::Monitoring);
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_on_shutdown() {
+        let shutdown = Shutdown::new();
+        let engine = create_test_engine().with_shutdown(shutdown.clone());
+        let running = tokio::spawn(engine.clone().run());
+
+        // Disabled, so the loop is sleeping between enable checks
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert!(!running.is_finished());
+
+        shutdown.trigger();
+        tokio::time::timeout(std::time::Duration::from_secs(1), running)
+            .await
+            .expect("engine should stop on shutdown")
+            .unwrap();
+
+        // Steps refuse to start once shut down
+        let err = engine.step_boundary().await.unwrap_err();
+        assert!(err.is::<Interrupted>());
+    }
+
+    #[test]
+    fn test_pause_and_resume_when_idle() {
+        let engine = create_test_engine();
+        engine.enable();
+
+        assert_eq!(engine.pause(), TradingState::Paused);
+        assert!(engine.is_paused());
+
+        assert_eq!(engine.resume(), TradingState::Monitoring);
+        assert!(!engine.is_paused());
+    }
+
+    #[test]
+    fn test_pause_waits_for_the_current_step() {
+        let engine = create_test_engine();
+        engine.enable();
+        let waiting = TradingState::WaitingForBitcoinDeposit {
+            txid: "abc".to_string(),
+        };
+        engine.set_state(waiting.clone());
+
+        // The step in progress keeps its state until the next boundary
+        assert_eq!(engine.pause(), waiting);
+        assert!(engine.is_paused());
+    }
+
+    #[test]
+    fn test_step_override_requires_a_waiting_step() {
+        let engine = create_test_engine();
+        engine.enable();
+        assert!(engine.override_step(StepOverride::Skip).is_err());
+
+        engine.set_state(TradingState::WaitingForTradeExecution {
+            order_id: "order123".to_string(),
+        });
+        assert!(engine.override_step(StepOverride::Complete).is_ok());
+    }
+
+    /// Exchange with one order that stays open, recording what gets cancelled
+    #[derive(Clone, Default)]
+    struct OpenOrderExchange {
+        /// Volume the order has filled so far
+        executed: &'static str,
+        polls: Arc<std::sync::atomic::AtomicUsize>,
+        cancelled: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl Exchange for OpenOrderExchange {
+        fn name(&self) -> &'static str {
+            "Test exchange"
+        }
+
+        async fn ticker(&self) -> Result<crate::services::exchange::Ticker> {
+            anyhow::bail!("not used")
+        }
+
+        async fn balances(&self) -> Result<crate::services::exchange::ExchangeBalances> {
+            anyhow::bail!("not used")
+        }
+
+        async fn volume_decimals(&self) -> Result<u32> {
+            anyhow::bail!("not used")
+        }
+
+        async fn deposit_address(&self, _asset: Asset, _new: bool) -> Result<String> {
+            anyhow::bail!("not used")
+        }
+
+        async fn submit_order(&self, _order: &OrderRequest) -> Result<String> {
+            anyhow::bail!("not used")
+        }
+
+        async fn order_status(&self, _order_id: &str) -> Result<Option<OrderReport>> {
+            self.polls.fetch_add(1, Ordering::SeqCst);
+            Ok(Some(OrderReport {
+                state: OrderState::Open,
+                executed: self.executed.to_string(),
+                cost: String::new(),
+                price: String::new(),
+                fee: String::new(),
+            }))
+        }
+
+        async fn cancel_order(&self, order_id: &str) -> Result<bool> {
+            self.cancelled.lock().unwrap().push(order_id.to_string());
+            Ok(true)
+        }
+
+        async fn cancel_open_orders(&self) -> Result<u64> {
+            self.cancelled.lock().unwrap().push("order123".to_string());
+            Ok(1)
+        }
+
+        async fn withdraw_to(
+            &self,
+            _asset: Asset,
+            _amount: &str,
+            _address: &str,
+        ) -> Result<String> {
+            anyhow::bail!("not used")
+        }
+
+        async fn deposits(&self, _asset: Asset) -> Result<Vec<crate::services::exchange::Deposit>> {
+            anyhow::bail!("not used")
+        }
+
+        async fn withdrawals(
+            &self,
+            _asset: Asset,
+        ) -> Result<Vec<crate::services::exchange::Withdrawal>> {
+            anyhow::bail!("not used")
+        }
+
+        async fn trading_fee_percent(&self) -> Result<f64> {
+            anyhow::bail!("not used")
+        }
+
+        async fn withdrawal_fee(&self, _asset: Asset, _amount: &str) -> Result<f64> {
+            anyhow::bail!("not used")
+        }
+    }
+
+    /// Enabled engine trading on `exchange`
+    fn open_order_engine(exchange: &OpenOrderExchange) -> TradingEngine<OpenOrderExchange> {
+        let engine = TradingEngine::new(
+            SharedTradingConfig::new(TradingConfig::default()),
+            exchange.clone(),
+            "http://localhost:8332".to_string(),
+            "/tmp/cookie".to_string(),
+            "test_wallet".to_string(),
+            "http://localhost:18082/json_rpc".to_string(),
+            "test_xmr_wallet".to_string(),
+            "".to_string(),
+        );
         engine.enable();
-        assert!(engine.is_enabled());
-        assert_eq!(engine.get_state(), TradingState::Monitoring);
+        engine
+    }
+
+    /// Wait on an open order on `exchange` and apply `step_override` once it has been polled
+    async fn override_trade_wait(
+        exchange: &OpenOrderExchange,
+        step_override: StepOverride,
+    ) -> Result<TradeFill> {
+        let engine = open_order_engine(exchange);
+        engine.set_state(TradingState::WaitingForTradeExecution {
+            order_id: "order123".to_string(),
+        });
+
+        let waiting = {
+            let engine = engine.clone();
+            let exchange = exchange.clone();
+            tokio::spawn(async move {
+                let config = TradingConfig::default();
+                engine
+                    .wait_for_trade_execution(&exchange, "order123", &config)
+                    .await
+            })
+        };
+        while exchange.polls.load(Ordering::SeqCst) == 0 {
+            tokio::task::yield_now().await;
+        }
+        engine.override_step(step_override).unwrap();
+
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiting)
+            .await
+            .expect("the override should end the wait")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cancel_all_holds_the_rebalance_in_progress() {
+        let exchange = OpenOrderExchange::default();
+        let engine = open_order_engine(&exchange);
+        engine.set_state(TradingState::WaitingForTradeExecution {
+            order_id: "order123".to_string(),
+        });
+
+        let summary = engine.cancel_all_orders().await.unwrap();
+        assert_eq!(summary.orders_cancelled, 1);
+        assert!(!engine.is_enabled());
+        assert!(engine.is_paused());
+
+        // The rebalance stops before its next step rather than placing another order
+        let next_step = {
+            let engine = engine.clone();
+            tokio::spawn(async move { engine.step_boundary().await })
+        };
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert!(!next_step.is_finished());
+        assert_eq!(engine.get_state(), TradingState::Paused);
+
+        engine.resume();
+        tokio::time::timeout(std::time::Duration::from_secs(1), next_step)
+            .await
+            .expect("resuming should release the step")
+            .unwrap()
+            .unwrap();
+        assert!(!engine.is_enabled());
+    }
+
+    #[tokio::test]
+    async fn test_skipped_trade_cancels_its_order() {
+        let exchange = OpenOrderExchange::default();
+
+        let Err(e) = override_trade_wait(&exchange, StepOverride::Skip).await else {
+            panic!("a skipped trade should abandon the rebalance");
+        };
+        assert!(e.to_string().contains("skipped by operator"), "{:#}", e);
+        assert_eq!(*exchange.cancelled.lock().unwrap(), vec!["order123"]);
+    }
+
+    #[tokio::test]
+    async fn test_completed_trade_keeps_what_filled() {
+        let exchange = OpenOrderExchange {
+            executed: "0.5",
+            ..Default::default()
+        };
+
+        let Ok(fill) = override_trade_wait(&exchange, StepOverride::Complete).await else {
+            panic!("a partly filled trade can be marked complete");
+        };
+        assert_eq!(fill.xmr, xmr_amount(0.5).unwrap());
+        assert!(exchange.cancelled.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_engine_with_database() {
+        let engine = create_test_engine();
+        assert!(engine.get_db().is_none());
+
+        // After creation, database should be None
+        assert!(!engine.is_enabled());
+    }
+
+    #[test]
+    fn test_trading_state_equality() {
+        assert_eq!(TradingState::Disabled, TradingState::Disabled);
+        assert_eq!(TradingState::Monitoring, TradingState::Monitoring);
+        assert_ne!(TradingState::Disabled, TradingState::Monitoring);
+
+        let state1 = TradingState::DepositingBitcoin { amount: 0.1 };
+        let state2 = TradingState::DepositingBitcoin { amount: 0.1 };
+        let state3 = TradingState::DepositingBitcoin { amount: 0.2 };
+        assert_eq!(state1, state2);
+        assert_ne!(state1, state3);
+    }
+
+    #[test]
+    fn test_trading_state_serialization() {
+        let states = vec![
+            TradingState::Disabled,
+            TradingState::Monitoring,
+            TradingState::OutsideSchedule,
+            TradingState::WaitingForUnlock { locked: 2.5 },
+            TradingState::WaitingForApproval {
+                approval_id: "abc123".to_string(),
+            },
+            TradingState::DepositingBitcoin { amount: 0.5 },
+            TradingState::WaitingForBitcoinDeposit {
+                txid: "test_txid".to_string(),
+            },
+            TradingState::Trading { btc_amount: 0.3 },
+            TradingState::SellingMonero { xmr_amount: 12.0 },
+            TradingState::WaitingForTradeExecution {
+                order_id: "order123".to_string(),
+            },
+            TradingState::WithdrawingMonero { amount: 10.0 },
+            TradingState::WaitingForMoneroWithdrawal {
+                refid: "ref456".to_string(),
+            },
+            TradingState::Paused,
+            TradingState::Error {
+                message: "test error".to_string(),
+            },
+        ];
+
+        for state in states {
+            // Test that states can be serialized
+            let json = serde_json::to_string(&state).expect("Should serialize");
+            assert!(!json.is_empty());
+
+            // Test that they can be deserialized
+            let deserialized: TradingState =
+                serde_json::from_str(&json).expect("Should deserialize");
+            assert_eq!(state, deserialized);
+        }
+    }
+
+    #[test]
+    fn test_trading_status_structure() {
+        let status = TradingStatus {
+            state: TradingState::Monitoring,
+            enabled: true,
+            paused: false,
+            last_check: Some("2024-01-01T00:00:00Z".to_string()),
+            last_rebalance: None,
+            current_btc_balance: Some(1.5),
+            current_xmr_balance: Some(50.0),
+            current_xmr_total_balance: Some(52.0),
+            current_xmr_locked_balance: Some(2.0),
+            kraken_btc_balance: Some(0.1),
+            kraken_xmr_balance: Some(5.0),
+        };
+
+        assert_eq!(status.state, TradingState::Monitoring);
+        assert!(status.enabled);
+        assert_eq!(status.current_btc_balance, Some(1.5));
+        assert_eq!(status.current_xmr_balance, Some(50.0));
+
+        // Test serialization
+        let json = serde_json::to_string(&status).expect("Should serialize");
+        assert!(!json.is_empty());
+    }
+
+    #[test]
+    fn test_config_integration() {
+        let config = TradingConfig::default();
+        let shared_config = SharedTradingConfig::new(config);
+
+        let engine: TradingEngine = TradingEngine::new(
+            shared_config.clone(),
+            KrakenClient::new("key".to_string(), "secret".to_string()).into(),
+            "http://localhost:8332".to_string(),
+            "/tmp/cookie".to_string(),
+            "wallet".to_string(),
+            "http://localhost:18082/json_rpc".to_string(),
+            "xmr_wallet".to_string(),
+            "".to_string(),
+        );
+
+        // Engine should have access to config
+        let current_config = shared_config.get();
+        assert!(current_config.monero_min_threshold > 0.0);
+        assert!(engine.is_enabled() == false);
+    }
+
+    #[test]
+    fn test_multiple_engines_independence() {
+        let engine1 = create_test_engine();
+        let engine2 = create_test_engine();
+
+        engine1.enable();
+        assert!(engine1.is_enabled());
+        assert!(!engine2.is_enabled());
+
+        engine2.enable();
+        assert!(engine1.is_enabled());
+        assert!(engine2.is_enabled());
+
+        engine1.disable();
+        assert!(!engine1.is_enabled());
+        assert!(engine2.is_enabled());
+    }
+
+    #[tokio::test]
+    async fn test_get_status_without_wallets() {
+        let engine = create_test_engine();
+
+        // Should not panic even if wallets aren't available
+        let status = engine.get_status().await;
+
+        assert_eq!(status.state, TradingState::Disabled);
+        assert!(!status.enabled);
+        // Balances may be None if wallets aren't available
+    }
+
+    // ===== Trading Logic Tests =====
+
+    #[test]
+    fn test_xmr_amount_calculation_when_below_threshold() {
+        // Test that we calculate the correct amount of XMR needed
+        let config = TradingConfig {
+            monero_min_threshold: 1.0,
+            monero_target_balance: 5.0,
+            bitcoin_reserve_minimum: 0.01,
+            max_btc_per_rebalance: 0.1,
+            min_btc_per_rebalance: 0.0,
+            check_interval_secs: 300,
+            order_timeout_secs: 600,
+            slippage_tolerance_percent: 1.0,
+            use_limit_orders: true,
+            max_pending_transactions: 5,
+            trading_schedule: None,
+            max_holdings_drop_percent: 20.0,
+            wait_for_xmr_unlock: false,
+            bitcoin_min_threshold: 0.0,
+            bitcoin_target_balance: 0.0,
+            monero_max_threshold: 0.0,
+            rbf_bump_after_mins: 0,
+            rbf_max_fee_rate: 50.0,
+            psbt_min_btc: 0.0,
+            psbt_signing_timeout_secs: 86400,
+        };
+
+        // Current XMR: 0.5, Target: 5.0 -> Need 4.5 XMR
+        let current_xmr = 0.5;
+        let xmr_needed = config.monero_target_balance - current_xmr;
+        assert_eq!(xmr_needed, 4.5);
+
+        // Current XMR: 0.0, Target: 5.0 -> Need 5.0 XMR
+        let current_xmr = 0.0;
+        let xmr_needed = config.monero_target_balance - current_xmr;
+        assert_eq!(xmr_needed, 5.0);
+
+        // Current XMR: 0.99, Target: 5.0 -> Need 4.01 XMR
+        let current_xmr = 0.99;
+        let xmr_needed = config.monero_target_balance - current_xmr;
+        assert!((xmr_needed - 4.01).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_should_trade_when_below_threshold() {
+        let config = TradingConfig {
+            monero_min_threshold: 1.0,
+            monero_target_balance: 5.0,
+            ..TradingConfig::default()
+        };
+
+        // Should trade when below threshold
+        let current_xmr = 0.5;
+        assert!(current_xmr < config.monero_min_threshold);
+
+        let current_xmr = 0.99;
+        assert!(current_xmr < config.monero_min_threshold);
+
+        let current_xmr = 0.0;
+        assert!(current_xmr < config.monero_min_threshold);
+    }
+
+    #[test]
+    fn test_should_not_trade_when_above_threshold() {
+        let config = TradingConfig {
+            monero_min_threshold: 1.0,
+            monero_target_balance: 5.0,
+            ..TradingConfig::default()
+        };
+
+        // Should NOT trade when at or above threshold
+        let current_xmr = 1.0;
+        assert!(current_xmr >= config.monero_min_threshold);
+
+        let current_xmr = 1.5;
+        assert!(current_xmr >= config.monero_min_threshold);
+
+        let current_xmr = 5.0;
+        assert!(current_xmr >= config.monero_min_threshold);
+
+        let current_xmr = 10.0;
+        assert!(current_xmr >= config.monero_min_threshold);
+    }
+
+    #[test]
+    fn test_btc_calculation_with_slippage() {
+        let config = TradingConfig {
+            monero_min_threshold: 1.0,
+            monero_target_balance: 5.0,
+            bitcoin_reserve_minimum: 0.01,
+            max_btc_per_rebalance: 0.5,
+            slippage_tolerance_percent: 1.0,
+            ..TradingConfig::default()
+        };
+
+        // Need 4 XMR, rate is 1 XMR = 0.02 BTC (50 XMR per BTC)
+        let xmr_needed = 4.0;
+        let btc_xmr_price = 0.02; // 1 XMR costs 0.02 BTC
+
+        let slippage_multiplier = 1.0 + (config.slippage_tolerance_percent / 100.0);
+        let btc_needed = xmr_needed * btc_xmr_price * slippage_multiplier;
+
+        // Expected: 4 * 0.02 * 1.01 = 0.0808
+        assert!((btc_needed - 0.0808).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_btc_capped_at_max_per_rebalance() {
+        let config = TradingConfig {
+            monero_min_threshold: 1.0,
+            monero_target_balance: 100.0, // Very high target
+            bitcoin_reserve_minimum: 0.01,
+            max_btc_per_rebalance: 0.1, // Max 0.1 BTC per trade
+            slippage_tolerance_percent: 1.0,
+            ..TradingConfig::default()
+        };
+
+        // Need 99 XMR, rate is 1 XMR = 0.02 BTC
+        let xmr_needed = 99.0;
+        let btc_xmr_price = 0.02;
+
+        let slippage_multiplier = 1.0 + (config.slippage_tolerance_percent / 100.0);
+        let btc_needed = xmr_needed * btc_xmr_price * slippage_multiplier;
+
+        // Would need 1.9998 BTC, but capped at max_btc_per_rebalance
+        let btc_to_use = btc_needed.min(config.max_btc_per_rebalance);
+        assert_eq!(btc_to_use, 0.1);
+    }
+
+    #[test]
+    fn test_insufficient_btc_calculation() {
+        let config = TradingConfig {
+            monero_min_threshold: 1.0,
+            monero_target_balance: 5.0,
+            bitcoin_reserve_minimum: 0.05, // Keep 0.05 BTC as reserve
+            max_btc_per_rebalance: 0.5,
+            slippage_tolerance_percent: 1.0,
+            ..TradingConfig::default()
+        };
+
+        // Current BTC balance: 0.1
+        let btc_balance = 0.1;
+        let btc_available = btc_balance - config.bitcoin_reserve_minimum;
+        // Available: 0.1 - 0.05 = 0.05 BTC
+        assert_eq!(btc_available, 0.05);
+
+        // If we need 0.08 BTC, we don't have enough
+        let btc_needed = 0.08;
+        assert!(btc_available < btc_needed);
+    }
+
+    #[test]
+    fn test_sufficient_btc_calculation() {
+        let config = TradingConfig {
+            monero_min_threshold: 1.0,
+            monero_target_balance: 5.0,
+            bitcoin_reserve_minimum: 0.01,
+            max_btc_per_rebalance: 0.5,
+            slippage_tolerance_percent: 1.0,
+            ..TradingConfig::default()
+        };
+
+        // Current BTC balance: 0.5
+        let btc_balance = 0.5;
+        let btc_available = btc_balance - config.bitcoin_reserve_minimum;
+        // Available: 0.5 - 0.01 = 0.49 BTC
+        assert_eq!(btc_available, 0.49);
+
+        // If we need 0.08 BTC, we have enough
+        let btc_needed = 0.08;
+        assert!(btc_available >= btc_needed);
+    }
+
+    #[test]
+    fn test_threshold_boundary_conditions() {
+        let config = TradingConfig {
+            monero_min_threshold: 1.0,
+            monero_target_balance: 5.0,
+            ..TradingConfig::default()
+        };
+
+        // Exactly at threshold - should NOT trade
+        let current_xmr = 1.0;
+        assert!(current_xmr >= config.monero_min_threshold);
+
+        // Just below threshold - SHOULD trade
+        let current_xmr = 0.999999;
+        assert!(current_xmr < config.monero_min_threshold);
+
+        // Just above threshold - should NOT trade
+        let current_xmr = 1.000001;
+        assert!(current_xmr >= config.monero_min_threshold);
+    }
+
+    #[test]
+    fn test_different_slippage_tolerances() {
+        // Test 0% slippage
+        let slippage_percent = 0.0;
+        let slippage_multiplier = 1.0 + (slippage_percent / 100.0);
+        assert_eq!(slippage_multiplier, 1.0);
+
+        // Test 1% slippage
+        let slippage_percent = 1.0;
+        let slippage_multiplier = 1.0 + (slippage_percent / 100.0);
+        assert_eq!(slippage_multiplier, 1.01);
+
+        // Test 5% slippage
+        let slippage_percent = 5.0;
+        let slippage_multiplier = 1.0 + (slippage_percent / 100.0);
+        assert_eq!(slippage_multiplier, 1.05);
+
+        // Test 10% slippage
+        let slippage_percent = 10.0;
+        let slippage_multiplier = 1.0 + (slippage_percent / 100.0);
+        assert_eq!(slippage_multiplier, 1.10);
+    }
+
+    #[test]
+    fn test_realistic_trading_scenario_low_xmr() {
+        // Scenario: User has received lots of BTC, XMR is low
+        let config = TradingConfig {
+            monero_min_threshold: 10.0,
+            monero_target_balance: 50.0,
+            bitcoin_reserve_minimum: 0.1,
+            max_btc_per_rebalance: 1.0,
+            slippage_tolerance_percent: 1.0,
+            ..TradingConfig::default()
+        };
+
+        let current_btc = 5.0;
+        let current_xmr = 2.0; // Below threshold!
+        let btc_xmr_price = 0.02; // 1 XMR = 0.02 BTC (50 XMR per BTC)
+
+        // Should trade
+        assert!(current_xmr < config.monero_min_threshold);
+
+        // Calculate how much XMR needed
+        let xmr_needed = config.monero_target_balance - current_xmr;
+        assert_eq!(xmr_needed, 48.0);
+
+        // Calculate BTC needed with slippage
+        let slippage_multiplier = 1.0 + (config.slippage_tolerance_percent / 100.0);
+        let btc_needed = xmr_needed * btc_xmr_price * slippage_multiplier;
+        // 48 * 0.02 * 1.01 = 0.9696
+        assert!((btc_needed - 0.9696).abs() < 0.0001);
+
+        // Cap at max per rebalance
+        let btc_to_use = btc_needed.min(config.max_btc_per_rebalance);
+        assert_eq!(btc_to_use, 0.9696);
+
+        // Check if we have enough BTC
+        let btc_available = current_btc - config.bitcoin_reserve_minimum;
+        assert_eq!(btc_available, 4.9);
+        assert!(btc_available >= btc_to_use); // Yes, we have enough
+    }
+
+    #[test]
+    fn test_realistic_trading_scenario_sufficient_xmr() {
+        // Scenario: Balanced system, no trade needed
+        let config = TradingConfig {
+            monero_min_threshold: 10.0,
+            monero_target_balance: 50.0,
+            bitcoin_reserve_minimum: 0.1,
+            max_btc_per_rebalance: 1.0,
+            slippage_tolerance_percent: 1.0,
+            ..TradingConfig::default()
+        };
+
+        let _current_btc = 1.0;
+        let current_xmr = 45.0; // Above threshold!
+
+        // Should NOT trade
+        assert!(current_xmr >= config.monero_min_threshold);
+    }
+
+    #[test]
+    fn test_edge_case_zero_balances() {
+        let config = TradingConfig {
+            monero_min_threshold: 1.0,
+            monero_target_balance: 5.0,
+            bitcoin_reserve_minimum: 0.01,
+            max_btc_per_rebalance: 0.5,
+            slippage_tolerance_percent: 1.0,
+            ..TradingConfig::default()
+        };
+
+        // Zero XMR - should trade
+        let current_xmr = 0.0;
+        assert!(current_xmr < config.monero_min_threshold);
+        let xmr_needed = config.monero_target_balance - current_xmr;
+        assert_eq!(xmr_needed, 5.0);
+
+        // Zero BTC - can't trade
+        let current_btc = 0.0;
+        let btc_available = current_btc - config.bitcoin_reserve_minimum;
+        assert!(btc_available < 0.0); // Not enough BTC
+    }
+
+    #[test]
+    fn test_config_validation_for_trading_logic() {
+        // Valid config
+        let config = TradingConfig {
+            monero_min_threshold: 1.0,
+            monero_target_balance: 5.0,
+            bitcoin_reserve_minimum: 0.01,
+            max_btc_per_rebalance: 0.5,
+            min_btc_per_rebalance: 0.0,
+            check_interval_secs: 300,
+            order_timeout_secs: 600,
+            slippage_tolerance_percent: 1.0,
+            use_limit_orders: true,
+            max_pending_transactions: 5,
+            trading_schedule: None,
+            max_holdings_drop_percent: 20.0,
+            wait_for_xmr_unlock: false,
+            bitcoin_min_threshold: 0.0,
+            bitcoin_target_balance: 0.0,
+            monero_max_threshold: 0.0,
+            rbf_bump_after_mins: 0,
+            rbf_max_fee_rate: 50.0,
+            psbt_min_btc: 0.0,
+            psbt_signing_timeout_secs: 86400,
+        };
+        assert!(config.validate().is_ok());
+
+        // Invalid: min_threshold >= target
+        let invalid_config = TradingConfig {
+            monero_min_threshold: 5.0,
+            monero_target_balance: 1.0,
+            ..config.clone()
+        };
+        assert!(invalid_config.validate().is_err());
 
-        engine.disable();
-        assert!(!engine.is_enabled());
-        assert_eq!(engine.get_state(), TradingState::Disabled);
-    }
+        // Invalid: negative values
+        let invalid_config = TradingConfig {
+            monero_min_threshold: -1.0,
+            ..config.clone()
+        };
+        assert!(invalid_config.validate().is_err());
 
-    #[test]
-    fn test_engine_state_management() {
-        let engine = create_test_engine();
+        // Reverse direction: valid when the XMR ceiling sits above the target
+        let reverse_config = TradingConfig {
+            bitcoin_min_threshold: 0.05,
+            bitcoin_target_balance: 0.2,
+            monero_max_threshold: 20.0,
+            ..config.clone()
+        };
+        assert!(reverse_config.validate().is_ok());
 
-        // Test different state transitions
-        engine.enable();
-        assert_eq!(engine.get_state(), TradingState::Monitoring);
+        // Invalid: a ceiling at or below the target would buy straight back
+        let invalid_config = TradingConfig {
+            monero_max_threshold: 5.0,
+            ..reverse_config.clone()
+        };
+        assert!(invalid_config.validate().is_err());
 
-        // Manually set different states (private method, but testing the storage)
-        assert_eq!(engine.get_state(), TradingState::Monitoring);
-    }
+        // Invalid: BTC threshold >= target
+        let invalid_config = TradingConfig {
+            bitcoin_target_balance: 0.05,
+            ..reverse_config
+        };
+        assert!(invalid_config.validate().is_err());
 
-    #[test]
-    fn test_engine_with_database() {
-        let engine = create_test_engine();
-        assert!(engine.get_db().is_none());
+        // Invalid: fee bumping with no feerate to bump to
+        let invalid_config = TradingConfig {
+            rbf_bump_after_mins: 30,
+            rbf_max_fee_rate: 0.0,
+            ..config.clone()
+        };
+        assert!(invalid_config.validate().is_err());
 
-        // After creation, database should be None
-        assert!(!engine.is_enabled());
+        // Invalid: offline signing that can never finish
+        let invalid_config = TradingConfig {
+            psbt_min_btc: 0.5,
+            psbt_signing_timeout_secs: 0,
+            ..config
+        };
+        assert!(invalid_config.validate().is_err());
     }
 
     #[test]
-    fn test_trading_state_equality() {
-        assert_eq!(TradingState::Disabled, TradingState::Disabled);
-        assert_eq!(TradingState::Monitoring, TradingState::Monitoring);
-        assert_ne!(TradingState::Disabled, TradingState::Monitoring);
-
-        let state1 = TradingState::DepositingBitcoin { amount: 0.1 };
-        let state2 = TradingState::DepositingBitcoin { amount: 0.1 };
-        let state3 = TradingState::DepositingBitcoin { amount: 0.2 };
-        assert_eq!(state1, state2);
-        assert_ne!(state1, state3);
+    fn test_bump_fee_rate() {
+        // Goes to bitcoind's estimate when that is well above the current rate
+        assert_eq!(bump_fee_rate(2.0, Some(12.0), 50.0), Some(12.0));
+        // Otherwise raises the current rate by a quarter, at least 1 sat/vB
+        assert_eq!(bump_fee_rate(8.0, Some(5.0), 50.0), Some(10.0));
+        assert_eq!(bump_fee_rate(2.0, None, 50.0), Some(3.0));
+        // Capped at the configured maximum
+        assert_eq!(bump_fee_rate(20.0, Some(80.0), 50.0), Some(50.0));
+        // Nothing left to bump once the cap leaves less than the minimum increment
+        assert_eq!(bump_fee_rate(49.5, Some(80.0), 50.0), None);
     }
 
-    #[test]
-    fn test_trading_state_serialization() {
-        let states = vec![
-            TradingState::Disabled,
-            TradingState::Monitoring,
-            TradingState::DepositingBitcoin { amount: 0.5 },
-            TradingState::WaitingForBitcoinDeposit {
-                txid: "test_txid".to_string(),
-            },
-            TradingState::Trading { btc_amount: 0.3 },
-            TradingState::WaitingForTradeExecution {
-                order_id: "order123".to_string(),
-            },
-            TradingState::WithdrawingMonero { amount: 10.0 },
-            TradingState::WaitingForMoneroWithdrawal {
-                refid: "ref456".to_string(),
-            },
-            TradingState::Error {
-                message: "test error".to_string(),
-            },
-        ];
-
-        for state in states {
-            // Test that states can be serialized
-            let json = serde_json::to_string(&state).expect("Should serialize");
-            assert!(!json.is_empty());
+    // ===== Trading Schedule Tests =====
 
-            // Test that they can be deserialized
-            let deserialized: TradingState =
-                serde_json::from_str(&json).expect("Should deserialize");
-            assert_eq!(state, deserialized);
+    fn window(days: Vec<Weekday>, start: (u32, u32), end: (u32, u32)) -> TradingWindow {
+        TradingWindow {
+            days,
+            start: NaiveTime::from_hms_opt(start.0, start.1, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(end.0, end.1, 0).unwrap(),
         }
     }
 
     #[test]
-    fn test_trading_status_structure() {
-        let status = TradingStatus {
-            state: TradingState::Monitoring,
-            enabled: true,
-            last_check: Some("2024-01-01T00:00:00Z".to_string()),
-            last_rebalance: None,
-            current_btc_balance: Some(1.5),
-            current_xmr_balance: Some(50.0),
-            kraken_btc_balance: Some(0.1),
-            kraken_xmr_balance: Some(5.0),
+    fn test_schedule_window_matching() {
+        let schedule = TradingSchedule {
+            windows: vec![window(vec![Weekday::Mon, Weekday::Tue], (8, 0), (17, 0))],
         };
 
-        assert_eq!(status.state, TradingState::Monitoring);
-        assert!(status.enabled);
-        assert_eq!(status.current_btc_balance, Some(1.5));
-        assert_eq!(status.current_xmr_balance, Some(50.0));
+        // 2024-01-01 is a Monday
+        let monday_noon = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let monday_evening = Utc.with_ymd_and_hms(2024, 1, 1, 17, 0, 0).unwrap();
+        let saturday_noon = Utc.with_ymd_and_hms(2024, 1, 6, 12, 0, 0).unwrap();
 
-        // Test serialization
-        let json = serde_json::to_string(&status).expect("Should serialize");
-        assert!(!json.is_empty());
+        assert!(schedule.is_open(monday_noon));
+        assert!(!schedule.is_open(monday_evening)); // end is exclusive
+        assert!(!schedule.is_open(saturday_noon));
+
+        // Empty day list means every day
+        let daily = TradingSchedule {
+            windows: vec![window(vec![], (8, 0), (17, 0))],
+        };
+        assert!(daily.is_open(saturday_noon));
     }
 
     #[test]
-    fn test_config_integration() {
-        let config = TradingConfig::default();
-        let shared_config = SharedTradingConfig::new(config);
+    fn test_schedule_validation() {
+        let valid = TradingSchedule {
+            windows: vec![
+                window(vec![Weekday::Mon], (8, 0), (12, 0)),
+                window(vec![Weekday::Mon], (12, 0), (17, 0)),
+                window(vec![Weekday::Tue], (10, 0), (14, 0)),
+            ],
+        };
+        assert!(valid.validate().is_ok());
 
-        let engine = TradingEngine::new(
-            shared_config.clone(),
-            "key".to_string(),
-            "secret".to_string(),
-            "http://localhost:8332".to_string(),
-            "/tmp/cookie".to_string(),
-            "wallet".to_string(),
-            "http://localhost:18082/json_rpc".to_string(),
-            "xmr_wallet".to_string(),
-            "".to_string(),
-        );
+        let empty = TradingSchedule { windows: vec![] };
+        assert!(empty.validate().is_err());
 
-        // Engine should have access to config
-        let current_config = shared_config.get();
-        assert!(current_config.monero_min_threshold > 0.0);
-        assert!(engine.is_enabled() == false);
+        let inverted = TradingSchedule {
+            windows: vec![window(vec![], (17, 0), (8, 0))],
+        };
+        assert!(inverted.validate().is_err());
+
+        // Overlapping on a shared day
+        let overlapping = TradingSchedule {
+            windows: vec![
+                window(vec![Weekday::Mon, Weekday::Wed], (8, 0), (12, 0)),
+                window(vec![Weekday::Wed], (11, 0), (15, 0)),
+            ],
+        };
+        assert!(overlapping.validate().is_err());
+
+        // An every-day window overlaps any other window at the same time
+        let overlapping_daily = TradingSchedule {
+            windows: vec![
+                window(vec![], (8, 0), (12, 0)),
+                window(vec![Weekday::Sun], (9, 0), (10, 0)),
+            ],
+        };
+        assert!(overlapping_daily.validate().is_err());
+
+        let config = TradingConfig {
+            trading_schedule: Some(overlapping),
+            ..TradingConfig::default()
+        };
+        assert!(config.validate().is_err());
     }
 
-    #[test]
-    fn test_multiple_engines_independence() {
-        let engine1 = create_test_engine();
-        let engine2 = create_test_engine();
+    #[tokio::test]
+    async fn test_rebalance_deferred_outside_schedule() {
+        let config = TradingConfig {
+            trading_schedule: Some(TradingSchedule {
+                windows: vec![window(vec![Weekday::Mon], (8, 0), (17, 0))],
+            }),
+            ..TradingConfig::default()
+        };
 
-        engine1.enable();
-        assert!(engine1.is_enabled());
-        assert!(!engine2.is_enabled());
+        // Saturday night, outside the Monday window
+        let mock_now = Utc.with_ymd_and_hms(2024, 1, 6, 23, 0, 0).unwrap();
+        let engine = create_test_engine_with_config(config).with_clock(move || mock_now);
+        engine.enable();
 
-        engine2.enable();
-        assert!(engine1.is_enabled());
-        assert!(engine2.is_enabled());
+        // No wallets are reachable, so this only succeeds if the schedule
+        // check short-circuits before any balance lookup or trade
+        engine
+            .check_and_rebalance()
+            .await
+            .expect("Rebalance should be deferred, not attempted");
+        assert_eq!(engine.get_state(), TradingState::OutsideSchedule);
+    }
 
-        engine1.disable();
-        assert!(!engine1.is_enabled());
-        assert!(engine2.is_enabled());
+    #[test]
+    fn test_pending_transaction_limit() {
+        assert!(check_pending_limit(0, 5).is_ok());
+        assert!(check_pending_limit(4, 5).is_ok());
+        assert!(check_pending_limit(5, 5).is_err());
+        assert!(check_pending_limit(8, 5).is_err());
+
+        let invalid = TradingConfig {
+            max_pending_transactions: 0,
+            ..TradingConfig::default()
+        };
+        assert!(invalid.validate().is_err());
     }
 
     #[tokio::test]
-    async fn test_get_status_without_wallets() {
+    async fn test_pending_capacity_without_database() {
+        // Without transaction tracking there is nothing to count
         let engine = create_test_engine();
-
-        // Should not panic even if wallets aren't available
-        let status = engine.get_status().await;
-
-        assert_eq!(status.state, TradingState::Disabled);
-        assert!(!status.enabled);
-        // Balances may be None if wallets aren't available
+        assert!(engine.ensure_pending_capacity().await.is_ok());
     }
 
-    // ===== Trading Logic Tests =====
-
     #[test]
-    fn test_xmr_amount_calculation_when_below_threshold() {
-        // Test that we calculate the correct amount of XMR needed
+    fn test_size_rebalance_exact_units() {
         let config = TradingConfig {
-            monero_min_threshold: 1.0,
-            monero_target_balance: 5.0,
             bitcoin_reserve_minimum: 0.01,
-            max_btc_per_rebalance: 0.1,
-            check_interval_secs: 300,
-            order_timeout_secs: 600,
+            max_btc_per_rebalance: 0.5,
             slippage_tolerance_percent: 1.0,
-            use_limit_orders: true,
+            ..TradingConfig::default()
         };
+        let balance = Btc::from_f64(1.0).unwrap();
 
-        // Current XMR: 0.5, Target: 5.0 -> Need 4.5 XMR
-        let current_xmr = 0.5;
-        let xmr_needed = config.monero_target_balance - current_xmr;
-        assert_eq!(xmr_needed, 4.5);
+        // 4 XMR at 0.02 BTC/XMR plus 1% slippage = 0.0808 BTC exactly
+        let btc = size_rebalance(Xmr::from_f64(4.0).unwrap(), 0.02, balance, &config).unwrap();
+        assert_eq!(btc, Some(Btc::from_sats(8_080_000)));
 
-        // Current XMR: 0.0, Target: 5.0 -> Need 5.0 XMR
-        let current_xmr = 0.0;
-        let xmr_needed = config.monero_target_balance - current_xmr;
-        assert_eq!(xmr_needed, 5.0);
+        // Capped at max_btc_per_rebalance
+        let btc = size_rebalance(Xmr::from_f64(99.0).unwrap(), 0.02, balance, &config).unwrap();
+        assert_eq!(btc, Some(Btc::from_sats(50_000_000)));
 
-        // Current XMR: 0.99, Target: 5.0 -> Need 4.01 XMR
-        let current_xmr = 0.99;
-        let xmr_needed = config.monero_target_balance - current_xmr;
-        assert!((xmr_needed - 4.01).abs() < 0.001);
+        // Not enough once the reserve is kept back
+        let low_balance = Btc::from_f64(0.05).unwrap();
+        assert!(size_rebalance(Xmr::from_f64(4.0).unwrap(), 0.02, low_balance, &config).is_err());
+
+        // Below the minimum trade size: hold rather than fail
+        let config = TradingConfig {
+            min_btc_per_rebalance: 0.1,
+            ..config
+        };
+        let btc = size_rebalance(Xmr::from_f64(4.0).unwrap(), 0.02, balance, &config).unwrap();
+        assert_eq!(btc, None);
+        assert_eq!(
+            decide_rebalance(balance, Xmr::from_f64(0.5).unwrap(), 0.02, &config),
+            RebalanceDecision::Hold
+        );
     }
 
     #[test]
-    fn test_should_trade_when_below_threshold() {
-        let config = TradingConfig {
-            monero_min_threshold: 1.0,
-            monero_target_balance: 5.0,
+    fn test_decide_rebalance_follows_candidate_config() {
+        let btc_balance = Btc::from_f64(1.0).unwrap();
+        let xmr_balance = Xmr::from_f64(0.5).unwrap();
+        let base = TradingConfig {
+            max_btc_per_rebalance: 0.5,
+            slippage_tolerance_percent: 0.0,
             ..TradingConfig::default()
         };
 
-        // Should trade when below threshold
-        let current_xmr = 0.5;
-        assert!(current_xmr < config.monero_min_threshold);
+        // Target 5: 4.5 XMR at 0.02 BTC/XMR
+        let decision = decide_rebalance(btc_balance, xmr_balance, 0.02, &base);
+        assert_eq!(
+            decision,
+            RebalanceDecision::Rebalance {
+                xmr_needed: Xmr::from_f64(4.5).unwrap(),
+                btc_to_spend: Btc::from_f64(0.09).unwrap(),
+            }
+        );
 
-        let current_xmr = 0.99;
-        assert!(current_xmr < config.monero_min_threshold);
+        // Target 10: same balances, twice the distance to cover
+        let higher_target = TradingConfig {
+            monero_target_balance: 10.0,
+            ..base.clone()
+        };
+        let decision = decide_rebalance(btc_balance, xmr_balance, 0.02, &higher_target);
+        assert_eq!(
+            decision,
+            RebalanceDecision::Rebalance {
+                xmr_needed: Xmr::from_f64(9.5).unwrap(),
+                btc_to_spend: Btc::from_f64(0.19).unwrap(),
+            }
+        );
 
-        let current_xmr = 0.0;
-        assert!(current_xmr < config.monero_min_threshold);
-    }
+        // A lower threshold means no trade at all
+        let lower_threshold = TradingConfig {
+            monero_min_threshold: 0.25,
+            ..base.clone()
+        };
+        assert_eq!(
+            decide_rebalance(btc_balance, xmr_balance, 0.02, &lower_threshold),
+            RebalanceDecision::Hold
+        );
 
-    #[test]
-    fn test_should_not_trade_when_above_threshold() {
-        let config = TradingConfig {
-            monero_min_threshold: 1.0,
-            monero_target_balance: 5.0,
-            ..TradingConfig::default()
+        // A larger reserve blocks the trade and says why
+        let big_reserve = TradingConfig {
+            bitcoin_reserve_minimum: 0.95,
+            ..base
         };
+        match decide_rebalance(btc_balance, xmr_balance, 0.02, &big_reserve) {
+            RebalanceDecision::Blocked { xmr_needed, reason } => {
+                assert_eq!(xmr_needed, Xmr::from_f64(4.5).unwrap());
+                assert!(reason.contains("Insufficient BTC"));
+            }
+            other => panic!("expected a blocked decision, got {:?}", other),
+        }
+    }
 
-        // Should NOT trade when at or above threshold
-        let current_xmr = 1.0;
-        assert!(current_xmr >= config.monero_min_threshold);
+    #[tokio::test]
+    async fn test_emergency_conversion_requires_confirmation() {
+        let engine = create_test_engine();
+        engine.enable();
+
+        let err = engine
+            .start_emergency_conversion(ConversionDirection::XmrToBtc, "yes please")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("CONVERT ALL XMR TO BTC"));
+
+        // Rejected before anything changed
+        assert!(engine.is_enabled());
+        assert!(!engine.emergency_active.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_emergency_conversion_runs_once_at_a_time() {
+        let engine = create_test_engine();
+        engine.emergency_active.store(true, Ordering::SeqCst);
+
+        let err = engine
+            .start_emergency_conversion(
+                ConversionDirection::XmrToBtc,
+                ConversionDirection::XmrToBtc.confirmation_phrase(),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("already in progress"));
+
+        // The in-flight conversion still owns the flag
+        assert!(engine.emergency_active.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_emergency_conversion_releases_guard_when_sizing_fails() {
+        // No wallets are reachable, so sizing fails before any trade leg runs
+        let engine = create_test_engine();
+        engine.enable();
+
+        let result = engine
+            .start_emergency_conversion(
+                ConversionDirection::BtcToXmr,
+                ConversionDirection::BtcToXmr.confirmation_phrase(),
+            )
+            .await;
+        assert!(result.is_err());
+
+        assert!(!engine.emergency_active.load(Ordering::SeqCst));
+        assert!(engine.is_enabled());
+        assert_eq!(engine.get_state(), TradingState::Monitoring);
+    }
+
+    /// Txid the fake bitcoind gives the emergency deposit
+    const DEPOSIT_TXID: &str = "emergency-deposit";
+
+    /// Exchange that fills every order and settles every transfer at once,
+    /// counting the orders and withdrawals it is asked for
+    #[derive(Clone, Default)]
+    struct ConversionExchange {
+        orders: Arc<std::sync::atomic::AtomicUsize>,
+        withdrawals: Arc<std::sync::Mutex<Vec<Asset>>>,
+    }
+
+    impl Exchange for ConversionExchange {
+        fn name(&self) -> &'static str {
+            "Test exchange"
+        }
+
+        async fn ticker(&self) -> Result<crate::services::exchange::Ticker> {
+            Ok(crate::services::exchange::Ticker {
+                bid: 0.0049,
+                ask: 0.0051,
+                last: 0.005,
+            })
+        }
+
+        async fn balances(&self) -> Result<crate::services::exchange::ExchangeBalances> {
+            anyhow::bail!("not used")
+        }
+
+        async fn volume_decimals(&self) -> Result<u32> {
+            Ok(8)
+        }
+
+        async fn deposit_address(&self, _asset: Asset, _new: bool) -> Result<String> {
+            Ok("bc1qexchange".to_string())
+        }
+
+        async fn submit_order(&self, _order: &OrderRequest) -> Result<String> {
+            self.orders.fetch_add(1, Ordering::SeqCst);
+            Ok("order123".to_string())
+        }
+
+        async fn order_status(&self, _order_id: &str) -> Result<Option<OrderReport>> {
+            Ok(Some(OrderReport {
+                state: OrderState::Closed,
+                executed: "2".to_string(),
+                cost: "0.01".to_string(),
+                price: "0.005".to_string(),
+                fee: String::new(),
+            }))
+        }
 
-        let current_xmr = 1.5;
-        assert!(current_xmr >= config.monero_min_threshold);
+        async fn cancel_order(&self, _order_id: &str) -> Result<bool> {
+            anyhow::bail!("not used")
+        }
 
-        let current_xmr = 5.0;
-        assert!(current_xmr >= config.monero_min_threshold);
+        async fn cancel_open_orders(&self) -> Result<u64> {
+            anyhow::bail!("not used")
+        }
 
-        let current_xmr = 10.0;
-        assert!(current_xmr >= config.monero_min_threshold);
-    }
+        async fn withdraw_to(&self, asset: Asset, _amount: &str, _address: &str) -> Result<String> {
+            self.withdrawals.lock().unwrap().push(asset);
+            Ok("withdrawal123".to_string())
+        }
 
-    #[test]
-    fn test_btc_calculation_with_slippage() {
-        let config = TradingConfig {
-            monero_min_threshold: 1.0,
-            monero_target_balance: 5.0,
-            bitcoin_reserve_minimum: 0.01,
-            max_btc_per_rebalance: 0.5,
-            slippage_tolerance_percent: 1.0,
-            ..TradingConfig::default()
-        };
+        async fn deposits(&self, _asset: Asset) -> Result<Vec<crate::services::exchange::Deposit>> {
+            Ok(vec![crate::services::exchange::Deposit {
+                refid: "deposit123".to_string(),
+                txid: DEPOSIT_TXID.to_string(),
+                amount: "0.01".to_string(),
+                time: 0,
+                state: TransferState::Succeeded,
+            }])
+        }
 
-        // Need 4 XMR, rate is 1 XMR = 0.02 BTC (50 XMR per BTC)
-        let xmr_needed = 4.0;
-        let btc_xmr_price = 0.02; // 1 XMR costs 0.02 BTC
+        async fn withdrawals(
+            &self,
+            _asset: Asset,
+        ) -> Result<Vec<crate::services::exchange::Withdrawal>> {
+            Ok(vec![crate::services::exchange::Withdrawal {
+                refid: "withdrawal123".to_string(),
+                txid: "withdrawal-txid".to_string(),
+                state: TransferState::Succeeded,
+                fee: String::new(),
+            }])
+        }
 
-        let slippage_multiplier = 1.0 + (config.slippage_tolerance_percent / 100.0);
-        let btc_needed = xmr_needed * btc_xmr_price * slippage_multiplier;
+        async fn trading_fee_percent(&self) -> Result<f64> {
+            anyhow::bail!("not used")
+        }
 
-        // Expected: 4 * 0.02 * 1.01 = 0.0808
-        assert!((btc_needed - 0.0808).abs() < 0.0001);
+        async fn withdrawal_fee(&self, _asset: Asset, _amount: &str) -> Result<f64> {
+            anyhow::bail!("not used")
+        }
     }
 
-    #[test]
-    fn test_btc_capped_at_max_per_rebalance() {
-        let config = TradingConfig {
-            monero_min_threshold: 1.0,
-            monero_target_balance: 100.0, // Very high target
-            bitcoin_reserve_minimum: 0.01,
-            max_btc_per_rebalance: 0.1, // Max 0.1 BTC per trade
-            slippage_tolerance_percent: 1.0,
-            ..TradingConfig::default()
-        };
+    /// Serve a JSON-RPC endpoint answering each method with `reply`, returning its URL
+    async fn serve_json_rpc(
+        reply: impl Fn(&str) -> serde_json::Value + Clone + Send + Sync + 'static,
+    ) -> String {
+        // bitcoind is called with a text/plain body, so it is parsed by hand
+        let app = axum::Router::new().fallback(move |body: String| {
+            let reply = reply.clone();
+            async move {
+                let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+                let method = request["method"].as_str().unwrap_or_default();
+                axum::Json(serde_json::json!({ "result": reply(method), "error": null }))
+            }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
 
-        // Need 99 XMR, rate is 1 XMR = 0.02 BTC
-        let xmr_needed = 99.0;
-        let btc_xmr_price = 0.02;
+    #[tokio::test]
+    async fn test_get_status_reads_the_monero_balance_once() {
+        // Counting connections, each of which reads the balance
+        let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counted = connections.clone();
+        let monero_wallet = serve_json_rpc(move |method| match method {
+            "open_wallet" => {
+                counted.fetch_add(1, Ordering::SeqCst);
+                serde_json::json!({})
+            }
+            "get_balance" => serde_json::json!({
+                "balance": 3_000_000_000_000u64,
+                "unlocked_balance": 2_000_000_000_000u64
+            }),
+            other => panic!("unexpected monero-wallet-rpc call {other}"),
+        })
+        .await;
+        let engine = TradingEngine::new(
+            SharedTradingConfig::new(TradingConfig::default()),
+            ConversionExchange::default(),
+            "http://localhost:8332".to_string(),
+            "/tmp/cookie".to_string(),
+            "test_wallet".to_string(),
+            format!("{}/json_rpc", monero_wallet),
+            "test_xmr_wallet".to_string(),
+            "".to_string(),
+        );
 
-        let slippage_multiplier = 1.0 + (config.slippage_tolerance_percent / 100.0);
-        let btc_needed = xmr_needed * btc_xmr_price * slippage_multiplier;
+        let status = engine.get_status().await;
 
-        // Would need 1.9998 BTC, but capped at max_btc_per_rebalance
-        let btc_to_use = btc_needed.min(config.max_btc_per_rebalance);
-        assert_eq!(btc_to_use, 0.1);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+        assert_eq!(status.current_xmr_balance, Some(2.0));
+        assert_eq!(status.current_xmr_total_balance, Some(3.0));
+        assert_eq!(status.current_xmr_locked_balance, Some(1.0));
     }
 
-    #[test]
-    fn test_insufficient_btc_calculation() {
-        let config = TradingConfig {
-            monero_min_threshold: 1.0,
-            monero_target_balance: 5.0,
-            bitcoin_reserve_minimum: 0.05, // Keep 0.05 BTC as reserve
-            max_btc_per_rebalance: 0.5,
-            slippage_tolerance_percent: 1.0,
-            ..TradingConfig::default()
-        };
+    #[tokio::test]
+    async fn test_emergency_legs_run_once_and_not_on_the_next_cycle() {
+        // Fake bitcoind holding 0.5 BTC, counting the deposits it sends
+        let sends = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let sent = sends.clone();
+        let bitcoind = serve_json_rpc(move |method| match method {
+            "loadwallet" => serde_json::json!({ "name": "test_wallet" }),
+            "getbalances" => serde_json::json!({
+                "mine": { "trusted": 0.5, "untrusted_pending": 0.0, "immature": 0.0 }
+            }),
+            "validateaddress" => serde_json::json!({ "isvalid": true }),
+            "sendtoaddress" => {
+                sent.fetch_add(1, Ordering::SeqCst);
+                serde_json::json!(DEPOSIT_TXID)
+            }
+            "gettransaction" => serde_json::json!({
+                "amount": -0.01, "confirmations": 0, "txid": DEPOSIT_TXID,
+                "time": 0, "fee": -0.0001
+            }),
+            other => panic!("unexpected bitcoind call {other}"),
+        })
+        .await;
+        // Fake monero-wallet-rpc with no XMR, so a normal rebalance would trade
+        let monero_wallet = serve_json_rpc(|method| match method {
+            "open_wallet" | "close_wallet" => serde_json::json!({}),
+            "get_balance" => serde_json::json!({ "balance": 0, "unlocked_balance": 0 }),
+            "get_address" => serde_json::json!({ "address": "4our-wallet" }),
+            other => panic!("unexpected monero-wallet-rpc call {other}"),
+        })
+        .await;
+        let cookie =
+            std::env::temp_dir().join(format!("eigenix-emergency-cookie-{}", std::process::id()));
+        std::fs::write(&cookie, "user:pass").unwrap();
+
+        let exchange = ConversionExchange::default();
+        let shutdown = Shutdown::new();
+        let engine = TradingEngine::new(
+            SharedTradingConfig::new(TradingConfig::default()),
+            exchange.clone(),
+            bitcoind,
+            cookie.to_string_lossy().into_owned(),
+            "test_wallet".to_string(),
+            format!("{}/json_rpc", monero_wallet),
+            "test_xmr_wallet".to_string(),
+            "".to_string(),
+        )
+        .with_shutdown(shutdown.clone());
+        engine.enable();
 
-        // Current BTC balance: 0.1
-        let btc_balance = 0.1;
-        let btc_available = btc_balance - config.bitcoin_reserve_minimum;
-        // Available: 0.1 - 0.05 = 0.05 BTC
-        assert_eq!(btc_available, 0.05);
+        let plan = engine
+            .start_emergency_conversion(
+                ConversionDirection::BtcToXmr,
+                ConversionDirection::BtcToXmr.confirmation_phrase(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(plan.btc_amount, btc_amount(0.01).unwrap());
 
-        // If we need 0.08 BTC, we don't have enough
-        let btc_needed = 0.08;
-        assert!(btc_available < btc_needed);
-    }
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while engine.emergency_active.load(Ordering::SeqCst) {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the conversion should finish");
+        let _ = std::fs::remove_file(&cookie);
 
-    #[test]
-    fn test_sufficient_btc_calculation() {
-        let config = TradingConfig {
-            monero_min_threshold: 1.0,
-            monero_target_balance: 5.0,
-            bitcoin_reserve_minimum: 0.01,
-            max_btc_per_rebalance: 0.5,
-            slippage_tolerance_percent: 1.0,
-            ..TradingConfig::default()
-        };
+        // Each leg ran once, and the engine was left disabled
+        assert_eq!(sends.load(Ordering::SeqCst), 1);
+        assert_eq!(exchange.orders.load(Ordering::SeqCst), 1);
+        assert_eq!(*exchange.withdrawals.lock().unwrap(), vec![Asset::Xmr]);
+        assert_eq!(engine.get_state(), TradingState::Disabled);
+        assert!(!engine.is_enabled());
 
-        // Current BTC balance: 0.5
-        let btc_balance = 0.5;
-        let btc_available = btc_balance - config.bitcoin_reserve_minimum;
-        // Available: 0.5 - 0.01 = 0.49 BTC
-        assert_eq!(btc_available, 0.49);
+        // The trading loop's next cycle doesn't trade again
+        let running = tokio::spawn(engine.clone().run());
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        shutdown.trigger();
+        tokio::time::timeout(std::time::Duration::from_secs(1), running)
+            .await
+            .expect("engine should stop on shutdown")
+            .unwrap();
 
-        // If we need 0.08 BTC, we have enough
-        let btc_needed = 0.08;
-        assert!(btc_available >= btc_needed);
+        assert_eq!(sends.load(Ordering::SeqCst), 1);
+        assert_eq!(exchange.orders.load(Ordering::SeqCst), 1);
+        assert_eq!(exchange.withdrawals.lock().unwrap().len(), 1);
+        assert_eq!(engine.get_state(), TradingState::Disabled);
     }
 
     #[test]
-    fn test_threshold_boundary_conditions() {
-        let config = TradingConfig {
-            monero_min_threshold: 1.0,
-            monero_target_balance: 5.0,
-            ..TradingConfig::default()
-        };
+    fn test_round_down_to_lot() {
+        let available = "0.12345679".parse::<Btc>().unwrap();
+
+        // Off-step volume goes down to the step, never up
+        let (volume, remainder) = round_down_to_lot(available, 5).unwrap();
+        assert_eq!(volume, "0.12345".parse().unwrap());
+        assert_eq!(remainder, "0.00000679".parse().unwrap());
+        assert!(volume <= available);
+        assert_eq!(volume.checked_add(remainder), Some(available));
+
+        // Already on the step
+        let on_step = "0.5".parse::<Btc>().unwrap();
+        assert_eq!(round_down_to_lot(on_step, 5).unwrap(), (on_step, Btc::ZERO));
+
+        // Less than one step can't be traded
+        assert!(round_down_to_lot("0.000001".parse().unwrap(), 5).is_err());
+    }
 
-        // Exactly at threshold - should NOT trade
-        let current_xmr = 1.0;
-        assert!(current_xmr >= config.monero_min_threshold);
+    #[test]
+    fn test_unexplained_holdings_drop_halts_engine() {
+        let engine = create_test_engine();
+        engine.enable();
+        let now = Utc::now();
 
-        // Just below threshold - SHOULD trade
-        let current_xmr = 0.999999;
-        assert!(current_xmr < config.monero_min_threshold);
+        // The first check only sets the baseline
+        let baseline = HoldingsSnapshot::new(1.0, 100.0, 0.005, now);
+        assert!(engine.observe_holdings(baseline, false, 20.0).is_none());
 
-        // Just above threshold - should NOT trade
-        let current_xmr = 1.000001;
-        assert!(current_xmr >= config.monero_min_threshold);
+        // Small moves are tolerated
+        let dip = HoldingsSnapshot::new(0.95, 100.0, 0.005, now);
+        assert!(engine.observe_holdings(dip, false, 20.0).is_none());
+        assert!(engine.is_enabled());
+
+        // Most of the XMR vanishes with nothing recorded: 1.45 -> 1.0 BTC
+        let drained = HoldingsSnapshot::new(0.95, 10.0, 0.005, now);
+        let alert = engine
+            .observe_holdings(drained, false, 20.0)
+            .expect("an unexplained drop should alert");
+
+        assert_eq!(alert.kind, "holdings_drop");
+        assert_eq!(alert.severity, AlertSeverity::Critical);
+        assert!(!engine.is_enabled());
+        assert!(matches!(engine.get_state(), TradingState::Error { .. }));
     }
 
     #[test]
-    fn test_different_slippage_tolerances() {
-        // Test 0% slippage
-        let slippage_percent = 0.0;
-        let slippage_multiplier = 1.0 + (slippage_percent / 100.0);
-        assert_eq!(slippage_multiplier, 1.0);
+    fn test_explained_holdings_drop_keeps_trading() {
+        let engine = create_test_engine();
+        engine.enable();
+        let now = Utc::now();
 
-        // Test 1% slippage
-        let slippage_percent = 1.0;
-        let slippage_multiplier = 1.0 + (slippage_percent / 100.0);
-        assert_eq!(slippage_multiplier, 1.01);
+        engine.observe_holdings(HoldingsSnapshot::new(1.0, 0.0, 0.005, now), false, 20.0);
 
-        // Test 5% slippage
-        let slippage_percent = 5.0;
-        let slippage_multiplier = 1.0 + (slippage_percent / 100.0);
-        assert_eq!(slippage_multiplier, 1.05);
+        // A deposit in flight to the exchange: recorded, so not a halt
+        let in_flight = HoldingsSnapshot::new(0.5, 0.0, 0.005, now);
+        assert!(engine.observe_holdings(in_flight, true, 20.0).is_none());
+        assert!(engine.is_enabled());
 
-        // Test 10% slippage
-        let slippage_percent = 10.0;
-        let slippage_multiplier = 1.0 + (slippage_percent / 100.0);
-        assert_eq!(slippage_multiplier, 1.10);
+        // The lower figure is the new baseline
+        let same = HoldingsSnapshot::new(0.5, 0.0, 0.005, now);
+        assert!(engine.observe_holdings(same, false, 20.0).is_none());
     }
 
-    #[test]
-    fn test_realistic_trading_scenario_low_xmr() {
-        // Scenario: User has received lots of BTC, XMR is low
-        let config = TradingConfig {
-            monero_min_threshold: 10.0,
-            monero_target_balance: 50.0,
-            bitcoin_reserve_minimum: 0.1,
-            max_btc_per_rebalance: 1.0,
-            slippage_tolerance_percent: 1.0,
-            ..TradingConfig::default()
+    #[tokio::test]
+    async fn test_invalid_deposit_address_is_replaced() {
+        let requests = std::sync::Mutex::new(Vec::new());
+        let fetch = |new: bool| {
+            requests.lock().unwrap().push(new);
+            let address = if new { "bc1qfresh" } else { "bc1qexpired" };
+            async move { Ok(address.to_string()) }
         };
+        let is_valid = |address: String| async move { Ok(address == "bc1qfresh") };
 
-        let current_btc = 5.0;
-        let current_xmr = 2.0; // Below threshold!
-        let btc_xmr_price = 0.02; // 1 XMR = 0.02 BTC (50 XMR per BTC)
-
-        // Should trade
-        assert!(current_xmr < config.monero_min_threshold);
+        let (address, rejected) = valid_deposit_address(fetch, is_valid).await.unwrap();
 
-        // Calculate how much XMR needed
-        let xmr_needed = config.monero_target_balance - current_xmr;
-        assert_eq!(xmr_needed, 48.0);
+        assert_eq!(address, "bc1qfresh");
+        assert_eq!(rejected, vec!["bc1qexpired".to_string()]);
+        // The retry asked the exchange for a new address rather than the same one
+        assert_eq!(*requests.lock().unwrap(), vec![false, true]);
+    }
 
-        // Calculate BTC needed with slippage
-        let slippage_multiplier = 1.0 + (config.slippage_tolerance_percent / 100.0);
-        let btc_needed = xmr_needed * btc_xmr_price * slippage_multiplier;
-        // 48 * 0.02 * 1.01 = 0.9696
-        assert!((btc_needed - 0.9696).abs() < 0.0001);
+    #[tokio::test]
+    async fn test_deposit_address_retries_are_bounded() {
+        let fetches = std::sync::atomic::AtomicUsize::new(0);
+        let fetch = |_new: bool| {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            async { Ok("not-an-address".to_string()) }
+        };
 
-        // Cap at max per rebalance
-        let btc_to_use = btc_needed.min(config.max_btc_per_rebalance);
-        assert_eq!(btc_to_use, 0.9696);
+        let result = valid_deposit_address(fetch, |_| async { Ok(false) }).await;
 
-        // Check if we have enough BTC
-        let btc_available = current_btc - config.bitcoin_reserve_minimum;
-        assert_eq!(btc_available, 4.9);
-        assert!(btc_available >= btc_to_use); // Yes, we have enough
+        assert!(result.is_err());
+        assert_eq!(fetches.load(Ordering::SeqCst), MAX_DEPOSIT_ADDRESS_ATTEMPTS);
     }
 
     #[test]
-    fn test_realistic_trading_scenario_sufficient_xmr() {
-        // Scenario: Balanced system, no trade needed
+    fn test_waits_for_locked_xmr_instead_of_rebalancing() {
         let config = TradingConfig {
             monero_min_threshold: 10.0,
-            monero_target_balance: 50.0,
-            bitcoin_reserve_minimum: 0.1,
-            max_btc_per_rebalance: 1.0,
-            slippage_tolerance_percent: 1.0,
+            monero_target_balance: 20.0,
+            wait_for_xmr_unlock: true,
             ..TradingConfig::default()
         };
+        let unlocked = Xmr::from_f64(4.0).unwrap();
+        let btc = Btc::from_f64(1.0).unwrap();
+
+        // On the unlocked balance alone a rebalance looks due
+        assert!(matches!(
+            decide_rebalance(btc, unlocked, 0.005, &config),
+            RebalanceDecision::Rebalance { .. }
+        ));
+
+        // A withdrawal that just landed covers the threshold once it unlocks
+        let total = Xmr::from_f64(12.5).unwrap();
+        assert_eq!(
+            pending_unlock(unlocked, total, &config).unwrap(),
+            Some(Xmr::from_f64(8.5).unwrap())
+        );
 
-        let _current_btc = 1.0;
-        let current_xmr = 45.0; // Above threshold!
+        // Not enough locked to matter: rebalance as usual
+        let total = Xmr::from_f64(9.0).unwrap();
+        assert_eq!(pending_unlock(unlocked, total, &config).unwrap(), None);
 
-        // Should NOT trade
-        assert!(current_xmr >= config.monero_min_threshold);
+        // Disabled by default
+        let config = TradingConfig {
+            wait_for_xmr_unlock: false,
+            ..config
+        };
+        let total = Xmr::from_f64(12.5).unwrap();
+        assert_eq!(pending_unlock(unlocked, total, &config).unwrap(), None);
     }
 
     #[test]
-    fn test_edge_case_zero_balances() {
+    fn test_decide_reverse_rebalance() {
         let config = TradingConfig {
-            monero_min_threshold: 1.0,
-            monero_target_balance: 5.0,
-            bitcoin_reserve_minimum: 0.01,
+            bitcoin_min_threshold: 0.05,
+            bitcoin_target_balance: 0.2,
+            monero_max_threshold: 20.0,
             max_btc_per_rebalance: 0.5,
-            slippage_tolerance_percent: 1.0,
+            slippage_tolerance_percent: 0.0,
             ..TradingConfig::default()
         };
+        let low_btc = Btc::from_f64(0.04).unwrap();
+
+        // 0.16 BTC short at 0.01 BTC/XMR: sell 16 of the 40 XMR
+        assert_eq!(
+            decide_rebalance(low_btc, Xmr::from_f64(40.0).unwrap(), 0.01, &config),
+            RebalanceDecision::SellXmr {
+                btc_needed: Btc::from_f64(0.16).unwrap(),
+                xmr_to_sell: Xmr::from_f64(16.0).unwrap(),
+            }
+        );
 
-        // Zero XMR - should trade
-        let current_xmr = 0.0;
-        assert!(current_xmr < config.monero_min_threshold);
-        let xmr_needed = config.monero_target_balance - current_xmr;
-        assert_eq!(xmr_needed, 5.0);
-
-        // Zero BTC - can't trade
-        let current_btc = 0.0;
-        let btc_available = current_btc - config.bitcoin_reserve_minimum;
-        assert!(btc_available < 0.0); // Not enough BTC
-    }
+        // Never sells below the ceiling, even if that leaves BTC short of target
+        assert_eq!(
+            decide_rebalance(low_btc, Xmr::from_f64(25.0).unwrap(), 0.01, &config),
+            RebalanceDecision::SellXmr {
+                btc_needed: Btc::from_f64(0.16).unwrap(),
+                xmr_to_sell: Xmr::from_f64(5.0).unwrap(),
+            }
+        );
 
-    #[test]
-    fn test_config_validation_for_trading_logic() {
-        // Valid config
-        let config = TradingConfig {
-            monero_min_threshold: 1.0,
-            monero_target_balance: 5.0,
-            bitcoin_reserve_minimum: 0.01,
-            max_btc_per_rebalance: 0.5,
-            check_interval_secs: 300,
-            order_timeout_secs: 600,
-            slippage_tolerance_percent: 1.0,
-            use_limit_orders: true,
-        };
-        assert!(config.validate().is_ok());
+        // XMR at or below the ceiling, or BTC above its threshold: nothing to do
+        assert_eq!(
+            decide_rebalance(low_btc, Xmr::from_f64(20.0).unwrap(), 0.01, &config),
+            RebalanceDecision::Hold
+        );
+        assert_eq!(
+            decide_rebalance(
+                Btc::from_f64(0.06).unwrap(),
+                Xmr::from_f64(30.0).unwrap(),
+                0.01,
+                &config
+            ),
+            RebalanceDecision::Hold
+        );
 
-        // Invalid: min_threshold >= target
-        let invalid_config = TradingConfig {
-            monero_min_threshold: 5.0,
-            monero_target_balance: 1.0,
+        // Proceeds under the minimum trade size wait for more XMR
+        let high_minimum = TradingConfig {
+            min_btc_per_rebalance: 0.1,
             ..config.clone()
         };
-        assert!(invalid_config.validate().is_err());
+        assert_eq!(
+            decide_rebalance(low_btc, Xmr::from_f64(25.0).unwrap(), 0.01, &high_minimum),
+            RebalanceDecision::Hold
+        );
 
-        // Invalid: negative values
-        let invalid_config = TradingConfig {
-            monero_min_threshold: -1.0,
-            ..config.clone()
-        };
-        assert!(invalid_config.validate().is_err());
+        // Disabled by default
+        assert_eq!(
+            decide_rebalance(
+                low_btc,
+                Xmr::from_f64(30.0).unwrap(),
+                0.01,
+                &TradingConfig::default()
+            ),
+            RebalanceDecision::Hold
+        );
     }
 }
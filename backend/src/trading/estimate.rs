@@ -0,0 +1,125 @@
+//! What a BTC->XMR rebalance costs before any funds move
+//!
+//! A rebalance pays three fees: the on-chain fee to send BTC to the exchange,
+//! the exchange's taker fee on the trade and its fee for withdrawing the XMR.
+//! Added up and set against the value traded, they give the spread the ASB
+//! has to earn on swaps just to pay for restocking the XMR it sold.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::amount::Xmr;
+
+/// Virtual size of the deposit transaction: one P2WPKH input, a payment and change
+pub const DEPOSIT_TX_VBYTES: f64 = 141.0;
+
+/// Blocks within which the BTC deposit should confirm: the medium fee estimate
+pub const DEPOSIT_CONF_TARGET: u16 = crate::services::bitcoin::MEDIUM_CONF_TARGET;
+
+/// Live prices and fees an estimate is built from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeQuotes {
+    pub btc_per_xmr: f64,
+    /// bitcoind's estimate for [`DEPOSIT_CONF_TARGET`], in BTC/kvB
+    pub bitcoin_feerate: f64,
+    pub trading_fee_percent: f64,
+    /// Charged in XMR
+    pub xmr_withdrawal_fee: f64,
+}
+
+/// Cost breakdown of buying `xmr_needed` XMR, all fees in BTC
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct CostEstimate {
+    pub exchange: String,
+    pub xmr_needed: Xmr,
+    pub btc_per_xmr: f64,
+    /// BTC value of the XMR at the current price, before fees
+    pub trade_value_btc: f64,
+    pub bitcoin_feerate_sat_vb: f64,
+    /// On-chain fee for sending the BTC to the exchange
+    pub deposit_fee_btc: f64,
+    pub trading_fee_percent: f64,
+    pub trading_fee_btc: f64,
+    /// XMR withdrawal fee, in XMR as the exchange charges it
+    pub xmr_withdrawal_fee: f64,
+    pub withdrawal_fee_btc: f64,
+    pub total_fee_btc: f64,
+    /// Total fees as a percentage of the trade value: the spread a swap
+    /// must earn for the rebalance behind it to break even
+    pub break_even_spread_percent: f64,
+}
+
+impl CostEstimate {
+    /// Break down the fees for buying `xmr_needed` XMR on `exchange`
+    pub fn new(exchange: &str, xmr_needed: Xmr, quotes: &FeeQuotes) -> Self {
+        let trade_value_btc = xmr_needed.to_f64() * quotes.btc_per_xmr;
+
+        // BTC/kvB -> sat/vB
+        let bitcoin_feerate_sat_vb = quotes.bitcoin_feerate * 1e5;
+        let deposit_fee_btc = bitcoin_feerate_sat_vb * DEPOSIT_TX_VBYTES / 1e8;
+        let trading_fee_btc = trade_value_btc * quotes.trading_fee_percent / 100.0;
+        let withdrawal_fee_btc = quotes.xmr_withdrawal_fee * quotes.btc_per_xmr;
+        let total_fee_btc = deposit_fee_btc + trading_fee_btc + withdrawal_fee_btc;
+
+        let break_even_spread_percent = if trade_value_btc > 0.0 {
+            total_fee_btc / trade_value_btc * 100.0
+        } else {
+            0.0
+        };
+
+        Self {
+            exchange: exchange.to_string(),
+            xmr_needed,
+            btc_per_xmr: quotes.btc_per_xmr,
+            trade_value_btc,
+            bitcoin_feerate_sat_vb,
+            deposit_fee_btc,
+            trading_fee_percent: quotes.trading_fee_percent,
+            trading_fee_btc,
+            xmr_withdrawal_fee: quotes.xmr_withdrawal_fee,
+            withdrawal_fee_btc,
+            total_fee_btc,
+            break_even_spread_percent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_breakdown() {
+        let quotes = FeeQuotes {
+            btc_per_xmr: 0.005,
+            // 10 sat/vB
+            bitcoin_feerate: 0.0001,
+            trading_fee_percent: 0.26,
+            xmr_withdrawal_fee: 0.0001,
+        };
+
+        let estimate = CostEstimate::new("Kraken", Xmr::from_f64(10.0).unwrap(), &quotes);
+
+        assert!((estimate.trade_value_btc - 0.05).abs() < 1e-12);
+        assert!((estimate.bitcoin_feerate_sat_vb - 10.0).abs() < 1e-9);
+        assert!((estimate.deposit_fee_btc - 0.0000141).abs() < 1e-12);
+        assert!((estimate.trading_fee_btc - 0.00013).abs() < 1e-12);
+        assert!((estimate.withdrawal_fee_btc - 0.0000005).abs() < 1e-12);
+        assert!((estimate.total_fee_btc - 0.0001446).abs() < 1e-12);
+        assert!((estimate.break_even_spread_percent - 0.2892).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_zero_amount_has_no_spread() {
+        let quotes = FeeQuotes {
+            btc_per_xmr: 0.005,
+            bitcoin_feerate: 0.0001,
+            trading_fee_percent: 0.26,
+            xmr_withdrawal_fee: 0.0001,
+        };
+
+        let estimate = CostEstimate::new("Kraken", Xmr::ZERO, &quotes);
+        assert_eq!(estimate.break_even_spread_percent, 0.0);
+        assert!(estimate.total_fee_btc > 0.0);
+    }
+}
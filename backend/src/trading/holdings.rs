@@ -0,0 +1,100 @@
+//! Guard against unexplained drops in total holdings
+//!
+//! Each trading check records everything the operator holds — both wallets
+//! and the Kraken account — along with the XMR price. A large drop between two
+//! checks with no recorded transaction to account for it points at theft or a
+//! bug, so the engine halts rather than keep moving funds. Both checks are
+//! valued at the later price, so a falling XMR price alone is not a drop.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// BTC and XMR held at one check
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct HoldingsSnapshot {
+    pub btc: f64,
+    pub xmr: f64,
+    /// BTC per XMR at the check
+    pub btc_per_xmr: f64,
+    pub at: DateTime<Utc>,
+}
+
+impl HoldingsSnapshot {
+    pub fn new(btc: f64, xmr: f64, btc_per_xmr: f64, at: DateTime<Utc>) -> Self {
+        Self {
+            btc,
+            xmr,
+            btc_per_xmr,
+            at,
+        }
+    }
+
+    /// Holdings valued in BTC at `btc_per_xmr`
+    pub fn total_btc_at(&self, btc_per_xmr: f64) -> f64 {
+        self.btc + self.xmr * btc_per_xmr
+    }
+
+    /// Holdings valued in BTC at the price of this check
+    pub fn total_btc(&self) -> f64 {
+        self.total_btc_at(self.btc_per_xmr)
+    }
+
+    /// Percentage drop from `previous`, valued at this check's price, if
+    /// larger than `max_drop_percent`
+    ///
+    /// A `max_drop_percent` of zero disables the check.
+    pub fn drop_exceeding(&self, previous: &Self, max_drop_percent: f64) -> Option<f64> {
+        let before = previous.total_btc_at(self.btc_per_xmr);
+        if max_drop_percent <= 0.0 || before <= 0.0 {
+            return None;
+        }
+
+        let drop_percent = (before - self.total_btc()) / before * 100.0;
+        (drop_percent > max_drop_percent).then_some(drop_percent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_in_btc() {
+        let snapshot = HoldingsSnapshot::new(0.5, 10.0, 0.005, Utc::now());
+        assert!((snapshot.total_btc() - 0.55).abs() < 1e-12);
+        assert!((snapshot.total_btc_at(0.004) - 0.54).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_drop_exceeding_threshold() {
+        let now = Utc::now();
+        let previous = HoldingsSnapshot::new(1.0, 0.0, 0.005, now);
+
+        let small = HoldingsSnapshot::new(0.9, 0.0, 0.005, now);
+        assert_eq!(small.drop_exceeding(&previous, 20.0), None);
+
+        let large = HoldingsSnapshot::new(0.5, 0.0, 0.005, now);
+        let drop = large.drop_exceeding(&previous, 20.0).unwrap();
+        assert!((drop - 50.0).abs() < 1e-9);
+
+        // Increases and a disabled guard never trip
+        let up = HoldingsSnapshot::new(2.0, 0.0, 0.005, now);
+        assert_eq!(up.drop_exceeding(&previous, 20.0), None);
+        assert_eq!(large.drop_exceeding(&previous, 0.0), None);
+    }
+
+    #[test]
+    fn test_price_drop_alone_is_not_a_drop() {
+        let now = Utc::now();
+        let previous = HoldingsSnapshot::new(0.1, 100.0, 0.005, now);
+
+        // XMR halves in price with the same coins held
+        let repriced = HoldingsSnapshot::new(0.1, 100.0, 0.0025, now);
+        assert_eq!(repriced.drop_exceeding(&previous, 20.0), None);
+
+        // Coins missing still count, at the new price
+        let drained = HoldingsSnapshot::new(0.1, 50.0, 0.0025, now);
+        let drop = drained.drop_exceeding(&previous, 20.0).unwrap();
+        assert!((drop - 0.125 / 0.35 * 100.0).abs() < 1e-9);
+    }
+}
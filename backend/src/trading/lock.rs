@@ -0,0 +1,306 @@
+//! Single-instance lock for the trading engine
+//!
+//! Two backends sharing the same wallets and Kraken key would both rebalance.
+//! Before its loop starts the engine takes a lease on the `trading_lock:current`
+//! row and renews it in the background; a second instance that finds a live
+//! lease held by someone else does not trade. A lease that isn't renewed
+//! expires, so a crashed instance only blocks others for one lease period.
+//! Taking the lease is a single conditional write, so two instances starting
+//! at once can't both get it.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use utoipa::ToSchema;
+
+use crate::db::MetricsDatabase;
+
+/// Lease on the trading lock
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TradingLease {
+    /// Instance holding the lease
+    pub holder: String,
+    pub acquired_at: DateTime<Utc>,
+    /// The lease is free for others to take from this point on
+    pub expires_at: DateTime<Utc>,
+}
+
+impl TradingLease {
+    /// Whether the lease keeps `holder` out at `now`
+    pub fn blocks(&self, holder: &str, now: DateTime<Utc>) -> bool {
+        self.holder != holder && self.expires_at > now
+    }
+}
+
+/// The trading lock as seen from one instance, for `GET /trading/lock`
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct LockStatus {
+    /// Whether this instance takes the lock before trading
+    pub enabled: bool,
+    /// Name this instance takes the lease under
+    pub instance: Option<String>,
+    /// The stored lease, which may have expired
+    pub lease: Option<TradingLease>,
+    /// Whether the lease is live, keeping every other instance out
+    pub held: bool,
+    /// Whether the live lease is ours
+    pub held_by_this_instance: bool,
+}
+
+impl LockStatus {
+    /// Status of `lease` for the instance holding `lock`, if it has one
+    pub fn new<S>(
+        lock: Option<&TradingLock<S>>,
+        lease: Option<TradingLease>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let held = lease.as_ref().is_some_and(|lease| lease.expires_at > now);
+        let instance = lock.map(|lock| lock.holder.clone());
+        Self {
+            enabled: lock.is_some(),
+            held_by_this_instance: held
+                && lease.as_ref().map(|lease| &lease.holder) == instance.as_ref(),
+            instance,
+            lease,
+            held,
+        }
+    }
+}
+
+/// Where the current lease is kept
+pub trait LeaseStore: Clone + Send + Sync + 'static {
+    fn load(&self) -> impl Future<Output = Result<Option<TradingLease>>> + Send;
+    /// Atomically store `lease` unless another holder's lease is live at
+    /// `now`, keeping `acquired_at` on renewal; `None` if it was refused
+    fn take(
+        &self,
+        lease: &TradingLease,
+        now: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Option<TradingLease>>> + Send;
+    /// Remove the lease if `holder` holds it
+    fn clear(&self, holder: &str) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl LeaseStore for MetricsDatabase {
+    async fn load(&self) -> Result<Option<TradingLease>> {
+        self.get_trading_lock().await
+    }
+
+    async fn take(&self, lease: &TradingLease, now: DateTime<Utc>) -> Result<Option<TradingLease>> {
+        self.take_trading_lock(lease, now).await
+    }
+
+    async fn clear(&self, holder: &str) -> Result<()> {
+        self.release_trading_lock(holder).await
+    }
+}
+
+/// Handle on the trading lock for one instance
+#[derive(Clone)]
+pub struct TradingLock<S = MetricsDatabase> {
+    store: S,
+    holder: String,
+    lease: Duration,
+}
+
+impl<S: LeaseStore> TradingLock<S> {
+    pub fn new(store: S, holder: impl Into<String>, lease: std::time::Duration) -> Self {
+        Self {
+            store,
+            holder: holder.into(),
+            lease: Duration::from_std(lease).unwrap_or(Duration::MAX),
+        }
+    }
+
+    pub fn holder(&self) -> &str {
+        &self.holder
+    }
+
+    /// How often the lease should be renewed to stay ahead of its expiry
+    pub fn renew_interval(&self) -> std::time::Duration {
+        (self.lease / 3)
+            .to_std()
+            .unwrap_or_default()
+            .max(std::time::Duration::from_secs(1))
+    }
+
+    /// Take the lease, or extend it if we already hold it
+    ///
+    /// Fails if another instance holds a lease that hasn't expired.
+    pub async fn acquire(&self) -> Result<TradingLease> {
+        let now = Utc::now();
+        let lease = TradingLease {
+            holder: self.holder.clone(),
+            acquired_at: now,
+            expires_at: now + self.lease,
+        };
+        if let Some(stored) = self.store.take(&lease, now).await? {
+            return Ok(stored);
+        }
+
+        match self.store.load().await? {
+            Some(current) => anyhow::bail!(
+                "Trading lock is held by {} until {}",
+                current.holder,
+                current.expires_at.to_rfc3339()
+            ),
+            None => anyhow::bail!("Trading lock was taken by another instance"),
+        }
+    }
+
+    /// Give up the lease if we hold it
+    pub async fn release(&self) -> Result<()> {
+        self.store.clear(&self.holder).await
+    }
+}
+
+/// Default instance name: host name and process id
+pub fn default_holder() -> String {
+    let host = std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "eigenix".to_string());
+
+    format!("{}:{}", host, std::process::id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Lease store shared by every lock created from it
+    #[derive(Clone, Default)]
+    struct MemoryStore(Arc<Mutex<Option<TradingLease>>>);
+
+    impl LeaseStore for MemoryStore {
+        async fn load(&self) -> Result<Option<TradingLease>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        async fn take(
+            &self,
+            lease: &TradingLease,
+            now: DateTime<Utc>,
+        ) -> Result<Option<TradingLease>> {
+            let mut current = self.0.lock().unwrap();
+            if current
+                .as_ref()
+                .is_some_and(|c| c.blocks(&lease.holder, now))
+            {
+                return Ok(None);
+            }
+
+            let acquired_at = current
+                .as_ref()
+                .filter(|c| c.holder == lease.holder)
+                .map_or(lease.acquired_at, |c| c.acquired_at);
+            let stored = TradingLease {
+                acquired_at,
+                ..lease.clone()
+            };
+            *current = Some(stored.clone());
+            Ok(Some(stored))
+        }
+
+        async fn clear(&self, holder: &str) -> Result<()> {
+            let mut current = self.0.lock().unwrap();
+            if current.as_ref().is_some_and(|c| c.holder == holder) {
+                *current = None;
+            }
+            Ok(())
+        }
+    }
+
+    fn lock(store: &MemoryStore, holder: &str, lease_secs: u64) -> TradingLock<MemoryStore> {
+        TradingLock::new(
+            store.clone(),
+            holder,
+            std::time::Duration::from_secs(lease_secs),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_second_instance_denied_while_lease_is_fresh() {
+        let store = MemoryStore::default();
+        let first = lock(&store, "host-a:100", 60);
+        let second = lock(&store, "host-b:200", 60);
+
+        let lease = first.acquire().await.unwrap();
+        assert_eq!(lease.holder, "host-a:100");
+
+        let err = second.acquire().await.unwrap_err();
+        assert!(err.to_string().contains("host-a:100"));
+
+        // Renewing keeps the original acquisition time
+        let renewed = first.acquire().await.unwrap();
+        assert_eq!(renewed.acquired_at, lease.acquired_at);
+        assert!(renewed.expires_at >= lease.expires_at);
+
+        // Once released the other instance can take over
+        second.release().await.unwrap();
+        assert!(second.acquire().await.is_err());
+        first.release().await.unwrap();
+        assert_eq!(second.acquire().await.unwrap().holder, "host-b:200");
+    }
+
+    #[tokio::test]
+    async fn test_expired_lease_can_be_taken_over() {
+        let store = MemoryStore::default();
+        let crashed = lock(&store, "host-a:100", 0);
+        crashed.acquire().await.unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let lease = lock(&store, "host-b:200", 60).acquire().await.unwrap();
+        assert_eq!(lease.holder, "host-b:200");
+
+        // The old holder is now the one locked out
+        assert!(crashed.acquire().await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_racing_instances_get_one_lease() {
+        let store = MemoryStore::default();
+        let racers: Vec<_> = (0..8)
+            .map(|i| {
+                let lock = lock(&store, &format!("host-{}:100", i), 60);
+                tokio::spawn(async move { lock.acquire().await })
+            })
+            .collect();
+
+        let mut winners = Vec::new();
+        for racer in racers {
+            if let Ok(lease) = racer.await.unwrap() {
+                winners.push(lease.holder);
+            }
+        }
+        assert_eq!(winners.len(), 1);
+        assert_eq!(store.load().await.unwrap().unwrap().holder, winners[0]);
+    }
+
+    #[tokio::test]
+    async fn test_status_tells_holder_from_others() {
+        let store = MemoryStore::default();
+        let first = lock(&store, "host-a:100", 60);
+        let second = lock(&store, "host-b:200", 60);
+        let lease = first.acquire().await.unwrap();
+        let now = Utc::now();
+
+        let ours = LockStatus::new(Some(&first), Some(lease.clone()), now);
+        assert!(ours.held && ours.held_by_this_instance);
+
+        let theirs = LockStatus::new(Some(&second), Some(lease.clone()), now);
+        assert!(theirs.held && !theirs.held_by_this_instance);
+        assert_eq!(theirs.instance.as_deref(), Some("host-b:200"));
+
+        // An expired lease holds nobody out, not even for its holder
+        let expired = LockStatus::new(Some(&first), Some(lease), now + Duration::hours(1));
+        assert!(!expired.held && !expired.held_by_this_instance);
+
+        let disabled = LockStatus::new::<MemoryStore>(None, None, now);
+        assert!(!disabled.enabled && !disabled.held);
+    }
+}
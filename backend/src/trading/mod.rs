@@ -1,5 +1,11 @@
 pub mod config;
+pub mod control;
+pub mod emergency;
 pub mod engine;
+pub mod estimate;
+pub mod holdings;
+pub mod lock;
+pub mod pnl;
 
 pub use config::TradingConfig;
 pub use engine::TradingEngine;
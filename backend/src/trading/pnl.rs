@@ -0,0 +1,484 @@
+//! Profit and loss of rebalancing
+//!
+//! A rebalance moves funds through the exchange in three legs: a deposit, a
+//! trade and a withdrawal. The legs are grouped back into cycles from the
+//! trading transaction history and each cycle's costs are tallied in BTC: the
+//! on-chain fee of the deposit, the exchange's trading fee, slippage of the
+//! fill against the mid price when the order was placed, and the exchange's
+//! withdrawal fee. Set against the spread the ASB earns on swaps, they show
+//! whether restocking pays for itself.
+//!
+//! Only completed legs count. A cost a leg has no record of (rows written
+//! before fees and mid prices were tracked, or an exchange that doesn't report
+//! them) counts as zero, so the totals are a lower bound. Costs are rounded
+//! to the satoshi and summed exactly.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    amount::{Btc, Xmr},
+    db::{StoredTradingTransaction, TransactionStatus, TransactionType},
+    trading::emergency::ConversionDirection,
+};
+
+/// Costs of one rebalance, all in BTC
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct CycleCosts {
+    pub direction: ConversionDirection,
+    pub started_at: DateTime<Utc>,
+    /// When the last leg in the window completed
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Whether the deposit, trade and withdrawal all fall in the window
+    pub complete: bool,
+    /// BTC spent on or received for the XMR
+    pub btc_traded: f64,
+    pub xmr_traded: f64,
+    /// BTC per XMR when the order was placed
+    pub mid_price: Option<f64>,
+    /// Average fill price, BTC per XMR
+    pub fill_price: Option<f64>,
+    /// On-chain fee of the deposit
+    pub network_fee_btc: f64,
+    pub trading_fee_btc: f64,
+    /// What the fill cost against the mid price; negative when it beat it
+    pub slippage_btc: f64,
+    pub withdrawal_fee_btc: f64,
+    pub total_cost_btc: f64,
+    /// Total cost as a percentage of the BTC traded
+    pub cost_percent: Option<f64>,
+    /// Total cost of this and every earlier cycle in the window
+    pub cumulative_cost_btc: f64,
+    pub transaction_ids: Vec<String>,
+}
+
+/// Costs summed over every cycle in the window
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct PnlTotals {
+    pub cycles: usize,
+    pub btc_traded: f64,
+    pub xmr_traded: f64,
+    pub network_fee_btc: f64,
+    pub trading_fee_btc: f64,
+    pub slippage_btc: f64,
+    pub withdrawal_fee_btc: f64,
+    pub total_cost_btc: f64,
+    pub cost_percent: Option<f64>,
+}
+
+/// Rebalancing costs over `[from, to]`, oldest cycle first
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct PnlReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub cycles: Vec<CycleCosts>,
+    pub totals: PnlTotals,
+}
+
+impl PnlReport {
+    /// Group `transactions`, in any order, into cycles and tally their costs
+    pub fn new(
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        transactions: &[StoredTradingTransaction],
+    ) -> Self {
+        let mut totals = Tally::default();
+        let cycles: Vec<CycleCosts> = group_cycles(transactions)
+            .iter()
+            .map(|cycle| {
+                let tally = cycle.tally();
+                totals.add(&tally);
+                cycle.costs(&tally, totals.total_cost())
+            })
+            .collect();
+
+        Self {
+            from,
+            to,
+            totals: PnlTotals {
+                cycles: cycles.len(),
+                btc_traded: totals.btc_traded.to_f64(),
+                xmr_traded: totals.xmr_traded.to_f64(),
+                network_fee_btc: totals.network_fee.to_f64(),
+                trading_fee_btc: totals.trading_fee.to_f64(),
+                slippage_btc: totals.slippage.to_f64(),
+                withdrawal_fee_btc: totals.withdrawal_fee.to_f64(),
+                total_cost_btc: totals.total_cost().to_f64(),
+                cost_percent: totals.cost_percent(),
+            },
+            cycles,
+        }
+    }
+}
+
+/// Amounts of one or more cycles, summed exactly and only converted to `f64`
+/// for the report
+#[derive(Debug, Clone, Copy, Default)]
+struct Tally {
+    btc_traded: Btc,
+    xmr_traded: Xmr,
+    network_fee: Btc,
+    trading_fee: Btc,
+    slippage: Btc,
+    withdrawal_fee: Btc,
+}
+
+impl Tally {
+    fn add(&mut self, other: &Tally) {
+        self.btc_traded = self.btc_traded.saturating_add(other.btc_traded);
+        self.xmr_traded = self.xmr_traded.saturating_add(other.xmr_traded);
+        self.network_fee = self.network_fee.saturating_add(other.network_fee);
+        self.trading_fee = self.trading_fee.saturating_add(other.trading_fee);
+        self.slippage = self.slippage.saturating_add(other.slippage);
+        self.withdrawal_fee = self.withdrawal_fee.saturating_add(other.withdrawal_fee);
+    }
+
+    fn total_cost(&self) -> Btc {
+        self.network_fee
+            .saturating_add(self.trading_fee)
+            .saturating_add(self.slippage)
+            .saturating_add(self.withdrawal_fee)
+    }
+
+    /// Total cost as a percentage of the BTC traded
+    fn cost_percent(&self) -> Option<f64> {
+        self.btc_traded
+            .is_positive()
+            .then(|| self.total_cost().to_f64() / self.btc_traded.to_f64() * 100.0)
+    }
+}
+
+/// Completed legs of one rebalance, in the order they happened
+struct Cycle<'a> {
+    direction: ConversionDirection,
+    legs: Vec<&'a StoredTradingTransaction>,
+}
+
+impl Cycle<'_> {
+    fn leg(&self, transaction_type: TransactionType) -> Option<&StoredTradingTransaction> {
+        self.legs
+            .iter()
+            .copied()
+            .find(|leg| leg.transaction_type == transaction_type)
+    }
+
+    fn has_withdrawal(&self) -> bool {
+        self.legs
+            .iter()
+            .any(|leg| is_withdrawal(&leg.transaction_type))
+    }
+
+    /// BTC per XMR when the order was placed, and the average fill price
+    fn prices(&self) -> (Option<f64>, Option<f64>) {
+        let trade = self.leg(TransactionType::Trade);
+        (
+            trade.and_then(|t| t.mid_price),
+            trade.and_then(|t| t.exchange_rate),
+        )
+    }
+
+    fn tally(&self) -> Tally {
+        let trade = self.leg(TransactionType::Trade);
+        let (mid_price, fill_price) = self.prices();
+        // Fees charged in XMR are valued at the price actually traded at
+        let btc_per_xmr = fill_price.or(mid_price);
+        let xmr_to_btc = |xmr: Xmr| {
+            btc_per_xmr
+                .and_then(|price| xmr.to_btc_at(price))
+                .unwrap_or_default()
+        };
+
+        let xmr_traded = xmr(trade.and_then(|t| t.xmr_amount));
+        let btc_traded = match trade.and_then(|t| t.btc_amount) {
+            Some(traded) => btc(Some(traded)),
+            None => xmr_to_btc(xmr_traded),
+        };
+
+        let slippage = match (mid_price, fill_price) {
+            (Some(mid), Some(fill)) => {
+                let worse_by = match self.direction {
+                    ConversionDirection::BtcToXmr => fill - mid,
+                    ConversionDirection::XmrToBtc => mid - fill,
+                };
+                xmr_traded.to_btc_at(worse_by).unwrap_or_default()
+            }
+            _ => Btc::ZERO,
+        };
+
+        let leg_fee = |transaction_type| self.leg(transaction_type).and_then(|leg| leg.fee);
+        let (network_fee, withdrawal_fee) = match self.direction {
+            ConversionDirection::BtcToXmr => (
+                btc(leg_fee(TransactionType::BitcoinDeposit)),
+                xmr_to_btc(xmr(leg_fee(TransactionType::MoneroWithdrawal))),
+            ),
+            ConversionDirection::XmrToBtc => (
+                xmr_to_btc(xmr(leg_fee(TransactionType::MoneroDeposit))),
+                btc(leg_fee(TransactionType::BitcoinWithdrawal)),
+            ),
+        };
+
+        Tally {
+            btc_traded,
+            xmr_traded,
+            network_fee,
+            trading_fee: btc(trade.and_then(|t| t.fee)),
+            slippage,
+            withdrawal_fee,
+        }
+    }
+
+    /// This cycle's costs for the report, `cumulative_cost` including them
+    fn costs(&self, tally: &Tally, cumulative_cost: Btc) -> CycleCosts {
+        let (mid_price, fill_price) = self.prices();
+
+        CycleCosts {
+            direction: self.direction,
+            started_at: self.legs[0].timestamp,
+            completed_at: self.legs.iter().filter_map(|leg| leg.completed_at).max(),
+            complete: self
+                .legs
+                .iter()
+                .any(|leg| is_deposit(&leg.transaction_type))
+                && self.leg(TransactionType::Trade).is_some()
+                && self.has_withdrawal(),
+            btc_traded: tally.btc_traded.to_f64(),
+            xmr_traded: tally.xmr_traded.to_f64(),
+            mid_price,
+            fill_price,
+            network_fee_btc: tally.network_fee.to_f64(),
+            trading_fee_btc: tally.trading_fee.to_f64(),
+            slippage_btc: tally.slippage.to_f64(),
+            withdrawal_fee_btc: tally.withdrawal_fee.to_f64(),
+            total_cost_btc: tally.total_cost().to_f64(),
+            cost_percent: tally.cost_percent(),
+            cumulative_cost_btc: cumulative_cost.to_f64(),
+            transaction_ids: self.legs.iter().filter_map(|leg| leg.id.clone()).collect(),
+        }
+    }
+}
+
+/// Split the completed rebalance legs into cycles
+///
+/// A deposit opens a cycle and a withdrawal closes it. Legs whose deposit
+/// falls before the window open a cycle of their own.
+fn group_cycles(transactions: &[StoredTradingTransaction]) -> Vec<Cycle<'_>> {
+    let mut legs: Vec<&StoredTradingTransaction> = transactions
+        .iter()
+        .filter(|tx| tx.status == TransactionStatus::Completed)
+        .collect();
+    legs.sort_by_key(|tx| tx.timestamp);
+
+    let mut cycles = Vec::new();
+    let mut open: Option<Cycle> = None;
+    for leg in legs {
+        let direction = match leg_direction(leg) {
+            Some(direction) => direction,
+            None => continue,
+        };
+
+        let continues = open.as_ref().is_some_and(|cycle| {
+            cycle.direction == direction && !is_deposit(&leg.transaction_type)
+        });
+        if !continues {
+            cycles.extend(open.take());
+        }
+
+        let cycle = open.get_or_insert_with(|| Cycle {
+            direction,
+            legs: Vec::new(),
+        });
+        cycle.legs.push(leg);
+        if is_withdrawal(&leg.transaction_type) {
+            cycles.extend(open.take());
+        }
+    }
+    cycles.extend(open);
+
+    cycles
+}
+
+/// Which way a rebalance leg moves funds, `None` for other transactions
+fn leg_direction(leg: &StoredTradingTransaction) -> Option<ConversionDirection> {
+    match leg.transaction_type {
+        TransactionType::BitcoinDeposit | TransactionType::MoneroWithdrawal => {
+            Some(ConversionDirection::BtcToXmr)
+        }
+        TransactionType::MoneroDeposit | TransactionType::BitcoinWithdrawal => {
+            Some(ConversionDirection::XmrToBtc)
+        }
+        // Sells record only the XMR amount when placed
+        TransactionType::Trade => Some(if leg.btc_amount.is_some() {
+            ConversionDirection::BtcToXmr
+        } else {
+            ConversionDirection::XmrToBtc
+        }),
+        // Emergency conversions record their legs separately
+        TransactionType::EmergencyConversion | TransactionType::InternalTransfer => None,
+    }
+}
+
+fn is_deposit(transaction_type: &TransactionType) -> bool {
+    matches!(
+        transaction_type,
+        TransactionType::BitcoinDeposit | TransactionType::MoneroDeposit
+    )
+}
+
+fn is_withdrawal(transaction_type: &TransactionType) -> bool {
+    matches!(
+        transaction_type,
+        TransactionType::MoneroWithdrawal | TransactionType::BitcoinWithdrawal
+    )
+}
+
+/// A recorded BTC amount, zero when the record has none
+fn btc(amount: Option<f64>) -> Btc {
+    amount.and_then(Btc::from_f64).unwrap_or_default()
+}
+
+/// A recorded XMR amount, zero when the record has none
+fn xmr(amount: Option<f64>) -> Xmr {
+    amount.and_then(Xmr::from_f64).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn leg(
+        minutes: i64,
+        transaction_type: TransactionType,
+        status: TransactionStatus,
+    ) -> StoredTradingTransaction {
+        let timestamp =
+            DateTime::from_timestamp(1_700_000_000, 0).unwrap() + Duration::minutes(minutes);
+        StoredTradingTransaction {
+            id: Some(format!("trading_transactions:{}", minutes)),
+            timestamp,
+            transaction_type,
+            status,
+            btc_amount: None,
+            xmr_amount: None,
+            exchange_rate: None,
+            txid: None,
+            order_id: None,
+            refid: None,
+            from_address: None,
+            to_address: None,
+            fee: None,
+            notes: None,
+            error_message: None,
+            completed_at: Some(timestamp + Duration::minutes(1)),
+            balance_before: None,
+            balance_after: None,
+            replaced_txids: Vec::new(),
+            psbt: None,
+            mid_price: None,
+        }
+    }
+
+    /// A BTC->XMR rebalance buying 10 XMR, legs starting at `minutes`
+    fn buy_cycle(minutes: i64) -> Vec<StoredTradingTransaction> {
+        let mut deposit = leg(
+            minutes,
+            TransactionType::BitcoinDeposit,
+            TransactionStatus::Completed,
+        );
+        deposit.btc_amount = Some(0.05);
+        deposit.fee = Some(0.00002);
+
+        let mut trade = leg(
+            minutes + 30,
+            TransactionType::Trade,
+            TransactionStatus::Completed,
+        );
+        trade.btc_amount = Some(0.0502);
+        trade.xmr_amount = Some(10.0);
+        trade.exchange_rate = Some(0.00502);
+        trade.mid_price = Some(0.005);
+        trade.fee = Some(0.00013);
+
+        let mut withdrawal = leg(
+            minutes + 40,
+            TransactionType::MoneroWithdrawal,
+            TransactionStatus::Completed,
+        );
+        withdrawal.xmr_amount = Some(10.0);
+        withdrawal.fee = Some(0.0001);
+
+        // The history is read newest first
+        vec![withdrawal, trade, deposit]
+    }
+
+    #[test]
+    fn test_buy_cycle_costs() {
+        let transactions = buy_cycle(0);
+        let report = PnlReport::new(Utc::now(), Utc::now(), &transactions);
+
+        assert_eq!(report.cycles.len(), 1);
+        let cycle = &report.cycles[0];
+        assert!(cycle.complete);
+        assert_eq!(cycle.direction, ConversionDirection::BtcToXmr);
+        assert_eq!(cycle.transaction_ids.len(), 3);
+        assert!((cycle.network_fee_btc - 0.00002).abs() < 1e-12);
+        assert!((cycle.trading_fee_btc - 0.00013).abs() < 1e-12);
+        assert!((cycle.slippage_btc - 0.0002).abs() < 1e-12);
+        // 0.0001 XMR at the fill price, to the satoshi
+        assert_eq!(cycle.withdrawal_fee_btc, 0.0000005);
+        assert_eq!(cycle.total_cost_btc, 0.0003505);
+        assert!((cycle.cost_percent.unwrap() - 0.0003505 / 0.0502 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cumulative_totals_across_cycles() {
+        let mut transactions = buy_cycle(0);
+        transactions.extend(buy_cycle(120));
+        // Failed legs and wallet sends are not part of any cycle
+        transactions.push(leg(
+            60,
+            TransactionType::BitcoinDeposit,
+            TransactionStatus::Failed,
+        ));
+        transactions.push(leg(
+            70,
+            TransactionType::InternalTransfer,
+            TransactionStatus::Completed,
+        ));
+
+        let report = PnlReport::new(Utc::now(), Utc::now(), &transactions);
+
+        assert_eq!(report.totals.cycles, 2);
+        // Summed exactly, so the totals match to the satoshi
+        assert_eq!(report.cycles[0].cumulative_cost_btc, 0.0003505);
+        assert_eq!(report.cycles[1].cumulative_cost_btc, 0.000701);
+        assert_eq!(report.totals.total_cost_btc, 0.000701);
+        assert_eq!(report.totals.btc_traded, 0.1004);
+    }
+
+    #[test]
+    fn test_cycle_cut_by_window_is_incomplete() {
+        // The deposit happened before the window
+        let mut transactions = buy_cycle(0);
+        transactions.pop();
+
+        let mut sell = leg(100, TransactionType::Trade, TransactionStatus::Completed);
+        sell.xmr_amount = Some(4.0);
+        sell.exchange_rate = Some(0.0049);
+        sell.mid_price = Some(0.005);
+        transactions.push(sell);
+
+        let report = PnlReport::new(Utc::now(), Utc::now(), &transactions);
+
+        assert_eq!(report.cycles.len(), 2);
+        assert!(!report.cycles[0].complete);
+        assert_eq!(report.cycles[0].network_fee_btc, 0.0);
+
+        let sell = &report.cycles[1];
+        assert_eq!(sell.direction, ConversionDirection::XmrToBtc);
+        assert!(!sell.complete);
+        assert!((sell.btc_traded - 0.0196).abs() < 1e-12);
+        assert!((sell.slippage_btc - 0.0004).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,262 @@
+//! Encrypted wallet backups
+//!
+//! A backup holds the Bitcoin wallet's private descriptors and the Monero
+//! wallet's keys and seed, serialized as JSON and encrypted under the
+//! configured passphrase (see [`crate::crypto`]). Files are named by
+//! creation time so the newest sort last, and all but the newest `keep` are
+//! deleted after each write.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::bitcoin::WalletDescriptor;
+use super::monero::WalletKeys;
+use super::WalletManager;
+use crate::config::BackupConfig;
+use crate::crypto;
+
+/// Identifies the file format and version
+const MAGIC: &[u8; 8] = b"EGXBAK1\0";
+
+const FILE_PREFIX: &str = "wallets-";
+const FILE_SUFFIX: &str = ".bak";
+
+/// Everything needed to restore both wallets
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalletBackup {
+    pub created_at: DateTime<Utc>,
+    pub bitcoin_wallet: String,
+    /// Private descriptors
+    pub bitcoin_descriptors: Vec<WalletDescriptor>,
+    pub monero_wallet: String,
+    pub monero_keys: WalletKeys,
+}
+
+impl std::fmt::Debug for WalletBackup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WalletBackup")
+            .field("created_at", &self.created_at)
+            .field("bitcoin_wallet", &self.bitcoin_wallet)
+            .field("bitcoin_descriptors", &self.bitcoin_descriptors.len())
+            .field("monero_wallet", &self.monero_wallet)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Encrypt `plaintext`, returning the magic, salt, nonce and ciphertext
+pub fn encrypt(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    crypto::seal(MAGIC, passphrase, plaintext)
+}
+
+/// Decrypt data written by [`encrypt`]
+pub fn decrypt(passphrase: &str, data: &[u8]) -> Result<Vec<u8>> {
+    if !crypto::is_sealed(MAGIC, data) {
+        anyhow::bail!("Not an eigenix wallet backup");
+    }
+    crypto::open(MAGIC, passphrase, data)
+}
+
+/// Read and decrypt a backup file
+pub fn read_backup(path: &Path, passphrase: &str) -> Result<WalletBackup> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let plaintext = decrypt(passphrase, &data)?;
+    serde_json::from_slice(&plaintext).context("Failed to parse wallet backup")
+}
+
+/// Encrypt `backup` into a new file in `dir`
+///
+/// The file is written under a temporary name and renamed into place, so a
+/// crash never leaves a partial backup behind. Only the owner may read it,
+/// and a directory created for it is the owner's alone too.
+pub fn write_backup(dir: &Path, passphrase: &str, backup: &WalletBackup) -> Result<PathBuf> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder
+        .create(dir)
+        .with_context(|| format!("Failed to create backup directory {}", dir.display()))?;
+
+    let plaintext = serde_json::to_vec(backup)?;
+    let data = encrypt(passphrase, &plaintext)?;
+
+    let name = format!(
+        "{}{}{}",
+        FILE_PREFIX,
+        backup.created_at.format("%Y%m%dT%H%M%SZ"),
+        FILE_SUFFIX
+    );
+    let path = dir.join(&name);
+    let tmp = dir.join(format!(".{}.tmp", name));
+    crypto::write_private(&tmp, &data)?;
+    std::fs::rename(&tmp, &path)
+        .with_context(|| format!("Failed to move backup into {}", path.display()))?;
+
+    Ok(path)
+}
+
+/// Backup files in `dir`, oldest first
+pub fn list_backups(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read backup directory {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX))
+        })
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+/// Delete all but the newest `keep` backups in `dir`
+///
+/// # Returns
+/// The deleted files
+pub fn rotate(dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let backups = list_backups(dir)?;
+    let excess = backups.len().saturating_sub(keep);
+
+    let mut removed = Vec::with_capacity(excess);
+    for path in backups.into_iter().take(excess) {
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to delete old backup {}", path.display()))?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+/// Take a backup of both wallets, write it to `config.dir` and rotate
+///
+/// # Returns
+/// The path of the new backup
+pub async fn backup_now(wallets: &WalletManager, config: &BackupConfig) -> Result<PathBuf> {
+    let (dir, passphrase) = config
+        .target()
+        .context("Backups need [backup] dir and EIGENIX_BACKUP_PASSPHRASE")?;
+
+    let backup = wallets.backup().await?;
+    let path = write_backup(dir, passphrase, &backup)?;
+    for removed in rotate(dir, config.keep)? {
+        tracing::debug!("Deleted old wallet backup {}", removed.display());
+    }
+
+    Ok(path)
+}
+
+/// Back up the wallets every `interval_secs` until the process exits
+pub async fn run_backups(wallets: Arc<WalletManager>, config: BackupConfig) {
+    let interval = Duration::from_secs(config.interval_secs);
+    loop {
+        match backup_now(&wallets, &config).await {
+            Ok(path) => tracing::info!("Wrote wallet backup {}", path.display()),
+            Err(e) => tracing::error!("Wallet backup failed: {:#}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_backup_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "eigenix-backup-{}-{}-{}",
+            name,
+            std::process::id(),
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn backup(created_at: DateTime<Utc>) -> WalletBackup {
+        WalletBackup {
+            created_at,
+            bitcoin_wallet: "eigenix".to_string(),
+            bitcoin_descriptors: vec![WalletDescriptor {
+                desc: "wpkh(tprv8ZgxMBicQKsPd/84h/1h/0h/0/*)#abcdefgh".to_string(),
+                timestamp: 1_714_564_800,
+                active: true,
+                internal: Some(false),
+                range: Some([0, 999]),
+            }],
+            monero_wallet: "eigenix".to_string(),
+            monero_keys: WalletKeys {
+                address: "44AFFq5kSiGBoZ4NMDwYtN18obc8AemS33DBLWs3H7otXft3XjrpDtQGv7SqSsaBYBb98uNbr2VBBEt7f2wfn3RVGQBEP3A".to_string(),
+                view_key: "aa".repeat(32),
+                spend_key: "bb".repeat(32),
+                seed: "abbey ".repeat(25).trim_end().to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let data = encrypt("correct horse", b"descriptors and keys").unwrap();
+        assert!(data.starts_with(MAGIC));
+        assert!(!data
+            .windows(b"descriptors".len())
+            .any(|w| w == b"descriptors"));
+
+        assert_eq!(
+            decrypt("correct horse", &data).unwrap(),
+            b"descriptors and keys"
+        );
+        assert!(decrypt("wrong horse", &data).is_err());
+        assert!(decrypt("correct horse", &data[..MAGIC.len() + 4]).is_err());
+        assert!(decrypt("correct horse", b"plain text").is_err());
+    }
+
+    #[test]
+    fn test_debug_leaves_out_keys() {
+        let printed = format!("{:?}", backup(Utc::now()));
+        assert!(printed.contains("eigenix"));
+        for secret in ["tprv", "abbey", &"bb".repeat(32)] {
+            assert!(!printed.contains(secret), "{} leaked: {}", secret, printed);
+        }
+
+        let descriptor = &backup(Utc::now()).bitcoin_descriptors[0];
+        assert!(!format!("{:?}", descriptor).contains("tprv"));
+    }
+
+    #[test]
+    fn test_write_and_rotate() {
+        let dir = temp_backup_dir("rotate");
+        let start = DateTime::from_timestamp(1_714_564_800, 0).unwrap();
+
+        let mut written = Vec::new();
+        for hour in 0..4 {
+            let created = start + chrono::Duration::hours(hour);
+            written.push(write_backup(&dir, "passphrase", &backup(created)).unwrap());
+        }
+        // Not a backup, so never rotated away
+        std::fs::write(dir.join("notes.txt"), "keep me").unwrap();
+
+        let removed = rotate(&dir, 2).unwrap();
+        assert_eq!(removed, written[..2].to_vec());
+        assert_eq!(list_backups(&dir).unwrap(), written[2..].to_vec());
+        assert!(dir.join("notes.txt").exists());
+
+        let restored = read_backup(&written[3], "passphrase").unwrap();
+        assert_eq!(restored, backup(start + chrono::Duration::hours(3)));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&written[3]).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -2,15 +2,86 @@ use anyhow::{Context, Result};
 use base64::{engine::general_purpose, Engine as _};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+use crate::amount::Btc;
 
 /// Bitcoin wallet client for sending/receiving BTC
 ///
 /// This wallet connects to a Bitcoin Core node and manages a descriptor-based wallet.
 /// It requires a descriptor (containing private keys) to be provided during initialization.
+#[derive(Clone)]
 pub struct BitcoinWallet {
     url: String,
     auth: String,
     wallet_name: String,
+    /// Progress of a descriptor import started by this process
+    import: Arc<Mutex<DescriptorImport>>,
+}
+
+/// State of the descriptor import job
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum DescriptorImport {
+    /// No import was started by this process (e.g. the wallet already existed)
+    #[default]
+    NotStarted,
+    /// `importdescriptors` is still running, usually because of a rescan
+    Running,
+    Completed,
+    Failed {
+        error: String,
+    },
+}
+
+/// `scanning` field of `getwalletinfo`: `false` when idle, or the active scan
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum WalletScan {
+    Idle(bool),
+    Active { duration: u64, progress: f64 },
+}
+
+impl Default for WalletScan {
+    fn default() -> Self {
+        Self::Idle(false)
+    }
+}
+
+/// Subset of `getwalletinfo` used for import progress
+#[derive(Debug, Deserialize)]
+struct WalletInfo {
+    #[serde(default)]
+    scanning: WalletScan,
+}
+
+/// Descriptor import and rescan progress, served by `GET /wallets/bitcoin/init-status`
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct WalletInitStatus {
+    /// True while the import job runs or Bitcoin Core reports a scan in progress
+    pub importing: bool,
+    /// Rescan progress between 0 and 1, when a scan is active
+    pub rescan_progress: Option<f64>,
+    /// Seconds the active scan has been running
+    pub rescan_duration_secs: Option<u64>,
+    pub import: DescriptorImport,
+}
+
+impl WalletInitStatus {
+    fn new(import: DescriptorImport, scan: WalletScan) -> Self {
+        let (rescan_progress, rescan_duration_secs) = match scan {
+            WalletScan::Active { duration, progress } => (Some(progress), Some(duration)),
+            WalletScan::Idle(_) => (None, None),
+        };
+
+        Self {
+            importing: import == DescriptorImport::Running || rescan_progress.is_some(),
+            rescan_progress,
+            rescan_duration_secs,
+            import,
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -43,6 +114,176 @@ pub struct Transaction {
     pub blockindex: Option<u64>,
     pub blocktime: Option<u64>,
     pub time: u64,
+    /// Fee paid in BTC, only reported for sends
+    #[serde(default)]
+    pub fee: Option<f64>,
+}
+
+/// Unspent output owned by the wallet, from `listunspent`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Utxo {
+    pub txid: String,
+    pub vout: u32,
+    /// Absent for outputs without a standard address (e.g. bare multisig)
+    #[serde(default)]
+    pub address: Option<String>,
+    pub amount: Btc,
+    pub confirmations: u64,
+}
+
+impl Utxo {
+    /// `txid:vout` identifier of the output
+    pub fn outpoint(&self) -> String {
+        format!("{}:{}", self.txid, self.vout)
+    }
+}
+
+/// Replacement transaction created by [`BitcoinWallet::bump_fee`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeBump {
+    pub txid: String,
+    /// Fees in BTC
+    pub original_fee: f64,
+    pub fee: f64,
+}
+
+/// Unsigned transaction funded by [`BitcoinWallet::create_funded_psbt`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundedPsbt {
+    /// Base64-encoded PSBT
+    pub psbt: String,
+    /// Known before signing, since the wallet only spends segwit outputs
+    pub txid: String,
+    /// Fee in BTC
+    pub fee: f64,
+}
+
+/// Descriptor of the wallet, as returned by `listdescriptors`
+#[derive(Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WalletDescriptor {
+    /// Descriptor with checksum, holding private keys when exported as private
+    pub desc: String,
+    /// Creation time, where a rescan on restore can start
+    pub timestamp: u64,
+    pub active: bool,
+    /// Whether it derives change addresses
+    #[serde(default)]
+    pub internal: Option<bool>,
+    /// Derivation range for ranged descriptors
+    #[serde(default)]
+    pub range: Option<[u64; 2]>,
+}
+
+impl std::fmt::Debug for WalletDescriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The descriptor may carry the xprv
+        f.debug_struct("WalletDescriptor")
+            .field("desc", &"<redacted>")
+            .field("timestamp", &self.timestamp)
+            .field("active", &self.active)
+            .field("internal", &self.internal)
+            .field("range", &self.range)
+            .finish()
+    }
+}
+
+/// Which unspent outputs [`BitcoinWallet::list_unspent`] returns
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UtxoFilter {
+    pub min_conf: u32,
+    /// Inclusive bounds on the output amount
+    pub min_amount: Option<Btc>,
+    pub max_amount: Option<Btc>,
+}
+
+impl Default for UtxoFilter {
+    fn default() -> Self {
+        Self {
+            min_conf: 1,
+            min_amount: None,
+            max_amount: None,
+        }
+    }
+}
+
+impl UtxoFilter {
+    pub fn matches(&self, utxo: &Utxo) -> bool {
+        utxo.confirmations >= u64::from(self.min_conf)
+            && self.min_amount.is_none_or(|min| utxo.amount >= min)
+            && self.max_amount.is_none_or(|max| utxo.amount <= max)
+    }
+}
+
+/// Parse a `listunspent` result, keeping the outputs `filter` matches
+fn parse_unspent(result: serde_json::Value, filter: &UtxoFilter) -> Result<Vec<Utxo>> {
+    let mut utxos: Vec<Utxo> =
+        serde_json::from_value(result).context("Failed to parse listunspent result")?;
+    utxos.retain(|utxo| filter.matches(utxo));
+
+    Ok(utxos)
+}
+
+/// Send that spends exactly the chosen outputs, with change back to the wallet
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CoinControlSend {
+    pub address: String,
+    pub amount: Btc,
+    /// Outpoints (`txid:vout`) to spend; no other inputs are added
+    pub inputs: Vec<String>,
+    /// Feerate in sat/vB, bitcoind's own estimate when unset
+    #[serde(default)]
+    pub fee_rate: Option<f64>,
+    /// Take the fee out of `amount` instead of the change
+    #[serde(default)]
+    pub subtract_fee: bool,
+}
+
+/// Check a coin-control send against the wallet's unspent outputs
+///
+/// # Returns
+/// The selected outputs, in the order given
+pub fn check_coin_selection(send: &CoinControlSend, unspent: &[Utxo]) -> Result<Vec<Utxo>> {
+    if !send.amount.is_positive() {
+        anyhow::bail!("Amount must be positive, got {}", send.amount);
+    }
+    if let Some(fee_rate) = send.fee_rate {
+        if !(fee_rate.is_finite() && fee_rate > 0.0) {
+            anyhow::bail!("Fee rate must be a positive sat/vB value, got {}", fee_rate);
+        }
+    }
+    if send.inputs.is_empty() {
+        anyhow::bail!("No inputs selected");
+    }
+
+    let mut selected: Vec<Utxo> = Vec::with_capacity(send.inputs.len());
+    for outpoint in &send.inputs {
+        let utxo = unspent
+            .iter()
+            .find(|utxo| &utxo.outpoint() == outpoint)
+            .with_context(|| format!("{} is not an unspent output of this wallet", outpoint))?;
+        if selected.contains(utxo) {
+            anyhow::bail!("{} is selected more than once", outpoint);
+        }
+        selected.push(utxo.clone());
+    }
+
+    let total = Btc::checked_sum(selected.iter().map(|utxo| utxo.amount))
+        .context("Selected amount overflows")?;
+    // Without subtract_fee the inputs also have to cover the fee
+    let covered = if send.subtract_fee {
+        total >= send.amount
+    } else {
+        total > send.amount
+    };
+    if !covered {
+        anyhow::bail!(
+            "Selected inputs total {} BTC, not enough to send {} BTC",
+            total,
+            send.amount
+        );
+    }
+
+    Ok(selected)
 }
 
 /// Address validation result
@@ -79,6 +320,7 @@ impl BitcoinWallet {
             url,
             auth,
             wallet_name: wallet_name.to_string(),
+            import: Arc::default(),
         };
 
         // Initialize the wallet in Bitcoin Core
@@ -107,6 +349,7 @@ impl BitcoinWallet {
             url,
             auth,
             wallet_name: wallet_name.to_string(),
+            import: Arc::default(),
         };
 
         // Try to load the wallet if it exists
@@ -233,16 +476,11 @@ impl BitcoinWallet {
     }
 
     /// Import descriptors into Bitcoin Core wallet
+    ///
+    /// With `rescan` the import scans the whole chain and can take hours, so it
+    /// runs as a background job; follow it with [`BitcoinWallet::get_init_status`].
+    /// Without a rescan the import is quick and runs inline.
     async fn import_descriptors(&self, descriptor: &str, rescan: bool) -> Result<()> {
-        #[derive(Deserialize)]
-        struct ImportResult {
-            success: bool,
-            #[serde(default)]
-            warnings: Vec<String>,
-            #[serde(default)]
-            error: Option<serde_json::Value>,
-        }
-
         // Add checksum to descriptor if missing
         let descriptor_with_checksum = if !descriptor.contains('#') {
             self.add_checksum_to_descriptor(descriptor).await?
@@ -268,6 +506,35 @@ impl BitcoinWallet {
             ]])
         };
 
+        self.set_import(DescriptorImport::Running);
+
+        if !rescan {
+            let result = self.run_import(params).await;
+            self.finish_import(&result);
+            return result;
+        }
+
+        tracing::info!("Importing descriptor with rescan in the background");
+        let wallet = self.clone();
+        tokio::spawn(async move {
+            let result = wallet.run_import(params).await;
+            wallet.finish_import(&result);
+        });
+
+        Ok(())
+    }
+
+    /// Run `importdescriptors` and check each result
+    async fn run_import(&self, params: serde_json::Value) -> Result<()> {
+        #[derive(Deserialize)]
+        struct ImportResult {
+            success: bool,
+            #[serde(default)]
+            warnings: Vec<String>,
+            #[serde(default)]
+            error: Option<serde_json::Value>,
+        }
+
         let results: Vec<ImportResult> = self.call_wallet("importdescriptors", params).await?;
 
         for result in &results {
@@ -291,7 +558,38 @@ impl BitcoinWallet {
         Ok(())
     }
 
+    fn set_import(&self, state: DescriptorImport) {
+        *self.import.lock().unwrap_or_else(|e| e.into_inner()) = state;
+    }
+
+    fn finish_import(&self, result: &Result<()>) {
+        match result {
+            Ok(()) => self.set_import(DescriptorImport::Completed),
+            Err(e) => {
+                tracing::error!("Descriptor import failed: {:#}", e);
+                self.set_import(DescriptorImport::Failed {
+                    error: format!("{:#}", e),
+                });
+            }
+        }
+    }
+
+    /// Descriptor import job state combined with Bitcoin Core's scan progress
+    pub async fn get_init_status(&self) -> Result<WalletInitStatus> {
+        let info: WalletInfo = self
+            .call_wallet("getwalletinfo", serde_json::json!([]))
+            .await?;
+        let import = self
+            .import
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+
+        Ok(WalletInitStatus::new(import, info.scanning))
+    }
+
     /// Call a Bitcoin RPC method (no wallet context)
+    #[tracing::instrument(name = "bitcoin_rpc", level = "debug", skip(self, params))]
     async fn call<T: for<'de> Deserialize<'de>>(
         &self,
         method: &str,
@@ -330,6 +628,7 @@ impl BitcoinWallet {
     }
 
     /// Call a Bitcoin wallet RPC method (with wallet context)
+    #[tracing::instrument(name = "bitcoin_wallet_rpc", level = "debug", skip(self, params))]
     async fn call_wallet<T: for<'de> Deserialize<'de>>(
         &self,
         method: &str,
@@ -443,7 +742,8 @@ impl BitcoinWallet {
             amount,
             "", // comment
             "", // comment_to
-            subtract_fee
+            subtract_fee,
+            true // replaceable, so a stuck send can be fee-bumped
         ]);
 
         let txid: String = self.call_wallet("sendtoaddress", params).await?;
@@ -464,6 +764,8 @@ impl BitcoinWallet {
             blocktime: Option<u64>,
             txid: String,
             time: u64,
+            #[serde(default)]
+            fee: Option<f64>,
         }
 
         let result: TxResult = self
@@ -478,6 +780,8 @@ impl BitcoinWallet {
             blockindex: result.blockindex,
             blocktime: result.blocktime,
             time: result.time,
+            // Bitcoin Core reports fees as negative amounts
+            fee: result.fee.map(f64::abs),
         })
     }
 
@@ -495,6 +799,8 @@ impl BitcoinWallet {
             blocktime: Option<u64>,
             txid: String,
             time: u64,
+            #[serde(default)]
+            fee: Option<f64>,
         }
 
         let result: Vec<TxListItem> = self
@@ -511,10 +817,89 @@ impl BitcoinWallet {
                 blockindex: tx.blockindex,
                 blocktime: tx.blocktime,
                 time: tx.time,
+                fee: tx.fee.map(f64::abs),
             })
             .collect())
     }
 
+    /// List the wallet's unspent outputs that match `filter`
+    pub async fn list_unspent(&self, filter: &UtxoFilter) -> Result<Vec<Utxo>> {
+        let result: serde_json::Value = self
+            .call_wallet("listunspent", serde_json::json!([filter.min_conf]))
+            .await?;
+
+        parse_unspent(result, filter)
+    }
+
+    /// Validate a coin-control send before it is made
+    ///
+    /// Checks the destination address and that the chosen inputs are
+    /// unspent outputs of this wallet (mempool outputs included) that cover
+    /// the amount.
+    pub async fn check_coin_control_send(&self, send: &CoinControlSend) -> Result<()> {
+        if !self.validate_address(&send.address).await? {
+            anyhow::bail!("Invalid Bitcoin address: {}", send.address);
+        }
+
+        let unspent = self
+            .list_unspent(&UtxoFilter {
+                min_conf: 0,
+                ..UtxoFilter::default()
+            })
+            .await?;
+        check_coin_selection(send, &unspent)?;
+        Ok(())
+    }
+
+    /// Send spending only the chosen inputs, via the `send` RPC
+    ///
+    /// Call [`check_coin_control_send`](Self::check_coin_control_send) first.
+    ///
+    /// # Returns
+    /// Transaction ID (txid) of the sent transaction
+    pub async fn send_coin_control(&self, send: &CoinControlSend) -> Result<String> {
+        #[derive(Deserialize)]
+        struct SendResult {
+            complete: bool,
+            txid: Option<String>,
+        }
+
+        let inputs = send
+            .inputs
+            .iter()
+            .map(|outpoint| {
+                let (txid, vout) = outpoint
+                    .rsplit_once(':')
+                    .and_then(|(txid, vout)| Some((txid, vout.parse::<u32>().ok()?)))
+                    .with_context(|| format!("Malformed outpoint: {}", outpoint))?;
+                Ok(serde_json::json!({"txid": txid, "vout": vout}))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut options = serde_json::json!({
+            "inputs": inputs,
+            "add_inputs": false,
+        });
+        if send.subtract_fee {
+            options["subtract_fee_from_outputs"] = serde_json::json!([0]);
+        }
+
+        let mut params = serde_json::json!({
+            "outputs": [{ send.address.as_str(): send.amount.to_f64() }],
+            "options": options,
+        });
+        if let Some(fee_rate) = send.fee_rate {
+            params["fee_rate"] = serde_json::json!(fee_rate);
+        }
+
+        // Named parameters, so fee_rate can be given without conf_target
+        let result: SendResult = self.call_wallet("send", params).await?;
+        match result.txid {
+            Some(txid) if result.complete => Ok(txid),
+            _ => anyhow::bail!("Wallet could not fully sign the transaction"),
+        }
+    }
+
     /// Estimate transaction fee for sending to an address
     ///
     /// # Arguments
@@ -545,6 +930,209 @@ impl BitcoinWallet {
         Ok(funded.fee)
     }
 
+    /// Feerate bitcoind expects to confirm within `conf_target` blocks
+    ///
+    /// # Returns
+    /// Feerate in BTC/kvB, or `None` while the node has too little data to estimate
+    pub async fn estimate_smart_fee(&self, conf_target: u16) -> Result<Option<f64>> {
+        #[derive(Deserialize)]
+        struct SmartFeeResult {
+            feerate: Option<f64>,
+        }
+
+        let result: SmartFeeResult = self
+            .call("estimatesmartfee", serde_json::json!([conf_target]))
+            .await?;
+
+        Ok(result.feerate)
+    }
+
+    /// Feerate of an unconfirmed transaction, from the node's mempool
+    ///
+    /// # Returns
+    /// Feerate in sat/vB
+    pub async fn mempool_fee_rate(&self, txid: &str) -> Result<f64> {
+        #[derive(Deserialize)]
+        struct Fees {
+            base: f64,
+        }
+
+        #[derive(Deserialize)]
+        struct MempoolEntry {
+            vsize: u64,
+            fees: Fees,
+        }
+
+        let entry: MempoolEntry = self
+            .call("getmempoolentry", serde_json::json!([txid]))
+            .await
+            .with_context(|| format!("{} is not in the mempool", txid))?;
+
+        Ok(entry.fees.base * 1e8 / entry.vsize.max(1) as f64)
+    }
+
+    /// Replace an unconfirmed wallet transaction with one paying `fee_rate` sat/vB
+    ///
+    /// The transaction must signal replaceability (BIP125), as
+    /// [`send_to_address`](Self::send_to_address) does.
+    pub async fn bump_fee(&self, txid: &str, fee_rate: f64) -> Result<FeeBump> {
+        #[derive(Deserialize)]
+        struct BumpFeeResult {
+            txid: String,
+            origfee: f64,
+            fee: f64,
+        }
+
+        let result: BumpFeeResult = self
+            .call_wallet(
+                "bumpfee",
+                serde_json::json!([txid, { "fee_rate": fee_rate }]),
+            )
+            .await?;
+
+        Ok(FeeBump {
+            txid: result.txid,
+            original_fee: result.origfee,
+            fee: result.fee,
+        })
+    }
+
+    /// Fund a send to `address` as a PSBT, to be signed offline
+    ///
+    /// Inputs are locked so later sends don't spend them before the signed
+    /// transaction is broadcast.
+    ///
+    /// # Arguments
+    /// * `fee_rate` - Feerate in sat/vB, bitcoind's estimate when unset
+    pub async fn create_funded_psbt(
+        &self,
+        address: &str,
+        amount: Btc,
+        fee_rate: Option<f64>,
+    ) -> Result<FundedPsbt> {
+        #[derive(Deserialize)]
+        struct CreatePsbtResult {
+            psbt: String,
+            fee: f64,
+        }
+
+        let mut options = serde_json::json!({
+            "replaceable": true,
+            "lockUnspents": true,
+        });
+        if let Some(fee_rate) = fee_rate {
+            options["fee_rate"] = serde_json::json!(fee_rate);
+        }
+
+        let result: CreatePsbtResult = self
+            .call_wallet(
+                "walletcreatefundedpsbt",
+                serde_json::json!([[], [{ address: amount.to_f64() }], 0, options]),
+            )
+            .await?;
+        let txid = self.psbt_txid(&result.psbt).await?;
+
+        Ok(FundedPsbt {
+            psbt: result.psbt,
+            txid,
+            fee: result.fee,
+        })
+    }
+
+    /// Txid of the transaction a PSBT spends into
+    pub async fn psbt_txid(&self, psbt: &str) -> Result<String> {
+        #[derive(Deserialize)]
+        struct DecodedTx {
+            txid: String,
+        }
+
+        #[derive(Deserialize)]
+        struct DecodedPsbt {
+            tx: DecodedTx,
+        }
+
+        let decoded: DecodedPsbt = self
+            .call("decodepsbt", serde_json::json!([psbt]))
+            .await
+            .context("Failed to decode PSBT")?;
+        Ok(decoded.tx.txid)
+    }
+
+    /// Unlock the inputs of a PSBT that will never be signed
+    pub async fn unlock_psbt_inputs(&self, psbt: &str) -> Result<()> {
+        #[derive(Deserialize, Serialize)]
+        struct Outpoint {
+            txid: String,
+            vout: u32,
+        }
+
+        #[derive(Deserialize)]
+        struct DecodedTx {
+            vin: Vec<Outpoint>,
+        }
+
+        #[derive(Deserialize)]
+        struct DecodedPsbt {
+            tx: DecodedTx,
+        }
+
+        let decoded: DecodedPsbt = self
+            .call("decodepsbt", serde_json::json!([psbt]))
+            .await
+            .context("Failed to decode PSBT")?;
+        let _: bool = self
+            .call_wallet("lockunspent", serde_json::json!([true, decoded.tx.vin]))
+            .await?;
+        Ok(())
+    }
+
+    /// Finalize a signed PSBT into a raw transaction
+    ///
+    /// # Returns
+    /// The transaction hex, ready to broadcast
+    pub async fn finalize_psbt(&self, psbt: &str) -> Result<String> {
+        #[derive(Deserialize)]
+        struct FinalizeResult {
+            hex: Option<String>,
+            complete: bool,
+        }
+
+        let result: FinalizeResult = self.call("finalizepsbt", serde_json::json!([psbt])).await?;
+        match result.hex {
+            Some(hex) if result.complete => Ok(hex),
+            _ => anyhow::bail!("PSBT is not fully signed"),
+        }
+    }
+
+    /// Broadcast a raw transaction, returning its txid
+    pub async fn broadcast_transaction(&self, hex: &str) -> Result<String> {
+        let txid: String = self
+            .call("sendrawtransaction", serde_json::json!([hex]))
+            .await?;
+        Ok(txid)
+    }
+
+    /// List the wallet's descriptors
+    ///
+    /// # Arguments
+    /// * `private` - Include the private keys, enough to restore the wallet
+    pub async fn list_descriptors(&self, private: bool) -> Result<Vec<WalletDescriptor>> {
+        #[derive(Deserialize)]
+        struct ListDescriptorsResult {
+            descriptors: Vec<WalletDescriptor>,
+        }
+
+        let result: ListDescriptorsResult = self
+            .call_wallet("listdescriptors", serde_json::json!([private]))
+            .await?;
+        Ok(result.descriptors)
+    }
+
+    /// Name of the wallet in Bitcoin Core
+    pub fn wallet_name(&self) -> &str {
+        &self.wallet_name
+    }
+
     /// Check if wallet is loaded and operational
     pub async fn is_ready(&self) -> bool {
         self.get_balance().await.is_ok()
@@ -555,6 +1143,182 @@ impl BitcoinWallet {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_init_status_surfaces_active_scan() {
+        let payload = serde_json::json!({
+            "walletname": "eigenix",
+            "walletversion": 169900,
+            "format": "sqlite",
+            "txcount": 42,
+            "keypoolsize": 3000,
+            "private_keys_enabled": true,
+            "descriptors": true,
+            "scanning": { "duration": 754, "progress": 0.3725 }
+        });
+        let info: WalletInfo = serde_json::from_value(payload).unwrap();
+
+        let status = WalletInitStatus::new(DescriptorImport::Running, info.scanning);
+        assert!(status.importing);
+        assert_eq!(status.rescan_progress, Some(0.3725));
+        assert_eq!(status.rescan_duration_secs, Some(754));
+
+        // A scan in progress counts as importing even if this process didn't start it
+        let status = WalletInitStatus::new(DescriptorImport::NotStarted, info.scanning);
+        assert!(status.importing);
+    }
+
+    #[test]
+    fn test_init_status_idle_wallet() {
+        let info: WalletInfo = serde_json::from_value(serde_json::json!({
+            "walletname": "eigenix",
+            "scanning": false
+        }))
+        .unwrap();
+
+        let status = WalletInitStatus::new(DescriptorImport::Completed, info.scanning);
+        assert!(!status.importing);
+        assert_eq!(status.rescan_progress, None);
+    }
+
+    #[test]
+    fn test_parse_unspent_filters_by_confirmations() {
+        let payload = serde_json::json!([
+            {
+                "txid": "3f4c5a2e9b0d8e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d4e3f",
+                "vout": 1,
+                "address": "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+                "label": "eigenix-deposit",
+                "scriptPubKey": "0014e8df018c7e326cc253faac7e46cdc51e68542c42",
+                "amount": 0.0125,
+                "confirmations": 6,
+                "spendable": true,
+                "solvable": true,
+                "safe": true
+            },
+            {
+                "txid": "a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f90",
+                "vout": 0,
+                "address": "bc1q9d4ywgfnd8h43da5tpcxcn6ajv590cg6d3tg6axemvljvt2k76zs50tv4q",
+                "amount": 0.00004321,
+                "confirmations": 0,
+                "spendable": true,
+                "solvable": true,
+                "safe": false
+            }
+        ]);
+
+        let all = parse_unspent(
+            payload.clone(),
+            &UtxoFilter {
+                min_conf: 0,
+                ..UtxoFilter::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(
+            all[0].outpoint(),
+            "3f4c5a2e9b0d8e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d4e3f:1"
+        );
+        assert_eq!(all[0].amount, Btc::from_sats(1_250_000));
+        assert_eq!(all[1].amount, Btc::from_sats(4_321));
+
+        let confirmed = parse_unspent(payload.clone(), &UtxoFilter::default()).unwrap();
+        assert_eq!(confirmed.len(), 1);
+        assert_eq!(confirmed[0].confirmations, 6);
+
+        let deep = UtxoFilter {
+            min_conf: 7,
+            ..UtxoFilter::default()
+        };
+        assert!(parse_unspent(payload, &deep).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_utxo_filter_amount_range() {
+        let utxo = |sats| Utxo {
+            txid: "3f4c5a2e9b0d8e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d4e3f".to_string(),
+            vout: 0,
+            address: None,
+            amount: Btc::from_sats(sats),
+            confirmations: 3,
+        };
+        let filter = UtxoFilter {
+            min_conf: 1,
+            min_amount: Some(Btc::from_sats(10_000)),
+            max_amount: Some(Btc::from_sats(50_000)),
+        };
+
+        assert!(!filter.matches(&utxo(9_999)));
+        assert!(filter.matches(&utxo(10_000)));
+        assert!(filter.matches(&utxo(50_000)));
+        assert!(!filter.matches(&utxo(50_001)));
+    }
+
+    #[test]
+    fn test_check_coin_selection() {
+        let unspent = vec![
+            Utxo {
+                txid: "aa".repeat(32),
+                vout: 0,
+                address: None,
+                amount: Btc::from_sats(30_000),
+                confirmations: 2,
+            },
+            Utxo {
+                txid: "bb".repeat(32),
+                vout: 1,
+                address: None,
+                amount: Btc::from_sats(20_000),
+                confirmations: 0,
+            },
+        ];
+        let send = CoinControlSend {
+            address: "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq".to_string(),
+            amount: Btc::from_sats(45_000),
+            inputs: vec![unspent[1].outpoint(), unspent[0].outpoint()],
+            fee_rate: Some(4.5),
+            subtract_fee: false,
+        };
+
+        let selected = check_coin_selection(&send, &unspent).unwrap();
+        assert_eq!(selected, vec![unspent[1].clone(), unspent[0].clone()]);
+
+        // The inputs have to leave room for the fee unless it is subtracted
+        let exact = CoinControlSend {
+            amount: Btc::from_sats(50_000),
+            ..send.clone()
+        };
+        assert!(check_coin_selection(&exact, &unspent).is_err());
+        let subtracted = CoinControlSend {
+            subtract_fee: true,
+            ..exact
+        };
+        assert!(check_coin_selection(&subtracted, &unspent).is_ok());
+
+        let unknown = CoinControlSend {
+            inputs: vec![format!("{}:0", "cc".repeat(32))],
+            ..send.clone()
+        };
+        assert!(check_coin_selection(&unknown, &unspent).is_err());
+        let duplicate = CoinControlSend {
+            inputs: vec![unspent[0].outpoint(), unspent[0].outpoint()],
+            amount: Btc::from_sats(10_000),
+            ..send.clone()
+        };
+        assert!(check_coin_selection(&duplicate, &unspent).is_err());
+        let zero_fee = CoinControlSend {
+            fee_rate: Some(0.0),
+            ..send.clone()
+        };
+        assert!(check_coin_selection(&zero_fee, &unspent).is_err());
+        let empty = CoinControlSend {
+            inputs: Vec::new(),
+            ..send
+        };
+        assert!(check_coin_selection(&empty, &unspent).is_err());
+    }
+
     #[tokio::test]
     #[ignore] // Only run with valid Bitcoin node
     async fn test_connect_existing() {
@@ -0,0 +1,334 @@
+//! Spend limits on the managed wallets
+//!
+//! Every send out of the wallets, by hand or by the trading engine, is
+//! checked against a cap per transaction and a cap on the total sent over the
+//! last 24 hours, per asset. Sends are recorded in the `spends` table, so the
+//! rolling total survives restarts and every instance reads the same one.
+//!
+//! A send is recorded before it goes out, and is refused if it can't be, so
+//! nothing leaves the wallets uncounted. Checks and records are serialized
+//! within the process only: two instances sending at the same moment can each
+//! fit under a cap that only one of the sends fits under. Amounts are what
+//! reaches the recipient; network fees are not counted.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+
+use super::transfer::TransferAsset;
+use crate::db::MetricsDatabase;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpendLimitsConfig {
+    /// Most BTC a single send may move. 0 disables.
+    #[serde(default)]
+    pub btc_per_transaction: f64,
+    /// Most XMR a single send may move. 0 disables.
+    #[serde(default)]
+    pub xmr_per_transaction: f64,
+    /// Most BTC sent over any 24 hours. 0 disables.
+    #[serde(default)]
+    pub btc_per_day: f64,
+    /// Most XMR sent over any 24 hours. 0 disables.
+    #[serde(default)]
+    pub xmr_per_day: f64,
+}
+
+impl SpendLimitsConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (name, limit) in [
+            ("btc_per_transaction", self.btc_per_transaction),
+            ("xmr_per_transaction", self.xmr_per_transaction),
+            ("btc_per_day", self.btc_per_day),
+            ("xmr_per_day", self.xmr_per_day),
+        ] {
+            if !limit.is_finite() || limit < 0.0 {
+                anyhow::bail!("limits.{} must be zero or positive", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// `(per transaction, per day)` caps of `asset`, `None` when unset
+    fn caps(&self, asset: TransferAsset) -> (Option<f64>, Option<f64>) {
+        let (per_transaction, per_day) = match asset {
+            TransferAsset::Btc => (self.btc_per_transaction, self.btc_per_day),
+            TransferAsset::Xmr => (self.xmr_per_transaction, self.xmr_per_day),
+        };
+        let set = |limit: f64| (limit > 0.0).then_some(limit);
+        (set(per_transaction), set(per_day))
+    }
+
+    fn is_enabled(&self) -> bool {
+        [TransferAsset::Btc, TransferAsset::Xmr]
+            .into_iter()
+            .any(|asset| self.caps(asset) != (None, None))
+    }
+}
+
+/// A send refused by a spend limit, told apart from wallet failures by callers
+#[derive(Debug)]
+pub struct LimitExceeded(pub String);
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// Fail with [`LimitExceeded`] if sending `amount` on top of `spent_today` would exceed a cap
+pub fn check_spend(
+    config: &SpendLimitsConfig,
+    asset: TransferAsset,
+    amount: f64,
+    spent_today: f64,
+) -> Result<()> {
+    let (per_transaction, per_day) = config.caps(asset);
+    if let Some(limit) = per_transaction {
+        if amount > limit {
+            return Err(LimitExceeded(format!(
+                "Sending {} {:?} exceeds the limit of {} per transaction",
+                amount, asset, limit
+            ))
+            .into());
+        }
+    }
+    if let Some(limit) = per_day {
+        if spent_today + amount > limit {
+            return Err(LimitExceeded(format!(
+                "Sending {} {:?} exceeds the daily limit of {}: {} already sent in the last 24 hours",
+                amount, asset, limit, spent_today
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// `source` of a send recorded before it went out
+const RESERVED_SOURCE: &str = "reserved";
+
+/// A send out of a managed wallet, as counted against the daily cap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpendRecord {
+    pub timestamp: DateTime<Utc>,
+    pub asset: TransferAsset,
+    pub amount: f64,
+    /// What sent it, e.g. `wallets/transfer` or `trading-engine`, or
+    /// `reserved` while the send is going out
+    pub source: String,
+    pub txid: Option<String>,
+}
+
+/// Caps and remaining budget of one asset
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AssetLimitStatus {
+    pub per_transaction: Option<f64>,
+    pub per_day: Option<f64>,
+    /// Sent over the last 24 hours
+    pub spent_24h: f64,
+    /// Left under the daily cap, `None` when there is none
+    pub remaining_24h: Option<f64>,
+}
+
+/// Response of `GET /limits/status`
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LimitsStatus {
+    pub btc: AssetLimitStatus,
+    pub xmr: AssetLimitStatus,
+}
+
+/// Enforces [`SpendLimitsConfig`], shared by the wallet routes and the engine
+#[derive(Clone, Default)]
+pub struct SpendLimiter {
+    config: SpendLimitsConfig,
+    db: Option<MetricsDatabase>,
+    lock: Arc<Mutex<()>>,
+}
+
+/// A send allowed by [`SpendLimiter::reserve`], counted from then on
+///
+/// Its `spends` row counts the send until it is committed with what was
+/// sent; dropping it removes the row, as for a send that failed. Other sends
+/// aren't held up meanwhile, since the row already counts against their caps.
+pub struct Reservation {
+    db: Option<MetricsDatabase>,
+    asset: TransferAsset,
+    /// Id of the `spends` row counting the send so far
+    record: Option<String>,
+}
+
+impl SpendLimiter {
+    pub fn new(config: SpendLimitsConfig, db: MetricsDatabase) -> Self {
+        Self {
+            config,
+            db: Some(db),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    async fn spent_today(&self, asset: TransferAsset) -> Result<f64> {
+        match &self.db {
+            Some(db) => {
+                db.get_spent_since(asset, Utc::now() - Duration::hours(24))
+                    .await
+            }
+            None => Ok(0.0),
+        }
+    }
+
+    /// Check that `amount` of `asset` may be sent now, and record it
+    ///
+    /// Fails if the send can't be recorded, so that it isn't made uncounted.
+    /// The limiter is only held while checking and recording, not while the
+    /// send goes out.
+    ///
+    /// # Returns
+    /// A reservation to commit with what was actually sent
+    pub async fn reserve(&self, asset: TransferAsset, amount: f64) -> Result<Reservation> {
+        let _guard = self.lock.lock().await;
+        if self.config.is_enabled() {
+            let spent_today = self
+                .spent_today(asset)
+                .await
+                .context("Failed to read today's spending")?;
+            check_spend(&self.config, asset, amount, spent_today)?;
+        }
+
+        let record = match &self.db {
+            Some(db) => {
+                let reserved = SpendRecord {
+                    timestamp: Utc::now(),
+                    asset,
+                    amount,
+                    source: RESERVED_SOURCE.to_string(),
+                    txid: None,
+                };
+                let id = db
+                    .store_spend(&reserved)
+                    .await
+                    .context("Failed to record the send before making it")?;
+                Some(id)
+            }
+            None => None,
+        };
+
+        Ok(Reservation {
+            db: self.db.clone(),
+            asset,
+            record,
+        })
+    }
+
+    /// Caps and remaining budget of both assets
+    pub async fn status(&self) -> Result<LimitsStatus> {
+        let asset_status = |asset, spent_24h: f64| {
+            let (per_transaction, per_day) = self.config.caps(asset);
+            AssetLimitStatus {
+                per_transaction,
+                per_day,
+                spent_24h,
+                remaining_24h: per_day.map(|limit| (limit - spent_24h).max(0.0)),
+            }
+        };
+
+        Ok(LimitsStatus {
+            btc: asset_status(
+                TransferAsset::Btc,
+                self.spent_today(TransferAsset::Btc).await?,
+            ),
+            xmr: asset_status(
+                TransferAsset::Xmr,
+                self.spent_today(TransferAsset::Xmr).await?,
+            ),
+        })
+    }
+}
+
+impl Reservation {
+    /// Count a send that went out as what was actually sent
+    ///
+    /// The funds have moved by now, so a failed write is logged rather than
+    /// returned; the send then stays counted at the amount reserved.
+    pub async fn commit(mut self, amount: f64, source: &str, txid: Option<String>) {
+        let (Some(db), Some(id)) = (&self.db, self.record.take()) else {
+            return;
+        };
+        let record = SpendRecord {
+            timestamp: Utc::now(),
+            asset: self.asset,
+            amount,
+            source: source.to_string(),
+            txid,
+        };
+        if let Err(e) = db.update_spend(&id, &record).await {
+            tracing::error!(
+                "Failed to record {:?} spend of {}, counted as reserved: {:#}",
+                self.asset,
+                amount,
+                e
+            );
+        }
+    }
+}
+
+impl Drop for Reservation {
+    /// Remove the record of a send that was never made
+    fn drop(&mut self) {
+        let (Some(db), Some(id)) = (self.db.clone(), self.record.take()) else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let asset = self.asset;
+        runtime.spawn(async move {
+            if let Err(e) = db.delete_spend(&id).await {
+                tracing::warn!(
+                    "Failed to remove the {:?} spend reserved for a failed send: {:#}",
+                    asset,
+                    e
+                );
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SpendLimitsConfig {
+        SpendLimitsConfig {
+            btc_per_transaction: 0.5,
+            btc_per_day: 1.0,
+            ..SpendLimitsConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_per_transaction_cap() {
+        assert!(check_spend(&config(), TransferAsset::Btc, 0.5, 0.0).is_ok());
+        let refused = check_spend(&config(), TransferAsset::Btc, 0.6, 0.0).unwrap_err();
+        assert!(refused.downcast_ref::<LimitExceeded>().is_some());
+    }
+
+    #[test]
+    fn test_daily_cap_counts_earlier_sends() {
+        assert!(check_spend(&config(), TransferAsset::Btc, 0.4, 0.6).is_ok());
+        assert!(check_spend(&config(), TransferAsset::Btc, 0.4, 0.7).is_err());
+    }
+
+    #[test]
+    fn test_unset_caps_allow_anything() {
+        assert!(check_spend(&config(), TransferAsset::Xmr, 1_000.0, 1_000.0).is_ok());
+        assert!(!SpendLimitsConfig::default().is_enabled());
+        assert!(config().is_enabled());
+    }
+}
@@ -1,6 +1,14 @@
+use super::backup::WalletBackup;
+use super::bitcoin::{CoinControlSend, WalletDescriptor};
+use super::limits::SpendLimiter;
+use super::monero::{Destination, SentTransaction, TransferPriority, WalletKeys};
+use super::transfer::{self, TransferAsset, TransferRequest};
 use super::{BitcoinWallet, MoneroWallet};
+use crate::amount::Xmr;
+use crate::db::StoredTradingTransaction;
 use crate::services::AsbClient;
 use anyhow::{Context, Result};
+use chrono::Utc;
 
 /// Wallet manager for initializing and managing Bitcoin and Monero wallets
 ///
@@ -11,6 +19,10 @@ use anyhow::{Context, Result};
 pub struct WalletManager {
     pub bitcoin: BitcoinWallet,
     pub monero: MoneroWallet,
+    /// Checked by every send made through the manager
+    limits: SpendLimiter,
+    /// Monero account internal transfers spend from
+    monero_account: u32,
 }
 
 /// Configuration for wallet initialization
@@ -25,6 +37,8 @@ pub struct WalletConfig {
     pub monero_rpc_url: String,
     pub monero_wallet_name: String,
     pub monero_wallet_password: String,
+    /// Account internal transfers spend from
+    pub monero_account: u32,
 
     // ASB configuration
     pub asb_rpc_url: String,
@@ -117,7 +131,12 @@ impl WalletManager {
 
         tracing::info!("All wallets initialized and ready");
 
-        Ok(Self { bitcoin, monero })
+        Ok(Self {
+            bitcoin,
+            monero,
+            limits: SpendLimiter::default(),
+            monero_account: config.monero_account,
+        })
     }
 
     /// Connect to existing wallets without re-initializing from ASB
@@ -154,7 +173,12 @@ impl WalletManager {
 
         tracing::info!("Connected to existing Monero wallet");
 
-        Ok(Self { bitcoin, monero })
+        Ok(Self {
+            bitcoin,
+            monero,
+            limits: SpendLimiter::default(),
+            monero_account: config.monero_account,
+        })
     }
 
     /// Initialize or connect to wallets (smart initialization)
@@ -187,6 +211,17 @@ impl WalletManager {
         }
     }
 
+    /// Enforce these spend limits on sends made through the manager
+    pub fn with_spend_limits(mut self, limits: SpendLimiter) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The spend limits in force
+    pub fn spend_limits(&self) -> &SpendLimiter {
+        &self.limits
+    }
+
     /// Get Bitcoin balance
     pub async fn get_bitcoin_balance(&self) -> Result<f64> {
         let balance = self.bitcoin.get_balance().await?;
@@ -211,6 +246,194 @@ impl WalletManager {
         self.bitcoin.is_ready().await && self.monero.is_ready().await
     }
 
+    /// Validate an internal transfer and return the source wallet's spendable balance
+    ///
+    /// Checks that `from_wallet` is a managed wallet for the asset, that the
+    /// destination is a valid address on the wallet's network, and that the
+    /// spendable balance covers the amount. XMR is spent from the configured
+    /// account.
+    pub async fn prepare_transfer(&self, request: &TransferRequest) -> Result<f64> {
+        let (wallet_name, address_valid, available) = match request.asset {
+            TransferAsset::Btc => (
+                self.bitcoin.wallet_name(),
+                self.bitcoin.validate_address(&request.to_address).await?,
+                self.bitcoin.get_balance().await?.balance,
+            ),
+            TransferAsset::Xmr => (
+                self.monero.wallet_name(),
+                self.monero.validate_address(&request.to_address).await?,
+                self.monero
+                    .get_account_balance(self.monero_account)
+                    .await?
+                    .unlocked_balance,
+            ),
+        };
+
+        if request.from_wallet != wallet_name {
+            anyhow::bail!(
+                "Unknown {:?} wallet '{}', expected '{}'",
+                request.asset,
+                request.from_wallet,
+                wallet_name
+            );
+        }
+        if !address_valid {
+            anyhow::bail!(
+                "'{}' is not a valid {:?} address for this network",
+                request.to_address,
+                request.asset
+            );
+        }
+        transfer::check_funds(request.asset, request.amount, available)?;
+
+        Ok(available)
+    }
+
+    /// Send a transfer checked by [`WalletManager::prepare_transfer`]
+    ///
+    /// Returns the `InternalTransfer` record for the caller to store.
+    pub async fn execute_transfer(
+        &self,
+        request: &TransferRequest,
+        balance_before: f64,
+    ) -> Result<StoredTradingTransaction> {
+        let reservation = self.limits.reserve(request.asset, request.amount).await?;
+        let (txid, fee) = match request.asset {
+            TransferAsset::Btc => {
+                let txid = self
+                    .bitcoin
+                    .send_to_address(&request.to_address, request.amount, false)
+                    .await?;
+                let fee = match self.bitcoin.get_transaction(&txid).await {
+                    Ok(tx) => tx.fee,
+                    Err(e) => {
+                        tracing::warn!("Failed to look up fee for transfer {}: {}", txid, e);
+                        None
+                    }
+                };
+                (txid, fee)
+            }
+            TransferAsset::Xmr => {
+                let (txid, fee) = self
+                    .monero
+                    .transfer_from_account(
+                        self.monero_account,
+                        &request.to_address,
+                        request.amount,
+                        TransferPriority::Default,
+                    )
+                    .await?;
+                (txid, Some(fee))
+            }
+        };
+        reservation
+            .commit(request.amount, "wallets/transfer", Some(txid.clone()))
+            .await;
+
+        tracing::info!(
+            "Internal transfer of {} {:?} from '{}' to {} sent: {}",
+            request.amount,
+            request.asset,
+            request.from_wallet,
+            request.to_address,
+            txid
+        );
+
+        transfer::transfer_record(request, txid, fee, balance_before)
+    }
+
+    /// Coin-control send, within the spend limits
+    pub async fn send_coin_control(&self, send: &CoinControlSend) -> Result<String> {
+        let amount = send.amount.to_f64();
+        let reservation = self.limits.reserve(TransferAsset::Btc, amount).await?;
+        let txid = self.bitcoin.send_coin_control(send).await?;
+        reservation
+            .commit(amount, "wallets/bitcoin/send", Some(txid.clone()))
+            .await;
+        Ok(txid)
+    }
+
+    /// Broadcast a signed transaction sending `amount` BTC, within the spend limits
+    pub async fn broadcast_bitcoin(&self, hex: &str, amount: f64) -> Result<String> {
+        let reservation = self.limits.reserve(TransferAsset::Btc, amount).await?;
+        let txid = self.bitcoin.broadcast_transaction(hex).await?;
+        reservation
+            .commit(amount, "wallets/bitcoin/psbt", Some(txid.clone()))
+            .await;
+        Ok(txid)
+    }
+
+    /// Sweep unlocked Monero outputs, within the spend limits
+    ///
+    /// Checked against the whole unlocked balance, the most a sweep can move,
+    /// and counted as what was actually swept.
+    pub async fn sweep_monero(
+        &self,
+        address: &str,
+        priority: TransferPriority,
+        below_amount: Option<Xmr>,
+    ) -> Result<Vec<SentTransaction>> {
+        let unlocked = self.monero.get_balance().await?.unlocked_balance;
+        let reservation = self.limits.reserve(TransferAsset::Xmr, unlocked).await?;
+        let transactions = self
+            .monero
+            .sweep_outputs(address, priority, below_amount)
+            .await?;
+        reservation
+            .commit(sent_total(&transactions), "wallets/monero/sweep", None)
+            .await;
+        Ok(transactions)
+    }
+
+    /// Pay several Monero recipients, within the spend limits
+    pub async fn transfer_split_monero(
+        &self,
+        destinations: &[Destination],
+        priority: TransferPriority,
+    ) -> Result<Vec<SentTransaction>> {
+        let amount = destinations
+            .iter()
+            .map(|destination| destination.amount.to_f64())
+            .sum();
+        let reservation = self.limits.reserve(TransferAsset::Xmr, amount).await?;
+        let transactions = self.monero.transfer_split(destinations, priority).await?;
+        reservation
+            .commit(
+                sent_total(&transactions),
+                "wallets/monero/transfer-split",
+                None,
+            )
+            .await;
+        Ok(transactions)
+    }
+
+    /// The Bitcoin wallet's descriptors, with private keys
+    pub async fn export_bitcoin_descriptors(&self) -> Result<Vec<WalletDescriptor>> {
+        self.bitcoin.list_descriptors(true).await
+    }
+
+    /// The Monero wallet's keys and seed
+    pub async fn export_monero_keys(&self) -> Result<WalletKeys> {
+        self.monero.export_keys().await
+    }
+
+    /// Collect what is needed to restore both wallets
+    pub async fn backup(&self) -> Result<WalletBackup> {
+        Ok(WalletBackup {
+            created_at: Utc::now(),
+            bitcoin_wallet: self.bitcoin.wallet_name().to_string(),
+            bitcoin_descriptors: self
+                .export_bitcoin_descriptors()
+                .await
+                .context("Failed to export Bitcoin descriptors")?,
+            monero_wallet: self.monero.wallet_name().to_string(),
+            monero_keys: self
+                .export_monero_keys()
+                .await
+                .context("Failed to export Monero keys")?,
+        })
+    }
+
     /// Refresh Monero wallet to sync with blockchain
     pub async fn refresh_monero(&self) -> Result<u64> {
         self.monero.refresh().await
@@ -228,11 +451,20 @@ impl Clone for WalletConfig {
             monero_rpc_url: self.monero_rpc_url.clone(),
             monero_wallet_name: self.monero_wallet_name.clone(),
             monero_wallet_password: self.monero_wallet_password.clone(),
+            monero_account: self.monero_account,
             asb_rpc_url: self.asb_rpc_url.clone(),
         }
     }
 }
 
+/// XMR paid out by the transactions of a sweep or split transfer, fees excluded
+fn sent_total(transactions: &[SentTransaction]) -> f64 {
+    transactions
+        .iter()
+        .map(|transaction| transaction.amount.to_f64())
+        .sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -248,6 +480,7 @@ mod tests {
             monero_rpc_url: "http://127.0.0.1:18082/json_rpc".to_string(),
             monero_wallet_name: "eigenix_test".to_string(),
             monero_wallet_password: "".to_string(),
+            monero_account: 0,
             asb_rpc_url: "http://127.0.0.1:9944".to_string(),
         };
 
@@ -266,6 +499,7 @@ mod tests {
             monero_rpc_url: "http://127.0.0.1:18082/json_rpc".to_string(),
             monero_wallet_name: "eigenix".to_string(),
             monero_wallet_password: "".to_string(),
+            monero_account: 0,
             asb_rpc_url: "http://127.0.0.1:9944".to_string(),
         };
 
@@ -4,9 +4,17 @@
 /// - Bitcoin wallet operations (sending/receiving BTC)
 /// - Monero wallet operations (sending/receiving XMR)
 /// - Wallet manager for orchestrating initialization from ASB
+/// - Internal transfers between the managed wallets
+/// - Bitcoin sends signed offline through PSBTs
+/// - Encrypted backups of the wallets' keys
+/// - Per-transaction and daily spend limits
+pub mod backup;
 pub mod bitcoin;
+pub mod limits;
 pub mod manager;
 pub mod monero;
+pub mod psbt;
+pub mod transfer;
 
 pub use bitcoin::BitcoinWallet;
 pub use manager::{WalletConfig, WalletManager};
@@ -1,5 +1,8 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::amount::Xmr;
 
 /// Monero wallet client for sending/receiving XMR
 ///
@@ -50,6 +53,136 @@ pub struct Subaddress {
     pub used: bool,
 }
 
+/// Fee level of a send; higher levels pay more to confirm sooner
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferPriority {
+    /// The wallet's configured default
+    #[default]
+    Default,
+    Unimportant,
+    Normal,
+    Elevated,
+    Priority,
+}
+
+impl TransferPriority {
+    /// `priority` value monero-wallet-rpc expects
+    pub fn level(self) -> u32 {
+        match self {
+            Self::Default => 0,
+            Self::Unimportant => 1,
+            Self::Normal => 2,
+            Self::Elevated => 3,
+            Self::Priority => 4,
+        }
+    }
+}
+
+/// Recipient of a multi-destination send
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Destination {
+    pub address: String,
+    pub amount: Xmr,
+}
+
+/// Transaction created by a sweep or split transfer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SentTransaction {
+    pub txid: String,
+    /// Proves the payment to a recipient; absent if the wallet didn't return it
+    pub tx_key: Option<String>,
+    pub amount: Xmr,
+    pub fee: Xmr,
+}
+
+/// Everything needed to restore the wallet elsewhere
+#[derive(Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WalletKeys {
+    pub address: String,
+    pub view_key: String,
+    pub spend_key: String,
+    /// 25-word mnemonic seed
+    pub seed: String,
+}
+
+impl std::fmt::Debug for WalletKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WalletKeys")
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Result of `transfer_split` and `sweep_all`: one entry per transaction in each list
+#[derive(Debug, Deserialize)]
+struct MultiTransferResult {
+    tx_hash_list: Vec<String>,
+    #[serde(default)]
+    tx_key_list: Vec<String>,
+    #[serde(default)]
+    amount_list: Vec<u64>,
+    #[serde(default)]
+    fee_list: Vec<u64>,
+}
+
+impl MultiTransferResult {
+    fn into_transactions(self) -> Result<Vec<SentTransaction>> {
+        if self.tx_hash_list.is_empty() {
+            anyhow::bail!("Wallet created no transaction");
+        }
+
+        self.tx_hash_list
+            .into_iter()
+            .enumerate()
+            .map(|(i, txid)| {
+                let piconero = |list: &[u64]| {
+                    let units = list.get(i).copied().unwrap_or(0);
+                    i64::try_from(units)
+                        .map(Xmr::from_piconero)
+                        .context("XMR amount overflow")
+                };
+                Ok(SentTransaction {
+                    tx_key: self
+                        .tx_key_list
+                        .get(i)
+                        .filter(|key| !key.is_empty())
+                        .cloned(),
+                    amount: piconero(&self.amount_list)?,
+                    fee: piconero(&self.fee_list)?,
+                    txid,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Check a multi-destination send against the unlocked balance
+pub fn check_destinations(destinations: &[Destination], unlocked: Xmr) -> Result<()> {
+    if destinations.is_empty() {
+        anyhow::bail!("No destinations given");
+    }
+    if let Some(destination) = destinations.iter().find(|d| !d.amount.is_positive()) {
+        anyhow::bail!(
+            "Amount for {} must be positive, got {}",
+            destination.address,
+            destination.amount
+        );
+    }
+
+    let total = Xmr::checked_sum(destinations.iter().map(|d| d.amount))
+        .context("Total amount overflows")?;
+    if total > unlocked {
+        anyhow::bail!(
+            "Sending {} XMR needs more than the {} XMR unlocked",
+            total,
+            unlocked
+        );
+    }
+
+    Ok(())
+}
+
 const ATOMIC_UNITS_PER_XMR: u64 = 1_000_000_000_000;
 
 impl MoneroWallet {
@@ -189,6 +322,7 @@ impl MoneroWallet {
     }
 
     /// Call a Monero wallet RPC method
+    #[tracing::instrument(name = "monero_wallet_rpc", level = "debug", skip(self, params))]
     async fn call<T: for<'de> Deserialize<'de>>(
         &self,
         method: &str,
@@ -237,13 +371,23 @@ impl MoneroWallet {
 
     /// Get wallet balance
     pub async fn get_balance(&self) -> Result<WalletBalance> {
+        self.get_account_balance(0).await
+    }
+
+    /// Get the balance of one account
+    pub async fn get_account_balance(&self, account_index: u32) -> Result<WalletBalance> {
         #[derive(Deserialize)]
         struct BalanceResult {
             balance: u64,          // in atomic units
             unlocked_balance: u64, // in atomic units
         }
 
-        let result: BalanceResult = self.call("get_balance", serde_json::json!({})).await?;
+        let result: BalanceResult = self
+            .call(
+                "get_balance",
+                serde_json::json!({ "account_index": account_index }),
+            )
+            .await?;
 
         Ok(WalletBalance {
             balance: Self::atomic_to_xmr(result.balance),
@@ -265,6 +409,30 @@ impl MoneroWallet {
         Ok(result.address)
     }
 
+    /// Query one of the wallet's secrets (`mnemonic`, `view_key` or `spend_key`)
+    async fn query_key(&self, key_type: &str) -> Result<String> {
+        #[derive(Deserialize)]
+        struct QueryKeyResult {
+            key: String,
+        }
+
+        let result: QueryKeyResult = self
+            .call("query_key", serde_json::json!({ "key_type": key_type }))
+            .await
+            .with_context(|| format!("Failed to query {}", key_type))?;
+        Ok(result.key)
+    }
+
+    /// Export the wallet's keys and seed
+    pub async fn export_keys(&self) -> Result<WalletKeys> {
+        Ok(WalletKeys {
+            address: self.get_address().await?,
+            view_key: self.query_key("view_key").await?,
+            spend_key: self.query_key("spend_key").await?,
+            seed: self.query_key("mnemonic").await?,
+        })
+    }
+
     /// Create a new subaddress
     ///
     /// # Arguments
@@ -315,7 +483,7 @@ impl MoneroWallet {
     /// # Arguments
     /// * `address` - Destination Monero address
     /// * `amount` - Amount in XMR to send
-    /// * `priority` - Transaction priority (0=default, 1=unimportant, 2=normal, 3=elevated, 4=priority)
+    /// * `priority` - Transaction fee level
     ///
     /// # Returns
     /// Transaction hash (txid) and fee in XMR
@@ -323,7 +491,19 @@ impl MoneroWallet {
         &self,
         address: &str,
         amount: f64,
-        priority: u32,
+        priority: TransferPriority,
+    ) -> Result<(String, f64)> {
+        self.transfer_from_account(0, address, amount, priority)
+            .await
+    }
+
+    /// Transfer XMR out of the account `account_index`, as [`Self::transfer`]
+    pub async fn transfer_from_account(
+        &self,
+        account_index: u32,
+        address: &str,
+        amount: f64,
+        priority: TransferPriority,
     ) -> Result<(String, f64)> {
         // Validate address first
         if !self.validate_address(address).await? {
@@ -343,7 +523,8 @@ impl MoneroWallet {
                 "amount": amount_atomic,
                 "address": address
             }],
-            "priority": priority,
+            "account_index": account_index,
+            "priority": priority.level(),
             "get_tx_key": true
         });
 
@@ -352,47 +533,97 @@ impl MoneroWallet {
         Ok((result.tx_hash, Self::atomic_to_xmr(result.fee)))
     }
 
+    /// Validate a multi-destination send before it is made
+    pub async fn check_split_transfer(&self, destinations: &[Destination]) -> Result<()> {
+        for destination in destinations {
+            if !self.validate_address(&destination.address).await? {
+                anyhow::bail!("Invalid Monero address: {}", destination.address);
+            }
+        }
+
+        let unlocked = Xmr::from_f64(self.get_balance().await?.unlocked_balance)
+            .context("XMR amount overflow")?;
+        check_destinations(destinations, unlocked)
+    }
+
+    /// Pay several recipients at once, split over as many transactions as needed
+    ///
+    /// Call [`check_split_transfer`](Self::check_split_transfer) first.
+    pub async fn transfer_split(
+        &self,
+        destinations: &[Destination],
+        priority: TransferPriority,
+    ) -> Result<Vec<SentTransaction>> {
+        let destinations: Vec<_> = destinations
+            .iter()
+            .map(|destination| {
+                serde_json::json!({
+                    "amount": destination.amount.as_piconero(),
+                    "address": destination.address
+                })
+            })
+            .collect();
+
+        let params = serde_json::json!({
+            "destinations": destinations,
+            "priority": priority.level(),
+            "get_tx_keys": true
+        });
+
+        let result: MultiTransferResult = self.call("transfer_split", params).await?;
+        result.into_transactions()
+    }
+
     /// Transfer all unlocked balance to an address
     ///
     /// # Arguments
     /// * `address` - Destination Monero address
-    /// * `priority` - Transaction priority
+    /// * `priority` - Transaction fee level
     ///
     /// # Returns
-    /// Transaction hash (txid) and fee in XMR
-    pub async fn sweep_all(&self, address: &str, priority: u32) -> Result<(String, f64)> {
+    /// Transaction hash (txid) and fee in XMR of the first sweep transaction
+    pub async fn sweep_all(
+        &self,
+        address: &str,
+        priority: TransferPriority,
+    ) -> Result<(String, f64)> {
+        let sent = self.sweep_outputs(address, priority, None).await?;
+        let first = sent.first().context("No transaction in sweep_all result")?;
+
+        Ok((first.txid.clone(), first.fee.to_f64()))
+    }
+
+    /// Sweep unlocked outputs to an address, e.g. to consolidate dust
+    ///
+    /// # Arguments
+    /// * `address` - Destination Monero address
+    /// * `priority` - Transaction fee level
+    /// * `below_amount` - Only sweep outputs smaller than this; all of them when unset
+    ///
+    /// # Returns
+    /// Every transaction the sweep created
+    pub async fn sweep_outputs(
+        &self,
+        address: &str,
+        priority: TransferPriority,
+        below_amount: Option<Xmr>,
+    ) -> Result<Vec<SentTransaction>> {
         // Validate address first
         if !self.validate_address(address).await? {
             anyhow::bail!("Invalid Monero address: {}", address);
         }
 
-        #[derive(Deserialize)]
-        struct SweepResult {
-            tx_hash_list: Vec<String>,
-            fee_list: Vec<u64>, // in atomic units
-        }
-
-        let params = serde_json::json!({
+        let mut params = serde_json::json!({
             "address": address,
-            "priority": priority,
+            "priority": priority.level(),
             "get_tx_keys": true
         });
+        if let Some(below_amount) = below_amount {
+            params["below_amount"] = serde_json::json!(below_amount.as_piconero());
+        }
 
-        let result: SweepResult = self.call("sweep_all", params).await?;
-
-        // Return first transaction (usually there's only one)
-        let txid = result
-            .tx_hash_list
-            .first()
-            .context("No transaction in sweep_all result")?
-            .clone();
-        let fee = result
-            .fee_list
-            .first()
-            .copied()
-            .context("No fee in sweep_all result")?;
-
-        Ok((txid, Self::atomic_to_xmr(fee)))
+        let result: MultiTransferResult = self.call("sweep_all", params).await?;
+        result.into_transactions()
     }
 
     /// Get transfer details by transaction ID
@@ -507,6 +738,11 @@ impl MoneroWallet {
         Ok(result.height)
     }
 
+    /// Name of the wallet file opened in monero-wallet-rpc
+    pub fn wallet_name(&self) -> &str {
+        &self.wallet_name
+    }
+
     /// Check if wallet is ready and operational
     pub async fn is_ready(&self) -> bool {
         self.get_balance().await.is_ok()
@@ -525,6 +761,47 @@ mod tests {
         assert_eq!(MoneroWallet::xmr_to_atomic(0.5), 500_000_000_000);
     }
 
+    #[test]
+    fn test_multi_transfer_result() {
+        let payload = serde_json::json!({
+            "tx_hash_list": ["aa11", "bb22"],
+            "tx_key_list": ["key1", ""],
+            "amount_list": [1_500_000_000_000u64, 250_000_000_000u64],
+            "fee_list": [30_000_000u64, 20_000_000u64],
+            "multisig_txset": "",
+            "unsigned_txset": ""
+        });
+
+        let result: MultiTransferResult = serde_json::from_value(payload).unwrap();
+        let sent = result.into_transactions().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].tx_key.as_deref(), Some("key1"));
+        assert_eq!(sent[0].amount, Xmr::from_f64(1.5).unwrap());
+        assert_eq!(sent[1].fee, Xmr::from_piconero(20_000_000));
+        // An empty key means the wallet didn't return one
+        assert_eq!(sent[1].tx_key, None);
+
+        let empty: MultiTransferResult =
+            serde_json::from_value(serde_json::json!({"tx_hash_list": []})).unwrap();
+        assert!(empty.into_transactions().is_err());
+    }
+
+    #[test]
+    fn test_check_destinations() {
+        let destination = |amount: f64| {
+            Destination {
+            address: "888tNkZrPN6JsEgekjMnABU4TBzc2Dt29EPAvkRxbANsAnjyPbb3iQ1YBRk1UXcdRsiKc9dhwMVgN5S9cQUiyoogDavup3H".to_string(),
+            amount: Xmr::from_f64(amount).unwrap(),
+        }
+        };
+        let unlocked = Xmr::from_f64(2.0).unwrap();
+
+        assert!(check_destinations(&[destination(1.2), destination(0.8)], unlocked).is_ok());
+        assert!(check_destinations(&[destination(1.2), destination(0.9)], unlocked).is_err());
+        assert!(check_destinations(&[destination(1.0), destination(0.0)], unlocked).is_err());
+        assert!(check_destinations(&[], unlocked).is_err());
+    }
+
     #[tokio::test]
     #[ignore] // Only run with valid Monero wallet RPC
     async fn test_connect_existing() {
@@ -0,0 +1,149 @@
+//! Bitcoin sends signed offline
+//!
+//! The hot wallet's bitcoind funds the send as a PSBT, which is recorded
+//! straight away with the unsigned PSBT on the transaction record for
+//! export. Uploading the signed PSBT finalizes and broadcasts it and clears
+//! the PSBT from the record, within the spend limits. Engine deposits above
+//! `psbt_min_btc` go through the same records, counted by the engine.
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::bitcoin::FundedPsbt;
+use crate::amount::Btc;
+use crate::db::{StoredTradingTransaction, TransactionStatus, TransactionType};
+
+/// Body of `POST /wallets/bitcoin/psbt`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PsbtRequest {
+    pub address: String,
+    pub amount: Btc,
+    /// Feerate in sat/vB, bitcoind's estimate when unset
+    #[serde(default)]
+    pub fee_rate: Option<f64>,
+}
+
+/// Pending record of a send waiting for its signature
+pub fn psbt_record(
+    transaction_type: TransactionType,
+    address: &str,
+    amount: Btc,
+    funded: &FundedPsbt,
+    notes: String,
+) -> StoredTradingTransaction {
+    StoredTradingTransaction {
+        id: None,
+        timestamp: Utc::now(),
+        transaction_type,
+        status: TransactionStatus::Pending,
+        btc_amount: Some(amount.to_f64()),
+        xmr_amount: None,
+        exchange_rate: None,
+        txid: Some(funded.txid.clone()),
+        order_id: None,
+        refid: None,
+        from_address: None,
+        to_address: Some(address.to_string()),
+        fee: Some(funded.fee),
+        notes: Some(notes),
+        error_message: None,
+        completed_at: None,
+        balance_before: None,
+        balance_after: None,
+        replaced_txids: Vec::new(),
+        psbt: Some(funded.psbt.clone()),
+        mid_price: None,
+    }
+}
+
+/// Check that a signed PSBT belongs to `record` and the record still awaits it
+pub fn check_signed(record: &StoredTradingTransaction, signed_txid: &str) -> Result<()> {
+    if record.psbt.is_none() || record.status != TransactionStatus::Pending {
+        anyhow::bail!("Transaction is not waiting for a signature");
+    }
+    if record.txid.as_deref() != Some(signed_txid) {
+        anyhow::bail!(
+            "Signed PSBT spends into {}, not the exported transaction {}",
+            signed_txid,
+            record.txid.as_deref().unwrap_or("(none)")
+        );
+    }
+
+    Ok(())
+}
+
+/// Record the broadcast of the signed transaction
+///
+/// Operator sends are done once broadcast; deposits stay pending until the
+/// exchange credits them.
+pub fn mark_broadcast(record: &mut StoredTradingTransaction, txid: String) {
+    record.psbt = None;
+    record.txid = Some(txid);
+    if record.transaction_type == TransactionType::InternalTransfer {
+        record.status = TransactionStatus::Completed;
+        record.completed_at = Some(Utc::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn funded() -> FundedPsbt {
+        FundedPsbt {
+            psbt: "cHNidP8BAHECAAAAAQ==".to_string(),
+            txid: "3f4c5a2e9b0d8e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d4e3f".to_string(),
+            fee: 0.0000141,
+        }
+    }
+
+    #[test]
+    fn test_signed_psbt_must_match_the_record() {
+        let record = psbt_record(
+            TransactionType::InternalTransfer,
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            Btc::from_sats(2_500_000),
+            &funded(),
+            "Cold-signed send".to_string(),
+        );
+        assert_eq!(record.psbt.as_deref(), Some("cHNidP8BAHECAAAAAQ=="));
+        assert_eq!(record.btc_amount, Some(0.025));
+
+        assert!(check_signed(&record, &funded().txid).is_ok());
+        assert!(check_signed(&record, &"aa".repeat(32)).is_err());
+
+        // Nothing to sign once broadcast
+        let mut broadcast = record.clone();
+        mark_broadcast(&mut broadcast, funded().txid);
+        assert!(check_signed(&broadcast, &funded().txid).is_err());
+    }
+
+    #[test]
+    fn test_mark_broadcast() {
+        let mut send = psbt_record(
+            TransactionType::InternalTransfer,
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            Btc::from_sats(2_500_000),
+            &funded(),
+            "Cold-signed send".to_string(),
+        );
+        mark_broadcast(&mut send, funded().txid);
+        assert_eq!(send.psbt, None);
+        assert_eq!(send.status, TransactionStatus::Completed);
+        assert!(send.completed_at.is_some());
+
+        // A deposit completes when the exchange credits it
+        let mut deposit = psbt_record(
+            TransactionType::BitcoinDeposit,
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            Btc::from_sats(2_500_000),
+            &funded(),
+            "Depositing".to_string(),
+        );
+        mark_broadcast(&mut deposit, funded().txid);
+        assert_eq!(deposit.psbt, None);
+        assert_eq!(deposit.status, TransactionStatus::Pending);
+    }
+}
@@ -0,0 +1,255 @@
+//! Internal transfers between the operator's own wallets
+//!
+//! A transfer is validated against the source wallet (name, destination
+//! address network, spendable balance) and the optional destination
+//! allowlist, sent, and recorded as an `InternalTransfer` transaction with
+//! the source balance before and after.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::amount::{Btc, Xmr};
+use crate::db::{StoredTradingTransaction, TransactionStatus, TransactionType};
+
+/// Asset moved by an internal transfer
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferAsset {
+    Btc,
+    Xmr,
+}
+
+impl TransferAsset {
+    /// Subtract amounts in exact base units, returning the result in coins
+    fn checked_sub(self, from: f64, amounts: &[f64]) -> Option<f64> {
+        match self {
+            Self::Btc => amounts
+                .iter()
+                .try_fold(Btc::from_f64(from)?, |acc, &amount| {
+                    acc.checked_sub(Btc::from_f64(amount)?)
+                })
+                .map(Btc::to_f64),
+            Self::Xmr => amounts
+                .iter()
+                .try_fold(Xmr::from_f64(from)?, |acc, &amount| {
+                    acc.checked_sub(Xmr::from_f64(amount)?)
+                })
+                .map(Xmr::to_f64),
+        }
+    }
+}
+
+/// Body of `POST /wallets/transfer`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TransferRequest {
+    pub asset: TransferAsset,
+    /// Name of the managed wallet to send from
+    pub from_wallet: String,
+    pub to_address: String,
+    /// Amount in BTC or XMR
+    pub amount: f64,
+}
+
+/// Fail unless `amount` is positive and covered by the spendable balance
+pub fn check_funds(asset: TransferAsset, amount: f64, available: f64) -> Result<()> {
+    if !amount.is_finite() || amount <= 0.0 {
+        anyhow::bail!("Transfer amount must be positive");
+    }
+
+    let remaining = asset
+        .checked_sub(available, &[amount])
+        .context("Invalid transfer amount")?;
+    if remaining < 0.0 {
+        anyhow::bail!(
+            "Insufficient funds: {} {:?} requested, {} available",
+            amount,
+            asset,
+            available
+        );
+    }
+
+    Ok(())
+}
+
+/// Fail unless `address` is an address of `asset` on `network` and, when
+/// `allowlist` isn't empty, one of its entries
+///
+/// `network` is named as in the `[bitcoin]` or `[monero]` config section.
+pub fn check_destination(
+    asset: TransferAsset,
+    address: &str,
+    network: &str,
+    allowlist: &[String],
+) -> Result<()> {
+    let on_network = match asset {
+        TransferAsset::Btc => {
+            let network = match network.to_lowercase().as_str() {
+                "mainnet" | "main" | "bitcoin" => bitcoin::Network::Bitcoin,
+                "testnet" | "testnet3" | "test" => bitcoin::Network::Testnet,
+                "testnet4" => bitcoin::Network::Testnet4,
+                "signet" => bitcoin::Network::Signet,
+                "regtest" => bitcoin::Network::Regtest,
+                other => anyhow::bail!("Unknown Bitcoin network: {}", other),
+            };
+            address
+                .parse::<bitcoin::Address<bitcoin::address::NetworkUnchecked>>()
+                .is_ok_and(|address| address.is_valid_for_network(network))
+        }
+        TransferAsset::Xmr => {
+            let network = match network.to_lowercase().as_str() {
+                "mainnet" => monero::Network::Mainnet,
+                "stagenet" => monero::Network::Stagenet,
+                "testnet" => monero::Network::Testnet,
+                other => anyhow::bail!("Unknown Monero network: {}", other),
+            };
+            address
+                .parse::<monero::Address>()
+                .is_ok_and(|address| address.network == network)
+        }
+    };
+    if !on_network {
+        anyhow::bail!(
+            "'{}' is not a valid {:?} address for {}",
+            address,
+            asset,
+            network
+        );
+    }
+
+    if !allowlist.is_empty() && !allowlist.iter().any(|allowed| allowed == address) {
+        anyhow::bail!("'{}' is not in wallets.transfer_allowlist", address);
+    }
+    Ok(())
+}
+
+/// Transaction record for a sent transfer
+///
+/// `balance_after` is the tracked balance: `balance_before` less the amount
+/// and fee, independent of when the wallet next reports its balance.
+pub fn transfer_record(
+    request: &TransferRequest,
+    txid: String,
+    fee: Option<f64>,
+    balance_before: f64,
+) -> Result<StoredTradingTransaction> {
+    let balance_after = request
+        .asset
+        .checked_sub(balance_before, &[request.amount, fee.unwrap_or(0.0)])
+        .context("Balance overflow")?;
+    let now = Utc::now();
+
+    let (btc_amount, xmr_amount) = match request.asset {
+        TransferAsset::Btc => (Some(request.amount), None),
+        TransferAsset::Xmr => (None, Some(request.amount)),
+    };
+
+    Ok(StoredTradingTransaction {
+        id: None,
+        timestamp: now,
+        transaction_type: TransactionType::InternalTransfer,
+        status: TransactionStatus::Completed,
+        btc_amount,
+        xmr_amount,
+        exchange_rate: None,
+        txid: Some(txid),
+        order_id: None,
+        refid: None,
+        from_address: None,
+        to_address: Some(request.to_address.clone()),
+        fee,
+        notes: Some(format!(
+            "Internal transfer from wallet '{}'",
+            request.from_wallet
+        )),
+        error_message: None,
+        completed_at: Some(now),
+        balance_before: Some(balance_before),
+        balance_after: Some(balance_after),
+        replaced_txids: Vec::new(),
+        psbt: None,
+        mid_price: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(asset: TransferAsset, amount: f64) -> TransferRequest {
+        TransferRequest {
+            asset,
+            from_wallet: "eigenix".to_string(),
+            to_address: "bc1qexampleaddress".to_string(),
+            amount,
+        }
+    }
+
+    #[test]
+    fn test_transfer_records_debit_of_source_wallet() {
+        let request = request(TransferAsset::Btc, 0.3);
+        let record = transfer_record(&request, "abcd".to_string(), Some(0.00001), 1.0).unwrap();
+
+        assert_eq!(record.transaction_type, TransactionType::InternalTransfer);
+        assert_eq!(record.status, TransactionStatus::Completed);
+        assert_eq!(record.btc_amount, Some(0.3));
+        assert_eq!(record.xmr_amount, None);
+        assert_eq!(record.txid.as_deref(), Some("abcd"));
+        assert_eq!(record.to_address.as_deref(), Some("bc1qexampleaddress"));
+        assert_eq!(record.balance_before, Some(1.0));
+        // Exact to the satoshi, not 0.69999...
+        assert_eq!(record.balance_after, Some(0.69999));
+    }
+
+    #[test]
+    fn test_xmr_transfer_without_fee() {
+        let record = transfer_record(
+            &request(TransferAsset::Xmr, 2.5),
+            "ff".to_string(),
+            None,
+            10.0,
+        )
+        .unwrap();
+
+        assert_eq!(record.xmr_amount, Some(2.5));
+        assert_eq!(record.balance_after, Some(7.5));
+    }
+
+    #[test]
+    fn test_check_funds() {
+        assert!(check_funds(TransferAsset::Btc, 0.5, 1.0).is_ok());
+        assert!(check_funds(TransferAsset::Btc, 1.0, 1.0).is_ok());
+        assert!(check_funds(TransferAsset::Btc, 1.00000001, 1.0).is_err());
+        assert!(check_funds(TransferAsset::Xmr, 0.0, 1.0).is_err());
+        assert!(check_funds(TransferAsset::Xmr, -1.0, 1.0).is_err());
+        assert!(check_funds(TransferAsset::Xmr, f64::NAN, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_check_destination() {
+        const BTC_MAINNET: &str = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+        const BTC_TESTNET: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+        const XMR_MAINNET: &str = "888tNkZrPN6JsEgekjMnABU4TBzc2Dt29EPAvkRxbANsAnjyPbb3iQ1YBRk1UXcdRsiKc9dhwMVgN5S9cQUiyoogDavup3H";
+        const XMR_STAGENET: &str = "52z3wMo796DLvkHTcnbHrd97YxWFA7ji4K4QAbcS891jVkp1pwkonb1CJAwNUz6Vsi4C5BzYoBssv8fM6rEUNdzYKQLzVV1";
+        let btc = TransferAsset::Btc;
+        let xmr = TransferAsset::Xmr;
+
+        assert!(check_destination(btc, BTC_MAINNET, "mainnet", &[]).is_ok());
+        assert!(check_destination(btc, BTC_TESTNET, "mainnet", &[]).is_err());
+        assert!(check_destination(btc, BTC_TESTNET, "testnet", &[]).is_ok());
+        assert!(check_destination(btc, "bc1qnotanaddress", "mainnet", &[]).is_err());
+        assert!(check_destination(btc, XMR_MAINNET, "mainnet", &[]).is_err());
+
+        assert!(check_destination(xmr, XMR_MAINNET, "mainnet", &[]).is_ok());
+        assert!(check_destination(xmr, XMR_STAGENET, "mainnet", &[]).is_err());
+        assert!(check_destination(xmr, XMR_STAGENET, "stagenet", &[]).is_ok());
+        assert!(check_destination(xmr, XMR_MAINNET, "moonnet", &[]).is_err());
+
+        // With an allowlist, only its entries are accepted
+        let allowlist = vec![BTC_MAINNET.to_string()];
+        assert!(check_destination(btc, BTC_MAINNET, "mainnet", &allowlist).is_ok());
+        let error = check_destination(xmr, XMR_MAINNET, "mainnet", &allowlist).unwrap_err();
+        assert!(error.to_string().contains("transfer_allowlist"));
+    }
+}
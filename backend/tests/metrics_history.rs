@@ -0,0 +1,240 @@
+/// Integration tests for metrics history windows and import
+///
+/// Run with: cargo nextest run --test metrics_history --ignored
+///
+/// These tests require a SurrealDB instance on 127.0.0.1:8001.
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, DurationRound, Utc};
+use eigenix_backend::db::aggregate::Aggregate;
+use eigenix_backend::db::{MetricsDatabase, MetricsExport, StoredBitcoinMetrics};
+use eigenix_backend::metrics::BitcoinMetrics;
+use tokio::time::{sleep, Duration};
+
+async fn setup_test_db() -> Result<MetricsDatabase> {
+    let db =
+        MetricsDatabase::connect("127.0.0.1:8001", "test_eigenix", "test_metrics_history").await?;
+    Ok(db)
+}
+
+fn bitcoin_metrics(blocks: u64) -> BitcoinMetrics {
+    BitcoinMetrics {
+        blocks,
+        headers: blocks,
+        verification_progress: 1.0,
+        size_on_disk: 0,
+        wallet_balance: None,
+        network: None,
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires database
+async fn test_fixed_window_returns_only_rows_inside_it() {
+    let db = match setup_test_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            println!("Skipping test - database not available: {}", e);
+            return;
+        }
+    };
+
+    // One row before the window, two inside it, one after
+    db.store_bitcoin_metrics(&bitcoin_metrics(100))
+        .await
+        .unwrap();
+    sleep(Duration::from_millis(50)).await;
+
+    let from = Utc::now();
+    db.store_bitcoin_metrics(&bitcoin_metrics(101))
+        .await
+        .unwrap();
+    db.store_bitcoin_metrics(&bitcoin_metrics(102))
+        .await
+        .unwrap();
+    let to = Utc::now();
+
+    sleep(Duration::from_millis(50)).await;
+    db.store_bitcoin_metrics(&bitcoin_metrics(103))
+        .await
+        .unwrap();
+
+    let rows = db.get_bitcoin_history(from, to).await.unwrap();
+    let blocks: Vec<u64> = rows.iter().map(|row| row.blocks).collect();
+
+    assert_eq!(blocks, vec![101, 102]);
+    assert!(rows
+        .iter()
+        .all(|row| row.timestamp >= from && row.timestamp <= to));
+}
+
+#[tokio::test]
+#[ignore] // Requires database
+async fn test_import_keeps_original_timestamps_and_is_idempotent() {
+    let db = match setup_test_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            println!("Skipping test - database not available: {}", e);
+            return;
+        }
+    };
+
+    // A window in the past that no other test writes to; unique per run
+    let start = (Utc::now() - ChronoDuration::days(400))
+        .duration_trunc(ChronoDuration::seconds(1))
+        .unwrap();
+    let times: Vec<String> = (0..3)
+        .map(|i| (start + ChronoDuration::minutes(i)).to_rfc3339())
+        .collect();
+
+    let file = format!(
+        r#"{{
+            "bitcoin": [
+                {{"timestamp": "{t0}", "blocks": 800000, "headers": 800000, "verification_progress": 1.0, "size_on_disk": 1, "wallet_balance": null}},
+                {{"timestamp": "{t1}", "blocks": 800001, "headers": 800001, "verification_progress": 1.0, "size_on_disk": 1, "wallet_balance": 0.5}}
+            ],
+            "containers": [
+                {{"timestamp": "{t2}", "name": "bitcoind", "up": true, "restarts": 0, "uptime_seconds": 60}},
+                {{"timestamp": "{t2}", "name": "monerod", "up": true, "restarts": 1, "uptime_seconds": 30}}
+            ]
+        }}"#,
+        t0 = times[0],
+        t1 = times[1],
+        t2 = times[2],
+    );
+    let export: MetricsExport = serde_json::from_str(&file).unwrap();
+
+    let summary = db.import_metrics(export).await.unwrap();
+    assert_eq!(summary.bitcoin, 2);
+    assert_eq!(summary.containers, 2);
+    assert_eq!(summary.monero, 0);
+
+    let end = start + ChronoDuration::minutes(5);
+    let rows = db.get_bitcoin_history(start, end).await.unwrap();
+    let stored: Vec<(String, u64)> = rows
+        .iter()
+        .map(|row| (row.timestamp.to_rfc3339(), row.blocks))
+        .collect();
+    assert_eq!(
+        stored,
+        vec![(times[0].clone(), 800000), (times[1].clone(), 800001)]
+    );
+
+    let containers = db.get_all_container_history(start, end).await.unwrap();
+    assert_eq!(containers.len(), 2);
+
+    // Re-importing the same file inserts nothing
+    let export: MetricsExport = serde_json::from_str(&file).unwrap();
+    let summary = db.import_metrics(export).await.unwrap();
+    assert_eq!(summary.bitcoin, 0);
+    assert_eq!(summary.containers, 0);
+    assert_eq!(db.get_bitcoin_history(start, end).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+#[ignore] // Requires database
+async fn test_aggregate_series_buckets() {
+    let db = match setup_test_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            println!("Skipping test - database not available: {}", e);
+            return;
+        }
+    };
+
+    // An hour in the past that no other test writes to
+    let start = (Utc::now() - ChronoDuration::days(500))
+        .duration_trunc(ChronoDuration::hours(1))
+        .unwrap();
+    let rows: Vec<StoredBitcoinMetrics> = [(0, 10), (1, 20), (5, 30), (7, 50)]
+        .into_iter()
+        .map(|(minute, blocks)| StoredBitcoinMetrics {
+            timestamp: start + ChronoDuration::minutes(minute),
+            blocks,
+            headers: blocks,
+            verification_progress: 1.0,
+            size_on_disk: 0,
+            wallet_balance: None,
+            network: None,
+        })
+        .collect();
+    db.import_bitcoin_history(rows).await.unwrap();
+
+    let end = start + ChronoDuration::minutes(10);
+    let bucket = ChronoDuration::minutes(5);
+    let later = start + bucket;
+    let cases = [
+        (Aggregate::Avg, [15.0, 40.0]),
+        (Aggregate::Min, [10.0, 30.0]),
+        (Aggregate::Max, [20.0, 50.0]),
+        (Aggregate::Last, [20.0, 50.0]),
+    ];
+    for (aggregate, [first, second]) in cases {
+        let series = db
+            .aggregate_series("bitcoin_metrics", "blocks", aggregate, bucket, start, end)
+            .await
+            .unwrap();
+        assert_eq!(
+            series,
+            vec![(start, first), (later, second)],
+            "{:?}",
+            aggregate
+        );
+    }
+
+    // Unset values are skipped rather than counted
+    let balances = db
+        .aggregate_series(
+            "bitcoin_metrics",
+            "wallet_balance",
+            Aggregate::Avg,
+            bucket,
+            start,
+            end,
+        )
+        .await
+        .unwrap();
+    assert!(balances.is_empty());
+}
+
+#[tokio::test]
+#[ignore] // Requires database
+async fn test_export_pages_cover_the_window_once() {
+    let db = match setup_test_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            println!("Skipping test - database not available: {}", e);
+            return;
+        }
+    };
+
+    // A window in the past that no other test writes to; two rows share a
+    // timestamp, as the rows of one container collection do
+    let start = (Utc::now() - ChronoDuration::days(600))
+        .duration_trunc(ChronoDuration::hours(1))
+        .unwrap();
+    let rows: Vec<StoredBitcoinMetrics> = [(0, 1), (1, 2), (1, 3), (2, 4), (3, 5)]
+        .into_iter()
+        .map(|(minute, blocks)| StoredBitcoinMetrics {
+            timestamp: start + ChronoDuration::minutes(minute),
+            blocks,
+            headers: blocks,
+            verification_progress: 1.0,
+            size_on_disk: 0,
+            wallet_balance: None,
+            network: None,
+        })
+        .collect();
+    db.import_bitcoin_history(rows).await.unwrap();
+
+    let end = start + ChronoDuration::minutes(10);
+    let mut blocks = Vec::new();
+    for page in 0..3 {
+        let rows: Vec<StoredBitcoinMetrics> = db
+            .export_page("bitcoin_metrics", start, end, page * 2, 2)
+            .await
+            .unwrap();
+        blocks.extend(rows.iter().map(|row| row.blocks));
+    }
+    blocks.sort();
+    assert_eq!(blocks, vec![1, 2, 3, 4, 5]);
+}
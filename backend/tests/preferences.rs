@@ -0,0 +1,144 @@
+/// Integration tests for the `/preferences` endpoints
+///
+/// Run with: cargo nextest run --test preferences --ignored
+///
+/// These tests require a SurrealDB instance on 127.0.0.1:8001.
+use anyhow::Result;
+use axum::{extract::Request, middleware::Next, response::Response, Router};
+use eigenix_backend::auth::{Caller, Principal, Role};
+use eigenix_backend::db::MetricsDatabase;
+use eigenix_backend::preferences::{BalanceUnit, FiatCurrency, Theme, UserPreferences};
+use eigenix_backend::routes::preferences::preferences_routes;
+use std::future::IntoFuture;
+
+async fn setup_test_db() -> Result<MetricsDatabase> {
+    let db = MetricsDatabase::connect("127.0.0.1:8001", "test_eigenix", "test_preferences").await?;
+    Ok(db)
+}
+
+/// Stand-in for the auth middleware: any bearer token is an operator key
+/// named after the token
+async fn authenticate(mut request: Request, next: Next) -> Response {
+    let token = request
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    if let Some(name) = token {
+        request.extensions_mut().insert(Caller {
+            principal: Principal::ApiKey(name),
+            role: Role::Operator,
+        });
+    }
+    next.run(request).await
+}
+
+/// Serve the preferences routes on a random local port
+async fn serve(db: MetricsDatabase) -> String {
+    let app = Router::new()
+        .nest("/preferences", preferences_routes())
+        .layer(axum::middleware::from_fn(authenticate))
+        .with_state(db);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(axum::serve(listener, app).into_future());
+
+    format!("http://{}/preferences", addr)
+}
+
+#[tokio::test]
+#[ignore] // Requires database
+async fn test_preferences_round_trip() {
+    let db = match setup_test_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            println!("Skipping test - database not available: {}", e);
+            return;
+        }
+    };
+    let url = serve(db).await;
+    let client = reqwest::Client::new();
+    // A fresh token per run so earlier runs don't leak in
+    let token = format!("test-{}", chrono::Utc::now().timestamp_nanos_opt().unwrap());
+
+    // Nothing saved yet: defaults
+    let initial: UserPreferences = client
+        .get(&url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(initial, UserPreferences::default());
+
+    let saved = UserPreferences {
+        unit: BalanceUnit::Sats,
+        theme: Theme::Light,
+        default_interval_minutes: 1440,
+        fiat_currency: FiatCurrency::Eur,
+    };
+    let response = client
+        .put(&url)
+        .bearer_auth(&token)
+        .json(&saved)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let loaded: UserPreferences = client
+        .get(&url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(loaded, saved);
+
+    // Another token doesn't see them
+    let other: UserPreferences = client
+        .get(&url)
+        .bearer_auth(format!("{}-other", token))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(other, UserPreferences::default());
+
+    // Anonymous callers can't write
+    let response = client.put(&url).json(&saved).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    // Unknown unit is rejected and the saved preferences are untouched
+    let response = client
+        .put(&url)
+        .bearer_auth(&token)
+        .json(&serde_json::json!({
+            "unit": "bits",
+            "theme": "dark",
+            "default_interval_minutes": 5,
+            "fiat_currency": "USD"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let loaded: UserPreferences = client
+        .get(&url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(loaded, saved);
+}
@@ -0,0 +1,118 @@
+/// Integration tests for the spend records behind the daily limits
+///
+/// Run with: cargo nextest run --test spends --ignored
+///
+/// These tests require a SurrealDB instance on 127.0.0.1:8001.
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use eigenix_backend::db::MetricsDatabase;
+use eigenix_backend::wallets::limits::{
+    LimitExceeded, SpendLimiter, SpendLimitsConfig, SpendRecord,
+};
+use eigenix_backend::wallets::transfer::TransferAsset;
+
+async fn setup_test_db() -> Result<MetricsDatabase> {
+    let db = MetricsDatabase::connect("127.0.0.1:8001", "test_eigenix", "test_spends").await?;
+    Ok(db)
+}
+
+#[tokio::test]
+#[ignore] // Requires database
+async fn test_spent_since_sums_one_asset() {
+    let db = match setup_test_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            println!("Skipping test - database not available: {}", e);
+            return;
+        }
+    };
+    let start = Utc::now();
+    for (asset, amount) in [
+        (TransferAsset::Btc, 0.25),
+        (TransferAsset::Btc, 0.5),
+        (TransferAsset::Xmr, 10.0),
+    ] {
+        let record = SpendRecord {
+            timestamp: Utc::now(),
+            asset,
+            amount,
+            source: "test".to_string(),
+            txid: None,
+        };
+        db.store_spend(&record).await.unwrap();
+    }
+
+    let since = start - Duration::milliseconds(1);
+    assert_eq!(
+        db.get_spent_since(TransferAsset::Btc, since).await.unwrap(),
+        0.75
+    );
+    assert_eq!(
+        db.get_spent_since(TransferAsset::Xmr, since).await.unwrap(),
+        10.0
+    );
+    assert_eq!(
+        db.get_spent_since(TransferAsset::Btc, Utc::now() + Duration::seconds(1))
+            .await
+            .unwrap(),
+        0.0
+    );
+}
+
+#[tokio::test]
+#[ignore] // Requires database
+async fn test_sends_are_counted_from_their_reservation() {
+    // A database of its own, so other tests' spends don't add up here
+    let database = format!("test_spends_reserved_{}", Utc::now().timestamp_millis());
+    let db = match MetricsDatabase::connect("127.0.0.1:8001", "test_eigenix", &database).await {
+        Ok(db) => db,
+        Err(e) => {
+            println!("Skipping test - database not available: {}", e);
+            return;
+        }
+    };
+    let limiter = SpendLimiter::new(
+        SpendLimitsConfig {
+            xmr_per_day: 1_000_000.0,
+            ..SpendLimitsConfig::default()
+        },
+        db.clone(),
+    );
+    let since = Utc::now() - Duration::milliseconds(1);
+    let spent = || db.get_spent_since(TransferAsset::Xmr, since);
+
+    // Counted at the reserved amount while the send goes out
+    let reservation = limiter.reserve(TransferAsset::Xmr, 5.0).await.unwrap();
+    assert_eq!(spent().await.unwrap(), 5.0);
+    // Then at what was actually sent
+    reservation.commit(4.5, "test", None).await;
+    assert_eq!(spent().await.unwrap(), 4.5);
+
+    // A send that never went out stops counting once its reservation is dropped
+    let reservation = limiter.reserve(TransferAsset::Xmr, 2.0).await.unwrap();
+    assert_eq!(spent().await.unwrap(), 6.5);
+    drop(reservation);
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    assert_eq!(spent().await.unwrap(), 4.5);
+
+    // A send still going out doesn't hold up the next one, but counts against it
+    let strict = SpendLimiter::new(
+        SpendLimitsConfig {
+            xmr_per_day: 10.0,
+            ..SpendLimitsConfig::default()
+        },
+        db.clone(),
+    );
+    let outstanding = strict.reserve(TransferAsset::Xmr, 3.0).await.unwrap();
+    let next = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        strict.reserve(TransferAsset::Xmr, 2.0),
+    )
+    .await
+    .expect("an outstanding reservation shouldn't block the next send")
+    .unwrap();
+    let over = strict.reserve(TransferAsset::Xmr, 1.0).await.unwrap_err();
+    assert!(over.downcast_ref::<LimitExceeded>().is_some(), "{:#}", over);
+    next.commit(2.0, "test", None).await;
+    outstanding.commit(3.0, "test", None).await;
+}
@@ -16,7 +16,9 @@ use chrono::{Duration as ChronoDuration, Utc};
 use eigenix_backend::db::{
     MetricsDatabase, StoredTradingTransaction, TransactionStatus, TransactionType,
 };
+use eigenix_backend::services::KrakenClient;
 use eigenix_backend::trading::{TradingConfig, TradingEngine};
+use eigenix_backend::wallets::transfer::{self, TransferAsset, TransferRequest};
 
 mod common;
 use common::TestConfig;
@@ -38,8 +40,7 @@ fn create_test_engine() -> TradingEngine {
 
     TradingEngine::new(
         shared_config,
-        kraken_key,
-        kraken_secret,
+        KrakenClient::new(kraken_key, kraken_secret).into(),
         btc_url,
         btc_cookie,
         wallet_name.clone(),
@@ -220,6 +221,11 @@ async fn test_database_transaction_tracking() {
         notes: Some("Test deposit".to_string()),
         error_message: None,
         completed_at: None,
+        balance_before: None,
+        balance_after: None,
+        replaced_txids: Vec::new(),
+        psbt: None,
+        mid_price: None,
     };
 
     // Store transaction
@@ -243,7 +249,7 @@ async fn test_database_transaction_tracking() {
     assert_eq!(retrieved.txid, Some("test_txid_123".to_string()));
 
     // Complete transaction
-    db.complete_trading_transaction(&transaction_id, None, None)
+    db.complete_trading_transaction(&transaction_id, None, None, None)
         .await
         .expect("Should complete transaction");
 
@@ -257,6 +263,53 @@ async fn test_database_transaction_tracking() {
     assert!(completed.completed_at.is_some());
 }
 
+#[tokio::test]
+#[ignore] // Requires database
+async fn test_internal_transfer_is_recorded() {
+    let db = match setup_test_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("⚠️  Skipping test: database not available: {}", e);
+            return;
+        }
+    };
+
+    let request = TransferRequest {
+        asset: TransferAsset::Xmr,
+        from_wallet: "eigenix".to_string(),
+        to_address: "test_cold_address".to_string(),
+        amount: 1.25,
+    };
+    let record = transfer::transfer_record(
+        &request,
+        "test_transfer_txid".to_string(),
+        Some(0.0001),
+        5.0,
+    )
+    .expect("Should build transfer record");
+
+    let id = db
+        .store_trading_transaction(&record)
+        .await
+        .expect("Should store transfer");
+    let stored = db
+        .get_trading_transaction(&id)
+        .await
+        .expect("Should retrieve transfer")
+        .expect("Transfer should exist");
+
+    assert_eq!(stored.transaction_type, TransactionType::InternalTransfer);
+    assert_eq!(stored.xmr_amount, Some(1.25));
+    assert_eq!(stored.balance_before, Some(5.0));
+    assert_eq!(stored.balance_after, Some(3.7499));
+
+    let transfers = db
+        .get_trading_transactions_by_type(TransactionType::InternalTransfer)
+        .await
+        .expect("Should query transfers");
+    assert!(transfers.iter().any(|tx| tx.id == stored.id));
+}
+
 #[tokio::test]
 #[ignore] // Requires database
 async fn test_database_transaction_queries() {
@@ -297,6 +350,11 @@ async fn test_database_transaction_queries() {
             notes: Some(format!("Test transaction {}", i)),
             error_message: None,
             completed_at: if i < 3 { Some(now) } else { None },
+            balance_before: None,
+            balance_after: None,
+            replaced_txids: Vec::new(),
+            psbt: None,
+            mid_price: None,
         };
 
         db.store_trading_transaction(&transaction)
@@ -380,6 +438,11 @@ async fn test_database_transaction_failure() {
         notes: Some("Test trade".to_string()),
         error_message: None,
         completed_at: None,
+        balance_before: None,
+        balance_after: None,
+        replaced_txids: Vec::new(),
+        psbt: None,
+        mid_price: None,
     };
 
     let transaction_id = db
@@ -489,6 +552,11 @@ async fn test_stored_transaction_creation() {
         notes: Some("Successful trade".to_string()),
         error_message: None,
         completed_at: Some(Utc::now()),
+        balance_before: None,
+        balance_after: None,
+        replaced_txids: Vec::new(),
+        psbt: None,
+        mid_price: None,
     };
 
     // Verify all fields are accessible
@@ -551,10 +619,9 @@ async fn test_multiple_engines_with_shared_config() {
     let (kraken_key, kraken_secret) = TestConfig::kraken();
 
     // Create two engines sharing the same config
-    let engine1 = TradingEngine::new(
+    let engine1: TradingEngine = TradingEngine::new(
         shared_config.clone(),
-        kraken_key.clone(),
-        kraken_secret.clone(),
+        KrakenClient::new(kraken_key.clone(), kraken_secret.clone()).into(),
         btc_url.clone(),
         btc_cookie.clone(),
         wallet_name.clone(),
@@ -563,10 +630,9 @@ async fn test_multiple_engines_with_shared_config() {
         xmr_password.clone(),
     );
 
-    let engine2 = TradingEngine::new(
+    let engine2: TradingEngine = TradingEngine::new(
         shared_config.clone(),
-        kraken_key,
-        kraken_secret,
+        KrakenClient::new(kraken_key, kraken_secret).into(),
         btc_url,
         btc_cookie,
         wallet_name,
@@ -629,6 +695,11 @@ async fn test_concurrent_transaction_creation() {
                 notes: Some(format!("Concurrent test {}", i)),
                 error_message: None,
                 completed_at: None,
+                balance_before: None,
+                balance_after: None,
+                replaced_txids: Vec::new(),
+                psbt: None,
+                mid_price: None,
             };
 
             db_clone.store_trading_transaction(&transaction).await
@@ -654,3 +725,79 @@ async fn test_concurrent_transaction_creation() {
 
     assert!(recent.len() >= 10, "Should have at least 10 transactions");
 }
+
+#[tokio::test]
+#[ignore] // Requires database
+async fn test_rebalance_blocked_at_pending_cap() {
+    let db = match setup_test_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("⚠️  Skipping test: database not available: {}", e);
+            return;
+        }
+    };
+
+    // Other tests may leave pending rows behind, so set the cap relative to them
+    let existing = db
+        .get_trading_transactions_by_status(TransactionStatus::Pending)
+        .await
+        .expect("Should query pending transactions")
+        .len();
+
+    let config = TradingConfig {
+        max_pending_transactions: existing + 3,
+        ..TradingConfig::default()
+    };
+    let engine = create_test_engine().with_database(db.clone());
+    engine.config.update(config).expect("Config should be valid");
+
+    assert!(engine.ensure_pending_capacity().await.is_ok());
+
+    // Seed pending transactions up to the cap
+    let mut ids = Vec::new();
+    for i in 0..3 {
+        let transaction = StoredTradingTransaction {
+            id: None,
+            timestamp: Utc::now(),
+            transaction_type: TransactionType::BitcoinDeposit,
+            status: TransactionStatus::Pending,
+            btc_amount: Some(0.01),
+            xmr_amount: None,
+            exchange_rate: None,
+            txid: Some(format!("pending_cap_txid_{}", i)),
+            order_id: None,
+            refid: None,
+            from_address: None,
+            to_address: None,
+            fee: None,
+            notes: Some("Pending cap test".to_string()),
+            error_message: None,
+            completed_at: None,
+            balance_before: None,
+            balance_after: None,
+            replaced_txids: Vec::new(),
+            psbt: None,
+            mid_price: None,
+        };
+        ids.push(
+            db.store_trading_transaction(&transaction)
+                .await
+                .expect("Should store transaction"),
+        );
+    }
+
+    assert!(
+        engine.ensure_pending_capacity().await.is_err(),
+        "Rebalance should be blocked at the pending cap"
+    );
+
+    // Resolving one of them frees capacity again
+    db.complete_trading_transaction(&ids[0], None, None, None)
+        .await
+        .expect("Should complete transaction");
+    assert!(engine.ensure_pending_capacity().await.is_ok());
+
+    for id in &ids[1..] {
+        let _ = db.fail_trading_transaction(id, "test cleanup".to_string()).await;
+    }
+}
@@ -0,0 +1,67 @@
+/// Integration tests for the trading lock against SurrealDB
+///
+/// Run with: cargo nextest run --test trading_lock --ignored
+///
+/// These tests require a SurrealDB instance on 127.0.0.1:8001.
+use std::time::Duration;
+
+use anyhow::Result;
+use eigenix_backend::db::MetricsDatabase;
+use eigenix_backend::trading::lock::TradingLock;
+
+async fn setup_test_db() -> Result<MetricsDatabase> {
+    // A fresh database per run, so no lease is left over from earlier runs
+    let database = format!(
+        "test_trading_lock_{}",
+        chrono::Utc::now().timestamp_millis()
+    );
+    let db = MetricsDatabase::connect("127.0.0.1:8001", "test_eigenix", &database).await?;
+    Ok(db)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[ignore] // Requires database
+async fn test_racing_instances_get_one_lease() {
+    let db = match setup_test_db().await {
+        Ok(db) => db,
+        Err(e) => {
+            println!("Skipping test - database not available: {}", e);
+            return;
+        }
+    };
+
+    let racers: Vec<_> = (0..8)
+        .map(|i| {
+            let lock = TradingLock::new(
+                db.clone(),
+                format!("host-{}:100", i),
+                Duration::from_secs(60),
+            );
+            tokio::spawn(async move { lock.acquire().await })
+        })
+        .collect();
+
+    let mut winners = Vec::new();
+    for racer in racers {
+        if let Ok(lease) = racer.await.unwrap() {
+            winners.push(lease.holder);
+        }
+    }
+    assert_eq!(winners.len(), 1, "{:?}", winners);
+
+    let stored = db.get_trading_lock().await.unwrap().unwrap();
+    assert_eq!(stored.holder, winners[0]);
+
+    // The winner renews without losing its acquisition time; others still can't
+    let winner = TradingLock::new(db.clone(), winners[0].clone(), Duration::from_secs(60));
+    let renewed = winner.acquire().await.unwrap();
+    assert_eq!(renewed.acquired_at, stored.acquired_at);
+    let loser = TradingLock::new(db.clone(), "host-other:100", Duration::from_secs(60));
+    assert!(loser.acquire().await.is_err());
+
+    // Releasing only works for the holder
+    loser.release().await.unwrap();
+    assert!(db.get_trading_lock().await.unwrap().is_some());
+    winner.release().await.unwrap();
+    assert_eq!(loser.acquire().await.unwrap().holder, "host-other:100");
+}
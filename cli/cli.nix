@@ -28,13 +28,17 @@
       packages.eigenix-cli = rustPlatform.buildRustPackage {
         pname = "eigenix-cli";
         version = "0.1.0";
-        src = lib.cleanSource ./.;
+        # The CLI depends on the backend crate, so build from the repository root
+        src = lib.cleanSource ../.;
+        cargoRoot = "cli";
+        buildAndTestSubdir = "cli";
 
         cargoLock.lockFile = ./Cargo.lock;
 
         nativeBuildInputs = [
           pkgs.pkg-config
           rustToolchain
+          pkgs.gcc
         ];
 
         buildInputs = [
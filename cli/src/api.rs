@@ -0,0 +1,122 @@
+use anyhow::{Context, Result};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Duration;
+
+/// Error body the backend answers failed requests with
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+    details: Option<String>,
+}
+
+/// Client for the backend's REST API
+pub struct ApiClient {
+    base_url: String,
+    api_key: Option<String>,
+    http: reqwest::Client,
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str, api_key: Option<&str>) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.map(str::to_string),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path))
+            // Wallet and exchange calls behind some endpoints can take a while
+            .timeout(Duration::from_secs(60));
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    /// Send `request`, turning error statuses into errors carrying the backend's message
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach the backend at {}", self.base_url))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        let message = match serde_json::from_str::<ErrorResponse>(&body) {
+            Ok(ErrorResponse {
+                error,
+                details: Some(details),
+            }) => format!("{}: {}", error, details),
+            Ok(ErrorResponse { error, .. }) => error,
+            Err(_) => body.trim().to_string(),
+        };
+        if status == StatusCode::UNAUTHORIZED && self.api_key.is_none() {
+            anyhow::bail!("{} ({}); pass --api-key", message, status);
+        }
+        anyhow::bail!("{} ({})", message, status)
+    }
+
+    /// GET `path` and parse the JSON response
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self.send(self.request(Method::GET, path)).await?;
+        response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse the response of GET {}", path))
+    }
+
+    /// POST `body` as JSON to `path`, returning the response's status and JSON body
+    pub async fn post<B, T>(&self, path: &str, body: &B) -> Result<(StatusCode, T)>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let response = self
+            .send(self.request(Method::POST, path).json(body))
+            .await?;
+        let status = response.status();
+        let body = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse the response of POST {}", path))?;
+        Ok((status, body))
+    }
+
+    /// PUT `body` as JSON to `path` and parse the JSON response
+    pub async fn put<B, T>(&self, path: &str, body: &B) -> Result<T>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let response = self
+            .send(self.request(Method::PUT, path).json(body))
+            .await?;
+        response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse the response of PUT {}", path))
+    }
+
+    /// PATCH `body` as JSON to `path` and parse the JSON response
+    pub async fn patch<B, T>(&self, path: &str, body: &B) -> Result<T>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let response = self
+            .send(self.request(Method::PATCH, path).json(body))
+            .await?;
+        response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse the response of PATCH {}", path))
+    }
+}
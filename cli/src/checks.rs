@@ -0,0 +1,240 @@
+//! Checks of a deployment against the host `eigenix validate` runs on
+//!
+//! [`DeploymentConfig::validate`] only looks at the values themselves; these
+//! also look at the filesystem, the addresses' formats and the ports, and
+//! with `--online` connect to the services.
+
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::{Address, Network};
+use multiaddr::Multiaddr;
+use std::fs;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::Path;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+use crate::config::{DeploymentConfig, Validation};
+
+/// How long `--online` waits for each service to accept a connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A port the deployment binds on the host
+struct Service {
+    name: &'static str,
+    port: u16,
+    enabled: bool,
+    /// Answers requests, so `--online` connects to it
+    rpc: bool,
+}
+
+fn services(config: &DeploymentConfig) -> [Service; 11] {
+    let ports = &config.ports;
+    let service = |name, port, enabled, rpc| Service {
+        name,
+        port,
+        enabled,
+        rpc,
+    };
+    [
+        service("ASB P2P", ports.asb_p2p, config.asb.enable, false),
+        service("ASB RPC", ports.asb_rpc, config.asb.enable, true),
+        service(
+            "bitcoind RPC",
+            ports.bitcoin_rpc,
+            config.bitcoin.enable,
+            true,
+        ),
+        service(
+            "bitcoind P2P",
+            ports.bitcoin_p2p,
+            config.bitcoin.enable,
+            false,
+        ),
+        service("electrs", ports.electrs, config.electrs.enable, true),
+        service("monerod RPC", ports.monero_rpc, config.monero.enable, true),
+        service("monerod P2P", ports.monero_p2p, config.monero.enable, false),
+        service(
+            "mempool web",
+            ports.mempool_web,
+            config.mempool.enable,
+            false,
+        ),
+        service(
+            "mempool API",
+            ports.mempool_api,
+            config.mempool.enable,
+            true,
+        ),
+        service("eigenix web", ports.eigenix_web, config.web.enable, false),
+        service(
+            "eigenix backend",
+            ports.eigenix_backend,
+            config.backend.enable,
+            true,
+        ),
+    ]
+}
+
+/// Add the problems found on this host to `validation`
+///
+/// Without `online` ports already taken are reported, since the services
+/// couldn't bind them; with it the services are expected to be running, so
+/// their RPC ports must accept connections instead. The base data directory
+/// is only required under `online`: before a deploy it may not exist yet, or
+/// the check runs on another machine.
+pub async fn check_host(config: &DeploymentConfig, validation: &mut Validation, online: bool) {
+    check_data_dir(Path::new(&config.storage.base_data_dir), validation, online);
+    check_multiaddrs(config, validation);
+    check_bitcoin_address(config, validation);
+
+    if online {
+        check_reachable(config, validation).await;
+    } else {
+        check_ports_free(config, validation);
+    }
+}
+
+/// Whether `dir` is a writable directory; an error under `online`, else a
+/// warning
+fn check_data_dir(dir: &Path, validation: &mut Validation, online: bool) {
+    let problems = if online {
+        &mut validation.errors
+    } else {
+        &mut validation.warnings
+    };
+    match fs::metadata(dir) {
+        Ok(metadata) if metadata.is_dir() => {}
+        Ok(_) => {
+            problems.push(format!(
+                "Base data directory {} is not a directory",
+                dir.display()
+            ));
+            return;
+        }
+        Err(e) => {
+            problems.push(format!(
+                "Base data directory {} is not accessible: {}",
+                dir.display(),
+                e
+            ));
+            return;
+        }
+    }
+
+    let probe = dir.join(format!(".eigenix-validate-{}", std::process::id()));
+    match fs::File::create(&probe) {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe);
+        }
+        Err(e) => problems.push(format!(
+            "Base data directory {} is not writable: {}",
+            dir.display(),
+            e
+        )),
+    }
+}
+
+fn check_multiaddrs(config: &DeploymentConfig, validation: &mut Validation) {
+    let addresses = config
+        .asb
+        .external_addresses
+        .iter()
+        .map(|address| ("external address", address))
+        .chain(
+            config
+                .asb
+                .rendezvous_points
+                .iter()
+                .map(|address| ("rendezvous point", address)),
+        );
+    for (kind, address) in addresses {
+        if let Err(e) = address.parse::<Multiaddr>() {
+            validation.errors.push(format!(
+                "ASB {} '{}' is not a valid multiaddr: {}",
+                kind, address, e
+            ));
+        }
+    }
+}
+
+fn check_bitcoin_address(config: &DeploymentConfig, validation: &mut Validation) {
+    let Some(address) = &config.asb.external_bitcoin_address else {
+        return;
+    };
+    let network = match config.networks.bitcoin.as_str() {
+        "Mainnet" => Network::Bitcoin,
+        "Testnet" => Network::Testnet,
+        "Signet" => Network::Signet,
+        "Regtest" => Network::Regtest,
+        other => {
+            validation
+                .errors
+                .push(format!("Unknown Bitcoin network '{}'", other));
+            return;
+        }
+    };
+
+    match address.parse::<Address<NetworkUnchecked>>() {
+        Ok(parsed) if parsed.is_valid_for_network(network) => {}
+        Ok(_) => validation.errors.push(format!(
+            "ASB external Bitcoin address {} is not a {} address",
+            address, config.networks.bitcoin
+        )),
+        Err(e) => validation.errors.push(format!(
+            "ASB external Bitcoin address {} is invalid: {}",
+            address, e
+        )),
+    }
+}
+
+fn check_ports_free(config: &DeploymentConfig, validation: &mut Validation) {
+    for service in services(config).iter().filter(|s| s.enabled) {
+        if let Err(e) = TcpListener::bind((Ipv4Addr::UNSPECIFIED, service.port)) {
+            if e.kind() == ErrorKind::AddrInUse {
+                validation.warnings.push(format!(
+                    "Port {} ({}) is already in use - fine if it is the running deployment's own",
+                    service.port, service.name
+                ));
+            }
+        }
+    }
+}
+
+async fn check_reachable(config: &DeploymentConfig, validation: &mut Validation) {
+    for service in services(config).iter().filter(|s| s.enabled && s.rpc) {
+        let connect = TcpStream::connect((Ipv4Addr::LOCALHOST, service.port));
+        let error = match tokio::time::timeout(CONNECT_TIMEOUT, connect).await {
+            Ok(Ok(_)) => continue,
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => format!("no answer within {}s", CONNECT_TIMEOUT.as_secs()),
+        };
+        validation.errors.push(format!(
+            "{} is not reachable on port {}: {}",
+            service.name, service.port, error
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_data_dir_is_only_an_error_online() {
+        let dir = std::env::temp_dir().join(format!("eigenix-missing-{}", std::process::id()));
+
+        let mut validation = Validation::default();
+        check_data_dir(&dir, &mut validation, false);
+        assert!(validation.is_valid());
+        assert_eq!(validation.warnings.len(), 1);
+
+        let mut validation = Validation::default();
+        check_data_dir(&dir, &mut validation, true);
+        assert_eq!(validation.errors.len(), 1);
+
+        let mut validation = Validation::default();
+        check_data_dir(&std::env::temp_dir(), &mut validation, true);
+        assert!(validation.is_valid() && validation.warnings.is_empty());
+    }
+}
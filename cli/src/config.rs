@@ -1,11 +1,26 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::schema::to_camel_case;
+
+/// Prefix of the environment variables overriding parameters, e.g.
+/// `EIGENIX_CONFIG_ASB__MAX_BUY_BTC=0.05` for `asb.maxBuyBtc`
+pub const ENV_PREFIX: &str = "EIGENIX_CONFIG_";
+
+/// Version of the parameters.json schema written by this CLI; bump it and add
+/// a step to [`crate::schema`] when fields are renamed
+pub const SCHEMA_VERSION: u32 = 1;
+
 /// Full deployment configuration matching parameters.json schema
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DeploymentConfig {
+    /// Schema version the file was written for; files from before versioning
+    /// have none and count as 0
+    #[serde(rename = "schemaVersion", default)]
+    pub schema_version: u32,
     pub deployment: DeploymentMeta,
     pub storage: StorageConfig,
     pub networks: NetworkConfig,
@@ -177,6 +192,19 @@ pub struct MonitoringConfig {
     pub log_retention_days: u32,
 }
 
+/// Problems found by [`DeploymentConfig::validate`]
+#[derive(Debug, Default, Serialize)]
+pub struct Validation {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl Validation {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
 impl Default for DeploymentConfig {
     fn default() -> Self {
         Self::mainnet()
@@ -188,8 +216,9 @@ impl DeploymentConfig {
     pub fn load(path: &Path) -> Result<Self> {
         let content = fs::read_to_string(path)
             .context(format!("Failed to read config file: {}", path.display()))?;
-        let config: Self =
-            serde_json::from_str(&content).context("Failed to parse configuration JSON")?;
+        let config: Self = serde_json::from_str(&content).context(
+            "Failed to parse configuration JSON (older files can be upgraded with 'eigenix config migrate')",
+        )?;
         Ok(config)
     }
 
@@ -202,9 +231,85 @@ impl DeploymentConfig {
         Ok(())
     }
 
+    /// Check the configuration for values the deployment can't work with
+    pub fn validate(&self) -> Validation {
+        let mut validation = Validation::default();
+        let mut error = |message: &str| validation.errors.push(message.to_string());
+
+        if self.deployment.name.is_empty() {
+            error("Deployment name cannot be empty");
+        }
+
+        if self.asb.enable {
+            if self.asb.min_buy_btc >= self.asb.max_buy_btc {
+                error("ASB min_buy_btc must be less than max_buy_btc");
+            }
+            if self.asb.ask_spread < 0.0 {
+                error("ASB ask_spread cannot be negative");
+            }
+        }
+
+        // Port conflict checks
+        let ports = [
+            self.ports.asb_p2p,
+            self.ports.asb_rpc,
+            self.ports.mempool_web,
+            self.ports.eigenix_web,
+            self.ports.eigenix_backend,
+        ];
+        let unique_ports: std::collections::HashSet<_> = ports.iter().collect();
+        if ports.len() != unique_ports.len() {
+            error("Port conflict detected - some ports are assigned to multiple services");
+        }
+
+        if self.schema_version < SCHEMA_VERSION {
+            validation.warnings.push(
+                "Configuration uses an older schema - run 'eigenix config migrate'".to_string(),
+            );
+        }
+        if self.asb.enable && self.asb.external_addresses.is_empty() {
+            validation.warnings.push(
+                "ASB has no external addresses configured - may not be discoverable".to_string(),
+            );
+        }
+
+        validation
+    }
+
+    /// Set each `key=value` pair, in order
+    ///
+    /// Keys are dotted paths into the JSON file such as `asb.maxBuyBtc`, in
+    /// camelCase or snake_case. Values replacing a string are taken as is,
+    /// apart from `null` to clear an optional one; others are parsed as JSON,
+    /// e.g. `0.05`, `true`, `null` or `["a", "b"]`.
+    pub fn apply_overrides(&mut self, overrides: &[(String, String)]) -> Result<()> {
+        let mut json = serde_json::to_value(&*self).context("Failed to serialize configuration")?;
+
+        for (key, raw) in overrides {
+            let mut field = &mut json;
+            for segment in key.split('.') {
+                field = field
+                    .as_object_mut()
+                    .and_then(|fields| fields.get_mut(&to_camel_case(segment)))
+                    .with_context(|| format!("Unknown configuration key '{}'", key))?;
+            }
+            *field = match &*field {
+                Value::String(_) if raw != "null" => Value::String(raw.clone()),
+                _ => serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.clone())),
+            };
+            // Checked one at a time so a bad value is blamed on its key
+            serde_json::from_value::<Self>(json.clone())
+                .with_context(|| format!("Invalid value '{}' for {}", raw, key))?;
+        }
+
+        *self = serde_json::from_value(json).context("Failed to apply configuration overrides")?;
+        Ok(())
+    }
+
     /// Create mainnet configuration template
     pub fn mainnet() -> Self {
         Self {
+            schema_version: SCHEMA_VERSION,
             deployment: DeploymentMeta {
                 name: "eigenix".to_string(),
                 environment: "production".to_string(),
@@ -371,6 +476,27 @@ impl DeploymentConfig {
     }
 }
 
+/// Parse a `--set key=value` argument
+pub fn parse_override(arg: &str) -> std::result::Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", arg)),
+    }
+}
+
+/// Overrides from `EIGENIX_CONFIG_*` variables, sorted by key; `__` separates
+/// the path's segments
+pub fn env_overrides() -> Vec<(String, String)> {
+    let mut overrides: Vec<(String, String)> = std::env::vars()
+        .filter_map(|(name, value)| {
+            let path = name.strip_prefix(ENV_PREFIX)?;
+            Some((path.to_lowercase().replace("__", "."), value))
+        })
+        .collect();
+    overrides.sort();
+    overrides
+}
+
 pub fn get_project_root() -> Result<PathBuf> {
     // Start from current directory and walk up to find the project root
     let mut current = std::env::current_dir()?;
@@ -397,3 +523,109 @@ pub fn get_parameters_path(base_path: &Path) -> PathBuf {
 pub fn parameters_exist(base_path: &Path) -> bool {
     get_parameters_path(base_path).exists()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_override() {
+        assert_eq!(
+            parse_override("asb.maxBuyBtc=0.05"),
+            Ok(("asb.maxBuyBtc".to_string(), "0.05".to_string()))
+        );
+        // Only the first `=` separates the key
+        assert_eq!(
+            parse_override("deployment.name=a=b"),
+            Ok(("deployment.name".to_string(), "a=b".to_string()))
+        );
+        assert_eq!(
+            parse_override("deployment.domain="),
+            Ok(("deployment.domain".to_string(), String::new()))
+        );
+        assert!(parse_override("=0.05").is_err());
+        assert!(parse_override("asb.maxBuyBtc").is_err());
+    }
+
+    #[test]
+    fn test_apply_overrides_parses_typed_values() {
+        let mut config = DeploymentConfig::mainnet();
+        config
+            .apply_overrides(&set(&[
+                ("asb.maxBuyBtc", "0.05"),
+                ("asb.enable_tor", "false"),
+                ("bitcoin.dbcache", "2048"),
+                (
+                    "asb.externalAddresses",
+                    r#"["/dns4/swap.example.com/tcp/9939"]"#,
+                ),
+                // Strings are taken as is, even when they read as JSON
+                ("deployment.name", "123"),
+                ("asb.externalBitcoinAddress", "bc1qexample"),
+            ]))
+            .unwrap();
+
+        assert_eq!(config.asb.max_buy_btc, 0.05);
+        assert!(!config.asb.enable_tor);
+        assert_eq!(config.bitcoin.dbcache, 2048);
+        assert_eq!(
+            config.asb.external_addresses,
+            vec!["/dns4/swap.example.com/tcp/9939"]
+        );
+        assert_eq!(config.deployment.name, "123");
+        assert_eq!(
+            config.asb.external_bitcoin_address.as_deref(),
+            Some("bc1qexample")
+        );
+    }
+
+    #[test]
+    fn test_apply_overrides_clears_optional_strings_with_null() {
+        let mut config = DeploymentConfig::mainnet();
+        config
+            .apply_overrides(&set(&[("deployment.domain", "swap.example.com")]))
+            .unwrap();
+        assert_eq!(
+            config.deployment.domain.as_deref(),
+            Some("swap.example.com")
+        );
+
+        // Later pairs win, in order
+        config
+            .apply_overrides(&set(&[
+                ("deployment.domain", "other.example.com"),
+                ("deployment.domain", "null"),
+            ]))
+            .unwrap();
+        assert_eq!(config.deployment.domain, None);
+    }
+
+    #[test]
+    fn test_apply_overrides_rejects_unknown_keys_and_bad_values() {
+        let mut config = DeploymentConfig::mainnet();
+        let before = config.asb.max_buy_btc;
+
+        let error = config
+            .apply_overrides(&set(&[("asb.maxBuyBtc", "0.05"), ("asb.noSuchKey", "1")]))
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("asb.noSuchKey"));
+        // Nothing is applied when any pair fails
+        assert_eq!(config.asb.max_buy_btc, before);
+
+        assert!(config
+            .apply_overrides(&set(&[("asb.maxBuyBtc.value", "1")]))
+            .is_err());
+        let error = config
+            .apply_overrides(&set(&[("asb.maxBuyBtc", "lots")]))
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("asb.maxBuyBtc"));
+        assert_eq!(config.asb.max_buy_btc, before);
+    }
+}
@@ -1,9 +1,26 @@
+mod api;
+mod checks;
 mod config;
-
+mod metrics;
+mod schema;
+mod services;
+mod trading;
+mod wallet;
+
+use anyhow::Context;
+use api::ApiClient;
 use clap::{Parser, Subcommand};
 use colored::Colorize;
-use config::{get_parameters_path, get_project_root, parameters_exist, DeploymentConfig};
+use config::{
+    env_overrides, get_parameters_path, get_project_root, parameters_exist, parse_override,
+    DeploymentConfig, Validation, ENV_PREFIX,
+};
 use dialoguer::{Confirm, Input, Select};
+use eigenix_backend::health::{DependencyHealth, HealthStatus};
+use eigenix_backend::services::breaker::CircuitState;
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser, Debug)]
 #[command(name = "eigenix")]
@@ -12,6 +29,12 @@ struct Args {
     /// Command to run
     #[command(subcommand)]
     command: Commands,
+    /// Backend the API commands talk to
+    #[arg(long, global = true, default_value = "http://127.0.0.1:3000")]
+    url: String,
+    /// API key, for backends that require one
+    #[arg(long, global = true, env = "EIGENIX_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -21,14 +44,29 @@ enum Commands {
         /// Template to use (mainnet or testnet)
         #[arg(short, long)]
         template: Option<String>,
-        /// Skip interactive configuration
+        /// Skip interactive configuration, overwriting any existing file
         #[arg(short, long)]
         yes: bool,
+        /// Set a parameter, e.g. `--set asb.maxBuyBtc=0.05`; repeatable
+        #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_override)]
+        set: Vec<(String, String)>,
+        /// Print the outcome as JSON; implies --yes
+        #[arg(long)]
+        json: bool,
     },
     /// Configure deployment parameters interactively
     Configure {
         /// Configuration section to edit
         section: Option<String>,
+        /// Set a parameter instead of prompting; repeatable
+        #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_override)]
+        set: Vec<(String, String)>,
+        /// Apply the EIGENIX_CONFIG_* variables instead of prompting
+        #[arg(long)]
+        from_env: bool,
+        /// Print the outcome as JSON
+        #[arg(long)]
+        json: bool,
     },
     /// Show current configuration
     Show {
@@ -36,43 +74,116 @@ enum Commands {
         section: Option<String>,
     },
     /// Validate configuration
-    Validate,
+    Validate {
+        /// Print the errors and warnings as JSON
+        #[arg(long)]
+        json: bool,
+        /// Also check that the enabled services accept connections
+        #[arg(long)]
+        online: bool,
+    },
+    /// Upgrade the parameters file or compare it with a template
+    Config {
+        #[command(subcommand)]
+        command: schema::ConfigCommand,
+    },
     /// Start the backend server
     Server {
         /// Port to bind to
         #[arg(short, long, default_value = "3000")]
         port: u16,
+        /// Backend configuration file (defaults are used when omitted)
+        #[arg(short, long, value_name = "FILE")]
+        config: Option<PathBuf>,
+    },
+    /// Check the backend and the services it depends on
+    Health {
+        /// Backend configuration file, for probing the services directly when
+        /// the backend is down (defaults are used when omitted)
+        #[arg(short, long, value_name = "FILE")]
+        config: Option<PathBuf>,
+    },
+    /// Exercise every integration read-only and report pass/fail
+    Selftest {
+        /// Backend configuration file (defaults are used when omitted)
+        #[arg(short, long, value_name = "FILE")]
+        config: Option<PathBuf>,
+    },
+    /// Start, stop, restart or check the deployment's services
+    Services {
+        #[command(subcommand)]
+        command: services::ServicesCommand,
+    },
+    /// Check balances, get deposit addresses and send, through the backend
+    Wallet {
+        #[command(subcommand)]
+        command: wallet::WalletCommand,
+    },
+    /// Inspect and control the trading engine
+    Trading {
+        #[command(subcommand)]
+        command: trading::TradingCommand,
+    },
+    /// Follow the collected metrics
+    Metrics {
+        #[command(subcommand)]
+        command: metrics::MetricsCommand,
+    },
+    /// Import metrics history from a `/metrics/export` JSON file
+    ImportMetrics {
+        /// Export file to import
+        file: PathBuf,
+        /// Backend configuration file (defaults are used when omitted)
+        #[arg(short, long, value_name = "FILE")]
+        config: Option<PathBuf>,
     },
-    /// Run a health check
-    Health,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    let api = ApiClient::new(&args.url, args.api_key.as_deref());
 
     match args.command {
-        Commands::Init { template, yes } => init_configuration(template.as_deref(), yes).await,
-        Commands::Configure { section } => configure_deployment(section.as_deref()).await,
+        Commands::Init {
+            template,
+            yes,
+            set,
+            json,
+        } => init_configuration(template.as_deref(), yes, &set, json).await,
+        Commands::Configure {
+            section,
+            set,
+            from_env,
+            json,
+        } => configure_deployment(section.as_deref(), &set, from_env, json).await,
         Commands::Show { section } => show_configuration(section.as_deref()).await,
-        Commands::Validate => validate_configuration().await,
-        Commands::Server { port } => {
-            println!("Starting server on port {}", port);
-            // TODO: Start the Axum server
-            Ok(())
-        }
-        Commands::Health => {
-            println!("{}", "Health check: OK".green());
-            Ok(())
+        Commands::Validate { json, online } => validate_configuration(json, online).await,
+        Commands::Config { command } => schema::run(command),
+        Commands::Server { port, config } => run_server(port, config.as_deref()).await,
+        Commands::Health { config } => {
+            run_health(&args.url, args.api_key.as_deref(), config.as_deref()).await
         }
+        Commands::Selftest { config } => run_selftest(config.as_deref()).await,
+        Commands::Services { command } => services::run(command),
+        Commands::Wallet { command } => wallet::run(&api, command).await,
+        Commands::Trading { command } => trading::run(&api, command).await,
+        Commands::Metrics { command } => metrics::run(&api, command).await,
+        Commands::ImportMetrics { file, config } => import_metrics(&file, config.as_deref()).await,
     }
 }
 
-async fn init_configuration(template: Option<&str>, skip_interactive: bool) -> anyhow::Result<()> {
+async fn init_configuration(
+    template: Option<&str>,
+    skip_interactive: bool,
+    set: &[(String, String)],
+    json: bool,
+) -> anyhow::Result<()> {
+    let skip_interactive = skip_interactive || json;
     let project_root = get_project_root()?;
     let params_path = get_parameters_path(&project_root);
 
-    if parameters_exist(&project_root) {
+    if parameters_exist(&project_root) && !skip_interactive {
         let overwrite = Confirm::new()
             .with_prompt("Configuration already exists. Overwrite?")
             .default(false)
@@ -86,6 +197,10 @@ async fn init_configuration(template: Option<&str>, skip_interactive: bool) -> a
 
     let template_name = template.unwrap_or("mainnet");
     let mut config = DeploymentConfig::from_template(template_name);
+    // Applied before the prompts, which then offer the overridden values
+    if let Err(e) = apply_overrides(&mut config, set, true) {
+        fail_validation(&params_path, format!("{:#}", e), json);
+    }
 
     if !skip_interactive {
         println!(
@@ -232,9 +347,18 @@ async fn init_configuration(template: Option<&str>, skip_interactive: bool) -> a
         std::fs::create_dir_all(parent)?;
     }
 
-    config.save(&params_path)?;
+    let validation = config.validate();
+    if validation.is_valid() {
+        config.save(&params_path)?;
+    }
+    if json {
+        report_validation(&params_path, &validation, json);
+        return Ok(());
+    }
 
     println!();
+    report_validation(&params_path, &validation, json);
+
     println!(
         "{}",
         "✓ Configuration initialized successfully!".green().bold()
@@ -259,7 +383,12 @@ async fn init_configuration(template: Option<&str>, skip_interactive: bool) -> a
     Ok(())
 }
 
-async fn configure_deployment(section: Option<&str>) -> anyhow::Result<()> {
+async fn configure_deployment(
+    section: Option<&str>,
+    set: &[(String, String)],
+    from_env: bool,
+    json: bool,
+) -> anyhow::Result<()> {
     let project_root = get_project_root()?;
     let params_path = get_parameters_path(&project_root);
 
@@ -273,6 +402,33 @@ async fn configure_deployment(section: Option<&str>) -> anyhow::Result<()> {
 
     let mut config = DeploymentConfig::load(&params_path)?;
 
+    // Overrides replace the prompts entirely, for provisioning tools. The
+    // variables alone don't, so a stray one can't skip the prompts unasked.
+    if !set.is_empty() || json || from_env {
+        if let Err(e) = apply_overrides(&mut config, set, from_env) {
+            fail_validation(&params_path, format!("{:#}", e), json);
+        }
+        let validation = config.validate();
+        if validation.is_valid() {
+            config.save(&params_path)?;
+        }
+        report_validation(&params_path, &validation, json);
+        if !json {
+            println!("{}", "✓ Configuration updated successfully!".green().bold());
+        }
+        return Ok(());
+    }
+    if !env_overrides().is_empty() {
+        println!(
+            "{}",
+            format!(
+                "Ignoring {}* variables; pass --from-env to apply them.",
+                ENV_PREFIX
+            )
+            .yellow()
+        );
+    }
+
     let sections = vec![
         "deployment",
         "storage",
@@ -596,69 +752,304 @@ async fn show_configuration(section: Option<&str>) -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn validate_configuration() -> anyhow::Result<()> {
+async fn validate_configuration(json: bool, online: bool) -> anyhow::Result<()> {
     let project_root = get_project_root()?;
     let params_path = get_parameters_path(&project_root);
 
     if !parameters_exist(&project_root) {
-        println!(
-            "{}",
-            "✗ No configuration found. Run 'eigenix init' first.".red()
+        fail_validation(
+            &params_path,
+            "No configuration found. Run 'eigenix init' first.".to_string(),
+            json,
         );
-        return Ok(());
     }
 
-    let config = DeploymentConfig::load(&params_path)?;
-    let mut errors = vec![];
-    let mut warnings = vec![];
+    let config = DeploymentConfig::load(&params_path)
+        .unwrap_or_else(|e| fail_validation(&params_path, format!("{:#}", e), json));
+    let mut validation = config.validate();
+    checks::check_host(&config, &mut validation, online).await;
 
-    // Validation checks
-    if config.deployment.name.is_empty() {
-        errors.push("Deployment name cannot be empty");
+    if !json && validation.errors.is_empty() && validation.warnings.is_empty() {
+        println!("{}", "✓ Configuration is valid!".green().bold());
     }
+    report_validation(&params_path, &validation, json);
 
-    if config.asb.enable {
-        if config.asb.min_buy_btc >= config.asb.max_buy_btc {
-            errors.push("ASB min_buy_btc must be less than max_buy_btc");
-        }
-        if config.asb.ask_spread < 0.0 {
-            errors.push("ASB ask_spread cannot be negative");
-        }
-        if config.asb.external_addresses.is_empty() {
-            warnings.push("ASB has no external addresses configured - may not be discoverable");
-        }
-    }
+    Ok(())
+}
 
-    // Port conflict checks
-    let ports = vec![
-        config.ports.asb_p2p,
-        config.ports.asb_rpc,
-        config.ports.mempool_web,
-        config.ports.eigenix_web,
-        config.ports.eigenix_backend,
-    ];
-    let unique_ports: std::collections::HashSet<_> = ports.iter().collect();
-    if ports.len() != unique_ports.len() {
-        errors.push("Port conflict detected - some ports are assigned to multiple services");
-    }
+/// Apply the `EIGENIX_CONFIG_*` variables if `from_env`, then the `--set` flags
+fn apply_overrides(
+    config: &mut DeploymentConfig,
+    set: &[(String, String)],
+    from_env: bool,
+) -> anyhow::Result<()> {
+    let mut overrides = if from_env {
+        env_overrides()
+    } else {
+        Vec::new()
+    };
+    overrides.extend_from_slice(set);
+    config.apply_overrides(&overrides)
+}
 
-    // Display results
-    if errors.is_empty() && warnings.is_empty() {
-        println!("{}", "✓ Configuration is valid!".green().bold());
+/// Report `error` as the only validation error, which exits with 1
+fn fail_validation(path: &Path, error: String, json: bool) -> ! {
+    let validation = Validation {
+        errors: vec![error],
+        ..Validation::default()
+    };
+    report_validation(path, &validation, json);
+    std::process::exit(1)
+}
+
+/// Print the errors and warnings for the file at `path`, or with `json` an
+/// object with `valid`, `path`, `errors` and `warnings`; exits with 1 when
+/// there are errors
+fn report_validation(path: &Path, validation: &Validation, json: bool) {
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "valid": validation.is_valid(),
+                "path": path,
+                "errors": validation.errors,
+                "warnings": validation.warnings,
+            })
+        );
     } else {
-        if !errors.is_empty() {
+        if !validation.errors.is_empty() {
             println!("{}", "Errors:".red().bold());
-            for error in errors {
+            for error in &validation.errors {
                 println!("  ✗ {}", error.red());
             }
         }
-        if !warnings.is_empty() {
+        if !validation.warnings.is_empty() {
             println!("{}", "Warnings:".yellow().bold());
-            for warning in warnings {
+            for warning in &validation.warnings {
                 println!("  ⚠ {}", warning.yellow());
             }
         }
     }
 
+    if !validation.is_valid() {
+        std::process::exit(1);
+    }
+}
+
+/// Run the backend in this process until Ctrl-C or SIGTERM
+///
+/// The ASB limits in the project's deployment parameters seed the trading
+/// config, as `--parameters` does for `eigenix-backend`.
+async fn run_server(port: u16, config_path: Option<&Path>) -> anyhow::Result<()> {
+    let telemetry = eigenix_backend::telemetry::init();
+
+    let mut args: Vec<OsString> = vec![
+        "eigenix-backend".into(),
+        "--port".into(),
+        port.to_string().into(),
+    ];
+    if let Some(path) = config_path {
+        args.extend(["--config".into(), path.as_os_str().to_owned()]);
+    }
+    if let Ok(project_root) = get_project_root() {
+        if parameters_exist(&project_root) {
+            let params_path = get_parameters_path(&project_root);
+            args.extend(["--parameters".into(), params_path.into_os_string()]);
+        }
+    }
+    let cli = eigenix_backend::config::Cli::try_parse_from(args)?;
+
+    println!("{}", format!("Starting server on port {}", port).bold());
+    eigenix_backend::server::run(cli, &telemetry).await
+}
+
+/// The parts of the backend's `/health` response the CLI shows
+#[derive(Deserialize)]
+struct HealthResponse {
+    status: HealthStatus,
+    dependencies: Vec<DependencyHealth>,
+}
+
+/// Ask the backend at `url` for its health
+///
+/// Both 200 and 503 carry the report; other statuses are errors.
+async fn fetch_health(url: &str, api_key: Option<&str>) -> anyhow::Result<HealthResponse> {
+    let mut request = reqwest::Client::new()
+        .get(format!("{}/health", url.trim_end_matches('/')))
+        // The backend gives each check 5s
+        .timeout(std::time::Duration::from_secs(15));
+    if let Some(key) = api_key {
+        request = request.bearer_auth(key);
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() && status != reqwest::StatusCode::SERVICE_UNAVAILABLE {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("{} answered {}: {}", url, status, body.trim());
+    }
+    response
+        .json()
+        .await
+        .with_context(|| format!("Failed to parse the health report from {}", url))
+}
+
+async fn run_health(
+    url: &str,
+    api_key: Option<&str>,
+    config_path: Option<&Path>,
+) -> anyhow::Result<()> {
+    println!("{}", "=== Eigenix Health ===".bold().cyan());
+
+    let health = match fetch_health(url, api_key).await {
+        Ok(health) => {
+            println!("Reported by the backend at {}", url);
+            health
+        }
+        Err(e)
+            if e.downcast_ref::<reqwest::Error>()
+                .is_some_and(|e| e.is_connect() || e.is_timeout()) =>
+        {
+            println!(
+                "{}",
+                format!(
+                    "⚠ Backend unreachable at {} ({:#}); probing the services directly",
+                    url, e
+                )
+                .yellow()
+            );
+            let config = match config_path {
+                Some(path) => eigenix_backend::Config::from_file(path)?,
+                None => eigenix_backend::Config::default(),
+            };
+            let mut dependencies = vec![DependencyHealth {
+                name: "backend".to_string(),
+                critical: true,
+                up: false,
+                latency_ms: 0,
+                detail: format!("{:#}", e),
+                circuit: None,
+            }];
+            dependencies.extend(eigenix_backend::health::probe_services(&config).await);
+            HealthResponse {
+                status: eigenix_backend::health::classify(&dependencies),
+                dependencies,
+            }
+        }
+        Err(e) => return Err(e),
+    };
+    println!();
+
+    let name_width = health
+        .dependencies
+        .iter()
+        .map(|dep| dep.name.len())
+        .max()
+        .unwrap_or(0);
+    for dep in &health.dependencies {
+        let status = match (dep.up, dep.critical) {
+            (true, _) => "✓ UP  ".green(),
+            (false, true) => "✗ DOWN".red(),
+            (false, false) => "⚠ DOWN".yellow(),
+        };
+        let circuit = match dep.circuit {
+            Some(CircuitState::Open) => " (circuit open)",
+            Some(CircuitState::HalfOpen) => " (circuit half-open)",
+            _ => "",
+        };
+        println!(
+            "  {}  {:<width$}  {:>6}  {}{}",
+            status,
+            dep.name.bold(),
+            format!("{}ms", dep.latency_ms),
+            dep.detail,
+            circuit,
+            width = name_width
+        );
+    }
+
+    println!();
+    match health.status {
+        HealthStatus::Healthy => println!("{}", "✓ Healthy".green().bold()),
+        HealthStatus::Degraded => println!(
+            "{}",
+            "⚠ Degraded: an optional service is down".yellow().bold()
+        ),
+        HealthStatus::Unhealthy => {
+            println!("{}", "✗ Unhealthy: a critical service is down".red().bold());
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+async fn run_selftest(config_path: Option<&Path>) -> anyhow::Result<()> {
+    let config = match config_path {
+        Some(path) => eigenix_backend::Config::from_file(path)?,
+        None => eigenix_backend::Config::default(),
+    };
+
+    println!("{}", "=== Eigenix Self-Test ===".bold().cyan());
+    println!();
+
+    let report = eigenix_backend::selftest::run_selftest(&config).await;
+    print!("{}", report.render_table());
+
+    println!();
+    if report.passed() {
+        println!("{}", "✓ All critical checks passed".green().bold());
+    } else {
+        let failed = report.critical_failures().count();
+        println!(
+            "{}",
+            format!("✗ {} critical check(s) failed", failed)
+                .red()
+                .bold()
+        );
+    }
+
+    std::process::exit(report.exit_code());
+}
+
+async fn import_metrics(file: &Path, config_path: Option<&Path>) -> anyhow::Result<()> {
+    let config = match config_path {
+        Some(path) => eigenix_backend::Config::from_file(path)?,
+        None => eigenix_backend::Config::default(),
+    };
+
+    let contents = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let export: eigenix_backend::db::MetricsExport = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse {} as a metrics export", file.display()))?;
+
+    let db = eigenix_backend::MetricsDatabase::open(&config.database).await?;
+
+    println!("{}", "=== Importing Metrics History ===".bold().cyan());
+    let sections = [
+        ("bitcoin", export.bitcoin.len()),
+        ("monero", export.monero.len()),
+        ("asb", export.asb.len()),
+        ("electrs", export.electrs.len()),
+        ("containers", export.containers.len()),
+    ];
+
+    let summary = db.import_metrics(export).await?;
+    let inserted = [
+        summary.bitcoin,
+        summary.monero,
+        summary.asb,
+        summary.electrs,
+        summary.containers,
+    ];
+
+    for ((name, total), inserted) in sections.iter().zip(inserted) {
+        println!(
+            "  {:<10}  {} imported, {} already present",
+            name.bold(),
+            inserted.to_string().green(),
+            total - inserted
+        );
+    }
+
     Ok(())
 }
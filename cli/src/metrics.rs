@@ -0,0 +1,245 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::{Args, Subcommand, ValueEnum};
+use colored::Colorize;
+use eigenix_backend::db::aggregate::{self, SERIES};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::api::ApiClient;
+
+/// Trailing values drawn in each line's sparkline
+const SPARKLINE_WIDTH: usize = 30;
+
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Subcommand, Debug)]
+pub enum MetricsCommand {
+    /// Print a field's recent values and keep printing new ones as they arrive
+    Tail(TailArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct TailArgs {
+    /// Metrics source, e.g. bitcoin, monero, asb or prices
+    #[arg(long)]
+    source: String,
+    /// Numeric field of the source, e.g. blocks or wallet_balance
+    #[arg(long)]
+    field: String,
+    /// How the samples in each bucket are reduced
+    #[arg(long = "fn", value_enum, default_value = "last")]
+    function: Function,
+    /// Bucket width, e.g. 30s, 1m or 1h
+    #[arg(long, default_value = "1m")]
+    bucket: String,
+    /// Minutes of history to start with
+    #[arg(long, default_value = "60")]
+    minutes: f64,
+    /// Seconds between polls
+    #[arg(long, default_value = "10")]
+    interval: u64,
+    /// Print the history and exit instead of following
+    #[arg(long)]
+    once: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Function {
+    Avg,
+    Min,
+    Max,
+    Last,
+}
+
+impl Function {
+    fn as_str(self) -> &'static str {
+        match self {
+            Function::Avg => "avg",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Last => "last",
+        }
+    }
+}
+
+pub async fn run(api: &ApiClient, command: MetricsCommand) -> Result<()> {
+    match command {
+        MetricsCommand::Tail(args) => tail(api, &args).await,
+    }
+}
+
+/// Draw `values` scaled between their minimum and maximum, leaving a gap for
+/// values that aren't numbers
+fn sparkline(values: &[f64]) -> String {
+    let finite = values.iter().copied().filter(|v| v.is_finite());
+    let min = finite.clone().fold(f64::INFINITY, f64::min);
+    let max = finite.fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+    values
+        .iter()
+        .map(|value| {
+            if !value.is_finite() {
+                ' '
+            } else if range > 0.0 {
+                let level = ((value - min) / range * (SPARK_CHARS.len() - 1) as f64).round();
+                SPARK_CHARS[level as usize]
+            } else {
+                SPARK_CHARS[0]
+            }
+        })
+        .collect()
+}
+
+/// Buckets of `series` to print after the newest one printed, `last`
+///
+/// A bucket still open at `now` would be printed with a partial value and
+/// never corrected, so it is held back until it closes, unless `include_open`.
+fn new_buckets(
+    series: Vec<(DateTime<Utc>, f64)>,
+    last: Option<DateTime<Utc>>,
+    width: chrono::Duration,
+    now: DateTime<Utc>,
+    include_open: bool,
+) -> Vec<(DateTime<Utc>, f64)> {
+    series
+        .into_iter()
+        .filter(|(timestamp, _)| last.is_none_or(|last| *timestamp > last))
+        .filter(|(timestamp, _)| include_open || *timestamp + width <= now)
+        .collect()
+}
+
+async fn tail(api: &ApiClient, args: &TailArgs) -> Result<()> {
+    let TailArgs {
+        source,
+        field,
+        function,
+        bucket,
+        minutes,
+        interval,
+        once,
+    } = args;
+
+    if aggregate::series_table(source, field).is_none() {
+        let fields = SERIES
+            .iter()
+            .find(|(name, _, _)| *name == source.as_str())
+            .map(|(_, _, fields)| fields.join(", "));
+        match fields {
+            Some(fields) => anyhow::bail!(
+                "No numeric field '{}' on {} metrics; try one of {}",
+                field,
+                source,
+                fields
+            ),
+            None => anyhow::bail!(
+                "Unknown metrics source '{}'; try one of {}",
+                source,
+                SERIES
+                    .iter()
+                    .map(|(name, _, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+    let Some(width) = aggregate::parse_bucket(bucket) else {
+        anyhow::bail!(
+            "Invalid bucket '{}', expected e.g. 30s, 5m, 1h or 1d",
+            bucket
+        );
+    };
+
+    let path = format!(
+        "/metrics/{}/aggregate?field={}&fn={}&bucket={}&minutes={}",
+        source,
+        field,
+        function.as_str(),
+        bucket,
+        minutes
+    );
+    println!(
+        "{}",
+        format!(
+            "=== {} {} ({} per {}) ===",
+            source,
+            field,
+            function.as_str(),
+            bucket
+        )
+        .bold()
+        .cyan()
+    );
+
+    // Values printed so far, for the sparklines, and the newest bucket among them
+    let mut window: VecDeque<f64> = VecDeque::with_capacity(SPARKLINE_WIDTH + 1);
+    let mut last: Option<DateTime<Utc>> = None;
+    loop {
+        let series: Vec<(DateTime<Utc>, f64)> = api.get(&path).await?;
+        // Followed buckets are printed once closed; a one-off print shows
+        // the current bucket as it stands
+        for (timestamp, value) in new_buckets(series, last, width, Utc::now(), *once) {
+            window.push_back(value);
+            if window.len() > SPARKLINE_WIDTH {
+                window.pop_front();
+            }
+            println!(
+                "  {}  {:>16}  {}",
+                timestamp.format("%Y-%m-%d %H:%M:%S"),
+                value,
+                sparkline(window.make_contiguous()).green()
+            );
+            last = Some(timestamp);
+        }
+
+        if *once {
+            if last.is_none() {
+                println!("No samples in the last {} minutes.", minutes);
+            }
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs((*interval).max(1))).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 1, 12, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[1.0, 2.0, 3.0, 8.0]), "▁▂▃█");
+        // Flat lines sit at the bottom
+        assert_eq!(sparkline(&[5.0, 5.0]), "▁▁");
+        assert_eq!(sparkline(&[]), "");
+        // Values that aren't numbers leave a gap and don't skew the scale
+        assert_eq!(sparkline(&[0.0, f64::NAN, 7.0]), "▁ █");
+        assert_eq!(sparkline(&[f64::NAN, f64::INFINITY]), "  ");
+    }
+
+    #[test]
+    fn test_open_bucket_is_held_back_until_it_closes() {
+        let width = chrono::Duration::minutes(1);
+        let series = vec![(at(0), 1.0), (at(1), 2.0), (at(2), 0.5)];
+
+        // At 12:02:30 the 12:02 bucket is still filling
+        let now = at(2) + chrono::Duration::seconds(30);
+        let printed = new_buckets(series.clone(), None, width, now, false);
+        assert_eq!(printed, vec![(at(0), 1.0), (at(1), 2.0)]);
+
+        // Once it closes it is printed with its final value, and only then
+        let series = vec![(at(1), 2.0), (at(2), 3.0), (at(3), 0.1)];
+        let now = at(3) + chrono::Duration::seconds(5);
+        let printed = new_buckets(series.clone(), Some(at(1)), width, now, false);
+        assert_eq!(printed, vec![(at(2), 3.0)]);
+
+        // A one-off print shows the open bucket too
+        let printed = new_buckets(series, Some(at(1)), width, now, true);
+        assert_eq!(printed, vec![(at(2), 3.0), (at(3), 0.1)]);
+    }
+}
@@ -0,0 +1,523 @@
+//! Upgrading parameter files to the current schema and comparing them with
+//! the templates
+//!
+//! The NixOS module accepts any keys in the parameters file and falls back to
+//! its defaults for the ones it knows, so a file written for an older schema
+//! (a renamed field, a missing one) still evaluates, just not as intended.
+//! `eigenix config migrate` rewrites such a file for [`SCHEMA_VERSION`].
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use colored::Colorize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{get_parameters_path, get_project_root, DeploymentConfig, SCHEMA_VERSION};
+
+/// Key holding the file's schema version
+const VERSION_KEY: &str = "schemaVersion";
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Upgrade a parameters file to the current schema
+    Migrate {
+        /// Parameters file (defaults to nix/settings.json)
+        file: Option<PathBuf>,
+        /// Show the changes without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show how a parameters file differs from a template's defaults
+    Diff {
+        /// Parameters file (defaults to nix/settings.json)
+        file: Option<PathBuf>,
+        /// Template to compare with (mainnet or testnet); defaults to the one
+        /// matching the file's environment
+        #[arg(short, long)]
+        template: Option<String>,
+    },
+}
+
+/// One step upgrading a file to `version` from the version before
+struct Migration {
+    version: u32,
+    apply: fn(&mut Map<String, Value>, &mut Vec<String>),
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    apply: camel_case_keys,
+}];
+
+pub fn run(command: ConfigCommand) -> Result<()> {
+    match command {
+        ConfigCommand::Migrate { file, dry_run } => migrate_file(file, dry_run),
+        ConfigCommand::Diff { file, template } => diff_file(file, template.as_deref()),
+    }
+}
+
+fn resolve(file: Option<PathBuf>) -> Result<PathBuf> {
+    match file {
+        Some(file) => Ok(file),
+        None => Ok(get_parameters_path(&get_project_root()?)),
+    }
+}
+
+fn read_json(path: &Path) -> Result<Map<String, Value>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    match serde_json::from_str(&content).context("Failed to parse configuration JSON")? {
+        Value::Object(fields) => Ok(fields),
+        _ => anyhow::bail!("{} does not hold a JSON object", path.display()),
+    }
+}
+
+/// The template a file was most likely made from
+fn template_for(file: &Map<String, Value>) -> &'static str {
+    match file
+        .get("deployment")
+        .and_then(|deployment| deployment.get("environment"))
+        .and_then(Value::as_str)
+    {
+        Some("testnet") => "testnet",
+        _ => "mainnet",
+    }
+}
+
+fn template_json(name: &str) -> Result<Map<String, Value>> {
+    match serde_json::to_value(DeploymentConfig::from_template(name))? {
+        Value::Object(fields) => Ok(fields),
+        _ => unreachable!("DeploymentConfig serializes to an object"),
+    }
+}
+
+/// `base_data_dir` → `baseDataDir`; camelCase keys are returned as they are
+pub fn to_camel_case(key: &str) -> String {
+    let mut camel = String::with_capacity(key.len());
+    let mut upper = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper = !camel.is_empty();
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+/// Version 1: every key is camelCase, as the NixOS module reads them, so
+/// snake_case spellings (`base_data_dir`) are renamed
+fn camel_case_keys(fields: &mut Map<String, Value>, changes: &mut Vec<String>) {
+    fn rename(fields: &mut Map<String, Value>, path: &str, changes: &mut Vec<String>) {
+        // Rebuilt rather than renamed in place so the keys keep their order
+        let old = std::mem::take(fields);
+        let present: Vec<String> = old.keys().cloned().collect();
+        for (key, mut value) in old {
+            let camel = to_camel_case(&key);
+            let key = if camel != key && !present.contains(&camel) {
+                changes.push(format!("renamed {}{} to {}", path, key, camel));
+                camel
+            } else {
+                key
+            };
+            if let Value::Object(nested) = &mut value {
+                rename(nested, &format!("{}{}.", path, key), changes);
+            }
+            fields.insert(key, value);
+        }
+    }
+    rename(fields, "", changes);
+}
+
+/// Add the keys of `defaults` that `fields` lacks
+fn fill_defaults(
+    fields: &mut Map<String, Value>,
+    defaults: &Map<String, Value>,
+    path: &str,
+    changes: &mut Vec<String>,
+) {
+    for (key, default) in defaults {
+        match (fields.get_mut(key), default) {
+            (None, _) => {
+                changes.push(format!("added {}{} = {}", path, key, default));
+                fields.insert(key.clone(), default.clone());
+            }
+            (Some(Value::Object(nested)), Value::Object(nested_defaults)) => {
+                fill_defaults(
+                    nested,
+                    nested_defaults,
+                    &format!("{}{}.", path, key),
+                    changes,
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Upgrade `fields` to [`SCHEMA_VERSION`], returning what was changed
+fn migrate(fields: &mut Map<String, Value>) -> Result<Vec<String>> {
+    let version = match fields.get(VERSION_KEY) {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .with_context(|| format!("{} is not a version number", VERSION_KEY))?,
+    };
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "The file is for schema version {}, newer than this CLI's {}; update the CLI",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+
+    let mut changes = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.version > version) {
+        (migration.apply)(fields, &mut changes);
+    }
+    if version < SCHEMA_VERSION {
+        let rest = std::mem::take(fields);
+        fields.insert(VERSION_KEY.to_string(), SCHEMA_VERSION.into());
+        fields.extend(rest.into_iter().filter(|(key, _)| key != VERSION_KEY));
+        changes.push(format!(
+            "set {} from {} to {}",
+            VERSION_KEY, version, SCHEMA_VERSION
+        ));
+    }
+    let defaults = template_json(template_for(fields))?;
+    fill_defaults(fields, &defaults, "", &mut changes);
+
+    serde_json::from_value::<DeploymentConfig>(Value::Object(fields.clone()))
+        .context("The upgraded file still doesn't match the schema")?;
+    Ok(changes)
+}
+
+fn migrate_file(file: Option<PathBuf>, dry_run: bool) -> Result<()> {
+    let path = resolve(file)?;
+    let mut fields = read_json(&path)?;
+    let changes = migrate(&mut fields)?;
+
+    if changes.is_empty() {
+        println!(
+            "{}",
+            format!(
+                "✓ {} is already at schema version {}",
+                path.display(),
+                SCHEMA_VERSION
+            )
+            .green()
+        );
+        return Ok(());
+    }
+
+    println!("{}", "=== Migration ===".bold().cyan());
+    for change in &changes {
+        println!("  {}", change);
+    }
+    println!();
+
+    if dry_run {
+        println!("Dry run, {} left unchanged.", path.display());
+        return Ok(());
+    }
+
+    let backup = path.with_extension("json.bak");
+    fs::copy(&path, &backup).with_context(|| format!("Failed to back up {}", path.display()))?;
+    let json = serde_json::to_string_pretty(&Value::Object(fields))
+        .context("Failed to serialize configuration")?;
+    fs::write(&path, json)
+        .with_context(|| format!("Failed to write config file: {}", path.display()))?;
+
+    println!(
+        "{}",
+        format!(
+            "✓ Upgraded {} to schema version {} (previous version kept as {})",
+            path.display(),
+            SCHEMA_VERSION,
+            backup.display()
+        )
+        .green()
+    );
+    Ok(())
+}
+
+/// A leaf where a file and a template disagree
+enum Difference {
+    Changed {
+        path: String,
+        template: Value,
+        file: Value,
+    },
+    /// Only in the file
+    Extra { path: String, value: Value },
+    /// Only in the template
+    Missing { path: String, value: Value },
+}
+
+fn diff(
+    file: &Map<String, Value>,
+    template: &Map<String, Value>,
+    path: &str,
+    out: &mut Vec<Difference>,
+) {
+    for (key, default) in template {
+        let key_path = format!("{}{}", path, key);
+        match (file.get(key), default) {
+            (None, _) => out.push(Difference::Missing {
+                path: key_path,
+                value: default.clone(),
+            }),
+            (Some(Value::Object(nested)), Value::Object(nested_template)) => {
+                diff(nested, nested_template, &format!("{}.", key_path), out)
+            }
+            (Some(value), _) if value != default => out.push(Difference::Changed {
+                path: key_path,
+                template: default.clone(),
+                file: value.clone(),
+            }),
+            _ => {}
+        }
+    }
+    for (key, value) in file {
+        if !template.contains_key(key) {
+            out.push(Difference::Extra {
+                path: format!("{}{}", path, key),
+                value: value.clone(),
+            });
+        }
+    }
+}
+
+fn diff_file(file: Option<PathBuf>, template: Option<&str>) -> Result<()> {
+    let path = resolve(file)?;
+    let fields = read_json(&path)?;
+    let template = template.unwrap_or_else(|| template_for(&fields));
+    if !matches!(template, "mainnet" | "testnet") {
+        anyhow::bail!(
+            "Unknown template '{}', expected mainnet or testnet",
+            template
+        );
+    }
+
+    let mut differences = Vec::new();
+    diff(&fields, &template_json(template)?, "", &mut differences);
+
+    println!(
+        "{}",
+        format!("=== {} vs the {} template ===", path.display(), template)
+            .bold()
+            .cyan()
+    );
+    if differences.is_empty() {
+        println!("{}", "✓ No differences".green());
+        return Ok(());
+    }
+    for difference in &differences {
+        match difference {
+            Difference::Changed {
+                path,
+                template,
+                file,
+            } => println!(
+                "  {} {}: {} → {}",
+                "~".yellow(),
+                path.bold(),
+                template,
+                file
+            ),
+            Difference::Extra { path, value } => println!(
+                "  {} {}: {} {}",
+                "+".green(),
+                path.bold(),
+                value,
+                "(not in the schema)".dimmed()
+            ),
+            Difference::Missing { path, value } => println!(
+                "  {} {}: {} {}",
+                "-".red(),
+                path.bold(),
+                value,
+                "(missing, run 'eigenix config migrate')".dimmed()
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn object(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(fields) => fields,
+            _ => panic!("not an object"),
+        }
+    }
+
+    /// The mainnet template as an unversioned file with snake_case keys, a
+    /// missing section and a key of the operator's own
+    fn old_file() -> Map<String, Value> {
+        let mut fields = template_json("mainnet").unwrap();
+        fields.remove(VERSION_KEY);
+        fields.remove("monitoring");
+        let storage = fields["storage"].as_object_mut().unwrap();
+        let base = storage.remove("baseDataDir").unwrap();
+        storage.insert("base_data_dir".to_string(), base);
+        let asb = fields["asb"].as_object_mut().unwrap();
+        asb.remove("maxBuyBtc");
+        asb.insert("max_buy_btc".to_string(), json!(0.5));
+        fields.insert("operatorNotes".to_string(), json!({"owner": "ops"}));
+        fields
+    }
+
+    #[test]
+    fn test_to_camel_case() {
+        assert_eq!(to_camel_case("base_data_dir"), "baseDataDir");
+        assert_eq!(to_camel_case("baseDataDir"), "baseDataDir");
+        assert_eq!(to_camel_case("enable"), "enable");
+        assert_eq!(to_camel_case("_private"), "private");
+    }
+
+    #[test]
+    fn test_camel_case_keys_renames_nested_keys_in_place() {
+        let mut fields = object(json!({
+            "storage": {"first_key": 1, "base_data_dir": "/mnt", "last": 2},
+            "asb": {"max_buy_btc": 0.5, "maxBuyBtc": 0.1},
+            "custom_section": {"keep": true},
+        }));
+        let mut changes = Vec::new();
+        camel_case_keys(&mut fields, &mut changes);
+
+        assert_eq!(
+            changes,
+            vec![
+                "renamed storage.first_key to firstKey",
+                "renamed storage.base_data_dir to baseDataDir",
+                "renamed custom_section to customSection",
+            ]
+        );
+        let storage: Vec<&String> = fields["storage"].as_object().unwrap().keys().collect();
+        assert_eq!(storage, ["firstKey", "baseDataDir", "last"]);
+        // A camelCase key already there is not overwritten
+        assert_eq!(fields["asb"], json!({"max_buy_btc": 0.5, "maxBuyBtc": 0.1}));
+        assert_eq!(fields["customSection"], json!({"keep": true}));
+
+        let mut again = Vec::new();
+        camel_case_keys(&mut fields, &mut again);
+        assert!(again.is_empty());
+    }
+
+    #[test]
+    fn test_fill_defaults_only_adds_what_is_missing() {
+        let defaults = object(json!({
+            "asb": {"enable": true, "maxBuyBtc": 0.1, "rendezvousPoints": []},
+            "monitoring": {"enableMetrics": true},
+        }));
+        let mut fields = object(json!({
+            "asb": {"maxBuyBtc": 0.5, "extra": "kept"},
+        }));
+        let mut changes = Vec::new();
+        fill_defaults(&mut fields, &defaults, "", &mut changes);
+
+        assert_eq!(
+            changes,
+            vec![
+                "added asb.enable = true",
+                "added asb.rendezvousPoints = []",
+                r#"added monitoring = {"enableMetrics":true}"#,
+            ]
+        );
+        assert_eq!(
+            Value::Object(fields.clone()),
+            json!({
+                "asb": {"maxBuyBtc": 0.5, "extra": "kept", "enable": true, "rendezvousPoints": []},
+                "monitoring": {"enableMetrics": true},
+            })
+        );
+
+        let mut again = Vec::new();
+        fill_defaults(&mut fields, &defaults, "", &mut again);
+        assert!(again.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_upgrades_an_old_file_once() {
+        let mut fields = old_file();
+        let changes = migrate(&mut fields).unwrap();
+
+        assert!(changes.contains(&"renamed storage.base_data_dir to baseDataDir".to_string()));
+        assert!(changes.contains(&"renamed asb.max_buy_btc to maxBuyBtc".to_string()));
+        assert!(changes.contains(&format!("set {} from 0 to {}", VERSION_KEY, SCHEMA_VERSION)));
+        assert!(changes
+            .iter()
+            .any(|change| change.starts_with("added monitoring = ")));
+
+        // The version leads, values and the operator's own keys are kept
+        assert_eq!(fields.keys().next().unwrap(), VERSION_KEY);
+        assert_eq!(fields[VERSION_KEY], json!(SCHEMA_VERSION));
+        assert_eq!(fields["asb"]["maxBuyBtc"], json!(0.5));
+        assert_eq!(fields["operatorNotes"], json!({"owner": "ops"}));
+        let config: DeploymentConfig =
+            serde_json::from_value(Value::Object(fields.clone())).unwrap();
+        assert_eq!(config.asb.max_buy_btc, 0.5);
+
+        let before = fields.clone();
+        assert!(migrate(&mut fields).unwrap().is_empty());
+        assert_eq!(fields, before);
+    }
+
+    #[test]
+    fn test_migrate_leaves_a_current_file_alone() {
+        let mut fields = template_json("testnet").unwrap();
+        let before = fields.clone();
+        assert!(migrate(&mut fields).unwrap().is_empty());
+        assert_eq!(fields, before);
+    }
+
+    #[test]
+    fn test_migrate_refuses_unknown_versions() {
+        let mut fields = template_json("mainnet").unwrap();
+        fields.insert(VERSION_KEY.to_string(), json!(SCHEMA_VERSION + 1));
+        let before = fields.clone();
+        assert!(migrate(&mut fields).is_err());
+        assert_eq!(fields, before);
+
+        fields.insert(VERSION_KEY.to_string(), json!("one"));
+        assert!(migrate(&mut fields).is_err());
+    }
+
+    #[test]
+    fn test_diff_lists_changed_extra_and_missing_keys() {
+        let template = template_json("mainnet").unwrap();
+        let mut file = template.clone();
+        file["asb"]["maxBuyBtc"] = json!(0.5);
+        file["asb"].as_object_mut().unwrap().remove("enable");
+        file.insert("operatorNotes".to_string(), json!("ops"));
+
+        let mut differences = Vec::new();
+        diff(&file, &template, "", &mut differences);
+        let listed: Vec<String> = differences
+            .iter()
+            .map(|difference| match difference {
+                Difference::Changed { path, file, .. } => format!("~{}={}", path, file),
+                Difference::Extra { path, .. } => format!("+{}", path),
+                Difference::Missing { path, .. } => format!("-{}", path),
+            })
+            .collect();
+        assert_eq!(
+            listed,
+            ["-asb.enable", "~asb.maxBuyBtc=0.5", "+operatorNotes"]
+        );
+
+        let mut none = Vec::new();
+        diff(&template, &template, "", &mut none);
+        assert!(none.is_empty());
+    }
+}
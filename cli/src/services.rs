@@ -0,0 +1,276 @@
+//! Starting, stopping and inspecting the deployment's services
+//!
+//! The NixOS module runs each service as a systemd unit, the containers
+//! through `podman-<name>.service`, so everything here goes through
+//! `systemctl`. Services are handled in dependency order: a start brings up
+//! what the service needs first, a stop takes down what needs it first.
+
+use anyhow::{Context, Result};
+use clap::{Subcommand, ValueEnum};
+use colored::Colorize;
+use std::collections::HashMap;
+use std::process::Command;
+
+use crate::config::{get_parameters_path, get_project_root, parameters_exist, DeploymentConfig};
+
+#[derive(Subcommand, Debug)]
+pub enum ServicesCommand {
+    /// Start a service and what it depends on, or every enabled service
+    Start { service: Option<Service> },
+    /// Stop a service and what depends on it, or every enabled service
+    Stop { service: Option<Service> },
+    /// Stop and start again a service and what depends on it
+    Restart { service: Option<Service> },
+    /// Show whether each service is running; exits with 1 if an enabled one isn't
+    Status { service: Option<Service> },
+}
+
+/// The managed services, in dependency order
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Service {
+    Bitcoind,
+    Monerod,
+    Electrs,
+    Asb,
+    Mempool,
+    Backend,
+    Web,
+}
+
+const ALL: [Service; 7] = [
+    Service::Bitcoind,
+    Service::Monerod,
+    Service::Electrs,
+    Service::Asb,
+    Service::Mempool,
+    Service::Backend,
+    Service::Web,
+];
+
+impl Service {
+    fn name(self) -> &'static str {
+        match self {
+            Service::Bitcoind => "bitcoind",
+            Service::Monerod => "monerod",
+            Service::Electrs => "electrs",
+            Service::Asb => "asb",
+            Service::Mempool => "mempool",
+            Service::Backend => "backend",
+            Service::Web => "web",
+        }
+    }
+
+    /// The systemd units making up the service, in start order
+    fn units(self) -> &'static [&'static str] {
+        match self {
+            Service::Bitcoind => &["podman-bitcoind.service"],
+            Service::Monerod => &["podman-monerod.service"],
+            Service::Electrs => &["podman-electrs.service"],
+            Service::Asb => &[
+                "podman-asb.service",
+                "podman-asb-controller.service",
+                "podman-asb-tracing-logger.service",
+            ],
+            Service::Mempool => &[
+                "podman-mempool-db.service",
+                "podman-mempool-api.service",
+                "podman-mempool-web.service",
+            ],
+            Service::Backend => &[
+                "podman-surrealdb.service",
+                "podman-monero-wallet-rpc.service",
+                "eigenix-backend.service",
+            ],
+            Service::Web => &["eigenix-web.service"],
+        }
+    }
+
+    /// Services this one needs running, as the units' `requires` say
+    fn dependencies(self) -> &'static [Service] {
+        match self {
+            Service::Bitcoind | Service::Monerod | Service::Web => &[],
+            Service::Electrs => &[Service::Bitcoind],
+            Service::Asb => &[Service::Electrs, Service::Monerod],
+            Service::Mempool => &[Service::Bitcoind, Service::Electrs],
+            Service::Backend => &[Service::Asb, Service::Monerod],
+        }
+    }
+
+    fn enabled(self, config: &DeploymentConfig) -> bool {
+        match self {
+            Service::Bitcoind => config.bitcoin.enable,
+            Service::Monerod => config.monero.enable,
+            Service::Electrs => config.electrs.enable,
+            Service::Asb => config.asb.enable,
+            Service::Mempool => config.mempool.enable,
+            Service::Backend => config.backend.enable,
+            Service::Web => config.web.enable,
+        }
+    }
+
+    /// Whether this service needs `other`, directly or through another one
+    fn depends_on(self, other: Service) -> bool {
+        self.dependencies()
+            .iter()
+            .any(|&dependency| dependency == other || dependency.depends_on(other))
+    }
+}
+
+/// Enabled services to act on, in dependency order: `service` with what it
+/// needs (`with_dependencies`) or with what needs it, or all of them
+fn plan(
+    config: &DeploymentConfig,
+    service: Option<Service>,
+    with_dependencies: bool,
+) -> Vec<Service> {
+    ALL.into_iter()
+        .filter(|s| s.enabled(config))
+        .filter(|&s| match service {
+            None => true,
+            Some(target) if with_dependencies => s == target || target.depends_on(s),
+            Some(target) => s == target || s.depends_on(target),
+        })
+        .collect()
+}
+
+fn systemctl(action: &str, units: &[&str]) -> Result<()> {
+    let output = Command::new("systemctl")
+        .arg(action)
+        .args(units)
+        .output()
+        .context("Failed to run systemctl")?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Run `action` on each service's units in turn, stopping at the first failure
+fn act(action: &str, label: &str, services: &[Service]) -> Result<()> {
+    for service in services {
+        print!("  {} {}... ", label, service.name().bold());
+        match systemctl(action, service.units()) {
+            Ok(()) => println!("{}", "✓".green()),
+            Err(e) => {
+                println!("{}", "✗".red());
+                return Err(e.context(format!("Failed to {} {}", action, service.name())));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn run(command: ServicesCommand) -> Result<()> {
+    let project_root = get_project_root()?;
+    if !parameters_exist(&project_root) {
+        anyhow::bail!("No configuration found. Run 'eigenix init' first.");
+    }
+    let config = DeploymentConfig::load(&get_parameters_path(&project_root))?;
+
+    let nothing = |service: Option<Service>| match service {
+        Some(service) if !service.enabled(&config) => {
+            println!(
+                "{}",
+                format!("{} is disabled in the configuration", service.name()).yellow()
+            );
+            true
+        }
+        _ => false,
+    };
+
+    match command {
+        ServicesCommand::Start { service } => {
+            if nothing(service) {
+                return Ok(());
+            }
+            act("start", "Starting", &plan(&config, service, true))
+        }
+        ServicesCommand::Stop { service } => {
+            if nothing(service) {
+                return Ok(());
+            }
+            let mut services = plan(&config, service, false);
+            services.reverse();
+            act("stop", "Stopping", &services)
+        }
+        ServicesCommand::Restart { service } => {
+            if nothing(service) {
+                return Ok(());
+            }
+            let services = plan(&config, service, false);
+            let reversed: Vec<Service> = services.iter().rev().copied().collect();
+            act("stop", "Stopping", &reversed)?;
+            act("start", "Starting", &services)
+        }
+        ServicesCommand::Status { service } => show_status(&config, service),
+    }
+}
+
+/// `systemctl show` properties of `unit`
+fn unit_properties(unit: &str) -> Result<HashMap<String, String>> {
+    let output = Command::new("systemctl")
+        .args([
+            "show",
+            "--property=ActiveState,SubState,ActiveEnterTimestamp",
+            unit,
+        ])
+        .output()
+        .context("Failed to run systemctl")?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect())
+}
+
+fn show_status(config: &DeploymentConfig, service: Option<Service>) -> Result<()> {
+    let services: Vec<Service> = match service {
+        Some(service) => vec![service],
+        None => ALL.to_vec(),
+    };
+    let width = services
+        .iter()
+        .flat_map(|s| s.units())
+        .map(|unit| unit.len())
+        .max()
+        .unwrap_or(0);
+
+    println!("{}", "=== Eigenix Services ===".bold().cyan());
+    let mut all_running = true;
+    for service in services {
+        println!("{}", service.name().bold());
+        if !service.enabled(config) {
+            println!("  {}", "disabled in the configuration".dimmed());
+            continue;
+        }
+        for unit in service.units() {
+            let properties = unit_properties(unit)?;
+            let get = |key: &str| properties.get(key).map(String::as_str).unwrap_or("");
+            // Padded before coloring, since the escape codes would count towards the width
+            let state = match get("ActiveState") {
+                "active" => format!("{:<12}", "● active").green(),
+                "failed" => format!("{:<12}", "✗ failed").red(),
+                "" => format!("{:<12}", "? unknown").red(),
+                other => format!("{:<12}", format!("○ {}", other)).yellow(),
+            };
+            all_running &= get("ActiveState") == "active";
+            let since = match get("ActiveEnterTimestamp") {
+                "" => String::new(),
+                since => format!("since {}", since),
+            };
+            println!(
+                "  {:<width$}  {} {:<10} {}",
+                unit,
+                state,
+                get("SubState"),
+                since.dimmed(),
+                width = width
+            );
+        }
+    }
+
+    if !all_running {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use colored::Colorize;
+use eigenix_backend::db::{StoredTradingTransaction, TransactionStatus};
+use eigenix_backend::trading::engine::TradingStatus;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::api::ApiClient;
+
+#[derive(Subcommand, Debug)]
+pub enum TradingCommand {
+    /// Show the engine's state and the balances it last saw
+    Status {
+        /// Keep refreshing until interrupted
+        #[arg(short, long)]
+        watch: bool,
+        /// Seconds between refreshes with --watch
+        #[arg(long, default_value = "5")]
+        interval: u64,
+    },
+    /// Turn automatic trading on
+    Enable,
+    /// Turn automatic trading off
+    Disable,
+    /// Show or change the trading configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// List the most recent deposits, trades, withdrawals and transfers
+    History {
+        /// Number of transactions to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the whole configuration, or one key of it
+    Get { key: Option<String> },
+    /// Change one key, e.g. `set max_btc_per_rebalance 0.05`
+    Set {
+        key: String,
+        /// New value, as JSON; anything that doesn't parse is taken as a string
+        value: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct EnableResponse {
+    enabled: bool,
+}
+
+pub async fn run(api: &ApiClient, command: TradingCommand) -> Result<()> {
+    match command {
+        TradingCommand::Status { watch, interval } => {
+            if !watch {
+                return show_status(api).await;
+            }
+            loop {
+                // Clear the screen and move the cursor home before each refresh
+                print!("\x1B[2J\x1B[H");
+                show_status(api).await?;
+                println!();
+                println!("Refreshing every {}s, Ctrl-C to stop", interval);
+                tokio::time::sleep(Duration::from_secs(interval.max(1))).await;
+            }
+        }
+        TradingCommand::Enable => set_enabled(api, true).await,
+        TradingCommand::Disable => set_enabled(api, false).await,
+        TradingCommand::Config {
+            command: ConfigCommand::Get { key },
+        } => show_config(api, key.as_deref()).await,
+        TradingCommand::Config {
+            command: ConfigCommand::Set { key, value },
+        } => set_config(api, &key, &value).await,
+        TradingCommand::History { limit } => show_history(api, limit).await,
+    }
+}
+
+fn balance(value: Option<f64>, decimals: usize) -> String {
+    value.map_or_else(|| "-".to_string(), |v| format!("{:.*}", decimals, v))
+}
+
+async fn show_status(api: &ApiClient) -> Result<()> {
+    let status: TradingStatus = api.get("/trading/status").await?;
+
+    let enabled = if status.enabled {
+        "yes".green()
+    } else {
+        "no".yellow()
+    };
+    let paused = if status.paused {
+        "yes".yellow()
+    } else {
+        "no".normal()
+    };
+
+    println!("{}", "=== Trading Status ===".bold().cyan());
+    println!("  {:<16}{:?}", "State", status.state);
+    println!("  {:<16}{}", "Enabled", enabled);
+    println!("  {:<16}{}", "Paused", paused);
+    println!(
+        "  {:<16}{}",
+        "Last check",
+        status.last_check.as_deref().unwrap_or("never")
+    );
+    println!(
+        "  {:<16}{}",
+        "Last rebalance",
+        status.last_rebalance.as_deref().unwrap_or("never")
+    );
+    println!();
+    println!("{}", "Balances".bold());
+    println!("  {:<16}{}", "BTC", balance(status.current_btc_balance, 8));
+    println!(
+        "  {:<16}{} ({} locked)",
+        "XMR",
+        balance(status.current_xmr_balance, 12),
+        balance(status.current_xmr_locked_balance, 12)
+    );
+    println!(
+        "  {:<16}{}",
+        "Exchange BTC",
+        balance(status.kraken_btc_balance, 8)
+    );
+    println!(
+        "  {:<16}{}",
+        "Exchange XMR",
+        balance(status.kraken_xmr_balance, 12)
+    );
+    Ok(())
+}
+
+async fn set_enabled(api: &ApiClient, enabled: bool) -> Result<()> {
+    let (_, response): (_, EnableResponse) = api
+        .post("/trading/enable", &json!({ "enabled": enabled }))
+        .await?;
+
+    if response.enabled {
+        println!("{}", "✓ Trading enabled".green().bold());
+    } else {
+        println!("{}", "✓ Trading disabled".yellow().bold());
+    }
+    Ok(())
+}
+
+async fn show_config(api: &ApiClient, key: Option<&str>) -> Result<()> {
+    let config: Value = api.get("/trading/config").await?;
+    let fields = config
+        .as_object()
+        .context("The trading config is not a JSON object")?;
+
+    if let Some(key) = key {
+        let value = fields
+            .get(key)
+            .with_context(|| format!("Unknown trading config key '{}'", key))?;
+        println!("{}", value);
+        return Ok(());
+    }
+
+    let width = fields.keys().map(String::len).max().unwrap_or(0);
+    println!("{}", "=== Trading Config ===".bold().cyan());
+    for (key, value) in fields {
+        println!("  {:<width$}  {}", key.bold(), value, width = width);
+    }
+    Ok(())
+}
+
+async fn set_config(api: &ApiClient, key: &str, raw: &str) -> Result<()> {
+    let config: Value = api.get("/trading/config").await?;
+    let previous = config
+        .get(key)
+        .with_context(|| format!("Unknown trading config key '{}'", key))?;
+    let value = serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()));
+
+    // Only this key is sent, so changes made meanwhile to others are kept
+    let updated: Value = api.patch("/trading/config", &json!({ key: value })).await?;
+    println!(
+        "{} {}: {} → {}",
+        "✓".green().bold(),
+        key.bold(),
+        previous,
+        updated[key]
+    );
+    Ok(())
+}
+
+async fn show_history(api: &ApiClient, limit: usize) -> Result<()> {
+    let transactions: Vec<StoredTradingTransaction> = api
+        .get(&format!("/trading/transactions?limit={}", limit))
+        .await?;
+
+    println!("{}", "=== Trading History ===".bold().cyan());
+    if transactions.is_empty() {
+        println!("No transactions recorded yet.");
+        return Ok(());
+    }
+
+    println!(
+        "  {:<16}  {:<20}  {:<9}  {:>12}  {:>16}  {:>12}  Reference",
+        "Time", "Type", "Status", "BTC", "XMR", "Fee"
+    );
+    for tx in &transactions {
+        // Padded before coloring, since the escape codes would count towards the width
+        let status = format!("{:<9}", format!("{:?}", tx.status));
+        let status = match tx.status {
+            TransactionStatus::Completed => status.green(),
+            TransactionStatus::Pending => status.yellow(),
+            TransactionStatus::Failed => status.red(),
+            TransactionStatus::Cancelled => status.dimmed(),
+        };
+        let reference = tx
+            .txid
+            .as_deref()
+            .or(tx.order_id.as_deref())
+            .or(tx.refid.as_deref())
+            .unwrap_or("-");
+        println!(
+            "  {:<16}  {:<20}  {}  {:>12}  {:>16}  {:>12}  {}",
+            tx.timestamp.format("%Y-%m-%d %H:%M"),
+            format!("{:?}", tx.transaction_type),
+            status,
+            balance(tx.btc_amount, 8),
+            balance(tx.xmr_amount, 12),
+            balance(tx.fee, 8),
+            reference
+        );
+    }
+    Ok(())
+}
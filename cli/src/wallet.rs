@@ -0,0 +1,185 @@
+use anyhow::Result;
+use clap::{Subcommand, ValueEnum};
+use colored::Colorize;
+use dialoguer::Confirm;
+use eigenix_backend::wallets::transfer::{TransferAsset, TransferRequest};
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::api::ApiClient;
+
+#[derive(Subcommand, Debug)]
+pub enum WalletCommand {
+    /// Show the BTC and XMR balances
+    Balance,
+    /// Show a deposit address
+    Address {
+        /// Wallet to show the address of
+        #[arg(long, value_enum)]
+        asset: Asset,
+        /// Create a new XMR subaddress instead of showing the primary address;
+        /// every BTC address is new
+        #[arg(long)]
+        new: bool,
+    },
+    /// Send funds out of a wallet
+    Send {
+        #[arg(long, value_enum)]
+        asset: Asset,
+        /// Destination address
+        #[arg(long)]
+        to: String,
+        /// Amount in BTC or XMR
+        #[arg(long)]
+        amount: f64,
+        /// Managed wallet to send from
+        #[arg(long, default_value = "eigenix")]
+        from: String,
+        /// Send without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Asset {
+    Btc,
+    Xmr,
+}
+
+impl Asset {
+    fn symbol(self) -> &'static str {
+        match self {
+            Asset::Btc => "BTC",
+            Asset::Xmr => "XMR",
+        }
+    }
+
+    fn wallet_path(self) -> &'static str {
+        match self {
+            Asset::Btc => "/wallets/bitcoin",
+            Asset::Xmr => "/wallets/monero",
+        }
+    }
+}
+
+impl From<Asset> for TransferAsset {
+    fn from(asset: Asset) -> Self {
+        match asset {
+            Asset::Btc => TransferAsset::Btc,
+            Asset::Xmr => TransferAsset::Xmr,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Balances {
+    bitcoin: f64,
+    monero: f64,
+}
+
+#[derive(Deserialize)]
+struct Address {
+    address: String,
+}
+
+/// What `POST /wallets/transfer` answers: the recorded send, or the approval holding it
+#[derive(Deserialize)]
+struct TransferResponse {
+    id: Option<String>,
+    txid: Option<String>,
+    fee: Option<f64>,
+}
+
+pub async fn run(api: &ApiClient, command: WalletCommand) -> Result<()> {
+    match command {
+        WalletCommand::Balance => show_balance(api).await,
+        WalletCommand::Address { asset, new } => show_address(api, asset, new).await,
+        WalletCommand::Send {
+            asset,
+            to,
+            amount,
+            from,
+            yes,
+        } => send(api, asset, &to, amount, &from, yes).await,
+    }
+}
+
+async fn show_balance(api: &ApiClient) -> Result<()> {
+    let balances: Balances = api.get("/wallets/balances").await?;
+
+    println!("{}", "=== Wallet Balances ===".bold().cyan());
+    println!("  {}  {:.8}", "BTC".bold(), balances.bitcoin);
+    println!("  {}  {:.12}", "XMR".bold(), balances.monero);
+    Ok(())
+}
+
+async fn show_address(api: &ApiClient, asset: Asset, new: bool) -> Result<()> {
+    let path = format!("{}/address", asset.wallet_path());
+    let address: Address = match (asset, new) {
+        (Asset::Xmr, true) => api.post(&path, &()).await?.1,
+        _ => api.get(&path).await?,
+    };
+
+    println!("{}", address.address);
+    Ok(())
+}
+
+async fn send(
+    api: &ApiClient,
+    asset: Asset,
+    to: &str,
+    amount: f64,
+    from: &str,
+    yes: bool,
+) -> Result<()> {
+    if !amount.is_finite() || amount <= 0.0 {
+        anyhow::bail!("--amount must be positive");
+    }
+
+    println!("{}", "=== Send ===".bold().cyan());
+    println!("  {:<7}{} {}", "Amount", amount, asset.symbol());
+    println!("  {:<7}{}", "To", to);
+    println!("  {:<7}{}", "From", from);
+    println!();
+
+    if !yes {
+        let confirmed = Confirm::new()
+            .with_prompt(format!("Send {} {} to {}?", amount, asset.symbol(), to))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    let request = TransferRequest {
+        asset: asset.into(),
+        from_wallet: from.to_string(),
+        to_address: to.to_string(),
+        amount,
+    };
+    let (status, response): (_, TransferResponse) = api.post("/wallets/transfer", &request).await?;
+
+    if status == StatusCode::ACCEPTED {
+        println!(
+            "{}",
+            format!(
+                "⚠ Held for approval {}; another key must approve it under /approvals",
+                response.id.unwrap_or_default()
+            )
+            .yellow()
+        );
+        return Ok(());
+    }
+
+    println!("{}", "✓ Sent".green().bold());
+    if let Some(txid) = response.txid {
+        println!("  {:<7}{}", "Txid", txid);
+    }
+    if let Some(fee) = response.fee {
+        println!("  {:<7}{} {}", "Fee", fee, asset.symbol());
+    }
+    Ok(())
+}
@@ -0,0 +1,24 @@
+//! Frontend display settings
+
+use serde::{Deserialize, Serialize};
+
+/// Number formatting for one asset
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AssetPrecision {
+    /// Decimals to display amounts with: the asset's smallest on-chain unit
+    pub display_decimals: u32,
+    /// Decimals Kraken accepts for amounts of this asset
+    pub exchange_decimals: u32,
+    /// False when Kraken's metadata was unavailable and `exchange_decimals`
+    /// fell back to `display_decimals`
+    pub from_exchange: bool,
+}
+
+/// Response for `GET /config/display`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DisplayConfig {
+    pub btc: AssetPrecision,
+    pub xmr: AssetPrecision,
+}
@@ -0,0 +1,24 @@
+//! Exchange prices
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Response for `GET /kraken/tickers`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct KrakenTickerResponse {
+    /// BTC/USD price
+    pub btc_usd: f64,
+    /// BTC/USD 24h change percentage
+    pub btc_usd_change_24h: f64,
+    /// XMR/USD price
+    pub xmr_usd: f64,
+    /// XMR/USD 24h change percentage
+    pub xmr_usd_change_24h: f64,
+    /// XMR/BTC price (BTC per XMR)
+    pub xmr_btc: f64,
+    /// XMR/BTC 24h change percentage
+    pub xmr_btc_change_24h: f64,
+    /// When the prices were fetched from Kraken
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,12 @@
+//! Request and response types of the Eigenix API
+//!
+//! Shared by the backend, which serves them, and the web dashboard, which
+//! reads them, so the two can't drift apart. With the `openapi` feature each
+//! type also derives its OpenAPI schema.
+
+pub mod config;
+pub mod kraken;
+pub mod metrics;
+pub mod preferences;
+pub mod trading;
+pub mod wallets;
@@ -0,0 +1,129 @@
+//! Node, ASB and container metrics as served by `/metrics`
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Bitcoin node peer and network health
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct BitcoinNetworkMetrics {
+    pub peers: u64,
+    pub inbound_peers: u64,
+    /// Entries in the node's ban list
+    pub banned_peers: u64,
+    /// Bytes sent and received since bitcoind started
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// Lowest feerate the node relays, in sat/vB
+    pub min_relay_fee: f64,
+}
+
+/// Monero daemon connection and sync health
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct MoneroNetworkMetrics {
+    pub incoming_connections: u64,
+    pub outgoing_connections: u64,
+    /// Size of the LMDB database in bytes
+    pub database_size: u64,
+    pub synchronized: bool,
+    /// monerod runs with `--offline` or has no network
+    pub offline: bool,
+    /// Fee per byte from `get_fee_estimate`, in atomic units
+    pub fee_per_byte: Option<u64>,
+}
+
+/// A Bitcoin metrics sample as served, with `size_on_disk` also formatted
+/// for display
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BitcoinMetricsView {
+    pub timestamp: DateTime<Utc>,
+    pub blocks: u64,
+    pub headers: u64,
+    pub verification_progress: f64,
+    pub size_on_disk: u64,
+    /// Binary units, e.g. `412.3 GiB`
+    pub size_on_disk_human: String,
+    /// In BTC
+    pub wallet_balance: Option<f64>,
+    /// Missing when the node didn't answer the peer and traffic calls
+    #[serde(default)]
+    pub network: Option<BitcoinNetworkMetrics>,
+}
+
+/// A Monero metrics sample as served, with `difficulty` also formatted for
+/// display
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MoneroMetricsView {
+    pub timestamp: DateTime<Utc>,
+    pub height: u64,
+    pub target_height: u64,
+    pub difficulty: u64,
+    /// SI units, e.g. `352.89 G`
+    pub difficulty_human: String,
+    pub tx_count: u64,
+    /// In XMR
+    pub wallet_balance: Option<f64>,
+    /// Missing when monerod didn't report its connections
+    #[serde(default)]
+    pub network: Option<MoneroNetworkMetrics>,
+}
+
+/// Database-stored ASB metrics with timestamp
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(
+    feature = "graphql",
+    derive(async_graphql::SimpleObject),
+    graphql(name = "AsbMetrics")
+)]
+pub struct StoredAsbMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub balance_btc: f64,
+    pub pending_swaps: u64,
+    pub completed_swaps: u64,
+    pub failed_swaps: u64,
+    pub up: bool,
+}
+
+/// Resource usage of a running container
+///
+/// Each figure is missing when the runtime doesn't report it: systemd units
+/// have no CPU percentage, and network counters only with `IPAccounting=`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct ContainerStats {
+    /// Share of one CPU, so above 100 when several cores are busy
+    pub cpu_percent: Option<f64>,
+    pub memory_bytes: Option<u64>,
+    /// The container's memory limit, or the host's memory when it has none
+    pub memory_limit_bytes: Option<u64>,
+    /// Bytes received and sent since the container started
+    pub net_rx_bytes: Option<u64>,
+    pub net_tx_bytes: Option<u64>,
+}
+
+/// Database-stored Container metrics with timestamp
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(
+    feature = "graphql",
+    derive(async_graphql::SimpleObject),
+    graphql(name = "ContainerMetrics")
+)]
+pub struct StoredContainerMetrics {
+    pub timestamp: DateTime<Utc>,
+    pub name: String,
+    pub up: bool,
+    pub restarts: u64,
+    pub uptime_seconds: u64,
+    /// Missing from rows collected before resource usage was recorded, and
+    /// while the container is down
+    #[serde(default)]
+    pub stats: Option<ContainerStats>,
+}
@@ -0,0 +1,122 @@
+//! Per-user dashboard preferences
+
+use serde::{Deserialize, Serialize};
+
+/// Longest default chart window, matching the history query limit (31 days)
+pub const MAX_INTERVAL_MINUTES: u32 = 31 * 24 * 60;
+
+/// Unit wallet balances are shown in
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum BalanceUnit {
+    #[default]
+    Btc,
+    Mbtc,
+    Sats,
+}
+
+/// Dashboard colour scheme
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// Fiat currency prices are quoted in
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FiatCurrency {
+    #[default]
+    Usd,
+    Eur,
+    Gbp,
+}
+
+/// Display preferences for one user
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UserPreferences {
+    pub unit: BalanceUnit,
+    pub theme: Theme,
+    /// Chart window the dashboard opens with
+    pub default_interval_minutes: u32,
+    pub fiat_currency: FiatCurrency,
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        Self {
+            unit: BalanceUnit::default(),
+            theme: Theme::default(),
+            default_interval_minutes: 5,
+            fiat_currency: FiatCurrency::default(),
+        }
+    }
+}
+
+impl UserPreferences {
+    /// Parse and validate a request body
+    ///
+    /// Unknown enum values (e.g. `"unit": "bits"`) are rejected with a message
+    /// naming the accepted ones.
+    pub fn from_json(value: serde_json::Value) -> Result<Self, String> {
+        let preferences: Self =
+            serde_json::from_value(value).map_err(|e| format!("Invalid preferences: {}", e))?;
+        preferences.validate()?;
+        Ok(preferences)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.default_interval_minutes == 0
+            || self.default_interval_minutes > MAX_INTERVAL_MINUTES
+        {
+            return Err(format!(
+                "default_interval_minutes must be between 1 and {}",
+                MAX_INTERVAL_MINUTES
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_json_validates_values() {
+        let preferences = UserPreferences::from_json(serde_json::json!({
+            "unit": "sats",
+            "theme": "light",
+            "default_interval_minutes": 60,
+            "fiat_currency": "EUR"
+        }))
+        .unwrap();
+        assert_eq!(preferences.unit, BalanceUnit::Sats);
+        assert_eq!(preferences.fiat_currency, FiatCurrency::Eur);
+
+        let err = UserPreferences::from_json(serde_json::json!({
+            "unit": "bits",
+            "theme": "dark",
+            "default_interval_minutes": 60,
+            "fiat_currency": "USD"
+        }))
+        .unwrap_err();
+        assert!(err.contains("bits"), "{}", err);
+
+        let err = UserPreferences::from_json(serde_json::json!({
+            "unit": "btc",
+            "theme": "dark",
+            "default_interval_minutes": 0,
+            "fiat_currency": "USD"
+        }))
+        .unwrap_err();
+        assert!(err.contains("default_interval_minutes"));
+    }
+}
@@ -0,0 +1,496 @@
+//! Trading engine status and configuration
+
+use chrono::{DateTime, Datelike, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// Trading configuration with runtime-updatable parameters
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TradingConfig {
+    /// Minimum Monero balance threshold (in XMR) before triggering rebalance
+    pub monero_min_threshold: f64,
+
+    /// Target Monero balance to maintain after rebalancing (in XMR)
+    pub monero_target_balance: f64,
+
+    /// Minimum Bitcoin balance to keep (don't trade below this, in BTC)
+    pub bitcoin_reserve_minimum: f64,
+
+    /// Maximum amount of Bitcoin to use in a single rebalance operation (in BTC)
+    pub max_btc_per_rebalance: f64,
+
+    /// Smallest rebalance worth placing (in BTC); smaller rebalances are skipped
+    #[serde(default)]
+    pub min_btc_per_rebalance: f64,
+
+    /// Check interval in seconds (how often to check balances)
+    pub check_interval_secs: u64,
+
+    /// Maximum time to wait for Kraken order execution in seconds
+    pub order_timeout_secs: u64,
+
+    /// Slippage tolerance percentage (e.g., 0.5 for 0.5%)
+    pub slippage_tolerance_percent: f64,
+
+    /// Whether to use limit orders (true) or market orders (false)
+    pub use_limit_orders: bool,
+
+    /// Maximum number of pending transactions before new rebalances are refused
+    #[serde(default = "default_max_pending_transactions")]
+    pub max_pending_transactions: usize,
+
+    /// Optional UTC windows during which rebalancing is allowed (always allowed when unset)
+    #[serde(default)]
+    pub trading_schedule: Option<TradingSchedule>,
+
+    /// Halt trading if total holdings (wallets + Kraken, valued in BTC) drop by
+    /// more than this percentage between checks with no recorded transaction
+    /// to explain it. 0 disables the guard.
+    #[serde(default = "default_max_holdings_drop_percent")]
+    pub max_holdings_drop_percent: f64,
+
+    /// Hold off rebalancing while received XMR that would cover the threshold
+    /// is still locked (waiting for its 10 confirmations)
+    #[serde(default)]
+    pub wait_for_xmr_unlock: bool,
+
+    /// Bitcoin balance (in BTC) below which XMR is sold back for BTC, as long
+    /// as XMR is above `monero_max_threshold`. 0 disables the reverse direction.
+    #[serde(default)]
+    pub bitcoin_min_threshold: f64,
+
+    /// Target Bitcoin balance after a reverse rebalance (in BTC)
+    #[serde(default)]
+    pub bitcoin_target_balance: f64,
+
+    /// XMR ceiling (in XMR): a reverse rebalance only starts above it and
+    /// never sells the balance below it
+    #[serde(default)]
+    pub monero_max_threshold: f64,
+
+    /// Fee-bump (RBF) the BTC deposit after this many minutes unconfirmed,
+    /// and again every such interval until it confirms. 0 disables bumping.
+    #[serde(default)]
+    pub rbf_bump_after_mins: u64,
+
+    /// Highest feerate (in sat/vB) a deposit is ever bumped to
+    #[serde(default = "default_rbf_max_fee_rate")]
+    pub rbf_max_fee_rate: f64,
+
+    /// BTC deposits of at least this much (in BTC) are created as PSBTs to
+    /// be signed offline instead of sent from the hot wallet. 0 disables.
+    #[serde(default)]
+    pub psbt_min_btc: f64,
+
+    /// How long to wait for a deposit PSBT to be signed and uploaded
+    #[serde(default = "default_psbt_signing_timeout_secs")]
+    pub psbt_signing_timeout_secs: u64,
+}
+
+fn default_max_pending_transactions() -> usize {
+    TradingConfig::default().max_pending_transactions
+}
+
+fn default_max_holdings_drop_percent() -> f64 {
+    TradingConfig::default().max_holdings_drop_percent
+}
+
+fn default_rbf_max_fee_rate() -> f64 {
+    TradingConfig::default().rbf_max_fee_rate
+}
+
+fn default_psbt_signing_timeout_secs() -> u64 {
+    TradingConfig::default().psbt_signing_timeout_secs
+}
+
+impl Default for TradingConfig {
+    fn default() -> Self {
+        Self {
+            monero_min_threshold: 1.0,        // Rebalance if XMR drops below 1.0
+            monero_target_balance: 5.0,       // Target 5.0 XMR after rebalancing
+            bitcoin_reserve_minimum: 0.00001, // Keep at least 0.00001 BTC
+            max_btc_per_rebalance: 0.01,      // Max 0.1 BTC per operation
+            min_btc_per_rebalance: 0.0,       // No minimum trade size
+            check_interval_secs: 300,         // Check every 5 minutes
+            order_timeout_secs: 600,          // Wait max 10 minutes for order
+            slippage_tolerance_percent: 1.0,  // 1% slippage tolerance
+            use_limit_orders: true,           // Use limit orders by default
+            max_pending_transactions: 5,      // Stop if 5 transactions are unresolved
+            trading_schedule: None,           // Trade at any time
+            max_holdings_drop_percent: 20.0,  // Halt on an unexplained 20% drop
+            wait_for_xmr_unlock: false,       // Rebalance on the unlocked balance alone
+            bitcoin_min_threshold: 0.0,       // Never sell XMR back for BTC
+            bitcoin_target_balance: 0.0,
+            monero_max_threshold: 0.0,
+            rbf_bump_after_mins: 0,           // Leave stuck deposits alone
+            rbf_max_fee_rate: 50.0,           // Never bump past 50 sat/vB
+            psbt_min_btc: 0.0,                // Send every deposit from the hot wallet
+            psbt_signing_timeout_secs: 86400, // Wait up to a day for a signature
+        }
+    }
+}
+
+impl TradingConfig {
+    /// Validate configuration parameters
+    pub fn validate(&self) -> Result<(), String> {
+        if self.monero_min_threshold >= self.monero_target_balance {
+            return Err("monero_min_threshold must be less than monero_target_balance".to_string());
+        }
+
+        if self.monero_min_threshold < 0.0 {
+            return Err("monero_min_threshold must be positive".to_string());
+        }
+
+        if self.bitcoin_reserve_minimum < 0.0 {
+            return Err("bitcoin_reserve_minimum must be positive".to_string());
+        }
+
+        if self.max_btc_per_rebalance <= 0.0 {
+            return Err("max_btc_per_rebalance must be positive".to_string());
+        }
+
+        if self.min_btc_per_rebalance < 0.0
+            || self.min_btc_per_rebalance > self.max_btc_per_rebalance
+        {
+            return Err(
+                "min_btc_per_rebalance must be between 0 and max_btc_per_rebalance".to_string(),
+            );
+        }
+
+        if self.check_interval_secs == 0 {
+            return Err("check_interval_secs must be greater than 0".to_string());
+        }
+
+        if self.slippage_tolerance_percent < 0.0 || self.slippage_tolerance_percent > 100.0 {
+            return Err("slippage_tolerance_percent must be between 0 and 100".to_string());
+        }
+
+        if self.max_pending_transactions == 0 {
+            return Err("max_pending_transactions must be greater than 0".to_string());
+        }
+
+        if !(0.0..=100.0).contains(&self.max_holdings_drop_percent) {
+            return Err("max_holdings_drop_percent must be between 0 and 100".to_string());
+        }
+
+        if self.bitcoin_min_threshold < 0.0 {
+            return Err("bitcoin_min_threshold must be positive".to_string());
+        }
+
+        if self.reverse_rebalance_enabled() {
+            if self.bitcoin_min_threshold >= self.bitcoin_target_balance {
+                return Err(
+                    "bitcoin_min_threshold must be less than bitcoin_target_balance".to_string(),
+                );
+            }
+
+            // Otherwise topping XMR up to its target could set off a sale straight away
+            if self.monero_max_threshold <= self.monero_target_balance {
+                return Err(
+                    "monero_max_threshold must be greater than monero_target_balance".to_string(),
+                );
+            }
+        }
+
+        if self.rbf_bump_enabled()
+            && !(self.rbf_max_fee_rate.is_finite() && self.rbf_max_fee_rate > 0.0)
+        {
+            return Err("rbf_max_fee_rate must be positive".to_string());
+        }
+
+        if self.psbt_min_btc < 0.0 {
+            return Err("psbt_min_btc must be positive".to_string());
+        }
+
+        if self.psbt_enabled() && self.psbt_signing_timeout_secs == 0 {
+            return Err("psbt_signing_timeout_secs must be greater than 0".to_string());
+        }
+
+        if let Some(schedule) = &self.trading_schedule {
+            schedule.validate()?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether XMR is sold back for BTC when the Bitcoin balance runs low
+    pub fn reverse_rebalance_enabled(&self) -> bool {
+        self.bitcoin_min_threshold > 0.0
+    }
+
+    /// Whether stuck BTC deposits are fee-bumped
+    pub fn rbf_bump_enabled(&self) -> bool {
+        self.rbf_bump_after_mins > 0
+    }
+
+    /// Whether large BTC deposits are signed offline
+    pub fn psbt_enabled(&self) -> bool {
+        self.psbt_min_btc > 0.0
+    }
+
+    /// Whether rebalancing is allowed at the given time
+    pub fn is_within_schedule(&self, now: DateTime<Utc>) -> bool {
+        self.trading_schedule
+            .as_ref()
+            .is_none_or(|schedule| schedule.is_open(now))
+    }
+}
+
+/// A single allowed trading window, in UTC
+///
+/// The window covers `[start, end)` on each of the listed days. An empty
+/// `days` list means every day of the week. Windows cannot wrap past
+/// midnight; use two windows for overnight periods.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TradingWindow {
+    /// Days of the week this window applies to (e.g. "Mon", "Tue")
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<String>))]
+    pub days: Vec<Weekday>,
+
+    /// Window start time (UTC, e.g. "08:00:00")
+    pub start: NaiveTime,
+
+    /// Window end time (UTC, exclusive)
+    pub end: NaiveTime,
+}
+
+impl TradingWindow {
+    /// Whether this window applies to the given weekday
+    fn applies_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    /// Whether the given time falls inside this window
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        let time = now.time();
+        self.applies_on(now.weekday()) && time >= self.start && time < self.end
+    }
+
+    /// Whether two windows share a day and have intersecting time ranges
+    fn overlaps(&self, other: &TradingWindow) -> bool {
+        let shares_day = ALL_WEEKDAYS
+            .iter()
+            .any(|day| self.applies_on(*day) && other.applies_on(*day));
+
+        shares_day && self.start < other.end && other.start < self.end
+    }
+}
+
+const ALL_WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// Set of UTC time windows during which the engine may rebalance
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TradingSchedule {
+    pub windows: Vec<TradingWindow>,
+}
+
+impl TradingSchedule {
+    /// Whether any window is open at the given time
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        self.windows.iter().any(|window| window.contains(now))
+    }
+
+    /// Validate schedule windows
+    pub fn validate(&self) -> Result<(), String> {
+        if self.windows.is_empty() {
+            return Err("trading_schedule must contain at least one window".to_string());
+        }
+
+        for (i, window) in self.windows.iter().enumerate() {
+            if window.start >= window.end {
+                return Err(format!(
+                    "trading_schedule window {} must start before it ends ({} >= {})",
+                    i, window.start, window.end
+                ));
+            }
+
+            for (j, other) in self.windows.iter().enumerate().skip(i + 1) {
+                if window.overlaps(other) {
+                    return Err(format!("trading_schedule windows {} and {} overlap", i, j));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Current state of the trading engine
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum TradingState {
+    /// Engine is disabled
+    Disabled,
+    /// Engine is idle, monitoring balances
+    Monitoring,
+    /// Engine is monitoring but the current time is outside the trading schedule
+    OutsideSchedule,
+    /// XMR below the threshold, but enough is locked to cover it once unlocked
+    WaitingForUnlock { locked: f64 },
+    /// Rebalance above the approval threshold, waiting for a second key
+    WaitingForApproval { approval_id: String },
+    /// Currently depositing Bitcoin to the exchange
+    DepositingBitcoin { amount: f64 },
+    /// Deposit PSBT waiting for an offline signature
+    WaitingForSignature { transaction_id: String },
+    /// Waiting for Bitcoin deposit to confirm on the exchange
+    WaitingForBitcoinDeposit { txid: String },
+    /// Executing BTC->XMR trade on the exchange
+    Trading { btc_amount: f64 },
+    /// Executing XMR->BTC trade on the exchange
+    SellingMonero { xmr_amount: f64 },
+    /// Waiting for trade order to complete
+    WaitingForTradeExecution { order_id: String },
+    /// Withdrawing Monero from the exchange
+    WithdrawingMonero { amount: f64 },
+    /// Waiting for Monero withdrawal to complete
+    WaitingForMoneroWithdrawal { refid: String },
+    /// Currently depositing Monero to the exchange
+    DepositingMonero { amount: f64 },
+    /// Waiting for Monero deposit to confirm on the exchange
+    WaitingForMoneroDeposit { txid: String },
+    /// Withdrawing Bitcoin from the exchange
+    WithdrawingBitcoin { amount: f64 },
+    /// Waiting for Bitcoin withdrawal to complete
+    WaitingForBitcoinWithdrawal { refid: String },
+    /// Paused by an operator, between checks or between steps of a rebalance
+    Paused,
+    /// Another instance holds the trading lock; this one takes over once it
+    /// is released or expires
+    Standby { message: String },
+    /// Error occurred during operation
+    Error { message: String },
+}
+
+impl TradingState {
+    /// Whether the engine is waiting on a deposit, trade or withdrawal that
+    /// an operator can skip or mark complete
+    pub fn awaits_step(&self) -> bool {
+        matches!(
+            self,
+            TradingState::WaitingForSignature { .. }
+                | TradingState::WaitingForBitcoinDeposit { .. }
+                | TradingState::WaitingForTradeExecution { .. }
+                | TradingState::WaitingForMoneroWithdrawal { .. }
+                | TradingState::WaitingForMoneroDeposit { .. }
+                | TradingState::WaitingForBitcoinWithdrawal { .. }
+        )
+    }
+
+    /// Variant name and fields, as serde tags the state in JSON
+    #[cfg(feature = "graphql")]
+    fn tagged(&self) -> (String, Option<serde_json::Value>) {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(tagged)) => match tagged.into_iter().next() {
+                Some((name, fields)) => (name, Some(fields)),
+                None => (String::new(), None),
+            },
+            Ok(serde_json::Value::String(name)) => (name, None),
+            _ => (String::new(), None),
+        }
+    }
+
+    /// Idle between checks, where a pause takes effect immediately
+    pub fn is_idle(&self) -> bool {
+        matches!(
+            self,
+            TradingState::Monitoring
+                | TradingState::OutsideSchedule
+                | TradingState::WaitingForUnlock { .. }
+                | TradingState::WaitingForApproval { .. }
+                | TradingState::Error { .. }
+        )
+    }
+}
+
+/// Status information about the trading engine
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(
+    feature = "graphql",
+    derive(async_graphql::SimpleObject),
+    graphql(complex)
+)]
+pub struct TradingStatus {
+    #[cfg_attr(feature = "graphql", graphql(skip))]
+    pub state: TradingState,
+    pub enabled: bool,
+    /// Set by an operator; the engine stops at the next step boundary
+    pub paused: bool,
+    pub last_check: Option<String>,
+    pub last_rebalance: Option<String>,
+    pub current_btc_balance: Option<f64>,
+    /// Spendable (unlocked) XMR, the balance rebalancing decisions use
+    pub current_xmr_balance: Option<f64>,
+    /// All XMR in the wallet, including recently received funds still locked
+    pub current_xmr_total_balance: Option<f64>,
+    /// XMR still waiting to unlock (total minus unlocked)
+    pub current_xmr_locked_balance: Option<f64>,
+    /// BTC held on the configured exchange (the field predates Binance support)
+    pub kraken_btc_balance: Option<f64>,
+    /// XMR held on the configured exchange
+    pub kraken_xmr_balance: Option<f64>,
+}
+
+#[cfg(feature = "graphql")]
+#[async_graphql::ComplexObject]
+impl TradingStatus {
+    /// Engine state, e.g. `Monitoring` or `WaitingForBitcoinDeposit`
+    async fn state(&self) -> String {
+        self.state.tagged().0
+    }
+
+    /// Fields of the state, e.g. `{"txid": "..."}`; null for states without any
+    async fn state_details(&self) -> Option<async_graphql::Json<serde_json::Value>> {
+        self.state.tagged().1.map(async_graphql::Json)
+    }
+}
+
+/// Request to enable/disable trading
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EnableRequest {
+    pub enabled: bool,
+}
+
+/// Response for enable/disable operations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EnableResponse {
+    pub success: bool,
+    pub enabled: bool,
+}
+
+#[cfg(all(test, feature = "graphql"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tagged_state() {
+        assert_eq!(
+            TradingState::Monitoring.tagged(),
+            ("Monitoring".to_string(), None)
+        );
+        assert_eq!(
+            TradingState::WaitingForBitcoinDeposit {
+                txid: "ab12".to_string()
+            }
+            .tagged(),
+            (
+                "WaitingForBitcoinDeposit".to_string(),
+                Some(serde_json::json!({ "txid": "ab12" }))
+            )
+        );
+    }
+}
@@ -0,0 +1,32 @@
+//! Wallet balances, health and deposit addresses
+
+use serde::{Deserialize, Serialize};
+
+/// Combined wallet balances response
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct WalletBalances {
+    /// Bitcoin balance in BTC
+    pub bitcoin: f64,
+    /// Monero balance in XMR
+    pub monero: f64,
+}
+
+/// Wallet health status response
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WalletHealth {
+    /// Whether wallets are healthy and operational
+    pub healthy: bool,
+    /// Individual wallet health status
+    pub bitcoin_ready: bool,
+    pub monero_ready: bool,
+}
+
+/// Deposit address response, for either wallet
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DepositAddress {
+    pub address: String,
+}
@@ -12,6 +12,8 @@ with lib;
 let
   cfg = config.services.eigenix-backend;
   settings = config.eigenix.finalSettings;
+  # Only the ASB limits are needed to seed the trading config
+  parametersFile = pkgs.writeText "eigenix-parameters.json" (builtins.toJSON { inherit (settings) asb; });
 in
 {
   options.services.eigenix-backend = {
@@ -172,9 +174,16 @@ in
             --bitcoin-rpc-url http://localhost:${toString settings.ports.bitcoinRpc} \
             --monero-rpc-url http://localhost:${toString settings.ports.moneroRpc} \
             --asb-rpc-url http://localhost:${toString settings.ports.asbRpc} \
+            --asb-log-path ${settings.storage.baseDataDir}/asb-data/logs \
+            --disk-path bitcoind=${settings.storage.baseDataDir}/bitcoind-data \
+            --disk-path monerod=${settings.storage.baseDataDir}/monerod-data \
+            --disk-path electrs=${settings.storage.baseDataDir}/electrs-data \
+            --disk-path surrealdb=${settings.storage.baseDataDir}/surrealdb \
             --db-endpoint localhost:${toString settings.ports.surrealdb} \
-            --bitcoin-cookie-path ${settings.storage.baseDataDir}/bitcoind-data/.cookie
+            --bitcoin-cookie-path ${settings.storage.baseDataDir}/bitcoind-data/.cookie \
+            --parameters ${parametersFile}
         '';
+        ExecReload = "${pkgs.coreutils}/bin/kill -HUP $MAINPID";
         Restart = "on-failure";
         RestartSec = "10s";
 
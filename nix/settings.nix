@@ -179,6 +179,13 @@ in
               default = "0.0.0.0";
               description = "Web frontend bind address (use 0.0.0.0 for external access)";
             };
+
+            apiUrl = mkOption {
+              type = types.nullOr types.str;
+              default = null;
+              example = "https://eigenix.example.com/api";
+              description = "Backend URL the frontend calls, absolute or relative to the page (default: the page's host on the backend port)";
+            };
           };
 
           ports = {
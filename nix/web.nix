@@ -12,6 +12,22 @@ with lib;
 let
   cfg = config.services.eigenix-web;
   settings = config.eigenix.finalSettings;
+
+  # Read by the frontend at startup to find the backend
+  runtimeConfig = pkgs.writeTextDir "config.json" (
+    builtins.toJSON (
+      { api_port = settings.ports.eigenixBackend; }
+      // optionalAttrs (settings.web.apiUrl != null) { api_url = settings.web.apiUrl; }
+    )
+  );
+
+  webRoot = pkgs.symlinkJoin {
+    name = "eigenix-web-root";
+    paths = [
+      runtimeConfig
+      cfg.package
+    ];
+  };
 in
 {
   options.services.eigenix-web = {
@@ -34,7 +50,7 @@ in
 
       serviceConfig = {
         Type = "simple";
-        ExecStart = "${pkgs.python3}/bin/python3 -m http.server ${toString settings.ports.eigenixWeb} --bind ${settings.web.host} --directory ${cfg.package}";
+        ExecStart = "${pkgs.python3}/bin/python3 -m http.server ${toString settings.ports.eigenixWeb} --bind ${settings.web.host} --directory ${webRoot}";
         Restart = "on-failure";
         RestartSec = "10s";
 
@@ -1,5 +1,4 @@
 fn main() {
-    // Define the API host and port at build time
-    println!("cargo:rustc-env=API_HOST=nixlab");
-    println!("cargo:rustc-env=API_PORT=3000");
+    // Optional backend URL baked into the bundle; see `src/constants.rs`
+    println!("cargo:rerun-if-env-changed=EIGENIX_API_URL");
 }
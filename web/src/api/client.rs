@@ -1,17 +1,107 @@
-use gloo_net::http::Request;
-use serde::de::DeserializeOwned;
+use std::sync::OnceLock;
 
-use crate::constants::api_base_url;
+use gloo_net::http::{Request, RequestBuilder};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::constants::{BUILD_API_URL, DEFAULT_API_PORT, RUNTIME_CONFIG_PATH};
+
+/// Backend URL resolved by [`ApiClient::configure`]
+static BASE_URL: OnceLock<String> = OnceLock::new();
+
+/// Deployment settings read from `/config.json`
+#[derive(Debug, Default, Deserialize)]
+struct RuntimeConfig {
+    /// Backend URL, absolute or relative to the page (e.g. `/api` behind a
+    /// reverse proxy)
+    api_url: Option<String>,
+    /// Backend port on the page's host, when `api_url` is unset
+    api_port: Option<u16>,
+}
+
+/// Fetch `/config.json`, treating a missing or invalid file as empty
+async fn fetch_runtime_config() -> RuntimeConfig {
+    let response = match Request::get(RUNTIME_CONFIG_PATH).send().await {
+        Ok(response) if response.ok() => response,
+        _ => return RuntimeConfig::default(),
+    };
+
+    response.json().await.unwrap_or_else(|e| {
+        dioxus_logger::tracing::warn!("Ignoring invalid {}: {}", RUNTIME_CONFIG_PATH, e);
+        RuntimeConfig::default()
+    })
+}
+
+/// The backend on the page's own host
+fn same_host_url(port: u16) -> String {
+    let location = web_sys::window().map(|window| window.location());
+    let protocol = location
+        .as_ref()
+        .and_then(|location| location.protocol().ok())
+        .unwrap_or_else(|| "http:".to_string());
+    let host = location
+        .as_ref()
+        .and_then(|location| location.hostname().ok())
+        .unwrap_or_else(|| "localhost".to_string());
+
+    format!("{}//{}:{}", protocol, host, port)
+}
+
+/// The backend URL when `/config.json` doesn't name one
+fn default_url() -> String {
+    match BUILD_API_URL {
+        Some(url) => url.to_string(),
+        None => same_host_url(DEFAULT_API_PORT),
+    }
+}
+
+/// Local storage key of the API key sent with every request
+const API_KEY_STORAGE_KEY: &str = "eigenix.api_key";
+
+/// Add the stored API key, if any, as a bearer token
+fn authorized(request: RequestBuilder) -> RequestBuilder {
+    let key = web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(API_KEY_STORAGE_KEY).ok().flatten())
+        .filter(|key| !key.is_empty());
+
+    match key {
+        Some(key) => request.header("Authorization", &format!("Bearer {}", key)),
+        None => request,
+    }
+}
 
 /// Shared API client with helper methods for making HTTP requests
 pub struct ApiClient;
 
 impl ApiClient {
+    /// Resolve the backend URL; call once before the first request
+    ///
+    /// `api_url` from `/config.json` wins, then its `api_port` on the page's
+    /// host, then `EIGENIX_API_URL` at build time, then port 3000 on the
+    /// page's host.
+    pub async fn configure() {
+        let config = fetch_runtime_config().await;
+        let url = match (config.api_url, config.api_port) {
+            (Some(url), _) => url,
+            (None, Some(port)) => same_host_url(port),
+            (None, None) => default_url(),
+        };
+        let url = url.trim_end_matches('/').to_string();
+
+        dioxus_logger::tracing::info!("Using backend at {}", url);
+        let _ = BASE_URL.set(url);
+    }
+
+    /// Base URL of the backend, without a trailing slash
+    pub fn base_url() -> String {
+        BASE_URL.get().cloned().unwrap_or_else(default_url)
+    }
+
     /// Make a GET request to the API
     pub async fn get<T: DeserializeOwned>(endpoint: &str) -> Result<T, String> {
-        let url = format!("{}{}", api_base_url(), endpoint);
+        let url = format!("{}{}", Self::base_url(), endpoint);
         
-        let response = Request::get(&url)
+        let response = authorized(Request::get(&url))
             .send()
             .await
             .map_err(|e| format!("Request failed: {}", e))?;
@@ -40,9 +130,9 @@ impl ApiClient {
         endpoint: &str,
         body: &B,
     ) -> Result<T, String> {
-        let url = format!("{}{}", api_base_url(), endpoint);
+        let url = format!("{}{}", Self::base_url(), endpoint);
         
-        let response = Request::post(&url)
+        let response = authorized(Request::post(&url))
             .json(body)
             .map_err(|e| format!("Failed to serialize body: {}", e))?
             .send()
@@ -73,9 +163,9 @@ impl ApiClient {
         endpoint: &str,
         body: &B,
     ) -> Result<T, String> {
-        let url = format!("{}{}", api_base_url(), endpoint);
+        let url = format!("{}{}", Self::base_url(), endpoint);
         
-        let response = Request::put(&url)
+        let response = authorized(Request::put(&url))
             .json(body)
             .map_err(|e| format!("Failed to serialize body: {}", e))?
             .send()
@@ -0,0 +1,7 @@
+use crate::api::ApiClient;
+use crate::types::metrics::DisplayConfig;
+
+/// Fetch per-asset display precision
+pub async fn fetch_display_config() -> Result<DisplayConfig, String> {
+    ApiClient::get("/config/display").await
+}
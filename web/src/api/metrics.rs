@@ -1,18 +1,46 @@
 use crate::api::ApiClient;
-use crate::types::metrics::{AsbMetrics, BitcoinMetrics, MoneroMetrics};
+use crate::types::metrics::{AsbMetrics, BitcoinMetrics, ContainerMetrics, MoneroMetrics};
+
+/// Most samples a chart asks for; longer ranges are downsampled by the backend
+const MAX_CHART_POINTS: i64 = 500;
+
+/// Interval endpoint path for `source`, with a `step` once the range would
+/// exceed [`MAX_CHART_POINTS`] samples a second
+fn interval_path(source: &str, minutes: i64) -> String {
+    let step = minutes * 60 / MAX_CHART_POINTS;
+    if step > 1 {
+        format!("/metrics/{}/interval?minutes={}&step={}", source, minutes, step)
+    } else {
+        format!("/metrics/{}/interval?minutes={}", source, minutes)
+    }
+}
 
 /// Fetch Bitcoin metrics for the given time interval (in minutes)
 pub async fn fetch_bitcoin_interval(minutes: i64) -> Result<Vec<BitcoinMetrics>, String> {
-    ApiClient::get(&format!("/metrics/bitcoin/interval?minutes={}", minutes)).await
+    ApiClient::get(&interval_path("bitcoin", minutes)).await
 }
 
 /// Fetch Monero metrics for the given time interval (in minutes)
 pub async fn fetch_monero_interval(minutes: i64) -> Result<Vec<MoneroMetrics>, String> {
-    ApiClient::get(&format!("/metrics/monero/interval?minutes={}", minutes)).await
+    ApiClient::get(&interval_path("monero", minutes)).await
 }
 
 /// Fetch ASB metrics for the given time interval (in minutes)
 pub async fn fetch_asb_interval(minutes: i64) -> Result<Vec<AsbMetrics>, String> {
-    ApiClient::get(&format!("/metrics/asb/interval?minutes={}", minutes)).await
+    ApiClient::get(&interval_path("asb", minutes)).await
+}
+
+/// Fetch the latest health of every monitored container
+pub async fn fetch_containers() -> Result<Vec<ContainerMetrics>, String> {
+    ApiClient::get("/metrics/containers").await
+}
+
+/// Fetch one container's metrics for the given time interval (in minutes)
+pub async fn fetch_container_interval(
+    name: &str,
+    minutes: i64,
+) -> Result<Vec<ContainerMetrics>, String> {
+    let path = format!("{}&name={}", interval_path("containers", minutes), name);
+    ApiClient::get(&path).await
 }
 
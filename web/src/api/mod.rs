@@ -1,7 +1,9 @@
 /// API client modules for interacting with the Eigenix backend
 pub mod client;
+pub mod config;
 pub mod kraken;
 pub mod metrics;
+pub mod preferences;
 pub mod trading;
 pub mod wallets;
 
@@ -0,0 +1,12 @@
+use crate::api::ApiClient;
+use crate::types::metrics::UserPreferences;
+
+/// Fetch the saved display preferences (defaults if none are saved)
+pub async fn fetch_preferences() -> Result<UserPreferences, String> {
+    ApiClient::get("/preferences").await
+}
+
+/// Save display preferences
+pub async fn save_preferences(preferences: &UserPreferences) -> Result<UserPreferences, String> {
+    ApiClient::put("/preferences", preferences).await
+}
@@ -1,6 +1,6 @@
 use crate::api::ApiClient;
 use crate::types::metrics::{TradingConfig, TradingStatus};
-use serde::Serialize;
+use eigenix_common::trading::{EnableRequest, EnableResponse};
 
 /// Fetch trading engine status
 pub async fn fetch_trading_status() -> Result<TradingStatus, String> {
@@ -17,17 +17,6 @@ pub async fn update_trading_config(config: &TradingConfig) -> Result<TradingConf
     ApiClient::put("/trading/config", config).await
 }
 
-#[derive(Serialize)]
-struct EnableRequest {
-    enabled: bool,
-}
-
-#[derive(serde::Deserialize)]
-struct EnableResponse {
-    success: bool,
-    enabled: bool,
-}
-
 /// Enable or disable the trading engine
 pub async fn set_trading_enabled(enabled: bool) -> Result<bool, String> {
     let response: EnableResponse = ApiClient::post("/trading/enable", &EnableRequest { enabled }).await?;
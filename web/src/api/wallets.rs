@@ -1,11 +1,6 @@
 use crate::api::ApiClient;
 use crate::types::metrics::{WalletBalances, WalletHealth};
-use serde::Deserialize;
-
-#[derive(Debug, Clone, Deserialize)]
-pub struct DepositAddress {
-    pub address: String,
-}
+pub use eigenix_common::wallets::DepositAddress;
 
 /// Fetch combined wallet balances (Bitcoin and Monero)
 pub async fn fetch_wallet_balances() -> Result<WalletBalances, String> {
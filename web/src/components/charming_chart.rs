@@ -9,6 +9,48 @@ use charming::{
 
 use crate::types::metrics::MetricValue;
 
+/// How a series is transformed before plotting
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ChartAgg {
+    /// Plot values as they are
+    #[default]
+    Raw,
+    /// Change since the previous point, for cumulative counters
+    Delta,
+    /// Change since the previous point per second
+    Rate,
+}
+
+/// Apply an aggregation to a series
+///
+/// `Delta` and `Rate` yield one point per consecutive pair, stamped with the
+/// later timestamp. `Rate` drops pairs whose timestamps don't parse or don't
+/// advance.
+pub fn aggregate(data: &[MetricValue], agg: ChartAgg) -> Vec<MetricValue> {
+    match agg {
+        ChartAgg::Raw => data.to_vec(),
+        ChartAgg::Delta => data
+            .windows(2)
+            .map(|pair| MetricValue {
+                timestamp: pair[1].timestamp.clone(),
+                value: pair[1].value - pair[0].value,
+            })
+            .collect(),
+        ChartAgg::Rate => data
+            .windows(2)
+            .filter_map(|pair| {
+                let from = chrono::DateTime::parse_from_rfc3339(&pair[0].timestamp).ok()?;
+                let to = chrono::DateTime::parse_from_rfc3339(&pair[1].timestamp).ok()?;
+                let secs = to.signed_duration_since(from).num_milliseconds() as f64 / 1000.0;
+                (secs > 0.0).then(|| MetricValue {
+                    timestamp: pair[1].timestamp.clone(),
+                    value: (pair[1].value - pair[0].value) / secs,
+                })
+            })
+            .collect(),
+    }
+}
+
 /// Reusable chart component for displaying time-series metric data using Charming (ECharts)
 ///
 /// This component renders an interactive line chart that automatically updates when data changes.
@@ -24,9 +66,12 @@ pub fn CharmingChart(
     color: String,
     /// Whether the Y-axis should begin at zero
     y_begin_at_zero: bool,
+    /// Transformation applied to `data` before plotting
+    #[props(default)]
+    aggregation: ChartAgg,
 ) -> Element {
     let chart_id = id.clone();
-    let chart_data = data.clone();
+    let chart_data = aggregate(&data, aggregation);
     let chart_color = color.clone();
 
     // Create renderer with fixed dimensions
@@ -178,3 +223,58 @@ pub fn CharmingChart(
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(points: &[(&str, f64)]) -> Vec<MetricValue> {
+        points
+            .iter()
+            .map(|(timestamp, value)| MetricValue {
+                timestamp: timestamp.to_string(),
+                value: *value,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_delta_diffs_consecutive_points() {
+        let data = series(&[
+            ("2024-01-01T00:00:00Z", 10.0),
+            ("2024-01-01T00:01:00Z", 12.0),
+            ("2024-01-01T00:02:00Z", 12.0),
+            ("2024-01-01T00:03:00Z", 17.0),
+        ]);
+
+        assert_eq!(aggregate(&data, ChartAgg::Raw), data);
+        assert_eq!(
+            aggregate(&data, ChartAgg::Delta),
+            series(&[
+                ("2024-01-01T00:01:00Z", 2.0),
+                ("2024-01-01T00:02:00Z", 0.0),
+                ("2024-01-01T00:03:00Z", 5.0),
+            ])
+        );
+        assert!(aggregate(&data[..1], ChartAgg::Delta).is_empty());
+    }
+
+    #[test]
+    fn test_rate_divides_delta_by_seconds() {
+        let data = series(&[
+            ("2024-01-01T00:00:00Z", 0.0),
+            ("2024-01-01T00:00:30Z", 15.0),
+            ("2024-01-01T00:02:30Z", 75.0),
+            // Same timestamp as the previous point: no rate
+            ("2024-01-01T00:02:30Z", 80.0),
+            ("not a timestamp", 90.0),
+        ]);
+
+        assert_eq!(
+            aggregate(&data, ChartAgg::Rate),
+            series(&[
+                ("2024-01-01T00:00:30Z", 0.5),
+                ("2024-01-01T00:02:30Z", 0.5),
+            ])
+        );
+    }
+}
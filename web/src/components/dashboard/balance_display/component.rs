@@ -1,7 +1,7 @@
 use dioxus::prelude::*;
 use crate::api;
 use crate::components::DepositModal;
-use crate::types::metrics::WalletBalances;
+use crate::types::metrics::{BalanceUnit, WalletBalances};
 
 /// Format a number to 6 significant figures
 fn format_significant_figures(value: f64, sig_figs: usize) -> String {
@@ -16,6 +16,15 @@ fn format_significant_figures(value: f64, sig_figs: usize) -> String {
     format!("{:.prec$}", value, prec = precision)
 }
 
+/// BTC amount converted to the preferred unit, with its label
+fn btc_in_unit(btc: f64, unit: BalanceUnit) -> (String, &'static str) {
+    match unit {
+        BalanceUnit::Btc => (format_significant_figures(btc, 6), "BTC"),
+        BalanceUnit::Mbtc => (format_significant_figures(btc * 1_000.0, 6), "mBTC"),
+        BalanceUnit::Sats => (format!("{:.0}", btc * 100_000_000.0), "sats"),
+    }
+}
+
 /// Skeleton version of balance display for loading states
 #[component]
 pub fn BalanceDisplaySkeleton() -> Element {
@@ -72,22 +81,30 @@ pub fn BalanceDisplaySkeleton() -> Element {
 
 /// Balance display component showing BTC and XMR balances with deposit buttons
 #[component]
-pub fn BalanceDisplay(balances: WalletBalances) -> Element {
+pub fn BalanceDisplay(balances: WalletBalances, #[props(default)] unit: BalanceUnit) -> Element {
     let mut show_btc_modal = use_signal(|| false);
     let mut show_xmr_modal = use_signal(|| false);
     let btc_address = use_signal(|| String::new());
     let xmr_address = use_signal(|| String::new());
+    let display_config = use_resource(|| async { api::config::fetch_display_config().await });
 
     // Debug logging
     dioxus_logger::tracing::info!("Rendering balances - BTC: {}, XMR: {}", balances.bitcoin, balances.monero);
 
-    // Format balances to 6 significant figures
-    let btc_display = format_significant_figures(balances.bitcoin, 6);
+    // Format balances to 6 significant figures, BTC in the preferred unit
+    let (btc_display, btc_unit) = btc_in_unit(balances.bitcoin, unit);
     let xmr_display = format_significant_figures(balances.monero, 6);
     
-    // Full precision for tooltips
-    let btc_full = format!("{:.8}", balances.bitcoin);
-    let xmr_full = format!("{:.12}", balances.monero);
+    // Full precision for tooltips, as reported by the backend
+    let (btc_decimals, xmr_decimals) = match &*display_config.read() {
+        Some(Ok(config)) => (
+            config.btc.display_decimals as usize,
+            config.xmr.display_decimals as usize,
+        ),
+        _ => (8, 12),
+    };
+    let btc_full = format!("{:.prec$}", balances.bitcoin, prec = btc_decimals);
+    let xmr_full = format!("{:.prec$}", balances.monero, prec = xmr_decimals);
 
     rsx! {
         document::Link { rel: "stylesheet", href: asset!("./style.css") }
@@ -115,7 +132,7 @@ pub fn BalanceDisplay(balances: WalletBalances) -> Element {
                     }
                     span {
                         class: "balance-currency btc-currency",
-                        "BTC"
+                        "{btc_unit}"
                     }
                 }
                 button {
@@ -158,6 +158,18 @@ pub fn ConfigDisplay(config: TradingConfig) -> Element {
                 }
             }
 
+            div {
+                class: "config-card",
+                h5 {
+                    class: "config-label",
+                    "MIN BTC PER REBALANCE"
+                }
+                p {
+                    class: "config-value config-value-sm",
+                    "{config.min_btc_per_rebalance:.8} BTC"
+                }
+            }
+
             div {
                 class: "config-card",
                 h5 {
@@ -1,13 +1,15 @@
 use dioxus::prelude::*;
 use crate::api;
-use crate::components::CharmingChart;
+use crate::components::{ChartAgg, CharmingChart};
 use crate::types::metrics::{AsbMetrics, MetricValue};
 
 /// ASB metrics section component
 #[component]
-pub fn AsbMetricsSection(interval: Signal<i64>) -> Element {
-    let data =
-        use_resource(move || async move { api::metrics::fetch_asb_interval(interval()).await });
+pub fn AsbMetricsSection(interval: Signal<i64>, generation: Signal<u64>) -> Element {
+    let data = use_resource(move || async move {
+        let _ = generation();
+        api::metrics::fetch_asb_interval(interval()).await
+    });
 
     rsx! {
         document::Link { rel: "stylesheet", href: asset!("./style.css") }
@@ -47,7 +49,7 @@ pub fn AsbCharts(data: Vec<AsbMetrics>) -> Element {
     let balance_data: Vec<MetricValue> = data
         .iter()
         .map(|m| MetricValue {
-            timestamp: m.timestamp.clone(),
+            timestamp: m.timestamp.to_rfc3339(),
             value: m.balance_btc,
         })
         .collect();
@@ -55,7 +57,7 @@ pub fn AsbCharts(data: Vec<AsbMetrics>) -> Element {
     let pending_data: Vec<MetricValue> = data
         .iter()
         .map(|m| MetricValue {
-            timestamp: m.timestamp.clone(),
+            timestamp: m.timestamp.to_rfc3339(),
             value: m.pending_swaps as f64,
         })
         .collect();
@@ -63,7 +65,7 @@ pub fn AsbCharts(data: Vec<AsbMetrics>) -> Element {
     let completed_data: Vec<MetricValue> = data
         .iter()
         .map(|m| MetricValue {
-            timestamp: m.timestamp.clone(),
+            timestamp: m.timestamp.to_rfc3339(),
             value: m.completed_swaps as f64,
         })
         .collect();
@@ -71,7 +73,7 @@ pub fn AsbCharts(data: Vec<AsbMetrics>) -> Element {
     let failed_data: Vec<MetricValue> = data
         .iter()
         .map(|m| MetricValue {
-            timestamp: m.timestamp.clone(),
+            timestamp: m.timestamp.to_rfc3339(),
             value: m.failed_swaps as f64,
         })
         .collect();
@@ -93,17 +95,19 @@ pub fn AsbCharts(data: Vec<AsbMetrics>) -> Element {
         }
         CharmingChart {
             id: "asb-completed".to_string(),
-            title: "COMPLETED SWAPS".to_string(),
+            title: "NEW COMPLETED SWAPS".to_string(),
             data: completed_data,
             color: "#00ff9f".to_string(),
-            y_begin_at_zero: true
+            y_begin_at_zero: true,
+            aggregation: ChartAgg::Delta
         }
         CharmingChart {
             id: "asb-failed".to_string(),
-            title: "FAILED SWAPS".to_string(),
+            title: "NEW FAILED SWAPS".to_string(),
             data: failed_data,
             color: "#ff3333".to_string(),
-            y_begin_at_zero: true
+            y_begin_at_zero: true,
+            aggregation: ChartAgg::Delta
         }
     }
 }
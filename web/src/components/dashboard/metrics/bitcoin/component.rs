@@ -5,8 +5,9 @@ use crate::types::metrics::{BitcoinMetrics, MetricValue};
 
 /// Bitcoin metrics section component
 #[component]
-pub fn BitcoinMetricsSection(interval: Signal<i64>) -> Element {
+pub fn BitcoinMetricsSection(interval: Signal<i64>, generation: Signal<u64>) -> Element {
     let data = use_resource(move || async move {
+        let _ = generation();
         api::metrics::fetch_bitcoin_interval(interval()).await
     });
 
@@ -48,7 +49,7 @@ pub fn BitcoinCharts(data: Vec<BitcoinMetrics>) -> Element {
     let blocks_data: Vec<MetricValue> = data
         .iter()
         .map(|m| MetricValue {
-            timestamp: m.timestamp.clone(),
+            timestamp: m.timestamp.to_rfc3339(),
             value: m.blocks as f64,
         })
         .collect();
@@ -56,7 +57,7 @@ pub fn BitcoinCharts(data: Vec<BitcoinMetrics>) -> Element {
     let progress_data: Vec<MetricValue> = data
         .iter()
         .map(|m| MetricValue {
-            timestamp: m.timestamp.clone(),
+            timestamp: m.timestamp.to_rfc3339(),
             value: m.verification_progress * 100.0,
         })
         .collect();
@@ -65,12 +66,22 @@ pub fn BitcoinCharts(data: Vec<BitcoinMetrics>) -> Element {
         .iter()
         .filter_map(|m| {
             m.wallet_balance.map(|b| MetricValue {
-                timestamp: m.timestamp.clone(),
+                timestamp: m.timestamp.to_rfc3339(),
                 value: b,
             })
         })
         .collect();
 
+    let peers_data: Vec<MetricValue> = data
+        .iter()
+        .filter_map(|m| {
+            m.network.as_ref().map(|n| MetricValue {
+                timestamp: m.timestamp.to_rfc3339(),
+                value: n.peers as f64,
+            })
+        })
+        .collect();
+
     rsx! {
         CharmingChart {
             id: "btc-blocks".to_string(),
@@ -86,6 +97,15 @@ pub fn BitcoinCharts(data: Vec<BitcoinMetrics>) -> Element {
             color: "#ffa500".to_string(),
             y_begin_at_zero: true
         }
+        if !peers_data.is_empty() {
+            CharmingChart {
+                id: "btc-peers".to_string(),
+                title: "PEERS".to_string(),
+                data: peers_data,
+                color: "#ffa500".to_string(),
+                y_begin_at_zero: true
+            }
+        }
         if !balance_data.is_empty() {
             CharmingChart {
                 id: "btc-balance".to_string(),
@@ -0,0 +1,155 @@
+use dioxus::prelude::*;
+use crate::api;
+use crate::components::{ChartAgg, CharmingChart};
+use crate::types::metrics::{ContainerMetrics, ContainerStats, MetricValue};
+
+/// Container metrics section component
+#[component]
+pub fn ContainerMetricsSection(interval: Signal<i64>, generation: Signal<u64>) -> Element {
+    let containers = use_resource(move || async move {
+        let _ = generation();
+        api::metrics::fetch_containers().await
+    });
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: asset!("./style.css") }
+
+        div {
+            class: "metrics-section container-metrics",
+
+            h3 {
+                class: "metrics-title",
+                "// CONTAINERS //"
+            }
+
+            match containers() {
+                Some(Ok(containers)) if containers.is_empty() => rsx! {
+                    p {
+                        class: "loading",
+                        "// NO CONTAINERS MONITORED"
+                    }
+                },
+                Some(Ok(containers)) => rsx! {
+                    for container in containers {
+                        ContainerCharts {
+                            key: "{container.name}",
+                            name: container.name.clone(),
+                            up: container.up,
+                            interval: interval,
+                            generation: generation
+                        }
+                    }
+                },
+                Some(Err(e)) => rsx! {
+                    p {
+                        class: "error",
+                        "ERROR: {e}"
+                    }
+                },
+                None => rsx! {
+                    p {
+                        class: "loading",
+                        "// LOADING CONTAINER METRICS..."
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// One usage figure of each sample that has it
+fn series(data: &[ContainerMetrics], field: fn(&ContainerStats) -> Option<f64>) -> Vec<MetricValue> {
+    data.iter()
+        .filter_map(|m| {
+            Some(MetricValue {
+                timestamp: m.timestamp.to_rfc3339(),
+                value: field(m.stats.as_ref()?)?,
+            })
+        })
+        .collect()
+}
+
+/// Resource usage charts of one container
+#[component]
+pub fn ContainerCharts(name: String, up: bool, interval: Signal<i64>, generation: Signal<u64>) -> Element {
+    let container = name.clone();
+    let data = use_resource(move || {
+        let container = container.clone();
+        async move {
+            let _ = generation();
+            api::metrics::fetch_container_interval(&container, interval()).await
+        }
+    });
+
+    let state = if up { "UP" } else { "DOWN" };
+
+    rsx! {
+        h4 {
+            class: "container-name",
+            "{name} [ {state} ]"
+        }
+
+        match data() {
+            Some(Ok(metrics)) => {
+                // Usage is only recorded while the container runs, and systemd reports no CPU
+                let cpu_data = series(&metrics, |s| s.cpu_percent);
+                let memory_data = series(&metrics, |s| s.memory_bytes.map(|b| b as f64 / 1048576.0));
+                let rx_data = series(&metrics, |s| s.net_rx_bytes.map(|b| b as f64));
+                let tx_data = series(&metrics, |s| s.net_tx_bytes.map(|b| b as f64));
+
+                rsx! {
+                    if !cpu_data.is_empty() {
+                        CharmingChart {
+                            id: format!("container-{name}-cpu"),
+                            title: "CPU %".to_string(),
+                            data: cpu_data,
+                            color: "#ff6b35".to_string(),
+                            y_begin_at_zero: true
+                        }
+                    }
+                    if !memory_data.is_empty() {
+                        CharmingChart {
+                            id: format!("container-{name}-memory"),
+                            title: "MEMORY (MiB)".to_string(),
+                            data: memory_data,
+                            color: "#ff00ff".to_string(),
+                            y_begin_at_zero: true
+                        }
+                    }
+                    if !rx_data.is_empty() {
+                        CharmingChart {
+                            id: format!("container-{name}-rx"),
+                            title: "NET RX (B/s)".to_string(),
+                            data: rx_data,
+                            color: "#00ff9f".to_string(),
+                            y_begin_at_zero: true,
+                            aggregation: ChartAgg::Rate
+                        }
+                    }
+                    if !tx_data.is_empty() {
+                        CharmingChart {
+                            id: format!("container-{name}-tx"),
+                            title: "NET TX (B/s)".to_string(),
+                            data: tx_data,
+                            color: "#00d4ff".to_string(),
+                            y_begin_at_zero: true,
+                            aggregation: ChartAgg::Rate
+                        }
+                    }
+                }
+            },
+            Some(Err(e)) => rsx! {
+                p {
+                    class: "error",
+                    "ERROR: {e}"
+                }
+            },
+            None => rsx! {
+                p {
+                    class: "loading",
+                    "// LOADING..."
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,9 @@
 pub mod bitcoin;
 pub mod monero;
 pub mod asb;
+pub mod containers;
 
 pub use bitcoin::*;
 pub use monero::*;
 pub use asb::*;
+pub use containers::*;
@@ -5,8 +5,9 @@ use crate::types::metrics::{MoneroMetrics, MetricValue};
 
 /// Monero metrics section component
 #[component]
-pub fn MoneroMetricsSection(interval: Signal<i64>) -> Element {
+pub fn MoneroMetricsSection(interval: Signal<i64>, generation: Signal<u64>) -> Element {
     let data = use_resource(move || async move {
+        let _ = generation();
         api::metrics::fetch_monero_interval(interval()).await
     });
 
@@ -48,7 +49,7 @@ pub fn MoneroCharts(data: Vec<MoneroMetrics>) -> Element {
     let height_data: Vec<MetricValue> = data
         .iter()
         .map(|m| MetricValue {
-            timestamp: m.timestamp.clone(),
+            timestamp: m.timestamp.to_rfc3339(),
             value: m.height as f64,
         })
         .collect();
@@ -56,7 +57,7 @@ pub fn MoneroCharts(data: Vec<MoneroMetrics>) -> Element {
     let difficulty_data: Vec<MetricValue> = data
         .iter()
         .map(|m| MetricValue {
-            timestamp: m.timestamp.clone(),
+            timestamp: m.timestamp.to_rfc3339(),
             value: m.difficulty as f64,
         })
         .collect();
@@ -64,7 +65,7 @@ pub fn MoneroCharts(data: Vec<MoneroMetrics>) -> Element {
     let tx_count_data: Vec<MetricValue> = data
         .iter()
         .map(|m| MetricValue {
-            timestamp: m.timestamp.clone(),
+            timestamp: m.timestamp.to_rfc3339(),
             value: m.tx_count as f64,
         })
         .collect();
@@ -73,12 +74,22 @@ pub fn MoneroCharts(data: Vec<MoneroMetrics>) -> Element {
         .iter()
         .filter_map(|m| {
             m.wallet_balance.map(|b| MetricValue {
-                timestamp: m.timestamp.clone(),
+                timestamp: m.timestamp.to_rfc3339(),
                 value: b,
             })
         })
         .collect();
 
+    let connections_data: Vec<MetricValue> = data
+        .iter()
+        .filter_map(|m| {
+            m.network.as_ref().map(|n| MetricValue {
+                timestamp: m.timestamp.to_rfc3339(),
+                value: (n.incoming_connections + n.outgoing_connections) as f64,
+            })
+        })
+        .collect();
+
     rsx! {
         CharmingChart {
             id: "xmr-height".to_string(),
@@ -101,6 +112,15 @@ pub fn MoneroCharts(data: Vec<MoneroMetrics>) -> Element {
             color: "#ff6b35".to_string(),
             y_begin_at_zero: false
         }
+        if !connections_data.is_empty() {
+            CharmingChart {
+                id: "xmr-connections".to_string(),
+                title: "CONNECTIONS".to_string(),
+                data: connections_data,
+                color: "#ff6b35".to_string(),
+                y_begin_at_zero: true
+            }
+        }
         if !balance_data.is_empty() {
             CharmingChart {
                 id: "xmr-balance".to_string(),
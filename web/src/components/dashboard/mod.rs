@@ -3,9 +3,11 @@ pub mod health_status;
 pub mod status_display;
 pub mod config_display;
 pub mod metrics;
+pub mod refresh_control;
 
 pub use balance_display::*;
 pub use health_status::*;
 pub use status_display::*;
 pub use config_display::*;
 pub use metrics::*;
+pub use refresh_control::*;
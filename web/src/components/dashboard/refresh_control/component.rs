@@ -0,0 +1,113 @@
+use dioxus::prelude::*;
+
+use super::state::{RefreshAction, RefreshInterval, RefreshState, STORAGE_KEY};
+
+/// Read the persisted refresh settings, falling back to the defaults
+fn load_state() -> RefreshState {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &RefreshState) {
+    let storage = web_sys::window().and_then(|window| window.local_storage().ok().flatten());
+    if let (Some(storage), Ok(json)) = (storage, serde_json::to_string(state)) {
+        let _ = storage.set_item(STORAGE_KEY, &json);
+    }
+}
+
+/// Resolve after `ms` milliseconds using the browser's `setTimeout`
+async fn sleep_ms(ms: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        if let Some(window) = web_sys::window() {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms);
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Apply a user action and persist the resulting settings
+fn apply(mut state: Signal<RefreshState>, action: RefreshAction) {
+    let (next, _) = state.peek().reduce(action);
+    state.set(next);
+    save_state(&next);
+}
+
+/// Dashboard auto-refresh: the settings and a generation counter
+///
+/// A coroutine ticks once a second and bumps the generation whenever the
+/// selected interval elapses. Resources that read the generation signal
+/// refetch each time it changes.
+pub fn use_dashboard_refresh() -> (Signal<RefreshState>, Signal<u64>) {
+    let mut state = use_signal(load_state);
+    let mut generation = use_signal(|| 0u64);
+
+    use_coroutine(move |_rx: UnboundedReceiver<()>| async move {
+        loop {
+            sleep_ms(1000).await;
+
+            let current = *state.peek();
+            let (next, refresh) = current.reduce(RefreshAction::Tick);
+            if next != current {
+                state.set(next);
+            }
+            if refresh {
+                generation.with_mut(|generation| *generation += 1);
+            }
+        }
+    });
+
+    (state, generation)
+}
+
+/// Interval selector and pause toggle for the dashboard auto-refresh
+#[component]
+pub fn RefreshControl(state: Signal<RefreshState>) -> Element {
+    let current = state();
+
+    let status = match current.interval.seconds() {
+        _ if current.paused => "PAUSED".to_string(),
+        Some(period) => format!(
+            "NEXT IN {:02}s",
+            period.saturating_sub(current.elapsed_secs)
+        ),
+        None => "AUTO-REFRESH OFF".to_string(),
+    };
+
+    rsx! {
+        div {
+            style: "display: flex; align-items: center; justify-content: center; gap: 20px; margin-bottom: 40px; flex-wrap: wrap;",
+            label {
+                style: "color: #fff; text-transform: uppercase; font-size: 12px; letter-spacing: 2px;",
+                "// AUTO-REFRESH:"
+            }
+            select {
+                value: "{current.interval.value()}",
+                style: "padding: 10px 35px 10px 15px; border: 1px solid #333; background: #0a0a0a; color: #fff; font-family: 'Courier New', monospace; font-size: 12px; text-transform: uppercase; cursor: pointer;",
+                onchange: move |evt| {
+                    if let Some(interval) = RefreshInterval::from_value(&evt.value()) {
+                        apply(state, RefreshAction::SetInterval(interval));
+                    }
+                },
+                for interval in RefreshInterval::ALL {
+                    option {
+                        value: interval.value(),
+                        selected: interval == current.interval,
+                        "{interval.label()}"
+                    }
+                }
+            }
+            button {
+                style: "padding: 10px 15px; border: 1px solid #333; background: #111; color: #fff; font-family: 'Courier New', monospace; font-size: 12px; text-transform: uppercase; letter-spacing: 1px; cursor: pointer;",
+                onclick: move |_| apply(state, RefreshAction::TogglePause),
+                if current.paused { "[ RESUME ]" } else { "[ PAUSE ]" }
+            }
+            span {
+                style: "color: #666; font-family: 'Courier New', monospace; font-size: 11px; letter-spacing: 1px;",
+                "{status}"
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+mod component;
+mod state;
+pub use component::{use_dashboard_refresh, RefreshControl};
+pub use state::{RefreshAction, RefreshInterval, RefreshState};
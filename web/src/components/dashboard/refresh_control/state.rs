@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+
+/// localStorage key holding the persisted [`RefreshState`]
+pub const STORAGE_KEY: &str = "eigenix.dashboard.refresh";
+
+/// How often the dashboard refetches its data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshInterval {
+    Off,
+    FiveSeconds,
+    FifteenSeconds,
+    SixtySeconds,
+}
+
+impl RefreshInterval {
+    pub const ALL: [RefreshInterval; 4] = [
+        RefreshInterval::Off,
+        RefreshInterval::FiveSeconds,
+        RefreshInterval::FifteenSeconds,
+        RefreshInterval::SixtySeconds,
+    ];
+
+    /// Seconds between refreshes, `None` when auto-refresh is off
+    pub fn seconds(self) -> Option<u32> {
+        match self {
+            RefreshInterval::Off => None,
+            RefreshInterval::FiveSeconds => Some(5),
+            RefreshInterval::FifteenSeconds => Some(15),
+            RefreshInterval::SixtySeconds => Some(60),
+        }
+    }
+
+    /// Value used in the `<select>` options
+    pub fn value(self) -> &'static str {
+        match self {
+            RefreshInterval::Off => "off",
+            RefreshInterval::FiveSeconds => "5",
+            RefreshInterval::FifteenSeconds => "15",
+            RefreshInterval::SixtySeconds => "60",
+        }
+    }
+
+    pub fn from_value(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|interval| interval.value() == value)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RefreshInterval::Off => "OFF",
+            RefreshInterval::FiveSeconds => "05 SEC",
+            RefreshInterval::FifteenSeconds => "15 SEC",
+            RefreshInterval::SixtySeconds => "60 SEC",
+        }
+    }
+}
+
+/// User input to the refresh control
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshAction {
+    SetInterval(RefreshInterval),
+    TogglePause,
+    /// One second of wall-clock time has passed
+    Tick,
+}
+
+/// Auto-refresh settings plus the time since the last refresh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefreshState {
+    pub interval: RefreshInterval,
+    pub paused: bool,
+    /// Seconds counted towards the next refresh; not persisted
+    #[serde(skip)]
+    pub elapsed_secs: u32,
+}
+
+impl Default for RefreshState {
+    fn default() -> Self {
+        Self {
+            interval: RefreshInterval::FifteenSeconds,
+            paused: false,
+            elapsed_secs: 0,
+        }
+    }
+}
+
+impl RefreshState {
+    /// Whether the timer is currently counting
+    pub fn is_active(&self) -> bool {
+        !self.paused && self.interval.seconds().is_some()
+    }
+
+    /// Apply an action, returning the new state and whether to refresh now
+    ///
+    /// Changing the interval or pausing restarts the countdown, so a refresh
+    /// never fires right after the user touches the control.
+    pub fn reduce(self, action: RefreshAction) -> (Self, bool) {
+        match action {
+            RefreshAction::SetInterval(interval) => (
+                Self {
+                    interval,
+                    elapsed_secs: 0,
+                    ..self
+                },
+                false,
+            ),
+            RefreshAction::TogglePause => (
+                Self {
+                    paused: !self.paused,
+                    elapsed_secs: 0,
+                    ..self
+                },
+                false,
+            ),
+            RefreshAction::Tick => {
+                let Some(period) = self.interval.seconds().filter(|_| !self.paused) else {
+                    return (self, false);
+                };
+                let elapsed_secs = self.elapsed_secs + 1;
+                if elapsed_secs >= period {
+                    (
+                        Self {
+                            elapsed_secs: 0,
+                            ..self
+                        },
+                        true,
+                    )
+                } else {
+                    (
+                        Self {
+                            elapsed_secs,
+                            ..self
+                        },
+                        false,
+                    )
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `ticks` one-second ticks and count the refreshes fired
+    fn run(mut state: RefreshState, ticks: u32) -> (RefreshState, u32) {
+        let mut fired = 0;
+        for _ in 0..ticks {
+            let (next, refresh) = state.reduce(RefreshAction::Tick);
+            state = next;
+            fired += refresh as u32;
+        }
+        (state, fired)
+    }
+
+    fn state(interval: RefreshInterval) -> RefreshState {
+        RefreshState {
+            interval,
+            ..RefreshState::default()
+        }
+    }
+
+    #[test]
+    fn test_ticks_fire_once_per_interval() {
+        let (_, fired) = run(state(RefreshInterval::FiveSeconds), 4);
+        assert_eq!(fired, 0);
+
+        let (_, fired) = run(state(RefreshInterval::FiveSeconds), 12);
+        assert_eq!(fired, 2);
+
+        let (_, fired) = run(state(RefreshInterval::SixtySeconds), 120);
+        assert_eq!(fired, 2);
+
+        let (_, fired) = run(state(RefreshInterval::Off), 600);
+        assert_eq!(fired, 0);
+    }
+
+    #[test]
+    fn test_pause_stops_and_resume_restarts_countdown() {
+        let (running, _) = run(state(RefreshInterval::FiveSeconds), 3);
+
+        let (paused, refresh) = running.reduce(RefreshAction::TogglePause);
+        assert!(!refresh);
+        assert!(paused.paused);
+        assert!(!paused.is_active());
+
+        let (paused, fired) = run(paused, 60);
+        assert_eq!(fired, 0);
+        assert_eq!(paused.elapsed_secs, 0);
+
+        let (resumed, _) = paused.reduce(RefreshAction::TogglePause);
+        assert!(resumed.is_active());
+        let (_, fired) = run(resumed, 4);
+        assert_eq!(fired, 0);
+        let (_, fired) = run(resumed, 5);
+        assert_eq!(fired, 1);
+    }
+
+    #[test]
+    fn test_interval_change_restarts_countdown() {
+        let (running, _) = run(state(RefreshInterval::SixtySeconds), 50);
+
+        let (changed, refresh) =
+            running.reduce(RefreshAction::SetInterval(RefreshInterval::FiveSeconds));
+        assert!(!refresh);
+        assert_eq!(changed.interval, RefreshInterval::FiveSeconds);
+        assert_eq!(changed.elapsed_secs, 0);
+
+        let (_, fired) = run(changed, 5);
+        assert_eq!(fired, 1);
+
+        // Turning it off keeps the pause flag but stops refreshing
+        let (off, _) = changed.reduce(RefreshAction::SetInterval(RefreshInterval::Off));
+        assert!(!off.is_active());
+        assert_eq!(run(off, 120).1, 0);
+    }
+
+    #[test]
+    fn test_persisted_form_skips_countdown() {
+        let (running, _) = run(state(RefreshInterval::FiveSeconds), 3);
+        let json = serde_json::to_string(&running).unwrap();
+        assert_eq!(json, r#"{"interval":"five_seconds","paused":false}"#);
+
+        let restored: RefreshState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.elapsed_secs, 0);
+        assert_eq!(restored.interval, RefreshInterval::FiveSeconds);
+
+        for interval in RefreshInterval::ALL {
+            assert_eq!(
+                RefreshInterval::from_value(interval.value()),
+                Some(interval)
+            );
+        }
+    }
+}
@@ -120,11 +120,20 @@ pub fn StatusDisplay(status: TradingStatus, config: TradingConfig) -> Element {
     let state_text = match &status.state {
         TradingState::Disabled => "DISABLED".to_string(),
         TradingState::Monitoring => "MONITORING".to_string(),
+        TradingState::OutsideSchedule => "OUTSIDE SCHEDULE".to_string(),
+        TradingState::WaitingForUnlock { locked } => format!("WAITING XMR UNLOCK ({:.12})", locked),
+        TradingState::WaitingForApproval { approval_id } => {
+            format!("WAITING APPROVAL ({})", approval_id)
+        }
         TradingState::DepositingBitcoin { amount } => format!("DEPOSITING BTC ({:.8})", amount),
+        TradingState::WaitingForSignature { transaction_id } => {
+            format!("WAITING PSBT SIGNATURE ({})", transaction_id)
+        }
         TradingState::WaitingForBitcoinDeposit { txid } => {
             format!("WAITING BTC DEPOSIT ({})", &txid[..8])
         }
         TradingState::Trading { btc_amount } => format!("TRADING ({:.8} BTC)", btc_amount),
+        TradingState::SellingMonero { xmr_amount } => format!("SELLING XMR ({:.12})", xmr_amount),
         TradingState::WaitingForTradeExecution { order_id } => {
             format!("WAITING TRADE ({})", order_id)
         }
@@ -132,34 +141,80 @@ pub fn StatusDisplay(status: TradingStatus, config: TradingConfig) -> Element {
         TradingState::WaitingForMoneroWithdrawal { refid } => {
             format!("WAITING XMR WITHDRAWAL ({})", refid)
         }
+        TradingState::DepositingMonero { amount } => format!("DEPOSITING XMR ({:.12})", amount),
+        TradingState::WaitingForMoneroDeposit { txid } => {
+            format!("WAITING XMR DEPOSIT ({})", &txid[..8])
+        }
+        TradingState::WithdrawingBitcoin { amount } => format!("WITHDRAWING BTC ({:.8})", amount),
+        TradingState::WaitingForBitcoinWithdrawal { refid } => {
+            format!("WAITING BTC WITHDRAWAL ({})", refid)
+        }
+        TradingState::Paused => "PAUSED".to_string(),
+        TradingState::Standby { .. } => "STANDBY".to_string(),
         TradingState::Error { message } => format!("ERROR: {}", message),
     };
 
     let state_color = match &status.state {
         TradingState::Disabled => "#666",
         TradingState::Monitoring => "#00d4ff",
+        TradingState::OutsideSchedule => "#666",
+        TradingState::WaitingForUnlock { .. } => "#00d4ff",
+        TradingState::WaitingForApproval { .. } => "#ffaa00",
         TradingState::DepositingBitcoin { .. } => "#ffaa00",
+        TradingState::WaitingForSignature { .. } => "#ffaa00",
         TradingState::WaitingForBitcoinDeposit { .. } => "#ffaa00",
         TradingState::Trading { .. } => "#ff00ff",
+        TradingState::SellingMonero { .. } => "#ff00ff",
         TradingState::WaitingForTradeExecution { .. } => "#ff00ff",
         TradingState::WithdrawingMonero { .. } => "#00ff9f",
         TradingState::WaitingForMoneroWithdrawal { .. } => "#00ff9f",
+        TradingState::DepositingMonero { .. } => "#ffaa00",
+        TradingState::WaitingForMoneroDeposit { .. } => "#ffaa00",
+        TradingState::WithdrawingBitcoin { .. } => "#00ff9f",
+        TradingState::WaitingForBitcoinWithdrawal { .. } => "#00ff9f",
+        TradingState::Paused => "#ffaa00",
+        TradingState::Standby { .. } => "#666",
         TradingState::Error { .. } => "#ff3333",
     };
 
     let state_tooltip = match &status.state {
         TradingState::Disabled => "Engine is not running",
         TradingState::Monitoring => "Actively monitoring balances for rebalancing opportunities",
+        TradingState::OutsideSchedule => "Rebalancing deferred until the next trading window",
+        TradingState::WaitingForUnlock { .. } => {
+            "Locked XMR will cover the threshold once it unlocks; rebalancing deferred"
+        }
+        TradingState::WaitingForApproval { .. } => {
+            "Rebalance is above the approval threshold and needs a second API key to approve it"
+        }
         TradingState::DepositingBitcoin { .. } => "Sending Bitcoin to Kraken exchange",
+        TradingState::WaitingForSignature { .. } => {
+            "Deposit PSBT is waiting to be signed offline and uploaded"
+        }
         TradingState::WaitingForBitcoinDeposit { .. } => {
             "Waiting for Bitcoin deposit confirmation on Kraken"
         }
-        TradingState::Trading { .. } => "Executing BTC→XMR trade on Kraken",
+        TradingState::Trading { .. } => "Executing trade on Kraken",
+        TradingState::SellingMonero { .. } => "Selling Monero for Bitcoin on the exchange",
         TradingState::WaitingForTradeExecution { .. } => "Waiting for trade order to complete",
         TradingState::WithdrawingMonero { .. } => "Withdrawing Monero from Kraken to local wallet",
         TradingState::WaitingForMoneroWithdrawal { .. } => {
             "Waiting for Monero withdrawal to complete"
         }
+        TradingState::DepositingMonero { .. } => "Sending Monero to Kraken exchange",
+        TradingState::WaitingForMoneroDeposit { .. } => {
+            "Waiting for Monero deposit confirmation on Kraken"
+        }
+        TradingState::WithdrawingBitcoin { .. } => {
+            "Withdrawing Bitcoin from Kraken to local wallet"
+        }
+        TradingState::WaitingForBitcoinWithdrawal { .. } => {
+            "Waiting for Bitcoin withdrawal to complete"
+        }
+        TradingState::Paused => "Paused by an operator; resume to continue",
+        TradingState::Standby { .. } => {
+            "Another instance holds the trading lock; trading starts here once it is free"
+        }
         TradingState::Error { .. } => "An error occurred during operation",
     };
 
@@ -376,6 +431,21 @@ pub fn StatusDisplay(status: TradingStatus, config: TradingConfig) -> Element {
                     }
                 }
 
+                if let Some(locked) = status.current_xmr_locked_balance.filter(|locked| *locked > 0.0) {
+                    div {
+                        class: "status-card status-card-secondary",
+
+                        h4 {
+                            class: "status-label",
+                            "LOCKED XMR"
+                        }
+                        p {
+                            class: "status-value status-value-sm",
+                            "{locked:.12}"
+                        }
+                    }
+                }
+
                 if let Some(xmr) = status.kraken_xmr_balance {
                     div {
                         class: "status-card status-card-secondary",
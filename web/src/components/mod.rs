@@ -5,7 +5,7 @@ pub mod header;
 pub mod navbar;
 pub mod dashboard;
 
-pub use charming_chart::CharmingChart;
+pub use charming_chart::{ChartAgg, CharmingChart};
 pub use deposit_modal::DepositModal;
 pub use header::*;
 pub use navbar::Navbar;
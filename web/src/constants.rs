@@ -1,15 +1,15 @@
-/// Backend API configuration
-///
-/// The backend server typically runs on port 3000 by default.
-/// Update these values if your backend is configured differently.
+//! Backend API configuration
+//!
+//! The backend URL is resolved at startup by [`crate::api::ApiClient::configure`]:
+//! from `/config.json` served next to the bundle, else from `EIGENIX_API_URL`
+//! at build time, else the page's own host on [`DEFAULT_API_PORT`]. The same
+//! bundle thereby works behind a reverse proxy, a domain or another port.
 
-/// API server port configuration
-pub const API_PORT: u16 = 3000;
+/// Port the backend listens on by default
+pub const DEFAULT_API_PORT: u16 = 3000;
 
-/// API server host configuration
-pub const API_HOST: &str = "nixlab";
+/// Backend URL baked into the bundle at build time, if any
+pub const BUILD_API_URL: Option<&str> = option_env!("EIGENIX_API_URL");
 
-/// Base URL for API requests
-pub fn api_base_url() -> String {
-    format!("http://{}:{}", API_HOST, API_PORT)
-}
+/// Runtime configuration fetched from the web server at startup
+pub const RUNTIME_CONFIG_PATH: &str = "/config.json";
@@ -8,6 +8,7 @@ mod pages;
 mod routes;
 mod types;
 
+use api::ApiClient;
 use routes::Route;
 
 const FAVICON: Asset = asset!("/assets/favicon.ico");
@@ -22,9 +23,14 @@ fn main() {
 /// Root application component
 #[component]
 fn App() -> Element {
+    // Pages fetch as soon as they mount, so find the backend first
+    let configured = use_resource(ApiClient::configure);
+
     rsx! {
         document::Link { rel: "icon", href: FAVICON }
         document::Link { rel: "stylesheet", href: MAIN_CSS }
-        Router::<Route> {}
+        if configured().is_some() {
+            Router::<Route> {}
+        }
     }
 }
@@ -8,18 +8,50 @@ use crate::components::{dashboard::*, Navbar};
 pub fn Dashboard() -> Element {
     let mut interval = use_signal(|| 5i64);
 
+    // Saved display preferences, loaded once on startup
+    let preferences = use_resource(|| async { api::preferences::fetch_preferences().await });
+    use_effect(move || {
+        if let Some(Ok(prefs)) = &*preferences.read() {
+            interval.set(prefs.default_interval_minutes as i64);
+        }
+    });
+    let unit = preferences
+        .read()
+        .as_ref()
+        .and_then(|result| result.as_ref().ok())
+        .map(|prefs| prefs.unit)
+        .unwrap_or_default();
+
     // Category toggles
     let mut show_bitcoin = use_signal(|| true);
     let mut show_monero = use_signal(|| true);
     let mut show_asb = use_signal(|| true);
+    let mut show_containers = use_signal(|| true);
+
+    // Auto-refresh; every resource below refetches when `generation` changes
+    let (refresh, generation) = use_dashboard_refresh();
+
+    let api_url = api::ApiClient::base_url();
 
     // Fetch wallet data
-    let balances = use_resource(|| async move { api::wallets::fetch_wallet_balances().await });
-    let health = use_resource(|| async move { api::wallets::fetch_wallet_health().await });
+    let balances = use_resource(move || async move {
+        let _ = generation();
+        api::wallets::fetch_wallet_balances().await
+    });
+    let health = use_resource(move || async move {
+        let _ = generation();
+        api::wallets::fetch_wallet_health().await
+    });
 
     // Fetch trading data
-    let status = use_resource(|| async move { api::trading::fetch_trading_status().await });
-    let config = use_resource(|| async move { api::trading::fetch_trading_config().await });
+    let status = use_resource(move || async move {
+        let _ = generation();
+        api::trading::fetch_trading_status().await
+    });
+    let config = use_resource(move || async move {
+        let _ = generation();
+        api::trading::fetch_trading_config().await
+    });
 
     rsx! {
         Navbar {}
@@ -27,10 +59,12 @@ pub fn Dashboard() -> Element {
             style: "padding: 40px; max-width: 1600px; margin: 0 auto; min-height: 100vh;",
 
             h1 {
-                style: "color: #fff; margin-bottom: 40px; text-align: center; font-size: 32px; text-transform: uppercase; letter-spacing: 4px; text-shadow: 0 0 20px rgba(255,255,255,0.8);",
+                style: "color: #fff; margin-bottom: 20px; text-align: center; font-size: 32px; text-transform: uppercase; letter-spacing: 4px; text-shadow: 0 0 20px rgba(255,255,255,0.8);",
                 "[ λix DASHBOARD ]"
             }
 
+            RefreshControl { state: refresh }
+
             // TOP ROW: WALLETS AND TRADING SIDE BY SIDE
             div {
                 style: "display: grid; grid-template-columns: 1fr 1fr; gap: 30px; margin-bottom: 60px;",
@@ -87,7 +121,7 @@ pub fn Dashboard() -> Element {
 
                         match balances() {
                             Some(Ok(balance_data)) => rsx! {
-                                BalanceDisplay { balances: balance_data }
+                                BalanceDisplay { balances: balance_data, unit }
                             },
                             Some(Err(e)) => rsx! {
                                 div {
@@ -96,7 +130,7 @@ pub fn Dashboard() -> Element {
                                 }
                                 p {
                                     style: "font-family: 'Courier New', monospace; font-size: 11px; color: #666; margin-top: 10px;",
-                                    "Unable to fetch wallet balances. Please check that the backend server is running on http://nixlab:3000"
+                                    "Unable to fetch wallet balances. Please check that the backend server is running on {api_url}"
                                 }
                                 details {
                                     summary {
@@ -229,6 +263,11 @@ pub fn Dashboard() -> Element {
                                 input { r#type: "checkbox", checked: show_asb(), onchange: move |evt| show_asb.set(evt.checked()) }
                                 span { style: "text-transform: uppercase; font-size: 11px; letter-spacing: 1px;", "[ ASB ]" }
                             }
+                            label {
+                                style: "display: flex; align-items: center; gap: 10px; color: #b0b0b0; cursor: pointer; padding: 8px 15px; border: 1px solid #333; background: #111; transition: all 0.3s ease;",
+                                input { r#type: "checkbox", checked: show_containers(), onchange: move |evt| show_containers.set(evt.checked()) }
+                                span { style: "text-transform: uppercase; font-size: 11px; letter-spacing: 1px;", "[ CONTAINERS ]" }
+                            }
                         }
                     }
                 }
@@ -239,17 +278,22 @@ pub fn Dashboard() -> Element {
 
                     // Bitcoin Metrics Card
                     if show_bitcoin() {
-                        BitcoinMetricsSection { interval: interval }
+                        BitcoinMetricsSection { interval: interval, generation: generation }
                     }
 
                     // Monero Metrics Card
                     if show_monero() {
-                        MoneroMetricsSection { interval: interval }
+                        MoneroMetricsSection { interval: interval, generation: generation }
                     }
 
                     // ASB Metrics Card
                     if show_asb() {
-                        AsbMetricsSection { interval: interval }
+                        AsbMetricsSection { interval: interval, generation: generation }
+                    }
+
+                    // Container Metrics Card
+                    if show_containers() {
+                        ContainerMetricsSection { interval: interval, generation: generation }
                     }
                 }
             }
@@ -1,107 +1,18 @@
 use serde::{Deserialize, Serialize};
 
+pub use eigenix_common::config::DisplayConfig;
+pub use eigenix_common::kraken::KrakenTickerResponse as KrakenTickers;
+pub use eigenix_common::metrics::{
+    BitcoinMetricsView as BitcoinMetrics, ContainerStats, MoneroMetricsView as MoneroMetrics,
+    StoredAsbMetrics as AsbMetrics, StoredContainerMetrics as ContainerMetrics,
+};
+pub use eigenix_common::preferences::{BalanceUnit, UserPreferences};
+pub use eigenix_common::trading::{TradingConfig, TradingState, TradingStatus};
+pub use eigenix_common::wallets::{WalletBalances, WalletHealth};
+
 /// A single metric data point with timestamp and value
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct MetricValue {
     pub timestamp: String,
     pub value: f64,
 }
-
-/// Bitcoin metrics from the backend
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub struct BitcoinMetrics {
-    pub timestamp: String,
-    pub blocks: u64,
-    pub headers: u64,
-    pub verification_progress: f64,
-    pub size_on_disk: u64,
-    pub wallet_balance: Option<f64>,
-}
-
-/// Monero metrics from the backend
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub struct MoneroMetrics {
-    pub timestamp: String,
-    pub height: u64,
-    pub target_height: u64,
-    pub difficulty: u64,
-    pub tx_count: u64,
-    pub wallet_balance: Option<f64>,
-}
-
-/// ASB (Atomic Swap Bot) metrics from the backend
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub struct AsbMetrics {
-    pub timestamp: String,
-    pub balance_btc: f64,
-    pub pending_swaps: u64,
-    pub completed_swaps: u64,
-    pub failed_swaps: u64,
-    pub up: bool,
-}
-
-/// Wallet balances response
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub struct WalletBalances {
-    pub bitcoin: f64,
-    pub monero: f64,
-}
-
-/// Wallet health status response
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub struct WalletHealth {
-    pub healthy: bool,
-    pub bitcoin_ready: bool,
-    pub monero_ready: bool,
-}
-
-/// Current state of the trading engine
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub enum TradingState {
-    Disabled,
-    Monitoring,
-    DepositingBitcoin { amount: f64 },
-    WaitingForBitcoinDeposit { txid: String },
-    Trading { btc_amount: f64 },
-    WaitingForTradeExecution { order_id: String },
-    WithdrawingMonero { amount: f64 },
-    WaitingForMoneroWithdrawal { refid: String },
-    Error { message: String },
-}
-
-/// Trading engine status
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub struct TradingStatus {
-    pub state: TradingState,
-    pub enabled: bool,
-    pub last_check: Option<String>,
-    pub last_rebalance: Option<String>,
-    pub current_btc_balance: Option<f64>,
-    pub current_xmr_balance: Option<f64>,
-    pub kraken_btc_balance: Option<f64>,
-    pub kraken_xmr_balance: Option<f64>,
-}
-
-/// Trading configuration
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub struct TradingConfig {
-    pub monero_min_threshold: f64,
-    pub monero_target_balance: f64,
-    pub bitcoin_reserve_minimum: f64,
-    pub max_btc_per_rebalance: f64,
-    pub check_interval_secs: u64,
-    pub order_timeout_secs: u64,
-    pub slippage_tolerance_percent: f64,
-    pub use_limit_orders: bool,
-}
-
-/// Kraken ticker prices response
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub struct KrakenTickers {
-    pub btc_usd: f64,
-    pub btc_usd_change_24h: f64,
-    pub xmr_usd: f64,
-    pub xmr_usd_change_24h: f64,
-    pub xmr_btc: f64,
-    pub xmr_btc_change_24h: f64,
-}
@@ -51,7 +51,9 @@
       packages.eigenix-web = rustPlatform.buildRustPackage {
         pname = "eigenix-web";
         version = "0.1.0";
-        src = lib.cleanSource ./.;
+        # The frontend depends on the shared types crate, so build from the repository root
+        src = lib.cleanSource ../.;
+        cargoRoot = "web";
 
         cargoLock = {
           lockFile = ./Cargo.lock;
@@ -74,10 +76,9 @@
         buildPhase = ''
           runHook preBuild
           export HOME=$TMPDIR
-          export API_HOST=nixlab
-          export API_PORT=3000
 
           # Build the web package with dx bundle
+          cd web
           dx bundle --release
           runHook postBuild
         '';